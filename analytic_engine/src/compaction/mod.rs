@@ -4,13 +4,15 @@
 
 use std::{collections::HashMap, sync::Arc};
 
+use common_types::{request_id::RequestId, time::Timestamp};
 use common_util::config::{ReadableSize, TimeUnit};
 use serde_derive::Deserialize;
 use snafu::{ensure, Backtrace, GenerateBacktrace, ResultExt, Snafu};
+use table_engine::table::CompactionTaskInfo;
 use tokio::sync::oneshot;
 
 use crate::{
-    compaction::picker::{CommonCompactionPicker, CompactionPickerRef},
+    compaction::picker::{CommonCompactionPicker, CompactionPickerRef, LeveledCompactionPicker},
     instance::write_worker::CompactionNotifier,
     sst::file::{FileHandle, Level},
     table::data::TableDataRef,
@@ -19,6 +21,7 @@ use crate::{
 
 mod metrics;
 pub mod picker;
+pub mod rollup;
 pub mod scheduler;
 
 #[derive(Debug, Snafu)]
@@ -62,6 +65,7 @@ pub enum CompactionStrategy {
     Default,
     TimeWindow(TimeWindowCompactionOptions),
     SizeTiered(SizeTieredCompactionOptions),
+    Leveled(LeveledCompactionOptions),
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
@@ -80,6 +84,19 @@ pub struct TimeWindowCompactionOptions {
     pub timestamp_resolution: TimeUnit,
 }
 
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+pub struct LeveledCompactionOptions {
+    /// Number of level 0 ssts to trigger the compaction of level 0.
+    pub level0_file_num_trigger: usize,
+    /// Max total size of the ssts in level 1.
+    pub max_level_base_size: ReadableSize,
+    /// Max total size of level n+1 is `level_size_multiplier` times that of
+    /// level n.
+    pub level_size_multiplier: usize,
+    /// Size of the ssts output by the compaction.
+    pub target_file_size: ReadableSize,
+}
+
 impl protobuf::Clear for SizeTieredCompactionOptions {
     fn clear(&mut self) {
         *self = SizeTieredCompactionOptions::default()
@@ -104,6 +121,12 @@ impl Default for SizeTieredCompactionOptions {
     }
 }
 
+impl protobuf::Clear for LeveledCompactionOptions {
+    fn clear(&mut self) {
+        *self = LeveledCompactionOptions::default()
+    }
+}
+
 impl Default for TimeWindowCompactionOptions {
     fn default() -> Self {
         Self {
@@ -113,6 +136,17 @@ impl Default for TimeWindowCompactionOptions {
     }
 }
 
+impl Default for LeveledCompactionOptions {
+    fn default() -> Self {
+        Self {
+            level0_file_num_trigger: 4,
+            max_level_base_size: ReadableSize::mb(256),
+            level_size_multiplier: 10,
+            target_file_size: ReadableSize::mb(64),
+        }
+    }
+}
+
 impl Default for CompactionStrategy {
     fn default() -> Self {
         CompactionStrategy::Default
@@ -125,9 +159,14 @@ const MIN_THRESHOLD_KEY: &str = "compaction_min_threshold";
 const MAX_THRESHOLD_KEY: &str = "compaction_max_threshold";
 const MIN_SSTABLE_SIZE_KEY: &str = "compaction_min_sstable_size";
 const TIMESTAMP_RESOLUTION_KEY: &str = "compaction_timestamp_resolution";
+const LEVEL0_FILE_NUM_TRIGGER_KEY: &str = "compaction_level0_file_num_trigger";
+const MAX_LEVEL_BASE_SIZE_KEY: &str = "compaction_max_level_base_size";
+const LEVEL_SIZE_MULTIPLIER_KEY: &str = "compaction_level_size_multiplier";
+const TARGET_FILE_SIZE_KEY: &str = "compaction_target_file_size";
 const DEFAULT_STRATEGY: &str = "default";
 const STC_STRATEGY: &str = "size_tiered";
 const TWC_STRATEGY: &str = "time_window";
+const LCS_STRATEGY: &str = "leveled";
 
 impl CompactionStrategy {
     pub(crate) fn parse_from(
@@ -142,6 +181,9 @@ impl CompactionStrategy {
             TWC_STRATEGY => Ok(CompactionStrategy::TimeWindow(
                 TimeWindowCompactionOptions::parse_from(options)?,
             )),
+            LCS_STRATEGY => Ok(CompactionStrategy::Leveled(
+                LeveledCompactionOptions::parse_from(options)?,
+            )),
             _ => ParseStrategy {
                 value: value.to_string(),
             }
@@ -165,6 +207,10 @@ impl CompactionStrategy {
                 m.insert(COMPACTION_STRATEGY.to_string(), TWC_STRATEGY.to_string());
                 opts.fill_raw_map(m);
             }
+            CompactionStrategy::Leveled(opts) => {
+                m.insert(COMPACTION_STRATEGY.to_string(), LCS_STRATEGY.to_string());
+                opts.fill_raw_map(m);
+            }
         }
     }
 }
@@ -304,6 +350,104 @@ impl TimeWindowCompactionOptions {
     }
 }
 
+impl LeveledCompactionOptions {
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        ensure!(
+            self.level0_file_num_trigger > 0,
+            InvalidOption {
+                error: format!("{} must be greater than 0", LEVEL0_FILE_NUM_TRIGGER_KEY),
+            }
+        );
+        ensure!(
+            self.level_size_multiplier > 1,
+            InvalidOption {
+                error: format!("{} must be greater than 1", LEVEL_SIZE_MULTIPLIER_KEY),
+            }
+        );
+        ensure!(
+            self.max_level_base_size.0 > 0 && self.target_file_size.0 > 0,
+            InvalidOption {
+                error: format!(
+                    "{} and {} must be greater than 0",
+                    MAX_LEVEL_BASE_SIZE_KEY, TARGET_FILE_SIZE_KEY
+                ),
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Max total size of the ssts in `level`, the `level` must be greater
+    /// than 0.
+    pub fn max_level_size(&self, level: Level) -> u64 {
+        debug_assert!(level > 0);
+
+        let mut size = self.max_level_base_size.0;
+        for _ in 1..level {
+            size = size.saturating_mul(self.level_size_multiplier as u64);
+        }
+        size
+    }
+
+    fn fill_raw_map(&self, m: &mut HashMap<String, String>) {
+        m.insert(
+            LEVEL0_FILE_NUM_TRIGGER_KEY.to_string(),
+            format!("{}", self.level0_file_num_trigger),
+        );
+        m.insert(
+            MAX_LEVEL_BASE_SIZE_KEY.to_string(),
+            format!("{}", self.max_level_base_size.0),
+        );
+        m.insert(
+            LEVEL_SIZE_MULTIPLIER_KEY.to_string(),
+            format!("{}", self.level_size_multiplier),
+        );
+        m.insert(
+            TARGET_FILE_SIZE_KEY.to_string(),
+            format!("{}", self.target_file_size.0),
+        );
+    }
+
+    pub(crate) fn parse_from(
+        options: &HashMap<String, String>,
+    ) -> Result<LeveledCompactionOptions, Error> {
+        let mut opts = LeveledCompactionOptions::default();
+        if let Some(v) = options.get(LEVEL0_FILE_NUM_TRIGGER_KEY) {
+            opts.level0_file_num_trigger = v.parse().context(ParseInt {
+                key: LEVEL0_FILE_NUM_TRIGGER_KEY,
+                value: v,
+            })?;
+        }
+        if let Some(v) = options.get(MAX_LEVEL_BASE_SIZE_KEY) {
+            opts.max_level_base_size =
+                v.parse::<ReadableSize>().map_err(|err| Error::ParseSize {
+                    key: MAX_LEVEL_BASE_SIZE_KEY.to_string(),
+                    value: v.to_string(),
+                    error: err,
+                    backtrace: Backtrace::generate(),
+                })?;
+        }
+        if let Some(v) = options.get(LEVEL_SIZE_MULTIPLIER_KEY) {
+            opts.level_size_multiplier = v.parse().context(ParseInt {
+                key: LEVEL_SIZE_MULTIPLIER_KEY,
+                value: v,
+            })?;
+        }
+        if let Some(v) = options.get(TARGET_FILE_SIZE_KEY) {
+            opts.target_file_size = v.parse::<ReadableSize>().map_err(|err| Error::ParseSize {
+                key: TARGET_FILE_SIZE_KEY.to_string(),
+                value: v.to_string(),
+                error: err,
+                backtrace: Backtrace::generate(),
+            })?;
+        }
+
+        opts.validate()?;
+
+        Ok(opts)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CompactionInputFiles {
     /// Level of the files to be compacted.
@@ -312,6 +456,19 @@ pub struct CompactionInputFiles {
     pub files: Vec<FileHandle>,
     /// The output level of the merged file.
     pub output_level: Level,
+    /// Files in the output level overlapping with the `files`, they are merged
+    /// together with the `files`.
+    pub output_level_files: Vec<FileHandle>,
+    /// Split the output into ssts of about this size, None to output a single
+    /// sst.
+    pub target_file_size: Option<ReadableSize>,
+}
+
+impl CompactionInputFiles {
+    /// Iterate all the files to merge.
+    pub fn iter_files(&self) -> impl Iterator<Item = &FileHandle> + Clone {
+        self.files.iter().chain(&self.output_level_files)
+    }
 }
 
 #[derive(Default, Clone)]
@@ -329,9 +486,29 @@ pub struct CompactionTask {
 }
 
 impl CompactionTask {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.compaction_inputs.is_empty() && self.expired.is_empty()
+    }
+
+    /// Returns the info of this task started by request `request_id`.
+    pub fn task_info(&self, request_id: RequestId) -> CompactionTaskInfo {
+        let input_files = self
+            .compaction_inputs
+            .iter()
+            .flat_map(|input| input.iter_files());
+        CompactionTaskInfo {
+            request_id: request_id.as_u64(),
+            start_time: Timestamp::now(),
+            num_input_files: input_files.clone().count() as u64,
+            input_size: input_files.map(|f| f.size()).sum(),
+            num_expired_files: self.expired.iter().map(|e| e.files.len() as u64).sum(),
+        }
+    }
+
     pub fn mark_files_being_compacted(&self, being_compacted: bool) {
         for input in &self.compaction_inputs {
-            for file in &input.files {
+            for file in input.iter_files() {
                 file.set_being_compacted(being_compacted);
             }
         }
@@ -347,6 +524,7 @@ pub struct PickerManager {
     default_picker: CompactionPickerRef,
     time_window_picker: CompactionPickerRef,
     size_tiered_picker: CompactionPickerRef,
+    leveled_picker: CompactionPickerRef,
 }
 
 impl Default for PickerManager {
@@ -362,6 +540,7 @@ impl Default for PickerManager {
             default_picker: time_window_picker.clone(),
             size_tiered_picker,
             time_window_picker,
+            leveled_picker: Arc::new(LeveledCompactionPicker::default()),
         }
     }
 }
@@ -372,6 +551,7 @@ impl PickerManager {
             CompactionStrategy::Default => self.default_picker.clone(),
             CompactionStrategy::SizeTiered(_) => self.size_tiered_picker.clone(),
             CompactionStrategy::TimeWindow(_) => self.time_window_picker.clone(),
+            CompactionStrategy::Leveled(_) => self.leveled_picker.clone(),
         }
     }
 }
@@ -490,5 +670,28 @@ mod tests {
             c,
             CompactionStrategy::parse_from("time_window", &m).unwrap()
         );
+
+        let lcs_opts = LeveledCompactionOptions {
+            level0_file_num_trigger: 8,
+            target_file_size: ReadableSize(4096),
+            ..Default::default()
+        };
+        let c = CompactionStrategy::Leveled(lcs_opts);
+        let mut m = HashMap::new();
+        c.fill_raw_map(&mut m);
+
+        assert_eq!(5, m.len());
+        assert_eq!(m[COMPACTION_STRATEGY], "leveled");
+        assert_eq!(m[LEVEL0_FILE_NUM_TRIGGER_KEY], "8");
+        assert_eq!(m[MAX_LEVEL_BASE_SIZE_KEY], "268435456");
+        assert_eq!(m[LEVEL_SIZE_MULTIPLIER_KEY], "10");
+        assert_eq!(m[TARGET_FILE_SIZE_KEY], "4096");
+        assert_eq!(c, CompactionStrategy::parse_from("leveled", &m).unwrap());
+
+        assert_eq!(268435456, lcs_opts.max_level_size(1));
+        assert_eq!(2684354560, lcs_opts.max_level_size(2));
+
+        m.insert(LEVEL_SIZE_MULTIPLIER_KEY.to_string(), "1".to_string());
+        assert!(CompactionStrategy::parse_from("leveled", &m).is_err());
     }
 }
@@ -15,8 +15,8 @@ use snafu::Snafu;
 
 use crate::{
     compaction::{
-        CompactionInputFiles, CompactionStrategy, CompactionTask, SizeTieredCompactionOptions,
-        TimeWindowCompactionOptions,
+        CompactionInputFiles, CompactionStrategy, CompactionTask, LeveledCompactionOptions,
+        SizeTieredCompactionOptions, TimeWindowCompactionOptions,
     },
     sst::{
         file::{FileHandle, Level},
@@ -51,6 +51,13 @@ impl PickerContext {
             _ => TimeWindowCompactionOptions::default(),
         }
     }
+
+    fn leveled_opts(&self) -> LeveledCompactionOptions {
+        match self.strategy {
+            CompactionStrategy::Leveled(opts) => opts,
+            _ => LeveledCompactionOptions::default(),
+        }
+    }
 }
 
 pub trait CompactionPicker {
@@ -86,9 +93,11 @@ pub struct CommonCompactionPicker {
 impl CommonCompactionPicker {
     pub fn new(strategy: CompactionStrategy) -> Self {
         let level_picker: LevelPickerRef = match strategy {
-            CompactionStrategy::SizeTiered(_) | CompactionStrategy::Default => {
-                Arc::new(SizeTieredPicker::default())
-            }
+            // The leveled strategy should use the `LeveledCompactionPicker`, fallback to
+            // size tiered here.
+            CompactionStrategy::SizeTiered(_)
+            | CompactionStrategy::Default
+            | CompactionStrategy::Leveled(_) => Arc::new(SizeTieredPicker::default()),
             CompactionStrategy::TimeWindow(_) => Arc::new(TimeWindowPicker::default()),
         };
         Self { level_picker }
@@ -114,6 +123,8 @@ impl CommonCompactionPicker {
                     files,
                     // Now, we always output to the same level.
                     output_level: level,
+                    output_level_files: Vec::new(),
+                    target_file_size: None,
                 });
             }
         }
@@ -578,6 +589,166 @@ impl LevelPicker for TimeWindowPicker {
     }
 }
 
+/// Leveled compaction strategy
+///
+/// The level 0 holds the ssts flushed from the memtables, whose key ranges may
+/// overlap. The ssts in each of the other levels have non-overlapping key
+/// ranges, and the total size of level n is limited to `max_level_base_size *
+/// level_size_multiplier ^ (n - 1)`. A compaction merges some ssts of a level
+/// with the overlapping ssts of the next level and outputs to the next level,
+/// so a query reads at most one sst of each level above level 0 for a key.
+#[derive(Default)]
+pub struct LeveledCompactionPicker {}
+
+impl CompactionPicker for LeveledCompactionPicker {
+    fn pick_compaction(
+        &self,
+        ctx: PickerContext,
+        levels_controller: &LevelsController,
+    ) -> Result<CompactionTask> {
+        let expire_time = ctx.ttl.map(Timestamp::expire_time);
+        let mut compaction_task = CompactionTask {
+            expired: levels_controller.expired_ssts(expire_time),
+            ..Default::default()
+        };
+
+        let opts = ctx.leveled_opts();
+        if let Some(input_files) = Self::pick_input_files(&opts, levels_controller, expire_time) {
+            info!(
+                "Leveled compaction picker pick files to compact, opts:{:?}, input_files:{:?}",
+                opts, input_files
+            );
+
+            compaction_task.compaction_inputs = vec![input_files];
+        }
+
+        Ok(compaction_task)
+    }
+}
+
+impl LeveledCompactionPicker {
+    fn pick_input_files(
+        opts: &LeveledCompactionOptions,
+        levels_controller: &LevelsController,
+        expire_time: Option<Timestamp>,
+    ) -> Option<CompactionInputFiles> {
+        let num_levels = levels_controller.num_levels();
+        // The last level has no next level to compact into.
+        let mut level_scores: Vec<_> = (0..num_levels.saturating_sub(1))
+            .map(|level| {
+                (
+                    level,
+                    Self::level_score(opts, levels_controller, level, expire_time),
+                )
+            })
+            .filter(|(_, score)| *score >= 1.0)
+            .collect();
+        // Compact the level exceeding its limit most first.
+        level_scores.sort_unstable_by(|(_, s1), (_, s2)| s2.partial_cmp(s1).unwrap());
+
+        for (level, score) in level_scores {
+            debug!("Try to compact level, level:{}, score:{}", level, score);
+
+            let files = Self::pick_files_at_level(levels_controller, level, expire_time);
+            if files.is_empty() {
+                continue;
+            }
+            let output_level = level + 1;
+            if let Some(output_level_files) =
+                Self::overlapping_files(levels_controller, output_level, &files, expire_time)
+            {
+                return Some(CompactionInputFiles {
+                    level,
+                    files,
+                    output_level,
+                    output_level_files,
+                    target_file_size: Some(opts.target_file_size),
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Returns the ratio of the number of ssts to the trigger for level 0, and
+    /// the ratio of the total size to the limit for other levels.
+    fn level_score(
+        opts: &LeveledCompactionOptions,
+        levels_controller: &LevelsController,
+        level: Level,
+        expire_time: Option<Timestamp>,
+    ) -> f64 {
+        let files = levels_controller
+            .iter_ssts_at_level(level)
+            .filter(|file| !file.time_range().is_expired(expire_time));
+        if level == 0 {
+            let num_files = files.filter(|file| !file.being_compacted()).count();
+            num_files as f64 / opts.level0_file_num_trigger as f64
+        } else {
+            let total_size: u64 = files.map(|file| file.size()).sum();
+            total_size as f64 / opts.max_level_size(level) as f64
+        }
+    }
+
+    /// Pick all the ssts of level 0 as their key ranges overlap, and the
+    /// oldest sst of other levels.
+    ///
+    /// Returns empty if no sst can be picked.
+    fn pick_files_at_level(
+        levels_controller: &LevelsController,
+        level: Level,
+        expire_time: Option<Timestamp>,
+    ) -> Vec<FileHandle> {
+        if level == 0 {
+            // The output of a running compaction of level 0 may overlap with the
+            // output of a new one.
+            if levels_controller
+                .iter_ssts_at_level(level)
+                .any(|file| file.being_compacted())
+            {
+                return Vec::new();
+            }
+            return find_uncompact_files(levels_controller, level, expire_time);
+        }
+
+        let files = find_uncompact_files(levels_controller, level, expire_time);
+        files
+            .into_iter()
+            .min_by_key(|file| file.max_sequence())
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns the ssts in `level` whose key ranges overlap with the `files`,
+    /// or None if any of them is being compacted.
+    fn overlapping_files(
+        levels_controller: &LevelsController,
+        level: Level,
+        files: &[FileHandle],
+        expire_time: Option<Timestamp>,
+    ) -> Option<Vec<FileHandle>> {
+        let min_key = files.iter().map(|file| file.min_key()).min()?;
+        let max_key = files.iter().map(|file| file.max_key()).max()?;
+
+        let mut overlapping = Vec::new();
+        for file in levels_controller.iter_ssts_at_level(level) {
+            // The expired ssts are deleted by the compaction task.
+            if file.time_range().is_expired(expire_time)
+                || file.max_key() < min_key
+                || file.min_key() > max_key
+            {
+                continue;
+            }
+            if file.being_compacted() {
+                return None;
+            }
+            overlapping.push(file.clone());
+        }
+
+        Some(overlapping)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
@@ -587,10 +758,14 @@ mod tests {
         tests::build_schema,
         time::{TimeRange, Timestamp},
     };
+    use common_util::config::ReadableSize;
 
     use crate::{
-        compaction::{picker::PickerContext, CompactionStrategy, PickerManager},
+        compaction::{
+            picker::PickerContext, CompactionStrategy, LeveledCompactionOptions, PickerManager,
+        },
         sst::{
+            bloom_filter::SstBloomFilter,
             file::SstMetaData,
             manager::{tests::LevelsControllerMockBuilder, LevelsController},
         },
@@ -605,6 +780,25 @@ mod tests {
             schema: build_schema(),
             size,
             row_num: 2,
+            column_stats: Vec::new(),
+            bloom_filter: SstBloomFilter::default(),
+            tag_index: None,
+            rolled_up: false,
+            rollup_source_files: Vec::new(),
+        }
+    }
+
+    fn build_leveled_sst_meta_data(
+        min_key: &'static [u8],
+        max_key: &'static [u8],
+        max_sequence: u64,
+        size: u64,
+    ) -> SstMetaData {
+        SstMetaData {
+            min_key: Bytes::from_static(min_key),
+            max_key: Bytes::from_static(max_key),
+            max_sequence,
+            ..build_sst_meta_data(TimeRange::min_to_max(), size)
         }
     }
 
@@ -737,4 +931,92 @@ mod tests {
             assert!(task.expired[0].files.is_empty());
         }
     }
+
+    #[test]
+    fn test_leveled_picker() {
+        let picker_manager = PickerManager::default();
+        let opts = LeveledCompactionOptions {
+            level0_file_num_trigger: 2,
+            max_level_base_size: ReadableSize(100),
+            ..Default::default()
+        };
+        let picker = picker_manager.get_picker(CompactionStrategy::Leveled(opts));
+        let ctx = PickerContext {
+            segment_duration: Duration::from_millis(1000),
+            ttl: None,
+            strategy: CompactionStrategy::Leveled(opts),
+        };
+
+        // Level 0 reaches the trigger and is merged with the overlapping ssts of
+        // level 1.
+        {
+            let lc = LevelsControllerMockBuilder::default()
+                .add_sst_to_level(
+                    0,
+                    vec![
+                        build_leveled_sst_meta_data(b"b", b"d", 10, 10),
+                        build_leveled_sst_meta_data(b"c", b"e", 11, 10),
+                    ],
+                )
+                .add_sst_to_level(
+                    1,
+                    vec![
+                        build_leveled_sst_meta_data(b"a", b"b", 1, 10),
+                        build_leveled_sst_meta_data(b"e", b"f", 2, 10),
+                        build_leveled_sst_meta_data(b"g", b"h", 3, 10),
+                    ],
+                )
+                .build();
+            let task = picker.pick_compaction(ctx.clone(), &lc).unwrap();
+            let input = &task.compaction_inputs[0];
+            assert_eq!(0, input.level);
+            assert_eq!(1, input.output_level);
+            let mut file_ids: Vec<_> = input.files.iter().map(|f| f.id()).collect();
+            file_ids.sort_unstable();
+            assert_eq!(vec![0, 1], file_ids);
+            let mut file_ids: Vec<_> = input.output_level_files.iter().map(|f| f.id()).collect();
+            file_ids.sort_unstable();
+            assert_eq!(vec![2, 3], file_ids);
+            assert_eq!(Some(opts.target_file_size), input.target_file_size);
+        }
+
+        // Level 1 exceeds its size limit and the oldest sst is pushed down.
+        {
+            let lc = LevelsControllerMockBuilder::default()
+                .add_sst_to_level(0, vec![build_leveled_sst_meta_data(b"a", b"z", 10, 10)])
+                .add_sst_to_level(
+                    1,
+                    vec![
+                        build_leveled_sst_meta_data(b"a", b"c", 5, 60),
+                        build_leveled_sst_meta_data(b"d", b"f", 3, 60),
+                    ],
+                )
+                .add_sst_to_level(
+                    2,
+                    vec![
+                        build_leveled_sst_meta_data(b"a", b"b", 1, 10),
+                        build_leveled_sst_meta_data(b"c", b"e", 1, 10),
+                    ],
+                )
+                .build();
+            let task = picker.pick_compaction(ctx.clone(), &lc).unwrap();
+            let input = &task.compaction_inputs[0];
+            assert_eq!(1, input.level);
+            assert_eq!(2, input.output_level);
+            assert_eq!(1, input.files.len());
+            assert_eq!(2, input.files[0].id());
+            assert_eq!(1, input.output_level_files.len());
+            assert_eq!(4, input.output_level_files[0].id());
+        }
+
+        // No level exceeds its limit.
+        {
+            let lc = LevelsControllerMockBuilder::default()
+                .add_sst_to_level(0, vec![build_leveled_sst_meta_data(b"a", b"z", 10, 10)])
+                .add_sst_to_level(1, vec![build_leveled_sst_meta_data(b"a", b"c", 5, 60)])
+                .build();
+            let task = picker.pick_compaction(ctx, &lc).unwrap();
+            assert!(task.compaction_inputs.is_empty());
+        }
+    }
 }
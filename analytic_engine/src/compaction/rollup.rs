@@ -0,0 +1,421 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Rollup the rows of the compaction input into the rollup table
+
+use std::{collections::VecDeque, time::Duration};
+
+use common_types::{
+    bytes::Bytes,
+    datum::{Datum, DatumKind},
+    record_batch::{RecordBatchWithKey, RecordBatchWithKeyBuilder},
+    row::Row,
+    schema::Schema,
+    time::{TimeRange, Timestamp},
+    SequenceNumber,
+};
+use common_util::{codec::Encoder, define_result};
+use snafu::{ResultExt, Snafu};
+use table_engine::rollup::{self, AggregateState, RollupOptions};
+
+use crate::memtable::key::{ComparableInternalKey, KeySequence};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to encode key of rollup row, err:{}", source))]
+    EncodeKey { source: crate::memtable::key::Error },
+
+    #[snafu(display("Failed to build record batch of rollup rows, err:{}", source))]
+    BuildRecordBatch {
+        source: common_types::record_batch::Error,
+    },
+}
+
+define_result!(Error);
+
+/// How to fill a column of the rollup table.
+#[derive(Debug, Clone, Copy)]
+enum RollupColumn {
+    /// Copy the column at the index of the source schema.
+    Copy(usize),
+    /// Aggregate the column at the index of the source schema into the state.
+    State(usize, AggregateState),
+    /// The column doesn't exist in the source schema, fill null.
+    Null,
+}
+
+/// Stats of the rows of the rollup table output by the [Aggregator].
+#[derive(Debug, Clone)]
+pub struct RollupStats {
+    pub min_key: Bytes,
+    pub max_key: Bytes,
+    pub time_range: TimeRange,
+    pub row_num: usize,
+}
+
+/// Aggregates the rows of a table into buckets of the rollup interval.
+///
+/// The rows of the same key (with the timestamp truncated by the interval) are
+/// aggregated into one row of the rollup table. The input rows must be sorted
+/// by the key, and the timestamp must be the last key column, so the rows of a
+/// bucket are adjacent and a bucket can be output once a row of another bucket
+/// is met.
+pub struct Aggregator {
+    interval: Duration,
+    rollup_schema: Schema,
+    /// Index of the timestamp column in the source schema.
+    timestamp_index: usize,
+    columns: Vec<RollupColumn>,
+    /// Sequence to encode the key of the rollup rows.
+    sequence: SequenceNumber,
+    num_rows_per_batch: usize,
+    /// Key and datums of the bucket being aggregated.
+    current: Option<(Vec<Datum>, Vec<Datum>)>,
+    builder: RecordBatchWithKeyBuilder,
+    /// Record batches of the rollup rows ready to output.
+    outputs: VecDeque<RecordBatchWithKey>,
+    min_key: Option<Bytes>,
+    max_key: Bytes,
+    min_ts: Timestamp,
+    max_ts: Timestamp,
+    row_num: usize,
+}
+
+impl Aggregator {
+    pub fn new(
+        source_schema: &Schema,
+        rollup_schema: Schema,
+        rollup: &RollupOptions,
+        sequence: SequenceNumber,
+        num_rows_per_batch: usize,
+    ) -> Self {
+        let mut state_columns = Vec::new();
+        for aggregate in &rollup.aggregates {
+            if let Some(index) = source_schema.index_of(&aggregate.column) {
+                for state in aggregate.function.states() {
+                    let name = rollup::state_column_name(&aggregate.column, *state);
+                    state_columns.push((name, RollupColumn::State(index, *state)));
+                }
+            }
+        }
+
+        let columns = rollup_schema
+            .columns()
+            .iter()
+            .map(|column| {
+                state_columns
+                    .iter()
+                    .find(|(name, _)| *name == column.name)
+                    .map(|(_, rollup_column)| *rollup_column)
+                    .or_else(|| source_schema.index_of(&column.name).map(RollupColumn::Copy))
+                    .unwrap_or(RollupColumn::Null)
+            })
+            .collect();
+        let builder = RecordBatchWithKeyBuilder::with_capacity(
+            rollup_schema.to_record_schema_with_key(),
+            num_rows_per_batch,
+        );
+
+        Self {
+            interval: rollup.interval.0,
+            rollup_schema,
+            timestamp_index: source_schema.timestamp_index(),
+            columns,
+            sequence,
+            num_rows_per_batch,
+            current: None,
+            builder,
+            outputs: VecDeque::new(),
+            min_key: None,
+            max_key: Bytes::new(),
+            min_ts: Timestamp::MAX,
+            max_ts: Timestamp::MIN,
+            row_num: 0,
+        }
+    }
+
+    /// Aggregate the rows of the `record_batch`, which must have the same
+    /// schema as the source schema.
+    ///
+    /// The record batches of the finished buckets can be taken by
+    /// [Aggregator::pop_output()].
+    pub fn aggregate(&mut self, record_batch: &RecordBatchWithKey) -> Result<()> {
+        let num_key_columns = self.rollup_schema.num_key_columns();
+        let timestamp_index = self.timestamp_index;
+        let interval = self.interval;
+        let datum_at = |column_index: usize, row_index: usize| {
+            let datum = record_batch.column(column_index).datum(row_index);
+            if column_index == timestamp_index {
+                match datum.as_timestamp() {
+                    Some(ts) => Datum::Timestamp(ts.truncate_by(interval)),
+                    None => datum,
+                }
+            } else {
+                datum
+            }
+        };
+
+        for row_index in 0..record_batch.num_rows() {
+            let key_datums: Vec<_> = self.columns[..num_key_columns]
+                .iter()
+                .map(|column| match column {
+                    RollupColumn::Copy(index) => datum_at(*index, row_index),
+                    RollupColumn::State(..) | RollupColumn::Null => Datum::Null,
+                })
+                .collect();
+
+            let is_new_bucket = match &self.current {
+                Some((key, _)) => *key != key_datums,
+                None => true,
+            };
+            if is_new_bucket {
+                self.output_current()?;
+
+                let bucket = self
+                    .columns
+                    .iter()
+                    .zip(self.rollup_schema.columns())
+                    .enumerate()
+                    .map(|(i, (column, column_schema))| match column {
+                        RollupColumn::Copy(_) if i < num_key_columns => key_datums[i].clone(),
+                        RollupColumn::Copy(index) => datum_at(*index, row_index),
+                        RollupColumn::State(_, AggregateState::Count) => Datum::UInt64(0),
+                        RollupColumn::State(..) | RollupColumn::Null => {
+                            if column_schema.is_nullable {
+                                Datum::Null
+                            } else {
+                                Datum::empty(&column_schema.data_type)
+                            }
+                        }
+                    })
+                    .collect();
+                self.current = Some((key_datums, bucket));
+            }
+
+            if let Some((_, bucket)) = &mut self.current {
+                for (column, state_datum) in self.columns.iter().zip(bucket.iter_mut()) {
+                    if let RollupColumn::State(index, state) = column {
+                        let datum = record_batch.column(*index).datum(row_index);
+                        update_state(*state, state_datum, datum);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finish the aggregation, the record batches of all the buckets left can
+    /// be taken by [Aggregator::pop_output()] then.
+    pub fn finish(&mut self) -> Result<()> {
+        self.output_current()?;
+        if !self.builder.is_empty() {
+            let record_batch = self.builder.build().context(BuildRecordBatch)?;
+            self.outputs.push_back(record_batch);
+        }
+
+        Ok(())
+    }
+
+    /// Take the next record batch of the rollup rows sorted by the key.
+    pub fn pop_output(&mut self) -> Option<RecordBatchWithKey> {
+        self.outputs.pop_front()
+    }
+
+    /// Returns the stats of the rollup rows output so far, or None if no row
+    /// is output.
+    pub fn stats(&self) -> Option<RollupStats> {
+        let min_key = self.min_key.clone()?;
+        let end_ts = self
+            .max_ts
+            .checked_add_i64(self.interval.as_millis() as i64)
+            .unwrap_or(Timestamp::MAX);
+        let time_range = TimeRange::new(self.min_ts, end_ts).unwrap_or_else(TimeRange::min_to_max);
+
+        Some(RollupStats {
+            min_key,
+            max_key: self.max_key.clone(),
+            time_range,
+            row_num: self.row_num,
+        })
+    }
+
+    /// Append the bucket being aggregated to the output.
+    fn output_current(&mut self) -> Result<()> {
+        let (key_datums, datums) = match self.current.take() {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+
+        let key = self.encode_key(&Row::from_datums(key_datums))?;
+        if self.min_key.is_none() {
+            self.min_key = Some(key.clone());
+        }
+        self.max_key = key;
+        if let Some(ts) = datums[self.rollup_schema.timestamp_index()].as_timestamp() {
+            self.min_ts = self.min_ts.min(ts);
+            self.max_ts = self.max_ts.max(ts);
+        }
+        self.row_num += 1;
+
+        self.builder
+            .append_row(Row::from_datums(datums))
+            .context(BuildRecordBatch)?;
+        if self.builder.len() >= self.num_rows_per_batch {
+            let record_batch = self.builder.build().context(BuildRecordBatch)?;
+            self.outputs.push_back(record_batch);
+        }
+
+        Ok(())
+    }
+
+    fn encode_key(&self, key_row: &Row) -> Result<Bytes> {
+        let mut buf = Vec::new();
+        ComparableInternalKey::new(KeySequence::new(self.sequence, 0), &self.rollup_schema)
+            .encode(&mut buf, key_row)
+            .context(EncodeKey)?;
+
+        Ok(Bytes::from(buf))
+    }
+}
+
+/// Update the `state` by the `datum` of the source column.
+fn update_state(state: AggregateState, state_datum: &mut Datum, datum: Datum) {
+    if datum.is_null() {
+        return;
+    }
+
+    let updated = match (state, &*state_datum) {
+        (AggregateState::Count, Datum::UInt64(count)) => Datum::UInt64(count + 1),
+        (AggregateState::Sum, acc) => match acc.kind() {
+            DatumKind::Double => {
+                Datum::Double(acc.as_f64().unwrap_or(0.0) + datum.as_f64().unwrap_or(0.0))
+            }
+            DatumKind::UInt64 => Datum::UInt64(
+                acc.as_u64()
+                    .unwrap_or(0)
+                    .wrapping_add(datum.as_u64().unwrap_or(0)),
+            ),
+            DatumKind::Int64 => Datum::Int64(
+                (acc.as_u64().unwrap_or(0) as i64).wrapping_add(datum.as_u64().unwrap_or(0) as i64),
+            ),
+            // The sum is null, init it by the datum.
+            _ => match datum.kind() {
+                DatumKind::Double | DatumKind::Float => {
+                    Datum::Double(datum.as_f64().unwrap_or(0.0))
+                }
+                DatumKind::UInt64 | DatumKind::UInt32 | DatumKind::UInt16 | DatumKind::UInt8 => {
+                    Datum::UInt64(datum.as_u64().unwrap_or(0))
+                }
+                _ => Datum::Int64(datum.as_u64().unwrap_or(0) as i64),
+            },
+        },
+        (AggregateState::Min, acc) if acc.is_null() || datum < *acc => datum,
+        (AggregateState::Max, acc) if acc.is_null() || datum > *acc => datum,
+        _ => return,
+    };
+
+    *state_datum = updated;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use common_types::tests::{build_row, build_schema};
+
+    use super::*;
+    use crate::row_iter::tests::build_record_batch_with_key;
+
+    #[test]
+    fn test_rollup_aggregator() {
+        let schema = build_schema();
+        let mut options = HashMap::new();
+        options.insert(
+            rollup::OPTION_KEY_ROLLUP_INTERVAL.to_string(),
+            "1s".to_string(),
+        );
+        options.insert(
+            rollup::OPTION_KEY_ROLLUP_AGGREGATES.to_string(),
+            "field1=avg".to_string(),
+        );
+        let rollup = RollupOptions::parse_from(&options).unwrap().unwrap();
+        let rollup_schema = rollup.build_rollup_schema(&schema).unwrap();
+
+        let mut aggregator = Aggregator::new(&schema, rollup_schema, &rollup, 1, 2);
+        assert!(aggregator.stats().is_none());
+
+        // The rows of a bucket may be split into different record batches.
+        let record_batch = build_record_batch_with_key(
+            schema.clone(),
+            vec![
+                build_row(b"a", 1000, 1.0, "v1"),
+                build_row(b"a", 1500, 2.0, "v2"),
+            ],
+        );
+        aggregator.aggregate(&record_batch).unwrap();
+        let record_batch = build_record_batch_with_key(
+            schema.clone(),
+            vec![
+                build_row(b"a", 1999, 5.0, "v5"),
+                build_row(b"a", 2000, 3.0, "v3"),
+            ],
+        );
+        aggregator.aggregate(&record_batch).unwrap();
+        // Only the first bucket is finished.
+        assert!(aggregator.pop_output().is_none());
+        assert_eq!(1, aggregator.stats().unwrap().row_num);
+
+        let record_batch =
+            build_record_batch_with_key(schema.clone(), vec![build_row(b"b", 1200, 4.0, "v4")]);
+        aggregator.aggregate(&record_batch).unwrap();
+        let mut record_batches = vec![aggregator.pop_output().unwrap()];
+        assert!(aggregator.pop_output().is_none());
+
+        aggregator.finish().unwrap();
+        while let Some(record_batch) = aggregator.pop_output() {
+            record_batches.push(record_batch);
+        }
+        assert_eq!(2, record_batches.len());
+
+        let stats = aggregator.stats().unwrap();
+        assert_eq!(3, stats.row_num);
+        assert!(stats.min_key < stats.max_key);
+        assert_eq!(
+            TimeRange::new(Timestamp::new(1000), Timestamp::new(3000)).unwrap(),
+            stats.time_range
+        );
+
+        let mut rows = Vec::new();
+        for record_batch in &record_batches {
+            for row_index in 0..record_batch.num_rows() {
+                let datums: Vec<_> = (0..record_batch.num_columns())
+                    .map(|column_index| record_batch.column(column_index).datum(row_index))
+                    .collect();
+                rows.push(datums);
+            }
+        }
+        assert_eq!(
+            vec![
+                vec![
+                    Datum::Varbinary(Bytes::from_static(b"a")),
+                    Datum::Timestamp(Timestamp::new(1000)),
+                    Datum::Double(8.0),
+                    Datum::UInt64(3),
+                ],
+                vec![
+                    Datum::Varbinary(Bytes::from_static(b"a")),
+                    Datum::Timestamp(Timestamp::new(2000)),
+                    Datum::Double(3.0),
+                    Datum::UInt64(1),
+                ],
+                vec![
+                    Datum::Varbinary(Bytes::from_static(b"b")),
+                    Datum::Timestamp(Timestamp::new(1000)),
+                    Datum::Double(4.0),
+                    Datum::UInt64(1),
+                ],
+            ],
+            rows
+        );
+    }
+}
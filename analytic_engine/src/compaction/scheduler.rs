@@ -435,11 +435,15 @@ impl<
 
         let sender = self.sender.clone();
         let request_id = RequestId::next_id();
+        if !compaction_task.is_empty() {
+            table_data.add_compaction_task(compaction_task.task_info(request_id));
+        }
         // Do actual costly compact job in background.
         self.runtime.spawn(async move {
             let res = space_store
                 .compact_table(runtime, &table_data, request_id, &compaction_task)
                 .await;
+            table_data.remove_compaction_task(request_id.as_u64());
 
             if let Err(e) = &res {
                 // Compaction is failed, we need to unset the compaction mark.
@@ -525,10 +529,13 @@ impl<
             for (table_data, compaction_task) in to_purge {
                 info!("Period purge expired files, table:{}, table_id:{}, request_id:{}", table_data.name, table_data.id, request_id);
 
-                if let Err(e) = space_store
+                table_data.add_compaction_task(compaction_task.task_info(request_id));
+                let res = space_store
                     .compact_table(runtime.clone(), &table_data, request_id, &compaction_task)
-                    .await
-                {
+                    .await;
+                table_data.remove_compaction_task(request_id.as_u64());
+
+                if let Err(e) = res {
                     error!(
                         "Failed to purge expired files of table, table:{}, table_id:{}, request_id:{}, err:{}",
                         table_data.name, table_data.id, request_id, e
@@ -14,8 +14,8 @@ use wal::manager::WalManager;
 use crate::{
     instance::{
         engine::{
-            AlterDroppedTable, FlushTable, InvalidOptions, InvalidPreVersion, InvalidSchemaVersion,
-            OperateByWriteWorker, Result, WriteManifest,
+            AlterDroppedTable, AlterTombstoneColumn, FlushTable, InvalidOptions, InvalidPreVersion,
+            InvalidSchemaVersion, OperateByWriteWorker, Result, WriteManifest,
         },
         flush_compaction::TableFlushOptions,
         write_worker,
@@ -164,6 +164,28 @@ where
             }
         );
 
+        // The tombstones find the deleted rows by the column names, so the columns
+        // used by them can't be dropped or renamed.
+        let current_schema = table_data.schema();
+        for tombstone in table_data.current_version().tombstones() {
+            for filter in &tombstone.request.tag_filters {
+                let column_id = current_schema
+                    .column_with_name(&filter.column)
+                    .map(|c| c.id);
+                let new_column_id = request
+                    .schema
+                    .column_with_name(&filter.column)
+                    .map(|c| c.id);
+                ensure!(
+                    column_id == new_column_id,
+                    AlterTombstoneColumn {
+                        table: &table_data.name,
+                        column: &filter.column,
+                    }
+                );
+            }
+        }
+
         Ok(())
     }
 
@@ -4,21 +4,24 @@
 
 use std::sync::Arc;
 
-use log::info;
+use log::{error, info};
 use object_store::ObjectStore;
-use snafu::ResultExt;
+use snafu::{ensure, ResultExt};
 use table_engine::engine::CreateTableRequest;
 use tokio::sync::oneshot;
 use wal::manager::WalManager;
 
 use crate::{
     instance::{
-        engine::{CreateTableData, InvalidOptions, OperateByWriteWorker, Result, WriteManifest},
+        engine::{
+            CreateTableData, InvalidOptions, OperateByWriteWorker, Result, TableNameConflict,
+            WriteManifest,
+        },
         write_worker::{self, CreateTableCommand, WorkerLocal},
         Instance,
     },
     meta::{
-        meta_update::{AddTableMeta, MetaUpdate},
+        meta_update::{AddTableMeta, DropTableMeta, MetaUpdate},
         Manifest,
     },
     space::SpaceRef,
@@ -105,6 +108,16 @@ where
             // Use the table data from the space instead of the table_data in params.
             return Ok(table_data);
         };
+        // Check the name before persisting the table, the sub tables of partitioned
+        // tables are created in the same space with generated names.
+        ensure!(
+            space.find_table(&table_data.name).is_none(),
+            TableNameConflict {
+                space_id: space.id,
+                table: &table_data.name,
+                table_id: table_data.id,
+            }
+        );
 
         // Store table info into meta
         let update = MetaUpdate::AddTable(AddTableMeta {
@@ -125,7 +138,37 @@ where
                 table_id: table_data.id,
             })?;
 
-        space.insert_table(table_data.clone());
+        if !space.insert_table(table_data.clone()) {
+            // Another table with the same name is created by other write worker
+            // meanwhile, revert the persisted table.
+            error!(
+                "Table name conflicts after persisting the table, space_id:{}, table:{}, table_id:{}",
+                space.id, table_data.name, table_data.id
+            );
+            let update = MetaUpdate::DropTable(DropTableMeta {
+                space_id: space.id,
+                table_id: table_data.id,
+                table_name: table_data.name.clone(),
+            });
+            self.space_store
+                .manifest
+                .store_update(update)
+                .await
+                .map_err(|e| Box::new(e) as _)
+                .context(WriteManifest {
+                    space_id: space.id,
+                    table: &table_data.name,
+                    table_id: table_data.id,
+                })?;
+
+            return TableNameConflict {
+                space_id: space.id,
+                table: &table_data.name,
+                table_id: table_data.id,
+            }
+            .fail();
+        }
+
         Ok(table_data)
     }
 }
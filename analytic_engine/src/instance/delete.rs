@@ -0,0 +1,291 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Delete logic of instance
+
+use std::sync::Arc;
+
+use log::{info, warn};
+use object_store::ObjectStore;
+use snafu::{ensure, OptionExt, ResultExt};
+use table_engine::table::DeleteRequest;
+use tokio::sync::oneshot;
+use wal::{
+    log_batch::{LogWriteBatch, LogWriteEntry},
+    manager::{SequenceNumber, WalManager, WriteContext},
+};
+
+use crate::{
+    instance::{
+        engine::{
+            DeleteDroppedTable, FlushTable, InvalidDeleteColumn, OperateByWriteWorker, Result,
+            WriteDeleteLog, WriteManifest,
+        },
+        flush_compaction::TableFlushOptions,
+        write_worker,
+        write_worker::{DeleteTableCommand, WorkerLocal},
+        Instance,
+    },
+    meta::{
+        meta_update::{MetaUpdate, VersionEditMeta},
+        Manifest,
+    },
+    payload::WritePayload,
+    space::SpaceAndTable,
+    sst::factory::Factory,
+    table::{
+        data::{TableData, TableDataRef},
+        tombstone::{self, Tombstone},
+        version_edit::VersionEdit,
+    },
+};
+
+impl<Wal, Meta, Store, Fa> Instance<Wal, Meta, Store, Fa>
+where
+    Wal: WalManager + Send + Sync + 'static,
+    Meta: Manifest + Send + Sync + 'static,
+    Store: ObjectStore,
+    Fa: Factory + Send + Sync + 'static,
+{
+    /// Delete rows matching the request from the table.
+    ///
+    /// The rows are not removed immediately, instead, a tombstone is added to
+    /// the table to hide them, and they will be dropped by compaction.
+    pub async fn delete_from_table(
+        &self,
+        space_table: &SpaceAndTable,
+        request: DeleteRequest,
+    ) -> Result<()> {
+        info!(
+            "Instance delete from table, space_table:{:?}, request:{:?}",
+            space_table, request
+        );
+
+        self.validate_before_delete(space_table.table_data(), &request)?;
+
+        // Create a oneshot channel to send/receive delete result.
+        let (tx, rx) = oneshot::channel();
+        let cmd = DeleteTableCommand {
+            space_table: space_table.clone(),
+            request,
+            tx,
+        };
+
+        // Send delete request to write worker, actual works done in
+        // Self::process_delete_table_command()
+        write_worker::process_command_in_write_worker(
+            cmd.into_command(),
+            space_table.table_data(),
+            rx,
+        )
+        .await
+        .context(OperateByWriteWorker {
+            space_id: space_table.space().id,
+            table: &space_table.table_data().name,
+            table_id: space_table.table_data().id,
+        })
+    }
+
+    /// Do the actual delete job, must called by write worker in write thread
+    /// sequentially.
+    pub(crate) async fn process_delete_table_command(
+        self: &Arc<Self>,
+        worker_local: &mut WorkerLocal,
+        space_table: &SpaceAndTable,
+        request: DeleteRequest,
+    ) -> Result<()> {
+        let table_data = space_table.table_data();
+        ensure!(
+            !table_data.is_dropped(),
+            DeleteDroppedTable {
+                table: &table_data.name,
+            }
+        );
+
+        // We are in write thread now and there is no write request being processed.
+        // Flush the memtables if they may contain rows in the time range to delete,
+        // so the rows hidden by the tombstone are all in ssts, then only ssts need
+        // to be filtered by the tombstone.
+        //
+        // The flush blocks the writes of the table until it is done, so deleting
+        // the recently written rows is expensive, while deleting old rows is not.
+        if table_data
+            .current_version()
+            .memtables_intersect_with(request.time_range)
+        {
+            let opts = TableFlushOptions {
+                block_on_write_thread: true,
+                ..Default::default()
+            };
+            self.flush_table_in_worker(worker_local, table_data, opts)
+                .await
+                .context(FlushTable {
+                    space_id: table_data.space_id,
+                    table: &table_data.name,
+                    table_id: table_data.id,
+                })?;
+        }
+
+        let sequence = self.write_delete_to_wal(table_data, &request).await?;
+
+        let tombstone = Tombstone { sequence, request };
+        self.add_tombstone(table_data, tombstone).await?;
+
+        table_data.set_last_sequence(sequence);
+
+        Ok(())
+    }
+
+    /// Replay the delete request in wal during recovery.
+    ///
+    /// Caller should ensure that no data race happens.
+    pub(crate) async fn replay_delete_request(
+        &self,
+        worker_local: &WorkerLocal,
+        table_data: &TableDataRef,
+        sequence: SequenceNumber,
+        request: DeleteRequest,
+    ) -> Result<()> {
+        let exists = table_data
+            .current_version()
+            .tombstones()
+            .iter()
+            .any(|t| t.sequence == sequence);
+        if exists {
+            return Ok(());
+        }
+
+        warn!(
+            "Instance replay tombstone not in manifest, table:{}, table_id:{}, sequence:{}",
+            table_data.name, table_data.id, sequence
+        );
+
+        // The memtables replayed may contain rows written before the tombstone, flush
+        // them before adding the tombstone.
+        if table_data.memtable_memory_usage() > 0 {
+            let flush_req = self
+                .preprocess_flush_without_race(worker_local, table_data)
+                .await
+                .context(FlushTable {
+                    space_id: table_data.space_id,
+                    table: &table_data.name,
+                    table_id: table_data.id,
+                })?;
+            self.flush_memtables_to_outputs(&flush_req)
+                .await
+                .context(FlushTable {
+                    space_id: table_data.space_id,
+                    table: &table_data.name,
+                    table_id: table_data.id,
+                })?;
+        }
+
+        let tombstone = Tombstone { sequence, request };
+        self.add_tombstone(table_data, tombstone).await
+    }
+
+    /// Persist the tombstone to manifest and apply it to the table version.
+    ///
+    /// REQUIRE: The memtables contain no row written before the tombstone in
+    /// its time range.
+    async fn add_tombstone(&self, table_data: &TableData, tombstone: Tombstone) -> Result<()> {
+        let edit_meta = VersionEditMeta {
+            space_id: table_data.space_id,
+            table_id: table_data.id,
+            flushed_sequence: 0,
+            files_to_add: Vec::new(),
+            files_to_delete: Vec::new(),
+            tombstones_to_add: vec![tombstone.clone()],
+            tombstones_to_delete: Vec::new(),
+        };
+        self.space_store
+            .manifest
+            .store_update(MetaUpdate::VersionEdit(edit_meta))
+            .await
+            .map_err(|e| Box::new(e) as _)
+            .context(WriteManifest {
+                space_id: table_data.space_id,
+                table: &table_data.name,
+                table_id: table_data.id,
+            })?;
+
+        info!(
+            "Instance add tombstone to table, table:{}, table_id:{}, tombstone:{:?}",
+            table_data.name, table_data.id, tombstone
+        );
+
+        let edit = VersionEdit {
+            flushed_sequence: 0,
+            mems_to_remove: Vec::new(),
+            files_to_add: Vec::new(),
+            files_to_delete: Vec::new(),
+            tombstones_to_add: vec![tombstone],
+            tombstones_to_delete: Vec::new(),
+        };
+        table_data.current_version().apply_edit(edit);
+
+        Ok(())
+    }
+
+    /// Write the delete request into wal, return the sequence number of the
+    /// log entry.
+    async fn write_delete_to_wal(
+        &self,
+        table_data: &TableData,
+        request: &DeleteRequest,
+    ) -> Result<SequenceNumber> {
+        let delete_req_pb = tombstone::delete_request_to_pb(request);
+
+        let mut log_batch = LogWriteBatch::new(table_data.wal_region_id());
+        log_batch.push(LogWriteEntry {
+            payload: WritePayload::Delete(&delete_req_pb),
+        });
+
+        let write_ctx = WriteContext::default();
+        self.space_store
+            .wal_manager
+            .write(&write_ctx, &log_batch)
+            .await
+            .context(WriteDeleteLog {
+                space_id: table_data.space_id,
+                table: &table_data.name,
+                table_id: table_data.id,
+            })
+    }
+
+    /// Only tag columns of the table are allowed in the tag filters, and the
+    /// type of values must be the same as the column.
+    fn validate_before_delete(
+        &self,
+        table_data: &TableDataRef,
+        request: &DeleteRequest,
+    ) -> Result<()> {
+        let schema = table_data.schema();
+        for filter in &request.tag_filters {
+            let column = schema
+                .column_with_name(&filter.column)
+                .context(InvalidDeleteColumn {
+                    table: &table_data.name,
+                    column: &filter.column,
+                    msg: "column not found",
+                })?;
+            ensure!(
+                column.is_tag,
+                InvalidDeleteColumn {
+                    table: &table_data.name,
+                    column: &filter.column,
+                    msg: "column is not a tag",
+                }
+            );
+            ensure!(
+                filter.values.iter().all(|v| v.kind() == column.data_type),
+                InvalidDeleteColumn {
+                    table: &table_data.name,
+                    column: &filter.column,
+                    msg: "value type mismatch",
+                }
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -37,6 +37,20 @@ pub enum Error {
         backtrace: Backtrace,
     },
 
+    #[snafu(display(
+        "Table name is used by another table in the space, space_id:{}, table:{}, table_id:{}.\nBacktrace:\n{}",
+        space_id,
+        table,
+        table_id,
+        backtrace,
+    ))]
+    TableNameConflict {
+        space_id: SpaceId,
+        table: String,
+        table_id: TableId,
+        backtrace: Backtrace,
+    },
+
     #[snafu(display("Failed to read meta update, table_id:{}, err:{}", table_id, source))]
     ReadMetaUpdate {
         table_id: TableId,
@@ -175,6 +189,49 @@ pub enum Error {
         backtrace
     ))]
     AlterDroppedTable { table: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Alter column used by tombstones, table:{}, column:{}.\nBacktrace:\n{}",
+        table,
+        column,
+        backtrace
+    ))]
+    AlterTombstoneColumn {
+        table: String,
+        column: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Delete from a dropped table:{}.\nBacktrace:\n{}", table, backtrace))]
+    DeleteDroppedTable { table: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Invalid column to delete by, table:{}, column:{}, msg:{}.\nBacktrace:\n{}",
+        table,
+        column,
+        msg,
+        backtrace
+    ))]
+    InvalidDeleteColumn {
+        table: String,
+        column: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Failed to write delete request to wal, space_id:{}, table:{}, table_id:{}, err:{}",
+        space_id,
+        table,
+        table_id,
+        source
+    ))]
+    WriteDeleteLog {
+        space_id: SpaceId,
+        table: String,
+        table_id: TableId,
+        source: wal::manager::Error,
+    },
 }
 
 define_result!(Error);
@@ -182,12 +239,14 @@ define_result!(Error);
 impl From<Error> for table_engine::engine::Error {
     fn from(err: Error) -> Self {
         match &err {
-            Error::InvalidOptions { table, .. } | Error::SpaceNotExist { table, .. } => {
-                Self::InvalidArguments {
-                    table: table.clone(),
-                    source: Box::new(err),
-                }
-            }
+            Error::InvalidOptions { table, .. }
+            | Error::SpaceNotExist { table, .. }
+            | Error::TableNameConflict { table, .. }
+            | Error::InvalidDeleteColumn { table, .. }
+            | Error::AlterTombstoneColumn { table, .. } => Self::InvalidArguments {
+                table: table.clone(),
+                source: Box::new(err),
+            },
             Error::WriteManifest { .. } => Self::WriteMeta {
                 source: Box::new(err),
             },
@@ -195,6 +254,8 @@ impl From<Error> for table_engine::engine::Error {
             | Error::InvalidPreVersion { .. }
             | Error::CreateTableData { .. }
             | Error::AlterDroppedTable { .. }
+            | Error::DeleteDroppedTable { .. }
+            | Error::WriteDeleteLog { .. }
             | Error::ReadMetaUpdate { .. }
             | Error::RecoverTableData { .. }
             | Error::ReadWal { .. }
@@ -5,35 +5,41 @@
 use std::{cmp, collections::Bound, sync::Arc};
 
 use common_types::{
+    bytes::Bytes,
     projected_schema::ProjectedSchema,
     record_batch::{RecordBatchWithKey, RecordBatchWithKeyBuilder},
     request_id::RequestId,
-    row::RowViewOnBatch,
-    time::TimeRange,
+    row::{Row, RowViewOnBatch},
+    schema::Schema,
+    time::{TimeRange, Timestamp},
     SequenceNumber,
 };
-use common_util::{config::ReadableDuration, define_result, runtime::Runtime};
+use common_util::{codec::Encoder, config::ReadableDuration, define_result, runtime::Runtime};
 use futures::{
     channel::{mpsc, mpsc::channel},
     future::try_join_all,
-    stream, SinkExt, TryStreamExt,
+    stream, SinkExt, Stream, StreamExt, TryStreamExt,
 };
-use log::{error, info};
+use log::{error, info, warn};
 use object_store::ObjectStore;
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
-use table_engine::{predicate::Predicate, table::Result as TableResult};
+use table_engine::{predicate::Predicate, rollup::RollupOptions, table::Result as TableResult};
 use tokio::sync::oneshot;
 use wal::manager::{RegionId, WalManager};
 
 use crate::{
     compaction::{
-        CompactionInputFiles, CompactionTask, ExpiredFiles, TableCompactionRequest, WaitError,
+        rollup::Aggregator, CompactionInputFiles, CompactionTask, ExpiredFiles,
+        TableCompactionRequest, WaitError,
     },
     instance::{
         write_worker::{self, CompactTableCommand, FlushTableCommand, WorkerLocal},
         Instance, SpaceStore,
     },
-    memtable::{ColumnarIterPtr, MemTableRef, ScanContext, ScanRequest},
+    memtable::{
+        key::{ComparableInternalKey, KeySequence},
+        ColumnarIterPtr, MemTableRef, ScanContext, ScanRequest,
+    },
     meta::{
         meta_update::{AlterOptionsMeta, MetaUpdate, VersionEditMeta},
         Manifest,
@@ -42,13 +48,16 @@ use crate::{
         self,
         dedup::DedupIterator,
         merge::{MergeBuilder, MergeConfig},
+        record_batch_stream::{self, SequencedRecordBatch},
+        split::RecordBatchStreamSplitter,
         IterOptions,
     },
-    space::SpaceAndTable,
+    space::{SpaceAndTable, SpaceId},
     sst::{
+        bloom_filter::SstBloomFilter,
         builder::RecordBatchStream,
         factory::{Factory, SstBuilderOptions, SstReaderOptions, SstType},
-        file::{self, FileMeta, SstMetaData},
+        file::{self, FileHandle, FileMeta, Level, SstMetaData},
     },
     table::{
         data::{MemTableId, TableData, TableDataRef},
@@ -128,8 +137,30 @@ pub enum Error {
     #[snafu(display("Failed to send to channel, source:{}", source))]
     ChannelSend { source: mpsc::SendError },
 
+    #[snafu(display("Failed to encode key of sst, err:{}", source))]
+    EncodeSstKey { source: crate::memtable::key::Error },
+
     #[snafu(display("Runtime join error, source:{}", source))]
     RuntimeJoin { source: common_util::runtime::Error },
+
+    #[snafu(display("Failed to read record batch to rollup, err:{}", source))]
+    ReadRecordBatch {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[snafu(display("Failed to rollup rows, err:{}", source))]
+    Rollup {
+        source: crate::compaction::rollup::Error,
+    },
+
+    #[snafu(display("Failed to build stream to rollup, table:{}, err:{}", table, source))]
+    BuildRollupStream {
+        table: String,
+        source: crate::row_iter::record_batch_stream::Error,
+    },
+
+    #[snafu(display("No row is rolled up.\nBacktrace:\n{}", backtrace))]
+    EmptyRollupOutput { backtrace: Backtrace },
 }
 
 define_result!(Error);
@@ -450,6 +481,8 @@ where
             flushed_sequence,
             files_to_add: files_to_level0.clone(),
             files_to_delete: Vec::new(),
+            tombstones_to_add: Vec::new(),
+            tombstones_to_delete: Vec::new(),
         };
         let meta_update = MetaUpdate::VersionEdit(edit_meta);
         self.space_store
@@ -466,6 +499,8 @@ where
             mems_to_remove,
             files_to_add: files_to_level0,
             files_to_delete: Vec::new(),
+            tombstones_to_add: Vec::new(),
+            tombstones_to_delete: Vec::new(),
         };
         table_data.current_version().apply_edit(edit);
 
@@ -520,6 +555,7 @@ where
             sst_type: table_data.sst_type,
             num_rows_per_row_group: table_data.table_options().num_rows_per_row_group,
             compression: table_data.table_options().compression,
+            build_tag_index: table_data.table_options().enable_inverted_index,
         };
 
         for time_range in &time_ranges {
@@ -537,6 +573,11 @@ where
                 schema: table_data.schema(),
                 size: 0,
                 row_num: 0,
+                column_stats: Vec::new(),
+                bloom_filter: SstBloomFilter::default(),
+                tag_index: None,
+                rolled_up: false,
+                rollup_source_files: Vec::new(),
             };
 
             let store = self.space_store.clone();
@@ -572,6 +613,8 @@ where
                 // update sst metadata by built info.
                 sst_meta.row_num = sst_info.row_num as u64;
                 sst_meta.size = sst_info.file_size as u64;
+                sst_meta.column_stats = sst_info.column_stats;
+                sst_meta.tag_index = sst_info.tag_index;
                 Ok(sst_meta)
             });
 
@@ -640,6 +683,11 @@ where
             schema: table_data.schema(),
             size: 0,
             row_num: 0,
+            column_stats: Vec::new(),
+            bloom_filter: SstBloomFilter::default(),
+            tag_index: None,
+            rolled_up: false,
+            rollup_source_files: Vec::new(),
         };
 
         // Alloc file id for next sst file
@@ -650,6 +698,7 @@ where
             sst_type: table_data.sst_type,
             num_rows_per_row_group: table_data.table_options().num_rows_per_row_group,
             compression: table_data.table_options().compression,
+            build_tag_index: table_data.table_options().enable_inverted_index,
         };
         let mut builder = self
             .space_store
@@ -683,6 +732,8 @@ where
         // update sst metadata by built info.
         sst_meta.row_num = sst_info.row_num as u64;
         sst_meta.size = sst_info.file_size as u64;
+        sst_meta.column_stats = sst_info.column_stats;
+        sst_meta.tag_index = sst_info.tag_index;
 
         Ok(Some(FileMeta {
             id: file_id,
@@ -714,6 +765,8 @@ impl<Wal, Meta: Manifest, Store: ObjectStore, Fa: Factory> SpaceStore<Wal, Meta,
             // Use the number of compaction inputs as the estimated number of files to add.
             files_to_add: Vec::with_capacity(task.compaction_inputs.len()),
             files_to_delete: Vec::new(),
+            tombstones_to_add: Vec::new(),
+            tombstones_to_delete: Vec::new(),
         };
 
         if task.expired.is_empty() && task.compaction_inputs.is_empty() {
@@ -747,6 +800,44 @@ impl<Wal, Meta: Manifest, Store: ObjectStore, Fa: Factory> SpaceStore<Wal, Meta,
         let edit = edit_meta.into_version_edit();
         table_data.current_version().apply_edit(edit);
 
+        self.purge_dead_tombstones(table_data, request_id).await
+    }
+
+    /// Remove the tombstones no longer needed by any sst of the table.
+    async fn purge_dead_tombstones(
+        &self,
+        table_data: &TableData,
+        request_id: RequestId,
+    ) -> Result<()> {
+        let dead_tombstones = table_data.current_version().dead_tombstones();
+        if dead_tombstones.is_empty() {
+            return Ok(());
+        }
+
+        info!(
+            "Instance purge dead tombstones, table:{}, table_id:{}, request_id:{}, tombstones:{:?}",
+            table_data.name, table_data.id, request_id, dead_tombstones,
+        );
+
+        let edit_meta = VersionEditMeta {
+            space_id: table_data.space_id,
+            table_id: table_data.id,
+            flushed_sequence: 0,
+            files_to_add: Vec::new(),
+            files_to_delete: Vec::new(),
+            tombstones_to_add: Vec::new(),
+            tombstones_to_delete: dead_tombstones,
+        };
+        let meta_update = MetaUpdate::VersionEdit(edit_meta.clone());
+        self.manifest
+            .store_update(meta_update)
+            .await
+            .map_err(|e| Box::new(e) as _)
+            .context(StoreVersionEdit)?;
+
+        let edit = edit_meta.into_version_edit();
+        table_data.current_version().apply_edit(edit);
+
         Ok(())
     }
 
@@ -761,6 +852,7 @@ impl<Wal, Meta: Manifest, Store: ObjectStore, Fa: Factory> SpaceStore<Wal, Meta,
         if input.files.is_empty() {
             return Ok(());
         }
+        let input_files: Vec<_> = input.iter_files().cloned().collect();
 
         // metrics
         let _timer = table_data
@@ -769,10 +861,10 @@ impl<Wal, Meta: Manifest, Store: ObjectStore, Fa: Factory> SpaceStore<Wal, Meta,
             .start_timer();
         table_data
             .metrics
-            .compaction_observe_sst_num(input.files.len());
+            .compaction_observe_sst_num(input_files.len());
         let mut sst_size = 0;
         let mut sst_row_num = 0;
-        for file in &input.files {
+        for file in &input_files {
             sst_size += file.size();
             sst_row_num += file.row_num();
         }
@@ -784,8 +876,8 @@ impl<Wal, Meta: Manifest, Store: ObjectStore, Fa: Factory> SpaceStore<Wal, Meta,
             .compaction_observe_input_sst_row_num(sst_row_num);
 
         info!(
-            "Instance try to compact table, table:{}, table_id:{}, request_id:{}, input_files:{:?}",
-            table_data.name, table_data.id, request_id, input.files,
+            "Instance try to compact table, table:{}, table_id:{}, request_id:{}, input_files:{:?}, output_level_files:{:?}",
+            table_data.name, table_data.id, request_id, input.files, input.output_level_files,
         );
 
         // The schema may be modified during compaction, so we acquire it first and use
@@ -793,45 +885,187 @@ impl<Wal, Meta: Manifest, Store: ObjectStore, Fa: Factory> SpaceStore<Wal, Meta,
         let schema = table_data.schema();
         let table_options = table_data.table_options();
 
-        let iter_options = IterOptions::default();
-        let merge_iter = {
-            let space_id = table_data.space_id;
-            let table_id = table_data.id;
-            let sequence = table_data.last_sequence();
-            let projected_schema = ProjectedSchema::no_projection(schema.clone());
-            let sst_reader_options = SstReaderOptions {
-                sst_type: table_data.sst_type,
-                read_batch_row_num: table_options.num_rows_per_row_group,
-                reverse: false,
-                projected_schema: projected_schema.clone(),
-                predicate: Arc::new(Predicate::new(TimeRange::min_to_max())),
-                meta_cache: self.meta_cache.clone(),
-                data_cache: self.data_cache.clone(),
-                runtime: runtime.clone(),
-            };
-            let mut builder = MergeBuilder::new(MergeConfig {
+        // Drop the expired rows of the partially expired ssts.
+        let record_batch_stream = self
+            .build_merge_stream(
+                runtime.clone(),
+                table_data,
                 request_id,
-                space_id,
-                table_id,
-                sequence,
-                projected_schema,
-                predicate: Arc::new(Predicate::empty()),
-                sst_factory: self.sst_factory.clone(),
-                sst_reader_options,
-                store: self.store_ref(),
-                merge_iter_options: iter_options.clone(),
-                need_dedup: table_options.need_dedup(),
-                reverse: false,
+                &schema,
+                &[
+                    (input.level, &input.files),
+                    (input.output_level, &input.output_level_files),
+                ],
+                table_options.expire_time(),
+            )
+            .await?;
+
+        let mut sst_meta = file::merge_sst_meta(&input_files, schema.clone());
+        // Only keep the rollup source files not compacted yet, the rows of the
+        // others are still rolled up as they are compacted into rolled up files.
+        if let Some(source_table) = table_engine::rollup::source_table_name(&table_data.name)
+            .and_then(|name| self.find_table_in_space(table_data.space_id, name))
+        {
+            let source_files = source_table.current_version().sst_file_ids();
+            sst_meta
+                .rollup_source_files
+                .retain(|file_id| source_files.contains(file_id));
+        }
+
+        // Estimate the number of rows of an output sst by the average row size of
+        // the input ssts.
+        let num_rows_per_sst = match input.target_file_size {
+            Some(target_file_size) if sst_size > 0 => {
+                let num_rows =
+                    target_file_size.as_bytes() as u128 * sst_row_num as u128 / sst_size as u128;
+                cmp::max(1, num_rows as usize)
+            }
+            _ => usize::MAX,
+        };
+        let mut splitter = RecordBatchStreamSplitter::new(record_batch_stream, num_rows_per_sst);
+
+        let sst_builder_options = SstBuilderOptions {
+            sst_type: table_data.sst_type,
+            num_rows_per_row_group: table_options.num_rows_per_row_group,
+            compression: table_options.compression,
+            build_tag_index: table_options.enable_inverted_index,
+        };
+        let mut output_files = Vec::new();
+        while let Some(split_stream) = splitter.next_split().await {
+            // Alloc file id for the merged sst.
+            let file_id = table_data.alloc_file_id();
+            let sst_file_path = table_data.set_sst_file_path(file_id);
+
+            let mut sst_builder = self
+                .sst_factory
+                .new_sst_builder(&sst_builder_options, &sst_file_path, self.store_ref())
+                .context(InvalidSstType {
+                    sst_type: table_data.sst_type,
+                })?;
+
+            let sst_info = sst_builder
+                .build(request_id, &sst_meta, split_stream)
+                .await
+                .map_err(|e| Box::new(e) as _)
+                .with_context(|| FailBuildSst {
+                    path: sst_file_path.to_string(),
+                })?;
+
+            // update sst metadata by built info.
+            let mut output_meta = sst_meta.clone();
+            if let Some((first_key, last_key)) = splitter.key_range_of_split().await {
+                output_meta.min_key = encode_sst_key(&schema, sst_meta.max_sequence, &first_key)?;
+                output_meta.max_key = encode_sst_key(&schema, sst_meta.max_sequence, &last_key)?;
+            }
+            output_meta.row_num = sst_info.row_num as u64;
+            output_meta.size = sst_info.file_size as u64;
+            output_meta.column_stats = sst_info.column_stats;
+            output_meta.tag_index = sst_info.tag_index;
+
+            table_data
+                .metrics
+                .compaction_observe_output_sst_size(output_meta.size);
+            table_data
+                .metrics
+                .compaction_observe_output_sst_row_num(output_meta.row_num);
+
+            info!(
+                "Instance files compacted, table:{}, table_id:{}, request_id:{}, output_path:{}, input_files:{:?}, sst_meta:{:?}",
+                table_data.name,
+                table_data.id,
+                request_id,
+                sst_file_path.to_string(),
+                input_files,
+                output_meta
+            );
+
+            output_files.push(FileMeta {
+                id: file_id,
+                meta: output_meta,
             });
-            // Add all ssts in compaction input to builder.
-            builder
-                .mut_ssts_of_level(input.level)
-                .extend_from_slice(&input.files);
-            let merge_iter = builder.build().await.context(BuildMergeIterator {
-                table: table_data.name.clone(),
-            })?;
-            merge_iter
+        }
+
+        // Rollup after the output ssts are built, so the rollup ssts are less likely
+        // to be left unused when building the output ssts failed.
+        let rolled_up = self
+            .rollup_input_files(runtime, table_data, request_id, &schema, input)
+            .await?;
+
+        // Store updates to edit_meta.
+        edit_meta.files_to_delete.reserve(input_files.len());
+        // The compacted file can be deleted later.
+        for file in &input.files {
+            edit_meta.files_to_delete.push(DeleteFile {
+                level: input.level,
+                file_id: file.id(),
+            });
+        }
+        for file in &input.output_level_files {
+            edit_meta.files_to_delete.push(DeleteFile {
+                level: input.output_level,
+                file_id: file.id(),
+            });
+        }
+        // Add the newly created files to meta.
+        for mut file in output_files {
+            file.meta.rolled_up = rolled_up;
+            edit_meta.files_to_add.push(AddFile {
+                level: input.output_level,
+                file,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Build the stream of the rows merged from the `ssts` of each level.
+    async fn build_merge_stream(
+        &self,
+        runtime: Arc<Runtime>,
+        table_data: &TableData,
+        request_id: RequestId,
+        schema: &Schema,
+        ssts: &[(Level, &Vec<FileHandle>)],
+        expire_time: Option<Timestamp>,
+    ) -> Result<RecordBatchStream> {
+        let table_options = table_data.table_options();
+        let iter_options = IterOptions::default();
+        let projected_schema = ProjectedSchema::no_projection(schema.clone());
+        let sst_reader_options = SstReaderOptions {
+            sst_type: table_data.sst_type,
+            read_batch_row_num: table_options.num_rows_per_row_group,
+            reverse: false,
+            projected_schema: projected_schema.clone(),
+            predicate: Arc::new(Predicate::new(TimeRange::min_to_max())),
+            meta_cache: self.meta_cache.clone(),
+            data_cache: self.data_cache.clone(),
+            runtime: runtime.clone(),
+            tsid_filter: None,
         };
+        let mut builder = MergeBuilder::new(MergeConfig {
+            request_id,
+            space_id: table_data.space_id,
+            table_id: table_data.id,
+            sequence: table_data.last_sequence(),
+            projected_schema,
+            predicate: Arc::new(Predicate::empty()),
+            sst_factory: self.sst_factory.clone(),
+            sst_reader_options,
+            store: self.store_ref(),
+            merge_iter_options: iter_options.clone(),
+            need_dedup: table_options.need_dedup(),
+            reverse: false,
+            // Drop the rows hidden by tombstones physically.
+            tombstones: table_data.current_version().tombstones(),
+            expire_time,
+            limit: None,
+        });
+        for (level, files) in ssts {
+            builder.mut_ssts_of_level(*level).extend_from_slice(files);
+        }
+        let merge_iter = builder.build().await.context(BuildMergeIterator {
+            table: table_data.name.clone(),
+        })?;
 
         let record_batch_stream = if table_options.need_dedup() {
             row_iter::record_batch_with_key_iter_to_stream(
@@ -842,72 +1076,264 @@ impl<Wal, Meta: Manifest, Store: ObjectStore, Fa: Factory> SpaceStore<Wal, Meta,
             row_iter::record_batch_with_key_iter_to_stream(merge_iter, &runtime)
         };
 
-        let mut sst_meta = file::merge_sst_meta(&input.files, schema);
+        Ok(record_batch_stream)
+    }
 
-        // Alloc file id for the merged sst.
-        let file_id = table_data.alloc_file_id();
-        let sst_file_path = table_data.set_sst_file_path(file_id);
+    /// Rollup the rows of the input files not rolled up yet into the rollup
+    /// table of the table, so every row is rolled up exactly once.
+    ///
+    /// Each file is rolled up into a sst of the rollup table, which records the
+    /// id of the file, so the files already rolled up are skipped if a
+    /// compaction failed after rollup is retried.
+    ///
+    /// Returns whether the rows of the output files should be considered as
+    /// rolled up.
+    async fn rollup_input_files(
+        &self,
+        runtime: Arc<Runtime>,
+        table_data: &TableData,
+        request_id: RequestId,
+        schema: &Schema,
+        input: &CompactionInputFiles,
+    ) -> Result<bool> {
+        let table_options = table_data.table_options();
+        let rollup = match &table_options.rollup {
+            Some(v) => v,
+            None => return Ok(false),
+        };
 
-        let sst_builder_options = SstBuilderOptions {
+        if input.iter_files().all(|f| f.rolled_up()) {
+            return Ok(true);
+        }
+
+        let rollup_table_name = table_engine::rollup::rollup_table_name(&table_data.name);
+        let rollup_table = match self.find_table_in_space(table_data.space_id, &rollup_table_name) {
+            Some(v) => v,
+            None => {
+                // Nothing to do if the rollup table is dropped, also mark the rows as
+                // rolled up so they won't be rolled up if the rollup table is created
+                // again.
+                warn!(
+                    "Rollup table not found, table:{}, table_id:{}, request_id:{}, rollup_table:{}",
+                    table_data.name, table_data.id, request_id, rollup_table_name
+                );
+                return Ok(true);
+            }
+        };
+
+        let rolled_up_files = rollup_table.current_version().rollup_source_files();
+        let files: Vec<_> = input
+            .iter_files()
+            .filter(|f| !f.rolled_up() && !rolled_up_files.contains(&f.id()))
+            .collect();
+
+        let mut files_to_add = Vec::with_capacity(files.len());
+        for file in files {
+            if let Some(file_meta) = self
+                .rollup_file(
+                    runtime.clone(),
+                    table_data,
+                    request_id,
+                    schema,
+                    rollup,
+                    &rollup_table,
+                    file,
+                )
+                .await?
+            {
+                files_to_add.push(AddFile {
+                    level: 0,
+                    file: file_meta,
+                });
+            }
+        }
+        if files_to_add.is_empty() {
+            return Ok(true);
+        }
+
+        // The rollup ssts are added to the level 0 of the rollup table directly,
+        // just like the ssts flushed from memtables.
+        let edit_meta = VersionEditMeta {
+            space_id: rollup_table.space_id,
+            table_id: rollup_table.id,
+            flushed_sequence: 0,
+            files_to_add,
+            files_to_delete: Vec::new(),
+            tombstones_to_add: Vec::new(),
+            tombstones_to_delete: Vec::new(),
+        };
+        let meta_update = MetaUpdate::VersionEdit(edit_meta.clone());
+        self.manifest
+            .store_update(meta_update)
+            .await
+            .map_err(|e| Box::new(e) as _)
+            .context(StoreVersionEdit)?;
+        rollup_table
+            .current_version()
+            .apply_edit(edit_meta.into_version_edit());
+
+        Ok(true)
+    }
+
+    /// Rollup the rows of the `file` into a new sst of the `rollup_table`,
+    /// returns None if there is no row to rollup.
+    ///
+    /// The rows of a sst are sorted by the key, so they are aggregated while
+    /// being read without merging with other ssts.
+    #[allow(clippy::too_many_arguments)]
+    async fn rollup_file(
+        &self,
+        runtime: Arc<Runtime>,
+        table_data: &TableData,
+        request_id: RequestId,
+        schema: &Schema,
+        rollup: &RollupOptions,
+        rollup_table: &TableData,
+        file: &FileHandle,
+    ) -> Result<Option<FileMeta>> {
+        let table_options = table_data.table_options();
+        let sst_reader_options = SstReaderOptions {
             sst_type: table_data.sst_type,
-            num_rows_per_row_group: table_options.num_rows_per_row_group,
-            compression: table_options.compression,
+            read_batch_row_num: table_options.num_rows_per_row_group,
+            reverse: false,
+            projected_schema: ProjectedSchema::no_projection(schema.clone()),
+            predicate: Arc::new(Predicate::new(TimeRange::min_to_max())),
+            meta_cache: self.meta_cache.clone(),
+            data_cache: self.data_cache.clone(),
+            runtime,
+            tsid_filter: None,
+        };
+        // The rows expired but not yet removed are still rolled up, as the rollup
+        // table usually keeps the rows longer.
+        let source_stream = record_batch_stream::filtered_stream_from_sst_file(
+            table_data.space_id,
+            table_data.id,
+            file,
+            &self.sst_factory,
+            &sst_reader_options,
+            self.store_ref(),
+            &table_data.current_version().tombstones(),
+            None,
+        )
+        .await
+        .context(BuildRollupStream {
+            table: table_data.name.clone(),
+        })?;
+        let mut source_stream = source_stream.fuse();
+
+        let rollup_schema = rollup_table.schema();
+        let rollup_options = rollup_table.table_options();
+        let sequence = rollup_table.last_sequence();
+        let mut aggregator = Aggregator::new(
+            schema,
+            rollup_schema.clone(),
+            rollup,
+            sequence,
+            rollup_options.num_rows_per_row_group,
+        );
+
+        // Don't build the sst until there are rows to rollup.
+        let first_batch = match next_rollup_batch(&mut source_stream, &mut aggregator).await? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        let file_id = rollup_table.alloc_file_id();
+        let sst_file_path = rollup_table.set_sst_file_path(file_id);
+        let sst_builder_options = SstBuilderOptions {
+            sst_type: rollup_table.sst_type,
+            num_rows_per_row_group: rollup_options.num_rows_per_row_group,
+            compression: rollup_options.compression,
+            build_tag_index: rollup_options.enable_inverted_index,
         };
         let mut sst_builder = self
             .sst_factory
             .new_sst_builder(&sst_builder_options, &sst_file_path, self.store_ref())
             .context(InvalidSstType {
-                sst_type: table_data.sst_type,
+                sst_type: rollup_table.sst_type,
             })?;
 
-        let sst_info = sst_builder
-            .build(request_id, &sst_meta, record_batch_stream)
-            .await
+        // The key range and time range are unknown until all the rows are rolled up.
+        let mut sst_meta = SstMetaData {
+            min_key: Bytes::new(),
+            max_key: Bytes::new(),
+            time_range: file.time_range(),
+            max_sequence: sequence,
+            schema: rollup_schema,
+            size: 0,
+            row_num: 0,
+            column_stats: Vec::new(),
+            bloom_filter: SstBloomFilter::default(),
+            tag_index: None,
+            rolled_up: false,
+            rollup_source_files: vec![file.id()],
+        };
+
+        let (mut batch_record_sender, batch_record_receiver) =
+            channel::<Result<RecordBatchWithKey>>(DEFAULT_CHANNEL_SIZE);
+        let aggregator_ref = &mut aggregator;
+        // The sender is moved into the future and dropped once all the batches are
+        // sent, which finishes the stream of the sst builder.
+        let send_batches = async move {
+            let mut next_batch = Some(first_batch);
+            while let Some(record_batch) = next_batch {
+                batch_record_sender
+                    .send(Ok(record_batch))
+                    .await
+                    .context(ChannelSend)?;
+                next_batch = next_rollup_batch(&mut source_stream, aggregator_ref).await?;
+            }
+
+            Ok::<_, Error>(())
+        };
+        let build_sst = sst_builder.build(
+            request_id,
+            &sst_meta,
+            Box::new(batch_record_receiver.map_err(|e| Box::new(e) as _)),
+        );
+        let (build_result, send_result) = futures::join!(build_sst, send_batches);
+        let sst_info = build_result
             .map_err(|e| Box::new(e) as _)
             .with_context(|| FailBuildSst {
                 path: sst_file_path.to_string(),
             })?;
+        send_result?;
 
-        // update sst metadata by built info.
+        // At least one row is rolled up as the first batch is not None.
+        let stats = aggregator.stats().context(EmptyRollupOutput)?;
+        sst_meta.min_key = stats.min_key;
+        sst_meta.max_key = stats.max_key;
+        sst_meta.time_range = stats.time_range;
         sst_meta.row_num = sst_info.row_num as u64;
         sst_meta.size = sst_info.file_size as u64;
-
-        table_data
-            .metrics
-            .compaction_observe_output_sst_size(sst_meta.size);
-        table_data
-            .metrics
-            .compaction_observe_output_sst_row_num(sst_meta.row_num);
+        sst_meta.column_stats = sst_info.column_stats;
+        sst_meta.tag_index = sst_info.tag_index;
 
         info!(
-            "Instance files compacted, table:{}, table_id:{}, request_id:{}, output_path:{}, input_files:{:?}, sst_meta:{:?}",
+            "Instance rollup sst, table:{}, table_id:{}, request_id:{}, file_id:{}, rollup_table:{}, output_path:{}, rows:{}",
             table_data.name,
             table_data.id,
             request_id,
+            file.id(),
+            rollup_table.name,
             sst_file_path.to_string(),
-            input.files,
-            sst_meta
+            stats.row_num,
         );
 
-        // Store updates to edit_meta.
-        edit_meta.files_to_delete.reserve(input.files.len());
-        // The compacted file can be deleted later.
-        for file in &input.files {
-            edit_meta.files_to_delete.push(DeleteFile {
-                level: input.level,
-                file_id: file.id(),
-            });
-        }
-        // Add the newly created file to meta.
-        edit_meta.files_to_add.push(AddFile {
-            level: input.output_level,
-            file: FileMeta {
-                id: file_id,
-                meta: sst_meta,
-            },
-        });
+        Ok(Some(FileMeta {
+            id: file_id,
+            meta: sst_meta,
+        }))
+    }
 
-        Ok(())
+    /// Find the table not dropped by name in the space.
+    fn find_table_in_space(&self, space_id: SpaceId, table_name: &str) -> Option<TableDataRef> {
+        self.spaces
+            .read()
+            .unwrap()
+            .get_by_id(space_id)
+            .and_then(|space| space.find_table(table_name))
+            .filter(|table| !table.is_dropped())
     }
 
     pub(crate) fn delete_expired_files(
@@ -935,6 +1361,48 @@ impl<Wal, Meta: Manifest, Store: ObjectStore, Fa: Factory> SpaceStore<Wal, Meta,
     }
 }
 
+/// Encode the key columns in `key` into the key of the sst meta, which has the
+/// same format as the key of the memtable.
+fn encode_sst_key(schema: &Schema, sequence: SequenceNumber, key: &Row) -> Result<Bytes> {
+    let mut buf = Vec::new();
+    ComparableInternalKey::new(KeySequence::new(sequence, 0), schema)
+        .encode(&mut buf, key)
+        .context(EncodeSstKey)?;
+
+    Ok(buf.into())
+}
+
+/// Returns the next record batch of the rows rolled up from the `source`, or
+/// None if all the rows are rolled up.
+async fn next_rollup_batch<S>(
+    source: &mut S,
+    aggregator: &mut Aggregator,
+) -> Result<Option<RecordBatchWithKey>>
+where
+    S: Stream<
+            Item = std::result::Result<
+                SequencedRecordBatch,
+                Box<dyn std::error::Error + Send + Sync>,
+            >,
+        > + Unpin,
+{
+    loop {
+        if let Some(record_batch) = aggregator.pop_output() {
+            return Ok(Some(record_batch));
+        }
+
+        match source.try_next().await.context(ReadRecordBatch)? {
+            Some(sequenced_record_batch) => aggregator
+                .aggregate(&sequenced_record_batch.record_batch)
+                .context(Rollup)?,
+            None => {
+                aggregator.finish().context(Rollup)?;
+                return Ok(aggregator.pop_output());
+            }
+        }
+    }
+}
+
 fn split_record_batch_with_time_ranges(
     record_batch: RecordBatchWithKey,
     time_ranges: &[TimeRange],
@@ -993,6 +1461,7 @@ fn build_mem_table_iter(memtable: MemTableRef, table_data: &TableData) -> Result
         projected_schema: ProjectedSchema::no_projection(table_data.schema()),
         need_dedup: table_data.dedup(),
         reverse: false,
+        tsid_filter: None,
     };
     memtable
         .scan(scan_ctx, scan_req)
@@ -8,6 +8,7 @@
 mod alter;
 mod close;
 mod create;
+mod delete;
 mod drop;
 pub mod engine;
 pub mod flush_compaction;
@@ -7,7 +7,7 @@ use std::sync::{Arc, RwLock};
 use common_types::schema::IndexInWriterSchema;
 use log::{debug, error, info, trace, warn};
 use object_store::ObjectStore;
-use snafu::ResultExt;
+use snafu::{ensure, ResultExt};
 use table_engine::table::TableId;
 use tokio::sync::oneshot;
 use wal::{
@@ -21,7 +21,7 @@ use crate::{
     instance::{
         engine::{
             ApplyMemTable, FlushTable, OperateByWriteWorker, ReadMetaUpdate, ReadWal,
-            RecoverTableData, Result,
+            RecoverTableData, Result, TableNameConflict,
         },
         mem_collector::MemUsageCollector,
         write_worker,
@@ -183,7 +183,14 @@ where
         )
         .await?;
 
-        space.insert_table(table_data.clone());
+        ensure!(
+            space.insert_table(table_data.clone()),
+            TableNameConflict {
+                space_id: space.id,
+                table: &table_data.name,
+                table_id: table_data.id,
+            }
+        );
         Ok(Some(table_data))
     }
 
@@ -402,6 +409,16 @@ where
                             })?;
                     }
                 }
+                ReadPayload::Delete { request } => {
+                    trace!(
+                        "Instance replay delete request, table:{}, request:{:?}",
+                        table_data.name,
+                        request
+                    );
+
+                    self.replay_delete_request(worker_local, table_data, sequence, request.clone())
+                        .await?;
+                }
             }
         }
 
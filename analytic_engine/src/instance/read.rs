@@ -3,21 +3,24 @@
 //! Read logic of instance
 
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashSet},
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
 use common_types::{
-    projected_schema::ProjectedSchema, record_batch::RecordBatch, schema::RecordSchema,
-    time::TimeRange,
+    projected_schema::ProjectedSchema,
+    record_batch::RecordBatch,
+    schema::{RecordSchema, Schema},
 };
 use common_util::{define_result, runtime::Runtime};
-use futures::stream::Stream;
+use futures::{future, stream::Stream};
 use log::{debug, error, trace};
 use object_store::ObjectStore;
 use snafu::{ResultExt, Snafu};
 use table_engine::{
+    predicate::Predicate,
     stream::{
         self, ErrWithSource, PartitionedStreams, RecordBatchStream, SendableRecordBatchStream,
     },
@@ -37,12 +40,14 @@ use crate::{
         IterOptions, RecordBatchWithKeyIterator,
     },
     space::SpaceAndTable,
-    sst::factory::{Factory, SstReaderOptions},
-    table::{
-        data::TableData,
-        version::{ReadView, TableVersion},
+    sst::{
+        factory::{Factory, SstReaderOptions},
+        file::FileHandle,
+        manager::FileId,
     },
+    table::{data::TableData, sst_util, tombstone::Tombstone, version::ReadView},
     table_options::TableOptions,
+    tag_index::TsidSetRef,
 };
 
 #[derive(Debug, Snafu)]
@@ -64,6 +69,28 @@ pub enum Error {
         table: String,
         source: crate::row_iter::chain::Error,
     },
+
+    #[snafu(display(
+        "Failed to project columns required by tombstones, table:{}, err:{}",
+        table,
+        source
+    ))]
+    ProjectTombstoneColumns {
+        table: String,
+        source: common_types::projected_schema::Error,
+    },
+
+    #[snafu(display(
+        "Failed to load tag index of sst, table:{}, file_id:{}, err:{}",
+        table,
+        file_id,
+        source
+    ))]
+    LoadTagIndex {
+        table: String,
+        file_id: FileId,
+        source: crate::sst::reader::Error,
+    },
 }
 
 define_result!(Error);
@@ -76,6 +103,161 @@ fn need_merge_sort_streams(table_options: &TableOptions, read_request: &ReadRequ
     table_options.need_dedup() || read_request.order.is_in_order()
 }
 
+/// Extend the `projected_schema` with the columns required by the
+/// `tombstones`, so the rows hidden by tombstones can be filtered out before
+/// merging.
+///
+/// The extra columns are removed when the record batches are projected by the
+/// original `projected_schema`.
+fn projected_schema_with_tombstones(
+    table: &str,
+    projected_schema: &ProjectedSchema,
+    tombstones: &[Tombstone],
+) -> Result<ProjectedSchema> {
+    let projection = match projected_schema.projection() {
+        Some(v) => v,
+        // All columns are projected.
+        None => return Ok(projected_schema.clone()),
+    };
+
+    let schema = projected_schema.original_schema();
+    let mut extended_projection = projection.to_vec();
+    for column in tombstones.iter().flat_map(|t| t.tag_columns()) {
+        if let Some(idx) = schema.index_of(column) {
+            if !extended_projection.contains(&idx) {
+                extended_projection.push(idx);
+            }
+        }
+    }
+
+    if extended_projection.len() == projection.len() {
+        return Ok(projected_schema.clone());
+    }
+
+    ProjectedSchema::new(schema.clone(), Some(extended_projection))
+        .context(ProjectTombstoneColumns { table })
+}
+
+/// Turn the tag predicates into the tsids of the series satisfying them by the
+/// tag indexes of the memtables and ssts in the `read_view`, the memtables and
+/// ssts having no such series are removed from the `read_view`.
+///
+/// The tsids are shared by all the scans, so None is returned if any memtable
+/// or sst left can't be looked up by its tag index, or no tag column is
+/// restricted by the `predicate`.
+fn prune_read_view_by_tag_index(
+    read_view: &mut ReadView,
+    schema: &Schema,
+    predicate: &Predicate,
+) -> Option<TsidSetRef> {
+    if predicate.exprs.is_empty() {
+        return None;
+    }
+
+    let mut tsids = HashSet::new();
+    let mut indexed = false;
+    // Whether any memtable or sst is read without looking up the tag index.
+    let mut unindexed = false;
+    // The sampling memtable is always read as it decides the segment duration.
+    if let Some(sampling_mem) = &read_view.sampling_mem {
+        match sampling_mem.mem.lookup_tsids(schema, predicate) {
+            Some(v) => {
+                indexed = true;
+                tsids.extend(v);
+            }
+            None => unindexed = true,
+        }
+    }
+    read_view.memtables.retain(
+        |mem_state| match mem_state.mem.lookup_tsids(schema, predicate) {
+            Some(v) => {
+                indexed = true;
+                let selected = !v.is_empty();
+                tsids.extend(v);
+                selected
+            }
+            None => {
+                unindexed = true;
+                true
+            }
+        },
+    );
+    for ssts in &mut read_view.leveled_ssts {
+        ssts.retain(|file| {
+            match file
+                .tag_index()
+                .and_then(|index| index.lookup(schema, predicate))
+            {
+                Some(v) => {
+                    indexed = true;
+                    let selected = !v.is_empty();
+                    tsids.extend(v);
+                    selected
+                }
+                None => {
+                    unindexed = true;
+                    true
+                }
+            }
+        });
+    }
+
+    if !indexed || unindexed {
+        return None;
+    }
+
+    debug!(
+        "Look up tsids by tag index, num_tsids:{}, predicate:{:?}",
+        tsids.len(),
+        predicate
+    );
+
+    Some(Arc::new(tsids))
+}
+
+/// Load the tag indexes of the ssts in the `read_view` from the sst meta if
+/// not loaded yet.
+async fn load_tag_indexes<Fa: Factory, S: ObjectStore>(
+    table_data: &TableData,
+    read_view: &ReadView,
+    sst_factory: &Fa,
+    sst_reader_options: &SstReaderOptions,
+    store: &S,
+) -> Result<()> {
+    let load_futures = read_view
+        .leveled_ssts
+        .iter()
+        .flatten()
+        .filter(|file| file.need_load_tag_index())
+        .map(|file| load_tag_index(table_data, file, sst_factory, sst_reader_options, store));
+    future::try_join_all(load_futures).await?;
+
+    Ok(())
+}
+
+async fn load_tag_index<Fa: Factory, S: ObjectStore>(
+    table_data: &TableData,
+    file: &FileHandle,
+    sst_factory: &Fa,
+    sst_reader_options: &SstReaderOptions,
+    store: &S,
+) -> Result<()> {
+    let path = sst_util::new_sst_file_path(table_data.space_id, table_data.id, file.id());
+    let mut sst_reader = match sst_factory.new_sst_reader(sst_reader_options, &path, store) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    // The sst meta is cached by the meta cache, so it won't be fetched again
+    // when the sst is read later.
+    let meta_data = sst_reader.meta_data().await.context(LoadTagIndex {
+        table: &table_data.name,
+        file_id: file.id(),
+    })?;
+    file.set_tag_index(meta_data.tag_index.clone());
+
+    Ok(())
+}
+
 impl<Wal: WalManager + Send + Sync, Meta: Manifest, Store: ObjectStore, Fa: Factory>
     Instance<Wal, Meta, Store, Fa>
 {
@@ -138,7 +320,12 @@ impl<Wal: WalManager + Send + Sync, Meta: Manifest, Store: ObjectStore, Fa: Fact
 
         let mut streams = Vec::with_capacity(read_parallelism);
         for iters in splited_iters {
-            let stream = iters_to_stream(iters, self.read_runtime(), &request.projected_schema);
+            let stream = iters_to_stream(
+                iters,
+                self.read_runtime(),
+                &request.projected_schema,
+                request.limit,
+            );
             streams.push(stream);
         }
 
@@ -156,8 +343,16 @@ impl<Wal: WalManager + Send + Sync, Meta: Manifest, Store: ObjectStore, Fa: Fact
     ) -> Result<Vec<DedupIterator<MergeIterator>>> {
         // Current visible sequence
         let sequence = table_data.last_sequence();
-        let projected_schema = request.projected_schema.clone();
-        let sst_reader_options = SstReaderOptions {
+        let version = table_data.current_version();
+        let tombstones = version.tombstones();
+        let projected_schema = projected_schema_with_tombstones(
+            &table_data.name,
+            &request.projected_schema,
+            &tombstones,
+        )?;
+        let time_range = request.predicate.time_range;
+        let mut read_view = version.pick_read_view(time_range);
+        let mut sst_reader_options = SstReaderOptions {
             sst_type: table_data.sst_type,
             read_batch_row_num: table_options.num_rows_per_row_group,
             reverse: request.order.is_in_desc_order(),
@@ -166,11 +361,27 @@ impl<Wal: WalManager + Send + Sync, Meta: Manifest, Store: ObjectStore, Fa: Fact
             meta_cache: self.meta_cache.clone(),
             data_cache: self.data_cache.clone(),
             runtime: self.read_runtime().clone(),
+            tsid_filter: None,
         };
+        if !request.predicate.exprs.is_empty() {
+            load_tag_indexes(
+                table_data,
+                &read_view,
+                &self.space_store.sst_factory,
+                &sst_reader_options,
+                self.space_store.store_ref(),
+            )
+            .await?;
+            sst_reader_options.tsid_filter = prune_read_view_by_tag_index(
+                &mut read_view,
+                &table_data.schema(),
+                &request.predicate,
+            );
+        }
 
-        let time_range = request.predicate.time_range;
-        let version = table_data.current_version();
-        let read_views = self.partition_ssts_and_memtables(time_range, version, &*table_options);
+        let read_views = self.partition_ssts_and_memtables(read_view, &*table_options);
+        // Hide the expired rows not yet dropped by compaction.
+        let expire_time = table_options.expire_time();
 
         let mut iters = Vec::with_capacity(read_views.len());
         for read_view in read_views {
@@ -187,6 +398,9 @@ impl<Wal: WalManager + Send + Sync, Meta: Manifest, Store: ObjectStore, Fa: Fact
                 merge_iter_options: iter_options.clone(),
                 need_dedup: table_options.need_dedup(),
                 reverse: request.order.is_in_desc_order(),
+                tombstones: tombstones.clone(),
+                expire_time,
+                limit: request.limit,
             };
 
             let merge_iter = MergeBuilder::new(merge_config)
@@ -213,11 +427,19 @@ impl<Wal: WalManager + Send + Sync, Meta: Manifest, Store: ObjectStore, Fa: Fact
         request: &ReadRequest,
         table_options: &TableOptions,
     ) -> Result<Vec<ChainIterator>> {
-        let projected_schema = request.projected_schema.clone();
+        let version = table_data.current_version();
+        let tombstones = version.tombstones();
+        let projected_schema = projected_schema_with_tombstones(
+            &table_data.name,
+            &request.projected_schema,
+            &tombstones,
+        )?;
 
         assert!(request.order.is_out_of_order());
 
-        let sst_reader_options = SstReaderOptions {
+        let time_range = request.predicate.time_range;
+        let mut read_view = version.pick_read_view(time_range);
+        let mut sst_reader_options = SstReaderOptions {
             sst_type: table_data.sst_type,
             read_batch_row_num: table_options.num_rows_per_row_group,
             // no need to read in order so just read in asc order by default.
@@ -227,11 +449,27 @@ impl<Wal: WalManager + Send + Sync, Meta: Manifest, Store: ObjectStore, Fa: Fact
             meta_cache: self.meta_cache.clone(),
             data_cache: self.data_cache.clone(),
             runtime: self.read_runtime().clone(),
+            tsid_filter: None,
         };
+        if !request.predicate.exprs.is_empty() {
+            load_tag_indexes(
+                table_data,
+                &read_view,
+                &self.space_store.sst_factory,
+                &sst_reader_options,
+                self.space_store.store_ref(),
+            )
+            .await?;
+            sst_reader_options.tsid_filter = prune_read_view_by_tag_index(
+                &mut read_view,
+                &table_data.schema(),
+                &request.predicate,
+            );
+        }
 
-        let time_range = request.predicate.time_range;
-        let version = table_data.current_version();
-        let read_views = self.partition_ssts_and_memtables(time_range, version, &*table_options);
+        let read_views = self.partition_ssts_and_memtables(read_view, &*table_options);
+        // Hide the expired rows not yet dropped by compaction.
+        let expire_time = table_options.expire_time();
 
         let mut iters = Vec::with_capacity(read_views.len());
         for read_view in read_views {
@@ -244,6 +482,8 @@ impl<Wal: WalManager + Send + Sync, Meta: Manifest, Store: ObjectStore, Fa: Fact
                 sst_reader_options: sst_reader_options.clone(),
                 sst_factory: self.space_store.sst_factory.clone(),
                 store: self.space_store.store_ref(),
+                tombstones: tombstones.clone(),
+                expire_time,
             };
             let builder = chain::Builder::new(chain_config);
             let chain_iter = builder
@@ -264,12 +504,9 @@ impl<Wal: WalManager + Send + Sync, Meta: Manifest, Store: ObjectStore, Fa: Fact
 
     fn partition_ssts_and_memtables(
         &self,
-        time_range: TimeRange,
-        version: &TableVersion,
+        read_view: ReadView,
         table_options: &TableOptions,
     ) -> Vec<ReadView> {
-        let read_view = version.pick_read_view(time_range);
-
         let segment_duration = match table_options.segment_duration {
             Some(v) => v.0,
             None => {
@@ -315,12 +552,15 @@ impl<Wal: WalManager + Send + Sync, Meta: Manifest, Store: ObjectStore, Fa: Fact
     }
 }
 
+/// Chain the iterators into a stream, which stops reading from the iterators
+/// once `limit` rows are sent.
 // TODO(xikai): this is a hack way to implement SendableRecordBatchStream for
 // MergeIterator.
 fn iters_to_stream<T>(
     collection: T,
     runtime: &Runtime,
     schema: &ProjectedSchema,
+    limit: Option<usize>,
 ) -> SendableRecordBatchStream
 where
     T: IntoIterator + Send + 'static,
@@ -331,14 +571,28 @@ where
     let projected_schema = schema.clone();
 
     runtime.spawn(async move {
+        let mut num_rows_to_send = limit.unwrap_or(usize::MAX);
         for mut iter in collection {
-            while let Some(record_batch) = iter.next_batch().await.transpose() {
-                let record_batch =
-                    record_batch
-                        .map_err(|e| Box::new(e) as _)
-                        .context(ErrWithSource {
-                            msg: "Read record batch",
-                        });
+            while num_rows_to_send > 0 {
+                let record_batch = match iter.next_batch().await.transpose() {
+                    Some(v) => v,
+                    None => break,
+                };
+                let record_batch = record_batch
+                    .map_err(|e| Box::new(e) as _)
+                    .context(ErrWithSource {
+                        msg: "Read record batch",
+                    })
+                    .map(|batch_with_key| {
+                        if batch_with_key.num_rows() > num_rows_to_send {
+                            batch_with_key.slice(0, num_rows_to_send)
+                        } else {
+                            batch_with_key
+                        }
+                    });
+                if let Ok(batch_with_key) = &record_batch {
+                    num_rows_to_send -= batch_with_key.num_rows();
+                }
 
                 // Apply the projection to RecordBatchWithKey and gets the final RecordBatch.
                 let record_batch = record_batch.and_then(|batch_with_key| {
@@ -9,7 +9,7 @@ use common_types::{
     row::RowGroup,
     schema::{IndexInWriterSchema, Schema},
 };
-use common_util::{codec::row, define_result};
+use common_util::{codec::row, define_result, runtime::JoinHandle};
 use log::{debug, error, info, trace, warn};
 use object_store::ObjectStore;
 use proto::table_requests;
@@ -48,6 +48,12 @@ pub enum Error {
         source: wal::manager::Error,
     },
 
+    #[snafu(display("Failed to wait for writing wal, table:{}, err:{}", table, source))]
+    WaitWriteLogBatch {
+        table: String,
+        source: common_util::runtime::Error,
+    },
+
     #[snafu(display("Failed to write to memtable, table:{}, err:{}", table, source))]
     WriteMemTable {
         table: String,
@@ -142,6 +148,38 @@ impl EncodeContext {
     }
 }
 
+/// Write request whose rows are encoded and ready to be written to the wal
+pub(crate) struct EncodedWrite {
+    space_table: SpaceAndTable,
+    row_group: RowGroup,
+    index_in_writer: IndexInWriterSchema,
+    /// Request to write into the wal
+    write_req_pb: table_requests::WriteRequest,
+}
+
+/// Write request whose log batch is being written to the wal
+///
+/// The rows are written into the memtable after the log batch is persisted.
+pub(crate) struct PendingWrite {
+    space_table: SpaceAndTable,
+    row_group: RowGroup,
+    index_in_writer: IndexInWriterSchema,
+    /// Handle of the task writing the log batch, returns the sequence of the
+    /// log batch
+    wal_write: JoinHandle<Result<SequenceNumber>>,
+}
+
+impl PendingWrite {
+    /// Wait until the log batch is written to the wal.
+    ///
+    /// Cancel safe, but must not be called again after it returns.
+    pub(crate) async fn wait_wal(&mut self) -> Result<SequenceNumber> {
+        (&mut self.wal_write).await.context(WaitWriteLogBatch {
+            table: &self.space_table.table_data().name,
+        })?
+    }
+}
+
 impl<Wal, Meta, Store, Fa> Instance<Wal, Meta, Store, Fa>
 where
     Wal: WalManager + Send + Sync + 'static,
@@ -169,7 +207,7 @@ where
         };
 
         // Send write request to write worker, actual works done in
-        // Self::process_write_table_command().
+        // Self::encode_write(), Self::start_write() and Self::finish_write().
         write_worker::process_command_in_write_worker(
             cmd.into_command(),
             space_table.table_data(),
@@ -179,21 +217,33 @@ where
         .context(Write)
     }
 
-    /// Do the actual write, must called by write worker in write thread
-    /// sequentially.
-    pub(crate) async fn process_write_table_command(
-        self: &Arc<Self>,
-        worker_local: &mut WorkerLocal,
+    /// Encode the rows of the write request into the log batch.
+    ///
+    /// This doesn't need the [WorkerLocal], so the write worker can encode the
+    /// next request while the log batch of the previous one is still being
+    /// written to the wal.
+    pub(crate) fn encode_write(
+        &self,
         space_table: &SpaceAndTable,
         request: WriteRequest,
-    ) -> Result<usize> {
-        let mut encode_ctx = EncodeContext::new(request.row_group);
-
-        self.preprocess_write(worker_local, space_table, &mut encode_ctx)
-            .await?;
-
+    ) -> Result<EncodedWrite> {
         let table_data = space_table.table_data();
+        ensure!(
+            !table_data.is_dropped(),
+            WriteDroppedTable {
+                table: &table_data.name,
+            }
+        );
+
+        let mut encode_ctx = EncodeContext::new(request.row_group);
         let schema = table_data.schema();
+        // Checks schema compability.
+        schema
+            .compatible_for_write(
+                encode_ctx.row_group.schema(),
+                &mut encode_ctx.index_in_writer,
+            )
+            .context(IncompatSchema)?;
         encode_ctx.encode_rows(&schema)?;
 
         let EncodeContext {
@@ -202,9 +252,85 @@ where
             encoded_rows,
         } = encode_ctx;
 
-        let sequence = self
-            .write_to_wal(worker_local, &**table_data, encoded_rows)
-            .await?;
+        // Convert into pb
+        let mut write_req_pb = table_requests::WriteRequest::new();
+        // Use the table schema instead of the schema in request to avoid schema
+        // mismatch during replaying
+        write_req_pb.set_schema(schema.into());
+        write_req_pb.set_rows(encoded_rows.into());
+
+        Ok(EncodedWrite {
+            space_table: space_table.clone(),
+            row_group,
+            index_in_writer,
+            write_req_pb,
+        })
+    }
+
+    /// Start to write the log batch of the encoded write into the wal, must
+    /// called by write worker in write thread sequentially.
+    ///
+    /// The caller should wait for the log batch of the previous write to be
+    /// written before calling this, so the sequences of the log batches are in
+    /// the same order as the writes.
+    pub(crate) async fn start_write(
+        self: &Arc<Self>,
+        worker_local: &mut WorkerLocal,
+        encoded: EncodedWrite,
+    ) -> Result<PendingWrite> {
+        let EncodedWrite {
+            space_table,
+            row_group,
+            index_in_writer,
+            write_req_pb,
+        } = encoded;
+
+        self.preprocess_write(worker_local, &space_table).await?;
+
+        let table_data = space_table.table_data();
+        let space_store = self.space_store.clone();
+        let table = table_data.name.clone();
+        let region_id = table_data.wal_region_id();
+        let wal_write = self.runtimes.write_runtime.spawn(async move {
+            let mut log_batch = LogWriteBatch::new(region_id);
+            // Now we only have one request, so no need to use with_capacity
+            log_batch.push(LogWriteEntry {
+                payload: WritePayload::Write(&write_req_pb),
+            });
+
+            // Write to wal manager
+            let write_ctx = WriteContext::default();
+            space_store
+                .wal_manager
+                .write(&write_ctx, &log_batch)
+                .await
+                .context(WriteLogBatch { table })
+        });
+
+        Ok(PendingWrite {
+            space_table,
+            row_group,
+            index_in_writer,
+            wal_write,
+        })
+    }
+
+    /// Write the rows of the pending write into memtable after its log batch
+    /// is written with `sequence`, must called by write worker in write thread
+    /// sequentially.
+    pub(crate) fn finish_write(
+        &self,
+        worker_local: &WorkerLocal,
+        pending: PendingWrite,
+        sequence: SequenceNumber,
+    ) -> Result<usize> {
+        let PendingWrite {
+            space_table,
+            row_group,
+            index_in_writer,
+            ..
+        } = pending;
+        let table_data = space_table.table_data();
 
         Self::write_to_memtable(
             worker_local,
@@ -264,35 +390,16 @@ where
     }
 
     /// Preprocess before write, check:
-    ///  - whether table is dropped
+    ///  - background status of the worker
     ///  - memtable capacity and maybe trigger flush
-    ///
-    /// Fills [common_types::schema::IndexInWriterSchema] in [EncodeContext]
     async fn preprocess_write(
         self: &Arc<Self>,
         worker_local: &mut WorkerLocal,
         space_table: &SpaceAndTable,
-        encode_ctx: &mut EncodeContext,
     ) -> Result<()> {
         let space = space_table.space();
         let table_data = space_table.table_data();
 
-        ensure!(
-            !table_data.is_dropped(),
-            WriteDroppedTable {
-                table: &table_data.name,
-            }
-        );
-
-        // Checks schema compability.
-        table_data
-            .schema()
-            .compatible_for_write(
-                encode_ctx.row_group.schema(),
-                &mut encode_ctx.index_in_writer,
-            )
-            .context(IncompatSchema)?;
-
         // TODO(yingwen): Allow write and retry flush.
         // Check background status, if background error occured, not allow to write
         // again.
@@ -337,40 +444,6 @@ where
         Ok(())
     }
 
-    /// Write log_batch into wal, return the sequence number of log_batch.
-    async fn write_to_wal(
-        &self,
-        _worker_local: &WorkerLocal,
-        table_data: &TableData,
-        encoded_rows: Vec<ByteVec>,
-    ) -> Result<SequenceNumber> {
-        // Convert into pb
-        let mut write_req_pb = table_requests::WriteRequest::new();
-        // Use the table schema instead of the schema in request to avoid schema
-        // mismatch during replaying
-        write_req_pb.set_schema(table_data.schema().into());
-        write_req_pb.set_rows(encoded_rows.into());
-
-        let mut log_batch = LogWriteBatch::new(table_data.wal_region_id());
-        // Now we only have one request, so no need to use with_capacity
-        log_batch.push(LogWriteEntry {
-            payload: WritePayload::Write(&write_req_pb),
-        });
-
-        // Write to wal manager
-        let write_ctx = WriteContext::default();
-        let sequence = self
-            .space_store
-            .wal_manager
-            .write(&write_ctx, &log_batch)
-            .await
-            .context(WriteLogBatch {
-                table: &table_data.name,
-            })?;
-
-        Ok(sequence)
-    }
-
     // TODO(yingwen): How to trigger flush if we found memtables are full during
     // inserting memtable? RocksDB checks memtable size in MemTableInserter
     /// Write data into memtable.
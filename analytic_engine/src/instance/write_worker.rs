@@ -24,18 +24,23 @@ use snafu::{Backtrace, ResultExt, Snafu};
 use table_engine::{
     engine::{CloseTableRequest, DropTableRequest},
     table::{
-        AlterSchemaRequest, Error as TableError, Result as TableResult, TableId, WriteRequest,
+        AlterSchemaRequest, DeleteRequest, Error as TableError, Result as TableResult, TableId,
+        WriteRequest,
     },
 };
 use tokio::sync::{mpsc, oneshot, watch, watch::Ref, Mutex, Notify};
-use wal::{log_batch::LogEntry, manager::WalManager};
+use wal::{
+    log_batch::LogEntry,
+    manager::{SequenceNumber, WalManager},
+};
 
 use crate::{
     compaction::{TableCompactionRequest, WaitResult},
     instance::{
         engine,
         flush_compaction::{self, TableFlushOptions},
-        write, write_worker, InstanceRef,
+        write::{self, PendingWrite},
+        write_worker, InstanceRef,
     },
     meta::Manifest,
     payload::ReadPayload,
@@ -306,6 +311,21 @@ impl WriteTableCommand {
     }
 }
 
+/// Delete table command.
+pub struct DeleteTableCommand {
+    pub space_table: SpaceAndTable,
+    pub request: DeleteRequest,
+    /// Sender for the worker to return result of delete
+    pub tx: oneshot::Sender<write_worker::Result<()>>,
+}
+
+impl DeleteTableCommand {
+    /// Convert into [Command]
+    pub fn into_command(self) -> Command {
+        Command::Delete(self)
+    }
+}
+
 /// Recover table command.
 pub struct RecoverTableCommand {
     pub space: SpaceRef,
@@ -434,6 +454,9 @@ pub enum Command {
     /// Write to table
     Write(WriteTableCommand),
 
+    /// Delete from table
+    Delete(DeleteTableCommand),
+
     /// Drop table
     Create(CreateTableCommand),
 
@@ -593,6 +616,7 @@ impl WriteGroup {
                     background_rx,
                 },
                 log_entry_buf: Vec::new(),
+                inflight_write: None,
             };
 
             let space_id = opts.space_id;
@@ -709,6 +733,18 @@ struct WriteWorker<Wal, Meta, Store, Fa> {
     local: WorkerLocal,
     /// Log entry buffer for recover
     log_entry_buf: Vec<LogEntry<ReadPayload>>,
+    /// Write whose log batch is being written to the wal
+    ///
+    /// The worker encodes the next write request while the log batch of this
+    /// write is being written.
+    inflight_write: Option<InflightWrite>,
+}
+
+/// Write being processed by the write worker
+struct InflightWrite {
+    write: PendingWrite,
+    /// Sender to return result of write
+    tx: oneshot::Sender<write::Result<usize>>,
 }
 
 impl<
@@ -722,7 +758,7 @@ impl<
     async fn run(&mut self) {
         // TODO(yingwen): Maybe batch write tasks to improve performance (group commit)
         loop {
-            let command = match self.rx.recv().await {
+            let command = match self.recv_command().await {
                 Some(cmd) => cmd,
                 None => {
                     info!(
@@ -730,14 +766,24 @@ impl<
                         self.space_id(),
                         self.id()
                     );
+                    self.wait_inflight_write().await;
                     return;
                 }
             };
 
+            // Only write commands can be processed while a write is in flight, others
+            // may change the table so wait for the in-flight write first.
+            if !matches!(command, Command::Write(_)) {
+                self.wait_inflight_write().await;
+            }
+
             match command {
                 Command::Write(cmd) => {
                     self.handle_write_table(cmd).await;
                 }
+                Command::Delete(cmd) => {
+                    self.handle_delete_table(cmd).await;
+                }
                 Command::Create(cmd) => {
                     self.handle_create_table(cmd).await;
                 }
@@ -774,6 +820,49 @@ impl<
         }
     }
 
+    /// Receive the next command, the in-flight write is finished once its log
+    /// batch is written to the wal.
+    async fn recv_command(&mut self) -> Option<Command> {
+        loop {
+            let inflight = match &mut self.inflight_write {
+                Some(v) => v,
+                None => return self.rx.recv().await,
+            };
+
+            let wal_res = tokio::select! {
+                biased;
+
+                res = inflight.write.wait_wal() => res,
+                cmd = self.rx.recv() => return cmd,
+            };
+            self.finish_inflight_write(wal_res);
+        }
+    }
+
+    /// Wait until the in-flight write (if any) is finished.
+    async fn wait_inflight_write(&mut self) {
+        if let Some(inflight) = &mut self.inflight_write {
+            let wal_res = inflight.write.wait_wal().await;
+            self.finish_inflight_write(wal_res);
+        }
+    }
+
+    /// Finish the in-flight write with the result of writing its log batch.
+    ///
+    /// REQUIRE: The in-flight write exists.
+    fn finish_inflight_write(&mut self, wal_res: write::Result<SequenceNumber>) {
+        let InflightWrite { write: pending, tx } = self.inflight_write.take().unwrap();
+
+        let write_res =
+            wal_res.and_then(|sequence| self.instance.finish_write(&self.local, pending, sequence));
+        if let Err(res) = tx.send(write_res) {
+            error!(
+                "handle write table failed to send result, write_res:{:?}",
+                res
+            );
+        }
+    }
+
     async fn wait_background_jobs_done(&self) {
         while self.num_background_jobs() > 0 {
             self.wait_for_notify().await;
@@ -787,13 +876,48 @@ impl<
             tx,
         } = cmd;
 
-        let write_res = self
+        // Encode the rows while the log batch of the in-flight write is being
+        // written.
+        let start_res = match self.instance.encode_write(&space_table, request) {
+            Ok(encoded) => {
+                // Log batches must be written in the order of writes, so wait for the
+                // in-flight write before starting the next one.
+                self.wait_inflight_write().await;
+
+                self.instance.start_write(&mut self.local, encoded).await
+            }
+            Err(e) => Err(e),
+        };
+
+        match start_res {
+            Ok(pending) => self.inflight_write = Some(InflightWrite { write: pending, tx }),
+            Err(e) => {
+                if let Err(res) = tx.send(Err(e)) {
+                    error!(
+                        "handle write table failed to send result, write_res:{:?}",
+                        res
+                    );
+                }
+            }
+        }
+    }
+
+    async fn handle_delete_table(&mut self, cmd: DeleteTableCommand) {
+        let DeleteTableCommand {
+            space_table,
+            request,
+            tx,
+        } = cmd;
+
+        let delete_res = self
             .instance
-            .process_write_table_command(&mut self.local, &space_table, request)
-            .await;
-        if let Err(res) = tx.send(write_res) {
+            .process_delete_table_command(&mut self.local, &space_table, request)
+            .await
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+            .context(Channel);
+        if let Err(res) = tx.send(delete_res) {
             error!(
-                "handle write table failed to send result, write_res:{:?}",
+                "handle delete table failed to send result, delete_res:{:?}",
                 res
             );
         }
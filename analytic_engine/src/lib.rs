@@ -17,13 +17,16 @@ pub mod sst;
 mod storage_options;
 pub mod table;
 pub mod table_options;
+pub mod tag_index;
 
 #[cfg(any(test, feature = "test"))]
 pub mod tests;
 
 use meta::details::Options as ManifestOptions;
 use serde_derive::Deserialize;
-use storage_options::{LocalOptions, StorageOptions};
+use storage_options::{
+    LocalOptions, StorageOptions, WalCompression, WalGroupCommitOptions, WalStorageOptions,
+};
 
 pub use crate::{compaction::scheduler::SchedulerConfig, table_options::TableOptions};
 
@@ -36,6 +39,14 @@ pub struct Config {
 
     /// WAL path of the engine.
     pub wal_path: String,
+    /// Storage options of the WAL.
+    pub wal_storage: WalStorageOptions,
+    /// Group commit options of the WAL, the writes to the WAL are committed
+    /// in groups if set.
+    pub wal_group_commit: Option<WalGroupCommitOptions>,
+    /// Compression of the payloads written to the WAL, the entries written
+    /// before are still readable after it is changed.
+    pub wal_compression: WalCompression,
 
     /// Batch size to read records from wal to replay.
     pub replay_batch_size: usize,
@@ -73,6 +84,9 @@ impl Default for Config {
                 data_path: String::from("/tmp/ceresdb"),
             }),
             wal_path: String::from("/tmp/ceresdb"),
+            wal_storage: WalStorageOptions::RocksDB,
+            wal_group_commit: None,
+            wal_compression: WalCompression::None,
             replay_batch_size: 500,
             max_replay_tables_per_batch: 64,
             write_group_worker_num: 8,
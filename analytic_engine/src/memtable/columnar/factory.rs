@@ -0,0 +1,48 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Columnar memtable factory
+
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize},
+    Arc, Mutex, RwLock,
+};
+
+use common_types::record_batch::RecordBatchWithKeyBuilder;
+
+use crate::{
+    memtable::{
+        columnar::{Buffer, ColumnarMemTable},
+        factory::{Factory, Options, Result},
+        MemTableRef,
+    },
+    tag_index::TagIndex,
+};
+
+/// Factory to create columnar memtable
+#[derive(Debug)]
+pub struct ColumnarMemTableFactory;
+
+impl Factory for ColumnarMemTableFactory {
+    fn create_memtable(&self, opts: Options) -> Result<MemTableRef> {
+        let builder = RecordBatchWithKeyBuilder::new(opts.schema.to_record_schema_with_key());
+        let memtable = Arc::new(ColumnarMemTable {
+            schema: opts.schema,
+            buffer: Mutex::new(Buffer {
+                batches: Vec::new(),
+                builder,
+                keys: Vec::new(),
+                min_key: None,
+                max_key: None,
+            }),
+            last_sequence: AtomicU64::new(opts.creation_sequence),
+            num_rows: AtomicUsize::new(0),
+            memory_usage: AtomicUsize::new(0),
+            collector: opts.collector,
+            tag_index: opts
+                .enable_tag_index
+                .then(|| RwLock::new(TagIndex::default())),
+        });
+
+        Ok(memtable)
+    }
+}
@@ -0,0 +1,218 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Columnar memtable iterator
+
+use std::{cmp::Ordering, ops::Bound, sync::Arc};
+
+use common_types::{
+    bytes::Bytes,
+    datum::Datum,
+    projected_schema::{ProjectedSchema, RowProjector},
+    record_batch::{RecordBatchWithKey, RecordBatchWithKeyBuilder},
+    SequenceNumber,
+};
+use log::trace;
+use snafu::ResultExt;
+
+use crate::{
+    memtable::{
+        columnar::ColumnarMemTable,
+        key::{self, KeySequence},
+        AppendRow, BuildRecordBatch, DecodeInternalKey, ProjectSchema, Result, ScanContext,
+        ScanRequest,
+    },
+    tag_index::TsidSetRef,
+};
+
+/// Position of a row in the sealed record batches
+#[derive(Debug, Clone, Copy)]
+struct RowPosition {
+    batch_idx: usize,
+    row_idx: usize,
+}
+
+/// Columnar iterator for [ColumnarMemTable]
+///
+/// The rows matched the request are sorted by the internal key when the
+/// iterator is created, so the rows are returned in the same order as the
+/// skiplist memtable.
+pub struct ColumnarIterImpl {
+    batches: Vec<Arc<RecordBatchWithKey>>,
+    /// Positions of the rows to return, in the order to return.
+    positions: Vec<RowPosition>,
+    /// Index of the next position to return.
+    next_idx: usize,
+
+    /// Projection of schema to read
+    projected_schema: ProjectedSchema,
+    projector: RowProjector,
+    batch_size: usize,
+}
+
+impl ColumnarIterImpl {
+    /// Create a new [ColumnarIterImpl]
+    ///
+    /// `keys` are the internal keys of the rows in `batches`, in the same
+    /// order.
+    pub fn new(
+        memtable: &ColumnarMemTable,
+        batches: Vec<Arc<RecordBatchWithKey>>,
+        keys: Vec<Bytes>,
+        ctx: ScanContext,
+        request: ScanRequest,
+    ) -> Result<Self> {
+        // Create projection for the memtable schema
+        let projector = request
+            .projected_schema
+            .try_project_with_key(&memtable.schema)
+            .context(ProjectSchema)?;
+
+        // The series not indexed may be missing from the filter.
+        let tsid_filter = if memtable.has_tag_index() {
+            request.tsid_filter.clone()
+        } else {
+            None
+        };
+        let filter = RowFilter {
+            start_user_key: &request.start_user_key,
+            end_user_key: &request.end_user_key,
+            sequence: request.sequence,
+            tsid_filter,
+            tsid_idx: memtable.schema.index_of_tsid(),
+        };
+
+        let mut selected = Vec::new();
+        let mut keys = keys.iter();
+        for (batch_idx, batch) in batches.iter().enumerate() {
+            for row_idx in 0..batch.num_rows() {
+                // Every row in the batches has its key.
+                let internal_key = keys.next().unwrap();
+                let (user_key, sequence) =
+                    key::user_key_from_internal_key(internal_key).context(DecodeInternalKey)?;
+                if filter.is_selected(user_key, sequence, batch, row_idx) {
+                    selected.push((internal_key, RowPosition { batch_idx, row_idx }));
+                }
+            }
+        }
+
+        // Sort on read, rows with the same user key are ordered by sequence
+        // descend, so the first one is the newest.
+        selected.sort_by(|a, b| a.0.cmp(b.0));
+        if request.need_dedup {
+            selected.dedup_by(|current, prev| {
+                // Keys are decoded above so the decoding never fails here.
+                let (current_key, _) = key::user_key_from_internal_key(current.0).unwrap();
+                let (prev_key, _) = key::user_key_from_internal_key(prev.0).unwrap();
+                current_key == prev_key
+            });
+        }
+
+        let mut positions: Vec<_> = selected.into_iter().map(|(_, pos)| pos).collect();
+        if request.reverse {
+            positions.reverse();
+        }
+
+        Ok(Self {
+            batches,
+            positions,
+            next_idx: 0,
+            projected_schema: request.projected_schema,
+            projector,
+            batch_size: ctx.batch_size,
+        })
+    }
+
+    /// Fetch next record batch
+    fn fetch_next_record_batch(&mut self) -> Result<Option<RecordBatchWithKey>> {
+        assert!(self.batch_size > 0);
+
+        if self.next_idx >= self.positions.len() {
+            return Ok(None);
+        }
+
+        let end_idx = self.positions.len().min(self.next_idx + self.batch_size);
+        let mut builder = RecordBatchWithKeyBuilder::with_capacity(
+            self.projected_schema.to_record_schema_with_key(),
+            end_idx - self.next_idx,
+        );
+        for pos in &self.positions[self.next_idx..end_idx] {
+            let row = self.batches[pos.batch_idx].clone_row_at(pos.row_idx);
+            let projected_row = self.projector.project_row(&row, Vec::new());
+
+            trace!("Column iterator fetch next row, row:{:?}", projected_row);
+
+            builder.append_row(projected_row).context(AppendRow)?;
+        }
+        self.next_idx = end_idx;
+
+        let batch = builder.build().context(BuildRecordBatch)?;
+        trace!("column iterator send one batch:{:?}", batch);
+
+        Ok(Some(batch))
+    }
+}
+
+impl Iterator for ColumnarIterImpl {
+    type Item = Result<RecordBatchWithKey>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.fetch_next_record_batch().transpose()
+    }
+}
+
+/// Conditions of the rows to scan
+struct RowFilter<'a> {
+    start_user_key: &'a Bound<Bytes>,
+    end_user_key: &'a Bound<Bytes>,
+    /// Max visible sequence
+    sequence: SequenceNumber,
+    /// Only the rows of the series in the set are selected if it is not None
+    tsid_filter: Option<TsidSetRef>,
+    tsid_idx: Option<usize>,
+}
+
+impl<'a> RowFilter<'a> {
+    fn is_selected(
+        &self,
+        user_key: &[u8],
+        sequence: KeySequence,
+        batch: &RecordBatchWithKey,
+        row_idx: usize,
+    ) -> bool {
+        sequence.sequence() <= self.sequence
+            && !self.is_before_start_bound(user_key)
+            && !self.is_after_end_bound(user_key)
+            && self.is_series_selected(batch, row_idx)
+    }
+
+    /// Return true if the key is before the `start_user_key` bound
+    fn is_before_start_bound(&self, key: &[u8]) -> bool {
+        match self.start_user_key {
+            Bound::Included(start) => key.cmp(start) == Ordering::Less,
+            Bound::Excluded(start) => key.cmp(start) != Ordering::Greater,
+            Bound::Unbounded => false,
+        }
+    }
+
+    /// Return true if the key is after the `end_user_key` bound
+    fn is_after_end_bound(&self, key: &[u8]) -> bool {
+        match self.end_user_key {
+            Bound::Included(end) => key.cmp(end) == Ordering::Greater,
+            Bound::Excluded(end) => key.cmp(end) != Ordering::Less,
+            Bound::Unbounded => false,
+        }
+    }
+
+    /// Return true if the series of the row is selected by the tsid filter
+    fn is_series_selected(&self, batch: &RecordBatchWithKey, row_idx: usize) -> bool {
+        let (tsids, tsid_idx) = match (&self.tsid_filter, self.tsid_idx) {
+            (Some(tsids), Some(idx)) => (tsids, idx),
+            _ => return true,
+        };
+
+        match batch.column(tsid_idx).datum(row_idx) {
+            Datum::UInt64(tsid) => tsids.contains(&tsid),
+            _ => true,
+        }
+    }
+}
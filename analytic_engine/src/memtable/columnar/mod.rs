@@ -0,0 +1,352 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Append-only memtable storing the rows in columnar format
+//!
+//! Rows are appended into column builders in the order they are put, without
+//! any index or dedup, so this memtable only suits the tables in append mode.
+//! The rows are sorted by the key at scan time.
+
+pub mod factory;
+pub mod iter;
+
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{self, AtomicU64, AtomicUsize},
+        Arc, Mutex, RwLock,
+    },
+};
+
+use arena::CollectorRef;
+use common_types::{
+    bytes::Bytes,
+    datum::Datum,
+    record_batch::{RecordBatchWithKey, RecordBatchWithKeyBuilder},
+    row::Row,
+    schema::Schema,
+    SequenceNumber,
+};
+use common_util::codec::Encoder;
+use log::{debug, trace};
+use snafu::{ensure, ResultExt};
+use table_engine::predicate::Predicate;
+
+use crate::{
+    memtable::{
+        columnar::iter::ColumnarIterImpl,
+        key::{ComparableInternalKey, KeySequence},
+        AppendRow, BuildRecordBatch, ColumnarIterPtr, EncodeInternalKey, InvalidPutSequence,
+        MemTable, PutContext, Result, ScanContext, ScanRequest,
+    },
+    tag_index::{self, TagIndex},
+};
+
+/// Number of rows of a sealed record batch.
+const NUM_ROWS_PER_BATCH: usize = 4096;
+
+/// Rows of the memtable.
+struct Buffer {
+    /// Record batches sealed, in the order of the rows put.
+    batches: Vec<Arc<RecordBatchWithKey>>,
+    /// Builder of the rows not sealed yet.
+    builder: RecordBatchWithKeyBuilder,
+    /// Encoded internal keys of all the rows, in the order of the rows put.
+    keys: Vec<Bytes>,
+    min_key: Option<Bytes>,
+    max_key: Option<Bytes>,
+}
+
+impl Buffer {
+    /// Seal the rows in the builder into a record batch.
+    fn seal(&mut self) -> Result<()> {
+        if self.builder.is_empty() {
+            return Ok(());
+        }
+
+        let batch = self.builder.build().context(BuildRecordBatch)?;
+        self.batches.push(Arc::new(batch));
+
+        Ok(())
+    }
+}
+
+/// MemTable implementation appending rows into columns
+pub struct ColumnarMemTable {
+    /// Schema of this memtable, is immutable.
+    schema: Schema,
+    buffer: Mutex<Buffer>,
+    /// The last sequence of the rows in this memtable. Update to this field
+    /// require external synchronization.
+    last_sequence: AtomicU64,
+    /// Number of rows put into this memtable.
+    num_rows: AtomicUsize,
+    /// Estimated bytes of the rows put into this memtable.
+    memory_usage: AtomicUsize,
+    /// Memory usage colllector
+    collector: CollectorRef,
+    /// Inverted index of the tag columns, None if the index is not enabled.
+    tag_index: Option<RwLock<TagIndex>>,
+}
+
+impl ColumnarMemTable {
+    #[inline]
+    fn has_tag_index(&self) -> bool {
+        self.tag_index.is_some()
+    }
+
+    /// Returns the sealed record batches and the keys of their rows, rows not
+    /// sealed yet are sealed first.
+    fn snapshot(&self) -> Result<(Vec<Arc<RecordBatchWithKey>>, Vec<Bytes>)> {
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.seal()?;
+
+        Ok((buffer.batches.clone(), buffer.keys.clone()))
+    }
+}
+
+impl MemTable for ColumnarMemTable {
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn min_key(&self) -> Option<Bytes> {
+        self.buffer.lock().unwrap().min_key.clone()
+    }
+
+    fn max_key(&self) -> Option<Bytes> {
+        self.buffer.lock().unwrap().max_key.clone()
+    }
+
+    fn put(
+        &self,
+        ctx: &mut PutContext,
+        sequence: KeySequence,
+        row: &Row,
+        schema: &Schema,
+    ) -> Result<()> {
+        trace!("columnar put row, sequence:{:?}, row:{:?}", sequence, row);
+
+        let key_encoder = ComparableInternalKey::new(sequence, schema);
+        let internal_key = &mut ctx.key_buf;
+        internal_key.clear();
+        internal_key.reserve(key_encoder.estimate_encoded_size(row));
+        key_encoder
+            .encode(internal_key, row)
+            .context(EncodeInternalKey)?;
+        let key = Bytes::copy_from_slice(internal_key);
+
+        // Fill the columns absent in the writer schema by their default values.
+        let datums: Vec<_> = (0..self.schema.num_columns())
+            .map(
+                |idx| match ctx.index_in_writer.column_index_in_writer(idx) {
+                    Some(writer_idx) => row[writer_idx].clone(),
+                    None => self.schema.column(idx).default_datum(),
+                },
+            )
+            .collect();
+        let row_size = key.len() + datums.iter().map(estimated_datum_size).sum::<usize>();
+        let row = Row::from_datums(datums);
+        if let Some(index) = &self.tag_index {
+            tag_index::index_row(index, &self.schema, &row);
+        }
+
+        {
+            let mut buffer = self.buffer.lock().unwrap();
+            buffer.builder.append_row(row).context(AppendRow)?;
+            if buffer.builder.len() >= NUM_ROWS_PER_BATCH {
+                buffer.seal()?;
+            }
+
+            if buffer.min_key.as_ref().map_or(true, |v| key < *v) {
+                buffer.min_key = Some(key.clone());
+            }
+            if buffer.max_key.as_ref().map_or(true, |v| key > *v) {
+                buffer.max_key = Some(key.clone());
+            }
+            buffer.keys.push(key);
+        }
+
+        self.num_rows.fetch_add(1, atomic::Ordering::Relaxed);
+        self.memory_usage
+            .fetch_add(row_size, atomic::Ordering::Relaxed);
+        self.collector.on_alloc(row_size);
+        self.collector.on_used(row_size);
+
+        Ok(())
+    }
+
+    fn scan(&self, ctx: ScanContext, request: ScanRequest) -> Result<ColumnarIterPtr> {
+        debug!(
+            "Scan columnar memtable, ctx:{:?}, request:{:?}",
+            ctx, request
+        );
+
+        let (batches, keys) = self.snapshot()?;
+        let iter = ColumnarIterImpl::new(self, batches, keys, ctx, request)?;
+
+        Ok(Box::new(iter))
+    }
+
+    fn lookup_tsids(&self, schema: &Schema, predicate: &Predicate) -> Option<HashSet<u64>> {
+        self.tag_index
+            .as_ref()
+            .and_then(|index| index.read().unwrap().lookup(schema, predicate))
+    }
+
+    fn approximate_memory_usage(&self) -> usize {
+        self.memory_usage.load(atomic::Ordering::Relaxed)
+    }
+
+    fn set_last_sequence(&self, sequence: SequenceNumber) -> Result<()> {
+        let last = self.last_sequence();
+        ensure!(
+            sequence >= last,
+            InvalidPutSequence {
+                given: sequence,
+                last
+            }
+        );
+
+        self.last_sequence
+            .store(sequence, atomic::Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    fn last_sequence(&self) -> SequenceNumber {
+        self.last_sequence.load(atomic::Ordering::Relaxed)
+    }
+
+    fn num_rows(&self) -> usize {
+        self.num_rows.load(atomic::Ordering::Relaxed)
+    }
+}
+
+impl Drop for ColumnarMemTable {
+    fn drop(&mut self) {
+        let memory_usage = self.approximate_memory_usage();
+        self.collector.on_free(memory_usage, memory_usage);
+    }
+}
+
+/// Estimated bytes to hold the `datum` in the column.
+fn estimated_datum_size(datum: &Datum) -> usize {
+    match datum {
+        Datum::Varbinary(v) => v.len(),
+        Datum::String(v) => v.len(),
+        _ => std::mem::size_of::<u64>(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use arena::NoopCollector;
+    use common_types::{
+        projected_schema::ProjectedSchema,
+        schema::IndexInWriterSchema,
+        tests::{build_row, build_schema},
+    };
+
+    use super::*;
+    use crate::memtable::{
+        columnar::factory::ColumnarMemTableFactory,
+        factory::{Factory, Options},
+        MemTableRef,
+    };
+
+    fn build_memtable(schema: &Schema) -> MemTableRef {
+        ColumnarMemTableFactory
+            .create_memtable(Options {
+                schema: schema.clone(),
+                arena_block_size: 512,
+                creation_sequence: 1,
+                collector: Arc::new(NoopCollector {}),
+                enable_tag_index: false,
+            })
+            .unwrap()
+    }
+
+    fn scan_rows(memtable: &MemTableRef, request: ScanRequest, batch_size: usize) -> Vec<Row> {
+        let iter = memtable.scan(ScanContext { batch_size }, request).unwrap();
+        let mut rows = Vec::new();
+        for batch in iter {
+            let batch = batch.unwrap();
+            assert!(batch.num_rows() <= batch_size);
+            for row_idx in 0..batch.num_rows() {
+                rows.push(batch.clone_row_at(row_idx));
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn test_columnar_memtable_scan() {
+        let schema = build_schema();
+        let memtable = build_memtable(&schema);
+        let mut ctx = PutContext::new(IndexInWriterSchema::for_same_schema(schema.num_columns()));
+        let input = vec![
+            (KeySequence::new(1, 1), build_row(b"d", 4, 10.0, "v4")),
+            (KeySequence::new(1, 2), build_row(b"b", 2, 10.0, "v2")),
+            (KeySequence::new(1, 3), build_row(b"c", 3, 10.0, "v3")),
+            (KeySequence::new(2, 1), build_row(b"a", 1, 10.0, "v1")),
+            (KeySequence::new(2, 2), build_row(b"c", 3, 10.0, "v3 again")),
+            (KeySequence::new(3, 1), build_row(b"e", 5, 10.0, "v5")),
+        ];
+        for (seq, row) in input {
+            memtable.put(&mut ctx, seq, &row, &schema).unwrap();
+        }
+        assert_eq!(6, memtable.num_rows());
+        assert!(memtable.min_key().unwrap() < memtable.max_key().unwrap());
+
+        let projected_schema = ProjectedSchema::no_projection(schema.clone());
+        let request = ScanRequest {
+            start_user_key: Bound::Unbounded,
+            end_user_key: Bound::Unbounded,
+            sequence: 2,
+            projected_schema: projected_schema.clone(),
+            need_dedup: false,
+            reverse: false,
+            tsid_filter: None,
+        };
+        // Rows are sorted by the key, and the newer row goes first.
+        assert_eq!(
+            vec![
+                build_row(b"a", 1, 10.0, "v1"),
+                build_row(b"b", 2, 10.0, "v2"),
+                build_row(b"c", 3, 10.0, "v3 again"),
+                build_row(b"c", 3, 10.0, "v3"),
+                build_row(b"d", 4, 10.0, "v4"),
+            ],
+            scan_rows(&memtable, request.clone(), 2)
+        );
+
+        // Rows put after the scan are visible to the next scan.
+        memtable
+            .put(
+                &mut ctx,
+                KeySequence::new(3, 2),
+                &build_row(b"a", 0, 10.0, "v0"),
+                &schema,
+            )
+            .unwrap();
+        let request = ScanRequest {
+            sequence: 3,
+            need_dedup: true,
+            reverse: true,
+            ..request
+        };
+        assert_eq!(
+            vec![
+                build_row(b"e", 5, 10.0, "v5"),
+                build_row(b"d", 4, 10.0, "v4"),
+                build_row(b"c", 3, 10.0, "v3 again"),
+                build_row(b"b", 2, 10.0, "v2"),
+                build_row(b"a", 1, 10.0, "v1"),
+                build_row(b"a", 0, 10.0, "v0"),
+            ],
+            scan_rows(&memtable, request, 4)
+        );
+    }
+}
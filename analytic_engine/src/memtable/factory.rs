@@ -26,6 +26,8 @@ pub struct Options {
     pub creation_sequence: SequenceNumber,
     /// Memory usage colllector
     pub collector: CollectorRef,
+    /// Build inverted index of the tag columns for the rows put.
+    pub enable_tag_index: bool,
 }
 
 /// MemTable factory
@@ -2,11 +2,12 @@
 
 //! MemTable
 
+pub mod columnar;
 pub mod factory;
 pub mod key;
 pub mod skiplist;
 
-use std::{ops::Bound, sync::Arc};
+use std::{collections::HashSet, ops::Bound, sync::Arc};
 
 use common_types::{
     bytes::{ByteVec, Bytes},
@@ -18,8 +19,9 @@ use common_types::{
 };
 use common_util::define_result;
 use snafu::{Backtrace, Snafu};
+use table_engine::predicate::Predicate;
 
-use crate::memtable::key::KeySequence;
+use crate::{memtable::key::KeySequence, tag_index::TsidSetRef};
 
 const DEFAULT_SCAN_BATCH_SIZE: usize = 500;
 
@@ -128,6 +130,11 @@ pub struct ScanRequest {
     pub projected_schema: ProjectedSchema,
     pub need_dedup: bool,
     pub reverse: bool,
+    /// Tsids of the series to scan, None to scan all the series.
+    ///
+    /// Ignored by the memtable without tag index, as the set is looked up
+    /// from the tag indexes and the series not indexed may be missing.
+    pub tsid_filter: Option<TsidSetRef>,
 }
 
 /// In memory storage for table's data.
@@ -175,6 +182,14 @@ pub trait MemTable {
     /// to be ordered by the primary key.
     fn scan(&self, ctx: ScanContext, request: ScanRequest) -> Result<ColumnarIterPtr>;
 
+    /// Look up the tsids of the series may satisfy the tag `predicate` from
+    /// the tag index, the columns in the `predicate` are resolved by the
+    /// latest table `schema`.
+    ///
+    /// Returns None if the memtable has no tag index or the index can't be
+    /// used for the `predicate`.
+    fn lookup_tsids(&self, schema: &Schema, predicate: &Predicate) -> Option<HashSet<u64>>;
+
     /// Returns an estimate of the number of bytes of data in used
     fn approximate_memory_usage(&self) -> usize;
 
@@ -189,6 +204,10 @@ pub trait MemTable {
     ///
     /// If the memtable is empty, then the last sequence is 0.
     fn last_sequence(&self) -> SequenceNumber;
+
+    /// Returns the number of rows put into the memtable, rows with the same
+    /// key are counted repeatedly.
+    fn num_rows(&self) -> usize;
 }
 
 /// A reference to memtable
@@ -2,15 +2,21 @@
 
 //! Skiplist memtable factory
 
-use std::sync::{atomic::AtomicU64, Arc};
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize},
+    Arc, RwLock,
+};
 
 use arena::MonoIncArena;
 use skiplist::Skiplist;
 
-use crate::memtable::{
-    factory::{Factory, Options, Result},
-    skiplist::{BytewiseComparator, SkiplistMemTable},
-    MemTableRef,
+use crate::{
+    memtable::{
+        factory::{Factory, Options, Result},
+        skiplist::{BytewiseComparator, SkiplistMemTable},
+        MemTableRef,
+    },
+    tag_index::TagIndex,
 };
 
 /// Factory to create memtable
@@ -25,6 +31,10 @@ impl Factory for SkiplistMemTableFactory {
             schema: opts.schema,
             skiplist,
             last_sequence: AtomicU64::new(opts.creation_sequence),
+            num_rows: AtomicUsize::new(0),
+            tag_index: opts
+                .enable_tag_index
+                .then(|| RwLock::new(TagIndex::default())),
         });
 
         Ok(memtable)
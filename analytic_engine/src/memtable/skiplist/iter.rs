@@ -7,9 +7,10 @@ use std::{cmp::Ordering, iter::Rev, ops::Bound};
 use arena::{Arena, BasicStats};
 use common_types::{
     bytes::{Bytes, BytesMut},
+    datum::DatumView,
     projected_schema::{ProjectedSchema, RowProjector},
     record_batch::{RecordBatchWithKey, RecordBatchWithKeyBuilder},
-    row::contiguous::{ContiguousRowReader, ProjectedContiguousRow},
+    row::contiguous::{ContiguousRow, ContiguousRowReader, ProjectedContiguousRow},
     schema::Schema,
     SequenceNumber,
 };
@@ -18,11 +19,14 @@ use log::trace;
 use skiplist::{ArenaSlice, IterRef, Skiplist};
 use snafu::ResultExt;
 
-use crate::memtable::{
-    key::{self, KeySequence},
-    skiplist::{BytewiseComparator, SkiplistMemTable},
-    AppendRow, BuildRecordBatch, DecodeInternalKey, EncodeInternalKey, IterReverse, ProjectSchema,
-    Result, ScanContext, ScanRequest,
+use crate::{
+    memtable::{
+        key::{self, KeySequence},
+        skiplist::{BytewiseComparator, SkiplistMemTable},
+        AppendRow, BuildRecordBatch, DecodeInternalKey, EncodeInternalKey, IterReverse,
+        ProjectSchema, Result, ScanContext, ScanRequest,
+    },
+    tag_index::TsidSetRef,
 };
 
 /// Iterator state
@@ -63,6 +67,8 @@ pub struct ColumnarIterImpl<A: Arena<Stats = BasicStats> + Clone + Sync + Send>
 
     /// Dedup rows with key
     need_dedup: bool,
+    /// Only the rows of the series in the set are returned if it is not None
+    tsid_filter: Option<TsidSetRef>,
 }
 
 impl<A: Arena<Stats = BasicStats> + Clone + Sync + Send> ColumnarIterImpl<A> {
@@ -79,6 +85,12 @@ impl<A: Arena<Stats = BasicStats> + Clone + Sync + Send> ColumnarIterImpl<A> {
             .context(ProjectSchema)?;
 
         let iter = memtable.skiplist.iter();
+        // The series not indexed may be missing from the filter.
+        let tsid_filter = if memtable.has_tag_index() {
+            request.tsid_filter
+        } else {
+            None
+        };
         let mut columnar_iter = Self {
             iter,
             memtable_schema: memtable.schema.clone(),
@@ -91,6 +103,7 @@ impl<A: Arena<Stats = BasicStats> + Clone + Sync + Send> ColumnarIterImpl<A> {
             state: State::Uninitialized,
             last_internal_key: None,
             need_dedup: request.need_dedup,
+            tsid_filter,
         };
 
         columnar_iter.init()?;
@@ -234,6 +247,10 @@ impl<A: Arena<Stats = BasicStats> + Clone + Sync + Send> ColumnarIterImpl<A> {
             // Move iter forward
             self.iter.next();
 
+            if !self.is_series_selected(&row) {
+                continue;
+            }
+
             return Ok(Some(row));
         }
 
@@ -242,6 +259,20 @@ impl<A: Arena<Stats = BasicStats> + Clone + Sync + Send> ColumnarIterImpl<A> {
         Ok(None)
     }
 
+    /// Return true if the series of the `row` is selected by the tsid filter
+    fn is_series_selected(&self, row: &ArenaSlice<A>) -> bool {
+        let (tsids, tsid_idx) = match (&self.tsid_filter, self.memtable_schema.index_of_tsid()) {
+            (Some(tsids), Some(idx)) => (tsids, idx),
+            _ => return true,
+        };
+
+        let row_reader = ContiguousRowReader::with_schema(row, &self.memtable_schema);
+        match row_reader.datum_view_at(tsid_idx) {
+            DatumView::UInt64(tsid) => tsids.contains(&tsid),
+            _ => true,
+        }
+    }
+
     /// Return true if the sequence is visible
     #[inline]
     fn is_visible(&self, sequence: KeySequence) -> bool {
@@ -7,8 +7,12 @@ pub mod iter;
 
 use std::{
     cmp::Ordering,
+    collections::HashSet,
     convert::TryInto,
-    sync::atomic::{self, AtomicU64},
+    sync::{
+        atomic::{self, AtomicU64, AtomicUsize},
+        RwLock,
+    },
 };
 
 use arena::{Arena, BasicStats};
@@ -22,12 +26,16 @@ use common_util::codec::Encoder;
 use log::{debug, trace};
 use skiplist::{KeyComparator, Skiplist};
 use snafu::{ensure, ResultExt};
-
-use crate::memtable::{
-    key::{ComparableInternalKey, KeySequence},
-    skiplist::iter::{ColumnarIterImpl, ReversedColumnarIterator},
-    ColumnarIterPtr, EncodeInternalKey, InvalidPutSequence, InvalidRow, MemTable, PutContext,
-    Result, ScanContext, ScanRequest,
+use table_engine::predicate::Predicate;
+
+use crate::{
+    memtable::{
+        key::{ComparableInternalKey, KeySequence},
+        skiplist::iter::{ColumnarIterImpl, ReversedColumnarIterator},
+        ColumnarIterPtr, EncodeInternalKey, InvalidPutSequence, InvalidRow, MemTable, PutContext,
+        Result, ScanContext, ScanRequest,
+    },
+    tag_index::{self, TagIndex},
 };
 
 /// MemTable implementation based on skiplist
@@ -38,6 +46,24 @@ pub struct SkiplistMemTable<A: Arena<Stats = BasicStats> + Clone + Sync + Send>
     /// The last sequence of the rows in this memtable. Update to this field
     /// require external synchronization.
     last_sequence: AtomicU64,
+    /// Number of rows put into this memtable.
+    num_rows: AtomicUsize,
+    /// Inverted index of the tag columns, None if the index is not enabled.
+    tag_index: Option<RwLock<TagIndex>>,
+}
+
+impl<A: Arena<Stats = BasicStats> + Clone + Sync + Send> SkiplistMemTable<A> {
+    #[inline]
+    fn has_tag_index(&self) -> bool {
+        self.tag_index.is_some()
+    }
+
+    /// Add the series of the `row` to the tag index if it is a new series.
+    fn index_row(&self, row: &Row, schema: &Schema) {
+        if let Some(index) = &self.tag_index {
+            tag_index::index_row(index, schema, row);
+        }
+    }
 }
 
 impl<A: Arena<Stats = BasicStats> + Clone + Sync + Send + 'static> MemTable
@@ -100,6 +126,8 @@ impl<A: Arena<Stats = BasicStats> + Clone + Sync + Send + 'static> MemTable
             .context(InvalidRow)?;
 
         self.skiplist.put(internal_key, row_value);
+        self.num_rows.fetch_add(1, atomic::Ordering::Relaxed);
+        self.index_row(row, schema);
 
         Ok(())
     }
@@ -122,6 +150,12 @@ impl<A: Arena<Stats = BasicStats> + Clone + Sync + Send + 'static> MemTable
         }
     }
 
+    fn lookup_tsids(&self, schema: &Schema, predicate: &Predicate) -> Option<HashSet<u64>> {
+        self.tag_index
+            .as_ref()
+            .and_then(|index| index.read().unwrap().lookup(schema, predicate))
+    }
+
     fn approximate_memory_usage(&self) -> usize {
         // Mem size of skiplist is u32, need to cast to usize
         match self.skiplist.mem_size().try_into() {
@@ -150,6 +184,10 @@ impl<A: Arena<Stats = BasicStats> + Clone + Sync + Send + 'static> MemTable
     fn last_sequence(&self) -> SequenceNumber {
         self.last_sequence.load(atomic::Ordering::Relaxed)
     }
+
+    fn num_rows(&self) -> usize {
+        self.num_rows.load(atomic::Ordering::Relaxed)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -207,6 +245,7 @@ mod tests {
                     projected_schema: projected_schema.clone(),
                     need_dedup: true,
                     reverse: false,
+                    tsid_filter: None,
                 },
                 vec![
                     build_row(b"a", 1, 10.0, "v1"),
@@ -226,6 +265,7 @@ mod tests {
                     projected_schema: projected_schema.clone(),
                     need_dedup: true,
                     reverse: false,
+                    tsid_filter: None,
                 },
                 vec![
                     build_row(b"a", 1, 10.0, "v1"),
@@ -244,6 +284,7 @@ mod tests {
                     projected_schema,
                     need_dedup: true,
                     reverse: false,
+                    tsid_filter: None,
                 },
                 vec![
                     build_row(b"a", 1, 10.0, "v1"),
@@ -275,6 +316,7 @@ mod tests {
                 projected_schema,
                 need_dedup: true,
                 reverse: false,
+                tsid_filter: None,
             },
             vec![
                 build_row_for_two_column(b"a", 1),
@@ -301,6 +343,7 @@ mod tests {
                 arena_block_size: 512,
                 creation_sequence: 1,
                 collector: Arc::new(NoopCollector {}),
+                enable_tag_index: false,
             })
             .unwrap();
 
@@ -18,7 +18,10 @@ use wal::log_batch::{Payload, PayloadDecoder};
 
 use crate::{
     space::SpaceId,
-    table::version_edit::{AddFile, DeleteFile, VersionEdit},
+    table::{
+        tombstone::Tombstone,
+        version_edit::{AddFile, DeleteFile, VersionEdit},
+    },
     TableOptions,
 };
 
@@ -49,6 +52,11 @@ pub enum Error {
     ConvertVersionEdit {
         source: crate::table::version_edit::Error,
     },
+
+    #[snafu(display("Failed to convert tombstone, err:{}", source))]
+    ConvertTombstone {
+        source: crate::table::tombstone::Error,
+    },
 }
 
 define_result!(Error);
@@ -289,6 +297,9 @@ pub struct VersionEditMeta {
     pub flushed_sequence: SequenceNumber,
     pub files_to_add: Vec<AddFile>,
     pub files_to_delete: Vec<DeleteFile>,
+    pub tombstones_to_add: Vec<Tombstone>,
+    /// Sequences of the tombstones to delete.
+    pub tombstones_to_delete: Vec<SequenceNumber>,
 }
 
 impl VersionEditMeta {
@@ -310,6 +321,13 @@ impl VersionEditMeta {
         }
         target.files_to_delete = files_to_delete.into();
 
+        let mut tombstones_to_add = Vec::with_capacity(self.tombstones_to_add.len());
+        for tombstone in self.tombstones_to_add {
+            tombstones_to_add.push(tombstone.into_pb());
+        }
+        target.tombstones_to_add = tombstones_to_add.into();
+        target.tombstones_to_delete = self.tombstones_to_delete;
+
         target
     }
 
@@ -321,6 +339,8 @@ impl VersionEditMeta {
             flushed_sequence: self.flushed_sequence,
             files_to_add: self.files_to_add,
             files_to_delete: self.files_to_delete,
+            tombstones_to_add: self.tombstones_to_add,
+            tombstones_to_delete: self.tombstones_to_delete,
         }
     }
 }
@@ -339,12 +359,19 @@ impl TryFrom<meta_pb::VersionEditMeta> for VersionEditMeta {
             files_to_delete.push(DeleteFile::try_from(file_meta).context(ConvertVersionEdit)?);
         }
 
+        let mut tombstones_to_add = Vec::with_capacity(src.tombstones_to_add.len());
+        for tombstone_meta in src.tombstones_to_add {
+            tombstones_to_add.push(Tombstone::try_from(tombstone_meta).context(ConvertTombstone)?);
+        }
+
         Ok(Self {
             space_id: src.space_id,
             table_id: TableId::from(src.table_id),
             flushed_sequence: src.flushed_sequence,
             files_to_add,
             files_to_delete,
+            tombstones_to_add,
+            tombstones_to_delete: src.tombstones_to_delete,
         })
     }
 }
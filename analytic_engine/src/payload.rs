@@ -16,8 +16,11 @@ use common_util::{
 use proto::table_requests;
 use protobuf::Message;
 use snafu::{Backtrace, ResultExt, Snafu};
+use table_engine::table::DeleteRequest;
 use wal::log_batch::{Payload, PayloadDecoder};
 
+use crate::table::tombstone;
+
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("Failed to encode header, err:{}", source))]
@@ -52,6 +55,11 @@ pub enum Error {
     DecodeRow {
         source: common_util::codec::row::Error,
     },
+
+    #[snafu(display("Failed to decode delete request, err:{}", source))]
+    DecodeDeleteRequest {
+        source: crate::table::tombstone::Error,
+    },
 }
 
 define_result!(Error);
@@ -60,6 +68,7 @@ define_result!(Error);
 #[derive(Clone, Copy)]
 enum Header {
     Write = 1,
+    Delete = 2,
 }
 
 impl Header {
@@ -70,6 +79,7 @@ impl Header {
     pub fn from_u8(value: u8) -> Option<Self> {
         match value {
             value if value == Self::Write as u8 => Some(Self::Write),
+            value if value == Self::Delete as u8 => Some(Self::Delete),
             _ => None,
         }
     }
@@ -87,6 +97,7 @@ const HEADER_SIZE: usize = 1;
 #[derive(Debug)]
 pub enum WritePayload<'a> {
     Write(&'a table_requests::WriteRequest),
+    Delete(&'a table_requests::DeleteRequest),
 }
 
 impl<'a> Payload for WritePayload<'a> {
@@ -95,6 +106,7 @@ impl<'a> Payload for WritePayload<'a> {
     fn encode_size(&self) -> usize {
         let body_size = match self {
             WritePayload::Write(req) => req.compute_size(),
+            WritePayload::Delete(req) => req.compute_size(),
         };
 
         HEADER_SIZE + body_size as usize
@@ -107,6 +119,11 @@ impl<'a> Payload for WritePayload<'a> {
                 let mut writer = Writer::new(buf);
                 req.write_to_writer(&mut writer).context(EncodeBody)?;
             }
+            WritePayload::Delete(req) => {
+                write_header(Header::Delete, buf)?;
+                let mut writer = Writer::new(buf);
+                req.write_to_writer(&mut writer).context(EncodeBody)?;
+            }
         }
 
         Ok(())
@@ -117,6 +134,7 @@ impl<'a> Payload for WritePayload<'a> {
 #[derive(Debug)]
 pub enum ReadPayload {
     Write { row_group: RowGroup },
+    Delete { request: DeleteRequest },
 }
 
 /// Wal payload decoder
@@ -167,6 +185,14 @@ impl PayloadDecoder for WalDecoder {
 
                 ReadPayload::Write { row_group }
             }
+            Header::Delete => {
+                let delete_req_pb: table_requests::DeleteRequest =
+                    Message::parse_from_bytes(buf.remaining_slice()).context(DecodeBody)?;
+                let request = tombstone::delete_request_from_pb(delete_req_pb)
+                    .context(DecodeDeleteRequest)?;
+
+                ReadPayload::Delete { request }
+            }
         };
 
         Ok(payload)
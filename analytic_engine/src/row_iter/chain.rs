@@ -5,7 +5,7 @@ use std::{fmt, time::Instant};
 use async_trait::async_trait;
 use common_types::{
     projected_schema::ProjectedSchema, record_batch::RecordBatchWithKey, request_id::RequestId,
-    schema::RecordSchemaWithKey,
+    schema::RecordSchemaWithKey, time::Timestamp,
 };
 use common_util::define_result;
 use futures::StreamExt;
@@ -24,7 +24,10 @@ use crate::{
         factory::{Factory, SstReaderOptions},
         file::FileHandle,
     },
-    table::version::{MemTableVec, SamplingMemTable},
+    table::{
+        tombstone::Tombstone,
+        version::{MemTableVec, SamplingMemTable},
+    },
 };
 
 #[derive(Debug, Snafu)]
@@ -62,6 +65,10 @@ pub struct ChainConfig<'a, S, Fa> {
     pub sst_factory: Fa,
     /// Sst storage
     pub store: &'a S,
+    /// Tombstones to apply to the ssts.
+    pub tombstones: Vec<Tombstone>,
+    /// The rows before the expire time are removed, None if ttl is disabled.
+    pub expire_time: Option<Timestamp>,
 }
 
 /// Builder for [ChainIterator].
@@ -116,6 +123,9 @@ impl<'a, S: ObjectStore, Fa: Factory> Builder<'a, S, Fa> {
                 &v.mem,
                 false,
                 self.config.predicate.as_ref(),
+                // The tsid filter is shared by the memtables and ssts.
+                self.config.sst_reader_options.tsid_filter.clone(),
+                self.config.expire_time,
             )
             .context(BuildStreamFromMemtable)?;
             streams.push(stream);
@@ -130,6 +140,9 @@ impl<'a, S: ObjectStore, Fa: Factory> Builder<'a, S, Fa> {
                 &memtable.mem,
                 false,
                 self.config.predicate.as_ref(),
+                // The tsid filter is shared by the memtables and ssts.
+                self.config.sst_reader_options.tsid_filter.clone(),
+                self.config.expire_time,
             )
             .context(BuildStreamFromMemtable)?;
             streams.push(stream);
@@ -144,6 +157,8 @@ impl<'a, S: ObjectStore, Fa: Factory> Builder<'a, S, Fa> {
                     &self.config.sst_factory,
                     &self.config.sst_reader_options,
                     self.config.store,
+                    &self.config.tombstones,
+                    self.config.expire_time,
                 )
                 .await
                 .context(BuildStreamFromSst)?;
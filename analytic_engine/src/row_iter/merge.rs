@@ -14,8 +14,9 @@ use common_types::{
     projected_schema::ProjectedSchema,
     record_batch::{RecordBatchWithKey, RecordBatchWithKeyBuilder},
     request_id::RequestId,
-    row::RowViewOnBatch,
+    row::{Row, RowViewOnBatch, RowWithMeta},
     schema::RecordSchemaWithKey,
+    time::Timestamp,
     SequenceNumber,
 };
 use common_util::define_result;
@@ -37,7 +38,10 @@ use crate::{
         file::FileHandle,
         manager::{FileId, MAX_LEVEL},
     },
-    table::version::{MemTableVec, SamplingMemTable},
+    table::{
+        tombstone::Tombstone,
+        version::{MemTableVec, SamplingMemTable},
+    },
 };
 
 #[derive(Debug, Snafu)]
@@ -104,6 +108,13 @@ pub struct MergeConfig<'a, S, Fa> {
 
     pub need_dedup: bool,
     pub reverse: bool,
+
+    /// Tombstones to apply to the ssts.
+    pub tombstones: Vec<Tombstone>,
+    /// The rows before the expire time are removed, None if ttl is disabled.
+    pub expire_time: Option<Timestamp>,
+    /// Max number of distinct keys to return, None if there is no limit.
+    pub limit: Option<usize>,
 }
 
 /// Builder for building merge stream from memtables and sst files.
@@ -181,6 +192,9 @@ impl<'a, S: ObjectStore, Fa: Factory> MergeBuilder<'a, S, Fa> {
                 &v.mem,
                 self.config.reverse,
                 self.config.predicate.as_ref(),
+                // The tsid filter is shared by the memtables and ssts.
+                self.config.sst_reader_options.tsid_filter.clone(),
+                self.config.expire_time,
             )
             .context(BuildStreamFromMemtable)?;
             streams.push(stream);
@@ -193,6 +207,9 @@ impl<'a, S: ObjectStore, Fa: Factory> MergeBuilder<'a, S, Fa> {
                 &memtable.mem,
                 self.config.reverse,
                 self.config.predicate.as_ref(),
+                // The tsid filter is shared by the memtables and ssts.
+                self.config.sst_reader_options.tsid_filter.clone(),
+                self.config.expire_time,
             )
             .context(BuildStreamFromMemtable)?;
             streams.push(stream);
@@ -208,6 +225,8 @@ impl<'a, S: ObjectStore, Fa: Factory> MergeBuilder<'a, S, Fa> {
                     &self.config.sst_factory,
                     &self.config.sst_reader_options,
                     self.config.store,
+                    &self.config.tombstones,
+                    self.config.expire_time,
                 )
                 .await
                 .context(BuildStreamFromSst)?;
@@ -224,6 +243,7 @@ impl<'a, S: ObjectStore, Fa: Factory> MergeBuilder<'a, S, Fa> {
             streams,
             self.config.merge_iter_options,
             self.config.reverse,
+            self.config.limit,
             Metrics::new(self.memtables.len(), sst_streams_num, sst_ids),
         ))
     }
@@ -629,10 +649,17 @@ pub struct MergeIterator {
     cold: BinaryHeap<HeapBufferedStream>,
     iter_options: IterOptions,
     reverse: bool,
+    /// Max number of distinct keys to return, None if there is no limit.
+    limit: Option<usize>,
+    /// Number of distinct keys returned.
+    num_keys_returned: usize,
+    /// Last row returned, used to find the distinct keys.
+    last_row: Option<Row>,
     metrics: Metrics,
 }
 
 impl MergeIterator {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         table_id: TableId,
         request_id: RequestId,
@@ -640,6 +667,7 @@ impl MergeIterator {
         streams: Vec<SequencedRecordBatchStream>,
         iter_options: IterOptions,
         reverse: bool,
+        limit: Option<usize>,
         metrics: Metrics,
     ) -> Self {
         let heap_cap = streams.len();
@@ -656,10 +684,70 @@ impl MergeIterator {
             cold: BinaryHeap::with_capacity(heap_cap),
             iter_options,
             reverse,
+            limit,
+            num_keys_returned: 0,
+            last_row: None,
             metrics,
         }
     }
 
+    /// Returns true if enough distinct keys are returned.
+    #[inline]
+    fn is_limit_reached(&self) -> bool {
+        matches!(self.limit, Some(limit) if self.num_keys_returned >= limit)
+    }
+
+    /// Cut the `record_batch` before the first row exceeding the limit.
+    ///
+    /// Only the keys are counted as the duplicate rows are removed by dedup
+    /// later, which also means the duplicate rows of the last key may be cut.
+    fn apply_limit(&mut self, record_batch: RecordBatchWithKey) -> RecordBatchWithKey {
+        let limit = match self.limit {
+            Some(v) => v,
+            None => return record_batch,
+        };
+
+        let mut num_rows = 0;
+        for row_idx in 0..record_batch.num_rows() {
+            let row = RowViewOnBatch {
+                record_batch: &record_batch,
+                row_idx,
+            };
+            let is_new_key = if row_idx > 0 {
+                let prev_row = RowViewOnBatch {
+                    record_batch: &record_batch,
+                    row_idx: row_idx - 1,
+                };
+                self.schema.compare_row(&prev_row, &row) != Ordering::Equal
+            } else if let Some(last_row) = &self.last_row {
+                let prev_row = RowWithMeta {
+                    row: last_row,
+                    schema: &self.schema,
+                };
+                self.schema.compare_row(&prev_row, &row) != Ordering::Equal
+            } else {
+                true
+            };
+
+            if is_new_key {
+                if self.num_keys_returned >= limit {
+                    break;
+                }
+                self.num_keys_returned += 1;
+            }
+            num_rows += 1;
+        }
+
+        if num_rows > 0 {
+            self.last_row = Some(record_batch.clone_row_at(num_rows - 1));
+        }
+        if num_rows == record_batch.num_rows() {
+            record_batch
+        } else {
+            record_batch.slice(0, num_rows)
+        }
+    }
+
     fn merge_window_end(&self) -> Option<RowViewOnBatch> {
         self.hot.peek().as_ref().map(|v| v.last_row_in_buffer())
     }
@@ -805,6 +893,20 @@ impl MergeIterator {
     ///
     /// `init_if_necessary` should be finished before this method.
     async fn fetch_next_batch(&mut self) -> Result<Option<RecordBatchWithKey>> {
+        // No need to merge the remaining rows if the limit is reached.
+        if self.is_limit_reached() {
+            return Ok(None);
+        }
+
+        let record_batch = self.fetch_next_merged_batch().await?;
+
+        Ok(record_batch
+            .map(|batch| self.apply_limit(batch))
+            .filter(|batch| !batch.is_empty()))
+    }
+
+    /// Fetch the next merged batch from the streams.
+    async fn fetch_next_merged_batch(&mut self) -> Result<Option<RecordBatchWithKey>> {
         self.init_if_necessary().await?;
 
         self.record_batch_builder.clear();
@@ -896,6 +998,7 @@ mod tests {
             streams,
             IterOptions::default(),
             false,
+            None,
             Metrics::new(1, 1, vec![]),
         );
 
@@ -939,6 +1042,7 @@ mod tests {
             streams,
             IterOptions::default(),
             true,
+            None,
             Metrics::new(1, 1, vec![]),
         );
 
@@ -954,4 +1058,46 @@ mod tests {
         )
         .await;
     }
+
+    #[tokio::test]
+    async fn test_row_merge_iterator_limit() {
+        // first two columns are key columns
+        let schema = build_schema();
+
+        let testcases = vec![
+            // (sequence, rows)
+            (
+                10,
+                vec![
+                    build_row(b"y", 1000001, 10.0, "v5"),
+                    build_row(b"y", 1000000, 10.0, "v4"),
+                ],
+            ),
+            (20, vec![build_row(b"y", 1000000, 10.0, "v3")]),
+            (100, vec![build_row(b"b", 1000000, 10.0, "v2")]),
+            (1, vec![build_row(b"a", 1000000, 10.0, "v1")]),
+        ];
+
+        let streams =
+            record_batch_stream::tests::build_sequenced_record_batch_stream(&schema, testcases);
+        let mut iter = MergeIterator::new(
+            TableId::MIN,
+            RequestId::next_id(),
+            schema.to_record_schema_with_key(),
+            streams,
+            IterOptions { batch_size: 1 },
+            true,
+            Some(2),
+            Metrics::new(1, 1, vec![]),
+        );
+
+        check_iterator(
+            &mut iter,
+            vec![
+                build_row(b"y", 1000001, 10.0, "v5"),
+                build_row(b"y", 1000000, 10.0, "v3"),
+            ],
+        )
+        .await;
+    }
 }
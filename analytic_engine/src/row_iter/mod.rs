@@ -20,6 +20,7 @@ pub mod chain;
 pub mod dedup;
 pub mod merge;
 pub mod record_batch_stream;
+pub mod split;
 #[cfg(test)]
 pub mod tests;
 
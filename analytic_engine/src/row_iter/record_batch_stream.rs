@@ -3,7 +3,11 @@
 use std::ops::Bound;
 
 use common_types::{
-    projected_schema::ProjectedSchema, record_batch::RecordBatchWithKey, SequenceNumber,
+    datum::DatumView,
+    projected_schema::ProjectedSchema,
+    record_batch::{self, RecordBatchWithKey},
+    time::Timestamp,
+    SequenceNumber,
 };
 use common_util::define_result;
 use futures::stream::{self, Stream, StreamExt};
@@ -20,7 +24,11 @@ use crate::{
     space::SpaceId,
     sst,
     sst::{factory::SstReaderOptions, file::FileHandle},
-    table::sst_util,
+    table::{
+        sst_util,
+        tombstone::{self, Tombstone, TombstoneFilter},
+    },
+    tag_index::TsidSetRef,
 };
 
 #[derive(Debug, Snafu)]
@@ -154,17 +162,126 @@ pub fn filter_stream(
     Box::new(stream)
 }
 
+/// Remove the rows hidden by the tombstones in `filter` from the sequenced
+/// record batch stream.
+pub fn filter_stream_by_tombstones(
+    origin_stream: SequencedRecordBatchStream,
+    filter: TombstoneFilter,
+) -> SequencedRecordBatchStream {
+    if filter.is_empty() {
+        return origin_stream;
+    }
+
+    let mut select_row_buf = Vec::new();
+    let stream = origin_stream.filter_map(move |sequence_record_batch| {
+        let v = match sequence_record_batch {
+            Ok(mut v) => match filter.filter(&mut v.record_batch, &mut select_row_buf) {
+                // All rows are deleted.
+                Ok(()) if v.num_rows() == 0 => None,
+                Ok(()) => Some(Ok(v)),
+                Err(e) => Some(Err(Box::new(e) as _)),
+            },
+            Err(e) => Some(Err(e)),
+        };
+
+        futures::future::ready(v)
+    });
+
+    Box::new(stream)
+}
+
+/// Remove the rows whose timestamp is before `expire_time` from the sequenced
+/// record batch stream, the stream is unchanged if `expire_time` is None.
+pub fn filter_stream_by_expire_time(
+    origin_stream: SequencedRecordBatchStream,
+    timestamp_column: String,
+    expire_time: Option<Timestamp>,
+) -> SequencedRecordBatchStream {
+    let expire_time = match expire_time {
+        Some(v) => v,
+        None => return origin_stream,
+    };
+
+    let mut select_row_buf = Vec::new();
+    let stream = origin_stream.filter_map(move |sequence_record_batch| {
+        let v = match sequence_record_batch {
+            Ok(mut v) => match remove_expired_rows(
+                &mut v.record_batch,
+                &timestamp_column,
+                expire_time,
+                &mut select_row_buf,
+            ) {
+                // All rows are expired.
+                Ok(()) if v.num_rows() == 0 => None,
+                Ok(()) => Some(Ok(v)),
+                Err(e) => Some(Err(Box::new(e) as _)),
+            },
+            Err(e) => Some(Err(e)),
+        };
+
+        futures::future::ready(v)
+    });
+
+    Box::new(stream)
+}
+
+/// Remove the rows whose timestamp is before `expire_time` from the
+/// `record_batch`.
+///
+/// The `selected_rows_buf` is for reuse.
+fn remove_expired_rows(
+    record_batch: &mut RecordBatchWithKey,
+    timestamp_column: &str,
+    expire_time: Timestamp,
+    selected_rows_buf: &mut Vec<bool>,
+) -> record_batch::Result<()> {
+    let timestamp_index = match record_batch.schema_with_key().index_of(timestamp_column) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    selected_rows_buf.clear();
+    let column = record_batch.column(timestamp_index);
+    let mut num_selected = 0;
+    for row_idx in 0..record_batch.num_rows() {
+        let selected = match column.datum_view(row_idx) {
+            DatumView::Timestamp(ts) => !ts.is_expired(expire_time),
+            _ => true,
+        };
+        if selected {
+            num_selected += 1;
+        }
+        selected_rows_buf.push(selected);
+    }
+
+    if num_selected == record_batch.num_rows() {
+        return Ok(());
+    }
+    record_batch.select_data(selected_rows_buf)
+}
+
 /// Build filtered (by `predicate`) [SequencedRecordBatchStream] from a
 /// memtable.
+///
+/// Only the rows of the series in `tsid_filter` are scanned if it is not None,
+/// and the rows expired at `expire_time` are removed.
 pub fn filtered_stream_from_memtable(
     projected_schema: ProjectedSchema,
     need_dedup: bool,
     memtable: &MemTableRef,
     reverse: bool,
     predicate: &Predicate,
+    tsid_filter: Option<TsidSetRef>,
+    expire_time: Option<Timestamp>,
 ) -> Result<SequencedRecordBatchStream> {
-    stream_from_memtable(projected_schema, need_dedup, memtable, reverse)
-        .map(|origin_stream| filter_stream(origin_stream, predicate))
+    let timestamp_column = projected_schema.timestamp_name().to_string();
+
+    stream_from_memtable(projected_schema, need_dedup, memtable, reverse, tsid_filter).map(
+        |origin_stream| {
+            let stream = filter_stream_by_expire_time(origin_stream, timestamp_column, expire_time);
+            filter_stream(stream, predicate)
+        },
+    )
 }
 
 /// Build [SequencedRecordBatchStream] from a memtable.
@@ -173,6 +290,7 @@ pub fn stream_from_memtable(
     need_dedup: bool,
     memtable: &MemTableRef,
     reverse: bool,
+    tsid_filter: Option<TsidSetRef>,
 ) -> Result<SequencedRecordBatchStream> {
     let scan_ctx = ScanContext::default();
     let max_seq = memtable.last_sequence();
@@ -183,6 +301,7 @@ pub fn stream_from_memtable(
         projected_schema,
         need_dedup,
         reverse,
+        tsid_filter,
     };
 
     let iter = memtable.scan(scan_ctx, scan_req).context(ScanMemtable)?;
@@ -199,6 +318,10 @@ pub fn stream_from_memtable(
 
 /// Build the filtered by `sst_read_options.predicate`
 /// [SequencedRecordBatchStream] from a sst.
+///
+/// The rows hidden by the `tombstones` applicable to the sst and the rows
+/// expired at `expire_time` are also removed.
+#[allow(clippy::too_many_arguments)]
 pub async fn filtered_stream_from_sst_file<Fa, S>(
     space_id: SpaceId,
     table_id: TableId,
@@ -206,11 +329,24 @@ pub async fn filtered_stream_from_sst_file<Fa, S>(
     sst_factory: &Fa,
     sst_reader_options: &SstReaderOptions,
     store: &S,
+    tombstones: &[Tombstone],
+    expire_time: Option<Timestamp>,
 ) -> Result<SequencedRecordBatchStream>
 where
     Fa: sst::factory::Factory,
     S: ObjectStore,
 {
+    let tombstones =
+        tombstone::tombstones_for_sst(tombstones, sst_file.max_sequence(), sst_file.time_range());
+    let timestamp_column = sst_reader_options
+        .projected_schema
+        .timestamp_name()
+        .to_string();
+    // No row of the sst is expired if its time range starts after the expire
+    // time.
+    let expire_time =
+        expire_time.filter(|t| sst_file.time_range().inclusive_start().is_expired(*t));
+
     stream_from_sst_file(
         space_id,
         table_id,
@@ -220,7 +356,13 @@ where
         store,
     )
     .await
-    .map(|origin_stream| filter_stream(origin_stream, sst_reader_options.predicate.as_ref()))
+    .map(|origin_stream| {
+        let stream =
+            filter_stream_by_expire_time(origin_stream, timestamp_column.clone(), expire_time);
+        let stream =
+            filter_stream_by_tombstones(stream, TombstoneFilter::new(timestamp_column, tombstones));
+        filter_stream(stream, sst_reader_options.predicate.as_ref())
+    })
 }
 
 /// Build the [SequencedRecordBatchStream] from a sst.
@@ -260,7 +402,12 @@ where
 
 #[cfg(test)]
 pub mod tests {
-    use common_types::{row::Row, schema::Schema};
+    use common_types::{
+        datum::Datum,
+        row::Row,
+        schema::Schema,
+        tests::{build_row, build_schema},
+    };
 
     use super::*;
     use crate::row_iter;
@@ -284,4 +431,59 @@ pub mod tests {
             })
             .collect()
     }
+
+    #[tokio::test]
+    async fn test_filter_stream_by_expire_time() {
+        let schema = build_schema();
+        let timestamp_column = schema.timestamp_name().to_string();
+        let build_stream = || {
+            build_sequenced_record_batch_stream(
+                &schema,
+                vec![
+                    (
+                        10,
+                        vec![
+                            build_row(b"a", 1000, 10.0, "v1"),
+                            build_row(b"b", 2000, 10.0, "v2"),
+                            build_row(b"c", 3000, 10.0, "v3"),
+                        ],
+                    ),
+                    (20, vec![build_row(b"d", 1500, 10.0, "v4")]),
+                ],
+            )
+        };
+
+        let mut streams = build_stream();
+        let stream = filter_stream_by_expire_time(
+            streams.pop().unwrap(),
+            timestamp_column.clone(),
+            Some(Timestamp::new(2000)),
+        );
+        // All the rows of the batch are expired.
+        assert!(stream.collect::<Vec<_>>().await.is_empty());
+
+        let stream = filter_stream_by_expire_time(
+            streams.pop().unwrap(),
+            timestamp_column.clone(),
+            Some(Timestamp::new(2000)),
+        );
+        let batches: Vec<_> = stream.map(|v| v.unwrap()).collect().await;
+        assert_eq!(1, batches.len());
+        let record_batch = &batches[0].record_batch;
+        assert_eq!(2, record_batch.num_rows());
+        let timestamp_index = schema.timestamp_index();
+        assert_eq!(
+            Datum::Timestamp(Timestamp::new(2000)),
+            record_batch.column(timestamp_index).datum(0)
+        );
+        assert_eq!(
+            Datum::Timestamp(Timestamp::new(3000)),
+            record_batch.column(timestamp_index).datum(1)
+        );
+
+        // Nothing is removed without expire time.
+        let stream = filter_stream_by_expire_time(build_stream().remove(0), timestamp_column, None);
+        let batches: Vec<_> = stream.map(|v| v.unwrap()).collect().await;
+        assert_eq!(3, batches[0].num_rows());
+    }
 }
@@ -0,0 +1,168 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Split a record batch stream into multiple streams
+
+use std::sync::Arc;
+
+use common_types::{record_batch::RecordBatchWithKey, row::Row};
+use futures::{future, stream, StreamExt};
+use tokio::sync::Mutex;
+
+use crate::sst::builder::RecordBatchStream;
+
+/// Splits a [RecordBatchStream] into streams of at least `num_rows_per_split`
+/// rows (except the last one), so the rows can be written into multiple ssts.
+///
+/// The split streams share the origin stream, so a split must be consumed
+/// entirely before getting the next one.
+pub struct RecordBatchStreamSplitter {
+    state: Arc<Mutex<SplitState>>,
+    num_rows_per_split: usize,
+}
+
+struct SplitState {
+    stream: RecordBatchStream,
+    /// Key columns of the first row of the current split.
+    first_key: Option<Row>,
+    /// Key columns of the last row read from the current split.
+    last_key: Option<Row>,
+}
+
+impl RecordBatchStreamSplitter {
+    pub fn new(stream: RecordBatchStream, num_rows_per_split: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SplitState {
+                stream,
+                first_key: None,
+                last_key: None,
+            })),
+            num_rows_per_split,
+        }
+    }
+
+    /// Returns the next split, or None if the origin stream is exhausted.
+    pub async fn next_split(&mut self) -> Option<RecordBatchStream> {
+        let first = {
+            let mut state = self.state.lock().await;
+            loop {
+                match state.stream.next().await? {
+                    Ok(record_batch) if record_batch.is_empty() => continue,
+                    Ok(record_batch) => {
+                        state.first_key = Some(key_of_row(&record_batch, 0));
+                        state.last_key =
+                            Some(key_of_row(&record_batch, record_batch.num_rows() - 1));
+                        break Ok(record_batch);
+                    }
+                    Err(e) => break Err(e),
+                }
+            }
+        };
+
+        let num_rows = first.as_ref().map_or(0, |v| v.num_rows());
+        let num_rows_per_split = self.num_rows_per_split;
+        let rest = stream::unfold(
+            (self.state.clone(), num_rows),
+            move |(state, num_rows)| async move {
+                if num_rows >= num_rows_per_split {
+                    return None;
+                }
+
+                let item = {
+                    let mut guard = state.lock().await;
+                    let item = guard.stream.next().await?;
+                    if let Ok(record_batch) = &item {
+                        if !record_batch.is_empty() {
+                            guard.last_key =
+                                Some(key_of_row(record_batch, record_batch.num_rows() - 1));
+                        }
+                    }
+                    item
+                };
+                let num_rows = num_rows + item.as_ref().map_or(0, |v| v.num_rows());
+
+                Some((item, (state, num_rows)))
+            },
+        );
+
+        let split: RecordBatchStream =
+            Box::new(Box::pin(stream::once(future::ready(first)).chain(rest)));
+        Some(split)
+    }
+
+    /// Returns the key columns of the first and the last rows of the split
+    /// last returned by `next_split()`.
+    pub async fn key_range_of_split(&self) -> Option<(Row, Row)> {
+        let state = self.state.lock().await;
+        state.first_key.clone().zip(state.last_key.clone())
+    }
+}
+
+fn key_of_row(record_batch: &RecordBatchWithKey, row_idx: usize) -> Row {
+    let num_key_columns = record_batch.schema_with_key().num_key_columns();
+    let datums = (0..num_key_columns)
+        .map(|col_idx| record_batch.column(col_idx).datum(row_idx))
+        .collect();
+
+    Row::from_datums(datums)
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::{
+        datum::Datum,
+        tests::{build_row, build_schema},
+        time::Timestamp,
+    };
+
+    use super::*;
+    use crate::{
+        row_iter::tests::build_record_batch_with_key, sst::builder::RecordBatchStreamItem,
+    };
+
+    #[tokio::test]
+    async fn test_split_record_batch_stream() {
+        let schema = build_schema();
+        let batches: Vec<RecordBatchStreamItem> = vec![
+            vec![
+                build_row(b"a", 1, 10.0, "v1"),
+                build_row(b"b", 2, 10.0, "v2"),
+            ],
+            vec![],
+            vec![build_row(b"c", 3, 10.0, "v3")],
+            vec![
+                build_row(b"d", 4, 10.0, "v4"),
+                build_row(b"e", 5, 10.0, "v5"),
+            ],
+            vec![build_row(b"f", 6, 10.0, "v6")],
+        ]
+        .into_iter()
+        .map(|rows| Ok(build_record_batch_with_key(schema.clone(), rows)))
+        .collect();
+
+        let mut splitter = RecordBatchStreamSplitter::new(Box::new(stream::iter(batches)), 3);
+        let mut splits = Vec::new();
+        while let Some(split) = splitter.next_split().await {
+            let num_rows = split
+                .fold(0, |acc, v| future::ready(acc + v.unwrap().num_rows()))
+                .await;
+            let (first_key, last_key) = splitter.key_range_of_split().await.unwrap();
+            splits.push((num_rows, first_key[1].clone(), last_key[1].clone()));
+        }
+
+        assert_eq!(
+            vec![
+                (
+                    3,
+                    Datum::Timestamp(Timestamp::new(1)),
+                    Datum::Timestamp(Timestamp::new(3))
+                ),
+                (
+                    3,
+                    Datum::Timestamp(Timestamp::new(4)),
+                    Datum::Timestamp(Timestamp::new(6))
+                ),
+            ],
+            splits
+        );
+    }
+}
@@ -5,7 +5,12 @@
 use std::{path::Path, sync::Arc};
 
 use common_util::{define_result, runtime::Runtime};
-use object_store::{aliyun::AliyunOSS, LocalFileSystem, ObjectStore};
+use object_store::{
+    aliyun::AliyunOSS,
+    cache::{CachedStore, CachedStoreConfig},
+    s3::{RusotoS3Client, S3Store},
+    LocalFileSystem, ObjectStore,
+};
 use parquet::{
     cache::{LruDataCache, LruMetaCache},
     DataCacheRef, MetaCacheRef,
@@ -13,6 +18,8 @@ use parquet::{
 use snafu::{ResultExt, Snafu};
 use table_engine::engine::{EngineRuntimes, TableEngineRef};
 use wal::{
+    file_impl::manager::{Builder as FileWalBuilder, Options as FileWalManagerOptions},
+    group_commit::GroupCommitWal,
     manager::{self, WalManager},
     rocks_impl::manager::Builder as WalBuilder,
 };
@@ -23,7 +30,9 @@ use crate::{
     instance::{Instance, InstanceRef},
     meta::{details::ManifestImpl, Manifest},
     sst::factory::{Factory, FactoryImpl},
-    storage_options::{AliyunOptions, LocalOptions},
+    storage_options::{
+        AliyunOptions, LocalCacheOptions, LocalOptions, S3Options, WalStorageOptions,
+    },
     Config,
 };
 
@@ -48,6 +57,11 @@ pub enum Error {
         source: object_store::ObjectStoreError,
     },
 
+    #[snafu(display("Failed to create s3 client, err:{}", source))]
+    CreateS3Client {
+        source: object_store::s3::ClientError,
+    },
+
     #[snafu(display("Failed to create dir for {}, err:{}", path, source))]
     CreateDir {
         path: String,
@@ -66,18 +80,55 @@ pub async fn open_analytic_table_engine(
     config: Config,
     engine_runtimes: Arc<EngineRuntimes>,
 ) -> Result<TableEngineRef> {
-    let wal = open_wal(
-        config.clone(),
-        engine_runtimes.write_runtime.clone(),
-        WAL_DIR_NAME,
-    )
-    .await?;
-    let manifest_wal = open_wal(
-        config.clone(),
-        engine_runtimes.write_runtime.clone(),
-        MANIFEST_DIR_NAME,
-    )
-    .await?;
+    let runtime = engine_runtimes.write_runtime.clone();
+    match config.wal_storage.clone() {
+        WalStorageOptions::RocksDB => {
+            let wal = open_wal(config.clone(), runtime.clone(), WAL_DIR_NAME).await?;
+            let manifest_wal = open_wal(config.clone(), runtime, MANIFEST_DIR_NAME).await?;
+            open_engine_with_wal(config, wal, manifest_wal, engine_runtimes).await
+        }
+        WalStorageOptions::File(opts) => {
+            let mut opts = FileWalManagerOptions::from(opts);
+            opts.compression = config.wal_compression.into();
+            let wal =
+                open_file_wal(config.clone(), opts.clone(), runtime.clone(), WAL_DIR_NAME).await?;
+            let manifest_wal =
+                open_file_wal(config.clone(), opts, runtime, MANIFEST_DIR_NAME).await?;
+            open_engine_with_wal(config, wal, manifest_wal, engine_runtimes).await
+        }
+    }
+}
+
+async fn open_engine_with_wal<Wal>(
+    config: Config,
+    wal: Wal,
+    manifest_wal: Wal,
+    engine_runtimes: Arc<EngineRuntimes>,
+) -> Result<TableEngineRef>
+where
+    Wal: WalManager + Send + Sync + 'static,
+{
+    // Only the writes of the tables are committed in groups, the manifest is
+    // seldom written.
+    match config.wal_group_commit.clone() {
+        Some(opts) => {
+            let wal = GroupCommitWal::new(wal, &engine_runtimes.write_runtime, opts.into());
+            open_engine_with_storage(config, wal, manifest_wal, engine_runtimes).await
+        }
+        None => open_engine_with_storage(config, wal, manifest_wal, engine_runtimes).await,
+    }
+}
+
+async fn open_engine_with_storage<Wal, ManifestWal>(
+    config: Config,
+    wal: Wal,
+    manifest_wal: ManifestWal,
+    engine_runtimes: Arc<EngineRuntimes>,
+) -> Result<TableEngineRef>
+where
+    Wal: WalManager + Send + Sync + 'static,
+    ManifestWal: WalManager + Send + Sync + 'static,
+{
     let manifest = open_manifest(config.clone(), manifest_wal).await?;
 
     match config.storage {
@@ -93,6 +144,24 @@ pub async fn open_analytic_table_engine(
                 open_instance(config, wal, manifest, storage, FactoryImpl, engine_runtimes).await?;
             Ok(Arc::new(TableEngineImpl::new(instance)))
         }
+        crate::storage_options::StorageOptions::S3(ref opts) => {
+            let storage = open_storage_s3(opts.clone())?;
+            match opts.cache.clone() {
+                Some(cache_opts) => {
+                    let storage = open_storage_cached(cache_opts, Box::new(storage)).await?;
+                    let instance =
+                        open_instance(config, wal, manifest, storage, FactoryImpl, engine_runtimes)
+                            .await?;
+                    Ok(Arc::new(TableEngineImpl::new(instance)))
+                }
+                None => {
+                    let instance =
+                        open_instance(config, wal, manifest, storage, FactoryImpl, engine_runtimes)
+                            .await?;
+                    Ok(Arc::new(TableEngineImpl::new(instance)))
+                }
+            }
+        }
     }
 }
 
@@ -146,6 +215,21 @@ async fn open_wal(
     let data_path = Path::new(&config.wal_path);
     let wal_path = data_path.join(sub_path);
     WalBuilder::with_default_rocksdb_config(wal_path, runtime)
+        .compression(config.wal_compression.into())
+        .build()
+        .context(OpenWal)
+}
+
+async fn open_file_wal(
+    config: Config,
+    opts: FileWalManagerOptions,
+    runtime: Arc<Runtime>,
+    sub_path: &str,
+) -> Result<impl WalManager + Send + Sync + 'static> {
+    let data_path = Path::new(&config.wal_path);
+    let wal_path = data_path.join(sub_path);
+    FileWalBuilder::new(wal_path, runtime)
+        .options(opts)
         .build()
         .context(OpenWal)
 }
@@ -181,3 +265,35 @@ async fn open_storage_aliyun(opts: AliyunOptions) -> Result<impl ObjectStore> {
         opts.bucket,
     ))
 }
+
+fn open_storage_s3(opts: S3Options) -> Result<S3Store<RusotoS3Client>> {
+    let client = RusotoS3Client::new(
+        opts.region,
+        opts.endpoint,
+        opts.key_id,
+        opts.key_secret,
+        opts.bucket,
+    )
+    .context(CreateS3Client)?;
+
+    Ok(S3Store::new(client))
+}
+
+/// Open a [CachedStore] which caches the objects of the `remote` store in the
+/// local disk.
+async fn open_storage_cached(
+    opts: LocalCacheOptions,
+    remote: Box<dyn ObjectStore>,
+) -> Result<CachedStore> {
+    let local = open_storage_local(LocalOptions {
+        data_path: opts.data_path,
+    })
+    .await?;
+    let config = CachedStoreConfig {
+        max_cache_size: opts.max_cache_size,
+    };
+
+    CachedStore::init(Box::new(local), remote, config)
+        .await
+        .context(OpenObjectStore)
+}
@@ -135,14 +135,13 @@ impl Space {
     /// Insert table data into space memory state if the table is
     /// absent. For internal use only
     ///
-    /// Panic if the table is already exists
-    pub(crate) fn insert_table(&self, table_data: TableDataRef) {
-        let success = self
-            .table_datas
+    /// Returns false if a table with the same name already exists
+    #[must_use]
+    pub(crate) fn insert_table(&self, table_data: TableDataRef) -> bool {
+        self.table_datas
             .write()
             .unwrap()
-            .insert_if_absent(table_data);
-        assert!(success);
+            .insert_if_absent(table_data)
     }
 
     /// Find table under this space by table name
@@ -0,0 +1,395 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Bloom filters of the tag columns in the sst
+
+use std::{collections::HashSet, fmt};
+
+use common_types::{datum::Datum, hash::hash64, record_batch::RecordBatchWithKey, schema::Schema};
+use common_util::codec::{compact::MemCompactEncoder, Encoder};
+use proto::sst::{BloomFilter as BloomFilterPb, RowGroupBloomFilter as RowGroupBloomFilterPb};
+use table_engine::predicate::Predicate;
+
+/// Bits allocated for each distinct value, which gives about 1% false positive
+/// rate with `NUM_HASHES` hash functions.
+const BITS_PER_VALUE: usize = 10;
+const NUM_HASHES: u32 = 7;
+
+/// Bloom filter of the values of a column.
+#[derive(Clone, PartialEq)]
+pub struct BloomFilter {
+    num_hashes: u32,
+    bits: Vec<u8>,
+}
+
+impl BloomFilter {
+    /// Create a filter for at most `num_values` distinct values.
+    pub fn with_capacity(num_values: usize) -> Self {
+        let num_bytes = (num_values.max(1) * BITS_PER_VALUE + 7) / 8;
+
+        Self {
+            num_hashes: NUM_HASHES,
+            bits: vec![0; num_bytes],
+        }
+    }
+
+    pub fn insert(&mut self, value: &[u8]) {
+        for bit in bit_indexes(value, self.num_hashes, self.num_bits()) {
+            self.bits[bit / 8] |= 1 << (bit % 8);
+        }
+    }
+
+    /// Returns false if the `value` is definitely not in the filter.
+    pub fn may_contain(&self, value: &[u8]) -> bool {
+        bit_indexes(value, self.num_hashes, self.num_bits())
+            .all(|bit| self.bits[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    #[inline]
+    fn num_bits(&self) -> u64 {
+        self.bits.len() as u64 * 8
+    }
+}
+
+impl fmt::Debug for BloomFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BloomFilter")
+            .field("num_hashes", &self.num_hashes)
+            .field("num_bytes", &self.bits.len())
+            .finish()
+    }
+}
+
+/// Indexes of the bits of the `value`, derived from one hash by double
+/// hashing.
+///
+/// REQUIRE: `num_bits` > 0
+fn bit_indexes(value: &[u8], num_hashes: u32, num_bits: u64) -> impl Iterator<Item = usize> {
+    let hash = hash64(value);
+    let (h1, h2) = (hash & 0xffff_ffff, hash >> 32);
+
+    (0..u64::from(num_hashes))
+        .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % num_bits) as usize)
+}
+
+/// Bloom filters of the columns in a row group.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RowGroupBloomFilter {
+    /// Filters in the order of the columns in the schema, None for the columns
+    /// without filter.
+    column_filters: Vec<Option<BloomFilter>>,
+}
+
+impl RowGroupBloomFilter {
+    /// Build the filters of the tag columns from the rows of a row group.
+    pub fn build(schema: &Schema, record_batches: &[RecordBatchWithKey]) -> Self {
+        let mut value_buf = Vec::new();
+        let column_filters = schema
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(col_idx, column)| {
+                if !column.is_tag {
+                    return None;
+                }
+
+                let mut values = HashSet::new();
+                for record_batch in record_batches {
+                    let column_block = record_batch.column(col_idx);
+                    for row_idx in 0..record_batch.num_rows() {
+                        let datum = column_block.datum(row_idx);
+                        if datum.is_null() {
+                            continue;
+                        }
+                        encode_value(&datum, &mut value_buf);
+                        if !values.contains(&value_buf) {
+                            values.insert(value_buf.clone());
+                        }
+                    }
+                }
+
+                let mut filter = BloomFilter::with_capacity(values.len());
+                for value in &values {
+                    filter.insert(value);
+                }
+                Some(filter)
+            })
+            .collect();
+
+        Self { column_filters }
+    }
+
+    #[inline]
+    fn column_filter(&self, col_idx: usize) -> Option<&BloomFilter> {
+        self.column_filters.get(col_idx).and_then(|v| v.as_ref())
+    }
+}
+
+impl From<RowGroupBloomFilter> for RowGroupBloomFilterPb {
+    fn from(src: RowGroupBloomFilter) -> Self {
+        let column_filters: Vec<_> = src
+            .column_filters
+            .into_iter()
+            .map(|filter| {
+                let mut target = BloomFilterPb::new();
+                if let Some(filter) = filter {
+                    target.set_num_hashes(filter.num_hashes);
+                    target.set_bits(filter.bits);
+                }
+                target
+            })
+            .collect();
+
+        let mut target = RowGroupBloomFilterPb::new();
+        target.column_filters = column_filters.into();
+        target
+    }
+}
+
+impl From<RowGroupBloomFilterPb> for RowGroupBloomFilter {
+    fn from(mut src: RowGroupBloomFilterPb) -> Self {
+        let column_filters = src
+            .take_column_filters()
+            .into_iter()
+            .map(|filter| {
+                // The column has no filter.
+                if filter.bits.is_empty() || filter.num_hashes == 0 {
+                    return None;
+                }
+
+                Some(BloomFilter {
+                    num_hashes: filter.num_hashes,
+                    bits: filter.bits,
+                })
+            })
+            .collect();
+
+        Self { column_filters }
+    }
+}
+
+/// Bloom filters of the row groups in the sst.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SstBloomFilter {
+    /// Filters in the order of the row groups, empty if the sst is built
+    /// without bloom filters.
+    row_group_filters: Vec<RowGroupBloomFilter>,
+}
+
+impl SstBloomFilter {
+    pub fn new(row_group_filters: Vec<RowGroupBloomFilter>) -> Self {
+        Self { row_group_filters }
+    }
+
+    /// Build the filters of the row groups, the filter is empty if the schema
+    /// has no tag column.
+    pub fn build(schema: &Schema, row_groups: &[Vec<RecordBatchWithKey>]) -> Self {
+        if !schema.columns().iter().any(|column| column.is_tag) {
+            return Self::default();
+        }
+
+        let row_group_filters = row_groups
+            .iter()
+            .map(|record_batches| RowGroupBloomFilter::build(schema, record_batches))
+            .collect();
+
+        Self { row_group_filters }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.row_group_filters.is_empty()
+    }
+
+    #[inline]
+    pub fn row_group_filters(&self) -> &[RowGroupBloomFilter] {
+        &self.row_group_filters
+    }
+
+    pub fn into_row_group_filters(self) -> Vec<RowGroupBloomFilter> {
+        self.row_group_filters
+    }
+
+    /// Determine whether a row group should be read according to the equality
+    /// and IN list exprs on the tag columns in the `predicate`.
+    ///
+    /// The `schema` is the schema of the sst and all row groups are read if
+    /// the sst has no bloom filter.
+    pub fn filter_row_groups(
+        &self,
+        schema: &Schema,
+        predicate: &Predicate,
+        num_row_groups: usize,
+    ) -> Vec<bool> {
+        let mut results = vec![true; num_row_groups];
+        if self.row_group_filters.len() != num_row_groups {
+            return results;
+        }
+
+        let mut value_buf = Vec::new();
+        for (col_idx, column) in schema.columns().iter().enumerate() {
+            if !column.is_tag {
+                continue;
+            }
+            let values = match predicate.values_of_column(&column.name, column.data_type) {
+                Some(values) => values,
+                None => continue,
+            };
+            let encoded_values: Vec<_> = values
+                .iter()
+                .map(|value| {
+                    encode_value(value, &mut value_buf);
+                    value_buf.clone()
+                })
+                .collect();
+
+            for (result, row_group_filter) in results.iter_mut().zip(&self.row_group_filters) {
+                if let Some(filter) = row_group_filter.column_filter(col_idx) {
+                    if !encoded_values.iter().any(|v| filter.may_contain(v)) {
+                        *result = false;
+                    }
+                }
+            }
+        }
+
+        results
+    }
+}
+
+fn encode_value(datum: &Datum, buf: &mut Vec<u8>) {
+    buf.clear();
+    MemCompactEncoder
+        .encode(buf, datum)
+        .expect("Should encode datum into the buffer successfully");
+}
+
+#[cfg(test)]
+pub mod tests {
+    use arrow_deps::datafusion::logical_plan::{col, lit, Expr};
+    use common_types::{
+        bytes::Bytes,
+        column_schema,
+        datum::DatumKind,
+        row::Row,
+        schema::{self, Schema},
+        string::StringBytes,
+        time::{TimeRange, Timestamp},
+    };
+
+    use super::*;
+    use crate::row_iter::tests::build_record_batch_with_key;
+
+    pub fn build_tag_schema() -> Schema {
+        schema::Builder::new()
+            .auto_increment_column_id(true)
+            .add_key_column(
+                column_schema::Builder::new("key".to_string(), DatumKind::Varbinary)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_key_column(
+                column_schema::Builder::new("timestamp".to_string(), DatumKind::Timestamp)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_normal_column(
+                column_schema::Builder::new("host".to_string(), DatumKind::String)
+                    .is_tag(true)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    pub fn build_tag_row(key: &[u8], ts: i64, host: &str) -> Row {
+        Row::from_datums(vec![
+            Datum::Varbinary(Bytes::copy_from_slice(key)),
+            Datum::Timestamp(Timestamp::new(ts)),
+            Datum::String(StringBytes::from(host)),
+        ])
+    }
+
+    fn new_predicate(exprs: Vec<Expr>) -> Predicate {
+        Predicate {
+            exprs,
+            time_range: TimeRange::min_to_max(),
+        }
+    }
+
+    #[test]
+    fn test_bloom_filter() {
+        let mut filter = BloomFilter::with_capacity(100);
+        for i in 0..100 {
+            filter.insert(format!("value{}", i).as_bytes());
+        }
+        for i in 0..100 {
+            assert!(filter.may_contain(format!("value{}", i).as_bytes()));
+        }
+        let false_positives = (100..1100)
+            .filter(|i| filter.may_contain(format!("value{}", i).as_bytes()))
+            .count();
+        assert!(false_positives < 50, "false_positives:{}", false_positives);
+    }
+
+    #[test]
+    fn test_filter_row_groups() {
+        let schema = build_tag_schema();
+        let row_groups = vec![
+            vec![build_record_batch_with_key(
+                schema.clone(),
+                vec![
+                    build_tag_row(b"a", 1, "host1"),
+                    build_tag_row(b"b", 2, "host2"),
+                ],
+            )],
+            vec![build_record_batch_with_key(
+                schema.clone(),
+                vec![build_tag_row(b"c", 3, "host3")],
+            )],
+        ];
+        let sst_filter = SstBloomFilter::build(&schema, &row_groups);
+        assert_eq!(2, sst_filter.row_group_filters().len());
+
+        // Encode to pb and decode back.
+        let decoded = SstBloomFilter::new(
+            sst_filter
+                .clone()
+                .into_row_group_filters()
+                .into_iter()
+                .map(|f| RowGroupBloomFilter::from(RowGroupBloomFilterPb::from(f)))
+                .collect(),
+        );
+        assert_eq!(sst_filter, decoded);
+
+        let predicate = new_predicate(vec![col("host").eq(lit("host3"))]);
+        assert_eq!(
+            vec![false, true],
+            sst_filter.filter_row_groups(&schema, &predicate, 2)
+        );
+
+        let predicate = new_predicate(vec![
+            col("host").in_list(vec![lit("host1"), lit("host9")], false)
+        ]);
+        assert_eq!(
+            vec![true, false],
+            sst_filter.filter_row_groups(&schema, &predicate, 2)
+        );
+
+        // Exprs on columns without filter are ignored.
+        let predicate = new_predicate(vec![col("key").eq(lit("x"))]);
+        assert_eq!(
+            vec![true, true],
+            sst_filter.filter_row_groups(&schema, &predicate, 2)
+        );
+
+        // All row groups are read if the sst has no filter.
+        let predicate = new_predicate(vec![col("host").eq(lit("host9"))]);
+        assert_eq!(
+            vec![true, true],
+            SstBloomFilter::default().filter_row_groups(&schema, &predicate, 2)
+        );
+    }
+}
@@ -6,7 +6,10 @@ use async_trait::async_trait;
 use common_types::{record_batch::RecordBatchWithKey, request_id::RequestId};
 use futures::Stream;
 
-use crate::sst::file::SstMetaData;
+use crate::{
+    sst::{column_stats::ColumnStats, file::SstMetaData},
+    tag_index::TagIndex,
+};
 
 pub mod error {
     use common_util::define_result;
@@ -44,6 +47,11 @@ pub mod error {
         ReadData {
             source: Box<dyn std::error::Error + Send + Sync>,
         },
+
+        #[snafu(display("Failed to build column stats, err:{}", source))]
+        BuildColumnStats {
+            source: crate::sst::column_stats::Error,
+        },
     }
 
     define_result!(Error);
@@ -56,10 +64,14 @@ pub type RecordBatchStreamItem =
 // TODO(yingwen): SstReader also has a RecordBatchStream, can we use same type?
 pub type RecordBatchStream = Box<dyn Stream<Item = RecordBatchStreamItem> + Send + Unpin>;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub struct SstInfo {
     pub file_size: usize,
     pub row_num: usize,
+    /// Stats of the columns, in the order of the columns in the schema
+    pub column_stats: Vec<ColumnStats>,
+    /// Inverted index of the tag columns, None if it is not built
+    pub tag_index: Option<TagIndex>,
 }
 
 /// The builder for sst.
@@ -0,0 +1,265 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Statistics of the columns in the sst
+
+use std::fmt;
+
+use common_types::{
+    datum::{Datum, DatumKind},
+    record_batch::RecordBatchWithKey,
+};
+use common_util::{
+    codec::{
+        compact::{MemCompactDecoder, MemCompactEncoder},
+        DecodeTo, Encoder,
+    },
+    define_result,
+};
+use hyperloglog::HyperLogLog;
+use proto::sst::ColumnStats as ColumnStatsPb;
+use snafu::{ResultExt, Snafu};
+
+/// Error rate of the distinct sketch, which takes 256 bytes per column.
+const HLL_ERROR_RATE: f64 = 0.065;
+/// All the sketches must be created with the same hash key to be mergeable.
+const HLL_KEY: u128 = 0;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to decode value of column stats, err:{}", source))]
+    DecodeValue {
+        source: common_util::codec::compact::Error,
+    },
+
+    #[snafu(display("Failed to encode distinct sketch, err:{}", source))]
+    EncodeSketch { source: bincode::Error },
+
+    #[snafu(display("Failed to decode distinct sketch, err:{}", source))]
+    DecodeSketch { source: bincode::Error },
+}
+
+define_result!(Error);
+
+/// Statistics of a column in the sst.
+#[derive(Clone, PartialEq)]
+pub struct ColumnStats {
+    /// Number of the null values
+    pub null_count: u64,
+    /// Min value of the column, Null if all the values are null
+    pub min_value: Datum,
+    /// Max value of the column, Null if all the values are null
+    pub max_value: Datum,
+    /// Serialized HyperLogLog to estimate the number of distinct values
+    pub distinct_sketch: Vec<u8>,
+}
+
+impl ColumnStats {
+    /// Estimate the number of distinct values of the column.
+    pub fn distinct_count(&self) -> Result<u64> {
+        let hll = decode_sketch(&self.distinct_sketch)?;
+
+        Ok(hll.len().round() as u64)
+    }
+
+    /// Build the stats from pb, the `kind` is the data type of the column.
+    pub fn from_pb(src: ColumnStatsPb, kind: &DatumKind) -> Result<Self> {
+        Ok(Self {
+            null_count: src.null_count,
+            min_value: decode_datum(&src.min_value, kind)?,
+            max_value: decode_datum(&src.max_value, kind)?,
+            distinct_sketch: src.distinct_sketch,
+        })
+    }
+}
+
+// The sketch is omitted as it is meaningless to print.
+impl fmt::Debug for ColumnStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ColumnStats")
+            .field("null_count", &self.null_count)
+            .field("min_value", &self.min_value)
+            .field("max_value", &self.max_value)
+            .field("distinct_sketch_len", &self.distinct_sketch.len())
+            .finish()
+    }
+}
+
+impl From<ColumnStats> for ColumnStatsPb {
+    fn from(src: ColumnStats) -> Self {
+        let mut target = ColumnStatsPb::new();
+        target.set_null_count(src.null_count);
+        target.set_min_value(encode_datum(&src.min_value));
+        target.set_max_value(encode_datum(&src.max_value));
+        target.set_distinct_sketch(src.distinct_sketch);
+
+        target
+    }
+}
+
+/// Estimate the number of distinct values of a column by merging the sketches
+/// of the column in multiple ssts.
+pub fn merge_distinct_count<'a>(stats: impl Iterator<Item = &'a ColumnStats>) -> Result<u64> {
+    let mut merged = new_sketch();
+    for column_stats in stats {
+        let hll = decode_sketch(&column_stats.distinct_sketch)?;
+        merged.merge(&hll);
+    }
+
+    Ok(merged.len().round() as u64)
+}
+
+/// Collects the [ColumnStats] of every column of the rows written to the sst.
+pub struct ColumnStatsCollector {
+    columns: Vec<ColumnCollector>,
+    value_buf: Vec<u8>,
+}
+
+impl ColumnStatsCollector {
+    pub fn new(num_columns: usize) -> Self {
+        Self {
+            columns: (0..num_columns)
+                .map(|_| ColumnCollector::default())
+                .collect(),
+            value_buf: Vec::new(),
+        }
+    }
+
+    pub fn collect(&mut self, record_batch: &RecordBatchWithKey) {
+        for (col_idx, column) in self.columns.iter_mut().enumerate() {
+            let column_block = record_batch.column(col_idx);
+            for row_idx in 0..record_batch.num_rows() {
+                let datum = column_block.datum(row_idx);
+                column.update(datum, &mut self.value_buf);
+            }
+        }
+    }
+
+    pub fn finish(self) -> Result<Vec<ColumnStats>> {
+        self.columns
+            .into_iter()
+            .map(ColumnCollector::finish)
+            .collect()
+    }
+}
+
+struct ColumnCollector {
+    null_count: u64,
+    min_value: Datum,
+    max_value: Datum,
+    hll: HyperLogLog,
+}
+
+impl Default for ColumnCollector {
+    fn default() -> Self {
+        Self {
+            null_count: 0,
+            min_value: Datum::Null,
+            max_value: Datum::Null,
+            hll: new_sketch(),
+        }
+    }
+}
+
+impl ColumnCollector {
+    fn update(&mut self, datum: Datum, value_buf: &mut Vec<u8>) {
+        if datum.is_null() {
+            self.null_count += 1;
+            return;
+        }
+
+        // Insert the encoded value so all kinds of datum can be hashed.
+        value_buf.clear();
+        MemCompactEncoder
+            .encode(value_buf, &datum)
+            .expect("Should encode datum into the buffer successfully");
+        self.hll.insert(&*value_buf);
+
+        if self.min_value.is_null() || datum < self.min_value {
+            self.min_value = datum.clone();
+        }
+        if self.max_value.is_null() || datum > self.max_value {
+            self.max_value = datum;
+        }
+    }
+
+    fn finish(self) -> Result<ColumnStats> {
+        let distinct_sketch = bincode::serialize(&self.hll).context(EncodeSketch)?;
+
+        Ok(ColumnStats {
+            null_count: self.null_count,
+            min_value: self.min_value,
+            max_value: self.max_value,
+            distinct_sketch,
+        })
+    }
+}
+
+#[inline]
+fn new_sketch() -> HyperLogLog {
+    HyperLogLog::new_deterministic(HLL_ERROR_RATE, HLL_KEY)
+}
+
+fn decode_sketch(buf: &[u8]) -> Result<HyperLogLog> {
+    bincode::deserialize(buf).context(DecodeSketch)
+}
+
+fn encode_datum(datum: &Datum) -> Vec<u8> {
+    let mut buf = Vec::new();
+    MemCompactEncoder
+        .encode(&mut buf, datum)
+        .expect("Should encode datum into the buffer successfully");
+
+    buf
+}
+
+fn decode_datum(mut buf: &[u8], kind: &DatumKind) -> Result<Datum> {
+    let mut datum = Datum::empty(kind);
+    MemCompactDecoder
+        .decode_to(&mut buf, &mut datum)
+        .context(DecodeValue)?;
+
+    Ok(datum)
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::tests::{build_row, build_schema};
+
+    use super::*;
+    use crate::row_iter::tests::build_record_batch_with_key;
+
+    #[test]
+    fn test_collect_column_stats() {
+        let schema = build_schema();
+        let rows = vec![
+            build_row(b"a", 1000, 10.0, "v1"),
+            build_row(b"b", 1001, 12.0, "v2"),
+            build_row(b"c", 1002, 11.0, "v2"),
+        ];
+        let batch = build_record_batch_with_key(schema.clone(), rows);
+
+        let mut collector = ColumnStatsCollector::new(schema.num_columns());
+        collector.collect(&batch);
+        let stats = collector.finish().unwrap();
+        assert_eq!(schema.num_columns(), stats.len());
+
+        let value_idx = schema.index_of("field1").unwrap();
+        let value_stats = &stats[value_idx];
+        assert_eq!(0, value_stats.null_count);
+        assert_eq!(Datum::Double(10.0), value_stats.min_value);
+        assert_eq!(Datum::Double(12.0), value_stats.max_value);
+        assert_eq!(3, value_stats.distinct_count().unwrap());
+
+        let string_idx = schema.index_of("field2").unwrap();
+        assert_eq!(2, stats[string_idx].distinct_count().unwrap());
+        assert_eq!(
+            2,
+            merge_distinct_count(vec![&stats[string_idx]; 2].into_iter()).unwrap()
+        );
+
+        // Encode to pb and decode back.
+        let kind = schema.column(value_idx).data_type;
+        let pb = ColumnStatsPb::from(value_stats.clone());
+        assert_eq!(*value_stats, ColumnStats::from_pb(pb, &kind).unwrap());
+    }
+}
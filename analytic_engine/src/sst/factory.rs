@@ -17,6 +17,7 @@ use crate::{
         reader::SstReader,
     },
     table_options::Compression,
+    tag_index::TsidSetRef,
 };
 
 pub trait Factory: Clone {
@@ -50,6 +51,11 @@ pub struct SstReaderOptions {
     pub meta_cache: Option<MetaCacheRef>,
     pub data_cache: Option<DataCacheRef>,
     pub runtime: Arc<Runtime>,
+    /// Tsids of the series to read, None to read all the series.
+    ///
+    /// Ignored by the sst without tag index, as the set is looked up from the
+    /// tag indexes and the series not indexed may be missing.
+    pub tsid_filter: Option<TsidSetRef>,
 }
 
 #[derive(Debug, Clone)]
@@ -57,6 +63,8 @@ pub struct SstBuilderOptions {
     pub sst_type: SstType,
     pub num_rows_per_row_group: usize,
     pub compression: Compression,
+    /// Whether to build the inverted index of the tag columns
+    pub build_tag_index: bool,
 }
 
 #[derive(Debug, Clone)]
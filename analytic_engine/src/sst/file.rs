@@ -5,14 +5,14 @@
 use std::{
     borrow::Borrow,
     cmp,
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
     convert::TryFrom,
     fmt,
     fmt::Debug,
     hash::{Hash, Hasher},
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, RwLock,
     },
 };
 
@@ -29,7 +29,13 @@ use common_util::{
 };
 use log::{debug, error, info};
 use object_store::ObjectStore;
-use proto::{common::TimeRange as TimeRangePb, sst::SstMetaData as SstMetaDataPb};
+use proto::{
+    common::TimeRange as TimeRangePb,
+    sst::{
+        ColumnStats as ColumnStatsPb, RowGroupBloomFilter as RowGroupBloomFilterPb,
+        SstMetaData as SstMetaDataPb,
+    },
+};
 use snafu::{ResultExt, Snafu};
 use table_engine::table::TableId;
 use tokio::sync::{
@@ -37,7 +43,16 @@ use tokio::sync::{
     Mutex,
 };
 
-use crate::{space::SpaceId, sst::manager::FileId, table::sst_util};
+use crate::{
+    space::SpaceId,
+    sst::{
+        bloom_filter::{RowGroupBloomFilter, SstBloomFilter},
+        column_stats::ColumnStats,
+        manager::FileId,
+    },
+    table::sst_util,
+    tag_index::TagIndex,
+};
 
 /// Error of sst file.
 #[derive(Debug, Snafu)]
@@ -48,6 +63,11 @@ pub enum Error {
     #[snafu(display("Failed to convert table schema, err:{}", source))]
     ConvertTableSchema { source: common_types::schema::Error },
 
+    #[snafu(display("Failed to convert column stats, err:{}", source))]
+    ConvertColumnStats {
+        source: crate::sst::column_stats::Error,
+    },
+
     #[snafu(display("Failed to join purger, err:{}", source))]
     StopPurger { source: common_util::runtime::Error },
 }
@@ -83,11 +103,7 @@ impl LevelHandler {
     }
 
     pub fn pick_ssts(&self, time_range: TimeRange) -> Vec<FileHandle> {
-        if self.level == 0 {
-            self.files.files_by_time_range(time_range)
-        } else {
-            Vec::new()
-        }
+        self.files.files_by_time_range(time_range)
     }
 
     #[inline]
@@ -145,13 +161,21 @@ impl Hash for FileHandle {
 }
 
 impl FileHandle {
-    pub fn new(meta: FileMeta, purge_queue: FilePurgeQueue) -> Self {
+    pub fn new(mut meta: FileMeta, purge_queue: FilePurgeQueue) -> Self {
+        let tag_index = match meta.meta.tag_index.take() {
+            Some(v) => LazyTagIndex::Loaded(Some(Arc::new(v))),
+            // Only the ssts of the tables with tsid column have tag index.
+            None if meta.meta.schema.index_of_tsid().is_none() => LazyTagIndex::Loaded(None),
+            None => LazyTagIndex::Unloaded,
+        };
+
         Self {
             inner: Arc::new(FileHandleInner {
                 meta,
                 purge_queue,
                 being_compacted: AtomicBool::new(false),
                 metrics: SstMetrics::default(),
+                tag_index: RwLock::new(tag_index),
             }),
         }
     }
@@ -216,6 +240,55 @@ impl FileHandle {
         self.inner.meta.meta.size
     }
 
+    #[inline]
+    pub fn schema(&self) -> &Schema {
+        &self.inner.meta.meta.schema
+    }
+
+    /// Stats of the columns in the schema of the sst, empty if the sst is
+    /// built without column stats.
+    #[inline]
+    pub fn column_stats(&self) -> &[ColumnStats] {
+        &self.inner.meta.meta.column_stats
+    }
+
+    /// Inverted index of the tag columns, None if the sst is built without it
+    /// or the index is not loaded yet.
+    pub fn tag_index(&self) -> Option<Arc<TagIndex>> {
+        match &*self.inner.tag_index.read().unwrap() {
+            LazyTagIndex::Loaded(v) => v.clone(),
+            LazyTagIndex::Unloaded => None,
+        }
+    }
+
+    /// Whether the tag index needs to be loaded from the sst meta.
+    ///
+    /// The tag index is not persisted in the manifest, so it is unloaded
+    /// after the file is recovered from the manifest.
+    pub fn need_load_tag_index(&self) -> bool {
+        matches!(
+            &*self.inner.tag_index.read().unwrap(),
+            LazyTagIndex::Unloaded
+        )
+    }
+
+    /// Set the tag index loaded from the sst meta.
+    pub fn set_tag_index(&self, tag_index: Option<TagIndex>) {
+        *self.inner.tag_index.write().unwrap() = LazyTagIndex::Loaded(tag_index.map(Arc::new));
+    }
+
+    /// Whether the rows of the sst have been rolled up into the rollup table.
+    #[inline]
+    pub fn rolled_up(&self) -> bool {
+        self.inner.meta.meta.rolled_up
+    }
+
+    /// Ids of the source files rolled up into the sst.
+    #[inline]
+    pub fn rollup_source_files(&self) -> &[FileId] {
+        &self.inner.meta.meta.rollup_source_files
+    }
+
     #[inline]
     pub fn set_being_compacted(&self, value: bool) {
         self.inner.being_compacted.store(value, Ordering::Relaxed);
@@ -255,12 +328,19 @@ impl fmt::Debug for SstMetrics {
     }
 }
 
+/// Tag index of the sst loaded lazily.
+enum LazyTagIndex {
+    Unloaded,
+    Loaded(Option<Arc<TagIndex>>),
+}
+
 struct FileHandleInner {
     meta: FileMeta,
     purge_queue: FilePurgeQueue,
     /// The file is being compacting.
     being_compacted: AtomicBool,
     metrics: SstMetrics,
+    tag_index: RwLock<LazyTagIndex>,
 }
 
 impl Drop for FileHandleInner {
@@ -420,6 +500,17 @@ pub struct SstMetaData {
     pub size: u64,
     // total row number
     pub row_num: u64,
+    /// Stats of the columns, in the order of the columns in the schema
+    pub column_stats: Vec<ColumnStats>,
+    /// Bloom filters of the row groups, only stored in the sst file
+    pub bloom_filter: SstBloomFilter,
+    /// Inverted index of the tag columns, None if the sst is built without it
+    pub tag_index: Option<TagIndex>,
+    /// Whether the rows of the sst have been rolled up into the rollup table
+    pub rolled_up: bool,
+    /// Ids of the source files rolled up into the sst, only set for the ssts
+    /// of the rollup table
+    pub rollup_source_files: Vec<FileId>,
 }
 
 impl From<SstMetaData> for SstMetaDataPb {
@@ -433,6 +524,24 @@ impl From<SstMetaData> for SstMetaDataPb {
         target.set_schema(src.schema.into());
         target.set_size(src.size);
         target.set_row_num(src.row_num);
+        let column_stats: Vec<_> = src
+            .column_stats
+            .into_iter()
+            .map(ColumnStatsPb::from)
+            .collect();
+        target.column_stats = column_stats.into();
+        let bloom_filters: Vec<_> = src
+            .bloom_filter
+            .into_row_group_filters()
+            .into_iter()
+            .map(RowGroupBloomFilterPb::from)
+            .collect();
+        target.bloom_filters = bloom_filters.into();
+        if let Some(tag_index) = src.tag_index {
+            target.set_tag_index(tag_index.into());
+        }
+        target.set_rolled_up(src.rolled_up);
+        target.set_rollup_source_files(src.rollup_source_files);
 
         target
     }
@@ -444,6 +553,18 @@ impl TryFrom<SstMetaDataPb> for SstMetaData {
     fn try_from(mut src: SstMetaDataPb) -> Result<Self> {
         let time_range = TimeRange::try_from(src.take_time_range()).context(ConvertTimeRange)?;
         let schema = Schema::try_from(src.take_schema()).context(ConvertTableSchema)?;
+        let column_stats = column_stats_from_pb(src.take_column_stats().into_vec(), &schema)?;
+        let bloom_filter = SstBloomFilter::new(
+            src.take_bloom_filters()
+                .into_iter()
+                .map(RowGroupBloomFilter::from)
+                .collect(),
+        );
+        let tag_index = if src.has_tag_index() {
+            Some(TagIndex::from(src.take_tag_index()))
+        } else {
+            None
+        };
         Ok(Self {
             min_key: src.min_key.into(),
             max_key: src.max_key.into(),
@@ -452,10 +573,26 @@ impl TryFrom<SstMetaDataPb> for SstMetaData {
             schema,
             size: src.size,
             row_num: src.row_num,
+            column_stats,
+            bloom_filter,
+            tag_index,
+            rolled_up: src.rolled_up,
+            rollup_source_files: src.rollup_source_files,
         })
     }
 }
 
+/// Convert the column stats from pb, the stats are in the order of the columns
+/// in the `schema`.
+pub fn column_stats_from_pb(src: Vec<ColumnStatsPb>, schema: &Schema) -> Result<Vec<ColumnStats>> {
+    src.into_iter()
+        .zip(schema.columns())
+        .map(|(stats, column)| {
+            ColumnStats::from_pb(stats, &column.data_type).context(ConvertColumnStats)
+        })
+        .collect()
+}
+
 // Queue to store files to be deleted for a table.
 #[derive(Clone)]
 pub struct FilePurgeQueue {
@@ -609,8 +746,14 @@ impl FilePurger {
 
 /// Merge sst meta of given `files`, panic if `files` is empty.
 ///
-/// The size and row_num of the merged meta is initialized to 0.
+/// The size and row_num of the merged meta is initialized to 0, and the rollup
+/// source files of the `files` are merged.
 pub fn merge_sst_meta(files: &[FileHandle], schema: Schema) -> SstMetaData {
+    let mut rollup_source_files = BTreeSet::new();
+    for file in files {
+        rollup_source_files.extend(file.rollup_source_files().iter().copied());
+    }
+
     let mut min_key = files[0].min_key();
     let mut max_key = files[0].max_key();
     let mut time_range_start = files[0].time_range().inclusive_start();
@@ -633,9 +776,15 @@ pub fn merge_sst_meta(files: &[FileHandle], schema: Schema) -> SstMetaData {
         time_range: TimeRange::new(time_range_start, time_range_end).unwrap(),
         max_sequence,
         schema,
-        // we don't know file size and total row number yet
+        // we don't know file size, total row number, column stats, bloom
+        // filter and tag index yet
         size: 0,
         row_num: 0,
+        column_stats: Vec::new(),
+        bloom_filter: SstBloomFilter::default(),
+        tag_index: None,
+        rolled_up: false,
+        rollup_source_files: rollup_source_files.into_iter().collect(),
     }
 }
 
@@ -691,6 +840,11 @@ pub mod tests {
                 schema: self.schema.clone(),
                 size: 0,
                 row_num: 0,
+                column_stats: Vec::new(),
+                bloom_filter: SstBloomFilter::default(),
+                tag_index: None,
+                rolled_up: false,
+                rollup_source_files: Vec::new(),
             }
         }
     }
@@ -2,7 +2,9 @@
 
 //! SST (Sorted String Table) file
 
+pub mod bloom_filter;
 pub mod builder;
+pub mod column_stats;
 pub mod factory;
 pub mod file;
 pub mod manager;
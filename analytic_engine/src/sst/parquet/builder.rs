@@ -19,21 +19,32 @@ use arrow_deps::{
         arrow::ArrowWriter,
         file::{properties::WriterProperties, writer::TryClone},
     },
+    thrift::protocol::{TCompactOutputProtocol, TOutputProtocol},
 };
 use async_trait::async_trait;
-use common_types::{bytes::BufMut, request_id::RequestId};
+use common_types::{bytes::BufMut, record_batch::RecordBatchWithKey, request_id::RequestId};
 use futures::{AsyncRead, AsyncReadExt};
 use log::debug;
 use object_store::{ObjectStore, Path};
 use snafu::ResultExt;
 
-use crate::sst::{
-    builder::{RecordBatchStream, SstBuilder, *},
-    factory::SstBuilderOptions,
-    file::SstMetaData,
-    parquet::encoding,
+use crate::{
+    sst::{
+        bloom_filter::{RowGroupBloomFilter, SstBloomFilter},
+        builder::{RecordBatchStream, SstBuilder, *},
+        column_stats::ColumnStatsCollector,
+        factory::SstBuilderOptions,
+        file::SstMetaData,
+        parquet::encoding,
+    },
+    tag_index::TagIndex,
 };
 
+/// Size of the length of the file meta data and the magic at the end of the
+/// parquet file.
+const PARQUET_FOOTER_TAIL_SIZE: usize = 8;
+const PARQUET_MAGIC: &[u8; 4] = b"PAR1";
+
 /// The implementation of sst based on parquet and object storage.
 #[derive(Debug)]
 pub struct ParquetSstBuilder<'a, S: ObjectStore> {
@@ -44,6 +55,7 @@ pub struct ParquetSstBuilder<'a, S: ObjectStore> {
     /// Max row group size.
     num_rows_per_row_group: usize,
     compression: Compression,
+    build_tag_index: bool,
 }
 
 impl<'a, S: ObjectStore> ParquetSstBuilder<'a, S> {
@@ -53,6 +65,7 @@ impl<'a, S: ObjectStore> ParquetSstBuilder<'a, S> {
             storage,
             num_rows_per_row_group: options.num_rows_per_row_group,
             compression: options.compression.into(),
+            build_tag_index: options.build_tag_index,
         }
     }
 }
@@ -110,6 +123,11 @@ impl EncodingBuffer {
         let mut inner = self.inner.lock().unwrap();
         inner.read(read_buf)
     }
+
+    fn replace_footer(&self, file_meta_data: &[u8]) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.replace_footer(file_meta_data)
+    }
 }
 
 /// The underlying buffer implementing [ParquetWriter].
@@ -181,6 +199,28 @@ impl EncodingBufferInner {
         read_len
     }
 
+    /// Replace the footer written by the [ArrowWriter] with a footer of the
+    /// `file_meta_data` encoded by thrift.
+    ///
+    /// The footer (the encoded file meta data, its length and the magic) is
+    /// written at once when closing the writer, so it is still in the buffer.
+    fn replace_footer(&mut self, file_meta_data: &[u8]) {
+        let buf_len = self.buf.len();
+        assert!(buf_len >= self.read_offset + PARQUET_FOOTER_TAIL_SIZE);
+        let mut len = [0; 4];
+        len.copy_from_slice(&self.buf[buf_len - PARQUET_FOOTER_TAIL_SIZE..buf_len - 4]);
+        let footer_size = u32::from_le_bytes(len) as usize + PARQUET_FOOTER_TAIL_SIZE;
+        assert!(buf_len >= self.read_offset + footer_size);
+
+        self.buf.truncate(buf_len - footer_size);
+        self.buf.extend_from_slice(file_meta_data);
+        self.buf
+            .extend_from_slice(&(file_meta_data.len() as u32).to_le_bytes());
+        self.buf.extend_from_slice(PARQUET_MAGIC);
+        self.bytes_written =
+            self.bytes_written - footer_size + file_meta_data.len() + PARQUET_FOOTER_TAIL_SIZE;
+    }
+
     /// Advance the `self.offset` by `len`.
     ///
     /// Caller should ensures the advanced offset wont exceed `self.buf.len()`.
@@ -202,38 +242,115 @@ struct RecordBytesReader {
     compression: Compression,
     meta_data: SstMetaData,
     total_row_num: Arc<AtomicUsize>,
-    arrow_record_batch_vec: Vec<ArrowRecordBatch>,
+    column_stats_collector: ColumnStatsCollector,
+    /// Inverted index of the tag columns, None if it is not built.
+    tag_index: Option<TagIndex>,
+    /// Bloom filters of the row groups written.
+    row_group_filters: Vec<RowGroupBloomFilter>,
+    record_batches: Vec<RecordBatchWithKey>,
     // Whether the underlying `record_stream` is finished
     stream_finished: bool,
 
     fetched_row_num: usize,
 }
 
-/// Build the write properties containing the sst meta data.
+impl RecordBytesReader {
+    /// Build the bloom filters of the row groups the `record_batches` are
+    /// written into, the [ArrowWriter] splits the rows written at once into
+    /// row groups of `num_rows_per_row_group` rows.
+    fn build_bloom_filters(&mut self, record_batches: &[RecordBatchWithKey]) {
+        // The filters are empty if the schema has no tag column.
+        if !self.meta_data.schema.columns().iter().any(|c| c.is_tag) {
+            return;
+        }
+
+        let mut row_group = Vec::new();
+        let mut row_group_num = 0;
+        for record_batch in record_batches {
+            let mut offset = 0;
+            while offset < record_batch.num_rows() {
+                let len = (self.num_rows_per_row_group - row_group_num)
+                    .min(record_batch.num_rows() - offset);
+                row_group.push(record_batch.slice(offset, len));
+                row_group_num += len;
+                offset += len;
+
+                if row_group_num >= self.num_rows_per_row_group {
+                    self.row_group_filters.push(RowGroupBloomFilter::build(
+                        &self.meta_data.schema,
+                        &row_group,
+                    ));
+                    row_group.clear();
+                    row_group_num = 0;
+                }
+            }
+        }
+
+        if !row_group.is_empty() {
+            self.row_group_filters.push(RowGroupBloomFilter::build(
+                &self.meta_data.schema,
+                &row_group,
+            ));
+        }
+    }
+
+    /// Close the writer and rewrite the footer with the sst meta data, as the
+    /// bloom filters and the tag index are only known after all the rows are
+    /// written.
+    fn close_writer(&mut self) -> Result<()> {
+        let arrow_writer = match self.arrow_writer.get_mut().unwrap() {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        let mut file_meta_data = arrow_writer
+            .close()
+            .map_err(|e| Box::new(e) as _)
+            .context(EncodeRecordBatch)?;
+
+        self.meta_data.bloom_filter =
+            SstBloomFilter::new(std::mem::take(&mut self.row_group_filters));
+        self.meta_data.tag_index = self.tag_index.clone();
+        let meta_data_kv = encoding::encode_sst_meta_data(self.meta_data.clone())
+            .map_err(|e| Box::new(e) as _)
+            .context(EncodeMetaData)?;
+        file_meta_data.key_value_metadata = Some(vec![meta_data_kv]);
+
+        let mut footer = Vec::new();
+        {
+            let mut protocol = TCompactOutputProtocol::new(&mut footer);
+            file_meta_data
+                .write_to_out_protocol(&mut protocol)
+                .map_err(|e| Box::new(e) as _)
+                .context(EncodeMetaData)?;
+            protocol
+                .flush()
+                .map_err(|e| Box::new(e) as _)
+                .context(EncodeMetaData)?;
+        }
+        self.encoding_buffer.replace_footer(&footer);
+
+        Ok(())
+    }
+}
+
+/// Build the write properties, the sst meta data is written when closing the
+/// writer.
 fn build_write_properties(
     num_rows_per_row_group: usize,
     compression: Compression,
-    meta_data: &SstMetaData,
-) -> Result<WriterProperties> {
-    let meta_data_kv = encoding::encode_sst_meta_data(meta_data.clone())
-        .map_err(|e| Box::new(e) as _)
-        .context(EncodeMetaData)?;
-
-    Ok(WriterProperties::builder()
-        .set_key_value_metadata(Some(vec![meta_data_kv]))
+) -> WriterProperties {
+    WriterProperties::builder()
         .set_max_row_group_size(num_rows_per_row_group)
         .set_compression(compression)
-        .build())
+        .build()
 }
 
 /// Encode the record batch with [ArrowWriter] and the encoded contents is
 /// written to the [EncodingBuffer].
-// TODO(xikai): too many parameters
 fn encode_record_batch(
     arrow_writer: &mut Option<ArrowWriter<EncodingBuffer>>,
     num_rows_per_row_group: usize,
     compression: Compression,
-    meta_data: &SstMetaData,
     mem_buf_writer: EncodingBuffer,
     arrow_record_batch_vec: Vec<ArrowRecordBatch>,
 ) -> Result<usize> {
@@ -245,7 +362,7 @@ fn encode_record_batch(
 
     // create arrow writer if not exist
     if arrow_writer.is_none() {
-        let write_props = build_write_properties(num_rows_per_row_group, compression, meta_data)?;
+        let write_props = build_write_properties(num_rows_per_row_group, compression);
         let writer = ArrowWriter::try_new(mem_buf_writer, arrow_schema.clone(), Some(write_props))
             .map_err(|e| Box::new(e) as _)
             .context(EncodeRecordBatch)?;
@@ -266,17 +383,6 @@ fn encode_record_batch(
     Ok(record_batch.num_rows())
 }
 
-fn close_writer(arrow_writer: &mut Option<ArrowWriter<EncodingBuffer>>) -> Result<()> {
-    if let Some(arrow_writer) = arrow_writer {
-        arrow_writer
-            .close()
-            .map_err(|e| Box::new(e) as _)
-            .context(EncodeRecordBatch)?;
-    }
-
-    Ok(())
-}
-
 impl AsyncRead for RecordBytesReader {
     fn poll_read(
         self: Pin<&mut Self>,
@@ -309,9 +415,11 @@ impl AsyncRead for RecordBytesReader {
                             );
 
                             reader.fetched_row_num += record_batch.num_rows();
-                            reader
-                                .arrow_record_batch_vec
-                                .push(record_batch.into_record_batch().into_arrow_record_batch());
+                            reader.column_stats_collector.collect(&record_batch);
+                            if let Some(tag_index) = &mut reader.tag_index {
+                                tag_index.add_record_batch(&reader.meta_data.schema, &record_batch);
+                            }
+                            reader.record_batches.push(record_batch);
                         }
                         Err(e) => {
                             return Poll::Ready(Err(std::io::Error::new(
@@ -325,7 +433,7 @@ impl AsyncRead for RecordBytesReader {
                         debug!(
                             "Record stream finished, request_id:{}, batch_len:{}, fetched_row_num:{}, num_rows_per_row_group:{}",
                             reader.request_id,
-                            reader.arrow_record_batch_vec.len(),
+                            reader.record_batches.len(),
                             reader.fetched_row_num,
                             reader.num_rows_per_row_group,
                         );
@@ -339,13 +447,18 @@ impl AsyncRead for RecordBytesReader {
 
         // Reset fetched row num.
         reader.fetched_row_num = 0;
+        let record_batches = std::mem::take(&mut reader.record_batches);
+        reader.build_bloom_filters(&record_batches);
+        let arrow_record_batch_vec = record_batches
+            .into_iter()
+            .map(|record_batch| record_batch.into_record_batch().into_arrow_record_batch())
+            .collect();
         match encode_record_batch(
             reader.arrow_writer.get_mut().unwrap(),
             reader.num_rows_per_row_group,
             reader.compression,
-            &reader.meta_data,
             reader.encoding_buffer.clone(),
-            std::mem::take(&mut reader.arrow_record_batch_vec),
+            arrow_record_batch_vec,
         ) {
             Err(e) => return Poll::Ready(Err(std::io::Error::new(std::io::ErrorKind::Other, e))),
             Ok(row_num) => {
@@ -354,7 +467,7 @@ impl AsyncRead for RecordBytesReader {
         }
 
         if reader.stream_finished {
-            if let Err(e) = close_writer(reader.arrow_writer.get_mut().unwrap()) {
+            if let Err(e) = reader.close_writer() {
                 return Poll::Ready(Err(std::io::Error::new(std::io::ErrorKind::Other, e)));
             }
         }
@@ -385,7 +498,10 @@ impl<'a, S: ObjectStore> SstBuilder for ParquetSstBuilder<'a, S> {
             num_rows_per_row_group: self.num_rows_per_row_group,
             compression: self.compression,
             total_row_num: total_row_num.clone(),
-            arrow_record_batch_vec: Vec::new(),
+            column_stats_collector: ColumnStatsCollector::new(meta.schema.num_columns()),
+            tag_index: self.build_tag_index.then(TagIndex::default),
+            row_group_filters: Vec::new(),
+            record_batches: Vec::new(),
             // TODO(xikai): should we avoid this clone?
             meta_data: meta.to_owned(),
             stream_finished: false,
@@ -399,7 +515,13 @@ impl<'a, S: ObjectStore> SstBuilder for ParquetSstBuilder<'a, S> {
             .await
             .map_err(|e| Box::new(e) as _)
             .context(ReadData)?;
-        drop(reader);
+        // The column stats and tag index are returned by the sst info and kept in
+        // the manifest.
+        let tag_index = reader.tag_index.take();
+        let column_stats = reader
+            .column_stats_collector
+            .finish()
+            .context(BuildColumnStats)?;
 
         self.storage
             .put(self.path, bytes.into())
@@ -411,6 +533,8 @@ impl<'a, S: ObjectStore> SstBuilder for ParquetSstBuilder<'a, S> {
         Ok(SstInfo {
             file_size: file_head.size,
             row_num: total_row_num.load(Ordering::Relaxed),
+            column_stats,
+            tag_index,
         })
     }
 }
@@ -418,8 +542,10 @@ impl<'a, S: ObjectStore> SstBuilder for ParquetSstBuilder<'a, S> {
 #[cfg(test)]
 mod tests {
 
+    use arrow_deps::datafusion::logical_plan::{col, lit};
     use common_types::{
         bytes::Bytes,
+        datum::Datum,
         projected_schema::ProjectedSchema,
         tests::{build_row, build_schema},
         time::{TimeRange, Timestamp},
@@ -434,6 +560,7 @@ mod tests {
     use crate::{
         row_iter::tests::build_record_batch_with_key,
         sst::{
+            bloom_filter::tests::{build_tag_row, build_tag_schema},
             factory::{Factory, FactoryImpl, SstBuilderOptions, SstReaderOptions, SstType},
             parquet::reader::ParquetSstReader,
             reader::{tests::check_stream, SstReader},
@@ -453,6 +580,120 @@ mod tests {
         parquet_write_and_then_read_back(runtime, 5, vec![5, 1, 5, 1, 3]);
     }
 
+    #[test]
+    fn test_parquet_build_bloom_filter_and_tag_index() {
+        let runtime = Arc::new(runtime::Builder::default().build().unwrap());
+        runtime.block_on(async {
+            let sst_builder_options = SstBuilderOptions {
+                sst_type: SstType::Parquet,
+                num_rows_per_row_group: 2,
+                compression: table_options::Compression::Uncompressed,
+                build_tag_index: true,
+            };
+
+            let dir = tempdir().unwrap();
+            let store = LocalFileSystem::new_with_prefix(dir.path()).unwrap();
+            let sst_file_path = Path::from("data.par");
+
+            let schema = build_tag_schema();
+            let sst_meta = SstMetaData {
+                min_key: Bytes::from_static(b"a"),
+                max_key: Bytes::from_static(b"f"),
+                time_range: TimeRange::new_unchecked(Timestamp::new(1), Timestamp::new(7)),
+                max_sequence: 200,
+                schema: schema.clone(),
+                size: 0,
+                row_num: 0,
+                column_stats: Vec::new(),
+                bloom_filter: SstBloomFilter::default(),
+                tag_index: None,
+                rolled_up: false,
+                rollup_source_files: Vec::new(),
+            };
+            let record_batches = vec![
+                build_record_batch_with_key(
+                    schema.clone(),
+                    vec![
+                        build_tag_row(b"a", 1, "host1"),
+                        build_tag_row(b"b", 2, "host1"),
+                        build_tag_row(b"c", 3, "host2"),
+                    ],
+                ),
+                build_record_batch_with_key(
+                    schema.clone(),
+                    vec![
+                        build_tag_row(b"d", 4, "host3"),
+                        build_tag_row(b"e", 5, "host3"),
+                        build_tag_row(b"f", 6, "host3"),
+                    ],
+                ),
+            ];
+            let record_batch_stream = Box::new(stream::iter(record_batches.into_iter().map(Ok)));
+
+            let mut builder = FactoryImpl
+                .new_sst_builder(&sst_builder_options, &sst_file_path, &store)
+                .unwrap();
+            let sst_info = builder
+                .build(RequestId::next_id(), &sst_meta, record_batch_stream)
+                .await
+                .unwrap();
+            assert_eq!(6, sst_info.row_num);
+            assert!(sst_info.tag_index.is_some());
+
+            let sst_reader_options = SstReaderOptions {
+                sst_type: SstType::Parquet,
+                read_batch_row_num: 5,
+                reverse: false,
+                projected_schema: ProjectedSchema::no_projection(schema.clone()),
+                predicate: Arc::new(Predicate::new(TimeRange::min_to_max())),
+                meta_cache: None,
+                data_cache: None,
+                runtime: runtime.clone(),
+                tsid_filter: None,
+            };
+            let mut reader = ParquetSstReader::new(&sst_file_path, &store, &sst_reader_options);
+            let row_groups: Vec<_> = reader
+                .row_groups()
+                .await
+                .iter()
+                .map(|g| g.num_rows())
+                .collect();
+            assert_eq!(vec![2, 1, 2, 1], row_groups);
+
+            // The meta data in the footer is rewritten with the bloom filters of all the
+            // row groups and the tag index.
+            let meta_data = reader.meta_data().await.unwrap().clone();
+            assert_eq!(sst_info.tag_index, meta_data.tag_index);
+            let bloom_filter = &meta_data.bloom_filter;
+            assert_eq!(row_groups.len(), bloom_filter.row_group_filters().len());
+            let predicate = Predicate {
+                exprs: vec![col("host").eq(lit("host2"))],
+                time_range: TimeRange::min_to_max(),
+            };
+            assert!(bloom_filter.filter_row_groups(&schema, &predicate, row_groups.len())[1]);
+            let predicate = Predicate {
+                exprs: vec![col("host").eq(lit("host3"))],
+                time_range: TimeRange::min_to_max(),
+            };
+            let selected = bloom_filter.filter_row_groups(&schema, &predicate, row_groups.len());
+            assert!(selected[2] && selected[3]);
+
+            let mut stream = reader.read().await.unwrap();
+            check_stream(
+                &mut stream,
+                vec![
+                    build_tag_row(b"a", 1, "host1"),
+                    build_tag_row(b"b", 2, "host1"),
+                    build_tag_row(b"c", 3, "host2"),
+                    build_tag_row(b"d", 4, "host3"),
+                    build_tag_row(b"e", 5, "host3"),
+                    build_tag_row(b"f", 6, "host3"),
+                ],
+            )
+            .await;
+        });
+    }
+
     fn parquet_write_and_then_read_back(
         runtime: Arc<Runtime>,
         num_rows_per_row_group: usize,
@@ -464,6 +705,7 @@ mod tests {
                 sst_type: SstType::Parquet,
                 num_rows_per_row_group,
                 compression: table_options::Compression::Uncompressed,
+                build_tag_index: false,
             };
 
             let dir = tempdir().unwrap();
@@ -481,6 +723,11 @@ mod tests {
                 schema: schema.clone(),
                 size: 10,
                 row_num: 2,
+                column_stats: Vec::new(),
+                bloom_filter: SstBloomFilter::default(),
+                tag_index: None,
+                rolled_up: false,
+                rollup_source_files: Vec::new(),
             };
 
             let mut counter = 10;
@@ -513,6 +760,16 @@ mod tests {
                 .unwrap();
 
             assert_eq!(15, sst_info.row_num);
+            assert_eq!(schema.num_columns(), sst_info.column_stats.len());
+            let key_stats = &sst_info.column_stats[0];
+            assert_eq!(
+                Datum::Varbinary(Bytes::from_static(b"a")),
+                key_stats.min_value
+            );
+            assert_eq!(
+                Datum::Varbinary(Bytes::from_static(b"c")),
+                key_stats.max_value
+            );
 
             // read sst back to test
             let sst_reader_options = SstReaderOptions {
@@ -524,6 +781,7 @@ mod tests {
                 meta_cache: None,
                 data_cache: None,
                 runtime: runtime.clone(),
+                tsid_filter: None,
             };
 
             let mut reader = ParquetSstReader::new(&sst_file_path, &store, &sst_reader_options);
@@ -3,6 +3,7 @@
 //! Sst reader implementation based on parquet.
 
 use std::{
+    ops::Range,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
@@ -14,93 +15,177 @@ use arrow_deps::{
     arrow::{error::Result as ArrowResult, record_batch::RecordBatch},
     parquet::{
         arrow::{ArrowReader, ParquetFileArrowReader},
-        file::{metadata::RowGroupMetaData, reader::FileReader},
+        file::{
+            footer,
+            metadata::{ParquetMetaData, RowGroupMetaData},
+            reader::FileReader,
+        },
     },
 };
 use async_trait::async_trait;
 use common_types::{
+    bytes::Bytes,
     projected_schema::{ProjectedSchema, RowProjector},
     record_batch::{ArrowRecordBatchProjector, RecordBatchWithKey},
-    schema::Schema,
 };
 use common_util::runtime::Runtime;
-use futures::Stream;
+use futures::{future, Stream};
 use log::{debug, error, trace};
 use object_store::{ObjectStore, Path};
 use parquet::{
     reverse_reader::Builder as ReverseRecordBatchReaderBuilder, CachableSerializedFileReader,
-    DataCacheRef, MetaCacheRef,
+    DataCacheRef, MetaCacheRef, RangedChunkReader,
 };
 use snafu::{ensure, OptionExt, ResultExt};
 use table_engine::predicate::PredicateRef;
 use tokio::sync::mpsc::{self, Receiver, Sender};
 
-use crate::sst::{
-    factory::SstReaderOptions,
-    file::SstMetaData,
-    parquet::encoding,
-    reader::{error::*, SstReader},
+use crate::{
+    sst::{
+        factory::SstReaderOptions,
+        file::SstMetaData,
+        parquet::encoding,
+        reader::{error::*, SstReader},
+    },
+    tag_index::{self, TsidSetRef},
 };
 
 const DEFAULT_CHANNEL_CAP: usize = 1000;
-
-pub async fn read_sst_meta<S: ObjectStore>(
+/// Size of the bytes read from the end of the sst at first, which is expected
+/// to cover the whole footer in most cases.
+const FOOTER_PREFETCH_SIZE: usize = 64 * 1024;
+/// Size of the metadata length and the magic at the end of the parquet file.
+const FOOTER_TAIL_SIZE: usize = 8;
+/// Column chunks whose gap is not larger than this size are fetched by one
+/// ranged read, so reading a few more bytes saves a round trip to the storage.
+const COALESCE_GAP_SIZE: usize = 64 * 1024;
+
+async fn read_range<S: ObjectStore>(
     storage: &S,
     path: &Path,
-    meta_cache: &Option<MetaCacheRef>,
-    data_cache: &Option<DataCacheRef>,
-) -> Result<(CachableSerializedFileReader<SliceableCursor>, SstMetaData)> {
-    let get_result = storage
-        .get(path)
+    range: Range<usize>,
+) -> Result<Bytes> {
+    storage
+        .get_range(path, range)
+        .await
+        .map_err(|e| Box::new(e) as _)
+        .with_context(|| ReadPersist {
+            path: path.to_string(),
+        })
+}
+
+async fn read_file_size<S: ObjectStore>(storage: &S, path: &Path) -> Result<usize> {
+    let object_meta = storage
+        .head(path)
         .await
         .map_err(|e| Box::new(e) as _)
         .with_context(|| ReadPersist {
             path: path.to_string(),
         })?;
-    // TODO: The `ChunkReader` (trait from parquet crate) doesn't support async
-    // read. So under this situation it would be better to pass a local file to
-    // it, avoiding consumes lots of memory. Once parquet support stream data source
-    // we can feed the `GetResult` to it directly.
-    let bytes = SliceableCursor::new(Arc::new(
-        get_result
-            .bytes()
-            .await
-            .map_err(|e| Box::new(e) as _)
-            .context(ReadPersist {
-                path: path.to_string(),
-            })?
-            .to_vec(),
-    ));
-
-    // generate the file reader
-    let file_reader = CachableSerializedFileReader::new(
-        path.to_string(),
-        bytes,
-        meta_cache.clone(),
-        data_cache.clone(),
-    )
-    .map_err(|e| Box::new(e) as _)
-    .with_context(|| ReadPersist {
-        path: path.to_string(),
-    })?;
-
-    // parse sst meta data
-    let sst_meta = {
-        let kv_metas = file_reader
-            .metadata()
-            .file_metadata()
-            .key_value_metadata()
-            .as_ref()
-            .context(SstMetaNotFound)?;
-
-        ensure!(!kv_metas.is_empty(), EmptySstMeta);
-
-        encoding::decode_sst_meta_data(&kv_metas[0])
-            .map_err(|e| Box::new(e) as _)
-            .context(DecodeSstMeta)?
+
+    Ok(object_meta.size)
+}
+
+/// Read the parquet metadata from the footer of the sst by ranged reads instead
+/// of fetching the whole file.
+async fn read_parquet_metadata<S: ObjectStore>(
+    storage: &S,
+    path: &Path,
+    file_size: usize,
+    meta_cache: &Option<MetaCacheRef>,
+) -> Result<Arc<ParquetMetaData>> {
+    let name = path.to_string();
+    if let Some(metadata) = meta_cache.as_ref().and_then(|cache| cache.get(&name)) {
+        return Ok(metadata);
+    }
+
+    let prefetch_size = FOOTER_PREFETCH_SIZE.min(file_size);
+    let mut footer = read_range(storage, path, file_size - prefetch_size..file_size).await?;
+    if footer.len() >= FOOTER_TAIL_SIZE {
+        let len_start = footer.len() - FOOTER_TAIL_SIZE;
+        let mut metadata_len = [0; 4];
+        metadata_len.copy_from_slice(&footer[len_start..len_start + 4]);
+        let footer_size = i32::from_le_bytes(metadata_len) as usize + FOOTER_TAIL_SIZE;
+        // Read the whole footer if the prefetched bytes is not enough, the invalid
+        // metadata length is left to the parquet to report.
+        if footer_size > footer.len() && footer_size <= file_size {
+            footer = read_range(storage, path, file_size - footer_size..file_size).await?;
+        }
+    }
+
+    let mut chunk_reader = RangedChunkReader::new(file_size as u64);
+    chunk_reader.add_chunk((file_size - footer.len()) as u64, footer.to_vec());
+    let metadata = footer::parse_metadata(&chunk_reader)
+        .map_err(|e| Box::new(e) as _)
+        .context(ReadPersist { path: name.clone() })?;
+    let metadata = Arc::new(metadata);
+    if let Some(meta_cache) = meta_cache {
+        meta_cache.put(name, metadata.clone());
+    }
+
+    Ok(metadata)
+}
+
+fn decode_sst_meta(parquet_metadata: &ParquetMetaData) -> Result<SstMetaData> {
+    let kv_metas = parquet_metadata
+        .file_metadata()
+        .key_value_metadata()
+        .as_ref()
+        .context(SstMetaNotFound)?;
+
+    ensure!(!kv_metas.is_empty(), EmptySstMeta);
+
+    encoding::decode_sst_meta_data(&kv_metas[0])
+        .map_err(|e| Box::new(e) as _)
+        .context(DecodeSstMeta)
+}
+
+pub async fn read_sst_meta<S: ObjectStore>(
+    storage: &S,
+    path: &Path,
+    meta_cache: &Option<MetaCacheRef>,
+) -> Result<(Arc<ParquetMetaData>, SstMetaData)> {
+    let file_size = read_file_size(storage, path).await?;
+    let parquet_metadata = read_parquet_metadata(storage, path, file_size, meta_cache).await?;
+    let sst_meta = decode_sst_meta(&parquet_metadata)?;
+
+    Ok((parquet_metadata, sst_meta))
+}
+
+/// Returns the byte ranges of the column chunks to read in the row group, all
+/// the columns are read if the `projection` is None.
+fn column_chunk_ranges(
+    row_group: &RowGroupMetaData,
+    projection: Option<&[usize]>,
+) -> Vec<Range<usize>> {
+    let byte_range = |idx: usize| {
+        let (start, length) = row_group.column(idx).byte_range();
+        start as usize..(start + length) as usize
     };
 
-    Ok((file_reader, sst_meta))
+    match projection {
+        Some(projection) => projection.iter().map(|idx| byte_range(*idx)).collect(),
+        None => (0..row_group.num_columns()).map(byte_range).collect(),
+    }
+}
+
+/// Sort the `ranges` and merge the ranges overlapping or whose gap is not
+/// larger than `max_gap`.
+fn coalesce_ranges(mut ranges: Vec<Range<usize>>, max_gap: usize) -> Vec<Range<usize>> {
+    ranges.retain(|range| !range.is_empty());
+    ranges.sort_unstable_by_key(|range| range.start);
+
+    let mut coalesced: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match coalesced.last_mut() {
+            Some(last) if range.start <= last.end + max_gap => {
+                last.end = last.end.max(range.end);
+            }
+            _ => coalesced.push(range),
+        }
+    }
+
+    coalesced
 }
 
 /// The implementation of sst based on parquet and object storage.
@@ -111,8 +196,11 @@ pub struct ParquetSstReader<'a, S: ObjectStore> {
     storage: &'a S,
     projected_schema: ProjectedSchema,
     predicate: PredicateRef,
+    tsid_filter: Option<TsidSetRef>,
     meta_data: Option<SstMetaData>,
-    file_reader: Option<CachableSerializedFileReader<SliceableCursor>>,
+    file_size: usize,
+    /// The parquet metadata, taken when reading the record batches.
+    parquet_metadata: Option<Arc<ParquetMetaData>>,
     /// The batch of rows in one `record_batch`.
     batch_size: usize,
     /// Read the rows in reverse order.
@@ -132,8 +220,10 @@ impl<'a, S: ObjectStore> ParquetSstReader<'a, S> {
             storage,
             projected_schema: options.projected_schema.clone(),
             predicate: options.predicate.clone(),
+            tsid_filter: options.tsid_filter.clone(),
             meta_data: None,
-            file_reader: None,
+            file_size: 0,
+            parquet_metadata: None,
             batch_size: options.read_batch_row_num,
             reverse: options.reverse,
             channel_cap: DEFAULT_CHANNEL_CAP,
@@ -150,32 +240,120 @@ impl<'a, S: ObjectStore> ParquetSstReader<'a, S> {
             return Ok(());
         }
 
-        let (file_reader, sst_meta) =
-            read_sst_meta(self.storage, self.path, &self.meta_cache, &self.data_cache).await?;
+        let file_size = read_file_size(self.storage, self.path).await?;
+        let parquet_metadata =
+            read_parquet_metadata(self.storage, self.path, file_size, &self.meta_cache).await?;
+        let sst_meta = decode_sst_meta(&parquet_metadata)?;
 
-        self.file_reader = Some(file_reader);
+        self.file_size = file_size;
+        self.parquet_metadata = Some(parquet_metadata);
         self.meta_data = Some(sst_meta);
 
         Ok(())
     }
 
-    fn read_record_batches(&mut self, tx: Sender<Result<RecordBatchWithKey>>) -> Result<()> {
+    /// Prune the row groups by the predicate, then fetch the projected column
+    /// chunks of the remaining row groups by ranged reads and build the file
+    /// reader on the fetched bytes.
+    async fn fetch_row_groups(
+        &mut self,
+        row_projector: &RowProjector,
+    ) -> Result<CachableSerializedFileReader<RangedChunkReader>> {
         let path = self.path.to_string();
-        ensure!(self.file_reader.is_some(), ReadAgain { path });
-
-        let file_reader = self.file_reader.take().unwrap();
-        let batch_size = self.batch_size;
-        let schema = {
-            let meta_data = self.meta_data.as_ref().unwrap();
+        let parquet_metadata = self
+            .parquet_metadata
+            .take()
+            .context(ReadAgain { path: path.clone() })?;
+
+        let meta_data = self.meta_data.as_ref().unwrap();
+        let reader_schema = self.projected_schema.original_schema();
+        // The exprs of the predicate refer to the columns by the names in the
+        // reader schema, which may be renamed or dropped since the sst is written.
+        let schema = if meta_data.schema.version() == reader_schema.version() {
             meta_data.schema.clone()
+        } else {
+            meta_data.schema.rename_columns_by_id(reader_schema)
+        };
+        let all_row_groups = parquet_metadata.row_groups();
+        let mut filter_results = self.predicate.filter_row_groups(&schema, all_row_groups);
+        // Min/max statistics can't prune row groups on the random tag values, so
+        // the bloom filters are also checked.
+        let bloom_filter_results = meta_data.bloom_filter.filter_row_groups(
+            &schema,
+            &self.predicate,
+            all_row_groups.len(),
+        );
+        for (result, selected) in filter_results.iter_mut().zip(bloom_filter_results) {
+            *result = *result && selected;
+        }
+        trace!(
+            "Finish filtering row groups, sst:{}, predicate:{:?}, filter_results:{:?}",
+            path,
+            self.predicate,
+            filter_results
+        );
+        let row_groups: Vec<_> = all_row_groups
+            .iter()
+            .zip(filter_results)
+            .filter_map(|(row_group, selected)| selected.then(|| row_group.clone()))
+            .collect();
+
+        let projection = if self.projected_schema.is_all_projection() {
+            None
+        } else {
+            Some(row_projector.existed_source_projection())
         };
+        let ranges = row_groups
+            .iter()
+            .flat_map(|row_group| column_chunk_ranges(row_group, projection.as_deref()))
+            .collect();
+        let ranges = coalesce_ranges(ranges, COALESCE_GAP_SIZE);
+        debug!(
+            "Fetch sst by ranges, sst:{}, row_groups:{}/{}, ranges:{:?}",
+            path,
+            row_groups.len(),
+            all_row_groups.len(),
+            ranges
+        );
+
+        let fetches = ranges
+            .iter()
+            .map(|range| read_range(self.storage, self.path, range.clone()));
+        let chunks = future::try_join_all(fetches).await?;
+
+        let mut chunk_reader = RangedChunkReader::new(self.file_size as u64);
+        for (range, bytes) in ranges.into_iter().zip(chunks) {
+            chunk_reader.add_chunk(range.start as u64, bytes.to_vec());
+        }
+        let parquet_metadata = Arc::new(ParquetMetaData::new(
+            parquet_metadata.file_metadata().clone(),
+            row_groups,
+        ));
+
+        Ok(CachableSerializedFileReader::with_metadata(
+            path,
+            chunk_reader,
+            parquet_metadata,
+            self.data_cache.clone(),
+        ))
+    }
+
+    fn read_record_batches(
+        &mut self,
+        file_reader: CachableSerializedFileReader<RangedChunkReader>,
+        row_projector: RowProjector,
+        tx: Sender<Result<RecordBatchWithKey>>,
+    ) -> Result<()> {
+        let path = self.path.to_string();
+        let batch_size = self.batch_size;
         let projected_schema = self.projected_schema.clone();
-        let row_projector = projected_schema
-            .try_project_with_key(&schema)
-            .map_err(|e| Box::new(e) as _)
-            .context(Projection)?;
         let predicate = self.predicate.clone();
         let reverse = self.reverse;
+        // The series not indexed may be missing from the filter.
+        let tsid_filter = match self.meta_data.as_ref() {
+            Some(meta) if meta.tag_index.is_some() => self.tsid_filter.clone(),
+            _ => None,
+        };
 
         let _ = self.runtime.spawn_blocking(move || {
             debug!(
@@ -197,12 +375,11 @@ impl<'a, S: ObjectStore> ParquetSstReader<'a, S> {
             let reader = ProjectAndFilterReader {
                 file_path: path.clone(),
                 file_reader: Some(file_reader),
-                schema,
                 projected_schema,
                 row_projector,
-                predicate,
                 batch_size,
                 reverse,
+                tsid_filter,
             };
 
             let start_fetch = Instant::now();
@@ -235,34 +412,25 @@ impl<'a, S: ObjectStore> ParquetSstReader<'a, S> {
     #[cfg(test)]
     pub(crate) async fn row_groups(&mut self) -> &[RowGroupMetaData] {
         self.init_if_necessary().await.unwrap();
-        self.file_reader.as_ref().unwrap().metadata().row_groups()
+        self.parquet_metadata.as_ref().unwrap().row_groups()
     }
 }
 
 /// A reader for projection and filter on the parquet file.
+///
+/// The row groups of the `file_reader` have been filtered by the predicate.
 struct ProjectAndFilterReader {
     file_path: String,
-    file_reader: Option<CachableSerializedFileReader<SliceableCursor>>,
-    schema: Schema,
+    file_reader: Option<CachableSerializedFileReader<RangedChunkReader>>,
     projected_schema: ProjectedSchema,
     row_projector: RowProjector,
-    predicate: PredicateRef,
     batch_size: usize,
     reverse: bool,
+    /// Only the rows of the series in the set are read if it is not None
+    tsid_filter: Option<TsidSetRef>,
 }
 
 impl ProjectAndFilterReader {
-    fn build_row_group_predicate(&self) -> Box<dyn Fn(&RowGroupMetaData, usize) -> bool + 'static> {
-        assert!(self.file_reader.is_some());
-
-        let row_groups = self.file_reader.as_ref().unwrap().metadata().row_groups();
-        let filter_results = self.predicate.filter_row_groups(&self.schema, row_groups);
-
-        trace!("Finish build row group predicate, predicate:{:?}, schema:{:?}, filter_results:{:?}, row_groups meta data:{:?}", self.predicate, self.schema, filter_results, row_groups);
-
-        Box::new(move |_, idx: usize| filter_results[idx])
-    }
-
     /// Generate the reader which has processed projection and filter.
     /// This `file_reader` is consumed after calling this method.
     fn project_and_filter_reader(
@@ -270,9 +438,7 @@ impl ProjectAndFilterReader {
     ) -> Result<Box<dyn Iterator<Item = ArrowResult<RecordBatch>>>> {
         assert!(self.file_reader.is_some());
 
-        let row_group_predicate = self.build_row_group_predicate();
-        let mut file_reader = self.file_reader.take().unwrap();
-        file_reader.filter_row_groups(&row_group_predicate);
+        let file_reader = self.file_reader.take().unwrap();
 
         if self.reverse {
             let mut builder =
@@ -314,6 +480,7 @@ impl ProjectAndFilterReader {
 
         let arrow_record_batch_projector = ArrowRecordBatchProjector::from(self.row_projector);
         let mut row_num = 0;
+        let mut selected_rows_buf = Vec::new();
         for record_batch in reader {
             trace!(
                 "Fetch one record batch from sst:{}, num_rows:{:?}",
@@ -331,8 +498,24 @@ impl ProjectAndFilterReader {
                     let record_batch_with_key = arrow_record_batch_projector
                         .project_to_record_batch_with_key(record_batch)
                         .map_err(|e| Box::new(e) as _)
-                        .context(DecodeRecordBatch);
-
+                        .context(DecodeRecordBatch)
+                        .and_then(|mut batch| {
+                            if let Some(tsids) = &self.tsid_filter {
+                                tag_index::filter_record_batch_by_tsids(
+                                    &mut batch,
+                                    tsids,
+                                    &mut selected_rows_buf,
+                                )
+                                .map_err(|e| Box::new(e) as _)
+                                .context(DecodeRecordBatch)?;
+                            }
+                            Ok(batch)
+                        });
+
+                    // All the rows are filtered out by the tsid filter.
+                    if matches!(&record_batch_with_key, Ok(batch) if batch.num_rows() == 0) {
+                        continue;
+                    }
                     send(record_batch_with_key)?;
                 }
                 Err(e) => {
@@ -377,9 +560,37 @@ impl<'a, S: ObjectStore> SstReader for ParquetSstReader<'a, S> {
         );
 
         self.init_if_necessary().await?;
+        let row_projector = {
+            let schema = &self.meta_data.as_ref().unwrap().schema;
+            self.projected_schema
+                .try_project_with_key(schema)
+                .map_err(|e| Box::new(e) as _)
+                .context(Projection)?
+        };
+        let file_reader = self.fetch_row_groups(&row_projector).await?;
         let (tx, rx) = mpsc::channel::<Result<RecordBatchWithKey>>(self.channel_cap);
-        self.read_record_batches(tx)?;
+        self.read_record_batches(file_reader, row_projector, tx)?;
 
         Ok(Box::new(RecordBatchReceiver { rx }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coalesce_ranges() {
+        let cases = vec![
+            (vec![], 0, vec![]),
+            (vec![0..10, 10..20, 30..40], 0, vec![0..20, 30..40]),
+            (vec![30..40, 0..10, 12..20], 2, vec![0..20, 30..40]),
+            (vec![0..10, 5..8, 8..15, 20..20], 0, vec![0..15]),
+            (vec![0..10, 20..30, 40..50], 10, vec![0..50]),
+        ];
+
+        for (ranges, max_gap, expected) in cases {
+            assert_eq!(expected, coalesce_ranges(ranges, max_gap));
+        }
+    }
+}
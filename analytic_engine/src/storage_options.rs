@@ -1,12 +1,21 @@
 // Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
 
+use std::time::Duration;
+
+use common_util::config::{ReadableDuration, ReadableSize};
 use serde::Deserialize;
+use wal::{
+    compression::Compression,
+    file_impl::manager::{Options as FileWalManagerOptions, SyncPolicy},
+    group_commit::Options as GroupCommitOptions,
+};
 
 /// Options for storage backend
 #[derive(Debug, Clone, Deserialize)]
 pub enum StorageOptions {
     Local(LocalOptions),
     Aliyun(AliyunOptions),
+    S3(S3Options),
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -21,3 +30,121 @@ pub struct AliyunOptions {
     pub endpoint: String,
     pub bucket: String,
 }
+
+/// Options for S3 compatible services, e.g. AWS S3 and MinIO
+#[derive(Debug, Clone, Deserialize)]
+pub struct S3Options {
+    pub region: String,
+    pub endpoint: String,
+    pub key_id: String,
+    pub key_secret: String,
+    pub bucket: String,
+    /// Cache the sst files in local disk if set
+    #[serde(default)]
+    pub cache: Option<LocalCacheOptions>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocalCacheOptions {
+    pub data_path: String,
+    /// Max size in bytes of the cached files
+    pub max_cache_size: usize,
+}
+
+/// Options for wal backend
+#[derive(Debug, Clone, Deserialize)]
+pub enum WalStorageOptions {
+    RocksDB,
+    /// Wal based on local segment files
+    File(FileWalOptions),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FileWalOptions {
+    /// Switch to a new segment file once the active one exceeds this size
+    pub segment_size: ReadableSize,
+    pub sync_policy: WalSyncPolicy,
+}
+
+impl Default for FileWalOptions {
+    fn default() -> Self {
+        Self {
+            segment_size: ReadableSize::mb(64),
+            sync_policy: WalSyncPolicy::Interval(ReadableDuration::secs(1)),
+        }
+    }
+}
+
+impl From<FileWalOptions> for FileWalManagerOptions {
+    fn from(opts: FileWalOptions) -> Self {
+        let sync_policy = match opts.sync_policy {
+            WalSyncPolicy::EveryWrite => SyncPolicy::EveryWrite,
+            WalSyncPolicy::Interval(interval) => SyncPolicy::Interval(Duration::from(interval)),
+            WalSyncPolicy::Never => SyncPolicy::Never,
+        };
+
+        Self {
+            segment_size: opts.segment_size.as_bytes() as usize,
+            sync_policy,
+            // Set by the `wal_compression` of the engine config.
+            compression: Compression::None,
+        }
+    }
+}
+
+/// Policy to sync the wal files to disk
+#[derive(Debug, Clone, Deserialize)]
+pub enum WalSyncPolicy {
+    EveryWrite,
+    /// Sync the written entries periodically in background
+    Interval(ReadableDuration),
+    Never,
+}
+
+/// Compression of the wal payloads
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum WalCompression {
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl From<WalCompression> for Compression {
+    fn from(compression: WalCompression) -> Self {
+        match compression {
+            WalCompression::None => Compression::None,
+            WalCompression::Lz4 => Compression::Lz4,
+            WalCompression::Zstd => Compression::Zstd,
+        }
+    }
+}
+
+/// Options for group commit of the wal
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WalGroupCommitOptions {
+    /// Max number of log batches merged into one commit
+    pub max_batch_num: usize,
+    /// Capacity of the queue of the log batches to commit
+    pub channel_cap: usize,
+}
+
+impl Default for WalGroupCommitOptions {
+    fn default() -> Self {
+        let opts = GroupCommitOptions::default();
+        Self {
+            max_batch_num: opts.max_batch_num,
+            channel_cap: opts.channel_cap,
+        }
+    }
+}
+
+impl From<WalGroupCommitOptions> for GroupCommitOptions {
+    fn from(opts: WalGroupCommitOptions) -> Self {
+        Self {
+            max_batch_num: opts.max_batch_num,
+            channel_cap: opts.channel_cap,
+        }
+    }
+}
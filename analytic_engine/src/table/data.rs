@@ -25,12 +25,16 @@ use common_util::define_result;
 use log::{debug, info};
 use object_store::Path;
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
-use table_engine::{engine::CreateTableRequest, table::TableId};
+use table_engine::{
+    engine::CreateTableRequest,
+    table::{CompactionTaskInfo, TableId},
+};
 use wal::manager::RegionId;
 
 use crate::{
     instance::write_worker::{WorkerLocal, WriteHandle},
     memtable::{
+        columnar::factory::ColumnarMemTableFactory,
         factory::{FactoryRef as MemTableFactoryRef, Options as MemTableOptions},
         skiplist::factory::SkiplistMemTableFactory,
     },
@@ -42,6 +46,7 @@ use crate::{
         sst_util,
         version::{MemTableForWrite, MemTableState, SamplingMemTable, TableVersion},
     },
+    table_options::MemTableType,
     TableOptions,
 };
 
@@ -126,6 +131,9 @@ pub struct TableData {
     /// No write/alter is allowed if the table is dropped.
     dropped: AtomicBool,
 
+    /// Running compaction tasks of this table.
+    compaction_tasks: Mutex<Vec<CompactionTaskInfo>>,
+
     /// Metrics of this table.
     pub metrics: Metrics,
 }
@@ -158,6 +166,14 @@ fn get_mutable_limit(opts: &TableOptions) -> u32 {
     opts.write_buffer_size * 7 / 8
 }
 
+#[inline]
+fn memtable_factory(opts: &TableOptions) -> MemTableFactoryRef {
+    match opts.memtable_type {
+        MemTableType::Skiplist => Arc::new(SkiplistMemTableFactory),
+        MemTableType::Columnar => Arc::new(ColumnarMemTableFactory),
+    }
+}
+
 impl TableData {
     /// Create a new TableData
     ///
@@ -174,7 +190,7 @@ impl TableData {
         // FIXME(yingwen): Validate TableOptions, such as bucket_duration >=
         // segment_duration and bucket_duration is aligned to segment_duration
 
-        let memtable_factory = Arc::new(SkiplistMemTableFactory);
+        let memtable_factory = memtable_factory(&table_opts);
         let purge_queue = purger.create_purge_queue(space_id, request.table_id);
         let current_version = TableVersion::new(purge_queue);
         let metrics = Metrics::new(&request.table_name);
@@ -196,6 +212,7 @@ impl TableData {
             last_memtable_id: AtomicU64::new(0),
             last_file_id: AtomicU64::new(0),
             dropped: AtomicBool::new(false),
+            compaction_tasks: Mutex::new(Vec::new()),
             metrics,
         })
     }
@@ -209,7 +226,7 @@ impl TableData {
         purger: &FilePurger,
         mem_usage_collector: CollectorRef,
     ) -> Result<Self> {
-        let memtable_factory = Arc::new(SkiplistMemTableFactory);
+        let memtable_factory = memtable_factory(&add_meta.opts);
         let purge_queue = purger.create_purge_queue(add_meta.space_id, add_meta.table_id);
         let current_version = TableVersion::new(purge_queue);
         let metrics = Metrics::new(&add_meta.table_name);
@@ -231,6 +248,7 @@ impl TableData {
             last_memtable_id: AtomicU64::new(0),
             last_file_id: AtomicU64::new(0),
             dropped: AtomicBool::new(false),
+            compaction_tasks: Mutex::new(Vec::new()),
             metrics,
         })
     }
@@ -308,6 +326,25 @@ impl TableData {
         self.current_version.total_memory_usage()
     }
 
+    /// Returns the running compaction tasks of this table.
+    pub fn compaction_tasks(&self) -> Vec<CompactionTaskInfo> {
+        self.compaction_tasks.lock().unwrap().clone()
+    }
+
+    /// Track a running compaction task of this table.
+    pub fn add_compaction_task(&self, task: CompactionTaskInfo) {
+        self.compaction_tasks.lock().unwrap().push(task);
+    }
+
+    /// Remove the compaction task started by request `request_id` once it
+    /// finished.
+    pub fn remove_compaction_task(&self, request_id: u64) {
+        self.compaction_tasks
+            .lock()
+            .unwrap()
+            .retain(|t| t.request_id != request_id);
+    }
+
     /// Find memtable for given timestamp to insert, create if not exists
     ///
     /// If the memtable schema is outdated, switch all memtables and create the
@@ -339,6 +376,7 @@ impl TableData {
             arena_block_size: table_options.arena_block_size,
             creation_sequence: last_sequence,
             collector: self.mem_usage_collector.clone(),
+            enable_tag_index: table_options.enable_inverted_index,
         };
         let mem = self
             .memtable_factory
@@ -585,6 +623,7 @@ pub mod tests {
                 arena_block_size: 1024 * 1024,
                 creation_sequence: 1000,
                 collector: Arc::new(NoopCollector),
+                enable_tag_index: false,
             };
 
             let factory = SkiplistMemTableFactory;
@@ -6,17 +6,23 @@ use std::{collections::HashMap, fmt, sync::Arc};
 
 use arrow_deps::datafusion::logical_plan::{Column, Expr};
 use async_trait::async_trait;
-use common_types::{row::Row, schema::Schema, time::TimeRange};
+use common_types::{
+    row::Row,
+    schema::Schema,
+    time::{TimeRange, Timestamp},
+};
 use futures::TryStreamExt;
 use object_store::ObjectStore;
 use snafu::{ensure, OptionExt, ResultExt};
 use table_engine::{
+    partition::PartitionInfo,
     predicate::Predicate,
     stream::{PartitionedStreams, SendableRecordBatchStream},
     table::{
-        AlterOptions, AlterSchema, AlterSchemaRequest, Compact, Flush, FlushRequest, Get,
-        GetInvalidPrimaryKey, GetNullPrimaryKey, GetRequest, ReadOptions, ReadOrder, ReadRequest,
-        Result, Scan, Table, TableId, TableStats, Write, WriteRequest,
+        AlterOptions, AlterSchema, AlterSchemaRequest, Compact, CompactionTaskInfo, Delete,
+        DeleteRequest, Flush, FlushRequest, Get, GetInvalidPrimaryKey, GetNullPrimaryKey,
+        GetRequest, ReadOptions, ReadOrder, ReadRequest, Result, Scan, ScanStatistics, SstFileInfo,
+        Table, TableId, TableStats, Write, WriteRequest,
     },
 };
 use tokio::sync::oneshot;
@@ -32,6 +38,8 @@ use crate::{
 pub mod data;
 pub mod metrics;
 pub mod sst_util;
+pub mod statistics;
+pub mod tombstone;
 pub mod version;
 pub mod version_edit;
 
@@ -93,18 +101,59 @@ impl<
         self.space_table.table_data().table_options().to_raw_map()
     }
 
+    fn partition_info(&self) -> Option<PartitionInfo> {
+        None
+    }
+
     fn engine_type(&self) -> &str {
         &self.engine_type
     }
 
     fn stats(&self) -> TableStats {
-        let metrics = &self.space_table.table_data().metrics;
+        let table_data = self.space_table.table_data();
+        let metrics = &table_data.metrics;
+        let sst_files = table_data.current_version().sst_files(table_data.id);
 
         TableStats {
             num_write: metrics.write_request_counter.get(),
             num_read: metrics.read_request_counter.get(),
             num_flush: metrics.flush_duration_histogram.get_sample_count(),
+            memtable_memory_usage: table_data.memtable_memory_usage() as u64,
+            num_sst_files: sst_files.len() as u64,
+            sst_size: sst_files.iter().map(|f| f.size).sum(),
+        }
+    }
+
+    fn sst_files(&self) -> Vec<SstFileInfo> {
+        let table_data = self.space_table.table_data();
+        table_data.current_version().sst_files(table_data.id)
+    }
+
+    fn compaction_tasks(&self) -> Vec<CompactionTaskInfo> {
+        self.space_table.table_data().compaction_tasks()
+    }
+
+    fn rollup_watermark(&self) -> Option<Timestamp> {
+        let table_data = self.space_table.table_data();
+        if table_data.table_options().rollup.is_none() {
+            return None;
         }
+        let rollup_table_name = table_engine::rollup::rollup_table_name(&table_data.name);
+        let rollup_table = self.space_table.space().find_table(&rollup_table_name)?;
+
+        let rolled_up_files = rollup_table.current_version().rollup_source_files();
+        let watermark = table_data
+            .current_version()
+            .min_timestamp_not_rolled_up(&rolled_up_files)
+            .unwrap_or(Timestamp::MAX);
+        Some(watermark)
+    }
+
+    fn scan_statistics(&self, time_range: TimeRange) -> ScanStatistics {
+        let table_data = self.space_table.table_data();
+        let read_view = table_data.current_version().pick_read_view(time_range);
+
+        statistics::estimate_scan_statistics(&read_view, time_range, &table_data.schema())
     }
 
     async fn write(&self, request: WriteRequest) -> Result<usize> {
@@ -117,6 +166,15 @@ impl<
         Ok(num_rows)
     }
 
+    async fn delete(&self, request: DeleteRequest) -> Result<usize> {
+        self.instance
+            .delete_from_table(&self.space_table, request)
+            .await
+            .map_err(|e| Box::new(e) as _)
+            .context(Delete { table: self.name() })?;
+        Ok(1)
+    }
+
     async fn read(&self, mut request: ReadRequest) -> Result<SendableRecordBatchStream> {
         request.opts.read_parallelism = 1;
         let mut streams = self
@@ -167,6 +225,7 @@ impl<
                 time_range: TimeRange::min_to_max(),
             }),
             order: ReadOrder::None,
+            limit: None,
         };
         let mut batch_stream = self
             .read(read_request)
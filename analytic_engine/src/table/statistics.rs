@@ -0,0 +1,186 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Estimate the statistics of the rows to scan
+
+use common_types::{column_schema::ColumnSchema, datum::Datum, schema::Schema, time::TimeRange};
+use log::warn;
+use table_engine::table::{ColumnStatistics, ScanStatistics};
+
+use crate::{
+    sst::{column_stats, file::FileHandle},
+    table::version::ReadView,
+};
+
+/// Estimate the statistics of the rows in `time_range` from the memtables and
+/// ssts in the `read_view`.
+///
+/// The rows of a memtable or sst are assumed to be evenly distributed in its
+/// time range. The column statistics only come from the ssts overlapping the
+/// `time_range` as the memtables don't collect them, they are unknown if any
+/// of these ssts lacks them.
+pub fn estimate_scan_statistics(
+    read_view: &ReadView,
+    time_range: TimeRange,
+    schema: &Schema,
+) -> ScanStatistics {
+    let mut num_rows = 0.0;
+    let mut total_byte_size = 0.0;
+
+    // The sampling memtable has no time range.
+    if let Some(sampling_mem) = &read_view.sampling_mem {
+        num_rows += sampling_mem.mem.num_rows() as f64;
+        total_byte_size += sampling_mem.mem.approximate_memory_usage() as f64;
+    }
+    for mem_state in &read_view.memtables {
+        let ratio = overlap_ratio(mem_state.time_range, time_range);
+        num_rows += mem_state.mem.num_rows() as f64 * ratio;
+        total_byte_size += mem_state.mem.approximate_memory_usage() as f64 * ratio;
+    }
+
+    let mut files = Vec::new();
+    for file in read_view.leveled_ssts.iter().flatten() {
+        let ratio = overlap_ratio(file.time_range(), time_range);
+        if ratio == 0.0 {
+            continue;
+        }
+        num_rows += file.row_num() as f64 * ratio;
+        total_byte_size += file.size() as f64 * ratio;
+        files.push((file, ratio));
+    }
+
+    let column_statistics = if files.is_empty() {
+        Vec::new()
+    } else {
+        schema
+            .columns()
+            .iter()
+            .map(|column| estimate_column_statistics(&files, column))
+            .collect()
+    };
+
+    ScanStatistics {
+        num_rows: Some(num_rows.round() as usize),
+        total_byte_size: Some(total_byte_size.round() as usize),
+        column_statistics,
+    }
+}
+
+/// Estimate the statistics of the `column` from the `files` paired with the
+/// ratio of their rows to scan.
+///
+/// The column is looked up by id as it may be renamed after some ssts are
+/// built. Only the null count is scaled by the ratio, the min/max values and
+/// distinct count of the whole file are used.
+fn estimate_column_statistics(
+    files: &[(&FileHandle, f64)],
+    column: &ColumnSchema,
+) -> ColumnStatistics {
+    let mut stats_of_files = Vec::with_capacity(files.len());
+    for (file, ratio) in files {
+        let stats = file
+            .schema()
+            .index_of_column_id(column.id)
+            .and_then(|idx| file.column_stats().get(idx));
+        match stats {
+            Some(stats) => stats_of_files.push((stats, *ratio)),
+            // The sst is built without column stats or before the column is
+            // added.
+            None => return ColumnStatistics::default(),
+        }
+    }
+
+    let mut null_count = 0.0;
+    let mut min_value: Option<Datum> = None;
+    let mut max_value: Option<Datum> = None;
+    for (stats, ratio) in &stats_of_files {
+        null_count += stats.null_count as f64 * ratio;
+        // The min/max values are null if all the values are null.
+        if stats.min_value.is_null() {
+            continue;
+        }
+        if min_value.as_ref().map_or(true, |v| stats.min_value < *v) {
+            min_value = Some(stats.min_value.clone());
+        }
+        if max_value.as_ref().map_or(true, |v| stats.max_value > *v) {
+            max_value = Some(stats.max_value.clone());
+        }
+    }
+
+    let distinct_count = match column_stats::merge_distinct_count(
+        stats_of_files.into_iter().map(|(stats, _)| stats),
+    ) {
+        Ok(v) => Some(v as usize),
+        Err(e) => {
+            warn!(
+                "Failed to merge distinct count, column:{}, err:{}",
+                column.name, e
+            );
+            None
+        }
+    };
+
+    ColumnStatistics {
+        null_count: Some(null_count.round() as usize),
+        min_value,
+        max_value,
+        distinct_count,
+    }
+}
+
+/// Ratio of the length of `target` overlapped by `range` to the length of
+/// `target`.
+fn overlap_ratio(target: TimeRange, range: TimeRange) -> f64 {
+    let target_len = range_len(target);
+    if target_len == 0 {
+        return if range.contains(target.inclusive_start()) {
+            1.0
+        } else {
+            0.0
+        };
+    }
+
+    match target.intersected_range(range) {
+        Some(overlapped) => range_len(overlapped) as f64 / target_len as f64,
+        None => 0.0,
+    }
+}
+
+#[inline]
+fn range_len(range: TimeRange) -> i128 {
+    range.exclusive_end().as_i64() as i128 - range.inclusive_start().as_i64() as i128
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlap_ratio() {
+        let target = TimeRange::new_unchecked_for_test(100, 200);
+        assert_eq!(1.0, overlap_ratio(target, TimeRange::min_to_max()));
+        assert_eq!(
+            0.5,
+            overlap_ratio(target, TimeRange::new_unchecked_for_test(150, 300))
+        );
+        assert_eq!(
+            0.0,
+            overlap_ratio(target, TimeRange::new_unchecked_for_test(200, 300))
+        );
+
+        let empty = TimeRange::new_unchecked_for_test(100, 100);
+        assert_eq!(
+            1.0,
+            overlap_ratio(empty, TimeRange::new_unchecked_for_test(50, 150))
+        );
+        assert_eq!(
+            0.0,
+            overlap_ratio(empty, TimeRange::new_unchecked_for_test(150, 200))
+        );
+
+        let ratio = overlap_ratio(
+            TimeRange::min_to_max(),
+            TimeRange::new_unchecked_for_test(0, i64::MAX),
+        );
+        assert!(ratio > 0.49 && ratio < 0.51);
+    }
+}
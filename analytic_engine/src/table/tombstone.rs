@@ -0,0 +1,384 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Tombstones of the deleted rows
+
+use std::convert::TryFrom;
+
+use common_types::{
+    datum::{Datum, DatumKind, DatumView},
+    record_batch::RecordBatchWithKey,
+    time::TimeRange,
+    SequenceNumber,
+};
+use common_util::{
+    codec::{
+        compact::{MemCompactDecoder, MemCompactEncoder},
+        DecodeTo, Encoder,
+    },
+    define_result,
+};
+use proto::{common::DataType as DataTypePb, meta_update as meta_pb, table_requests};
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
+use table_engine::table::{DeleteRequest, TagFilter};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to decode tag value, err:{}", source))]
+    DecodeTagValue {
+        source: common_util::codec::compact::Error,
+    },
+
+    #[snafu(display("Time range of delete request is missing.\nBacktrace:\n{}", backtrace))]
+    MissingTimeRange { backtrace: Backtrace },
+
+    #[snafu(display("Failed to convert time range, err:{}", source))]
+    ConvertTimeRange { source: common_types::time::Error },
+
+    #[snafu(display("Delete request of tombstone is missing.\nBacktrace:\n{}", backtrace))]
+    MissingDeleteRequest { backtrace: Backtrace },
+
+    #[snafu(display(
+        "Column of tombstone not in record batch, column:{}.\nBacktrace:\n{}",
+        column,
+        backtrace
+    ))]
+    ColumnNotInBatch {
+        column: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to select rows not deleted, err:{}", source))]
+    SelectRows {
+        source: common_types::record_batch::Error,
+    },
+}
+
+define_result!(Error);
+
+/// Tombstone persisted for a delete request.
+///
+/// A tombstone only hides the rows written before it, that is, the rows whose
+/// sequence is less than the sequence of the tombstone. The memtables that may
+/// contain rows in the time range of the tombstone are flushed before it is
+/// written, so only the ssts with `max_sequence` less than the sequence of
+/// tombstone need to be filtered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tombstone {
+    /// Sequence of the delete request.
+    pub sequence: SequenceNumber,
+    pub request: DeleteRequest,
+}
+
+impl Tombstone {
+    /// Returns true if the rows of the sst with `max_sequence` may be hidden
+    /// by this tombstone.
+    #[inline]
+    pub fn applies_to(&self, max_sequence: SequenceNumber) -> bool {
+        max_sequence < self.sequence
+    }
+
+    /// Returns true if the tombstone may hide rows in `time_range`.
+    #[inline]
+    pub fn intersect_with(&self, time_range: TimeRange) -> bool {
+        self.request.time_range.intersect_with(time_range)
+    }
+
+    /// Names of the tag columns required to evaluate this tombstone.
+    pub fn tag_columns(&self) -> impl Iterator<Item = &str> {
+        self.request
+            .tag_filters
+            .iter()
+            .map(|filter| filter.column.as_str())
+    }
+
+    pub fn into_pb(self) -> meta_pb::TombstoneMeta {
+        let mut target = meta_pb::TombstoneMeta::new();
+        target.set_sequence(self.sequence);
+        target.set_request(delete_request_to_pb(&self.request));
+
+        target
+    }
+}
+
+impl TryFrom<meta_pb::TombstoneMeta> for Tombstone {
+    type Error = Error;
+
+    fn try_from(mut src: meta_pb::TombstoneMeta) -> Result<Self> {
+        ensure!(src.has_request(), MissingDeleteRequest);
+        let request = delete_request_from_pb(src.take_request())?;
+
+        Ok(Self {
+            sequence: src.sequence,
+            request,
+        })
+    }
+}
+
+/// Convert the [DeleteRequest] into its protobuf form, the tag values are
+/// encoded in compact format.
+pub fn delete_request_to_pb(request: &DeleteRequest) -> table_requests::DeleteRequest {
+    let encoder = MemCompactEncoder;
+    let mut tag_filters = Vec::with_capacity(request.tag_filters.len());
+    for filter in &request.tag_filters {
+        let mut filter_pb = table_requests::TagFilter::new();
+        filter_pb.set_column(filter.column.clone());
+        let kind = filter
+            .values
+            .first()
+            .map(|v| v.kind())
+            .unwrap_or(DatumKind::Null);
+        filter_pb.set_data_type(DataTypePb::from(kind));
+
+        let mut values = Vec::with_capacity(filter.values.len());
+        for value in &filter.values {
+            let mut buf = Vec::with_capacity(encoder.estimate_encoded_size(value));
+            encoder
+                .encode(&mut buf, value)
+                .expect("Should encode tag value into the buffer successfully");
+            values.push(buf);
+        }
+        filter_pb.set_values(values.into());
+
+        tag_filters.push(filter_pb);
+    }
+
+    let mut target = table_requests::DeleteRequest::new();
+    target.set_time_range(request.time_range.into());
+    target.set_tag_filters(tag_filters.into());
+
+    target
+}
+
+/// Convert the protobuf form of delete request into [DeleteRequest].
+pub fn delete_request_from_pb(mut src: table_requests::DeleteRequest) -> Result<DeleteRequest> {
+    ensure!(src.has_time_range(), MissingTimeRange);
+    let time_range = TimeRange::try_from(src.take_time_range()).context(ConvertTimeRange)?;
+
+    let decoder = MemCompactDecoder;
+    let mut tag_filters = Vec::with_capacity(src.tag_filters.len());
+    for filter_pb in src.take_tag_filters().into_iter() {
+        let kind = DatumKind::from(filter_pb.data_type);
+        let mut values = Vec::with_capacity(filter_pb.values.len());
+        for value_bytes in &filter_pb.values {
+            let mut value = Datum::empty(&kind);
+            decoder
+                .decode_to(&mut value_bytes.as_slice(), &mut value)
+                .context(DecodeTagValue)?;
+            values.push(value);
+        }
+
+        tag_filters.push(TagFilter {
+            column: filter_pb.column,
+            values,
+        });
+    }
+
+    Ok(DeleteRequest {
+        time_range,
+        tag_filters,
+    })
+}
+
+/// Filter to remove the rows hidden by tombstones from the record batches.
+///
+/// All the tombstones in the filter are assumed to be applicable to the source
+/// of the record batches.
+#[derive(Debug, Clone)]
+pub struct TombstoneFilter {
+    timestamp_column: String,
+    tombstones: Vec<Tombstone>,
+}
+
+impl TombstoneFilter {
+    pub fn new(timestamp_column: String, tombstones: Vec<Tombstone>) -> Self {
+        Self {
+            timestamp_column,
+            tombstones,
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tombstones.is_empty()
+    }
+
+    /// Remove the rows hidden by the tombstones from the `record_batch`.
+    ///
+    /// The `selected_rows_buf` is for reuse.
+    pub fn filter(
+        &self,
+        record_batch: &mut RecordBatchWithKey,
+        selected_rows_buf: &mut Vec<bool>,
+    ) -> Result<()> {
+        let num_rows = record_batch.num_rows();
+        if self.is_empty() || num_rows == 0 {
+            return Ok(());
+        }
+
+        selected_rows_buf.clear();
+        selected_rows_buf.resize(num_rows, true);
+
+        let schema = record_batch.schema_with_key();
+        let timestamp_index =
+            schema
+                .index_of(&self.timestamp_column)
+                .context(ColumnNotInBatch {
+                    column: &self.timestamp_column,
+                })?;
+
+        let mut num_deleted = 0;
+        for tombstone in &self.tombstones {
+            let mut tag_indexes = Vec::with_capacity(tombstone.request.tag_filters.len());
+            for filter in &tombstone.request.tag_filters {
+                let index = schema.index_of(&filter.column).context(ColumnNotInBatch {
+                    column: &filter.column,
+                })?;
+                tag_indexes.push(index);
+            }
+
+            for (row_idx, selected) in selected_rows_buf.iter_mut().enumerate() {
+                if !*selected {
+                    continue;
+                }
+
+                let timestamp_column = record_batch.column(timestamp_index);
+                let in_time_range = match timestamp_column.datum_view(row_idx) {
+                    DatumView::Timestamp(ts) => tombstone.request.time_range.contains(ts),
+                    _ => false,
+                };
+                if !in_time_range {
+                    continue;
+                }
+
+                let tags_matched = tombstone
+                    .request
+                    .tag_filters
+                    .iter()
+                    .zip(tag_indexes.iter())
+                    .all(|(filter, index)| {
+                        let value = record_batch.column(*index).datum_view(row_idx);
+                        filter.values.iter().any(|v| v.as_view() == value)
+                    });
+                if tags_matched {
+                    *selected = false;
+                    num_deleted += 1;
+                }
+            }
+        }
+
+        if num_deleted > 0 {
+            record_batch
+                .select_data(selected_rows_buf.as_slice())
+                .context(SelectRows)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the tombstones (sorted by sequence) that may hide rows of the sst
+/// with `max_sequence` in `time_range`.
+pub fn tombstones_for_sst(
+    tombstones: &[Tombstone],
+    max_sequence: SequenceNumber,
+    time_range: TimeRange,
+) -> Vec<Tombstone> {
+    tombstones
+        .iter()
+        .filter(|t| t.applies_to(max_sequence) && t.intersect_with(time_range))
+        .cloned()
+        .collect()
+}
+
+/// Returns true if the tombstone with `sequence` is still needed by any of the
+/// ssts with the max sequences in `sst_max_sequences`.
+pub fn is_tombstone_alive(
+    sequence: SequenceNumber,
+    sst_max_sequences: impl IntoIterator<Item = SequenceNumber>,
+) -> bool {
+    sst_max_sequences
+        .into_iter()
+        .any(|max_sequence| max_sequence < sequence)
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::{
+        bytes::Bytes,
+        row::Row,
+        tests::{build_row, build_schema},
+    };
+
+    use super::*;
+    use crate::row_iter::tests::build_record_batch_with_key;
+
+    fn build_tombstone(sequence: SequenceNumber, key1: &[u8], start: i64, end: i64) -> Tombstone {
+        Tombstone {
+            sequence,
+            request: DeleteRequest {
+                time_range: TimeRange::new_unchecked_for_test(start, end),
+                tag_filters: vec![TagFilter {
+                    column: "key1".to_string(),
+                    values: vec![Datum::Varbinary(Bytes::copy_from_slice(key1))],
+                }],
+            },
+        }
+    }
+
+    #[test]
+    fn test_tombstone_pb_conversion() {
+        let tombstone = build_tombstone(10, b"a", 1000, 2000);
+        let pb = tombstone.clone().into_pb();
+        let decoded = Tombstone::try_from(pb).unwrap();
+
+        assert_eq!(tombstone, decoded);
+    }
+
+    #[test]
+    fn test_tombstone_filter() {
+        let schema = build_schema();
+        let rows: Vec<Row> = vec![
+            build_row(b"a", 1000, 10.0, "v1"),
+            build_row(b"a", 1500, 10.0, "v2"),
+            build_row(b"a", 2500, 10.0, "v3"),
+            build_row(b"b", 1000, 10.0, "v4"),
+        ];
+        let mut record_batch = build_record_batch_with_key(schema.clone(), rows);
+
+        let filter = TombstoneFilter::new(
+            schema.timestamp_name().to_string(),
+            vec![build_tombstone(10, b"a", 1000, 2000)],
+        );
+        let mut buf = Vec::new();
+        filter.filter(&mut record_batch, &mut buf).unwrap();
+
+        assert_eq!(2, record_batch.num_rows());
+        assert_eq!(
+            record_batch.clone_row_at(0),
+            build_row(b"a", 2500, 10.0, "v3")
+        );
+        assert_eq!(
+            record_batch.clone_row_at(1),
+            build_row(b"b", 1000, 10.0, "v4")
+        );
+    }
+
+    #[test]
+    fn test_tombstones_for_sst() {
+        let tombstones = vec![
+            build_tombstone(10, b"a", 1000, 2000),
+            build_tombstone(20, b"a", 3000, 4000),
+        ];
+
+        let time_range = TimeRange::new_unchecked_for_test(0, 5000);
+        assert_eq!(2, tombstones_for_sst(&tombstones, 5, time_range).len());
+        assert_eq!(1, tombstones_for_sst(&tombstones, 15, time_range).len());
+        assert!(tombstones_for_sst(&tombstones, 20, time_range).is_empty());
+
+        let time_range = TimeRange::new_unchecked_for_test(0, 1000);
+        assert!(tombstones_for_sst(&tombstones, 5, time_range).is_empty());
+
+        assert!(is_tombstone_alive(10, [5, 30]));
+        assert!(!is_tombstone_alive(10, [10, 30]));
+    }
+}
@@ -4,7 +4,7 @@
 
 use std::{
     cmp,
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt,
     ops::Bound,
     sync::{Arc, RwLock},
@@ -19,6 +19,7 @@ use common_types::{
 };
 use common_util::define_result;
 use snafu::{ensure, Backtrace, ResultExt, Snafu};
+use table_engine::table::{SstFileInfo, TableId};
 
 use crate::{
     compaction::{
@@ -34,6 +35,7 @@ use crate::{
     },
     table::{
         data::MemTableId,
+        tombstone::{self, Tombstone},
         version_edit::{AddFile, VersionEdit},
     },
 };
@@ -504,9 +506,26 @@ struct TableVersionInner {
     /// The earliest sequence number of the entries already flushed (inclusive).
     /// All log entry with sequence <= `flushed_sequence` can be deleted
     flushed_sequence: SequenceNumber,
+
+    /// Tombstones of the delete requests, ordered by sequence.
+    tombstones: Vec<Tombstone>,
 }
 
 impl TableVersionInner {
+    fn add_tombstone(&mut self, tombstone: Tombstone) {
+        // The tombstone may be added again during recovery.
+        if let Err(pos) = self
+            .tombstones
+            .binary_search_by_key(&tombstone.sequence, |t| t.sequence)
+        {
+            self.tombstones.insert(pos, tombstone);
+        }
+    }
+
+    fn remove_tombstone(&mut self, sequence: SequenceNumber) {
+        self.tombstones.retain(|t| t.sequence != sequence);
+    }
+
     fn memtable_for_write(
         &self,
         _write_lock: &WorkerLocal,
@@ -546,6 +565,7 @@ impl TableVersion {
                 memtable_view: MemTableView::new(),
                 levels: LevelsController::new(purge_queue),
                 flushed_sequence: 0,
+                tombstones: Vec::new(),
             }),
         }
     }
@@ -686,6 +706,14 @@ impl TableVersion {
         for mem_id in edit.mems_to_remove {
             inner.memtable_view.remove_immutable_or_sampling(mem_id);
         }
+
+        for tombstone in edit.tombstones_to_add {
+            inner.add_tombstone(tombstone);
+        }
+
+        for sequence in edit.tombstones_to_delete {
+            inner.remove_tombstone(sequence);
+        }
     }
 
     /// Atomically apply the meta to the version, useful in recover.
@@ -697,6 +725,149 @@ impl TableVersion {
         for add_file in meta.files.into_values() {
             inner.levels.add_sst_to_level(add_file.level, add_file.file);
         }
+
+        for tombstone in meta.tombstones.into_values() {
+            inner.add_tombstone(tombstone);
+        }
+    }
+
+    /// Returns all the tombstones of this version, ordered by sequence.
+    pub fn tombstones(&self) -> Vec<Tombstone> {
+        self.inner.read().unwrap().tombstones.clone()
+    }
+
+    /// Returns sequences of the tombstones no longer needed by any sst.
+    ///
+    /// A tombstone is useless if all the ssts are written after it as the
+    /// memtables are always flushed before adding a tombstone.
+    pub fn dead_tombstones(&self) -> Vec<SequenceNumber> {
+        let inner = self.inner.read().unwrap();
+        if inner.tombstones.is_empty() {
+            return Vec::new();
+        }
+
+        let mut sst_max_sequences = Vec::new();
+        for level in 0..inner.levels.num_levels() {
+            sst_max_sequences.extend(
+                inner
+                    .levels
+                    .iter_ssts_at_level(level)
+                    .map(|f| f.max_sequence()),
+            );
+        }
+
+        inner
+            .tombstones
+            .iter()
+            .filter(|t| {
+                !tombstone::is_tombstone_alive(t.sequence, sst_max_sequences.iter().copied())
+            })
+            .map(|t| t.sequence)
+            .collect()
+    }
+
+    /// Returns the info of all the ssts of this version, `table_id` is the id
+    /// of the table owning this version.
+    pub fn sst_files(&self, table_id: TableId) -> Vec<SstFileInfo> {
+        let inner = self.inner.read().unwrap();
+
+        let mut files = Vec::new();
+        for level in 0..inner.levels.num_levels() {
+            files.extend(inner.levels.iter_ssts_at_level(level).map(|f| SstFileInfo {
+                table_id,
+                level,
+                file_id: f.id(),
+                time_range: f.time_range(),
+                size: f.size(),
+                row_num: f.row_num(),
+                max_sequence: f.max_sequence(),
+                being_compacted: f.being_compacted(),
+            }));
+        }
+
+        files
+    }
+
+    /// Returns true if the memtables may contain rows in the `time_range`.
+    pub fn memtables_intersect_with(&self, time_range: TimeRange) -> bool {
+        let inner = self.inner.read().unwrap();
+
+        let mut memtables = MemTableVec::new();
+        let mut sampling_mem = None;
+        inner
+            .memtable_view
+            .memtables_for_read(time_range, &mut memtables, &mut sampling_mem);
+
+        !memtables.is_empty() || sampling_mem.is_some()
+    }
+
+    /// Returns the ids of the source files rolled up into the ssts of this
+    /// version.
+    pub fn rollup_source_files(&self) -> HashSet<FileId> {
+        let inner = self.inner.read().unwrap();
+
+        let mut files = HashSet::new();
+        for level in 0..inner.levels.num_levels() {
+            for sst in inner.levels.iter_ssts_at_level(level) {
+                files.extend(sst.rollup_source_files().iter().copied());
+            }
+        }
+
+        files
+    }
+
+    /// Returns the min timestamp of the rows not rolled up yet, or None if all
+    /// the rows are rolled up. The ssts in `rolled_up_files` are rolled up
+    /// even if they are not marked as rolled up.
+    pub fn min_timestamp_not_rolled_up(
+        &self,
+        rolled_up_files: &HashSet<FileId>,
+    ) -> Option<Timestamp> {
+        let inner = self.inner.read().unwrap();
+
+        let mut memtables = MemTableVec::new();
+        let mut sampling_mem = None;
+        inner.memtable_view.memtables_for_read(
+            TimeRange::min_to_max(),
+            &mut memtables,
+            &mut sampling_mem,
+        );
+        // The sampling memtable may contain rows of any time.
+        if sampling_mem.map_or(false, |v| v.mem.num_rows() > 0) {
+            return Some(Timestamp::MIN);
+        }
+
+        let mut min_timestamp = memtables
+            .iter()
+            .filter(|v| v.mem.num_rows() > 0)
+            .map(|v| v.time_range.inclusive_start())
+            .min();
+        for level in 0..inner.levels.num_levels() {
+            let sst_min_timestamp = inner
+                .levels
+                .iter_ssts_at_level(level)
+                .filter(|f| !f.rolled_up() && !rolled_up_files.contains(&f.id()))
+                .map(|f| f.time_range().inclusive_start())
+                .min();
+            min_timestamp = match (min_timestamp, sst_min_timestamp) {
+                (Some(a), Some(b)) => Some(cmp::min(a, b)),
+                (a, b) => a.or(b),
+            };
+        }
+
+        min_timestamp
+    }
+
+    /// Returns the ids of all the ssts of this version.
+    pub fn sst_file_ids(&self) -> HashSet<FileId> {
+        let inner = self.inner.read().unwrap();
+
+        let mut files = HashSet::new();
+        for level in 0..inner.levels.num_levels() {
+            files.extend(inner.levels.iter_ssts_at_level(level).map(|f| f.id()));
+        }
+
+        files
     }
 
     pub fn pick_read_view(&self, time_range: TimeRange) -> ReadView {
@@ -757,6 +928,7 @@ pub struct TableVersionMeta {
     pub flushed_sequence: SequenceNumber,
     files: HashMap<FileId, AddFile>,
     max_file_id: FileId,
+    tombstones: BTreeMap<SequenceNumber, Tombstone>,
 }
 
 impl TableVersionMeta {
@@ -772,6 +944,14 @@ impl TableVersionMeta {
         for delete_file in edit.files_to_delete {
             self.files.remove(&delete_file.file_id);
         }
+
+        for tombstone in edit.tombstones_to_add {
+            self.tombstones.insert(tombstone.sequence, tombstone);
+        }
+
+        for sequence in edit.tombstones_to_delete {
+            self.tombstones.remove(&sequence);
+        }
     }
 
     /// Returns the max file id in the files to add.
@@ -785,6 +965,11 @@ impl TableVersionMeta {
 
         files_vec
     }
+
+    /// Returns the tombstones ordered by sequence.
+    pub fn ordered_tombstones(&self) -> Vec<Tombstone> {
+        self.tombstones.values().cloned().collect()
+    }
 }
 
 #[cfg(test)]
@@ -1082,6 +1267,8 @@ mod tests {
             mems_to_remove: vec![memtable_id1, memtable_id2],
             files_to_add: vec![add_file],
             files_to_delete: vec![],
+            tombstones_to_add: vec![],
+            tombstones_to_delete: vec![],
         };
         version.apply_edit(edit);
 
@@ -1093,4 +1280,44 @@ mod tests {
         assert_eq!(1, read_view.leveled_ssts[0].len());
         assert_eq!(file_id, read_view.leveled_ssts[0][0].id());
     }
+
+    #[test]
+    fn test_min_timestamp_not_rolled_up() {
+        let version = new_table_version();
+        let schema = MemTableMocker::default().build().schema().clone();
+        assert_eq!(None, version.min_timestamp_not_rolled_up(&HashSet::new()));
+
+        let add_file = |file_id, start, rolled_up| {
+            let mut sst_meta = SstMetaDataMocker::new(schema.clone())
+                .time_range(TimeRange::new_unchecked_for_test(start, start + 1000))
+                .build();
+            sst_meta.rolled_up = rolled_up;
+            AddFileMocker::new(sst_meta).file_id(file_id).build()
+        };
+        let edit = VersionEdit {
+            flushed_sequence: 1,
+            mems_to_remove: vec![],
+            files_to_add: vec![
+                add_file(1, 1000, true),
+                add_file(2, 2000, false),
+                add_file(3, 3000, false),
+            ],
+            files_to_delete: vec![],
+            tombstones_to_add: vec![],
+            tombstones_to_delete: vec![],
+        };
+        version.apply_edit(edit);
+
+        assert_eq!(
+            Some(Timestamp::new(2000)),
+            version.min_timestamp_not_rolled_up(&HashSet::new())
+        );
+        let rolled_up_files = vec![2].into_iter().collect();
+        assert_eq!(
+            Some(Timestamp::new(3000)),
+            version.min_timestamp_not_rolled_up(&rolled_up_files)
+        );
+        let rolled_up_files = vec![2, 3].into_iter().collect();
+        assert_eq!(None, version.min_timestamp_not_rolled_up(&rolled_up_files));
+    }
 }
@@ -6,15 +6,16 @@ use std::convert::{TryFrom, TryInto};
 
 use common_types::{bytes::Bytes, schema::Schema, time::TimeRange, SequenceNumber};
 use common_util::define_result;
-use proto::meta_update as meta_pb;
+use proto::{meta_update as meta_pb, sst::ColumnStats as ColumnStatsPb};
 use snafu::{Backtrace, ResultExt, Snafu};
 
 use crate::{
     sst::{
-        file::{FileMeta, SstMetaData},
+        bloom_filter::SstBloomFilter,
+        file::{self, FileMeta, SstMetaData},
         manager::FileId,
     },
-    table::data::MemTableId,
+    table::{data::MemTableId, tombstone::Tombstone},
 };
 
 #[derive(Debug, Snafu)]
@@ -31,6 +32,9 @@ pub enum Error {
 
     #[snafu(display("Fail to convert table schema, err:{}", source))]
     ConvertTableSchema { source: common_types::schema::Error },
+
+    #[snafu(display("Fail to convert column stats, err:{}", source))]
+    ConvertColumnStats { source: crate::sst::file::Error },
 }
 
 define_result!(Error);
@@ -57,6 +61,16 @@ impl AddFile {
         target.set_schema(self.file.meta.schema.into());
         target.set_size(self.file.meta.size);
         target.set_row_num(self.file.meta.row_num);
+        let column_stats: Vec<_> = self
+            .file
+            .meta
+            .column_stats
+            .into_iter()
+            .map(ColumnStatsPb::from)
+            .collect();
+        target.column_stats = column_stats.into();
+        target.set_rolled_up(self.file.meta.rolled_up);
+        target.set_rollup_source_files(self.file.meta.rollup_source_files);
 
         target
     }
@@ -68,6 +82,8 @@ impl TryFrom<meta_pb::AddFileMeta> for AddFile {
     fn try_from(mut src: meta_pb::AddFileMeta) -> Result<Self> {
         let time_range = TimeRange::try_from(src.take_time_range()).context(ConvertTimeRange)?;
         let schema = Schema::try_from(src.take_schema()).context(ConvertTableSchema)?;
+        let column_stats = file::column_stats_from_pb(src.take_column_stats().into_vec(), &schema)
+            .context(ConvertColumnStats)?;
         Ok(Self {
             level: src
                 .level
@@ -83,6 +99,12 @@ impl TryFrom<meta_pb::AddFileMeta> for AddFile {
                     schema,
                     size: src.size,
                     row_num: src.row_num,
+                    column_stats,
+                    // The bloom filter and tag index are only stored in the sst file.
+                    bloom_filter: SstBloomFilter::default(),
+                    tag_index: None,
+                    rolled_up: src.rolled_up,
+                    rollup_source_files: src.rollup_source_files,
                 },
             },
         })
@@ -138,6 +160,10 @@ pub struct VersionEdit {
     pub files_to_add: Vec<AddFile>,
     /// Sst files to delete.
     pub files_to_delete: Vec<DeleteFile>,
+    /// Tombstones to add.
+    pub tombstones_to_add: Vec<Tombstone>,
+    /// Sequences of the tombstones to delete.
+    pub tombstones_to_delete: Vec<SequenceNumber>,
 }
 
 #[cfg(test)]
@@ -12,15 +12,21 @@ use common_util::{
     time::DurationExt,
 };
 use proto::analytic_common::{
-    CompactionOptions as CompactionOptionsPb, CompactionStrategy as CompactionStrategyPb,
-    Compression as CompressionPb, TableOptions as TableOptionsPb, UpdateMode as UpdateModePb,
+    AggregateFunction as AggregateFunctionPb, CompactionOptions as CompactionOptionsPb,
+    CompactionStrategy as CompactionStrategyPb, Compression as CompressionPb,
+    MemTableType as MemTableTypePb, RollupAggregate as RollupAggregatePb,
+    RollupOptions as RollupOptionsPb, TableOptions as TableOptionsPb, UpdateMode as UpdateModePb,
 };
 use serde_derive::Deserialize;
-use snafu::{Backtrace, GenerateBacktrace, ResultExt, Snafu};
-use table_engine::OPTION_KEY_ENABLE_TTL;
+use snafu::{ensure, Backtrace, GenerateBacktrace, ResultExt, Snafu};
+use table_engine::{
+    rollup::{AggregateFunction, RollupAggregate, RollupOptions},
+    OPTION_KEY_ENABLE_TTL,
+};
 
 use crate::compaction::{
-    CompactionStrategy, SizeTieredCompactionOptions, TimeWindowCompactionOptions,
+    CompactionStrategy, LeveledCompactionOptions, SizeTieredCompactionOptions,
+    TimeWindowCompactionOptions,
 };
 
 pub const SEGMENT_DURATION: &str = "segment_duration";
@@ -32,6 +38,8 @@ pub const COMPACTION_STRATEGY: &str = "compaction_strategy";
 pub const NUM_ROWS_PER_ROW_GROUP: &str = "num_rows_per_row_group";
 pub const UPDATE_MODE: &str = "update_mode";
 pub const COMPRESSION: &str = "compression";
+pub const ENABLE_INVERTED_INDEX: &str = "enable_inverted_index";
+pub const MEMTABLE_TYPE: &str = "memtable_type";
 
 const UPDATE_MODE_OVERWRITE: &str = "OVERWRITE";
 const UPDATE_MODE_APPEND: &str = "APPEND";
@@ -39,10 +47,10 @@ const COMPRESSION_UNCOMPRESSED: &str = "UNCOMPRESSED";
 const COMPRESSION_LZ4: &str = "LZ4";
 const COMPRESSION_SNAPPY: &str = "SNAPPY";
 const COMPRESSION_ZSTD: &str = "ZSTD";
+const MEMTABLE_TYPE_SKIPLIST: &str = "SKIPLIST";
+const MEMTABLE_TYPE_COLUMNAR: &str = "COLUMNAR";
 const AT_LEAST_OPTIONS_NUM: usize = 9;
 
-/// Default bucket duration (1d)
-const BUCKET_DURATION_1D: Duration = Duration::from_secs(24 * 60 * 60);
 /// Default duration of a segment (2h).
 pub const DEFAULT_SEGMENT_DURATION: Duration = Duration::from_secs(60 * 60 * 2);
 /// Default arena block size (2M).
@@ -54,6 +62,9 @@ const DEFAULT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
 /// Default row number of a row group.
 const DEFAULT_NUM_ROW_PER_ROW_GROUP: usize = 8192;
 
+/// Min ttl of table (1m), the ttl is also aligned to this granularity.
+const MIN_TTL: Duration = Duration::from_secs(60);
+
 /// Max arena block size (2G)
 const MAX_ARENA_BLOCK_SIZE: u32 = 2 * 1024 * 1024 * 1024;
 /// Min arena block size (1K)
@@ -97,6 +108,21 @@ pub enum Error {
         backtrace
     ))]
     ParseCompressionName { name: String, backtrace: Backtrace },
+    #[snafu(display(
+        "Failed to parse memtable type, raw str:{}.\nBacktrace:\n{}",
+        s,
+        backtrace
+    ))]
+    ParseMemTableType { s: String, backtrace: Backtrace },
+    #[snafu(display(
+        "Columnar memtable requires append update mode.\nBacktrace:\n{}",
+        backtrace
+    ))]
+    ColumnarMemTableNotAppend { backtrace: Backtrace },
+    #[snafu(display("Failed to parse rollup options, err:{}", source))]
+    ParseRollup { source: table_engine::rollup::Error },
+    #[snafu(display("Rollup requires append update mode.\nBacktrace:\n{}", backtrace))]
+    RollupNotAppend { backtrace: Backtrace },
 }
 
 define_result!(Error);
@@ -128,6 +154,54 @@ impl ToString for UpdateMode {
     }
 }
 
+/// Implementation of the memtables of a table.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+pub enum MemTableType {
+    /// Sorted by key on write, supports all the update modes.
+    Skiplist,
+    /// Sorted by key on read, only supports the append update mode.
+    Columnar,
+}
+
+impl MemTableType {
+    pub fn parse_from(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case(MEMTABLE_TYPE_SKIPLIST) {
+            Ok(MemTableType::Skiplist)
+        } else if s.eq_ignore_ascii_case(MEMTABLE_TYPE_COLUMNAR) {
+            Ok(MemTableType::Columnar)
+        } else {
+            ParseMemTableType { s }.fail()
+        }
+    }
+}
+
+impl ToString for MemTableType {
+    fn to_string(&self) -> String {
+        match self {
+            MemTableType::Skiplist => MEMTABLE_TYPE_SKIPLIST.to_string(),
+            MemTableType::Columnar => MEMTABLE_TYPE_COLUMNAR.to_string(),
+        }
+    }
+}
+
+impl From<MemTableType> for MemTableTypePb {
+    fn from(memtable_type: MemTableType) -> Self {
+        match memtable_type {
+            MemTableType::Skiplist => MemTableTypePb::SKIPLIST,
+            MemTableType::Columnar => MemTableTypePb::COLUMNAR,
+        }
+    }
+}
+
+impl From<MemTableTypePb> for MemTableType {
+    fn from(memtable_type: MemTableTypePb) -> Self {
+        match memtable_type {
+            MemTableTypePb::SKIPLIST => MemTableType::Skiplist,
+            MemTableTypePb::COLUMNAR => MemTableType::Columnar,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
 pub enum Compression {
     Uncompressed,
@@ -208,6 +282,8 @@ pub struct TableOptions {
     pub segment_duration: Option<ReadableDuration>,
     /// Table update mode, now support Overwrite(Default) and Append
     pub update_mode: UpdateMode,
+    /// Memtable implementation, the columnar memtable requires Append mode
+    pub memtable_type: MemTableType,
 
     // The following options can be altered.
     /// Enable ttl
@@ -224,6 +300,11 @@ pub struct TableOptions {
     pub num_rows_per_row_group: usize,
     /// Table Compression
     pub compression: Compression,
+    /// Build inverted index of the tag columns in the memtables and ssts
+    pub enable_inverted_index: bool,
+    /// Rollup the rows into the rollup table during compaction, None if the
+    /// table has no rollup.
+    pub rollup: Option<RollupOptions>,
 }
 
 impl TableOptions {
@@ -251,6 +332,7 @@ impl TableOptions {
                 .unwrap_or_else(String::new),
         );
         m.insert(UPDATE_MODE.to_string(), self.update_mode.to_string());
+        m.insert(MEMTABLE_TYPE.to_string(), self.memtable_type.to_string());
         m.insert(ENABLE_TTL.to_string(), self.enable_ttl.to_string());
         m.insert(TTL.to_string(), format!("{}", self.ttl));
         m.insert(
@@ -267,6 +349,13 @@ impl TableOptions {
             format!("{}", self.num_rows_per_row_group),
         );
         m.insert(COMPRESSION.to_string(), self.compression.to_string());
+        m.insert(
+            ENABLE_INVERTED_INDEX.to_string(),
+            self.enable_inverted_index.to_string(),
+        );
+        if let Some(rollup) = &self.rollup {
+            rollup.fill_raw_map(&mut m);
+        }
 
         assert!(m.len() >= AT_LEAST_OPTIONS_NUM);
 
@@ -275,8 +364,6 @@ impl TableOptions {
 
     /// Sanitize options silently.
     pub fn sanitize(&mut self) {
-        let one_day_secs = BUCKET_DURATION_1D.as_secs();
-
         if let Some(segment_duration) = self.segment_duration {
             let mut segment_duration_secs = segment_duration.as_secs();
             if segment_duration_secs == 0 {
@@ -285,10 +372,10 @@ impl TableOptions {
             self.segment_duration = Some(ReadableDuration::secs(segment_duration_secs));
         }
 
-        let ttl_secs = self.ttl.as_secs();
-        // Ttl must align to day.
-        let ttl_secs = ttl_secs / one_day_secs * one_day_secs;
-        self.ttl = ReadableDuration::secs(ttl_secs);
+        // Ttl must align to minute and be at least one minute.
+        let min_ttl_secs = MIN_TTL.as_secs();
+        let ttl_secs = self.ttl.as_secs() / min_ttl_secs * min_ttl_secs;
+        self.ttl = ReadableDuration::secs(ttl_secs.max(min_ttl_secs));
 
         if self.arena_block_size < MIN_ARENA_BLOCK_SIZE {
             self.arena_block_size = MIN_ARENA_BLOCK_SIZE;
@@ -314,8 +401,16 @@ impl TableOptions {
         }
     }
 
+    /// Returns the time before which the rows are expired, None if ttl is
+    /// disabled.
+    #[inline]
+    pub fn expire_time(&self) -> Option<Timestamp> {
+        self.ttl().map(|ttl| Timestamp::expire_time(ttl.0))
+    }
+
     pub fn is_expired(&self, timestamp: Timestamp) -> bool {
-        self.enable_ttl && timestamp.is_expired(Timestamp::expire_time(self.ttl.0))
+        self.expire_time()
+            .map_or(false, |expire_time| timestamp.is_expired(expire_time))
     }
 }
 
@@ -369,6 +464,79 @@ impl From<CompactionOptionsPb> for TimeWindowCompactionOptions {
     }
 }
 
+impl From<LeveledCompactionOptions> for CompactionOptionsPb {
+    fn from(opts: LeveledCompactionOptions) -> Self {
+        let mut target = CompactionOptionsPb::new();
+        target.set_level0_file_num_trigger(opts.level0_file_num_trigger as u32);
+        target.set_max_level_base_size(opts.max_level_base_size.0);
+        target.set_level_size_multiplier(opts.level_size_multiplier as u32);
+        target.set_target_file_size(opts.target_file_size.0);
+
+        target
+    }
+}
+
+impl From<CompactionOptionsPb> for LeveledCompactionOptions {
+    fn from(opts: CompactionOptionsPb) -> Self {
+        Self {
+            level0_file_num_trigger: opts.level0_file_num_trigger as usize,
+            max_level_base_size: ReadableSize(opts.max_level_base_size),
+            level_size_multiplier: opts.level_size_multiplier as usize,
+            target_file_size: ReadableSize(opts.target_file_size),
+        }
+    }
+}
+
+fn rollup_options_to_pb(rollup: RollupOptions) -> RollupOptionsPb {
+    let mut target = RollupOptionsPb::new();
+    target.set_interval(rollup.interval.0.as_millis_u64());
+    let aggregates: Vec<_> = rollup
+        .aggregates
+        .into_iter()
+        .map(|aggregate| {
+            let function = match aggregate.function {
+                AggregateFunction::Avg => AggregateFunctionPb::AVG,
+                AggregateFunction::Min => AggregateFunctionPb::MIN,
+                AggregateFunction::Max => AggregateFunctionPb::MAX,
+                AggregateFunction::Sum => AggregateFunctionPb::SUM,
+                AggregateFunction::Count => AggregateFunctionPb::COUNT,
+            };
+            let mut aggregate_pb = RollupAggregatePb::new();
+            aggregate_pb.set_column(aggregate.column);
+            aggregate_pb.set_function(function);
+            aggregate_pb
+        })
+        .collect();
+    target.aggregates = aggregates.into();
+
+    target
+}
+
+fn rollup_options_from_pb(mut rollup: RollupOptionsPb) -> RollupOptions {
+    let aggregates = rollup
+        .take_aggregates()
+        .into_iter()
+        .map(|mut aggregate| {
+            let function = match aggregate.function {
+                AggregateFunctionPb::AVG => AggregateFunction::Avg,
+                AggregateFunctionPb::MIN => AggregateFunction::Min,
+                AggregateFunctionPb::MAX => AggregateFunction::Max,
+                AggregateFunctionPb::SUM => AggregateFunction::Sum,
+                AggregateFunctionPb::COUNT => AggregateFunction::Count,
+            };
+            RollupAggregate {
+                column: aggregate.take_column(),
+                function,
+            }
+        })
+        .collect();
+
+    RollupOptions {
+        interval: Duration::from_millis(rollup.interval).into(),
+        aggregates,
+    }
+}
+
 impl From<TableOptions> for TableOptionsPb {
     fn from(opts: TableOptions) -> Self {
         let mut target = TableOptionsPb::new();
@@ -396,6 +564,10 @@ impl From<TableOptions> for TableOptionsPb {
                 target.set_compaction_strategy(CompactionStrategyPb::TIME_WINDOW);
                 target.set_compaction_options(opts.into());
             }
+            CompactionStrategy::Leveled(opts) => {
+                target.set_compaction_strategy(CompactionStrategyPb::LEVELED);
+                target.set_compaction_options(opts.into());
+            }
         }
 
         match opts.update_mode {
@@ -409,6 +581,11 @@ impl From<TableOptions> for TableOptionsPb {
 
         target.set_write_buffer_size(opts.write_buffer_size);
         target.set_compression(opts.compression.into());
+        target.set_enable_inverted_index(opts.enable_inverted_index);
+        target.set_memtable_type(opts.memtable_type.into());
+        if let Some(rollup) = opts.rollup {
+            target.set_rollup(rollup_options_to_pb(rollup));
+        }
 
         target
     }
@@ -432,6 +609,13 @@ impl From<TableOptionsPb> for TableOptions {
                     .unwrap_or_default();
                 CompactionStrategy::TimeWindow(opts)
             }
+            CompactionStrategyPb::LEVELED => {
+                let opts = opts
+                    .compaction_options
+                    .map(LeveledCompactionOptions::from)
+                    .unwrap_or_default();
+                CompactionStrategy::Leveled(opts)
+            }
         };
 
         let update_mode = match opts.update_mode {
@@ -458,8 +642,11 @@ impl From<TableOptionsPb> for TableOptions {
             compaction_strategy,
             num_rows_per_row_group: opts.num_rows_per_row_group as usize,
             update_mode,
+            memtable_type: opts.memtable_type.into(),
             write_buffer_size: opts.write_buffer_size,
             compression: opts.compression.into(),
+            enable_inverted_index: opts.enable_inverted_index,
+            rollup: opts.rollup.map(rollup_options_from_pb),
         }
     }
 }
@@ -474,8 +661,11 @@ impl Default for TableOptions {
             compaction_strategy: CompactionStrategy::default(),
             num_rows_per_row_group: DEFAULT_NUM_ROW_PER_ROW_GROUP,
             update_mode: UpdateMode::Overwrite,
+            memtable_type: MemTableType::Skiplist,
             write_buffer_size: DEFAULT_WRITE_BUFFER_SIZE,
             compression: Compression::Zstd,
+            enable_inverted_index: false,
+            rollup: None,
         }
     }
 }
@@ -508,6 +698,14 @@ fn merge_table_options(
         if let Some(v) = options.get(UPDATE_MODE) {
             table_opts.update_mode = UpdateMode::parse_from(v)?;
         }
+        if let Some(v) = options.get(MEMTABLE_TYPE) {
+            table_opts.memtable_type = MemTableType::parse_from(v)?;
+        }
+        ensure!(
+            table_opts.memtable_type != MemTableType::Columnar
+                || table_opts.update_mode == UpdateMode::Append,
+            ColumnarMemTableNotAppend
+        );
     }
 
     if let Some(v) = options.get(TTL) {
@@ -534,6 +732,18 @@ fn merge_table_options(
     if let Some(v) = options.get(COMPRESSION) {
         table_opts.compression = Compression::parse_from(v)?;
     }
+    if let Some(v) = options.get(ENABLE_INVERTED_INDEX) {
+        table_opts.enable_inverted_index = v.parse::<bool>().context(ParseBool)?;
+    }
+    if let Some(rollup) = RollupOptions::parse_from(options).context(ParseRollup)? {
+        table_opts.rollup = Some(rollup);
+    }
+    // The ssts are rolled up independently, so the overwritten rows would be
+    // rolled up more than once.
+    ensure!(
+        table_opts.rollup.is_none() || table_opts.update_mode == UpdateMode::Append,
+        RollupNotAppend
+    );
     Ok(table_opts)
 }
 
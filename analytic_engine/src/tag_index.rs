@@ -0,0 +1,414 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Inverted index from the tag values to the tsids of the series
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    sync::{Arc, RwLock},
+};
+
+use common_types::{
+    column_schema::ColumnId,
+    datum::{Datum, DatumView},
+    record_batch::{self, RecordBatchWithKey},
+    row::Row,
+    schema::{Schema, TSID_COLUMN},
+};
+use common_util::codec::{compact::MemCompactEncoder, Encoder};
+use proto::sst::{
+    ColumnTagPostings as ColumnTagPostingsPb, TagIndex as TagIndexPb, TagPostings as TagPostingsPb,
+};
+use table_engine::predicate::Predicate;
+
+/// Set of the tsids pushed down to the scans of the memtables and ssts.
+pub type TsidSetRef = Arc<HashSet<u64>>;
+
+/// Add the series of the `row` to the `tag_index` shared by the writer and
+/// readers of a memtable if it is a new series.
+pub fn index_row(tag_index: &RwLock<TagIndex>, schema: &Schema, row: &Row) {
+    let tsid = match schema.index_of_tsid().and_then(|idx| row[idx].as_u64()) {
+        Some(v) => v,
+        None => return,
+    };
+
+    // Most rows belong to the series already indexed, so check with the read
+    // lock first.
+    if tag_index.read().unwrap().contains_series(tsid) {
+        return;
+    }
+    tag_index.write().unwrap().add_row(schema, row);
+}
+
+/// Inverted index of the tag columns of a memtable or sst.
+///
+/// The index maps the value of every tag column to the tsids of the series
+/// having the value, so the tag predicates can be turned into a set of tsids
+/// without scanning the rows. Only works on the tables with tsid column.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagIndex {
+    /// {column id} => {encoded value} => {tsids}
+    ///
+    /// Keyed by the column id so the index still works after the columns are
+    /// renamed.
+    columns: BTreeMap<ColumnId, HashMap<Vec<u8>, BTreeSet<u64>>>,
+    /// Tsids of all the series in the index.
+    series: HashSet<u64>,
+}
+
+impl TagIndex {
+    #[inline]
+    pub fn contains_series(&self, tsid: u64) -> bool {
+        self.series.contains(&tsid)
+    }
+
+    #[inline]
+    pub fn num_series(&self) -> usize {
+        self.series.len()
+    }
+
+    /// Add the series of the `row` to the index, the row is ignored if the
+    /// `schema` has no tsid column.
+    pub fn add_row(&mut self, schema: &Schema, row: &Row) {
+        self.add_series(schema, |col_idx| row[col_idx].clone());
+    }
+
+    /// Add the series of the rows in `record_batch` to the index, the schema of
+    /// the batch must be the `schema`.
+    pub fn add_record_batch(&mut self, schema: &Schema, record_batch: &RecordBatchWithKey) {
+        for row_idx in 0..record_batch.num_rows() {
+            self.add_series(schema, |col_idx| {
+                record_batch.column(col_idx).datum(row_idx)
+            });
+        }
+    }
+
+    fn add_series(&mut self, schema: &Schema, datum_of_column: impl Fn(usize) -> Datum) {
+        let tsid = match schema
+            .index_of_tsid()
+            .and_then(|idx| datum_of_column(idx).as_u64())
+        {
+            Some(v) => v,
+            None => return,
+        };
+        // All the rows of a series have the same tag values.
+        if !self.series.insert(tsid) {
+            return;
+        }
+
+        let mut value_buf = Vec::new();
+        for (col_idx, column) in schema.columns().iter().enumerate() {
+            if !column.is_tag {
+                continue;
+            }
+            let datum = datum_of_column(col_idx);
+            if datum.is_null() {
+                continue;
+            }
+            encode_value(&datum, &mut value_buf);
+            self.columns
+                .entry(column.id)
+                .or_default()
+                .entry(value_buf.clone())
+                .or_default()
+                .insert(tsid);
+        }
+    }
+
+    /// Returns the tsids of the series may satisfy the equality and IN list
+    /// exprs on the tag columns in the `predicate`.
+    ///
+    /// The column names in the `predicate` are resolved by the `schema`, which
+    /// should be the latest schema of the table.
+    ///
+    /// Returns None if no tag column of the `schema` is restricted by the
+    /// `predicate`, or any restricted column is absent from the index (e.g. the
+    /// column is added after the index is built), in which case the index
+    /// can't tell which series satisfy the `predicate`.
+    pub fn lookup(&self, schema: &Schema, predicate: &Predicate) -> Option<HashSet<u64>> {
+        let mut result: Option<HashSet<u64>> = None;
+        let mut value_buf = Vec::new();
+        for column in schema.columns() {
+            if !column.is_tag {
+                continue;
+            }
+            let values = match predicate.values_of_column(&column.name, column.data_type) {
+                Some(values) => values,
+                None => continue,
+            };
+
+            let postings = self.columns.get(&column.id)?;
+            let mut tsids = HashSet::new();
+            for value in &values {
+                encode_value(value, &mut value_buf);
+                if let Some(v) = postings.get(&value_buf) {
+                    tsids.extend(v.iter().copied());
+                }
+            }
+
+            // The values of all the columns must be satisfied.
+            result = Some(match result {
+                Some(prev) => prev.intersection(&tsids).copied().collect(),
+                None => tsids,
+            });
+        }
+
+        result
+    }
+}
+
+impl From<TagIndex> for TagIndexPb {
+    fn from(src: TagIndex) -> Self {
+        let columns: Vec<_> = src
+            .columns
+            .into_iter()
+            .map(|(column_id, postings)| {
+                let postings: Vec<_> = postings
+                    .into_iter()
+                    .map(|(value, tsids)| {
+                        let mut target = TagPostingsPb::new();
+                        target.set_value(value);
+                        target.set_tsids(tsids.into_iter().collect());
+                        target
+                    })
+                    .collect();
+
+                let mut target = ColumnTagPostingsPb::new();
+                target.set_column_id(column_id);
+                target.postings = postings.into();
+                target
+            })
+            .collect();
+
+        let mut target = TagIndexPb::new();
+        target.columns = columns.into();
+        target
+    }
+}
+
+impl From<TagIndexPb> for TagIndex {
+    fn from(mut src: TagIndexPb) -> Self {
+        let mut index = TagIndex::default();
+        for mut column in src.take_columns().into_iter() {
+            let postings = column
+                .take_postings()
+                .into_iter()
+                .map(|mut postings| {
+                    let tsids: BTreeSet<_> = postings.take_tsids().into_iter().collect();
+                    index.series.extend(tsids.iter().copied());
+                    (postings.take_value(), tsids)
+                })
+                .collect();
+            index.columns.insert(column.column_id, postings);
+        }
+
+        index
+    }
+}
+
+/// Remove the rows of the series not in `tsids` from the `record_batch`, the
+/// batch is unchanged if it has no tsid column.
+///
+/// The `selected_rows_buf` is for reuse.
+pub fn filter_record_batch_by_tsids(
+    record_batch: &mut RecordBatchWithKey,
+    tsids: &HashSet<u64>,
+    selected_rows_buf: &mut Vec<bool>,
+) -> record_batch::Result<()> {
+    let tsid_index = match record_batch.schema_with_key().index_of(TSID_COLUMN) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    selected_rows_buf.clear();
+    let tsid_column = record_batch.column(tsid_index);
+    let mut num_selected = 0;
+    for row_idx in 0..record_batch.num_rows() {
+        let selected = match tsid_column.datum_view(row_idx) {
+            DatumView::UInt64(tsid) => tsids.contains(&tsid),
+            _ => true,
+        };
+        if selected {
+            num_selected += 1;
+        }
+        selected_rows_buf.push(selected);
+    }
+
+    if num_selected == record_batch.num_rows() {
+        return Ok(());
+    }
+    record_batch.select_data(selected_rows_buf)
+}
+
+fn encode_value(datum: &Datum, buf: &mut Vec<u8>) {
+    buf.clear();
+    MemCompactEncoder
+        .encode(buf, datum)
+        .expect("Should encode datum into the buffer successfully");
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_deps::datafusion::logical_plan::{col, lit, Expr};
+    use common_types::{
+        column_schema,
+        datum::DatumKind,
+        schema,
+        string::StringBytes,
+        time::{TimeRange, Timestamp},
+    };
+
+    use super::*;
+    use crate::row_iter::tests::build_record_batch_with_key;
+
+    fn build_tsid_schema() -> Schema {
+        build_tsid_schema_with_tags(&["host", "dc"])
+    }
+
+    /// Build the schema with tag columns named `tags`, the ids of the columns
+    /// are assigned in order.
+    fn build_tsid_schema_with_tags(tags: &[&str]) -> Schema {
+        let mut builder = schema::Builder::new()
+            .auto_increment_column_id(true)
+            .enable_tsid_primary_key(true)
+            .add_key_column(
+                column_schema::Builder::new("tsid".to_string(), DatumKind::UInt64)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_key_column(
+                column_schema::Builder::new("timestamp".to_string(), DatumKind::Timestamp)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+        for tag in tags {
+            builder = builder
+                .add_normal_column(
+                    column_schema::Builder::new(tag.to_string(), DatumKind::String)
+                        .is_tag(true)
+                        .build()
+                        .unwrap(),
+                )
+                .unwrap();
+        }
+
+        builder.build().unwrap()
+    }
+
+    fn build_tsid_row(tsid: u64, ts: i64, host: &str, dc: &str) -> Row {
+        Row::from_datums(vec![
+            Datum::UInt64(tsid),
+            Datum::Timestamp(Timestamp::new(ts)),
+            Datum::String(StringBytes::from(host)),
+            Datum::String(StringBytes::from(dc)),
+        ])
+    }
+
+    fn build_tag_index(schema: &Schema) -> TagIndex {
+        let mut index = TagIndex::default();
+        for row in &[
+            build_tsid_row(1, 1000, "h1", "a"),
+            build_tsid_row(1, 1001, "h1", "a"),
+            build_tsid_row(2, 1000, "h2", "a"),
+            build_tsid_row(3, 1000, "h3", "b"),
+        ] {
+            index.add_row(schema, row);
+        }
+        index
+    }
+
+    fn new_predicate(exprs: Vec<Expr>) -> Predicate {
+        Predicate {
+            exprs,
+            time_range: TimeRange::min_to_max(),
+        }
+    }
+
+    #[test]
+    fn test_tag_index_lookup() {
+        let schema = build_tsid_schema();
+        let index = build_tag_index(&schema);
+        assert_eq!(3, index.num_series());
+        assert!(index.contains_series(2));
+        assert!(!index.contains_series(4));
+
+        let predicate = new_predicate(vec![col("dc").eq(lit("a"))]);
+        assert_eq!(
+            HashSet::from([1, 2]),
+            index.lookup(&schema, &predicate).unwrap()
+        );
+
+        let predicate = new_predicate(vec![
+            col("dc").eq(lit("a")),
+            col("host").in_list(vec![lit("h2"), lit("h3")], false),
+        ]);
+        assert_eq!(
+            HashSet::from([2]),
+            index.lookup(&schema, &predicate).unwrap()
+        );
+
+        let predicate = new_predicate(vec![col("host").eq(lit("h9"))]);
+        assert!(index.lookup(&schema, &predicate).unwrap().is_empty());
+
+        // Exprs on the columns other than the tags are ignored.
+        let predicate = new_predicate(vec![col("tsid").eq(lit(1u64))]);
+        assert!(index.lookup(&schema, &predicate).is_none());
+
+        // Encode to pb and decode back.
+        let decoded = TagIndex::from(TagIndexPb::from(index.clone()));
+        assert_eq!(index, decoded);
+    }
+
+    #[test]
+    fn test_tag_index_lookup_renamed_column() {
+        let index = build_tag_index(&build_tsid_schema());
+
+        // The "dc" column is renamed to "region" with the column id unchanged.
+        let schema = build_tsid_schema_with_tags(&["host", "region"]);
+        let predicate = new_predicate(vec![col("region").eq(lit("a"))]);
+        assert_eq!(
+            HashSet::from([1, 2]),
+            index.lookup(&schema, &predicate).unwrap()
+        );
+
+        // The old name is not a column of the schema any more.
+        let predicate = new_predicate(vec![col("dc").eq(lit("a"))]);
+        assert!(index.lookup(&schema, &predicate).is_none());
+    }
+
+    #[test]
+    fn test_tag_index_lookup_column_not_indexed() {
+        let index = build_tag_index(&build_tsid_schema());
+
+        // The "zone" column is added after the index is built.
+        let schema = build_tsid_schema_with_tags(&["host", "dc", "zone"]);
+        let predicate = new_predicate(vec![col("zone").eq(lit("z1"))]);
+        assert!(index.lookup(&schema, &predicate).is_none());
+
+        let predicate = new_predicate(vec![col("host").eq(lit("h1")), col("zone").eq(lit("z1"))]);
+        assert!(index.lookup(&schema, &predicate).is_none());
+    }
+
+    #[test]
+    fn test_filter_record_batch_by_tsids() {
+        let schema = build_tsid_schema();
+        let rows = vec![
+            build_tsid_row(1, 1000, "h1", "a"),
+            build_tsid_row(2, 1000, "h2", "a"),
+            build_tsid_row(3, 1000, "h3", "b"),
+        ];
+        let mut record_batch = build_record_batch_with_key(schema, rows);
+
+        let mut selected_rows_buf = Vec::new();
+        filter_record_batch_by_tsids(
+            &mut record_batch,
+            &HashSet::from([1, 3]),
+            &mut selected_rows_buf,
+        )
+        .unwrap();
+        assert_eq!(2, record_batch.num_rows());
+        assert_eq!(Datum::UInt64(1), record_batch.column(0).datum(0));
+        assert_eq!(Datum::UInt64(3), record_batch.column(0).datum(1));
+    }
+}
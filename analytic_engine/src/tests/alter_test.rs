@@ -6,7 +6,7 @@ use std::collections::{BTreeMap, HashMap};
 
 use common_types::{
     column_schema,
-    datum::DatumKind,
+    datum::{Datum, DatumKind},
     row::{RowGroup, RowGroupBuilder},
     schema::{self, Schema},
     time::Timestamp,
@@ -74,6 +74,304 @@ fn test_alter_table_add_column() {
     });
 }
 
+#[test]
+fn test_alter_table_drop_and_rename_column() {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context();
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table1 = "test_table1";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table1).await;
+
+        let start_ms = test_ctx.start_ms();
+        let rows = [
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+            ),
+        ];
+
+        // Write data to table and flush it to sst.
+        let row_group = fixed_schema_table.rows_to_row_group(&rows);
+        test_ctx.write_to_table(test_table1, row_group).await;
+        test_ctx.flush_table(test_table1).await;
+
+        // Rename double_field1 to renamed_field1, then drop string_field2 and add
+        // it back.
+        let old_schema = test_ctx.table(test_table1).schema();
+        let mut schema_builder = schema::Builder::new()
+            .version(old_schema.version() + 1)
+            .max_column_id(old_schema.max_column_id());
+        for key_column in old_schema.key_columns() {
+            schema_builder = schema_builder.add_key_column(key_column.clone()).unwrap();
+        }
+        for normal_column in old_schema.normal_columns() {
+            let mut column = normal_column.clone();
+            match column.name.as_str() {
+                "double_field1" => column.name = "renamed_field1".to_string(),
+                "string_field2" => continue,
+                _ => (),
+            }
+            schema_builder = schema_builder.add_normal_column(column).unwrap();
+        }
+        let new_schema = schema_builder
+            .auto_increment_column_id(true)
+            .add_normal_column(
+                column_schema::Builder::new("string_field2".to_string(), DatumKind::String)
+                    .is_nullable(true)
+                    .build()
+                    .expect("should succeed build column schema"),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(
+            new_schema.column_with_name("string_field2").unwrap().id > old_schema.max_column_id()
+        );
+
+        let request = AlterSchemaRequest {
+            schema: new_schema.clone(),
+            pre_schema_version: old_schema.version(),
+        };
+        let affected = test_ctx
+            .try_alter_schema(test_table1, request)
+            .await
+            .unwrap();
+        assert_eq!(1, affected);
+
+        // The renamed column is read by its id and the data of the dropped column
+        // is hidden.
+        let new_schema_rows = [
+            row_util::new_row_6((
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                Null,
+            )),
+            row_util::new_row_6((
+                "key2",
+                Timestamp::new(start_ms),
+                "tag1-2",
+                12.0,
+                110.0,
+                Null,
+            )),
+        ];
+        let new_schema_row_group =
+            RowGroupBuilder::with_rows(new_schema.clone(), new_schema_rows.to_vec())
+                .unwrap()
+                .build();
+        check_read_row_group(
+            &test_ctx,
+            "Test read after drop and rename columns",
+            test_table1,
+            &new_schema,
+            &new_schema_row_group,
+        )
+        .await;
+
+        // Compact the ssts to remove the dropped column.
+        test_ctx.compact_table(test_table1).await;
+        check_read_row_group(
+            &test_ctx,
+            "Test read after compaction",
+            test_table1,
+            &new_schema,
+            &new_schema_row_group,
+        )
+        .await;
+
+        // Reopen db.
+        test_ctx.reopen_with_tables(&[test_table1]).await;
+        assert_eq!(new_schema, test_ctx.table(test_table1).schema());
+        check_read_row_group(
+            &test_ctx,
+            "Test read after reopen",
+            test_table1,
+            &new_schema,
+            &new_schema_row_group,
+        )
+        .await;
+    });
+}
+
+#[test]
+fn test_alter_table_add_column_with_default_value() {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context();
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table1 = "test_table1";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table1).await;
+
+        let start_ms = test_ctx.start_ms();
+        let rows = [
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+            ),
+        ];
+
+        // Write data to table and flush it to sst.
+        let row_group = fixed_schema_table.rows_to_row_group(&rows);
+        test_ctx.write_to_table(test_table1, row_group).await;
+        test_ctx.flush_table(test_table1).await;
+
+        // Add a not null column and a nullable column with default values.
+        let old_schema = test_ctx.table(test_table1).schema();
+        let new_schema = FixedSchemaTable::default_schema_builder()
+            .version(old_schema.version() + 1)
+            .add_normal_column(
+                column_schema::Builder::new("add_string".to_string(), DatumKind::String)
+                    .is_nullable(false)
+                    .default_value(Some(Datum::String("default".into())))
+                    .build()
+                    .expect("should succeed build column schema"),
+            )
+            .unwrap()
+            .add_normal_column(
+                column_schema::Builder::new("add_double".to_string(), DatumKind::Double)
+                    .is_nullable(true)
+                    .default_value(Some(Datum::Double(1.5)))
+                    .build()
+                    .expect("should succeed build column schema"),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let request = AlterSchemaRequest {
+            schema: new_schema.clone(),
+            pre_schema_version: old_schema.version(),
+        };
+        let affected = test_ctx
+            .try_alter_schema(test_table1, request)
+            .await
+            .unwrap();
+        assert_eq!(1, affected);
+
+        // Write data with the old schema to the memtable.
+        let rows = [
+            (
+                "key1",
+                Timestamp::new(start_ms + 10),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms + 10),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+            ),
+        ];
+        let row_group = fixed_schema_table.rows_to_row_group(&rows);
+        test_ctx.write_to_table(test_table1, row_group).await;
+
+        // The added columns of both the sst and the memtable are filled by the default
+        // values.
+        let new_schema_rows = row_util::new_rows_8(&[
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+                "default",
+                1.5,
+            ),
+            (
+                "key1",
+                Timestamp::new(start_ms + 10),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+                "default",
+                1.5,
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+                "default",
+                1.5,
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms + 10),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+                "default",
+                1.5,
+            ),
+        ]);
+        let new_schema_row_group = RowGroupBuilder::with_rows(new_schema.clone(), new_schema_rows)
+            .unwrap()
+            .build();
+        check_read_row_group(
+            &test_ctx,
+            "Test read after add columns with default value",
+            test_table1,
+            &new_schema,
+            &new_schema_row_group,
+        )
+        .await;
+
+        // Reopen db.
+        test_ctx.reopen_with_tables(&[test_table1]).await;
+        assert_eq!(new_schema, test_ctx.table(test_table1).schema());
+        check_read_row_group(
+            &test_ctx,
+            "Test read after reopen",
+            test_table1,
+            &new_schema,
+            &new_schema_row_group,
+        )
+        .await;
+    });
+}
+
 // Add two columns:
 // - add_string
 // - add_double
@@ -7,6 +7,7 @@ use table_engine::table::FlushRequest;
 
 use crate::{
     compaction::SizeTieredCompactionOptions,
+    table_options,
     tests::util::{self, TestEnv},
 };
 
@@ -88,3 +89,82 @@ fn test_table_compact_current_segment() {
         .await;
     });
 }
+
+#[test]
+fn test_table_leveled_compaction() {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context();
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table1 = "test_table1";
+        // Split the compaction output into ssts of one row and make the level 1
+        // always exceed its size limit.
+        let fixed_schema_table = test_ctx
+            .create_fixed_schema_table_with_options(
+                test_table1,
+                &[
+                    (table_options::COMPACTION_STRATEGY, "leveled"),
+                    ("compaction_level0_file_num_trigger", "2"),
+                    ("compaction_max_level_base_size", "1"),
+                    ("compaction_target_file_size", "1"),
+                ],
+            )
+            .await;
+
+        let mut expect_rows = Vec::new();
+        let start_ms = test_ctx.start_ms();
+        for offset in 0..4 {
+            let rows = [
+                (
+                    "key1",
+                    Timestamp::new(start_ms + offset),
+                    "tag1-1",
+                    11.0,
+                    110.0,
+                    "tag2-1",
+                ),
+                (
+                    "key2",
+                    Timestamp::new(start_ms + offset),
+                    "tag1-2",
+                    12.0,
+                    110.0,
+                    "tag2-2",
+                ),
+            ];
+            expect_rows.extend_from_slice(&rows);
+            let row_group = fixed_schema_table.rows_to_row_group(&rows);
+
+            test_ctx.write_to_table(test_table1, row_group).await;
+
+            test_ctx
+                .flush_table_with_request(
+                    test_table1,
+                    FlushRequest {
+                        compact_after_flush: false,
+                        sync: true,
+                    },
+                )
+                .await;
+        }
+
+        expect_rows.sort_unstable_by_key(|row_tuple| (row_tuple.0, row_tuple.1));
+
+        // The first compaction merges the ssts of level 0 into level 1, and the
+        // second one pushes an sst of level 1 down to level 2.
+        for _ in 0..2 {
+            test_ctx.compact_table(test_table1).await;
+
+            util::check_read(
+                &test_ctx,
+                &fixed_schema_table,
+                "Test read after leveled compaction",
+                test_table1,
+                &expect_rows,
+            )
+            .await;
+        }
+    });
+}
@@ -0,0 +1,34 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Create table tests
+
+use table_engine::table::{TableId, TableSeq};
+
+use crate::tests::util::TestEnv;
+
+#[test]
+fn test_create_table_with_conflicting_name() {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context();
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table1 = "test_table1";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table1).await;
+
+        // Another table with the same name but different id.
+        let mut request = fixed_schema_table.create_request().clone();
+        let conflict_id = TableId::new(test_ctx.schema_id, TableSeq::from(1000));
+        request.table_id = conflict_id;
+        assert!(test_ctx.engine().create_table(request).await.is_err());
+
+        // The conflicting table is not persisted.
+        test_ctx.reopen_with_tables(&[test_table1]).await;
+        let table_opt = test_ctx
+            .try_open_table(conflict_id, test_table1)
+            .await
+            .unwrap();
+        assert!(table_opt.is_none());
+    });
+}
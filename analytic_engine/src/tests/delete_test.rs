@@ -0,0 +1,131 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Delete test.
+
+use common_types::time::{TimeRange, Timestamp};
+use table_engine::table::DeleteRequest;
+
+use crate::tests::util::{self, TestEnv};
+
+#[test]
+fn test_delete_and_read() {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context();
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "test_delete_table";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table).await;
+
+        let start_ms = test_ctx.start_ms();
+        let rows = [
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms + 1),
+                "tag1-2",
+                12.0,
+                110.0,
+                "tag2-2",
+            ),
+            (
+                "key3",
+                Timestamp::new(start_ms + 2),
+                "tag1-3",
+                13.0,
+                110.0,
+                "tag2-3",
+            ),
+        ];
+        let row_group = fixed_schema_table.rows_to_row_group(&rows);
+        test_ctx.write_to_table(test_table, row_group).await;
+
+        // Delete the rows in the memtable.
+        let request = DeleteRequest {
+            time_range: TimeRange::new_unchecked(
+                Timestamp::new(start_ms),
+                Timestamp::new(start_ms + 2),
+            ),
+            tag_filters: Vec::new(),
+        };
+        test_ctx.delete_from_table(test_table, request).await;
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test read after delete",
+            test_table,
+            &rows[2..],
+        )
+        .await;
+
+        // The rows written after the delete are not hidden.
+        let new_rows = [(
+            "key1",
+            Timestamp::new(start_ms),
+            "tag1-4",
+            14.0,
+            110.0,
+            "tag2-4",
+        )];
+        let row_group = fixed_schema_table.rows_to_row_group(&new_rows);
+        test_ctx.write_to_table(test_table, row_group).await;
+        let expect_rows = [new_rows[0], rows[2]];
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test read rows written after delete",
+            test_table,
+            &expect_rows,
+        )
+        .await;
+
+        // Delete the rows not in the memtable, the rows in the memtable are
+        // still visible.
+        let request = DeleteRequest {
+            time_range: TimeRange::new_unchecked(
+                Timestamp::new(start_ms + 100),
+                Timestamp::new(start_ms + 200),
+            ),
+            tag_filters: Vec::new(),
+        };
+        test_ctx.delete_from_table(test_table, request).await;
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test read after delete rows not in memtable",
+            test_table,
+            &expect_rows,
+        )
+        .await;
+
+        // The deleted rows are still hidden after flush, compaction and reopen.
+        test_ctx.flush_table(test_table).await;
+        test_ctx.compact_table(test_table).await;
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test read after compaction",
+            test_table,
+            &expect_rows,
+        )
+        .await;
+
+        test_ctx.reopen_with_tables(&[test_table]).await;
+        util::check_read(
+            &test_ctx,
+            &fixed_schema_table,
+            "Test read after reopen",
+            test_table,
+            &expect_rows,
+        )
+        .await;
+    });
+}
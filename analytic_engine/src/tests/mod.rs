@@ -7,6 +7,10 @@ mod alter_test;
 #[cfg(test)]
 mod compaction_test;
 #[cfg(test)]
+mod create_test;
+#[cfg(test)]
+mod delete_test;
+#[cfg(test)]
 mod drop_test;
 #[cfg(test)]
 mod open_test;
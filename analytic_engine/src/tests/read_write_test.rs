@@ -10,7 +10,10 @@ use table_engine::table::ReadOrder;
 
 use crate::{
     table_options,
-    tests::util::{self, TestEnv},
+    tests::{
+        table,
+        util::{self, TestEnv},
+    },
 };
 
 #[test]
@@ -733,3 +736,78 @@ fn test_table_write_read_reverse_after_flush() {
         .await;
     });
 }
+
+#[test]
+fn test_table_write_read_reverse_with_limit() {
+    let env = TestEnv::builder().build();
+    let mut test_ctx = env.new_context();
+
+    env.block_on(async {
+        test_ctx.open().await;
+
+        let test_table = "test_table";
+        let fixed_schema_table = test_ctx.create_fixed_schema_table(test_table).await;
+
+        let start_ms = test_ctx.start_ms();
+        let rows1 = [
+            (
+                "key1",
+                Timestamp::new(start_ms),
+                "tag1-1",
+                11.0,
+                110.0,
+                "tag2-1",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms),
+                "tag1-3",
+                13.0,
+                110.0,
+                "tag2-3",
+            ),
+        ];
+
+        let rows2 = vec![
+            // update the row of key2
+            (
+                "key2",
+                Timestamp::new(start_ms),
+                "tag1-4",
+                14.0,
+                110.0,
+                "tag2-4",
+            ),
+            (
+                "key2",
+                Timestamp::new(start_ms + 1),
+                "tag1-4",
+                14.0,
+                110.0,
+                "tag2-4",
+            ),
+        ];
+
+        // The updated row is counted only once.
+        let expect_reversed_rows = vec![rows2[1], rows2[0]];
+        let row_group1 = fixed_schema_table.rows_to_row_group(&rows1);
+        // Write data to table and flush
+        test_ctx.write_to_table(test_table, row_group1).await;
+        test_ctx.flush_table(test_table).await;
+
+        let row_group2 = fixed_schema_table.rows_to_row_group(&rows2);
+        // Write data to table and not flush
+        test_ctx.write_to_table(test_table, row_group2).await;
+
+        for read_opts in table::read_opts_list() {
+            info!("Test read reverse with limit, opts:{:?}", read_opts);
+
+            let mut read_request =
+                fixed_schema_table.new_read_all_request(read_opts, ReadOrder::Desc);
+            read_request.limit = Some(2);
+            let record_batches = test_ctx.read_table(test_table, read_request).await;
+
+            fixed_schema_table.assert_batch_eq_to_rows(&record_batches, &expect_reversed_rows);
+        }
+    });
+}
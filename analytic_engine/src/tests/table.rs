@@ -180,6 +180,7 @@ pub fn new_read_all_request_with_order(
         projected_schema: ProjectedSchema::no_projection(schema),
         predicate: Arc::new(Predicate::new(TimeRange::min_to_max())),
         order,
+        limit: None,
     }
 }
 
@@ -285,6 +286,13 @@ impl Builder {
         self
     }
 
+    pub fn option(mut self, key: &str, value: &str) -> Self {
+        self.create_request
+            .options
+            .insert(key.to_string(), value.to_string());
+        self
+    }
+
     pub fn build_fixed(self) -> FixedSchemaTable {
         FixedSchemaTable {
             create_request: self.create_request,
@@ -19,8 +19,8 @@ use table_engine::{
         Result as EngineResult, TableEngineRef,
     },
     table::{
-        AlterSchemaRequest, FlushRequest, GetRequest, ReadOrder, ReadRequest, Result, SchemaId,
-        TableId, TableRef, WriteRequest,
+        AlterSchemaRequest, DeleteRequest, FlushRequest, GetRequest, ReadOrder, ReadRequest,
+        Result, SchemaId, TableId, TableRef, WriteRequest,
     },
 };
 use tempfile::TempDir;
@@ -167,6 +167,7 @@ impl TestContext {
                 table_name: table_name.to_string(),
                 table_id,
                 engine: table_engine::ANALYTIC_ENGINE_TYPE.to_string(),
+                partition_info: None,
             })
             .await
             .unwrap()
@@ -189,6 +190,7 @@ impl TestContext {
                 table_name: table_name.to_string(),
                 table_id,
                 engine: table_engine::ANALYTIC_ENGINE_TYPE.to_string(),
+                partition_info: None,
             })
             .await?;
 
@@ -210,6 +212,7 @@ impl TestContext {
             schema_id: self.schema_id,
             table_name: table_name.to_string(),
             engine: table_engine::ANALYTIC_ENGINE_TYPE.to_string(),
+            partition_info: None,
         };
 
         let ret = self.engine().drop_table(request).await.unwrap();
@@ -225,12 +228,24 @@ impl TestContext {
     }
 
     pub async fn create_fixed_schema_table(&mut self, table_name: &str) -> FixedSchemaTable {
-        let fixed_schema_table = FixedSchemaTable::builder()
+        self.create_fixed_schema_table_with_options(table_name, &[])
+            .await
+    }
+
+    pub async fn create_fixed_schema_table_with_options(
+        &mut self,
+        table_name: &str,
+        options: &[(&str, &str)],
+    ) -> FixedSchemaTable {
+        let mut builder = FixedSchemaTable::builder()
             .schema_id(self.schema_id)
             .table_name(table_name.to_string())
             .table_id(self.next_table_id())
-            .ttl("7d".parse::<ReadableDuration>().unwrap())
-            .build_fixed();
+            .ttl("7d".parse::<ReadableDuration>().unwrap());
+        for (key, value) in options {
+            builder = builder.option(key, value);
+        }
+        let fixed_schema_table = builder.build_fixed();
 
         self.create_table(fixed_schema_table.create_request().clone())
             .await;
@@ -251,6 +266,12 @@ impl TestContext {
         table.write(WriteRequest { row_group }).await.unwrap();
     }
 
+    pub async fn delete_from_table(&self, table_name: &str, request: DeleteRequest) {
+        let table = self.table(table_name);
+
+        table.delete(request).await.unwrap();
+    }
+
     pub async fn read_table(
         &self,
         table_name: &str,
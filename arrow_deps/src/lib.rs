@@ -12,3 +12,5 @@ pub mod util;
 pub use arrow;
 pub use datafusion;
 pub use parquet;
+pub use parquet_format;
+pub use thrift;
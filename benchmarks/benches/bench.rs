@@ -12,6 +12,7 @@ use benchmarks::{
     parquet_bench::ParquetBench,
     scan_memtable_bench::ScanMemTableBench,
     sst_bench::SstBench,
+    write_bench::WriteBench,
 };
 use criterion::*;
 
@@ -196,6 +197,30 @@ fn bench_arrow2(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_write_iter(b: &mut Bencher<'_>, bench: &WriteBench) {
+    b.iter(|| bench.run_bench())
+}
+
+fn bench_write(c: &mut Criterion) {
+    let config = init_bench();
+
+    let mut group = c.benchmark_group("write");
+
+    group.measurement_time(config.write_bench.bench_measurement_time.0);
+    group.sample_size(config.write_bench.bench_sample_size);
+
+    let bench = WriteBench::new(config.write_bench);
+
+    group.throughput(Throughput::Elements(bench.rows_per_run() as u64));
+    group.bench_with_input(
+        BenchmarkId::new("write", bench.rows_per_run()),
+        &bench,
+        bench_write_iter,
+    );
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_read_sst,
@@ -204,5 +229,6 @@ criterion_group!(
     bench_scan_memtable,
     bench_merge_memtable,
     bench_arrow2,
+    bench_write,
 );
 criterion_main!(benches);
@@ -21,6 +21,7 @@ pub struct BenchConfig {
     pub merge_sst_bench: MergeSstBenchConfig,
     pub scan_memtable_bench: ScanMemTableBenchConfig,
     pub merge_memtable_bench: MergeMemTableBenchConfig,
+    pub write_bench: WriteBenchConfig,
 }
 
 // TODO(yingwen): Maybe we can use layze static to load config first.
@@ -121,3 +122,20 @@ pub struct MergeMemTableBenchConfig {
 
     pub arena_block_size: ReadableSize,
 }
+
+#[derive(Deserialize)]
+pub struct WriteBenchConfig {
+    pub table_id: TableId,
+    pub runtime_thread_num: usize,
+
+    pub bench_measurement_time: ReadableDuration,
+    pub bench_sample_size: usize,
+
+    /// Number of rows in each write request.
+    pub batch_size: usize,
+    /// Number of write requests sent to the table concurrently.
+    pub concurrency: usize,
+
+    /// Config of the analytic engine to write.
+    pub engine: analytic_engine::Config,
+}
@@ -28,7 +28,7 @@ use common_types::{
 use common_util::runtime::Runtime;
 use log::info;
 use object_store::LocalFileSystem;
-use parquet::{DataCacheRef, MetaCacheRef};
+use parquet::MetaCacheRef;
 use table_engine::{predicate::Predicate, table::TableId};
 
 use crate::{config::MergeMemTableBenchConfig, util};
@@ -56,16 +56,10 @@ impl MergeMemTableBench {
         let table_id = config.table_id;
 
         let meta_cache: Option<MetaCacheRef> = None;
-        let data_cache: Option<DataCacheRef> = None;
 
         // Use first sst's schema.
         let sst_path = sst_util::new_sst_file_path(space_id, table_id, config.sst_file_ids[0]);
-        let schema = runtime.block_on(util::schema_from_sst(
-            &store,
-            &sst_path,
-            &meta_cache,
-            &data_cache,
-        ));
+        let schema = runtime.block_on(util::schema_from_sst(&store, &sst_path, &meta_cache));
 
         let projected_schema = ProjectedSchema::no_projection(schema.clone());
         let max_projections = cmp::min(config.max_projections, schema.num_columns());
@@ -80,6 +74,7 @@ impl MergeMemTableBench {
                 schema: schema.clone(),
                 arena_block_size: config.arena_block_size.0 as u32,
                 creation_sequence: crate::INIT_SEQUENCE,
+                enable_tag_index: false,
             };
             let memtable = memtable_factory.create_memtable(memtable_opts).unwrap();
 
@@ -154,6 +149,9 @@ impl MergeMemTableBench {
             merge_iter_options: iter_options.clone(),
             need_dedup: true,
             reverse: false,
+            tombstones: Vec::new(),
+            expire_time: None,
+            limit: None,
         });
 
         builder.mut_memtables().extend_from_slice(&self.memtables);
@@ -203,5 +201,6 @@ fn mock_sst_reader_options(
         meta_cache: None,
         data_cache: None,
         runtime,
+        tsid_filter: None,
     }
 }
@@ -55,12 +55,7 @@ impl MergeSstBench {
         let meta_cache: Option<MetaCacheRef> = None;
         let data_cache: Option<DataCacheRef> = None;
 
-        let schema = runtime.block_on(util::schema_from_sst(
-            &store,
-            &sst_path,
-            &meta_cache,
-            &data_cache,
-        ));
+        let schema = runtime.block_on(util::schema_from_sst(&store, &sst_path, &meta_cache));
 
         let predicate = config.predicate.into_predicate();
         let projected_schema = ProjectedSchema::no_projection(schema.clone());
@@ -73,6 +68,7 @@ impl MergeSstBench {
             meta_cache: meta_cache.clone(),
             data_cache: data_cache.clone(),
             runtime: runtime.clone(),
+            tsid_filter: None,
         };
         let max_projections = cmp::min(config.max_projections, schema.num_columns());
 
@@ -86,7 +82,6 @@ impl MergeSstBench {
             &config.sst_file_ids,
             purge_queue,
             &meta_cache,
-            &data_cache,
         ));
 
         MergeSstBench {
@@ -138,6 +133,9 @@ impl MergeSstBench {
             merge_iter_options: iter_options.clone(),
             need_dedup: true,
             reverse: false,
+            tombstones: Vec::new(),
+            expire_time: None,
+            limit: None,
         });
 
         builder
@@ -183,6 +181,8 @@ impl MergeSstBench {
             sst_factory,
             sst_reader_options: self.sst_reader_options.clone(),
             store: &self.store,
+            tombstones: Vec::new(),
+            expire_time: None,
         })
         .ssts(vec![self.file_handles.clone()]);
 
@@ -15,7 +15,7 @@ use common_types::schema::Schema;
 use common_util::runtime::Runtime;
 use log::info;
 use object_store::{LocalFileSystem, ObjectStore, Path};
-use parquet::{DataCacheRef, MetaCacheRef};
+use parquet::MetaCacheRef;
 use table_engine::predicate::PredicateRef;
 
 use crate::{config::SstBenchConfig, util};
@@ -41,14 +41,8 @@ impl ParquetBench {
 
         let sst_path = Path::from(config.sst_file_name.clone());
         let meta_cache: Option<MetaCacheRef> = None;
-        let data_cache: Option<DataCacheRef> = None;
-
-        let schema = runtime.block_on(util::schema_from_sst(
-            &store,
-            &sst_path,
-            &meta_cache,
-            &data_cache,
-        ));
+
+        let schema = runtime.block_on(util::schema_from_sst(&store, &sst_path, &meta_cache));
 
         let predicate = Arc::new(config.predicate.into_predicate());
 
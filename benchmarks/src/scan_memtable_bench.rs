@@ -13,7 +13,7 @@ use arena::NoopCollector;
 use common_types::projected_schema::ProjectedSchema;
 use log::info;
 use object_store::{LocalFileSystem, Path};
-use parquet::{DataCacheRef, MetaCacheRef};
+use parquet::MetaCacheRef;
 
 use crate::{config::ScanMemTableBenchConfig, util};
 
@@ -29,14 +29,8 @@ impl ScanMemTableBench {
 
         let runtime = Arc::new(util::new_runtime(config.runtime_thread_num));
         let meta_cache: Option<MetaCacheRef> = None;
-        let data_cache: Option<DataCacheRef> = None;
         let sst_path = Path::from(config.sst_file_name);
-        let schema = runtime.block_on(util::schema_from_sst(
-            &store,
-            &sst_path,
-            &meta_cache,
-            &data_cache,
-        ));
+        let schema = runtime.block_on(util::schema_from_sst(&store, &sst_path, &meta_cache));
 
         let projected_schema = ProjectedSchema::no_projection(schema.clone());
 
@@ -46,6 +40,7 @@ impl ScanMemTableBench {
             schema: schema.clone(),
             arena_block_size: config.arena_block_size.0 as u32,
             creation_sequence: crate::INIT_SEQUENCE,
+            enable_tag_index: false,
         };
         let memtable = memtable_factory.create_memtable(memtable_opts).unwrap();
 
@@ -90,6 +85,7 @@ impl ScanMemTableBench {
             projected_schema: self.projected_schema.clone(),
             need_dedup: true,
             reverse: false,
+            tsid_filter: None,
         };
 
         let iter = self.memtable.scan(scan_ctx, scan_req).unwrap();
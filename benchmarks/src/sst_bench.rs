@@ -46,12 +46,7 @@ impl SstBench {
                 None
             };
 
-        let schema = runtime.block_on(util::schema_from_sst(
-            &store,
-            &sst_path,
-            &meta_cache,
-            &data_cache,
-        ));
+        let schema = runtime.block_on(util::schema_from_sst(&store, &sst_path, &meta_cache));
 
         let predicate = config.predicate.into_predicate();
         let projected_schema = ProjectedSchema::no_projection(schema.clone());
@@ -64,6 +59,7 @@ impl SstBench {
             meta_cache,
             data_cache,
             runtime: runtime.clone(),
+            tsid_filter: None,
         };
         let max_projections = cmp::min(config.max_projections, schema.num_columns());
 
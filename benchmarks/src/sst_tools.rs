@@ -47,6 +47,7 @@ async fn create_sst_from_stream(config: SstConfig, record_batch_stream: RecordBa
         sst_type: SstType::Parquet,
         num_rows_per_row_group: config.num_rows_per_row_group,
         compression: config.compression,
+        build_tag_index: config.sst_meta.tag_index.is_some(),
     };
 
     info!(
@@ -85,7 +86,7 @@ pub async fn rebuild_sst(config: RebuildSstConfig, runtime: Arc<Runtime>) {
     let store = LocalFileSystem::new_with_prefix(config.store_path.clone()).unwrap();
     let input_path = Path::from(config.input_file_name);
 
-    let sst_meta = util::meta_from_sst(&store, &input_path, &None, &None).await;
+    let sst_meta = util::meta_from_sst(&store, &input_path, &None).await;
 
     let projected_schema = ProjectedSchema::no_projection(sst_meta.schema.clone());
     let sst_reader_options = SstReaderOptions {
@@ -97,6 +98,7 @@ pub async fn rebuild_sst(config: RebuildSstConfig, runtime: Arc<Runtime>) {
         meta_cache: None,
         data_cache: None,
         runtime,
+        tsid_filter: None,
     };
 
     let record_batch_stream =
@@ -108,6 +110,7 @@ pub async fn rebuild_sst(config: RebuildSstConfig, runtime: Arc<Runtime>) {
         sst_file_name: config.output_file_name,
         num_rows_per_row_group: config.num_rows_per_row_group,
         compression: config.compression,
+        build_tag_index: config.sst_meta.tag_index.is_some(),
     };
 
     create_sst_from_stream(output_sst_config, record_batch_stream).await;
@@ -168,7 +171,6 @@ pub async fn merge_sst(config: MergeSstConfig, runtime: Arc<Runtime>) {
         &config.sst_file_ids,
         purge_queue,
         &None,
-        &None,
     )
     .await;
     let max_sequence = file_handles
@@ -178,7 +180,7 @@ pub async fn merge_sst(config: MergeSstConfig, runtime: Arc<Runtime>) {
         .unwrap();
 
     let first_sst_path = sst_util::new_sst_file_path(space_id, table_id, config.sst_file_ids[0]);
-    let schema = util::schema_from_sst(&store, &first_sst_path, &None, &None).await;
+    let schema = util::schema_from_sst(&store, &first_sst_path, &None).await;
     let iter_options = IterOptions {
         batch_size: config.read_batch_row_num,
     };
@@ -198,6 +200,7 @@ pub async fn merge_sst(config: MergeSstConfig, runtime: Arc<Runtime>) {
             meta_cache: None,
             data_cache: None,
             runtime: runtime.clone(),
+            tsid_filter: None,
         };
 
         let sst_factory = FactoryImpl;
@@ -214,6 +217,9 @@ pub async fn merge_sst(config: MergeSstConfig, runtime: Arc<Runtime>) {
             merge_iter_options: iter_options.clone(),
             need_dedup: true,
             reverse: false,
+            tombstones: Vec::new(),
+            expire_time: None,
+            limit: None,
         });
         builder
             .mut_ssts_of_level(0)
@@ -236,6 +242,7 @@ pub async fn merge_sst(config: MergeSstConfig, runtime: Arc<Runtime>) {
         sst_file_name: config.output_file_name,
         num_rows_per_row_group: config.num_rows_per_row_group,
         compression: config.compression,
+        build_tag_index: config.sst_meta.tag_index.is_some(),
     };
 
     create_sst_from_stream(output_sst_config, record_batch_stream).await;
@@ -23,7 +23,7 @@ use common_types::{
 use common_util::runtime::{self, Runtime};
 use futures::stream::StreamExt;
 use object_store::{LocalFileSystem, Path};
-use parquet::{DataCacheRef, MetaCacheRef};
+use parquet::MetaCacheRef;
 use table_engine::{predicate::Predicate, table::TableId};
 
 pub fn new_runtime(thread_num: usize) -> Runtime {
@@ -39,9 +39,8 @@ pub async fn meta_from_sst(
     store: &LocalFileSystem,
     sst_path: &Path,
     meta_cache: &Option<MetaCacheRef>,
-    data_cache: &Option<DataCacheRef>,
 ) -> SstMetaData {
-    let (_, sst_meta) = reader::read_sst_meta(store, sst_path, meta_cache, data_cache)
+    let (_, sst_meta) = reader::read_sst_meta(store, sst_path, meta_cache)
         .await
         .unwrap();
 
@@ -52,9 +51,8 @@ pub async fn schema_from_sst(
     store: &LocalFileSystem,
     sst_path: &Path,
     meta_cache: &Option<MetaCacheRef>,
-    data_cache: &Option<DataCacheRef>,
 ) -> Schema {
-    let sst_meta = meta_from_sst(store, sst_path, meta_cache, data_cache).await;
+    let sst_meta = meta_from_sst(store, sst_path, meta_cache).await;
 
     sst_meta.schema
 }
@@ -89,6 +87,7 @@ pub async fn load_sst_to_memtable(
         meta_cache: None,
         data_cache: None,
         runtime,
+        tsid_filter: None,
     };
     let sst_factory = FactoryImpl;
     let mut sst_reader = sst_factory
@@ -123,14 +122,13 @@ pub async fn file_handles_from_ssts(
     sst_file_ids: &[FileId],
     purge_queue: FilePurgeQueue,
     meta_cache: &Option<MetaCacheRef>,
-    data_cache: &Option<DataCacheRef>,
 ) -> Vec<FileHandle> {
     let mut file_handles = Vec::with_capacity(sst_file_ids.len());
 
     for file_id in sst_file_ids.iter() {
         let path = sst_util::new_sst_file_path(space_id, table_id, *file_id);
 
-        let sst_meta = meta_from_sst(store, &path, meta_cache, data_cache).await;
+        let sst_meta = meta_from_sst(store, &path, meta_cache).await;
         let file_meta = FileMeta {
             id: *file_id,
             meta: sst_meta,
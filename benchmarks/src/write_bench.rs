@@ -0,0 +1,176 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Write bench.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+};
+
+use analytic_engine::setup;
+use common_types::{
+    bytes::Bytes,
+    column_schema,
+    datum::{Datum, DatumKind},
+    row::{Row, RowGroup, RowGroupBuilder},
+    schema::{self, Schema},
+    time::Timestamp,
+};
+use common_util::runtime::Runtime;
+use futures::future;
+use log::info;
+use table_engine::{
+    engine::{CreateTableRequest, EngineRuntimes, OpenTableRequest, TableEngineRef, TableState},
+    table::{TableId, TableRef, WriteRequest},
+    ANALYTIC_ENGINE_TYPE,
+};
+
+use crate::{config::WriteBenchConfig, util};
+
+const TABLE_NAME: &str = "write_bench";
+
+pub struct WriteBench {
+    runtime: Arc<Runtime>,
+    // Hold the engine to keep it open during the bench.
+    _engine: TableEngineRef,
+    table: TableRef,
+    batch_size: usize,
+    concurrency: usize,
+    /// Timestamp of the next row to write.
+    next_timestamp: AtomicI64,
+}
+
+impl WriteBench {
+    pub fn new(config: WriteBenchConfig) -> Self {
+        let runtime = Arc::new(util::new_runtime(config.runtime_thread_num));
+        let engine_runtimes = Arc::new(EngineRuntimes {
+            read_runtime: runtime.clone(),
+            write_runtime: runtime.clone(),
+            bg_runtime: runtime.clone(),
+        });
+
+        let engine = runtime
+            .block_on(setup::open_analytic_table_engine(
+                config.engine,
+                engine_runtimes,
+            ))
+            .unwrap();
+        let table = runtime.block_on(open_or_create_table(&engine, config.table_id));
+
+        info!(
+            "\nWriteBench table opened, table:{}, batch_size:{}, concurrency:{}",
+            TABLE_NAME, config.batch_size, config.concurrency
+        );
+
+        Self {
+            runtime,
+            _engine: engine,
+            table,
+            batch_size: config.batch_size,
+            concurrency: config.concurrency,
+            next_timestamp: AtomicI64::new(Timestamp::now().as_i64()),
+        }
+    }
+
+    /// Returns the number of rows written in each run.
+    pub fn rows_per_run(&self) -> usize {
+        self.batch_size * self.concurrency
+    }
+
+    pub fn run_bench(&self) {
+        let requests: Vec<_> = (0..self.concurrency)
+            .map(|_| WriteRequest {
+                row_group: self.build_row_group(),
+            })
+            .collect();
+
+        // Send the requests concurrently so the writes to the same table can be
+        // pipelined by the write worker.
+        let writes = requests
+            .into_iter()
+            .map(|request| self.table.write(request));
+        let written = self.runtime.block_on(future::try_join_all(writes)).unwrap();
+
+        assert_eq!(self.rows_per_run(), written.into_iter().sum::<usize>());
+    }
+
+    fn build_row_group(&self) -> RowGroup {
+        let schema = self.table.schema();
+        let start = self
+            .next_timestamp
+            .fetch_add(self.batch_size as i64, Ordering::Relaxed);
+
+        let mut builder = RowGroupBuilder::with_capacity(schema, self.batch_size);
+        for i in 0..self.batch_size {
+            let ts = start + i as i64;
+            let datums = vec![
+                Datum::Varbinary(Bytes::from(format!("host-{}", i % 100))),
+                Datum::Timestamp(Timestamp::new(ts)),
+                Datum::Double(ts as f64),
+            ];
+            builder.push_checked_row(Row::from_datums(datums));
+        }
+
+        builder.build()
+    }
+}
+
+/// Build the schema of the table to write:
+/// (host(varbinary), ts(timestamp), value(double))
+fn build_schema() -> Schema {
+    schema::Builder::new()
+        .auto_increment_column_id(true)
+        .add_key_column(
+            column_schema::Builder::new("host".to_string(), DatumKind::Varbinary)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_key_column(
+            column_schema::Builder::new("ts".to_string(), DatumKind::Timestamp)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .add_normal_column(
+            column_schema::Builder::new("value".to_string(), DatumKind::Double)
+                .build()
+                .unwrap(),
+        )
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+async fn open_or_create_table(engine: &TableEngineRef, table_id: TableId) -> TableRef {
+    let open_request = OpenTableRequest {
+        catalog_name: "ceresdb".to_string(),
+        schema_name: "public".to_string(),
+        schema_id: table_id.schema_id(),
+        table_name: TABLE_NAME.to_string(),
+        table_id,
+        engine: ANALYTIC_ENGINE_TYPE.to_string(),
+        partition_info: None,
+    };
+    if let Some(table) = engine.open_table(open_request).await.unwrap() {
+        return table;
+    }
+
+    let create_request = CreateTableRequest {
+        catalog_name: "ceresdb".to_string(),
+        schema_name: "public".to_string(),
+        schema_id: table_id.schema_id(),
+        table_id,
+        table_name: TABLE_NAME.to_string(),
+        table_schema: build_schema(),
+        partition_info: None,
+        engine: ANALYTIC_ENGINE_TYPE.to_string(),
+        options: HashMap::new(),
+        state: TableState::Stable,
+    };
+
+    engine.create_table(create_request).await.unwrap()
+}
@@ -5,10 +5,11 @@
 use std::{collections::HashMap, sync::Arc};
 
 use async_trait::async_trait;
-use common_types::column_schema::ColumnSchema;
+use common_types::{column_schema::ColumnSchema, time::Timestamp};
 use snafu::{Backtrace, Snafu};
 use table_engine::{
     engine::{self, TableEngineRef, TableState},
+    partition::PartitionInfo,
     table::{SchemaId, TableId, TableRef},
 };
 
@@ -70,6 +71,19 @@ pub enum Error {
         source: Box<dyn std::error::Error + Send + Sync>,
     },
 
+    #[snafu(display("Failed to read table meta, table:{}, err:{}", table, source))]
+    ReadTableMeta {
+        table: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[snafu(display(
+        "Failed to create table, the name is reserved for sub tables, table:{}.\nBacktrace:\n{}",
+        table,
+        backtrace
+    ))]
+    ReservedTableName { table: String, backtrace: Backtrace },
+
     #[snafu(display(
         "Catalog mismatch, expect:{}, given:{}.\nBacktrace:\n{}",
         expect,
@@ -117,6 +131,22 @@ pub enum Error {
     #[snafu(display("Failed to drop table, err:{}", source))]
     DropTable { source: table_engine::engine::Error },
 
+    #[snafu(display(
+        "Failed to create view, view already exists, view:{}.\nBacktrace:\n{}",
+        view,
+        backtrace
+    ))]
+    CreateExistView { view: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to find view, view:{}.\nBacktrace:\n{}", view, backtrace))]
+    ViewNotFound { view: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to persist view meta, view:{}, err:{}", view, source))]
+    WriteViewMeta {
+        view: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
     #[snafu(display(
         "Too many table, cannot create table, schema:{}, table:{}.\nBacktrace:\n{}",
         schema,
@@ -145,6 +175,9 @@ pub struct CreateTableRequest {
     pub table_name: String,
     /// Table schema
     pub table_schema: common_types::schema::Schema,
+    /// Partition info if this is a partitioned table, the ids of the sub
+    /// tables are allocated by the schema on creation
+    pub partition_info: Option<PartitionInfo>,
     /// Table engine type
     pub engine: String,
     /// Table options used by each engine
@@ -162,7 +195,7 @@ impl CreateTableRequest {
             table_name: self.table_name,
             table_id,
             table_schema: self.table_schema,
-            partition_info: None,
+            partition_info: self.partition_info,
             engine: self.engine,
             options: self.options,
             state: self.state,
@@ -224,6 +257,30 @@ pub struct AlterTableRequest {
     pub operations: Vec<AlterTableOperation>,
 }
 
+/// Definition of a materialized view.
+///
+/// The rows of the view are stored in the table with the same name as the
+/// view, and the view is refreshed by the buckets of the time bucket in its
+/// query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ViewInfo {
+    /// Catalog name
+    pub catalog_name: String,
+    /// Schema name
+    pub schema_name: String,
+    /// View name, also the name of the table storing the rows of the view
+    pub view_name: String,
+    /// Sql of the query of the view
+    pub query: String,
+    /// Name of the table the view selects from
+    pub source_table: String,
+    /// Period of the time bucket in the query, such as `PT5M`
+    pub period: String,
+    /// Rows of the source table before the watermark are already
+    /// materialized into the view
+    pub watermark: Timestamp,
+}
+
 /// Schema manage tables.
 #[async_trait]
 pub trait Schema {
@@ -264,6 +321,16 @@ pub trait Schema {
 
     /// All tables
     fn all_tables(&self) -> Result<Vec<TableRef>>;
+
+    /// Create the view, the table storing the rows of the view should be
+    /// created before.
+    async fn create_view(&self, view: ViewInfo) -> Result<()>;
+
+    /// Update the definition of the existing view, e.g. the watermark.
+    async fn update_view(&self, view: ViewInfo) -> Result<()>;
+
+    /// All views
+    fn all_views(&self) -> Result<Vec<ViewInfo>>;
 }
 
 /// A name reference
@@ -3,7 +3,11 @@
 use std::sync::Arc;
 
 use catalog::{consts::SYSTEM_CATALOG, manager::Manager, schema::NameRef, CatalogRef};
-use system_catalog::{tables::Tables, SystemTableAdapter};
+use df_operator::registry::FunctionRegistryRef;
+use system_catalog::{
+    columns::Columns, compaction_tasks::CompactionTasks, functions::Functions, sst_files::SstFiles,
+    table_stats::TableStats, tables::Tables, SystemTableAdapter,
+};
 
 use crate::system_tables::{SystemTables, SystemTablesBuilder};
 
@@ -19,10 +23,17 @@ pub struct CatalogManagerImpl<M> {
 }
 
 impl<M: Manager + 'static> CatalogManagerImpl<M> {
-    pub fn new(manager: M) -> Self {
+    pub fn new(manager: M, function_registry: FunctionRegistryRef) -> Self {
         let mut system_tables_builder = SystemTablesBuilder::new();
         system_tables_builder = system_tables_builder
-            .insert_table(SystemTableAdapter::new(Tables::new(manager.clone())));
+            .insert_table(SystemTableAdapter::new(Tables::new(manager.clone())))
+            .insert_table(SystemTableAdapter::new(Columns::new(manager.clone())))
+            .insert_table(SystemTableAdapter::new(SstFiles::new(manager.clone())))
+            .insert_table(SystemTableAdapter::new(TableStats::new(manager.clone())))
+            .insert_table(SystemTableAdapter::new(CompactionTasks::new(
+                manager.clone(),
+            )))
+            .insert_table(SystemTableAdapter::new(Functions::new(function_registry)));
         Self {
             system_tables: system_tables_builder.build(),
             user_catalog_manager: manager,
@@ -50,3 +61,164 @@ impl<M: Manager> Manager for CatalogManagerImpl<M> {
         self.user_catalog_manager.all_catalogs()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use analytic_engine::tests::util::TestEnv;
+    use arrow_deps::datafusion::logical_plan::{col, lit, Expr};
+    use catalog::{
+        consts::{DEFAULT_CATALOG, DEFAULT_SCHEMA, SYSTEM_CATALOG_SCHEMA},
+        schema::{CreateOptions, CreateTableRequest},
+    };
+    use common_types::{
+        datum::Datum,
+        projected_schema::ProjectedSchema,
+        request_id::RequestId,
+        row::{Row, RowGroupBuilder},
+        tests::{build_rows, build_schema},
+    };
+    use df_operator::registry::{FunctionRegistry, FunctionRegistryImpl};
+    use futures::TryStreamExt;
+    use system_catalog::{
+        COLUMNS_TABLE_NAME, COMPACTION_TASKS_TABLE_NAME, FUNCTIONS_TABLE_NAME,
+        SST_FILES_TABLE_NAME, TABLE_STATS_TABLE_NAME,
+    };
+    use table_engine::{
+        engine::TableState,
+        predicate::PredicateBuilder,
+        table::{FlushRequest, ReadOptions, ReadOrder, ReadRequest, TableRef, WriteRequest},
+        ANALYTIC_ENGINE_TYPE,
+    };
+
+    use super::*;
+    use crate::table_based::TableBasedManager;
+
+    async fn create_and_flush_table(manager: &TableBasedManager, table_name: &str) -> TableRef {
+        let schema = manager
+            .catalog_by_name(DEFAULT_CATALOG)
+            .unwrap()
+            .unwrap()
+            .schema_by_name(DEFAULT_SCHEMA)
+            .unwrap()
+            .unwrap();
+        let request = CreateTableRequest {
+            catalog_name: DEFAULT_CATALOG.to_string(),
+            schema_name: DEFAULT_SCHEMA.to_string(),
+            schema_id: schema.id(),
+            table_name: table_name.to_string(),
+            table_schema: build_schema(),
+            partition_info: None,
+            engine: ANALYTIC_ENGINE_TYPE.to_string(),
+            options: HashMap::new(),
+            state: TableState::Stable,
+        };
+        let opts = CreateOptions {
+            table_engine: manager.get_engine_proxy(),
+            create_if_not_exists: false,
+        };
+        let table = schema.create_table(request, opts).await.unwrap();
+
+        let row_group = RowGroupBuilder::with_rows(table.schema(), build_rows())
+            .unwrap()
+            .build();
+        table.write(WriteRequest { row_group }).await.unwrap();
+        table.flush(FlushRequest::default()).await.unwrap();
+
+        table
+    }
+
+    /// Read all rows of the system table `name` with the pushed down `exprs`.
+    async fn read_system_table<M: Manager>(
+        manager: &CatalogManagerImpl<M>,
+        name: &str,
+        exprs: &[Expr],
+    ) -> Vec<Row> {
+        let table = manager
+            .catalog_by_name(SYSTEM_CATALOG)
+            .unwrap()
+            .unwrap()
+            .schema_by_name(SYSTEM_CATALOG_SCHEMA)
+            .unwrap()
+            .unwrap()
+            .table_by_name(name)
+            .unwrap()
+            .unwrap();
+        let request = ReadRequest {
+            request_id: RequestId::next_id(),
+            opts: ReadOptions::default(),
+            projected_schema: ProjectedSchema::no_projection(table.schema()),
+            predicate: PredicateBuilder::default()
+                .add_pushdown_exprs(exprs)
+                .build(),
+            order: ReadOrder::None,
+            limit: None,
+        };
+        let batches: Vec<_> = table
+            .read(request)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        let mut rows = Vec::new();
+        for batch in batches {
+            for i in 0..batch.num_rows() {
+                let datums = (0..batch.num_columns())
+                    .map(|col_idx| batch.column(col_idx).datum(i))
+                    .collect();
+                rows.push(Row::from_datums(datums));
+            }
+        }
+        rows
+    }
+
+    #[tokio::test]
+    async fn test_read_system_tables() {
+        let env = TestEnv::builder().build();
+        let mut test_ctx = env.new_context();
+        test_ctx.open().await;
+
+        let analytic = test_ctx.engine();
+        let user_manager = TableBasedManager::new(analytic.clone(), analytic)
+            .await
+            .unwrap();
+        let table = create_and_flush_table(&user_manager, "test_table").await;
+        let mut function_registry = FunctionRegistryImpl::new();
+        function_registry.load_functions().unwrap();
+        let num_functions = function_registry.list_udfs().unwrap().len()
+            + function_registry.list_udafs().unwrap().len();
+        let manager = CatalogManagerImpl::new(user_manager, Arc::new(function_registry));
+
+        // The first 4 columns are the timestamp, catalog, schema and table name.
+        let filter = [col("table_name").eq(lit("test_table"))];
+        let rows = read_system_table(&manager, COLUMNS_TABLE_NAME, &filter).await;
+        assert_eq!(table.schema().num_columns(), rows.len());
+        for (row, column) in rows.iter().zip(table.schema().columns()) {
+            assert_eq!(Datum::from(column.name.as_str()), row[4]);
+        }
+        // Tables not matching the filter are skipped.
+        let not_exist = [col("table_name").eq(lit("not_exist"))];
+        let rows = read_system_table(&manager, COLUMNS_TABLE_NAME, &not_exist).await;
+        assert!(rows.is_empty());
+
+        let rows = read_system_table(&manager, SST_FILES_TABLE_NAME, &filter).await;
+        assert_eq!(1, rows.len());
+        assert_eq!(Datum::from(table.id().as_u64()), rows[0][4]);
+
+        let rows = read_system_table(&manager, TABLE_STATS_TABLE_NAME, &filter).await;
+        assert_eq!(1, rows.len());
+
+        // No compaction is running after the flush finishes.
+        let rows = read_system_table(&manager, COMPACTION_TASKS_TABLE_NAME, &filter).await;
+        assert!(rows.is_empty());
+
+        let rows = read_system_table(&manager, FUNCTIONS_TABLE_NAME, &[]).await;
+        assert_eq!(num_functions, rows.len());
+        let filter = [col("name").eq(lit("time_bucket"))];
+        let rows = read_system_table(&manager, FUNCTIONS_TABLE_NAME, &filter).await;
+        assert_eq!(1, rows.len());
+    }
+}
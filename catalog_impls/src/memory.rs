@@ -17,13 +17,17 @@ use catalog::{
     schema::{
         self, CatalogMismatch, CloseOptions, CloseTable, CloseTableRequest, CreateOptions,
         CreateTable, CreateTableRequest, DropOptions, DropTable, DropTableRequest, NameRef,
-        OpenOptions, OpenTable, OpenTableRequest, Schema, SchemaMismatch, SchemaRef,
+        OpenOptions, OpenTable, OpenTableRequest, ReservedTableName, Schema, SchemaMismatch,
+        SchemaRef,
     },
     Catalog, CatalogRef,
 };
 use log::info;
 use snafu::{ensure, ResultExt};
-use table_engine::table::{SchemaId, TableId, TableRef};
+use table_engine::{
+    partition,
+    table::{SchemaId, TableId, TableRef},
+};
 use tokio::sync::Mutex;
 
 #[async_trait]
@@ -245,9 +249,16 @@ impl<T: TableIdGenerator + Send + Sync> Schema for SchemaImpl<T> {
     // In memory schema does not support persisting table info
     async fn create_table(
         &self,
-        request: CreateTableRequest,
+        mut request: CreateTableRequest,
         opts: CreateOptions,
     ) -> schema::Result<TableRef> {
+        ensure!(
+            !partition::is_reserved_table_name(&request.table_name),
+            ReservedTableName {
+                table: &request.table_name,
+            }
+        );
+
         if let Some(table) = self.get_table(
             &request.catalog_name,
             &request.schema_name,
@@ -277,6 +288,23 @@ impl<T: TableIdGenerator + Send + Sync> Schema for SchemaImpl<T> {
                 table: &request.table_name,
             })?;
 
+        if let Some(partition_info) = &mut request.partition_info {
+            let mut sub_table_ids = Vec::with_capacity(partition_info.partition_num());
+            for idx in 0..partition_info.partition_num() {
+                let key = partition::sub_table_id_key(table_id, idx);
+                let sub_table_id = self
+                    .table_id_gen
+                    .alloc_table_id(request.schema_id, &key)
+                    .await
+                    .map_err(|e| Box::new(e) as _)
+                    .context(schema::AllocateTableId {
+                        schema: &self.schema_name,
+                        table: &key,
+                    })?;
+                sub_table_ids.push(sub_table_id);
+            }
+            partition_info.sub_table_ids = sub_table_ids;
+        }
         let request = request.into_engine_create_request(table_id);
 
         // Table engine handles duplicate table creation
@@ -293,7 +321,7 @@ impl<T: TableIdGenerator + Send + Sync> Schema for SchemaImpl<T> {
 
     async fn drop_table(
         &self,
-        request: DropTableRequest,
+        mut request: DropTableRequest,
         opts: DropOptions,
     ) -> schema::Result<bool> {
         if self
@@ -321,6 +349,9 @@ impl<T: TableIdGenerator + Send + Sync> Schema for SchemaImpl<T> {
 
         let schema_id = request.schema_id;
         let table_name = request.table_name.clone();
+        // The table info is not persisted, so the sub tables to drop are decided
+        // by the opened table.
+        request.partition_info = table.partition_info();
 
         // drop the table in the engine first.
         let real_dropped = opts
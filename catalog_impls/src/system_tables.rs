@@ -9,7 +9,7 @@ use catalog::{
     consts::{SYSTEM_CATALOG, SYSTEM_CATALOG_SCHEMA},
     schema::{
         CloseOptions, CloseTableRequest, CreateOptions, CreateTableRequest, DropOptions,
-        DropTableRequest, NameRef, OpenOptions, OpenTableRequest, Schema, SchemaRef,
+        DropTableRequest, NameRef, OpenOptions, OpenTableRequest, Schema, SchemaRef, ViewInfo,
     },
     Catalog,
 };
@@ -118,6 +118,24 @@ impl Schema for SystemTables {
             .map(|(_, v)| v.clone() as TableRef)
             .collect())
     }
+
+    async fn create_view(&self, _view: ViewInfo) -> catalog::schema::Result<()> {
+        catalog::schema::UnSupported {
+            msg: UNSUPPORTED_MSG,
+        }
+        .fail()
+    }
+
+    async fn update_view(&self, _view: ViewInfo) -> catalog::schema::Result<()> {
+        catalog::schema::UnSupported {
+            msg: UNSUPPORTED_MSG,
+        }
+        .fail()
+    }
+
+    fn all_views(&self) -> catalog::schema::Result<Vec<ViewInfo>> {
+        Ok(Vec::new())
+    }
 }
 
 #[async_trait]
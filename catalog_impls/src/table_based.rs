@@ -12,10 +12,11 @@ use catalog::{
     self, consts,
     manager::{self, Manager},
     schema::{
-        self, CatalogMismatch, CloseOptions, CloseTableRequest, CreateExistTable, CreateOptions,
-        CreateTable, CreateTableRequest, DropOptions, DropTable, DropTableRequest, NameRef,
-        OpenOptions, OpenTableRequest, Schema, SchemaMismatch, SchemaRef, TooManyTable,
-        WriteTableMeta,
+        self, CatalogMismatch, CloseOptions, CloseTableRequest, CreateExistTable, CreateExistView,
+        CreateOptions, CreateTable, CreateTableRequest, DropOptions, DropTable, DropTableRequest,
+        NameRef, OpenOptions, OpenTableRequest, ReadTableMeta, ReservedTableName, Schema,
+        SchemaMismatch, SchemaRef, TooManyTable, ViewInfo, ViewNotFound, WriteTableMeta,
+        WriteViewMeta,
     },
     Catalog, CatalogRef,
 };
@@ -28,6 +29,7 @@ use system_catalog::sys_catalog_table::{
 };
 use table_engine::{
     engine::{TableEngineRef, TableState},
+    partition,
     table::{
         ReadOptions, SchemaId, SchemaIdGenerator, TableId, TableInfo, TableRef, TableSeqGenerator,
     },
@@ -189,6 +191,7 @@ impl Inner {
             schema_name: consts::SYSTEM_CATALOG_SCHEMA.to_string(),
             schema_id,
             tables: RwLock::new(tables),
+            views: RwLock::new(HashMap::new()),
             mutex: Mutex::new(()),
             catalog_table: self.catalog_table.clone(),
             table_seq_generator: TableSeqGenerator::default(),
@@ -386,11 +389,18 @@ impl<'a> Visitor for VisitorImpl<'a> {
                     schema: &table_info.schema_name,
                 })?;
 
-        // Update max table sequence of the schema.
+        // Update max table sequence of the schema, the ids of the sub tables are
+        // also allocated from the schema.
         let table_id = table_info.table_id;
-        let table_seq = table_id.table_seq();
-        if table_seq.as_u64() >= schema.table_seq_generator.last_table_seq_u64() {
-            schema.table_seq_generator.set_last_table_seq(table_seq);
+        let sub_table_ids = table_info
+            .partition_info
+            .iter()
+            .flat_map(|info| info.sub_table_ids.iter().copied());
+        for id in std::iter::once(table_id).chain(sub_table_ids) {
+            let table_seq = id.table_seq();
+            if table_seq.as_u64() >= schema.table_seq_generator.last_table_seq_u64() {
+                schema.table_seq_generator.set_last_table_seq(table_seq);
+            }
         }
 
         // Only the stable/altering table can be opened.
@@ -425,6 +435,27 @@ impl<'a> Visitor for VisitorImpl<'a> {
 
         Ok(())
     }
+
+    fn visit_view(&mut self, view: ViewInfo) -> sys_catalog_table::Result<()> {
+        debug!("Visitor visit view, view:{:?}", view);
+
+        let catalog =
+            self.catalogs
+                .get_mut(&view.catalog_name)
+                .context(VisitorCatalogNotFound {
+                    catalog: &view.catalog_name,
+                })?;
+        let schema = catalog
+            .find_schema(&view.schema_name)
+            .context(VisitorSchemaNotFound {
+                catalog: &view.catalog_name,
+                schema: &view.schema_name,
+            })?;
+
+        schema.insert_view_into_memory(view);
+
+        Ok(())
+    }
 }
 
 type SchemaMap = HashMap<String, Arc<SchemaImpl>>;
@@ -547,10 +578,13 @@ struct SchemaImpl {
     schema_id: SchemaId,
     /// Tables of schema
     tables: RwLock<SchemaTables>,
+    /// Materialized views of schema
+    views: RwLock<HashMap<String, ViewInfo>>,
     /// Mutex
     ///
     /// Protects:
     /// - add/drop/alter table
+    /// - add/update view
     /// - persist to sys catalog table
     mutex: Mutex<()>,
     /// Sys catalog table
@@ -570,6 +604,7 @@ impl SchemaImpl {
             schema_name: schema_name.to_string(),
             schema_id,
             tables: RwLock::new(SchemaTables::default()),
+            views: RwLock::new(HashMap::new()),
             mutex: Mutex::new(()),
             catalog_table,
             table_seq_generator: TableSeqGenerator::default(),
@@ -625,6 +660,16 @@ impl SchemaImpl {
         Ok(None)
     }
 
+    /// Insert view into memory, wont check existence
+    fn insert_view_into_memory(&self, view: ViewInfo) {
+        let mut views = self.views.write().unwrap();
+        views.insert(view.view_name.clone(), view);
+    }
+
+    fn find_view_by_name(&self, name: NameRef) -> Option<ViewInfo> {
+        self.views.read().unwrap().get(name).cloned()
+    }
+
     fn find_table_by_name(&self, name: NameRef) -> Option<TableRef> {
         self.tables
             .read()
@@ -691,7 +736,7 @@ impl Schema for SchemaImpl {
     // TODO(yingwen): Do not persist if engine is memory engine.
     async fn create_table(
         &self,
-        request: CreateTableRequest,
+        mut request: CreateTableRequest,
         opts: CreateOptions,
     ) -> schema::Result<TableRef> {
         info!(
@@ -700,6 +745,12 @@ impl Schema for SchemaImpl {
         );
 
         self.validate_schema_info(&request.catalog_name, &request.schema_name)?;
+        ensure!(
+            !partition::is_reserved_table_name(&request.table_name),
+            ReservedTableName {
+                table: &request.table_name,
+            }
+        );
 
         // TODO(yingwen): Validate table id is unique.
 
@@ -721,6 +772,15 @@ impl Schema for SchemaImpl {
 
         // Create table
         let table_id = self.alloc_table_id(&request.table_name).await?;
+        if let Some(partition_info) = &mut request.partition_info {
+            // Sub tables are isolated in this schema as their ids are allocated from it.
+            let mut sub_table_ids = Vec::with_capacity(partition_info.partition_num());
+            for idx in 0..partition_info.partition_num() {
+                let key = partition::sub_table_id_key(table_id, idx);
+                sub_table_ids.push(self.alloc_table_id(&key).await?);
+            }
+            partition_info.sub_table_ids = sub_table_ids;
+        }
         let request = request.into_engine_create_request(table_id);
         let table_name = request.table_name.clone();
         let table = opts
@@ -759,20 +819,31 @@ impl Schema for SchemaImpl {
 
         self.validate_schema_info(&request.catalog_name, &request.schema_name)?;
 
-        if self.find_table_by_name(&request.table_name).is_none() {
-            return Ok(false);
-        };
-
         let _lock = self.mutex.lock().await;
-        // double check whether the table to drop exists.
-        let table = match self.find_table_by_name(&request.table_name) {
-            Some(v) => v,
-            None => return Ok(false),
+        // The table is not in memory if it fails to open, e.g. a sub table of the
+        // partitioned table is missing, so the persisted table info is used to
+        // drop the table.
+        let table_info = self
+            .catalog_table
+            .table_info(
+                &request.catalog_name,
+                &request.schema_name,
+                &request.table_name,
+            )
+            .await
+            .map_err(|e| Box::new(e) as _)
+            .context(ReadTableMeta {
+                table: &request.table_name,
+            })?;
+        let table_info = match table_info {
+            Some(v) if matches!(v.state, TableState::Stable) => v,
+            _ => return Ok(false),
         };
 
         // Determine the real engine type of the table to drop.
         // FIXME(xikai): the engine should not be part of the DropRequest.
-        request.engine = table.engine_type().to_string();
+        request.engine = table_info.engine;
+        request.partition_info = table_info.partition_info;
 
         // Prepare to drop table info in the sys_catalog.
         self.catalog_table
@@ -808,6 +879,19 @@ impl Schema for SchemaImpl {
             tables.remove(&request.table_name);
         };
 
+        // The view is also dropped with the table storing its rows.
+        if let Some(view) = self.find_view_by_name(&request.table_name) {
+            self.catalog_table
+                .drop_view(view)
+                .await
+                .map_err(|e| Box::new(e) as _)
+                .context(WriteViewMeta {
+                    view: &request.table_name,
+                })?;
+
+            self.views.write().unwrap().remove(&request.table_name);
+        }
+
         info!(
             "Table based catalog manager drop table successfully, request:{:?}",
             request
@@ -861,6 +945,62 @@ impl Schema for SchemaImpl {
             .map(|(_, v)| v.clone())
             .collect())
     }
+
+    async fn create_view(&self, view: ViewInfo) -> schema::Result<()> {
+        info!("Table based catalog manager create view, view:{:?}", view);
+
+        self.validate_schema_info(&view.catalog_name, &view.schema_name)?;
+
+        let _lock = self.mutex.lock().await;
+        ensure!(
+            self.find_view_by_name(&view.view_name).is_none(),
+            CreateExistView {
+                view: &view.view_name,
+            }
+        );
+
+        self.catalog_table
+            .write_view(view.clone())
+            .await
+            .map_err(|e| Box::new(e) as _)
+            .context(WriteViewMeta {
+                view: &view.view_name,
+            })?;
+
+        self.insert_view_into_memory(view);
+
+        Ok(())
+    }
+
+    async fn update_view(&self, view: ViewInfo) -> schema::Result<()> {
+        debug!("Table based catalog manager update view, view:{:?}", view);
+
+        self.validate_schema_info(&view.catalog_name, &view.schema_name)?;
+
+        let _lock = self.mutex.lock().await;
+        ensure!(
+            self.find_view_by_name(&view.view_name).is_some(),
+            ViewNotFound {
+                view: &view.view_name,
+            }
+        );
+
+        self.catalog_table
+            .write_view(view.clone())
+            .await
+            .map_err(|e| Box::new(e) as _)
+            .context(WriteViewMeta {
+                view: &view.view_name,
+            })?;
+
+        self.insert_view_into_memory(view);
+
+        Ok(())
+    }
+
+    fn all_views(&self) -> schema::Result<Vec<ViewInfo>> {
+        Ok(self.views.read().unwrap().values().cloned().collect())
+    }
 }
 
 #[cfg(any(test, feature = "test"))]
@@ -871,8 +1011,11 @@ mod tests {
     use catalog::{
         consts::DEFAULT_CATALOG,
         manager::Manager,
-        schema::{CreateOptions, CreateTableRequest, DropOptions, DropTableRequest, SchemaRef},
+        schema::{
+            CreateOptions, CreateTableRequest, DropOptions, DropTableRequest, SchemaRef, ViewInfo,
+        },
     };
+    use common_types::time::Timestamp;
     use server::table_engine::{MemoryTableEngine, TableEngineProxy};
     use table_engine::{
         engine::{TableEngineRef, TableState},
@@ -921,6 +1064,7 @@ mod tests {
             schema_id: schema.id(),
             table_name: table_name.to_string(),
             table_schema: common_types::tests::build_schema(),
+            partition_info: None,
             engine: ANALYTIC_ENGINE_TYPE.to_string(),
             options: HashMap::new(),
             state: TableState::Stable,
@@ -1039,6 +1183,7 @@ mod tests {
             schema_id: schema.id(),
             table_name: table_name.to_string(),
             engine: engine_name.to_string(),
+            partition_info: None,
         };
         let drop_table_opts = DropOptions {
             table_engine: catalog_manager.get_engine_proxy(),
@@ -1124,4 +1269,70 @@ mod tests {
             assert!(schema.table_by_name(table_name).unwrap().is_none());
         }
     }
+    #[tokio::test]
+    async fn test_create_update_view() {
+        let env = TestEnv::builder().build();
+        let mut test_ctx = env.new_context();
+        test_ctx.open().await;
+
+        let catalog_manager = build_catalog_manager(test_ctx.engine()).await;
+        let schema = build_default_schema_with_catalog(&catalog_manager).await;
+
+        let view_name = "test_view";
+        let create_table_request = build_create_table_req(view_name, schema.clone()).await;
+        let create_table_opts = CreateOptions {
+            table_engine: catalog_manager.get_engine_proxy(),
+            create_if_not_exists: false,
+        };
+        schema
+            .create_table(create_table_request, create_table_opts)
+            .await
+            .unwrap();
+
+        let mut view = ViewInfo {
+            catalog_name: DEFAULT_CATALOG.to_string(),
+            schema_name: schema.name().to_string(),
+            view_name: view_name.to_string(),
+            query: "SELECT time_bucket(t, 'PT5M') AS t, avg(v) AS v FROM test GROUP BY \
+                    time_bucket(t, 'PT5M')"
+                .to_string(),
+            source_table: "test".to_string(),
+            period: "PT5M".to_string(),
+            watermark: Timestamp::new(0),
+        };
+        schema.create_view(view.clone()).await.unwrap();
+        assert!(schema.create_view(view.clone()).await.is_err());
+
+        view.watermark = Timestamp::new(300_000);
+        schema.update_view(view.clone()).await.unwrap();
+        assert_eq!(vec![view.clone()], schema.all_views().unwrap());
+
+        // The view is loaded from the sys catalog table.
+        let catalog_manager = build_catalog_manager(test_ctx.engine()).await;
+        let schema = build_default_schema_with_catalog(&catalog_manager).await;
+        assert_eq!(vec![view.clone()], schema.all_views().unwrap());
+
+        // Drop the view with its table.
+        let drop_table_request = DropTableRequest {
+            catalog_name: DEFAULT_CATALOG.to_string(),
+            schema_name: schema.name().to_string(),
+            schema_id: schema.id(),
+            table_name: view_name.to_string(),
+            engine: ANALYTIC_ENGINE_TYPE.to_string(),
+            partition_info: None,
+        };
+        let drop_table_opts = DropOptions {
+            table_engine: catalog_manager.get_engine_proxy(),
+        };
+        assert!(schema
+            .drop_table(drop_table_request, drop_table_opts)
+            .await
+            .unwrap());
+        assert!(schema.all_views().unwrap().is_empty());
+        assert!(schema.update_view(view).await.is_err());
+
+        let catalog_manager = build_catalog_manager(test_ctx.engine()).await;
+        let schema = build_default_schema_with_catalog(&catalog_manager).await;
+        assert!(schema.all_views().unwrap().is_empty());
+    }
 }
@@ -8,7 +8,7 @@ use arrow_deps::arrow::datatypes::{DataType, Field};
 use proto::common as common_pb;
 use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
 
-use crate::datum::DatumKind;
+use crate::datum::{Datum, DatumKind};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -61,6 +61,20 @@ pub enum Error {
         source: Box<dyn std::error::Error + Send + Sync>,
         backtrace: Backtrace,
     },
+
+    #[snafu(display(
+        "Invalid default value of column, name:{}, data_type:{}, default_value:{:?}.\nBacktrace:\n{}",
+        name,
+        data_type,
+        default_value,
+        backtrace
+    ))]
+    InvalidDefaultValue {
+        name: String,
+        data_type: DatumKind,
+        default_value: Datum,
+        backtrace: Backtrace,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -97,8 +111,9 @@ pub const COLUMN_ID_UNINIT: ColumnId = 0;
 pub enum ReadOp {
     /// Use the column exactly
     Exact,
-    /// Fill the column by null
-    FillNull,
+    /// Fill the column by its default value, or null if the column has no
+    /// default value
+    FillDefault,
 }
 
 /// Meta data of the arrow field.
@@ -148,6 +163,9 @@ pub struct ColumnSchema {
     pub is_tag: bool,
     /// Comment of the column
     pub comment: String,
+    /// Default value of the column, used to fill the column if it is absent
+    /// from the written row or the data written by an older schema
+    pub default_value: Option<Datum>,
 }
 
 impl ColumnSchema {
@@ -172,6 +190,12 @@ impl ColumnSchema {
         }
     }
 
+    /// Returns the datum to fill this column if it is missing, that is the
+    /// default value if any, otherwise null.
+    pub fn default_datum(&self) -> Datum {
+        self.default_value.clone().unwrap_or(Datum::Null)
+    }
+
     /// Convert `self` to [proto::common::ColumnSchema]
     ///
     /// The `is_key` is needed because it is maintained by
@@ -184,6 +208,9 @@ impl ColumnSchema {
         column_schema.set_id(self.id);
         column_schema.set_is_tag(self.is_tag);
         column_schema.set_comment(self.comment.clone());
+        if let Some(default_value) = &self.default_value {
+            column_schema.set_default_value(default_value.clone().into());
+        }
 
         column_schema
     }
@@ -223,14 +250,14 @@ impl ColumnSchema {
         &self,
         source_schema: &ColumnSchema,
     ) -> std::result::Result<ReadOp, CompatError> {
-        if self.is_nullable {
-            // Column is nullable
+        if self.is_nullable || self.default_value.is_some() {
+            // Column is nullable or has a default value
             if self.id == source_schema.id {
                 // Same column
                 Ok(ReadOp::Exact)
             } else {
-                // Not the same column, maybe dropped, fill by null.
-                Ok(ReadOp::FillNull)
+                // Not the same column, maybe dropped, fill by default value.
+                Ok(ReadOp::FillDefault)
             }
         } else {
             // Column is not null. We consider the old column was dropped if they have
@@ -249,7 +276,13 @@ impl ColumnSchema {
 }
 
 impl From<common_pb::ColumnSchema> for ColumnSchema {
-    fn from(column_schema: common_pb::ColumnSchema) -> Self {
+    fn from(mut column_schema: common_pb::ColumnSchema) -> Self {
+        let default_value = if column_schema.has_default_value() {
+            Some(Datum::from(column_schema.take_default_value()))
+        } else {
+            None
+        };
+
         Self {
             id: column_schema.id,
             name: column_schema.name,
@@ -257,6 +290,7 @@ impl From<common_pb::ColumnSchema> for ColumnSchema {
             is_nullable: column_schema.is_nullable,
             is_tag: column_schema.is_tag,
             comment: column_schema.comment,
+            default_value,
         }
     }
 }
@@ -290,6 +324,7 @@ impl TryFrom<&Field> for ColumnSchema {
             is_nullable: field.is_nullable(),
             is_tag,
             comment,
+            default_value: None,
         })
     }
 }
@@ -357,6 +392,7 @@ pub struct Builder {
     is_nullable: bool,
     is_tag: bool,
     comment: String,
+    default_value: Option<Datum>,
 }
 
 impl Builder {
@@ -369,6 +405,7 @@ impl Builder {
             is_nullable: false,
             is_tag: false,
             comment: String::new(),
+            default_value: None,
         }
     }
 
@@ -394,6 +431,13 @@ impl Builder {
         self
     }
 
+    /// Set the default value of this column, default is None (no default
+    /// value).
+    pub fn default_value(mut self, default_value: Option<Datum>) -> Self {
+        self.default_value = default_value;
+        self
+    }
+
     pub fn validate(&self) -> Result<()> {
         if self.is_tag {
             ensure!(
@@ -404,6 +448,17 @@ impl Builder {
             );
         }
 
+        if let Some(default_value) = &self.default_value {
+            ensure!(
+                default_value.kind() == self.data_type,
+                InvalidDefaultValue {
+                    name: &self.name,
+                    data_type: self.data_type,
+                    default_value: default_value.clone(),
+                }
+            );
+        }
+
         Ok(())
     }
 
@@ -417,6 +472,7 @@ impl Builder {
             is_nullable: self.is_nullable,
             is_tag: self.is_tag,
             comment: self.comment,
+            default_value: self.default_value,
         })
     }
 }
@@ -433,6 +489,7 @@ mod tests {
             .is_nullable(true)
             .is_tag(true)
             .comment("Comment of this column".to_string())
+            .default_value(Some(Datum::Boolean(true)))
             .build()
             .expect("should succeed to build column schema")
     }
@@ -447,6 +504,7 @@ mod tests {
             is_nullable: true,
             is_tag: true,
             comment: "Comment of this column".to_string(),
+            default_value: Some(Datum::Boolean(true)),
         };
 
         assert_eq!(&lhs, &rhs);
@@ -463,6 +521,38 @@ mod tests {
         assert_eq!(&schema_from_pb, &column_schema);
     }
 
+    #[test]
+    fn test_invalid_default_value() {
+        let res = Builder::new("test_column_schema".to_string(), DatumKind::Int32)
+            .default_value(Some(Datum::String("abc".into())))
+            .build();
+        assert!(matches!(res, Err(Error::InvalidDefaultValue { .. })));
+    }
+
+    #[test]
+    fn test_compatible_for_read_with_default() {
+        let source_schema = Builder::new("col".to_string(), DatumKind::Int32)
+            .id(1)
+            .build()
+            .unwrap();
+        let no_default = Builder::new("col".to_string(), DatumKind::Int32)
+            .id(2)
+            .build()
+            .unwrap();
+        assert!(no_default.compatible_for_read(&source_schema).is_err());
+
+        let with_default = Builder::new("col".to_string(), DatumKind::Int32)
+            .id(2)
+            .default_value(Some(Datum::Int32(10)))
+            .build()
+            .unwrap();
+        assert!(matches!(
+            with_default.compatible_for_read(&source_schema).unwrap(),
+            ReadOp::FillDefault
+        ));
+        assert_eq!(Datum::Int32(10), with_default.default_datum());
+    }
+
     #[test]
     fn test_valid_tag_type() {
         let invalid_tag_types = vec![DatumKind::Null, DatumKind::Float, DatumKind::Double];
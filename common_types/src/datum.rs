@@ -9,7 +9,7 @@ use arrow_deps::{
     datafusion::scalar::ScalarValue,
 };
 use chrono::{Local, TimeZone};
-use proto::common::DataType as DataTypePb;
+use proto::common::{DataType as DataTypePb, Datum as DatumPb, Datum_oneof_value};
 use serde::ser::{Serialize, Serializer};
 use snafu::{Backtrace, ResultExt, Snafu};
 use sqlparser::ast::{DataType as SqlDataType, Value};
@@ -631,7 +631,43 @@ impl Datum {
         }
     }
 
-    #[cfg(test)]
+    /// Create a datum from the [ScalarValue], returns None if the value is null
+    /// or its type is not supported.
+    pub fn from_scalar_value(val: &ScalarValue) -> Option<Self> {
+        match val {
+            ScalarValue::Boolean(v) => v.map(Datum::Boolean),
+            ScalarValue::Float32(v) => v.map(Datum::Float),
+            ScalarValue::Float64(v) => v.map(Datum::Double),
+            ScalarValue::Int8(v) => v.map(Datum::Int8),
+            ScalarValue::Int16(v) => v.map(Datum::Int16),
+            ScalarValue::Int32(v) => v.map(Datum::Int32),
+            ScalarValue::Int64(v) => v.map(Datum::Int64),
+            ScalarValue::UInt8(v) => v.map(Datum::UInt8),
+            ScalarValue::UInt16(v) => v.map(Datum::UInt16),
+            ScalarValue::UInt32(v) => v.map(Datum::UInt32),
+            ScalarValue::UInt64(v) => v.map(Datum::UInt64),
+            ScalarValue::Utf8(v) | ScalarValue::LargeUtf8(v) => v
+                .as_ref()
+                .map(|v| Datum::String(StringBytes::copy_from_str(v.as_str()))),
+            ScalarValue::Binary(v) | ScalarValue::LargeBinary(v) => v
+                .as_ref()
+                .map(|v| Datum::Varbinary(Bytes::copy_from_slice(v.as_slice()))),
+            ScalarValue::TimestampMillisecond(v, _) => {
+                v.map(|v| Datum::Timestamp(Timestamp::new(v)))
+            }
+            ScalarValue::List(_, _)
+            | ScalarValue::Date32(_)
+            | ScalarValue::Date64(_)
+            | ScalarValue::TimestampSecond(_, _)
+            | ScalarValue::TimestampMicrosecond(_, _)
+            | ScalarValue::TimestampNanosecond(_, _)
+            | ScalarValue::IntervalYearMonth(_)
+            | ScalarValue::IntervalDayTime(_)
+            | ScalarValue::Struct(_, _)
+            | ScalarValue::Decimal128(_, _, _) => None,
+        }
+    }
+
     pub fn as_view(&self) -> DatumView {
         match self {
             Datum::Null => DatumView::Null,
@@ -717,6 +753,57 @@ impl From<Option<&[u8]>> for Datum {
     }
 }
 
+impl From<Datum> for DatumPb {
+    fn from(datum: Datum) -> Self {
+        let value = match datum {
+            Datum::Null => None,
+            Datum::Timestamp(v) => Some(Datum_oneof_value::timestamp_value(v.as_i64())),
+            Datum::Double(v) => Some(Datum_oneof_value::double_value(v)),
+            Datum::Float(v) => Some(Datum_oneof_value::float_value(v)),
+            Datum::Varbinary(v) => Some(Datum_oneof_value::varbinary_value(v.to_vec())),
+            Datum::String(v) => Some(Datum_oneof_value::string_value(v.to_string())),
+            Datum::UInt64(v) => Some(Datum_oneof_value::uint64_value(v)),
+            Datum::UInt32(v) => Some(Datum_oneof_value::uint32_value(v)),
+            Datum::UInt16(v) => Some(Datum_oneof_value::uint16_value(v.into())),
+            Datum::UInt8(v) => Some(Datum_oneof_value::uint8_value(v.into())),
+            Datum::Int64(v) => Some(Datum_oneof_value::int64_value(v)),
+            Datum::Int32(v) => Some(Datum_oneof_value::int32_value(v)),
+            Datum::Int16(v) => Some(Datum_oneof_value::int16_value(v.into())),
+            Datum::Int8(v) => Some(Datum_oneof_value::int8_value(v.into())),
+            Datum::Boolean(v) => Some(Datum_oneof_value::bool_value(v)),
+        };
+
+        DatumPb {
+            value,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<DatumPb> for Datum {
+    fn from(datum: DatumPb) -> Self {
+        // The narrow integers are always encoded from the datum of the same kind, so
+        // the truncation won't happen.
+        match datum.value {
+            None => Datum::Null,
+            Some(Datum_oneof_value::timestamp_value(v)) => Datum::Timestamp(Timestamp::new(v)),
+            Some(Datum_oneof_value::double_value(v)) => Datum::Double(v),
+            Some(Datum_oneof_value::float_value(v)) => Datum::Float(v),
+            Some(Datum_oneof_value::varbinary_value(v)) => Datum::Varbinary(Bytes::from(v)),
+            Some(Datum_oneof_value::string_value(v)) => Datum::String(StringBytes::from(v)),
+            Some(Datum_oneof_value::uint64_value(v)) => Datum::UInt64(v),
+            Some(Datum_oneof_value::uint32_value(v)) => Datum::UInt32(v),
+            Some(Datum_oneof_value::uint16_value(v)) => Datum::UInt16(v as u16),
+            Some(Datum_oneof_value::uint8_value(v)) => Datum::UInt8(v as u8),
+            Some(Datum_oneof_value::int64_value(v)) => Datum::Int64(v),
+            Some(Datum_oneof_value::int32_value(v)) => Datum::Int32(v),
+            Some(Datum_oneof_value::int16_value(v)) => Datum::Int16(v as i16),
+            Some(Datum_oneof_value::int8_value(v)) => Datum::Int8(v as i8),
+            Some(Datum_oneof_value::bool_value(v)) => Datum::Boolean(v),
+        }
+    }
+}
+
 /// impl serde serialize for Datum
 impl Serialize for Datum {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
@@ -884,4 +971,30 @@ mod tests {
         assert_eq!(13, DatumKind::Int8.into_u8());
         assert_eq!(14, DatumKind::Boolean.into_u8());
     }
+
+    #[test]
+    fn test_pb_convert() {
+        let datums = vec![
+            Datum::Null,
+            Datum::Timestamp(Timestamp::new(1651737067000)),
+            Datum::Double(1.5),
+            Datum::Float(2.5),
+            Datum::Varbinary(Bytes::from_static(b"binary")),
+            Datum::String(StringBytes::from("string")),
+            Datum::UInt64(u64::MAX),
+            Datum::UInt32(u32::MAX),
+            Datum::UInt16(u16::MAX),
+            Datum::UInt8(u8::MAX),
+            Datum::Int64(i64::MIN),
+            Datum::Int32(i32::MIN),
+            Datum::Int16(i16::MIN),
+            Datum::Int8(i8::MIN),
+            Datum::Boolean(true),
+        ];
+
+        for datum in datums {
+            let pb = DatumPb::from(datum.clone());
+            assert_eq!(datum, Datum::from(pb));
+        }
+    }
 }
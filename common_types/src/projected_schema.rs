@@ -45,7 +45,8 @@ pub struct RowProjector {
     schema_with_key: RecordSchemaWithKey,
     source_schema: Schema,
     /// The Vec stores the column index in source, and `None` means this column
-    /// is not in source but required by reader, and need to filled by null.
+    /// is not in source but required by reader, and need to filled by its
+    /// default value or null.
     /// The length of Vec is the same as the number of columns reader intended
     /// to read.
     source_projection: Vec<Option<usize>>,
@@ -78,10 +79,10 @@ impl RowProjector {
 
         datums_buffer.reserve(self.schema_with_key.num_columns());
 
-        for p in &self.source_projection {
+        for (index, p) in self.source_projection.iter().enumerate() {
             let datum = match p {
                 Some(index_in_source) => row[*index_in_source].clone(),
-                None => Datum::Null,
+                None => self.schema_with_key.columns()[index].default_datum(),
             };
 
             datums_buffer.push(datum);
@@ -118,6 +119,25 @@ impl ProjectedSchema {
         self.0.is_all_projection()
     }
 
+    /// Returns the schema before projection.
+    #[inline]
+    pub fn original_schema(&self) -> &Schema {
+        &self.0.original_schema
+    }
+
+    /// Returns the index of the projected columns in the original schema,
+    /// `None` if all columns are projected.
+    #[inline]
+    pub fn projection(&self) -> Option<&[usize]> {
+        self.0.projection.as_deref()
+    }
+
+    /// Returns the name of the timestamp column.
+    #[inline]
+    pub fn timestamp_name(&self) -> &str {
+        self.0.original_schema.timestamp_name()
+    }
+
     /// Returns the [RowProjector] to project the rows with source schema to
     /// rows with [RecordSchemaWithKey].
     ///
@@ -206,8 +226,6 @@ impl ProjectedSchemaInner {
         self.projection.is_none()
     }
 
-    // TODO(yingwen): We can fill missing not null column with default value instead
-    //  of returning error.
     fn try_project_with_key(&self, source_schema: &Schema) -> Result<RowProjector> {
         debug_assert_eq!(
             self.schema_with_key.key_columns(),
@@ -237,10 +255,19 @@ impl ProjectedSchemaInner {
         source_schema: &Schema,
         source_projection: &mut Vec<Option<usize>>,
     ) -> Result<()> {
-        match source_schema.index_of(&column.name) {
+        let same_version = self.original_schema.version() == source_schema.version();
+        let source_idx = if same_version {
+            source_schema.index_of(&column.name)
+        } else {
+            // The column may be renamed, or dropped and then added again since the
+            // source schema, so find the column by its id instead of its name.
+            source_schema.index_of_column_id(column.id)
+        };
+
+        match source_idx {
             Some(source_idx) => {
                 // Column is in source
-                if self.original_schema.version() == source_schema.version() {
+                if same_version {
                     // Same version, just use that column in source
                     source_projection.push(Some(source_idx));
                 } else {
@@ -255,7 +282,7 @@ impl ProjectedSchemaInner {
                         ReadOp::Exact => {
                             source_projection.push(Some(source_idx));
                         }
-                        ReadOp::FillNull => {
+                        ReadOp::FillDefault => {
                             source_projection.push(None);
                         }
                     }
@@ -263,8 +290,12 @@ impl ProjectedSchemaInner {
             }
             None => {
                 // Column is not in source
-                ensure!(column.is_nullable, MissingReadColumn { name: &column.name });
-                // Column is nullable, fill this column by null
+                ensure!(
+                    column.is_nullable || column.default_value.is_some(),
+                    MissingReadColumn { name: &column.name }
+                );
+                // Column is nullable or has a default value, fill this column by its default
+                // value
                 source_projection.push(None);
             }
         }
@@ -275,7 +306,10 @@ impl ProjectedSchemaInner {
 
 #[cfg(test)]
 mod tests {
-    use crate::{projected_schema::ProjectedSchema, tests::build_schema};
+    use crate::{
+        column_schema, datum::DatumKind, projected_schema::ProjectedSchema, schema,
+        tests::build_schema,
+    };
 
     #[test]
     fn test_projected_schema() {
@@ -289,4 +323,42 @@ mod tests {
         );
         assert!(!projected_schema.is_all_projection());
     }
+
+    #[test]
+    fn test_project_renamed_and_dropped_columns() {
+        let source_schema = build_schema();
+        // Rename field1 to field3, then drop field2 and add a new field2.
+        let mut field3 = source_schema.column(2).clone();
+        field3.name = "field3".to_string();
+        let schema = schema::Builder::new()
+            .version(source_schema.version() + 1)
+            .max_column_id(source_schema.max_column_id())
+            .add_key_column(source_schema.column(0).clone())
+            .unwrap()
+            .add_key_column(source_schema.column(1).clone())
+            .unwrap()
+            .add_normal_column(field3)
+            .unwrap()
+            .auto_increment_column_id(true)
+            .add_normal_column(
+                column_schema::Builder::new("field2".to_string(), DatumKind::String)
+                    .is_nullable(true)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        // The id of the dropped field2 is not reused.
+        assert_eq!(source_schema.max_column_id() + 1, schema.column(3).id);
+
+        let projected_schema = ProjectedSchema::no_projection(schema);
+        let row_projector = projected_schema
+            .try_project_with_key(&source_schema)
+            .unwrap();
+        assert_eq!(
+            &[Some(0), Some(1), Some(2), None],
+            row_projector.source_projection()
+        );
+    }
 }
@@ -306,12 +306,13 @@ impl RecordBatchWithKey {
     /// Project the [RecordBatchWithKey] into a [RecordBatch] according to
     /// [ProjectedSchema].
     ///
-    /// REQUIRE: The schema_with_key of the [RecordBatchWithKey] is the same as
-    /// the schema_with_key of [ProjectedSchema].
+    /// REQUIRE: The schema_with_key of the [RecordBatchWithKey] has the same
+    /// key columns as the schema_with_key of [ProjectedSchema] and contains all
+    /// its columns.
     pub fn try_project(mut self, projected_schema: &ProjectedSchema) -> Result<RecordBatch> {
         debug_assert_eq!(
-            &self.schema_with_key,
-            projected_schema.as_record_schema_with_key()
+            self.schema_with_key.key_columns(),
+            projected_schema.as_record_schema_with_key().key_columns()
         );
 
         // Get the schema after projection.
@@ -559,8 +560,8 @@ impl From<RowProjector> for ArrowRecordBatchProjector {
 impl ArrowRecordBatchProjector {
     /// Project the [arrow::RecordBatch] to [RecordBatchWithKey] and these
     /// things is to be done:
-    ///  - Insert the null column if the projected column does not appear in the
-    ///    source schema.
+    ///  - Insert the column filled by default value (or null) if the projected
+    ///    column does not appear in the source schema.
     ///  - Convert the [arrow::RecordBatch] to [RecordBatchWithKey].
     ///
     /// REQUIRE: Schema of the `arrow_record_batch` is the same as the
@@ -600,10 +601,21 @@ impl ArrowRecordBatchProjector {
                 }
                 None => {
                     // Need to push row with specific type.
-                    let null_block =
-                        ColumnBlock::new_null_with_type(&column_schema.data_type, num_rows)
-                            .context(CreateColumnBlock)?;
-                    column_blocks.push(null_block);
+                    let column_block = match &column_schema.default_value {
+                        Some(default_value) => {
+                            let mut builder = ColumnBlockBuilder::with_capacity(
+                                &column_schema.data_type,
+                                num_rows,
+                            );
+                            for _ in 0..num_rows {
+                                builder.append(default_value.clone()).context(AppendDatum)?;
+                            }
+                            builder.build()
+                        }
+                        None => ColumnBlock::new_null_with_type(&column_schema.data_type, num_rows)
+                            .context(CreateColumnBlock)?,
+                    };
+                    column_blocks.push(column_block);
                 }
             }
         }
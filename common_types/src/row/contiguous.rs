@@ -119,7 +119,11 @@ impl<'a, T: ContiguousRow> ContiguousRow for ProjectedContiguousRow<'a, T> {
 
         match p {
             Some(index_in_source) => self.source_row.datum_view_at(index_in_source),
-            None => DatumView::Null,
+            None => self.projector.schema_with_key().columns()[index]
+                .default_value
+                .as_ref()
+                .map(Datum::as_view)
+                .unwrap_or(DatumView::Null),
         }
     }
 }
@@ -288,8 +292,20 @@ impl<'a, T: RowBuffer + 'a> ContiguousRowWriter<'a, T> {
 
                 // Write datum bytes to the buffer.
                 Self::write_datum(self.inner, datum, byte_offset, &mut next_string_offset)?;
+            } else if let Some(default_value) =
+                &self.table_schema.column(index_in_table).default_value
+            {
+                // Column not in row is filled by its default value.
+                let byte_offset = self.table_schema.byte_offset(index_in_table);
+                Self::write_datum(
+                    self.inner,
+                    default_value,
+                    byte_offset,
+                    &mut next_string_offset,
+                )?;
             }
-            // Column not in row is already filled by null.
+            // Column not in row without default value is already filled by
+            // null.
         }
 
         Ok(())
@@ -155,6 +155,8 @@ pub enum Error {
 // TODO(boyan)  make these constants configurable
 pub const TSID_COLUMN: &str = "tsid";
 pub const TIMESTAMP_COLUMN: &str = "timestamp";
+/// Name prefix of the dropped columns in [Schema::rename_columns_by_id].
+const DROPPED_COLUMN_PREFIX: &str = "__dropped_column_";
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -226,7 +228,7 @@ impl IndexInWriterSchema {
     /// write to.
     ///
     /// If the column is not in writer schema, returns None, which means that
-    /// this column should be filled by null.
+    /// this column should be filled by its default value or null.
     ///
     /// Panic if the index_in_table is out of bound
     pub fn column_index_in_writer(&self, index_in_table: usize) -> Option<usize> {
@@ -472,6 +474,9 @@ pub struct Schema {
     column_schemas: Arc<ColumnSchemas>,
     /// Version of the schema, schemas with same version should be identical.
     version: Version,
+    /// The max column id ever allocated to the columns of the table, including
+    /// the dropped ones, so the ids of the dropped columns are never reused.
+    max_column_id: ColumnId,
 }
 
 impl fmt::Debug for Schema {
@@ -574,6 +579,11 @@ impl Schema {
         self.column_schemas.index_of(name)
     }
 
+    /// Find the index of the column with the given column id.
+    pub fn index_of_column_id(&self, id: ColumnId) -> Option<usize> {
+        self.columns().iter().position(|column| column.id == id)
+    }
+
     /// Returns the number of columns in primary key
     #[inline]
     pub fn num_key_columns(&self) -> usize {
@@ -598,6 +608,12 @@ impl Schema {
         self.version
     }
 
+    /// Get the max column id ever allocated to the table
+    #[inline]
+    pub fn max_column_id(&self) -> ColumnId {
+        self.max_column_id
+    }
+
     /// Compare the two rows.
     ///
     /// REQUIRES: the two rows must have the key columns defined by the schema.
@@ -633,13 +649,14 @@ impl Schema {
                     index_in_writer.0.push(Some(writer_index));
                 }
                 None => {
-                    // Column is not found in writer, then the column should be nullable.
+                    // Column is not found in writer, then the column should be nullable or
+                    // have a default value.
                     ensure!(
-                        column.is_nullable,
+                        column.is_nullable || column.default_value.is_some(),
                         MissingWriteColumn { name: &column.name }
                     );
 
-                    // Column is nullable, push index mapping
+                    // Column can be filled by default value, push index mapping
                     index_in_writer.0.push(None);
                 }
             }
@@ -666,6 +683,35 @@ impl Schema {
         Ok(())
     }
 
+    /// Rename the columns of this schema to the names of the columns with the
+    /// same id in the `reader_schema`, so the exprs built on the reader schema
+    /// refer to the right columns of this (older) schema.
+    ///
+    /// The columns not in the `reader_schema` have been dropped and are renamed
+    /// to names no user column would have.
+    pub fn rename_columns_by_id(&self, reader_schema: &Schema) -> Schema {
+        let columns: Vec<_> = self
+            .columns()
+            .iter()
+            .map(|column| {
+                let mut column = column.clone();
+                column.name = match reader_schema.index_of_column_id(column.id) {
+                    Some(index) => reader_schema.column(index).name.clone(),
+                    None => format!("{}{}", DROPPED_COLUMN_PREFIX, column.id),
+                };
+                column
+            })
+            .collect();
+        let fields = columns.iter().map(|c| c.to_arrow_field()).collect();
+        let meta = self.arrow_schema.metadata().clone();
+
+        Schema {
+            arrow_schema: Arc::new(ArrowSchema::new_with_metadata(fields, meta)),
+            column_schemas: Arc::new(ColumnSchemas::new(columns)),
+            ..self.clone()
+        }
+    }
+
     pub fn to_record_schema(&self) -> RecordSchema {
         RecordSchema {
             arrow_schema: self.arrow_schema.clone(),
@@ -749,6 +795,7 @@ impl TryFrom<common_pb::TableSchema> for Schema {
     fn try_from(schema: common_pb::TableSchema) -> Result<Self> {
         let mut builder = Builder::with_capacity(schema.columns.len())
             .version(schema.version)
+            .max_column_id(schema.max_column_id)
             .enable_tsid_primary_key(schema.enable_tsid_primary_key);
 
         for (i, column_schema_pb) in schema.columns.into_iter().enumerate() {
@@ -779,6 +826,7 @@ impl From<Schema> for common_pb::TableSchema {
         table_schema.timestamp_index = schema.timestamp_index as u32;
         table_schema.enable_tsid_primary_key = schema.enable_tsid_primary_key;
         table_schema.version = schema.version;
+        table_schema.max_column_id = schema.max_column_id;
 
         table_schema
     }
@@ -872,6 +920,13 @@ impl Builder {
         self
     }
 
+    /// Set the max column id ever allocated to the table, the auto incremented
+    /// column ids are greater than it.
+    pub fn max_column_id(mut self, max_column_id: ColumnId) -> Self {
+        self.max_column_id = cmp::max(self.max_column_id, max_column_id);
+        self
+    }
+
     /// When auto increment is true, assign the column schema an auto
     /// incremented id if its id is [crate::column_schema::COLUMN_ID_UNINIT].
     ///
@@ -964,6 +1019,11 @@ impl Builder {
             version,
         } = Self::parse_arrow_schema_meta_or_default(arrow_schema.metadata())?;
         let tsid_index = Self::find_tsid_index(enable_tsid_primary_key, &columns)?;
+        let max_column_id = columns
+            .iter()
+            .map(|c| c.id)
+            .max()
+            .unwrap_or(column_schema::COLUMN_ID_UNINIT);
 
         let column_schemas = Arc::new(ColumnSchemas::new(columns));
 
@@ -975,6 +1035,7 @@ impl Builder {
             enable_tsid_primary_key,
             column_schemas,
             version,
+            max_column_id,
         })
     }
 
@@ -1099,6 +1160,7 @@ impl Builder {
             enable_tsid_primary_key: self.enable_tsid_primary_key,
             column_schemas: Arc::new(ColumnSchemas::new(self.columns)),
             version: self.version,
+            max_column_id: self.max_column_id,
         })
     }
 }
@@ -1400,6 +1462,70 @@ mod tests {
         assert_eq!(7, columns[3].id);
     }
 
+    #[test]
+    fn test_rename_columns_by_id() {
+        let source_schema = Builder::new()
+            .auto_increment_column_id(true)
+            .add_key_column(
+                column_schema::Builder::new("timestamp".to_string(), DatumKind::Timestamp)
+                    .build()
+                    .expect("should succeed build column schema"),
+            )
+            .unwrap()
+            .add_normal_column(
+                column_schema::Builder::new("field1".to_string(), DatumKind::Double)
+                    .is_nullable(true)
+                    .build()
+                    .expect("should succeed build column schema"),
+            )
+            .unwrap()
+            .add_normal_column(
+                column_schema::Builder::new("field2".to_string(), DatumKind::Double)
+                    .is_nullable(true)
+                    .build()
+                    .expect("should succeed build column schema"),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // Drop field1 and rename field2 to field1.
+        let mut field2 = source_schema.column(2).clone();
+        field2.name = "field1".to_string();
+        let reader_schema = Builder::new()
+            .version(source_schema.version() + 1)
+            .max_column_id(source_schema.max_column_id())
+            .add_key_column(source_schema.column(0).clone())
+            .unwrap()
+            .add_normal_column(field2)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(3, reader_schema.max_column_id());
+        assert_eq!(Some(1), reader_schema.index_of_column_id(3));
+        assert!(reader_schema.index_of_column_id(2).is_none());
+
+        // The max column id is persisted.
+        let schema_pb = common_pb::TableSchema::from(reader_schema.clone());
+        let schema_from_pb = Schema::try_from(schema_pb).unwrap();
+        assert_eq!(reader_schema, schema_from_pb);
+        assert_eq!(3, schema_from_pb.max_column_id());
+
+        let renamed_schema = source_schema.rename_columns_by_id(&reader_schema);
+        assert_eq!(source_schema.version(), renamed_schema.version());
+        let names: Vec<_> = renamed_schema
+            .columns()
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(vec!["timestamp", "__dropped_column_2", "field1"], names);
+        assert_eq!(
+            "field1",
+            renamed_schema.as_arrow_schema_ref().field(2).name()
+        );
+        assert_eq!(Some(2), renamed_schema.index_of("field1"));
+    }
+
     fn assert_row_compare(ordering: Ordering, schema: &Schema, row1: &Row, row2: &Row) {
         let schema_with_key = schema.to_record_schema_with_key();
         let lhs = RowWithMeta {
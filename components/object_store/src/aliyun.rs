@@ -1,15 +1,19 @@
 // Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
 
-use std::{collections::HashMap, fmt::Display, ops::Range};
+use std::{collections::HashMap, fmt::Display, ops::Range, time::SystemTime};
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use crypto::{hmac::Hmac, mac::Mac, sha1::Sha1};
 use futures::{
     stream::{self, BoxStream},
-    StreamExt,
+    StreamExt, TryStreamExt,
 };
 use oss_rust_sdk::{async_object::AsyncObjectAPI, errors::Error as AliyunError, prelude::OSS};
-use snafu::{ResultExt, Snafu};
+use quick_xml::events::Event;
+use reqwest::{header, Client, StatusCode, Url};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use upstream::{
     path::Path, Error as OssError, GetResult, ListResult, ObjectMeta, ObjectStore, Result,
 };
@@ -28,8 +32,50 @@ enum Error {
     #[snafu(display("Failed to delete object at path: {}, err: {}", path, source))]
     DeleteObject { path: String, source: AliyunError },
 
-    #[snafu(display("Operation {} is not implemented", op))]
-    Unimplemented { op: String },
+    #[snafu(display("Invalid url of endpoint: {}, err: {}", endpoint, source))]
+    InvalidUrl {
+        endpoint: String,
+        source: url::ParseError,
+    },
+
+    #[snafu(display("Failed to send request to path: {}, err: {}", path, source))]
+    SendRequest {
+        path: String,
+        source: reqwest::Error,
+    },
+
+    #[snafu(display(
+        "Unexpected status of request to path: {}, status: {}, body: {}",
+        path,
+        status,
+        body
+    ))]
+    UnexpectedStatus {
+        path: String,
+        status: StatusCode,
+        body: String,
+    },
+
+    #[snafu(display(
+        "Unexpected length of range read at path: {}, expect: {}, actual: {}",
+        path,
+        expect,
+        actual
+    ))]
+    UnexpectedRangeLength {
+        path: String,
+        expect: usize,
+        actual: usize,
+    },
+
+    #[snafu(display("Failed to parse list result, err: {}", source))]
+    ParseListResult { source: quick_xml::Error },
+
+    #[snafu(display("Missing field {} in list result", field))]
+    MissingListField { field: String },
+
+    #[snafu(display("Invalid field {} in list result, value: {}", field, value))]
+    InvalidListField { field: String, value: String },
 }
 
 impl From<Error> for OssError {
@@ -41,9 +87,24 @@ impl From<Error> for OssError {
     }
 }
 
+/// Max number of keys returned in a page of the list request
+const LIST_MAX_KEYS: usize = 1000;
+/// Delimiter of the path
+const DELIMITER: &str = "/";
+
 #[derive(Debug)]
 pub struct AliyunOSS {
     oss: OSS<'static>,
+    /// Client for the requests not supported by [OSS], e.g. ranged read and
+    /// list.
+    client: Client,
+    key_id: String,
+    key_secret: String,
+    endpoint: String,
+    bucket: String,
+    /// Access the bucket by url `endpoint/bucket/key` instead of
+    /// `bucket.endpoint/key`.
+    path_style: bool,
 }
 
 impl AliyunOSS {
@@ -53,13 +114,254 @@ impl AliyunOSS {
         endpoint: impl Into<String>,
         bucket: impl Into<String>,
     ) -> Self {
-        let oss = OSS::new(
+        let (key_id, key_secret, endpoint, bucket) = (
             key_id.into(),
             key_secret.into(),
             endpoint.into(),
             bucket.into(),
         );
-        Self { oss }
+        let oss = OSS::new(
+            key_id.clone(),
+            key_secret.clone(),
+            endpoint.clone(),
+            bucket.clone(),
+        );
+        Self {
+            oss,
+            client: Client::new(),
+            key_id,
+            key_secret,
+            endpoint,
+            bucket,
+            path_style: false,
+        }
+    }
+
+    /// Use the path style url for ranged read and list, which is required by
+    /// some OSS compatible services (and the mock server in tests).
+    pub fn with_path_style(mut self, path_style: bool) -> Self {
+        self.path_style = path_style;
+        self
+    }
+
+    /// Build the url of the `key`, the url of the bucket is returned if the key
+    /// is empty.
+    fn url(&self, key: &str) -> std::result::Result<Url, Error> {
+        // Use http if no scheme is given, the same as [OSS].
+        let (scheme, host) = match self.endpoint.split_once("://") {
+            Some((scheme, host)) => (scheme, host),
+            None => ("http", self.endpoint.as_str()),
+        };
+        let base = if self.path_style {
+            format!("{}://{}/{}/", scheme, host, self.bucket)
+        } else {
+            format!("{}://{}.{}/", scheme, self.bucket, host)
+        };
+        let mut url = Url::parse(&base).context(InvalidUrl {
+            endpoint: &self.endpoint,
+        })?;
+        if !key.is_empty() {
+            url.path_segments_mut()
+                .expect("http url must be a base")
+                .pop_if_empty()
+                .extend(key.split(DELIMITER));
+        }
+
+        Ok(url)
+    }
+
+    /// Sign the GET request of `key` (an empty key for the bucket) according to
+    /// the v1 signature of OSS, returns the value of the date and authorization
+    /// headers.
+    fn sign_get(&self, key: &str) -> (String, String) {
+        let date = httpdate::fmt_http_date(SystemTime::now());
+        let resource = format!("/{}/{}", self.bucket, key);
+        let string_to_sign = format!("GET\n\n\n{}\n{}", date, resource);
+
+        let mut hmac = Hmac::new(Sha1::new(), self.key_secret.as_bytes());
+        hmac.input(string_to_sign.as_bytes());
+        let signature = base64::encode(hmac.result().code());
+
+        (date, format!("OSS {}:{}", self.key_id, signature))
+    }
+
+    /// Send a signed GET request, returns the body of the response.
+    async fn send_get(
+        &self,
+        key: &str,
+        url: Url,
+        range: Option<&Range<usize>>,
+    ) -> std::result::Result<(StatusCode, Bytes), Error> {
+        let (date, authorization) = self.sign_get(key);
+        let mut request = self
+            .client
+            .get(url)
+            .header(header::DATE, date)
+            .header(header::AUTHORIZATION, authorization);
+        if let Some(range) = range {
+            // The end of the http range is inclusive.
+            request = request.header(
+                header::RANGE,
+                format!("bytes={}-{}", range.start, range.end - 1),
+            );
+        }
+
+        let response = request.send().await.context(SendRequest { path: key })?;
+        let status = response.status();
+        let body = response.bytes().await.context(SendRequest { path: key })?;
+        ensure!(
+            status.is_success(),
+            UnexpectedStatus {
+                path: key,
+                status,
+                body: String::from_utf8_lossy(&body),
+            }
+        );
+
+        Ok((status, body))
+    }
+
+    async fn list_page(
+        &self,
+        prefix: Option<&str>,
+        delimiter: Option<&str>,
+        marker: Option<&str>,
+    ) -> std::result::Result<ListPage, Error> {
+        let mut url = self.url("")?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("max-keys", &LIST_MAX_KEYS.to_string());
+            if let Some(prefix) = prefix {
+                query.append_pair("prefix", prefix);
+            }
+            if let Some(delimiter) = delimiter {
+                query.append_pair("delimiter", delimiter);
+            }
+            if let Some(marker) = marker {
+                query.append_pair("marker", marker);
+            }
+        }
+
+        let (_, body) = self.send_get("", url, None).await?;
+        ListPage::parse(&String::from_utf8_lossy(&body))
+    }
+}
+
+/// Returns the prefix to list objects under the `prefix` path.
+fn list_prefix(prefix: Option<&Path>) -> Option<String> {
+    prefix
+        .map(|p| p.to_string())
+        .filter(|p| !p.is_empty())
+        .map(|p| format!("{}{}", p, DELIMITER))
+}
+
+/// A page of the result of the list request.
+#[derive(Debug, Default)]
+struct ListPage {
+    objects: Vec<ObjectMeta>,
+    /// Common prefixes without the trailing delimiter
+    common_prefixes: Vec<Path>,
+    /// Marker to list the next page, None if this is the last page
+    next_marker: Option<String>,
+}
+
+impl ListPage {
+    /// Parse the xml body of the list response, e.g.
+    ///
+    /// ```xml
+    /// <ListBucketResult>
+    ///   <IsTruncated>true</IsTruncated>
+    ///   <NextMarker>a/c</NextMarker>
+    ///   <Contents>
+    ///     <Key>a/b</Key>
+    ///     <LastModified>2022-06-01T08:00:00.000Z</LastModified>
+    ///     <Size>1024</Size>
+    ///   </Contents>
+    ///   <CommonPrefixes>
+    ///     <Prefix>a/d/</Prefix>
+    ///   </CommonPrefixes>
+    /// </ListBucketResult>
+    /// ```
+    fn parse(xml: &str) -> std::result::Result<Self, Error> {
+        let mut reader = quick_xml::Reader::from_str(xml);
+        reader.trim_text(true);
+
+        let mut page = ListPage::default();
+        let mut is_truncated = false;
+        // Path of the elements from the root to the current element.
+        let mut elements: Vec<String> = Vec::new();
+        // Fields of the current object: (key, last modified, size).
+        let mut object: (Option<String>, Option<DateTime<Utc>>, Option<usize>) = (None, None, None);
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event(&mut buf).context(ParseListResult)? {
+                Event::Start(e) => {
+                    elements.push(String::from_utf8_lossy(e.name()).into_owned());
+                }
+                Event::End(_) => {
+                    if elements.pop().as_deref() == Some("Contents") {
+                        let (key, last_modified, size) = std::mem::take(&mut object);
+                        page.objects.push(ObjectMeta {
+                            location: Path::from(key.context(MissingListField { field: "Key" })?),
+                            last_modified: last_modified.context(MissingListField {
+                                field: "LastModified",
+                            })?,
+                            size: size.context(MissingListField { field: "Size" })?,
+                        });
+                    }
+                }
+                Event::Text(e) => {
+                    let text = e.unescape_and_decode(&reader).context(ParseListResult)?;
+                    let parent = elements.iter().rev().nth(1).map(|v| v.as_str());
+                    match (parent, elements.last().map(|v| v.as_str())) {
+                        (Some("ListBucketResult"), Some("IsTruncated")) => {
+                            is_truncated = text == "true";
+                        }
+                        (Some("ListBucketResult"), Some("NextMarker")) => {
+                            page.next_marker = Some(text);
+                        }
+                        (Some("Contents"), Some("Key")) => object.0 = Some(text),
+                        (Some("Contents"), Some("LastModified")) => {
+                            let last_modified = DateTime::parse_from_rfc3339(&text).ok().context(
+                                InvalidListField {
+                                    field: "LastModified",
+                                    value: &text,
+                                },
+                            )?;
+                            object.1 = Some(last_modified.with_timezone(&Utc));
+                        }
+                        (Some("Contents"), Some("Size")) => {
+                            let size = text.parse().ok().context(InvalidListField {
+                                field: "Size",
+                                value: &text,
+                            })?;
+                            object.2 = Some(size);
+                        }
+                        (Some("CommonPrefixes"), Some("Prefix")) => {
+                            let prefix = text.strip_suffix(DELIMITER).unwrap_or(&text);
+                            page.common_prefixes.push(Path::from(prefix.to_string()));
+                        }
+                        _ => (),
+                    }
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+            buf.clear();
+        }
+
+        if !is_truncated {
+            page.next_marker = None;
+        } else {
+            ensure!(
+                page.next_marker.is_some(),
+                MissingListField {
+                    field: "NextMarker"
+                }
+            );
+        }
+
+        Ok(page)
     }
 }
 
@@ -93,11 +395,30 @@ impl ObjectStore for AliyunOSS {
         Ok(GetResult::Stream(stream::once(async { Ok(bytes) }).boxed()))
     }
 
-    async fn get_range(&self, _location: &Path, _range: Range<usize>) -> Result<Bytes> {
-        Err(Error::Unimplemented {
-            op: "get_range".to_string(),
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        if range.is_empty() {
+            return Ok(Bytes::new());
         }
-        .into())
+
+        let key = location.to_string();
+        let url = self.url(&key)?;
+        let (status, bytes) = self.send_get(&key, url, Some(&range)).await?;
+        // The whole object is returned if the range is not supported by the server.
+        let bytes = if status == StatusCode::PARTIAL_CONTENT {
+            bytes
+        } else {
+            bytes.slice(range.start.min(bytes.len())..range.end.min(bytes.len()))
+        };
+        ensure!(
+            bytes.len() == range.len(),
+            UnexpectedRangeLength {
+                path: key,
+                expect: range.len(),
+                actual: bytes.len(),
+            }
+        );
+
+        Ok(bytes)
     }
 
     async fn head(&self, location: &Path) -> Result<ObjectMeta> {
@@ -127,18 +448,54 @@ impl ObjectStore for AliyunOSS {
         Ok(())
     }
 
-    async fn list(&self, _prefix: Option<&Path>) -> Result<BoxStream<'_, Result<ObjectMeta>>> {
-        Err(Error::Unimplemented {
-            op: "list".to_string(),
-        }
-        .into())
+    async fn list(&self, prefix: Option<&Path>) -> Result<BoxStream<'_, Result<ObjectMeta>>> {
+        let prefix = list_prefix(prefix);
+
+        // The state is (marker, whether the listing is finished).
+        let pages = stream::try_unfold((None, false), move |(marker, finished)| {
+            let prefix = prefix.clone();
+            async move {
+                if finished {
+                    return Ok(None);
+                }
+
+                let page = self
+                    .list_page(prefix.as_deref(), None, marker.as_deref())
+                    .await?;
+                let objects = page.objects.into_iter().map(Ok);
+                let finished = page.next_marker.is_none();
+
+                Ok::<_, OssError>(Some((stream::iter(objects), (page.next_marker, finished))))
+            }
+        });
+
+        Ok(pages.try_flatten().boxed())
     }
 
-    async fn list_with_delimiter(&self, _prefix: Option<&Path>) -> Result<ListResult> {
-        Err(Error::Unimplemented {
-            op: "list_with_delimiter".to_string(),
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let prefix = list_prefix(prefix);
+
+        let mut objects = Vec::new();
+        let mut common_prefixes = Vec::new();
+        let mut marker = None;
+        loop {
+            let page = self
+                .list_page(prefix.as_deref(), Some(DELIMITER), marker.as_deref())
+                .await?;
+            objects.extend(page.objects);
+            common_prefixes.extend(page.common_prefixes);
+
+            marker = page.next_marker;
+            if marker.is_none() {
+                break;
+            }
         }
-        .into())
+
+        Ok(ListResult {
+            next_token: None,
+            common_prefixes,
+            objects,
+        })
     }
 }
 
@@ -147,3 +504,227 @@ impl Display for AliyunOSS {
         write!(f, "AliyunOSS({})", self.oss.bucket())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        net::SocketAddr,
+        sync::Arc,
+    };
+
+    use warp::{http::Response, hyper::Body, path::Tail, Filter};
+
+    use super::*;
+
+    const KEY_ID: &str = "test_key_id";
+    const BUCKET: &str = "test_bucket";
+    /// Max number of keys returned in a page by the mock server
+    const MOCK_PAGE_SIZE: usize = 2;
+
+    /// Start a mock server serving the ranged read and list requests of the
+    /// `objects`, returns the address of the server.
+    fn start_mock_server(objects: BTreeMap<String, Bytes>) -> SocketAddr {
+        let objects = Arc::new(objects);
+        let route = warp::get()
+            .and(warp::path::tail())
+            .and(warp::query::raw().or(warp::any().map(String::new)).unify())
+            .and(warp::header::optional::<String>("range"))
+            .and(warp::header::optional::<String>("authorization"))
+            .map(
+                move |tail: Tail, query: String, range: Option<String>, auth: Option<String>| {
+                    let authorized = auth
+                        .map(|v| v.starts_with(&format!("OSS {}:", KEY_ID)))
+                        .unwrap_or(false);
+                    if !authorized {
+                        return response(StatusCode::FORBIDDEN, Bytes::new());
+                    }
+
+                    match tail.as_str().split_once('/') {
+                        Some((BUCKET, "")) => mock_list(&objects, &query),
+                        Some((BUCKET, key)) => mock_get(&objects, key, range),
+                        _ => response(StatusCode::NOT_FOUND, Bytes::new()),
+                    }
+                },
+            );
+
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        addr
+    }
+
+    fn response(status: StatusCode, body: Bytes) -> Response<Body> {
+        Response::builder()
+            .status(status)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    fn mock_get(
+        objects: &BTreeMap<String, Bytes>,
+        key: &str,
+        range: Option<String>,
+    ) -> Response<Body> {
+        let bytes = match objects.get(key) {
+            Some(v) => v,
+            None => return response(StatusCode::NOT_FOUND, Bytes::new()),
+        };
+        match range {
+            Some(range) => {
+                let (start, end) = range
+                    .strip_prefix("bytes=")
+                    .and_then(|r| r.split_once('-'))
+                    .unwrap();
+                let (start, end): (usize, usize) = (start.parse().unwrap(), end.parse().unwrap());
+                let body = bytes.slice(start..(end + 1).min(bytes.len()));
+                response(StatusCode::PARTIAL_CONTENT, body)
+            }
+            None => response(StatusCode::OK, bytes.clone()),
+        }
+    }
+
+    fn mock_list(objects: &BTreeMap<String, Bytes>, query: &str) -> Response<Body> {
+        let params: HashMap<_, _> = url::form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect();
+        let prefix = params.get("prefix").map(|v| v.as_str()).unwrap_or("");
+        let delimiter = params.get("delimiter").map(|v| v.as_str());
+        let marker = params.get("marker").map(|v| v.as_str()).unwrap_or("");
+
+        // Entries of (key or common prefix, whether it is a common prefix).
+        let mut entries = BTreeSet::new();
+        for key in objects.keys().filter(|k| k.starts_with(prefix)) {
+            let rest = &key[prefix.len()..];
+            match delimiter.and_then(|d| rest.find(d).map(|pos| pos + d.len())) {
+                Some(end) => entries.insert((format!("{}{}", prefix, &rest[..end]), true)),
+                None => entries.insert((key.clone(), false)),
+            };
+        }
+        let remaining: Vec<_> = entries
+            .into_iter()
+            .filter(|(name, _)| name.as_str() > marker)
+            .collect();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?><ListBucketResult>");
+        xml.push_str(&format!(
+            "<Name>{}</Name><Prefix>{}</Prefix>",
+            BUCKET, prefix
+        ));
+        let page = &remaining[..remaining.len().min(MOCK_PAGE_SIZE)];
+        if page.len() < remaining.len() {
+            xml.push_str(&format!(
+                "<IsTruncated>true</IsTruncated><NextMarker>{}</NextMarker>",
+                page.last().unwrap().0
+            ));
+        } else {
+            xml.push_str("<IsTruncated>false</IsTruncated>");
+        }
+        for (name, is_prefix) in page {
+            if *is_prefix {
+                xml.push_str(&format!(
+                    "<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>",
+                    name
+                ));
+            } else {
+                xml.push_str(&format!(
+                    "<Contents><Key>{}</Key><LastModified>2022-06-01T08:00:00.000Z</LastModified>\
+                     <Size>{}</Size><Owner><ID>0</ID></Owner></Contents>",
+                    name,
+                    objects[name].len()
+                ));
+            }
+        }
+        xml.push_str("</ListBucketResult>");
+
+        response(StatusCode::OK, Bytes::from(xml))
+    }
+
+    fn new_store(objects: &[(&str, &'static [u8])]) -> AliyunOSS {
+        let objects = objects
+            .iter()
+            .map(|(k, v)| (k.to_string(), Bytes::from_static(v)))
+            .collect();
+        let addr = start_mock_server(objects);
+
+        AliyunOSS::new(
+            KEY_ID,
+            "test_key_secret",
+            format!("http://{}", addr),
+            BUCKET,
+        )
+        .with_path_style(true)
+    }
+
+    #[tokio::test]
+    async fn test_get_range() {
+        let store = new_store(&[("a/b.sst", b"0123456789")]);
+        let location = Path::from("a/b.sst");
+
+        assert_eq!(
+            Bytes::from_static(b"2345"),
+            store.get_range(&location, 2..6).await.unwrap()
+        );
+        assert_eq!(
+            Bytes::from_static(b"89"),
+            store.get_range(&location, 8..10).await.unwrap()
+        );
+        assert!(store.get_range(&location, 8..12).await.is_err());
+        assert!(store.get_range(&Path::from("a/c.sst"), 0..1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_list() {
+        let store = new_store(&[
+            ("t/1/a", b"v"),
+            ("t/1/b", b"v"),
+            ("t/2/a", b"v"),
+            ("t/x", b"value"),
+            ("t/y", b"v"),
+            ("u/a", b"v"),
+        ]);
+
+        let metas: Vec<_> = store
+            .list(Some(&Path::from("t")))
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let keys: Vec<_> = metas.iter().map(|m| m.location.to_string()).collect();
+        assert_eq!(vec!["t/1/a", "t/1/b", "t/2/a", "t/x", "t/y"], keys);
+        assert_eq!(5, metas[3].size);
+
+        let result = store
+            .list_with_delimiter(Some(&Path::from("t")))
+            .await
+            .unwrap();
+        let prefixes: Vec<_> = result
+            .common_prefixes
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let objects: Vec<_> = result
+            .objects
+            .iter()
+            .map(|o| o.location.to_string())
+            .collect();
+        assert_eq!(vec!["t/1", "t/2"], prefixes);
+        assert_eq!(vec!["t/x", "t/y"], objects);
+    }
+
+    #[test]
+    fn test_url() {
+        let store = AliyunOSS::new(KEY_ID, "secret", "oss.example.com", BUCKET);
+        assert_eq!(
+            "http://test_bucket.oss.example.com/a/b%20c.sst",
+            store.url("a/b c.sst").unwrap().as_str()
+        );
+
+        let store = AliyunOSS::new(KEY_ID, "secret", "https://oss.example.com", BUCKET)
+            .with_path_style(true);
+        assert_eq!(
+            "https://oss.example.com/test_bucket/",
+            store.url("").unwrap().as_str()
+        );
+    }
+}
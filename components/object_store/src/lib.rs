@@ -9,3 +9,4 @@ pub use upstream::{
 
 pub mod aliyun;
 pub mod cache;
+pub mod s3;
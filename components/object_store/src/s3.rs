@@ -0,0 +1,982 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! [ObjectStore] implementation for S3 compatible services (e.g. AWS S3,
+//! MinIO).
+//!
+//! The store accesses the service through [S3Client], [RusotoS3Client] is the
+//! implementation talking to the real service, and other implementations (e.g.
+//! an in-process fake) can be used for testing.
+
+use std::{fmt::Display, ops::Range};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::{
+    future,
+    stream::{self, BoxStream},
+    StreamExt, TryStreamExt,
+};
+use rusoto_core::{credential::StaticProvider, HttpClient, Region, RusotoError};
+use rusoto_s3::{
+    AbortMultipartUploadRequest, CompleteMultipartUploadRequest, CompletedMultipartUpload,
+    CompletedPart, CreateMultipartUploadRequest, DeleteObjectRequest, GetObjectRequest,
+    HeadObjectRequest, ListObjectsV2Request, PutObjectRequest, UploadPartRequest, S3,
+};
+use snafu::{ResultExt, Snafu};
+use upstream::{
+    path::Path, Error as OssError, GetResult, ListResult, ObjectMeta, ObjectStore, Result,
+};
+
+/// Error of [S3Client]
+pub type ClientError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Delimiter of the path
+const DELIMITER: &str = "/";
+/// Minimal size of a part in multipart upload, except the last part
+const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+/// Default size of a part in multipart upload
+pub const DEFAULT_PART_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Snafu)]
+enum Error {
+    #[snafu(display("Failed to put object at path: {}, err: {}", path, source))]
+    PutObject { path: String, source: ClientError },
+
+    #[snafu(display(
+        "Failed to upload part of object at path: {}, upload_id: {}, err: {}",
+        path,
+        upload_id,
+        source
+    ))]
+    UploadPart {
+        path: String,
+        upload_id: String,
+        source: ClientError,
+    },
+
+    #[snafu(display("Failed to get object at path: {}, err: {}", path, source))]
+    GetObject { path: String, source: ClientError },
+
+    #[snafu(display("Failed to head object at path: {}, err: {}", path, source))]
+    HeadObject { path: String, source: ClientError },
+
+    #[snafu(display("Failed to delete object at path: {}, err: {}", path, source))]
+    DeleteObject { path: String, source: ClientError },
+
+    #[snafu(display("Failed to list objects with prefix: {:?}, err: {}", prefix, source))]
+    ListObjects {
+        prefix: Option<String>,
+        source: ClientError,
+    },
+
+    #[snafu(display("Missing field {} in the response of {}", field, op))]
+    MissingField { op: String, field: String },
+
+    #[snafu(display("Invalid last modified time: {}, err: {}", time, source))]
+    InvalidLastModified {
+        time: String,
+        source: chrono::ParseError,
+    },
+}
+
+impl From<Error> for OssError {
+    fn from(source: Error) -> Self {
+        Self::Generic {
+            store: "S3",
+            source: Box::new(source),
+        }
+    }
+}
+
+/// Meta of an object returned by [S3Client]
+#[derive(Debug, Clone)]
+pub struct S3Object {
+    pub key: String,
+    pub size: usize,
+    pub last_modified: DateTime<Utc>,
+}
+
+/// A page of objects returned by [S3Client::list_objects]
+#[derive(Debug, Default)]
+pub struct ListObjectsOutput {
+    pub objects: Vec<S3Object>,
+    /// Common prefixes (ends with the delimiter) if the delimiter is given
+    pub common_prefixes: Vec<String>,
+    /// Token to list the next page, None if this is the last page
+    pub next_continuation_token: Option<String>,
+}
+
+/// A part uploaded by [S3Client::upload_part]
+#[derive(Debug, Clone)]
+pub struct UploadedPart {
+    pub part_number: i64,
+    pub e_tag: String,
+}
+
+/// Operations on a bucket of the S3 compatible service used by [S3Store]
+#[async_trait]
+pub trait S3Client: std::fmt::Debug + Send + Sync + 'static {
+    /// Name of the bucket
+    fn bucket(&self) -> &str;
+
+    async fn put_object(&self, key: &str, body: Bytes) -> std::result::Result<(), ClientError>;
+
+    /// Get the object, only the bytes in `range` are returned if it is given
+    async fn get_object(
+        &self,
+        key: &str,
+        range: Option<Range<usize>>,
+    ) -> std::result::Result<Bytes, ClientError>;
+
+    async fn head_object(&self, key: &str) -> std::result::Result<S3Object, ClientError>;
+
+    async fn delete_object(&self, key: &str) -> std::result::Result<(), ClientError>;
+
+    /// List a page of objects whose key starts with the `prefix`
+    async fn list_objects(
+        &self,
+        prefix: Option<&str>,
+        delimiter: Option<&str>,
+        continuation_token: Option<String>,
+    ) -> std::result::Result<ListObjectsOutput, ClientError>;
+
+    /// Start a multipart upload, returns the upload id
+    async fn create_multipart_upload(&self, key: &str) -> std::result::Result<String, ClientError>;
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: i64,
+        body: Bytes,
+    ) -> std::result::Result<UploadedPart, ClientError>;
+
+    async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<UploadedPart>,
+    ) -> std::result::Result<(), ClientError>;
+
+    async fn abort_multipart_upload(
+        &self,
+        key: &str,
+        upload_id: &str,
+    ) -> std::result::Result<(), ClientError>;
+}
+
+/// Object store on a bucket of the S3 compatible service
+#[derive(Debug)]
+pub struct S3Store<C> {
+    client: C,
+    /// Objects larger than this are uploaded by multipart upload
+    part_size: usize,
+}
+
+impl<C: S3Client> S3Store<C> {
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+            part_size: DEFAULT_PART_SIZE,
+        }
+    }
+
+    /// Set the size of each part in multipart upload, it will be adjusted to
+    /// [MIN_PART_SIZE] if it is too small.
+    pub fn with_part_size(mut self, part_size: usize) -> Self {
+        self.part_size = part_size.max(MIN_PART_SIZE);
+        self
+    }
+
+    async fn put_multipart(&self, key: &str, bytes: Bytes) -> Result<()> {
+        let upload_id = self
+            .client
+            .create_multipart_upload(key)
+            .await
+            .context(PutObject { path: key })?;
+
+        let uploads = (0..bytes.len())
+            .step_by(self.part_size)
+            .enumerate()
+            .map(|(idx, start)| {
+                let end = (start + self.part_size).min(bytes.len());
+                // Part number starts from 1.
+                self.client
+                    .upload_part(key, &upload_id, idx as i64 + 1, bytes.slice(start..end))
+            });
+        let parts = match future::try_join_all(uploads).await {
+            Ok(v) => v,
+            Err(e) => {
+                // Abort the upload to free the uploaded parts, the error of abort is ignored
+                // as the upload failed anyway.
+                let _ = self.client.abort_multipart_upload(key, &upload_id).await;
+                return Err(e)
+                    .context(UploadPart {
+                        path: key,
+                        upload_id,
+                    })
+                    .map_err(Into::into);
+            }
+        };
+
+        if let Err(e) = self
+            .client
+            .complete_multipart_upload(key, &upload_id, parts)
+            .await
+        {
+            let _ = self.client.abort_multipart_upload(key, &upload_id).await;
+            return Err(e).context(PutObject { path: key }).map_err(Into::into);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the prefix to list objects under the `prefix` path.
+fn list_prefix(prefix: Option<&Path>) -> Option<String> {
+    prefix
+        .map(|p| p.to_string())
+        .filter(|p| !p.is_empty())
+        .map(|p| format!("{}{}", p, DELIMITER))
+}
+
+fn object_meta(object: S3Object) -> ObjectMeta {
+    ObjectMeta {
+        location: Path::from(object.key),
+        last_modified: object.last_modified,
+        size: object.size,
+    }
+}
+
+#[async_trait]
+impl<C: S3Client> ObjectStore for S3Store<C> {
+    async fn put(&self, location: &Path, bytes: Bytes) -> Result<()> {
+        let key = location.to_string();
+        if bytes.len() > self.part_size {
+            return self.put_multipart(&key, bytes).await;
+        }
+
+        self.client
+            .put_object(&key, bytes)
+            .await
+            .context(PutObject { path: key })?;
+
+        Ok(())
+    }
+
+    async fn get(&self, location: &Path) -> Result<GetResult> {
+        let key = location.to_string();
+        let bytes = self
+            .client
+            .get_object(&key, None)
+            .await
+            .context(GetObject { path: key })?;
+
+        Ok(GetResult::Stream(stream::once(async { Ok(bytes) }).boxed()))
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        if range.is_empty() {
+            return Ok(Bytes::new());
+        }
+
+        let key = location.to_string();
+        let bytes = self
+            .client
+            .get_object(&key, Some(range))
+            .await
+            .context(GetObject { path: key })?;
+
+        Ok(bytes)
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        let key = location.to_string();
+        let object = self
+            .client
+            .head_object(&key)
+            .await
+            .context(HeadObject { path: key })?;
+
+        Ok(ObjectMeta {
+            location: location.clone(),
+            last_modified: object.last_modified,
+            size: object.size,
+        })
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        let key = location.to_string();
+        self.client
+            .delete_object(&key)
+            .await
+            .context(DeleteObject { path: key })?;
+
+        Ok(())
+    }
+
+    async fn list(&self, prefix: Option<&Path>) -> Result<BoxStream<'_, Result<ObjectMeta>>> {
+        let prefix = list_prefix(prefix);
+
+        // The state is (continuation token, whether the listing is finished).
+        let pages = stream::try_unfold((None, false), move |(token, finished)| {
+            let prefix = prefix.clone();
+            async move {
+                if finished {
+                    return Ok(None);
+                }
+
+                let output = self
+                    .client
+                    .list_objects(prefix.as_deref(), None, token)
+                    .await
+                    .context(ListObjects {
+                        prefix: prefix.clone(),
+                    })?;
+                let objects = output.objects.into_iter().map(|o| Ok(object_meta(o)));
+                let finished = output.next_continuation_token.is_none();
+                let state = (output.next_continuation_token, finished);
+
+                Ok::<_, OssError>(Some((stream::iter(objects), state)))
+            }
+        });
+
+        Ok(pages.try_flatten().boxed())
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let prefix = list_prefix(prefix);
+
+        let mut objects = Vec::new();
+        let mut common_prefixes = Vec::new();
+        let mut token = None;
+        loop {
+            let output = self
+                .client
+                .list_objects(prefix.as_deref(), Some(DELIMITER), token)
+                .await
+                .context(ListObjects {
+                    prefix: prefix.clone(),
+                })?;
+
+            objects.extend(output.objects.into_iter().map(object_meta));
+            common_prefixes.extend(
+                output
+                    .common_prefixes
+                    .into_iter()
+                    .map(|p| Path::from(p.strip_suffix(DELIMITER).unwrap_or(&p).to_string())),
+            );
+
+            token = output.next_continuation_token;
+            if token.is_none() {
+                break;
+            }
+        }
+
+        Ok(ListResult {
+            next_token: None,
+            common_prefixes,
+            objects,
+        })
+    }
+}
+
+impl<C: S3Client> Display for S3Store<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "S3Store({})", self.client.bucket())
+    }
+}
+
+/// [S3Client] talks to the S3 compatible service
+pub struct RusotoS3Client {
+    client: rusoto_s3::S3Client,
+    bucket: String,
+}
+
+impl RusotoS3Client {
+    /// Create a client to access the `bucket`.
+    ///
+    /// The `endpoint` can be the address of any S3 compatible service, e.g.
+    /// `http://127.0.0.1:9000` for a local MinIO.
+    pub fn new(
+        region: impl Into<String>,
+        endpoint: impl Into<String>,
+        key_id: impl Into<String>,
+        key_secret: impl Into<String>,
+        bucket: impl Into<String>,
+    ) -> std::result::Result<Self, ClientError> {
+        let http_client = HttpClient::new()?;
+        let credentials = StaticProvider::new_minimal(key_id.into(), key_secret.into());
+        let region = Region::Custom {
+            name: region.into(),
+            endpoint: endpoint.into(),
+        };
+
+        Ok(Self {
+            client: rusoto_s3::S3Client::new_with(http_client, credentials, region),
+            bucket: bucket.into(),
+        })
+    }
+}
+
+impl std::fmt::Debug for RusotoS3Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RusotoS3Client")
+            .field("bucket", &self.bucket)
+            .finish()
+    }
+}
+
+/// Parse the last modified time in the response, which is in RFC 3339 format
+/// in ListObjects and RFC 2822 format in HeadObject.
+fn parse_last_modified(time: &str) -> std::result::Result<DateTime<Utc>, ClientError> {
+    let parsed = DateTime::parse_from_rfc3339(time)
+        .or_else(|_| DateTime::parse_from_rfc2822(time))
+        .context(InvalidLastModified { time })?;
+
+    Ok(parsed.with_timezone(&Utc))
+}
+
+fn missing_field(op: &str, field: &str) -> ClientError {
+    Box::new(Error::MissingField {
+        op: op.to_string(),
+        field: field.to_string(),
+    })
+}
+
+fn box_err<E: std::error::Error + Send + Sync + 'static>(e: RusotoError<E>) -> ClientError {
+    Box::new(e)
+}
+
+#[async_trait]
+impl S3Client for RusotoS3Client {
+    fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    async fn put_object(&self, key: &str, body: Bytes) -> std::result::Result<(), ClientError> {
+        let request = PutObjectRequest {
+            bucket: self.bucket.clone(),
+            key: key.to_string(),
+            content_length: Some(body.len() as i64),
+            body: Some(body.to_vec().into()),
+            ..Default::default()
+        };
+        self.client.put_object(request).await.map_err(box_err)?;
+
+        Ok(())
+    }
+
+    async fn get_object(
+        &self,
+        key: &str,
+        range: Option<Range<usize>>,
+    ) -> std::result::Result<Bytes, ClientError> {
+        let request = GetObjectRequest {
+            bucket: self.bucket.clone(),
+            key: key.to_string(),
+            // The end of the http range is inclusive.
+            range: range.map(|r| format!("bytes={}-{}", r.start, r.end - 1)),
+            ..Default::default()
+        };
+        let output = self.client.get_object(request).await.map_err(box_err)?;
+        let body = output
+            .body
+            .ok_or_else(|| missing_field("GetObject", "body"))?;
+
+        let mut buf = Vec::with_capacity(output.content_length.unwrap_or(0).max(0) as usize);
+        let mut body = body;
+        while let Some(chunk) = body.try_next().await? {
+            buf.extend_from_slice(&chunk);
+        }
+
+        Ok(Bytes::from(buf))
+    }
+
+    async fn head_object(&self, key: &str) -> std::result::Result<S3Object, ClientError> {
+        let request = HeadObjectRequest {
+            bucket: self.bucket.clone(),
+            key: key.to_string(),
+            ..Default::default()
+        };
+        let output = self.client.head_object(request).await.map_err(box_err)?;
+
+        let size = output
+            .content_length
+            .ok_or_else(|| missing_field("HeadObject", "content_length"))?;
+        let last_modified = output
+            .last_modified
+            .as_deref()
+            .ok_or_else(|| missing_field("HeadObject", "last_modified"))
+            .and_then(parse_last_modified)?;
+
+        Ok(S3Object {
+            key: key.to_string(),
+            size: size as usize,
+            last_modified,
+        })
+    }
+
+    async fn delete_object(&self, key: &str) -> std::result::Result<(), ClientError> {
+        let request = DeleteObjectRequest {
+            bucket: self.bucket.clone(),
+            key: key.to_string(),
+            ..Default::default()
+        };
+        self.client.delete_object(request).await.map_err(box_err)?;
+
+        Ok(())
+    }
+
+    async fn list_objects(
+        &self,
+        prefix: Option<&str>,
+        delimiter: Option<&str>,
+        continuation_token: Option<String>,
+    ) -> std::result::Result<ListObjectsOutput, ClientError> {
+        let request = ListObjectsV2Request {
+            bucket: self.bucket.clone(),
+            prefix: prefix.map(|p| p.to_string()),
+            delimiter: delimiter.map(|d| d.to_string()),
+            continuation_token,
+            ..Default::default()
+        };
+        let output = self
+            .client
+            .list_objects_v2(request)
+            .await
+            .map_err(box_err)?;
+
+        let mut objects = Vec::new();
+        for object in output.contents.unwrap_or_default() {
+            let key = object
+                .key
+                .ok_or_else(|| missing_field("ListObjectsV2", "key"))?;
+            let last_modified = object
+                .last_modified
+                .as_deref()
+                .ok_or_else(|| missing_field("ListObjectsV2", "last_modified"))
+                .and_then(parse_last_modified)?;
+            objects.push(S3Object {
+                key,
+                size: object.size.unwrap_or(0) as usize,
+                last_modified,
+            });
+        }
+        let common_prefixes = output
+            .common_prefixes
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|p| p.prefix)
+            .collect();
+        let next_continuation_token = if output.is_truncated.unwrap_or(false) {
+            output.next_continuation_token
+        } else {
+            None
+        };
+
+        Ok(ListObjectsOutput {
+            objects,
+            common_prefixes,
+            next_continuation_token,
+        })
+    }
+
+    async fn create_multipart_upload(&self, key: &str) -> std::result::Result<String, ClientError> {
+        let request = CreateMultipartUploadRequest {
+            bucket: self.bucket.clone(),
+            key: key.to_string(),
+            ..Default::default()
+        };
+        let output = self
+            .client
+            .create_multipart_upload(request)
+            .await
+            .map_err(box_err)?;
+
+        output
+            .upload_id
+            .ok_or_else(|| missing_field("CreateMultipartUpload", "upload_id"))
+    }
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        part_number: i64,
+        body: Bytes,
+    ) -> std::result::Result<UploadedPart, ClientError> {
+        let request = UploadPartRequest {
+            bucket: self.bucket.clone(),
+            key: key.to_string(),
+            upload_id: upload_id.to_string(),
+            part_number,
+            content_length: Some(body.len() as i64),
+            body: Some(body.to_vec().into()),
+            ..Default::default()
+        };
+        let output = self.client.upload_part(request).await.map_err(box_err)?;
+        let e_tag = output
+            .e_tag
+            .ok_or_else(|| missing_field("UploadPart", "e_tag"))?;
+
+        Ok(UploadedPart { part_number, e_tag })
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<UploadedPart>,
+    ) -> std::result::Result<(), ClientError> {
+        let parts = parts
+            .into_iter()
+            .map(|p| CompletedPart {
+                e_tag: Some(p.e_tag),
+                part_number: Some(p.part_number),
+            })
+            .collect();
+        let request = CompleteMultipartUploadRequest {
+            bucket: self.bucket.clone(),
+            key: key.to_string(),
+            upload_id: upload_id.to_string(),
+            multipart_upload: Some(CompletedMultipartUpload { parts: Some(parts) }),
+            ..Default::default()
+        };
+        self.client
+            .complete_multipart_upload(request)
+            .await
+            .map_err(box_err)?;
+
+        Ok(())
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        key: &str,
+        upload_id: &str,
+    ) -> std::result::Result<(), ClientError> {
+        let request = AbortMultipartUploadRequest {
+            bucket: self.bucket.clone(),
+            key: key.to_string(),
+            upload_id: upload_id.to_string(),
+            ..Default::default()
+        };
+        self.client
+            .abort_multipart_upload(request)
+            .await
+            .map_err(box_err)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::{BTreeMap, BTreeSet, HashMap},
+        sync::Mutex,
+    };
+
+    use tempfile::tempdir;
+    use upstream::local::LocalFileSystem;
+
+    use super::*;
+    use crate::cache::{CachedStore, CachedStoreConfig};
+
+    /// Max number of keys returned in a page of [MemClient::list_objects]
+    const PAGE_SIZE: usize = 2;
+
+    /// An in-process fake of the S3 compatible service
+    #[derive(Debug, Default)]
+    struct MemClient {
+        objects: Mutex<BTreeMap<String, Bytes>>,
+        /// Upload id -> (key, parts)
+        uploads: Mutex<HashMap<String, (String, BTreeMap<i64, Bytes>)>>,
+    }
+
+    fn not_found(key: &str) -> ClientError {
+        format!("object not found, key:{}", key).into()
+    }
+
+    fn s3_object(key: &str, bytes: &Bytes) -> S3Object {
+        S3Object {
+            key: key.to_string(),
+            size: bytes.len(),
+            last_modified: Utc::now(),
+        }
+    }
+
+    #[async_trait]
+    impl S3Client for MemClient {
+        fn bucket(&self) -> &str {
+            "mem"
+        }
+
+        async fn put_object(&self, key: &str, body: Bytes) -> std::result::Result<(), ClientError> {
+            self.objects.lock().unwrap().insert(key.to_string(), body);
+            Ok(())
+        }
+
+        async fn get_object(
+            &self,
+            key: &str,
+            range: Option<Range<usize>>,
+        ) -> std::result::Result<Bytes, ClientError> {
+            let objects = self.objects.lock().unwrap();
+            let bytes = objects.get(key).ok_or_else(|| not_found(key))?;
+            match range {
+                Some(r) => Ok(bytes.slice(r.start..r.end.min(bytes.len()))),
+                None => Ok(bytes.clone()),
+            }
+        }
+
+        async fn head_object(&self, key: &str) -> std::result::Result<S3Object, ClientError> {
+            let objects = self.objects.lock().unwrap();
+            let bytes = objects.get(key).ok_or_else(|| not_found(key))?;
+            Ok(s3_object(key, bytes))
+        }
+
+        async fn delete_object(&self, key: &str) -> std::result::Result<(), ClientError> {
+            self.objects.lock().unwrap().remove(key);
+            Ok(())
+        }
+
+        async fn list_objects(
+            &self,
+            prefix: Option<&str>,
+            delimiter: Option<&str>,
+            continuation_token: Option<String>,
+        ) -> std::result::Result<ListObjectsOutput, ClientError> {
+            let prefix = prefix.unwrap_or("");
+            let objects = self.objects.lock().unwrap();
+
+            // Group the keys into objects and common prefixes, then paginate them.
+            let mut entries = BTreeSet::new();
+            for key in objects.keys().filter(|k| k.starts_with(prefix)) {
+                let rest = &key[prefix.len()..];
+                match delimiter.and_then(|d| rest.find(d).map(|pos| pos + d.len())) {
+                    Some(end) => entries.insert((format!("{}{}", prefix, &rest[..end]), true)),
+                    None => entries.insert((key.clone(), false)),
+                };
+            }
+            let start = continuation_token.map(|t| t.parse().unwrap()).unwrap_or(0);
+            let end = (start + PAGE_SIZE).min(entries.len());
+
+            let mut output = ListObjectsOutput::default();
+            for (key, is_prefix) in entries.iter().skip(start).take(end - start) {
+                if *is_prefix {
+                    output.common_prefixes.push(key.clone());
+                } else {
+                    output.objects.push(s3_object(key, &objects[key]));
+                }
+            }
+            if end < entries.len() {
+                output.next_continuation_token = Some(end.to_string());
+            }
+
+            Ok(output)
+        }
+
+        async fn create_multipart_upload(
+            &self,
+            key: &str,
+        ) -> std::result::Result<String, ClientError> {
+            let mut uploads = self.uploads.lock().unwrap();
+            let upload_id = uploads.len().to_string();
+            uploads.insert(upload_id.clone(), (key.to_string(), BTreeMap::new()));
+            Ok(upload_id)
+        }
+
+        async fn upload_part(
+            &self,
+            _key: &str,
+            upload_id: &str,
+            part_number: i64,
+            body: Bytes,
+        ) -> std::result::Result<UploadedPart, ClientError> {
+            let mut uploads = self.uploads.lock().unwrap();
+            let (_, parts) = uploads.get_mut(upload_id).ok_or("upload not found")?;
+            parts.insert(part_number, body);
+            Ok(UploadedPart {
+                part_number,
+                e_tag: format!("etag-{}", part_number),
+            })
+        }
+
+        async fn complete_multipart_upload(
+            &self,
+            key: &str,
+            upload_id: &str,
+            parts: Vec<UploadedPart>,
+        ) -> std::result::Result<(), ClientError> {
+            let (_, uploaded) = self
+                .uploads
+                .lock()
+                .unwrap()
+                .remove(upload_id)
+                .ok_or("upload not found")?;
+            let mut buf = Vec::new();
+            for part in parts {
+                buf.extend_from_slice(&uploaded[&part.part_number]);
+            }
+            self.put_object(key, Bytes::from(buf)).await
+        }
+
+        async fn abort_multipart_upload(
+            &self,
+            _key: &str,
+            upload_id: &str,
+        ) -> std::result::Result<(), ClientError> {
+            self.uploads.lock().unwrap().remove(upload_id);
+            Ok(())
+        }
+    }
+
+    fn new_store() -> S3Store<MemClient> {
+        S3Store::new(MemClient::default())
+    }
+
+    async fn read_all(store: &impl ObjectStore, location: &Path) -> Bytes {
+        store.get(location).await.unwrap().bytes().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_put_get() {
+        let store = new_store();
+        let location = Path::from("a/b.sst");
+        let data = Bytes::from_static(b"0123456789");
+        store.put(&location, data.clone()).await.unwrap();
+
+        assert_eq!(data, read_all(&store, &location).await);
+        assert_eq!(
+            Bytes::from_static(b"2345"),
+            store.get_range(&location, 2..6).await.unwrap()
+        );
+        assert_eq!(10, store.head(&location).await.unwrap().size);
+
+        store.delete(&location).await.unwrap();
+        assert!(store.head(&location).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_multipart_put() {
+        let store = new_store().with_part_size(MIN_PART_SIZE);
+        let location = Path::from("large.sst");
+        let data: Vec<u8> = (0..MIN_PART_SIZE * 2 + 100)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        store
+            .put(&location, Bytes::from(data.clone()))
+            .await
+            .unwrap();
+
+        assert!(store.client.uploads.lock().unwrap().is_empty());
+        assert_eq!(&data[..], &read_all(&store, &location).await[..]);
+        let range = MIN_PART_SIZE - 10..MIN_PART_SIZE + 10;
+        assert_eq!(
+            &data[range.clone()],
+            &store.get_range(&location, range).await.unwrap()[..]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list() {
+        let store = new_store();
+        for key in ["t/1/a", "t/1/b", "t/1/c", "t/2/a", "t/x", "u/a"] {
+            store
+                .put(&Path::from(key), Bytes::from_static(b"v"))
+                .await
+                .unwrap();
+        }
+
+        let keys: Vec<_> = store
+            .list(Some(&Path::from("t")))
+            .await
+            .unwrap()
+            .map_ok(|meta| meta.location.to_string())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(vec!["t/1/a", "t/1/b", "t/1/c", "t/2/a", "t/x"], keys);
+
+        let result = store
+            .list_with_delimiter(Some(&Path::from("t")))
+            .await
+            .unwrap();
+        let prefixes: Vec<_> = result
+            .common_prefixes
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let objects: Vec<_> = result
+            .objects
+            .iter()
+            .map(|o| o.location.to_string())
+            .collect();
+        assert_eq!(vec!["t/1", "t/2"], prefixes);
+        assert_eq!(vec!["t/x"], objects);
+    }
+
+    #[tokio::test]
+    async fn test_under_cached_store() {
+        let local_path = tempdir().unwrap();
+        let local_store = Box::new(LocalFileSystem::new_with_prefix(local_path.path()).unwrap());
+        let config = CachedStoreConfig {
+            max_cache_size: 4096,
+        };
+        let store = CachedStore::init(local_store, Box::new(new_store()), config)
+            .await
+            .unwrap();
+
+        let location = Path::from("cached.sst");
+        let data = Bytes::from_static(b"cached data");
+        store.put(&location, data.clone()).await.unwrap();
+
+        assert_eq!(data, read_all(&store, &location).await);
+        assert_eq!(
+            Bytes::from_static(b"data"),
+            store.get_range(&location, 7..11).await.unwrap()
+        );
+    }
+
+    /// Run against a local MinIO, e.g.
+    /// `docker run -p 9000:9000 minio/minio server /data`, with the bucket
+    /// created and the env `S3_TEST_ENDPOINT`, `S3_TEST_KEY_ID`,
+    /// `S3_TEST_KEY_SECRET` and `S3_TEST_BUCKET` set.
+    #[tokio::test]
+    #[ignore]
+    async fn test_minio() {
+        let env = |name: &str| std::env::var(name).unwrap();
+        let client = RusotoS3Client::new(
+            "minio",
+            env("S3_TEST_ENDPOINT"),
+            env("S3_TEST_KEY_ID"),
+            env("S3_TEST_KEY_SECRET"),
+            env("S3_TEST_BUCKET"),
+        )
+        .unwrap();
+        let store = S3Store::new(client).with_part_size(MIN_PART_SIZE);
+
+        let location = Path::from("ceresdb_test/large.sst");
+        let data: Vec<u8> = (0..MIN_PART_SIZE + 100).map(|i| (i % 251) as u8).collect();
+        store
+            .put(&location, Bytes::from(data.clone()))
+            .await
+            .unwrap();
+
+        assert_eq!(&data[..], &read_all(&store, &location).await[..]);
+        assert_eq!(
+            &data[10..20],
+            &store.get_range(&location, 10..20).await.unwrap()[..]
+        );
+        let result = store
+            .list_with_delimiter(Some(&Path::from("ceresdb_test")))
+            .await
+            .unwrap();
+        assert_eq!(1, result.objects.len());
+
+        store.delete(&location).await.unwrap();
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! A [ChunkReader] on the byte ranges fetched from a parquet file.
+
+use std::{ops::Range, sync::Arc};
+
+use arrow_deps::parquet::{
+    errors::{ParquetError, Result},
+    file::reader::{ChunkReader, Length},
+    util::cursor::SliceableCursor,
+};
+
+/// A [ChunkReader] which only holds some byte ranges of a parquet file, e.g.
+/// the footer and the column chunks to read, so the file needn't to be fetched
+/// entirely from the (remote) storage.
+///
+/// Reading the bytes not fetched leads to an error.
+#[derive(Debug, Clone)]
+pub struct RangedChunkReader {
+    file_size: u64,
+    /// Fetched chunks (start offset, bytes) sorted by the start offset.
+    chunks: Vec<(u64, Arc<Vec<u8>>)>,
+}
+
+impl RangedChunkReader {
+    pub fn new(file_size: u64) -> Self {
+        Self {
+            file_size,
+            chunks: Vec::new(),
+        }
+    }
+
+    /// Add the `bytes` starting from `start` of the file.
+    pub fn add_chunk(&mut self, start: u64, bytes: Vec<u8>) {
+        let pos = self.chunks.partition_point(|(offset, _)| *offset <= start);
+        self.chunks.insert(pos, (start, Arc::new(bytes)));
+    }
+
+    /// Returns true if the bytes in `range` have been fetched.
+    pub fn contains(&self, range: Range<u64>) -> bool {
+        self.find_chunk(range.start, (range.end - range.start) as usize)
+            .is_some()
+    }
+
+    fn find_chunk(&self, start: u64, length: usize) -> Option<&(u64, Arc<Vec<u8>>)> {
+        // Only the last chunk starting before `start` is checked, the chunks are
+        // expected to be added without overlapping.
+        let pos = self.chunks.partition_point(|(offset, _)| *offset <= start);
+        let chunk = self.chunks[..pos].last()?;
+        if start + length as u64 <= chunk.0 + chunk.1.len() as u64 {
+            Some(chunk)
+        } else {
+            None
+        }
+    }
+}
+
+impl Length for RangedChunkReader {
+    fn len(&self) -> u64 {
+        self.file_size
+    }
+}
+
+impl ChunkReader for RangedChunkReader {
+    type T = SliceableCursor;
+
+    fn get_read(&self, start: u64, length: usize) -> Result<Self::T> {
+        let (offset, bytes) = self.find_chunk(start, length).ok_or_else(|| {
+            ParquetError::General(format!(
+                "Bytes not fetched, start:{}, length:{}",
+                start, length
+            ))
+        })?;
+        let cursor = SliceableCursor::new(bytes.clone()).slice(start - offset, length)?;
+
+        Ok(cursor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use arrow_deps::parquet::file::reader::{FileReader, SerializedFileReader};
+
+    use super::*;
+    use crate::CachableSerializedFileReader;
+
+    fn read_test_file() -> Vec<u8> {
+        let mut buf = Vec::new();
+        crate::tests::get_test_file("alltypes_plain.parquet")
+            .read_to_end(&mut buf)
+            .unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_read_ranges() {
+        let mut reader = RangedChunkReader::new(100);
+        reader.add_chunk(50, (50..100).collect());
+        reader.add_chunk(10, (10..20).collect());
+
+        let mut buf = Vec::new();
+        reader
+            .get_read(12, 4)
+            .unwrap()
+            .read_to_end(&mut buf)
+            .unwrap();
+        assert_eq!(vec![12, 13, 14, 15], buf);
+        assert!(reader.contains(50..100));
+        assert!(!reader.contains(15..25));
+        assert!(reader.get_read(0, 1).is_err());
+        assert!(reader.get_read(95, 10).is_err());
+    }
+
+    #[test]
+    fn test_read_fetched_column_chunks() {
+        let buf = read_test_file();
+        let file_size = buf.len() as u64;
+        let full_reader = SerializedFileReader::new(SliceableCursor::new(buf.clone())).unwrap();
+
+        // Only the column chunks and the footer are fetched.
+        let mut chunk_reader = RangedChunkReader::new(file_size);
+        let mut footer_start = 0;
+        for row_group in full_reader.metadata().row_groups() {
+            for column in row_group.columns() {
+                let (start, length) = column.byte_range();
+                chunk_reader.add_chunk(
+                    start,
+                    buf[start as usize..(start + length) as usize].to_vec(),
+                );
+                footer_start = footer_start.max(start + length);
+            }
+        }
+        chunk_reader.add_chunk(footer_start, buf[footer_start as usize..].to_vec());
+        assert!(!chunk_reader.contains(0..4));
+
+        let reader =
+            CachableSerializedFileReader::new("test".to_string(), chunk_reader, None, None)
+                .unwrap();
+        let rows = reader.get_row_iter(None).unwrap();
+        assert!(full_reader.get_row_iter(None).unwrap().eq(rows));
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
 
 pub mod cache;
+pub mod chunk_reader;
 pub mod reverse_reader;
 mod serialized_reader;
 #[cfg(test)]
@@ -9,6 +10,7 @@ pub mod tests;
 // use cache::Cache;
 use std::sync::Arc;
 
+pub use chunk_reader::RangedChunkReader;
 pub use serialized_reader::CachableSerializedFileReader;
 
 use crate::cache::{DataCache, MetaCache};
@@ -103,6 +103,22 @@ impl<R: 'static + ChunkReader> CachableSerializedFileReader<R> {
         })
     }
 
+    /// Creates file reader with the metadata already parsed from the footer
+    /// of the file.
+    pub fn with_metadata(
+        name: String,
+        chunk_reader: R,
+        metadata: Arc<ParquetMetaData>,
+        data_cache: Option<DataCacheRef>,
+    ) -> Self {
+        Self {
+            name,
+            chunk_reader: Arc::new(chunk_reader),
+            metadata,
+            data_cache,
+        }
+    }
+
     /// Filters row group metadata to only those row groups,
     /// for which the predicate function returns true
     pub fn filter_row_groups(&mut self, predicate: &dyn Fn(&RowGroupMetaData, usize) -> bool) {
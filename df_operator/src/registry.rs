@@ -29,6 +29,8 @@ pub trait FunctionRegistry {
     fn find_udaf(&self, name: &str) -> Result<Option<AggregateUdf>>;
 
     fn list_udfs(&self) -> Result<Vec<ScalarUdf>>;
+
+    fn list_udafs(&self) -> Result<Vec<AggregateUdf>>;
 }
 
 /// Default function registry.
@@ -87,6 +89,11 @@ impl FunctionRegistry for FunctionRegistryImpl {
         let udfs = self.scalar_functions.values().cloned().collect();
         Ok(udfs)
     }
+
+    fn list_udafs(&self) -> Result<Vec<AggregateUdf>> {
+        let udafs = self.aggregate_functions.values().cloned().collect();
+        Ok(udafs)
+    }
 }
 
 pub type FunctionRegistryRef = Arc<dyn FunctionRegistry + Send + Sync>;
@@ -5,7 +5,7 @@
 use crate::registry::{FunctionRegistry, Result};
 
 mod thetasketch_distinct;
-mod time_bucket;
+pub mod time_bucket;
 
 pub fn register_all_udfs(registry: &mut dyn FunctionRegistry) -> Result<()> {
     // Register all udfs
@@ -190,7 +190,7 @@ pub enum Period {
 }
 
 impl Period {
-    fn parse(period: &str) -> Result<Period> {
+    pub fn parse(period: &str) -> Result<Period> {
         ensure!(period.len() >= 3, InvalidPeriod { period });
         let is_pt = if period.starts_with("PT") {
             true
@@ -238,7 +238,33 @@ impl Period {
         Ok(parsed)
     }
 
-    fn truncate(&self, ts: Timestamp) -> Option<Timestamp> {
+    /// Returns true if the boundaries of the buckets of this period are always
+    /// multiples of `interval`, so a bucket of `interval` is entirely contained
+    /// by a bucket of this period.
+    pub fn is_aligned_to(&self, interval: Duration) -> bool {
+        const SECOND_MILLIS: i64 = 1000;
+        const MINUTE_MILLIS: i64 = 60 * SECOND_MILLIS;
+        const HOUR_MILLIS: i64 = 60 * MINUTE_MILLIS;
+        const DAY_MILLIS: i64 = 24 * HOUR_MILLIS;
+
+        let interval_millis = interval.as_millis() as i64;
+        let step_millis = match self {
+            Period::Second(period) => i64::from(*period) * SECOND_MILLIS,
+            Period::Minute(period) => i64::from(*period) * MINUTE_MILLIS,
+            Period::Hour(period) => i64::from(*period) * HOUR_MILLIS,
+            Period::Week => 7 * DAY_MILLIS,
+            // These buckets start at the midnight of the default timezone.
+            Period::Day(_) | Period::Month | Period::Year => gcd(
+                DAY_MILLIS,
+                i64::from(DEFAULT_TIMEZONE_OFFSET_SECS).abs() * SECOND_MILLIS,
+            ),
+        };
+
+        interval_millis > 0 && step_millis > 0 && step_millis % interval_millis == 0
+    }
+
+    /// Returns the start of the bucket containing `ts`.
+    pub fn truncate(&self, ts: Timestamp) -> Option<Timestamp> {
         const MINUTE_SECONDS: u64 = 60;
         const HOUR_SECONDS: u64 = 60 * MINUTE_SECONDS;
 
@@ -322,3 +348,40 @@ impl Period {
         Timestamp::new(truncated_ts)
     }
 }
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_period_aligned_to() {
+        let minute = Duration::from_secs(60);
+        let cases = [
+            ("PT30S", minute, false),
+            ("PT5M", minute, true),
+            ("PT5M", Duration::from_secs(120), false),
+            ("PT1H", Duration::from_secs(300), true),
+            ("P1D", Duration::from_secs(300), true),
+            ("P1D", Duration::from_secs(5 * 3600), false),
+            ("P1M", Duration::from_secs(3600), true),
+            ("P1W", Duration::from_secs(3600), true),
+        ];
+        for (period, interval, expect) in cases {
+            let period = Period::parse(period).unwrap();
+            assert_eq!(
+                expect,
+                period.is_aligned_to(interval),
+                "period:{:?}",
+                period
+            );
+        }
+    }
+}
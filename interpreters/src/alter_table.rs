@@ -8,9 +8,12 @@ use common_types::{
     schema::{self, Schema},
 };
 use common_util::define_result;
-use snafu::{ensure, ResultExt, Snafu};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use sql::plan::{AlterTableOperation, AlterTablePlan};
-use table_engine::table::AlterSchemaRequest;
+use table_engine::{
+    rollup::RollupOptions,
+    table::{AlterSchemaRequest, TableRef},
+};
 
 use crate::interpreter::{self, AlterTable, Interpreter, InterpreterPtr, Output};
 
@@ -28,8 +31,26 @@ pub enum Error {
     #[snafu(display("Failed to alter table options, err:{}", source))]
     AlterOptions { source: table_engine::table::Error },
 
-    #[snafu(display("Not allow to add a not null column, name:{}", name))]
+    #[snafu(display(
+        "Not allow to add a not null column without default value, name:{}",
+        name
+    ))]
     AddNotNull { name: String },
+
+    #[snafu(display("Column to alter not found, name:{}", name))]
+    ColumnNotFound { name: String },
+
+    #[snafu(display("Not allow to drop or rename a key column, name:{}", name))]
+    AlterKeyColumn { name: String },
+
+    #[snafu(display("Not allow to drop or rename a partition key column, name:{}", name))]
+    AlterPartitionColumn { name: String },
+
+    #[snafu(display("Not allow to drop or rename a column used by rollup, name:{}", name))]
+    AlterRollupColumn { name: String },
+
+    #[snafu(display("Invalid rollup options of table, err:{}", source))]
+    InvalidRollup { source: table_engine::rollup::Error },
 }
 
 define_result!(Error);
@@ -60,14 +81,38 @@ impl AlterTableInterpreter {
                 let current_schema = table.schema();
                 let new_schema = build_new_schema(&current_schema, columns)?;
 
-                let request = AlterSchemaRequest {
-                    schema: new_schema,
-                    pre_schema_version: current_schema.version(),
-                };
+                alter_schema(&table, &current_schema, new_schema).await
+            }
+            AlterTableOperation::DropColumn(names) => {
+                let current_schema = table.schema();
+                let partition_columns = partition_columns_of(&table);
+                let rollup = rollup_of(&table)?;
+                for name in &names {
+                    validate_alter_column(
+                        &current_schema,
+                        &partition_columns,
+                        rollup.as_ref(),
+                        name,
+                    )?;
+                }
+                let new_schema = build_schema_without_columns(&current_schema, &names)?;
 
-                let num_rows = table.alter_schema(request).await.context(AlterSchema)?;
+                alter_schema(&table, &current_schema, new_schema).await
+            }
+            AlterTableOperation::RenameColumn { old_name, new_name } => {
+                let current_schema = table.schema();
+                let partition_columns = partition_columns_of(&table);
+                let rollup = rollup_of(&table)?;
+                validate_alter_column(
+                    &current_schema,
+                    &partition_columns,
+                    rollup.as_ref(),
+                    &old_name,
+                )?;
+                let new_schema =
+                    build_schema_with_renamed_column(&current_schema, &old_name, &new_name)?;
 
-                Ok(Output::AffectedRows(num_rows))
+                alter_schema(&table, &current_schema, new_schema).await
             }
             AlterTableOperation::ModifySetting(options) => {
                 let num_rows = table.alter_options(options).await.context(AlterOptions)?;
@@ -77,29 +122,70 @@ impl AlterTableInterpreter {
     }
 }
 
-fn build_new_schema(current_schema: &Schema, column_schemas: Vec<ColumnSchema>) -> Result<Schema> {
-    let current_version = current_schema.version();
+async fn alter_schema(
+    table: &TableRef,
+    current_schema: &Schema,
+    new_schema: Schema,
+) -> Result<Output> {
+    let request = AlterSchemaRequest {
+        schema: new_schema,
+        pre_schema_version: current_schema.version(),
+    };
 
-    let mut builder =
-        schema::Builder::with_capacity(current_schema.num_columns() + column_schemas.len())
-            // Increment the schema version.
-            .version(current_version + 1);
-    // Add existing columns to builder.
+    let num_rows = table.alter_schema(request).await.context(AlterSchema)?;
+
+    Ok(Output::AffectedRows(num_rows))
+}
+
+fn partition_columns_of(table: &TableRef) -> Vec<String> {
+    table
+        .partition_info()
+        .map(|info| {
+            info.method
+                .columns()
+                .into_iter()
+                .map(|c| c.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn rollup_of(table: &TableRef) -> Result<Option<RollupOptions>> {
+    RollupOptions::parse_from(&table.options()).context(InvalidRollup)
+}
+
+/// Create a builder of the next version of the `current_schema` with all the
+/// key columns added.
+fn next_schema_builder(current_schema: &Schema, capacity: usize) -> Result<schema::Builder> {
+    let mut builder = schema::Builder::with_capacity(capacity)
+        // Increment the schema version.
+        .version(current_schema.version() + 1)
+        // Never reuse the ids of the dropped columns.
+        .max_column_id(current_schema.max_column_id())
+        .enable_tsid_primary_key(current_schema.index_of_tsid().is_some());
     for key_column in current_schema.key_columns() {
         builder = builder
             .add_key_column(key_column.clone())
             .context(AddColumnSchema)?;
     }
+
+    Ok(builder)
+}
+
+fn build_new_schema(current_schema: &Schema, column_schemas: Vec<ColumnSchema>) -> Result<Schema> {
+    let mut builder = next_schema_builder(
+        current_schema,
+        current_schema.num_columns() + column_schemas.len(),
+    )?;
+    // Add existing columns to builder.
     for normal_column in current_schema.normal_columns() {
         builder = builder
             .add_normal_column(normal_column.clone())
             .context(AddColumnSchema)?;
     }
 
-    builder = builder
-        // Enable column id generation of the schema builder.
-        .auto_increment_column_id(true)
-        .enable_tsid_primary_key(current_schema.index_of_tsid().is_some());
+    // Enable column id generation of the schema builder.
+    builder = builder.auto_increment_column_id(true);
 
     // Add new columns
     for mut column_schema in column_schemas {
@@ -120,9 +206,11 @@ fn build_new_schema(current_schema: &Schema, column_schemas: Vec<ColumnSchema>)
     Ok(new_schema)
 }
 
+/// The added column must be nullable or have a default value, so the existing
+/// data can be read with the new schema.
 fn validate_add_column(column_schema: &ColumnSchema) -> Result<()> {
     ensure!(
-        column_schema.is_nullable,
+        column_schema.is_nullable || column_schema.default_value.is_some(),
         AddNotNull {
             name: &column_schema.name
         }
@@ -130,3 +218,71 @@ fn validate_add_column(column_schema: &ColumnSchema) -> Result<()> {
 
     Ok(())
 }
+
+/// Only the normal columns not used by the partition rule or the rollup can be
+/// dropped or renamed.
+///
+/// The rollup table is filled by the names of the aggregated fields and the
+/// tags, so they must be kept.
+fn validate_alter_column(
+    current_schema: &Schema,
+    partition_columns: &[String],
+    rollup: Option<&RollupOptions>,
+    name: &str,
+) -> Result<()> {
+    let index = current_schema
+        .index_of(name)
+        .context(ColumnNotFound { name })?;
+    ensure!(
+        index >= current_schema.num_key_columns(),
+        AlterKeyColumn { name }
+    );
+    ensure!(
+        !partition_columns.iter().any(|c| c == name),
+        AlterPartitionColumn { name }
+    );
+    if let Some(rollup) = rollup {
+        let column = current_schema.column(index);
+        ensure!(
+            !column.is_tag && rollup.aggregate_of(name).is_none(),
+            AlterRollupColumn { name }
+        );
+    }
+
+    Ok(())
+}
+
+fn build_schema_without_columns(current_schema: &Schema, names: &[String]) -> Result<Schema> {
+    let mut builder = next_schema_builder(current_schema, current_schema.num_columns())?;
+    for normal_column in current_schema.normal_columns() {
+        if names.contains(&normal_column.name) {
+            continue;
+        }
+        builder = builder
+            .add_normal_column(normal_column.clone())
+            .context(AddColumnSchema)?;
+    }
+
+    builder.build().context(BuildSchema)
+}
+
+fn build_schema_with_renamed_column(
+    current_schema: &Schema,
+    old_name: &str,
+    new_name: &str,
+) -> Result<Schema> {
+    let mut builder = next_schema_builder(current_schema, current_schema.num_columns())?;
+    for normal_column in current_schema.normal_columns() {
+        let mut column_schema = normal_column.clone();
+        if column_schema.name == old_name {
+            // The column id is kept, so the data written by the old name is still
+            // readable.
+            column_schema.name = new_name.to_string();
+        }
+        builder = builder
+            .add_normal_column(column_schema)
+            .context(AddColumnSchema)?;
+    }
+
+    builder.build().context(BuildSchema)
+}
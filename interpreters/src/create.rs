@@ -5,11 +5,15 @@
 use async_trait::async_trait;
 use catalog::{
     manager::Manager,
-    schema::{CreateOptions, CreateTableRequest},
+    schema::{CreateOptions, CreateTableRequest, DropOptions, DropTableRequest},
 };
-use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+use log::error;
+use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
 use sql::plan::CreateTablePlan;
-use table_engine::engine::{TableEngineRef, TableState};
+use table_engine::{
+    engine::{TableEngineRef, TableState},
+    rollup::{self, RollupOptions},
+};
 
 use crate::{
     context::Context,
@@ -42,8 +46,33 @@ pub enum Error {
         source: catalog::schema::Error,
     },
 
+    #[snafu(display("Failed to find table, name:{}, err:{}", table, source))]
+    FindTable {
+        table: String,
+        source: catalog::schema::Error,
+    },
+
+    #[snafu(display("Failed to drop table, name:{}, err:{}", table, source))]
+    SchemaDropTable {
+        table: String,
+        source: catalog::schema::Error,
+    },
+
     #[snafu(display("Failed to allocate table id, err:{}", source))]
     AllocTableId { source: catalog::schema::Error },
+
+    #[snafu(display("Invalid rollup options, table:{}, err:{}", table, source))]
+    InvalidRollup {
+        table: String,
+        source: table_engine::rollup::Error,
+    },
+
+    #[snafu(display(
+        "Rollup is not supported by partitioned table, table:{}.\nBacktrace:\n{}",
+        table,
+        backtrace
+    ))]
+    PartitionedRollup { table: String, backtrace: Backtrace },
 }
 
 define_result!(Error);
@@ -101,28 +130,121 @@ impl<C: Manager> CreateInterpreter<C> {
             table_schema,
             if_not_exists,
             options,
+            partition_info,
         } = self.plan;
 
+        // Build the schema of the rollup table before creating the table, so an
+        // invalid rollup won't leave a table without its rollup table.
+        let rollup_schema =
+            match RollupOptions::parse_from(&options).context(InvalidRollup { table: &table })? {
+                Some(rollup) => {
+                    ensure!(
+                        partition_info.is_none(),
+                        PartitionedRollup { table: &table }
+                    );
+
+                    Some(
+                        rollup
+                            .build_rollup_schema(&table_schema)
+                            .context(InvalidRollup { table: &table })?,
+                    )
+                }
+                None => None,
+            };
+
+        // The rollup table is only created along with its table.
+        let table_exists = schema
+            .table_by_name(&table)
+            .context(FindTable { table: &table })?
+            .is_some();
         let request = CreateTableRequest {
             catalog_name: catalog.name().to_string(),
             schema_name: schema.name().to_string(),
             schema_id: schema.id(),
             table_name: table.clone(),
             table_schema,
-            engine,
+            partition_info,
+            engine: engine.clone(),
             options,
             state: TableState::Stable,
         };
 
         let opts = CreateOptions {
-            table_engine: self.table_engine,
+            table_engine: self.table_engine.clone(),
             create_if_not_exists: if_not_exists,
         };
 
         schema
             .create_table(request, opts)
             .await
-            .context(SchemaCreateTable { table })?;
+            .context(SchemaCreateTable { table: &table })?;
+
+        // Create the companion table holding the rolled up rows, whose rows are
+        // written by the engine during compaction.
+        if let Some(rollup_schema) = rollup_schema.filter(|_| !table_exists) {
+            let rollup_table = rollup::rollup_table_name(&table);
+            // A rollup table left by the table dropped before must not be reused,
+            // as its rows are rolled up from the old table.
+            let drop_request = DropTableRequest {
+                catalog_name: catalog.name().to_string(),
+                schema_name: schema.name().to_string(),
+                schema_id: schema.id(),
+                table_name: rollup_table.clone(),
+                engine: engine.clone(),
+                partition_info: None,
+            };
+            let drop_opts = DropOptions {
+                table_engine: self.table_engine.clone(),
+            };
+            schema
+                .drop_table(drop_request, drop_opts)
+                .await
+                .context(SchemaDropTable {
+                    table: &rollup_table,
+                })?;
+
+            let request = CreateTableRequest {
+                catalog_name: catalog.name().to_string(),
+                schema_name: schema.name().to_string(),
+                schema_id: schema.id(),
+                table_name: rollup_table.clone(),
+                table_schema: rollup_schema,
+                partition_info: None,
+                engine: engine.clone(),
+                options: rollup::rollup_table_options(),
+                state: TableState::Stable,
+            };
+            let opts = CreateOptions {
+                table_engine: self.table_engine.clone(),
+                create_if_not_exists: false,
+            };
+
+            if let Err(e) = schema.create_table(request, opts).await {
+                // Drop the table so it can be created again, otherwise it has
+                // no rollup table.
+                let drop_request = DropTableRequest {
+                    catalog_name: catalog.name().to_string(),
+                    schema_name: schema.name().to_string(),
+                    schema_id: schema.id(),
+                    table_name: table.clone(),
+                    engine,
+                    partition_info: None,
+                };
+                let drop_opts = DropOptions {
+                    table_engine: self.table_engine,
+                };
+                if let Err(drop_err) = schema.drop_table(drop_request, drop_opts).await {
+                    error!(
+                        "Failed to drop table without rollup table, table:{}, err:{}",
+                        table, drop_err
+                    );
+                }
+
+                return Err(e).context(SchemaCreateTable {
+                    table: rollup_table,
+                });
+            }
+        }
 
         Ok(Output::AffectedRows(1))
     }
@@ -0,0 +1,194 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Interpreter for create materialized view statements
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use catalog::{
+    manager::Manager,
+    schema::{CreateOptions, CreateTableRequest, DropOptions, ViewInfo},
+};
+use common_types::time::Timestamp;
+use log::error;
+use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+use sql::plan::CreateMaterializedViewPlan;
+use table_engine::{
+    engine::{DropTableRequest, TableEngineRef, TableState},
+    ANALYTIC_ENGINE_TYPE,
+};
+
+use crate::{
+    context::Context,
+    interpreter::{CreateView, Interpreter, InterpreterPtr, Output, Result as InterpreterResult},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to find catalog, name:{}, err:{}", name, source))]
+    FindCatalog {
+        name: String,
+        source: catalog::manager::Error,
+    },
+
+    #[snafu(display("Catalog not exists, name:{}.\nBacktrace:\n{}", name, backtrace))]
+    CatalogNotExists { name: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to find schema, name:{}, err:{}", name, source))]
+    FindSchema {
+        name: String,
+        source: catalog::Error,
+    },
+
+    #[snafu(display("Schema not exists, name:{}.\nBacktrace:\n{}", name, backtrace))]
+    SchemaNotExists { name: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to get views, err:{}", source))]
+    GetViews { source: catalog::schema::Error },
+
+    #[snafu(display("Failed to create table of view, view:{}, err:{}", view, source))]
+    SchemaCreateTable {
+        view: String,
+        source: catalog::schema::Error,
+    },
+
+    #[snafu(display("Failed to create view, view:{}, err:{}", view, source))]
+    SchemaCreateView {
+        view: String,
+        source: catalog::schema::Error,
+    },
+}
+
+define_result!(Error);
+
+/// Create materialized view interpreter
+pub struct CreateMaterializedViewInterpreter<C> {
+    ctx: Context,
+    plan: CreateMaterializedViewPlan,
+    catalog_manager: C,
+    table_engine: TableEngineRef,
+}
+
+impl<C: Manager + 'static> CreateMaterializedViewInterpreter<C> {
+    pub fn create(
+        ctx: Context,
+        plan: CreateMaterializedViewPlan,
+        catalog_manager: C,
+        table_engine: TableEngineRef,
+    ) -> InterpreterPtr {
+        Box::new(Self {
+            ctx,
+            plan,
+            catalog_manager,
+            table_engine,
+        })
+    }
+}
+
+impl<C: Manager> CreateMaterializedViewInterpreter<C> {
+    async fn execute_create(self: Box<Self>) -> Result<Output> {
+        let default_catalog = self.ctx.default_catalog();
+        let catalog = self
+            .catalog_manager
+            .catalog_by_name(default_catalog)
+            .context(FindCatalog {
+                name: default_catalog,
+            })?
+            .context(CatalogNotExists {
+                name: default_catalog,
+            })?;
+
+        let default_schema = self.ctx.default_schema();
+        let schema = catalog
+            .schema_by_name(default_schema)
+            .context(FindSchema {
+                name: default_schema,
+            })?
+            .context(SchemaNotExists {
+                name: default_schema,
+            })?;
+
+        let CreateMaterializedViewPlan {
+            if_not_exists,
+            view,
+            query,
+            source_table,
+            period,
+            table_schema,
+        } = self.plan;
+
+        if if_not_exists
+            && schema
+                .all_views()
+                .context(GetViews)?
+                .iter()
+                .any(|v| v.view_name == view)
+        {
+            return Ok(Output::AffectedRows(0));
+        }
+
+        // The rows of the view are stored in a table with the same name.
+        let request = CreateTableRequest {
+            catalog_name: catalog.name().to_string(),
+            schema_name: schema.name().to_string(),
+            schema_id: schema.id(),
+            table_name: view.clone(),
+            table_schema,
+            partition_info: None,
+            engine: ANALYTIC_ENGINE_TYPE.to_string(),
+            options: HashMap::new(),
+            state: TableState::Stable,
+        };
+        let opts = CreateOptions {
+            table_engine: self.table_engine.clone(),
+            create_if_not_exists: false,
+        };
+        schema
+            .create_table(request, opts)
+            .await
+            .context(SchemaCreateTable { view: &view })?;
+
+        // The view starts from a zero watermark, so the first refresh backfills
+        // all the existing rows of the source table.
+        let view_info = ViewInfo {
+            catalog_name: catalog.name().to_string(),
+            schema_name: schema.name().to_string(),
+            view_name: view.clone(),
+            query,
+            source_table,
+            period,
+            watermark: Timestamp::new(0),
+        };
+        if let Err(e) = schema.create_view(view_info).await {
+            // Drop the table of the view so the view can be created again.
+            let drop_request = DropTableRequest {
+                catalog_name: catalog.name().to_string(),
+                schema_name: schema.name().to_string(),
+                schema_id: schema.id(),
+                table_name: view.clone(),
+                engine: ANALYTIC_ENGINE_TYPE.to_string(),
+                partition_info: None,
+            };
+            let drop_opts = DropOptions {
+                table_engine: self.table_engine,
+            };
+            if let Err(drop_err) = schema.drop_table(drop_request, drop_opts).await {
+                error!(
+                    "Failed to drop table of view, view:{}, err:{}",
+                    view, drop_err
+                );
+            }
+
+            return Err(e).context(SchemaCreateView { view });
+        }
+
+        Ok(Output::AffectedRows(1))
+    }
+}
+
+#[async_trait]
+impl<C: Manager> Interpreter for CreateMaterializedViewInterpreter<C> {
+    async fn execute(self: Box<Self>) -> InterpreterResult<Output> {
+        self.execute_create().await.context(CreateView)
+    }
+}
@@ -0,0 +1,49 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Interpreter for delete statement
+
+use async_trait::async_trait;
+use snafu::{ResultExt, Snafu};
+use sql::plan::DeletePlan;
+
+use crate::{
+    context::Context,
+    interpreter::{Delete, Interpreter, InterpreterPtr, Output, Result},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to delete from table, err:{}", source))]
+    DeleteTable { source: table_engine::table::Error },
+}
+
+pub struct DeleteInterpreter {
+    ctx: Context,
+    plan: DeletePlan,
+}
+
+impl DeleteInterpreter {
+    pub fn create(ctx: Context, plan: DeletePlan) -> InterpreterPtr {
+        Box::new(Self { ctx, plan })
+    }
+}
+
+#[async_trait]
+impl Interpreter for DeleteInterpreter {
+    async fn execute(self: Box<Self>) -> Result<Output> {
+        let DeletePlan { table, request } = self.plan;
+
+        // Context is unused now
+        let _ctx = self.ctx;
+
+        // The number of deleted rows is unknown until compaction, so the affected
+        // rows is the number of delete requests accepted by the table.
+        let num_requests = table
+            .delete(request)
+            .await
+            .context(DeleteTable)
+            .context(Delete)?;
+
+        Ok(Output::AffectedRows(num_requests))
+    }
+}
@@ -6,7 +6,10 @@ use async_trait::async_trait;
 use catalog::{manager::Manager, schema::DropOptions};
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
 use sql::plan::DropTablePlan;
-use table_engine::engine::{DropTableRequest, TableEngineRef};
+use table_engine::{
+    engine::{DropTableRequest, TableEngineRef},
+    rollup::{self, RollupOptions},
+};
 
 use crate::{
     context::Context,
@@ -33,6 +36,12 @@ pub enum Error {
     #[snafu(display("Schema not exists, name:{}.\nBacktrace:\n{}", name, backtrace))]
     SchemaNotExists { name: String, backtrace: Backtrace },
 
+    #[snafu(display("Failed to find table, name:{}, err:{}", table, source))]
+    FindTable {
+        table: String,
+        source: catalog::schema::Error,
+    },
+
     #[snafu(display("Failed to drop table in schema, name:{}, err:{}", table, source))]
     SchemaDropTable {
         table: String,
@@ -96,16 +105,23 @@ impl<C: Manager> DropInterpreter<C> {
             })?;
 
         let table = self.plan.table;
+        // The rollup table is dropped along with its table.
+        let has_rollup = schema
+            .table_by_name(&table)
+            .context(FindTable { table: &table })?
+            .map(|t| matches!(RollupOptions::parse_from(&t.options()), Ok(Some(_))))
+            .unwrap_or(false);
         let request = DropTableRequest {
             catalog_name: catalog.name().to_string(),
             schema_name: schema.name().to_string(),
             schema_id: schema.id(),
             table_name: table.clone(),
-            engine: self.plan.engine,
+            engine: self.plan.engine.clone(),
+            partition_info: None,
         };
 
         let opts = DropOptions {
-            table_engine: self.table_engine,
+            table_engine: self.table_engine.clone(),
         };
 
         let dropped = schema
@@ -113,6 +129,27 @@ impl<C: Manager> DropInterpreter<C> {
             .await
             .context(SchemaDropTable { table: &table })?;
 
+        if has_rollup {
+            let rollup_table = rollup::rollup_table_name(&table);
+            let request = DropTableRequest {
+                catalog_name: catalog.name().to_string(),
+                schema_name: schema.name().to_string(),
+                schema_id: schema.id(),
+                table_name: rollup_table.clone(),
+                engine: self.plan.engine,
+                partition_info: None,
+            };
+            let opts = DropOptions {
+                table_engine: self.table_engine,
+            };
+            schema
+                .drop_table(request, opts)
+                .await
+                .context(SchemaDropTable {
+                    table: rollup_table,
+                })?;
+        }
+
         Ok(Output::AffectedRows(if dropped { 1 } else { 0 }))
     }
 }
@@ -9,6 +9,7 @@ use table_engine::engine::TableEngineRef;
 
 use crate::{
     alter_table::AlterTableInterpreter, context::Context, create::CreateInterpreter,
+    create_view::CreateMaterializedViewInterpreter, delete::DeleteInterpreter,
     describe::DescribeInterpreter, drop::DropInterpreter, exists::ExistsInterpreter,
     insert::InsertInterpreter, interpreter::InterpreterPtr, select::SelectInterpreter,
     show_create::ShowCreateInInterpreter,
@@ -34,6 +35,7 @@ impl<Q: Executor + 'static, C: CatalogManager + 'static> Factory<Q, C> {
         match plan {
             Plan::Query(p) => SelectInterpreter::create(ctx, p, self.query_executor),
             Plan::Insert(p) => InsertInterpreter::create(ctx, p),
+            Plan::Delete(p) => DeleteInterpreter::create(ctx, p),
             Plan::Create(p) => {
                 CreateInterpreter::create(ctx, p, self.catalog_manager, self.table_engine)
             }
@@ -44,6 +46,12 @@ impl<Q: Executor + 'static, C: CatalogManager + 'static> Factory<Q, C> {
             Plan::AlterTable(p) => AlterTableInterpreter::create(p),
             Plan::ShowCreate(p) => ShowCreateInInterpreter::create(p),
             Plan::Exists(p) => ExistsInterpreter::create(p),
+            Plan::CreateMaterializedView(p) => CreateMaterializedViewInterpreter::create(
+                ctx,
+                p,
+                self.catalog_manager,
+                self.table_engine,
+            ),
         }
     }
 }
@@ -5,6 +5,7 @@
 use async_trait::async_trait;
 use query_engine::executor::RecordBatchVec;
 use snafu::Snafu;
+use table_engine::stream::SendableRecordBatchStream;
 
 // Make the variant closer to actual error code like invalid arguments.
 #[derive(Debug, Snafu)]
@@ -16,12 +17,18 @@ pub enum Error {
     #[snafu(display("Failed to execute create table, err:{}", source))]
     Create { source: crate::create::Error },
 
+    #[snafu(display("Failed to execute create materialized view, err:{}", source))]
+    CreateView { source: crate::create_view::Error },
+
     #[snafu(display("Failed to execute drop table, err:{}", source))]
     Drop { source: crate::drop::Error },
 
     #[snafu(display("Failed to execute insert, err:{}", source))]
     Insert { source: crate::insert::Error },
 
+    #[snafu(display("Failed to execute delete, err:{}", source))]
+    Delete { source: crate::delete::Error },
+
     #[snafu(display("Failed to execute describe, err:{}", source))]
     Describe { source: crate::describe::Error },
 
@@ -37,13 +44,15 @@ pub enum Error {
 
 define_result!(Error);
 
-// TODO(yingwen): Maybe add a stream variant for streaming result
 /// The interpreter output
 pub enum Output {
     /// Affected rows number
     AffectedRows(usize),
     /// A vec of RecordBatch
     Records(RecordBatchVec),
+    /// A stream of RecordBatch, the results are produced while the stream is
+    /// polled
+    Stream(SendableRecordBatchStream),
 }
 
 /// Interpreter executes the plan it holds
@@ -10,6 +10,8 @@ extern crate common_util;
 pub mod alter_table;
 pub mod context;
 pub mod create;
+pub mod create_view;
+pub mod delete;
 pub mod describe;
 pub mod drop;
 pub mod exists;
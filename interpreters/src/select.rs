@@ -58,9 +58,9 @@ impl<T: Executor> Interpreter for SelectInterpreter<T> {
             .context(CreateQueryContext)
             .context(Select)?;
         let query = Query::new(self.plan);
-        let record_batches = self
+        let stream = self
             .executor
-            .execute_logical_plan(query_ctx, query)
+            .execute_logical_plan_stream(query_ctx, query)
             .await
             .context(ExecutePlan)
             .context(Select)?;
@@ -70,6 +70,6 @@ impl<T: Executor> Interpreter for SelectInterpreter<T> {
             request_id
         );
 
-        Ok(Output::Records(record_batches))
+        Ok(Output::Stream(stream))
     }
 }
@@ -8,10 +8,14 @@ use arrow_deps::arrow::{
     record_batch::RecordBatch,
 };
 use async_trait::async_trait;
+use common_types::datum::Datum;
 use query_engine::executor::RecordBatchVec;
 use snafu::{ensure, Backtrace, ResultExt, Snafu};
 use sql::{ast::ShowCreateObject, plan::ShowCreatePlan};
-use table_engine::table::TableRef;
+use table_engine::{
+    partition::{PartitionInfo, PartitionMethod},
+    table::TableRef,
+};
 
 use crate::interpreter::{
     Interpreter, InterpreterPtr, Output, Result as InterpreterResult, ShowCreate,
@@ -78,11 +82,12 @@ impl ShowCreateInInterpreter {
     fn render_table_sql(table_ref: TableRef) -> String {
         //TODO(boyan) pretty output
         format!(
-            "CREATE TABLE `{}` ({}) ENGINE={}{}",
+            "CREATE TABLE `{}` ({}) ENGINE={}{}{}",
             table_ref.name(),
             Self::render_columns_and_constrains(&table_ref),
             table_ref.engine_type(),
-            Self::render_options(table_ref.options())
+            Self::render_options(table_ref.options()),
+            Self::render_partition(table_ref.partition_info())
         )
     }
 
@@ -100,6 +105,9 @@ impl ShowCreateInInterpreter {
             if !col.is_nullable {
                 res += " NOT NULL";
             }
+            if let Some(default_value) = &col.default_value {
+                res += format!(" DEFAULT {}", Self::render_value(default_value)).as_str();
+            }
 
             if !col.comment.is_empty() {
                 res += format!(" COMMENT '{}'", col.comment).as_str();
@@ -126,6 +134,64 @@ impl ShowCreateInInterpreter {
             "".to_string()
         }
     }
+
+    fn render_partition(partition_info: Option<PartitionInfo>) -> String {
+        let partition_info = match partition_info {
+            Some(v) => v,
+            None => return "".to_string(),
+        };
+
+        match partition_info.method {
+            PartitionMethod::Hash {
+                columns,
+                partition_num,
+            } => format!(
+                " PARTITION BY HASH({}) PARTITIONS {}",
+                columns.join(","),
+                partition_num
+            ),
+            PartitionMethod::Range { column, partitions } => {
+                let definitions: Vec<String> = partitions
+                    .iter()
+                    .map(|p| match p.less_than {
+                        Some(ts) => {
+                            format!("PARTITION {} VALUES LESS THAN ({})", p.name, ts.as_i64())
+                        }
+                        None => format!("PARTITION {} VALUES LESS THAN MAXVALUE", p.name),
+                    })
+                    .collect();
+                format!(
+                    " PARTITION BY RANGE({}) ({})",
+                    column,
+                    definitions.join(", ")
+                )
+            }
+            PartitionMethod::List { column, partitions } => {
+                let definitions: Vec<String> = partitions
+                    .iter()
+                    .map(|p| {
+                        let values: Vec<String> = p.values.iter().map(Self::render_value).collect();
+                        format!("PARTITION {} VALUES IN ({})", p.name, values.join(", "))
+                    })
+                    .collect();
+                format!(
+                    " PARTITION BY LIST({}) ({})",
+                    column,
+                    definitions.join(", ")
+                )
+            }
+        }
+    }
+
+    fn render_value(value: &Datum) -> String {
+        match value {
+            Datum::String(v) => format!("'{}'", v.as_str()),
+            Datum::Varbinary(v) => format!("'{}'", String::from_utf8_lossy(v)),
+            // Render timestamp as a number so the sql can be parsed again.
+            Datum::Timestamp(v) => v.as_i64().to_string(),
+            _ => value.display_string(),
+        }
+    }
 }
 
 #[async_trait]
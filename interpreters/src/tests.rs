@@ -1,10 +1,15 @@
 // Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
 
 use analytic_engine::tests::util::TestEnv;
-use catalog::consts::{DEFAULT_CATALOG, DEFAULT_SCHEMA};
+use catalog::{
+    consts::{DEFAULT_CATALOG, DEFAULT_SCHEMA},
+    manager::Manager,
+    schema::Schema,
+    Catalog,
+};
 use catalog_impls::table_based::TableBasedManager;
 use common_types::request_id::RequestId;
-use query_engine::executor::ExecutorImpl;
+use query_engine::executor::{self, ExecutorImpl, RecordBatchVec};
 use sql::{
     parser::Parser, plan::Plan, planner::Planner, provider::MetaProvider, tests::MockMetaProvider,
 };
@@ -32,6 +37,14 @@ fn sql_to_plan<M: MetaProvider>(meta_provider: &M, sql: &str) -> Plan {
     planner.statement_to_plan(statements.remove(0)).unwrap()
 }
 
+async fn output_to_records(output: Output) -> RecordBatchVec {
+    match output {
+        Output::Records(v) => v,
+        Output::Stream(stream) => executor::collect(stream).await.unwrap(),
+        Output::AffectedRows(_) => panic!(),
+    }
+}
+
 struct Env<M>
 where
     M: MetaProvider,
@@ -82,6 +95,28 @@ where
         }
     }
 
+    async fn test_create_materialized_view(&self) {
+        let sql = "CREATE MATERIALIZED VIEW IF NOT EXISTS test_view AS \
+                   SELECT time_bucket(key2, 'PT5M') AS ts, key1, avg(field1) AS avg_field1 \
+                   FROM test_table GROUP BY time_bucket(key2, 'PT5M'), key1";
+        let output = self.sql_to_output(sql).await.unwrap();
+        if let Output::AffectedRows(v) = output {
+            assert_eq!(v, 1);
+        } else {
+            panic!();
+        }
+
+        // The view already exists.
+        let output = self.sql_to_output(sql).await.unwrap();
+        if let Output::AffectedRows(v) = output {
+            assert_eq!(v, 0);
+        } else {
+            panic!();
+        }
+        let sql = sql.replace(" IF NOT EXISTS", "");
+        assert!(self.sql_to_output(&sql).await.is_err());
+    }
+
     async fn test_desc_table(&self) {
         let sql = "desc table test_table";
         let output = self.sql_to_output(sql).await.unwrap();
@@ -115,21 +150,16 @@ where
     async fn test_select_table(&self) {
         let sql = "select * from test_table";
         let output = self.sql_to_output(sql).await.unwrap();
-        if let Output::Records(v) = output {
-            assert_eq!(v.len(), 1);
-            assert_eq!(v[0].num_rows(), 2);
-        } else {
-            panic!();
-        }
+        assert!(matches!(output, Output::Stream(_)));
+        let v = output_to_records(output).await;
+        assert_eq!(v.len(), 1);
+        assert_eq!(v[0].num_rows(), 2);
 
         let sql = "select count(*) from test_table";
         let output = self.sql_to_output(sql).await.unwrap();
-        if let Output::Records(v) = output {
-            assert_eq!(v.len(), 1);
-            assert_eq!(v[0].num_rows(), 1);
-        } else {
-            panic!();
-        }
+        let v = output_to_records(output).await;
+        assert_eq!(v.len(), 1);
+        assert_eq!(v[0].num_rows(), 1);
     }
 
     async fn test_show_create_table(&self) {
@@ -152,6 +182,44 @@ where
             panic!();
         }
 
+        // A not null column can only be added with a default value.
+        let sql = "alter table test_table add column not_null_col int not null";
+        assert!(self.sql_to_output(sql).await.is_err());
+        let sql = "alter table test_table add column not_null_col int not null default 10";
+        let output = self.sql_to_output(sql).await.unwrap();
+        if let Output::AffectedRows(v) = output {
+            assert_eq!(v, 1);
+        } else {
+            panic!();
+        }
+
+        let sql = "alter table test_table rename column field2 to field3";
+        let output = self.sql_to_output(sql).await.unwrap();
+        if let Output::AffectedRows(v) = output {
+            assert_eq!(v, 1);
+        } else {
+            panic!();
+        }
+
+        let sql = "alter table test_table drop column field1";
+        let output = self.sql_to_output(sql).await.unwrap();
+        if let Output::AffectedRows(v) = output {
+            assert_eq!(v, 1);
+        } else {
+            panic!();
+        }
+
+        // Key columns can't be dropped or renamed.
+        let sql = "alter table test_table drop column key1";
+        assert!(self.sql_to_output(sql).await.is_err());
+        let sql = "alter table test_table rename column key2 to ts";
+        assert!(self.sql_to_output(sql).await.is_err());
+        let sql = "alter table test_table drop column not_exist";
+        assert!(self.sql_to_output(sql).await.is_err());
+        // The new name is used by another column.
+        let sql = "alter table test_table rename column field2 to field1";
+        assert!(self.sql_to_output(sql).await.is_err());
+
         let sql = "alter table test_table modify SETTING ttl='9d'";
         let output = self.sql_to_output(sql).await.unwrap();
         if let Output::AffectedRows(v) = output {
@@ -161,6 +229,62 @@ where
         }
     }
 
+    async fn table_exists(&self, table: &str) -> bool {
+        let catalog_manager = build_catalog_manager(self.engine()).await;
+        let schema = catalog_manager
+            .catalog_by_name(DEFAULT_CATALOG)
+            .unwrap()
+            .unwrap()
+            .schema_by_name(DEFAULT_SCHEMA)
+            .unwrap()
+            .unwrap();
+        schema.table_by_name(table).unwrap().is_some()
+    }
+
+    async fn test_create_and_drop_rollup_table(&self) {
+        // The rows overwritten would be rolled up more than once.
+        let sql = "CREATE TABLE rollup_test_table(c1 string tag not null, c2 string tag, \
+                   ts timestamp not null, c3 double, timestamp key(ts), primary key(c1, ts)) \
+                   ENGINE=Analytic WITH (rollup_interval='5m', rollup_aggregates='c3=avg')";
+        assert!(self.sql_to_output(sql).await.is_err());
+        assert!(!self.table_exists("rollup_test_table").await);
+        assert!(!self.table_exists("rollup_test_table__rollup").await);
+
+        let sql = "CREATE TABLE rollup_test_table(c1 string tag not null, c2 string tag, \
+                   ts timestamp not null, c3 double, timestamp key(ts), primary key(c1, ts)) \
+                   ENGINE=Analytic WITH (update_mode='append', rollup_interval='5m', \
+                   rollup_aggregates='c3=avg')";
+        for _ in 0..2 {
+            let output = self.sql_to_output(sql).await.unwrap();
+            if let Output::AffectedRows(v) = output {
+                assert_eq!(v, 1);
+            } else {
+                panic!();
+            }
+            assert!(self.table_exists("rollup_test_table__rollup").await);
+
+            // The columns used by the rollup can't be dropped or renamed.
+            let alter_sql = "alter table rollup_test_table rename column c3 to c4";
+            assert!(self.sql_to_output(alter_sql).await.is_err());
+            let alter_sql = "alter table rollup_test_table drop column c2";
+            assert!(self.sql_to_output(alter_sql).await.is_err());
+
+            // The rollup table is dropped along with its table, so the table can be
+            // created again.
+            let output = self
+                .sql_to_output("drop table if exists rollup_test_table")
+                .await
+                .unwrap();
+            if let Output::AffectedRows(v) = output {
+                assert_eq!(v, 1);
+            } else {
+                panic!();
+            }
+            assert!(!self.table_exists("rollup_test_table").await);
+            assert!(!self.table_exists("rollup_test_table__rollup").await);
+        }
+    }
+
     async fn test_drop_table(&self) {
         let sql = "drop table test_table";
         let output = self.sql_to_output(sql).await.unwrap();
@@ -184,6 +308,7 @@ async fn test_interpreters() {
     };
 
     env.test_create_table().await;
+    env.test_create_materialized_view().await;
     env.test_desc_table().await;
     env.test_exists_table().await;
     env.test_insert_table().await;
@@ -191,4 +316,5 @@ async fn test_interpreters() {
     env.test_show_create_table().await;
     env.test_alter_table().await;
     env.test_drop_table().await;
+    env.test_create_and_drop_rollup_table().await;
 }
@@ -63,12 +63,23 @@ impl Query {
 /// Executes the logical plan
 #[async_trait]
 pub trait Executor: Clone + Send + Sync {
-    // TODO(yingwen): Maybe return a stream
     /// Execute the query, returning the query results as RecordBatchVec
     ///
     /// REQUIRE: The meta data of tables in query should be found from
     /// ContextRef
     async fn execute_logical_plan(&self, ctx: ContextRef, query: Query) -> Result<RecordBatchVec>;
+
+    /// Execute the query, returning the query results as a stream, so the
+    /// results can be consumed batch by batch without being materialized
+    /// first.
+    ///
+    /// REQUIRE: The meta data of tables in query should be found from
+    /// ContextRef
+    async fn execute_logical_plan_stream(
+        &self,
+        ctx: ContextRef,
+        query: Query,
+    ) -> Result<SendableRecordBatchStream>;
 }
 
 #[derive(Clone, Default)]
@@ -83,24 +94,8 @@ impl ExecutorImpl {
 #[async_trait]
 impl Executor for ExecutorImpl {
     async fn execute_logical_plan(&self, ctx: ContextRef, query: Query) -> Result<RecordBatchVec> {
-        let plan = query.plan;
-
-        // Register catalogs to datafusion execution context.
-        let catalogs = CatalogProviderAdapter::new_adapters(plan.tables.clone());
-        let df_ctx = ctx.df_exec_ctx();
-        for (name, catalog) in catalogs {
-            df_ctx.register_catalog(&name, Arc::new(catalog));
-        }
         let request_id = ctx.request_id();
-
-        let physical_plan = optimize_plan(ctx, plan).await?;
-
-        debug!(
-            "Executor physical optimization finished, request_id:{}, physical_plan: {:?}",
-            request_id, physical_plan
-        );
-
-        let stream = physical_plan.execute().await.context(ExecutePhysical)?;
+        let (physical_plan, stream) = execute_plan(ctx, query.plan).await?;
 
         // Collect all records in the pool, as the stream may perform some costly
         // calculation
@@ -114,6 +109,48 @@ impl Executor for ExecutorImpl {
 
         Ok(record_batches)
     }
+
+    async fn execute_logical_plan_stream(
+        &self,
+        ctx: ContextRef,
+        query: Query,
+    ) -> Result<SendableRecordBatchStream> {
+        let request_id = ctx.request_id();
+        let (_, stream) = execute_plan(ctx, query.plan).await?;
+
+        debug!(
+            "Executor begin to stream results of plan, request_id:{}",
+            request_id
+        );
+
+        Ok(stream)
+    }
+}
+
+/// Optimize the plan and execute it, returns the physical plan and the stream
+/// of its results.
+async fn execute_plan(
+    ctx: ContextRef,
+    plan: QueryPlan,
+) -> Result<(PhysicalPlanPtr, SendableRecordBatchStream)> {
+    // Register catalogs to datafusion execution context.
+    let catalogs = CatalogProviderAdapter::new_adapters(plan.tables.clone());
+    let df_ctx = ctx.df_exec_ctx();
+    for (name, catalog) in catalogs {
+        df_ctx.register_catalog(&name, Arc::new(catalog));
+    }
+    let request_id = ctx.request_id();
+
+    let physical_plan = optimize_plan(ctx, plan).await?;
+
+    debug!(
+        "Executor physical optimization finished, request_id:{}, physical_plan: {:?}",
+        request_id, physical_plan
+    );
+
+    let stream = physical_plan.execute().await.context(ExecutePhysical)?;
+
+    Ok((physical_plan, stream))
 }
 
 async fn optimize_plan(ctx: ContextRef, plan: QueryPlan) -> Result<PhysicalPlanPtr> {
@@ -133,6 +170,7 @@ async fn optimize_plan(ctx: ContextRef, plan: QueryPlan) -> Result<PhysicalPlanP
         .context(PhysicalOptimize)
 }
 
-async fn collect(stream: SendableRecordBatchStream) -> Result<RecordBatchVec> {
+/// Collect all the record batches in the stream.
+pub async fn collect(stream: SendableRecordBatchStream) -> Result<RecordBatchVec> {
     stream.try_collect().await.context(Collect)
 }
@@ -3,6 +3,7 @@
 use std::{convert::TryFrom, sync::Arc};
 
 use arrow_deps::datafusion::{
+    datasource::TableProvider,
     execution::context::ExecutionProps,
     logical_plan::{
         plan::{Extension, Filter, Projection, Sort},
@@ -12,6 +13,7 @@ use arrow_deps::datafusion::{
 };
 use common_types::schema::Schema;
 use log::info;
+use table_engine::provider::TableProviderAdapter;
 
 use crate::df_planner_extension::table_scan_by_primary_key::TableScanByPrimaryKey;
 
@@ -64,6 +66,12 @@ impl OrderByPrimaryKeyRule {
                         table_name, source, ..
                     }) = scan_plan.as_ref()
                     {
+                        // The rows of a partitioned table can't be scanned in the order of
+                        // primary key across partitions.
+                        if Self::is_partitioned_table(source.as_ref()) {
+                            return Ok(None);
+                        }
+
                         let schema = Schema::try_from(source.schema()).map_err(|e| {
                             let err_msg = format!(
                                 "fail to convert arrow schema to schema, table:{}, err:{:?}",
@@ -94,6 +102,14 @@ impl OrderByPrimaryKeyRule {
         Ok(None)
     }
 
+    fn is_partitioned_table(source: &dyn TableProvider) -> bool {
+        source
+            .as_any()
+            .downcast_ref::<TableProviderAdapter>()
+            .map(|adapter| adapter.as_table_ref().partition_info().is_some())
+            .unwrap_or(false)
+    }
+
     /// Check:
     ///  - Whether `timestamp` is the first column in the primary key.
     ///  - Whether `sort_exprs` is equals the any prefix of primary key.
@@ -153,11 +169,21 @@ impl OrderByPrimaryKeyRule {
     ///       Project:
     ///         Filter:
     ///           TableScanByPrimaryKey
+    ///
+    /// The limit is also pushed down to the TableScanByPrimaryKey if there is
+    /// no filter, so the table stops reading once enough rows are read.
     fn rewrite_plan(rewrite_ctx: RewriteContext) -> LogicalPlan {
+        // The filter is not applied exactly by the table, so the rows read
+        // can't be limited if some of them may be filtered out later.
+        let scan_plan = if rewrite_ctx.filter_predicate.is_none() {
+            Self::push_down_limit(rewrite_ctx.scan_plan, rewrite_ctx.limit)
+        } else {
+            rewrite_ctx.scan_plan
+        };
         let order_by_primary_key_scan = Arc::new(LogicalPlan::Extension(Extension {
             node: Arc::new(TableScanByPrimaryKey::new_from_scan_plan(
                 rewrite_ctx.sort_in_asc_order,
-                rewrite_ctx.scan_plan,
+                scan_plan,
             )),
         }));
 
@@ -191,6 +217,20 @@ impl OrderByPrimaryKeyRule {
             input: new_sort_plan,
         })
     }
+
+    /// Set the limit of the `scan_plan` to `limit` if it has no smaller limit.
+    fn push_down_limit(scan_plan: Arc<LogicalPlan>, limit: usize) -> Arc<LogicalPlan> {
+        match scan_plan.as_ref() {
+            LogicalPlan::TableScan(table_scan) => {
+                let limit = table_scan.limit.map_or(limit, |v| v.min(limit));
+                Arc::new(LogicalPlan::TableScan(TableScan {
+                    limit: Some(limit),
+                    ..table_scan.clone()
+                }))
+            }
+            _ => scan_plan,
+        }
+    }
 }
 
 impl OptimizerRule for OrderByPrimaryKeyRule {
@@ -330,7 +370,15 @@ mod tests {
             .expect("Optimize plan")
             .expect("Succeed to optimize plan");
         let expected_plan = {
-            let mut builder = builder.table_scan().table_scan_in_primary_key_order(asc);
+            // The limit is pushed down to the scan only if there is no filter.
+            let scan_limit = if filter_expr.is_none() {
+                Some(10)
+            } else {
+                None
+            };
+            let mut builder = builder
+                .table_scan_with_limit(scan_limit)
+                .table_scan_in_primary_key_order(asc);
             if let Some(filter) = filter_expr {
                 builder = builder.filter(filter);
             }
@@ -119,7 +119,11 @@ impl LogicalPlanNodeBuilder {
         self
     }
 
-    pub fn table_scan(mut self) -> Self {
+    pub fn table_scan(self) -> Self {
+        self.table_scan_with_limit(None)
+    }
+
+    pub fn table_scan_with_limit(mut self, limit: Option<usize>) -> Self {
         let provider = MockTableProvider {
             schema: self.schema.clone(),
         };
@@ -131,7 +135,7 @@ impl LogicalPlanNodeBuilder {
             projection: None,
             projected_schema,
             filters: vec![],
-            limit: None,
+            limit,
         });
 
         self.plan = Some(Arc::new(plan));
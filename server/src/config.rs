@@ -2,7 +2,10 @@
 
 //! Server configs
 
+use std::time::Duration;
+
 use analytic_engine;
+use common_util::config::ReadableDuration;
 use meta_client::MetaClientConfig;
 use serde_derive::Deserialize;
 
@@ -49,6 +52,9 @@ pub struct Config {
 
     // Analytic engine configs:
     pub analytic: analytic_engine::Config,
+
+    /// Interval to check whether the materialized views need to be refreshed.
+    pub view_refresh_interval: ReadableDuration,
 }
 
 impl Default for RuntimeConfig {
@@ -83,6 +89,7 @@ impl Default for Config {
             },
             route_rules: RuleList::default(),
             analytic: analytic_engine::Config::default(),
+            view_refresh_interval: ReadableDuration(Duration::from_secs(60)),
         }
     }
 }
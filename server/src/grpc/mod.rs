@@ -30,6 +30,7 @@ use grpcio::{
     ClientStreamingSink, Environment, Metadata, RequestStream, RpcContext, Server, ServerBuilder,
     ServerStreamingSink, UnarySink, WriteFlags,
 };
+use interpreters::interpreter::Output;
 use log::{error, info};
 use meta_client::{
     ClusterViewRef, FailGetCatalog, FailOnChangeView, MetaClient, MetaClientConfig, MetaWatcher,
@@ -50,10 +51,10 @@ use crate::{
 };
 
 mod metrics;
-mod prom_query;
+pub(crate) mod prom_query;
 mod query;
 mod route;
-mod write;
+pub(crate) mod write;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -237,6 +238,11 @@ impl RpcServices {
     pub fn shutdown(&mut self) {
         self.rpc_server.shutdown();
     }
+
+    /// Meta client shared by the rpc services
+    pub fn meta_client(&self) -> Arc<dyn MetaClient + Send + Sync> {
+        self.meta_client.clone()
+    }
 }
 
 pub struct Builder<C, Q> {
@@ -631,20 +637,44 @@ impl<C: CatalogManager + 'static, Q: QueryExecutor + 'static> StorageService
                     error!("Failed to handle request, mod:stream_query, handler:handle_stream_query, err:{}", e);
                     e
                 })?;
-            if let Some(batch) = query::get_record_batch(&output) {
-                for i in 0..batch.len() {
-                    let resp = query::convert_records(&batch[i..i + 1]);
-                    if tx.send(resp).await.is_err() {
-                        error!("Failed to send handler result, mod:stream_query, handler:handle_stream_query");
-                        break;
+            match output {
+                Some(Output::Stream(mut stream)) => {
+                    // Convert and send the record batches one by one, the bounded channel
+                    // stops polling the stream if the client is slow.
+                    while let Some(batch) = stream.next().await {
+                        let resp = batch
+                            .map_err(|e| Box::new(e) as _)
+                            .context(ErrWithCause {
+                                code: StatusCode::InternalError,
+                                msg: "Failed to poll query results",
+                            })
+                            .and_then(|batch| query::convert_records(&[batch]));
+                        let has_err = resp.is_err();
+                        if tx.send(resp).await.is_err() {
+                            error!("Failed to send handler result, mod:stream_query, handler:handle_stream_query");
+                            break;
+                        }
+                        if has_err {
+                            break;
+                        }
                     }
                 }
-            } else {
-                let mut resp = QueryResponse::new();
-                resp.set_header(build_ok_header());
+                Some(Output::Records(batch)) => {
+                    for i in 0..batch.len() {
+                        let resp = query::convert_records(&batch[i..i + 1]);
+                        if tx.send(resp).await.is_err() {
+                            error!("Failed to send handler result, mod:stream_query, handler:handle_stream_query");
+                            break;
+                        }
+                    }
+                }
+                Some(Output::AffectedRows(_)) | None => {
+                    let mut resp = QueryResponse::new();
+                    resp.set_header(build_ok_header());
 
-                if tx.send(ServerResult::Ok(resp)).await.is_err() {
-                    error!("Failed to send handler result, mod:stream_query, handler:handle_stream_query");
+                    if tx.send(ServerResult::Ok(resp)).await.is_err() {
+                        error!("Failed to send handler result, mod:stream_query, handler:handle_stream_query");
+                    }
                 }
             }
             ServerResult::Ok(())
@@ -689,24 +719,96 @@ impl<C: CatalogManager + 'static, Q: QueryExecutor + 'static> StorageService
 }
 
 /// Create CreateTablePlan from a write metric.
-// The caller must ENSURE that the HandlerContext's schema_config is not None.
-pub fn write_metric_to_create_table_plan<
-    C: CatalogManager + 'static,
-    Q: QueryExecutor + 'static,
->(
-    ctx: &HandlerContext<C, Q>,
+pub fn write_metric_to_create_table_plan(
+    schema_config: &SchemaConfig,
     write_metric: &WriteMetric,
 ) -> Result<CreateTablePlan> {
-    let schema_config = ctx.schema_config.unwrap();
     Ok(CreateTablePlan {
         engine: schema_config.default_engine_type.clone(),
         if_not_exists: true,
         table: write_metric.get_metric().to_string(),
         table_schema: build_schema_from_metric(schema_config, write_metric)?,
         options: HashMap::default(),
+        partition_info: None,
     })
 }
 
+/// Build the schemas of the tag and field columns in the `write_metric` but
+/// not in the `schema`, the data types are inferred from the written values.
+pub fn write_metric_to_new_columns(
+    schema: &Schema,
+    write_metric: &WriteMetric,
+) -> Result<Vec<ColumnSchema>> {
+    let table_name = write_metric.get_metric();
+    let tag_names = write_metric.get_tag_names();
+    let field_names = write_metric.get_field_names();
+
+    let mut name_column_map: BTreeMap<&str, ColumnSchema> = BTreeMap::new();
+    for write_entry in write_metric.get_entries() {
+        for tag in write_entry.get_tags() {
+            // The invalid tags are rejected by the write later.
+            if let Some(tag_name) = tag_names.get(tag.name_index as usize) {
+                if let Some(tag_value) = &tag.get_value().value {
+                    collect_new_column(
+                        schema,
+                        table_name,
+                        tag_name,
+                        tag_value,
+                        true,
+                        &mut name_column_map,
+                    )?;
+                }
+            }
+        }
+
+        for field_group in write_entry.get_field_groups() {
+            for field in field_group.get_fields() {
+                if let Some(field_name) = field_names.get(field.name_index as usize) {
+                    if let Some(field_value) = &field.get_value().value {
+                        collect_new_column(
+                            schema,
+                            table_name,
+                            field_name,
+                            field_value,
+                            false,
+                            &mut name_column_map,
+                        )?;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(name_column_map.into_values().collect())
+}
+
+/// Add the column `name` to the `name_column_map` if it is not in the
+/// `schema`.
+fn collect_new_column<'a>(
+    schema: &Schema,
+    table_name: &str,
+    name: &'a str,
+    value: &Value_oneof_value,
+    is_tag: bool,
+    name_column_map: &mut BTreeMap<&'a str, ColumnSchema>,
+) -> Result<()> {
+    if schema.index_of(name).is_some() {
+        return Ok(());
+    }
+
+    let data_type = try_get_data_type_from_value(value)?;
+    match name_column_map.get(name) {
+        Some(column_schema) => {
+            ensure_data_type_compatible(table_name, name, is_tag, data_type, column_schema)
+        }
+        None => {
+            let column_schema = build_column_schema(name, data_type, is_tag)?;
+            name_column_map.insert(name, column_schema);
+            Ok(())
+        }
+    }
+}
+
 fn build_column_schema(
     column_name: &str,
     data_type: DatumKind,
@@ -1031,4 +1133,46 @@ mod tests {
             assert!(column.is_nullable);
         }
     }
+
+    #[test]
+    fn test_write_metric_to_new_columns() {
+        let write_metric = generate_write_metric();
+
+        // The schema only contains part of the tags and fields of the metric.
+        let schema = SchemaBuilder::new()
+            .auto_increment_column_id(true)
+            .add_key_column(
+                column_schema::Builder::new(TIMESTAMP_COLUMN.to_string(), DatumKind::Timestamp)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_normal_column(build_column_schema(TAG1, DatumKind::String, true).unwrap())
+            .unwrap()
+            .add_normal_column(build_column_schema(FIELD1, DatumKind::Double, false).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let columns = write_metric_to_new_columns(&schema, &write_metric).unwrap();
+
+        // sorted by column names because of btree
+        let names: Vec<_> = columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(vec![TAG2, FIELD3, FIELD2, FIELD4], names);
+        assert!(columns[0].is_tag);
+        assert_eq!(DatumKind::String, columns[0].data_type);
+        assert!(!columns[1].is_tag);
+        assert_eq!(DatumKind::String, columns[1].data_type);
+        assert_eq!(DatumKind::Double, columns[2].data_type);
+        assert_eq!(DatumKind::Boolean, columns[3].data_type);
+        for column in columns {
+            assert!(column.is_nullable);
+        }
+
+        // No new columns if the schema contains all of them.
+        let full_schema =
+            build_schema_from_metric(&SchemaConfig::default(), &write_metric).unwrap();
+        let columns = write_metric_to_new_columns(&full_schema, &write_metric).unwrap();
+        assert!(columns.is_empty());
+    }
 }
@@ -1,7 +1,7 @@
 // Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     sync::Arc,
 };
 
@@ -22,13 +22,15 @@ use query_engine::executor::{Executor as QueryExecutor, RecordBatchVec};
 use snafu::{ensure, OptionExt, ResultExt};
 use sql::{
     frontend::{Context as SqlContext, Error as FrontendError, Frontend},
-    promql::ColumnNames,
+    plan::Plan,
+    promql::{ColumnNames, Expr, Selector},
     provider::CatalogMetaProvider,
 };
 
 use crate::{
     error::{ErrNoCause, ErrWithCause, Result, ServerError, StatusCode},
-    grpc::HandlerContext,
+    grpc::{query, HandlerContext},
+    instance::InstanceRef,
 };
 
 fn is_table_not_found_error(e: &FrontendError) -> bool {
@@ -55,18 +57,13 @@ where
         req,
     );
 
-    let instance = &ctx.instance;
-    // We use tenant as schema
-    // TODO(yingwen): Privilege check, cannot access data of other tenant
-    // TODO(yingwen): Maybe move MetaProvider to instance
-    let provider = CatalogMetaProvider {
-        manager: &instance.catalog_manager,
-        default_catalog: ctx.catalog(),
-        default_schema: ctx.tenant(),
-        function_registry: &*instance.function_registry,
+    let query_ctx = QueryContext {
+        request_id,
+        catalog: ctx.catalog(),
+        tenant: ctx.tenant(),
+        instance: &ctx.instance,
     };
-    let frontend = Frontend::new(provider);
-
+    let frontend = new_frontend(&query_ctx);
     let mut sql_ctx = SqlContext::new(request_id);
     let expr = frontend
         .parse_promql(&mut sql_ctx, req)
@@ -76,22 +73,130 @@ where
             msg: "Invalid request",
         })?;
 
+    let series_set = execute_expr(&query_ctx, expr).await?;
+    Ok(convert_series_set(series_set))
+}
+
+/// Context of the prometheus query
+pub(crate) struct QueryContext<'a, C, Q> {
+    pub request_id: RequestId,
+    pub catalog: &'a str,
+    pub tenant: &'a str,
+    pub instance: &'a InstanceRef<C, Q>,
+}
+
+pub(crate) fn new_frontend<'a, C: CatalogManager, Q>(
+    ctx: &QueryContext<'a, C, Q>,
+) -> Frontend<CatalogMetaProvider<'a, C>> {
+    let instance = ctx.instance;
+    // We use tenant as schema
+    // TODO(yingwen): Privilege check, cannot access data of other tenant
+    // TODO(yingwen): Maybe move MetaProvider to instance
+    let provider = CatalogMetaProvider {
+        manager: &instance.catalog_manager,
+        default_catalog: ctx.catalog,
+        default_schema: ctx.tenant,
+        function_registry: &*instance.function_registry,
+    };
+    Frontend::new(provider)
+}
+
+/// Plan and execute the prometheus expr, returns the time series of the
+/// result.
+pub(crate) async fn execute_expr<C, Q>(
+    ctx: &QueryContext<'_, C, Q>,
+    expr: Expr,
+) -> Result<Vec<Series>>
+where
+    C: CatalogManager + 'static,
+    Q: QueryExecutor + 'static,
+{
+    let frontend = new_frontend(ctx);
+    let mut sql_ctx = SqlContext::new(ctx.request_id);
     let (plan, column_name) = frontend
         .promql_expr_to_plan(&mut sql_ctx, expr)
-        .map_err(|e| {
-            let code = if is_table_not_found_error(&e) {
-                StatusCode::NotFound
-            } else {
-                StatusCode::InternalError
-            };
-            ServerError::ErrWithCause {
-                code,
-                msg: "Failed to create plan".to_string(),
-                source: Box::new(e),
-            }
-        })?;
+        .map_err(create_plan_error)?;
+
+    let output = execute_plan(ctx, plan).await?;
+    convert_output(output, column_name)
+        .map_err(|e| Box::new(e) as _)
+        .with_context(|| ErrWithCause {
+            code: StatusCode::InternalError,
+            msg: "Failed to convert output",
+        })
+}
+
+/// Plan and execute the query to list the series matching the prometheus
+/// selector, returns the labels of the series.
+///
+/// Only the tag columns are read, the labels with empty values are removed
+/// like [execute_expr] does.
+pub(crate) async fn execute_series<C, Q>(
+    ctx: &QueryContext<'_, C, Q>,
+    selector: Selector,
+) -> Result<Vec<BTreeMap<String, String>>>
+where
+    C: CatalogManager + 'static,
+    Q: QueryExecutor + 'static,
+{
+    let frontend = new_frontend(ctx);
+    let mut sql_ctx = SqlContext::new(ctx.request_id);
+    let (plan, tag_keys) = frontend
+        .promql_series_to_plan(&mut sql_ctx, selector)
+        .map_err(create_plan_error)?;
+
+    let records = match execute_plan(ctx, plan).await? {
+        Output::Records(records) => records,
+        _ => unreachable!(),
+    };
 
-    if ctx.instance.limiter.should_limit(&plan) {
+    let mut series_labels = Vec::new();
+    for record_batch in records {
+        let schema = record_batch.schema();
+        let tags_idx: Vec<_> = tag_keys
+            .iter()
+            .filter_map(|tag_key| schema.index_of(tag_key).map(|idx| (tag_key, idx)))
+            .collect();
+        for row_idx in 0..record_batch.num_rows() {
+            let labels = tags_idx
+                .iter()
+                .filter_map(|(tag_key, col_idx)| {
+                    record_batch
+                        .column(*col_idx)
+                        .datum(row_idx)
+                        .as_str()
+                        .filter(|tag_value| !tag_value.is_empty())
+                        .map(|tag_value| (tag_key.to_string(), tag_value.to_string()))
+                })
+                .collect();
+            series_labels.push(labels);
+        }
+    }
+
+    Ok(series_labels)
+}
+
+fn create_plan_error(e: FrontendError) -> ServerError {
+    let code = if is_table_not_found_error(&e) {
+        StatusCode::NotFound
+    } else {
+        StatusCode::InternalError
+    };
+    ServerError::ErrWithCause {
+        code,
+        msg: "Failed to create plan".to_string(),
+        source: Box::new(e),
+    }
+}
+
+async fn execute_plan<C, Q>(ctx: &QueryContext<'_, C, Q>, plan: Plan) -> Result<Output>
+where
+    C: CatalogManager + 'static,
+    Q: QueryExecutor + 'static,
+{
+    let request_id = ctx.request_id;
+    let instance = ctx.instance;
+    if instance.limiter.should_limit(&plan) {
         ErrNoCause {
             code: StatusCode::TooManyRequests,
             msg: "Query limited by reject list",
@@ -102,7 +207,7 @@ where
     // Execute in interpreter
     let interpreter_ctx = InterpreterContext::builder(request_id)
         // Use current ctx's catalog and tenant as default catalog and tenant
-        .default_catalog_and_schema(ctx.catalog().to_string(), ctx.tenant().to_string())
+        .default_catalog_and_schema(ctx.catalog.to_string(), ctx.tenant.to_string())
         .build();
     let interpreter_factory = Factory::new(
         instance.query_executor.clone(),
@@ -120,35 +225,80 @@ where
             msg: "Failed to execute interpreter",
         })?;
 
-    let resp = convert_output(output, column_name)
+    query::collect_stream_output(output).await
+}
+
+/// List the names of the tag columns of all the tables in the tenant, the
+/// names are sorted and deduplicated.
+pub(crate) fn list_tag_names<C: CatalogManager, Q>(
+    ctx: &QueryContext<'_, C, Q>,
+) -> Result<Vec<String>> {
+    let tables = ctx
+        .instance
+        .catalog_manager
+        .catalog_by_name(ctx.catalog)
         .map_err(|e| Box::new(e) as _)
         .with_context(|| ErrWithCause {
             code: StatusCode::InternalError,
-            msg: "Failed to convert output",
+            msg: format!("Failed to find catalog, catalog_name:{}", ctx.catalog),
+        })?
+        .with_context(|| ErrNoCause {
+            code: StatusCode::InvalidArgument,
+            msg: format!("Catalog not found, catalog_name:{}", ctx.catalog),
+        })?
+        .schema_by_name(ctx.tenant)
+        .map_err(|e| Box::new(e) as _)
+        .with_context(|| ErrWithCause {
+            code: StatusCode::InternalError,
+            msg: format!("Failed to find tenant, tenant_name:{}", ctx.tenant),
+        })?
+        .with_context(|| ErrNoCause {
+            code: StatusCode::InvalidArgument,
+            msg: format!("Tenant not found, tenant_name:{}", ctx.tenant),
+        })?
+        .all_tables()
+        .map_err(|e| Box::new(e) as _)
+        .with_context(|| ErrWithCause {
+            code: StatusCode::InternalError,
+            msg: format!("Failed to list tables, tenant_name:{}", ctx.tenant),
         })?;
 
-    Ok(resp)
+    let tag_names: BTreeSet<_> = tables
+        .iter()
+        .flat_map(|table| {
+            table
+                .schema()
+                .columns()
+                .iter()
+                .filter(|column| column.is_tag)
+                .map(|column| column.name.clone())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    Ok(tag_names.into_iter().collect())
 }
 
-fn convert_output(
-    output: Output,
-    column_name: Arc<ColumnNames>,
-) -> Result<PrometheusQueryResponse> {
+fn convert_output(output: Output, column_name: Arc<ColumnNames>) -> Result<Vec<Series>> {
     match output {
         Output::Records(records) => convert_records(records, column_name),
         _ => unreachable!(),
     }
 }
 
-fn convert_records(
-    records: RecordBatchVec,
-    column_name: Arc<ColumnNames>,
-) -> Result<PrometheusQueryResponse> {
+/// Time series of the prometheus query result
+#[derive(Debug)]
+pub(crate) struct Series {
+    /// Labels sorted by the names
+    pub labels: BTreeMap<String, String>,
+    pub samples: Vec<Sample>,
+}
+
+fn convert_records(records: RecordBatchVec, column_name: Arc<ColumnNames>) -> Result<Vec<Series>> {
     if records.is_empty() {
-        return Ok(empty_ok_resp());
+        return Ok(Vec::new());
     }
 
-    let mut resp = empty_ok_resp();
     let mut tsid_to_tags = HashMap::new();
     let mut tsid_to_samples = HashMap::new();
 
@@ -167,16 +317,29 @@ fn convert_records(
     let series_set = tsid_to_samples
         .into_iter()
         .map(|(tsid, samples)| {
-            let tags = tsid_to_tags
-                .get(&tsid)
+            let labels = tsid_to_tags
+                .remove(&tsid)
                 .expect("ensured in convert_to_samples");
+            Series { labels, samples }
+        })
+        .collect();
+
+    Ok(series_set)
+}
+
+fn convert_series_set(series_set: Vec<Series>) -> PrometheusQueryResponse {
+    let mut resp = empty_ok_resp();
+    let series_set = series_set
+        .into_iter()
+        .map(|Series { labels, samples }| {
             let mut timeseries = TimeSeries::new();
             timeseries.set_labels(
-                tags.iter()
+                labels
+                    .into_iter()
                     .map(|(k, v)| {
                         let mut label = Label::new();
-                        label.set_name(k.clone());
-                        label.set_value(v.clone());
+                        label.set_name(k);
+                        label.set_value(v);
                         label
                     })
                     .collect::<Vec<_>>()
@@ -188,7 +351,7 @@ fn convert_records(
         .collect::<Vec<_>>();
 
     resp.set_timeseries(series_set.into());
-    Ok(resp)
+    resp
 }
 
 fn empty_ok_resp() -> PrometheusQueryResponse {
@@ -13,7 +13,7 @@ use common_types::{record_batch::RecordBatch, request_id::RequestId};
 use common_util::time::InstantExt;
 use interpreters::{context::Context as InterpreterContext, factory::Factory, interpreter::Output};
 use log::info;
-use query_engine::executor::{Executor as QueryExecutor, RecordBatchVec};
+use query_engine::executor::{self, Executor as QueryExecutor};
 use snafu::{ensure, ResultExt};
 use sql::{
     frontend::{Context as SqlContext, Frontend},
@@ -45,6 +45,7 @@ pub async fn handle_query<C: CatalogManager + 'static, Q: QueryExecutor + 'stati
 ) -> Result<QueryResponse> {
     let output_result = fetch_query_output(ctx, &req).await?;
     if let Some(output) = output_result {
+        let output = collect_stream_output(output).await?;
         convert_output(&output)
             .map_err(|e| Box::new(e) as _)
             .with_context(|| ErrWithCause {
@@ -172,14 +173,21 @@ fn convert_output(output: &Output) -> Result<QueryResponse> {
     }
 }
 
-pub fn get_record_batch(op: &Option<Output>) -> Option<&RecordBatchVec> {
-    if let Some(output) = op {
-        match output {
-            Output::Records(records) => Some(records),
-            _ => unreachable!(),
+/// Collect all the record batches if the output is a stream, other outputs
+/// are returned directly.
+pub async fn collect_stream_output(output: Output) -> Result<Output> {
+    match output {
+        Output::Stream(stream) => {
+            let records = executor::collect(stream)
+                .await
+                .map_err(|e| Box::new(e) as _)
+                .context(ErrWithCause {
+                    code: StatusCode::InternalError,
+                    msg: "Failed to collect query results",
+                })?;
+            Ok(Output::Records(records))
         }
-    } else {
-        None
+        output => Ok(output),
     }
 }
 
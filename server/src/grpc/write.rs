@@ -17,20 +17,26 @@ use common_types::{
     time::Timestamp,
 };
 use interpreters::{context::Context as InterpreterContext, factory::Factory, interpreter::Output};
-use log::debug;
+use log::{debug, warn};
+use meta_client::SchemaConfig;
 use query_engine::executor::Executor as QueryExecutor;
 use snafu::{ensure, OptionExt, ResultExt};
-use sql::plan::{InsertPlan, Plan};
+use sql::plan::{AlterTableOperation, AlterTablePlan, InsertPlan, Plan};
 use table_engine::table::TableRef;
 
 use crate::{
     error::{ErrNoCause, ErrWithCause, Result, StatusCode},
     grpc::{self, HandlerContext},
+    instance::InstanceRef,
 };
 
+/// Max times to retry adding the missing columns of a write, as the schema
+/// may be altered by the concurrent writes.
+const MAX_ADD_COLUMNS_RETRIES: usize = 3;
+
 pub(crate) async fn handle_write<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
     ctx: &HandlerContext<'_, C, Q>,
-    req: WriteRequest,
+    mut req: WriteRequest,
 ) -> Result<WriteResponse> {
     let request_id = RequestId::next_id();
 
@@ -45,13 +51,52 @@ pub(crate) async fn handle_write<C: CatalogManager + 'static, Q: QueryExecutor +
         req.get_metrics().len(),
     );
 
-    let instance = &ctx.instance;
-    let plan_vec = write_request_to_insert_plan(ctx, req, request_id).await?;
+    let write_ctx = WriteContext {
+        request_id,
+        catalog: ctx.catalog(),
+        tenant: ctx.tenant(),
+        instance: &ctx.instance,
+        schema_config: ctx.schema_config,
+    };
+    let success = write_metrics(&write_ctx, req.take_metrics().into_vec()).await?;
+
+    let mut resp = WriteResponse::new();
+    resp.set_header(grpc::build_ok_header());
+    resp.set_success(success as u32);
+
+    debug!(
+        "Grpc handle write finished, catalog:{}, tenant:{}, resp:{:?}",
+        ctx.catalog(),
+        ctx.tenant(),
+        resp
+    );
+
+    Ok(resp)
+}
+
+/// Context to write metrics into tables, shared by the write handlers of the
+/// grpc and http services.
+pub(crate) struct WriteContext<'a, C, Q> {
+    pub request_id: RequestId,
+    pub catalog: &'a str,
+    pub tenant: &'a str,
+    pub instance: &'a InstanceRef<C, Q>,
+    /// Config of the tenant, the tables are auto created or altered only if
+    /// the `auto_create_tables` of the config is enabled.
+    pub schema_config: Option<&'a SchemaConfig>,
+}
+
+/// Write the `metrics` into their tables, returns the number of written rows.
+pub(crate) async fn write_metrics<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
+    ctx: &WriteContext<'_, C, Q>,
+    metrics: Vec<WriteMetric>,
+) -> Result<usize> {
+    let plan_vec = write_metrics_to_insert_plan(ctx, metrics).await?;
 
     let mut success = 0;
     for insert_plan in plan_vec {
         debug!(
-            "Grpc handle write table begin, table:{}, row_num:{}",
+            "Handle write table begin, table:{}, row_num:{}",
             insert_plan.table.name(),
             insert_plan.rows.num_rows()
         );
@@ -65,69 +110,37 @@ pub(crate) async fn handle_write<C: CatalogManager + 'static, Q: QueryExecutor +
             .fail()?;
         }
 
-        let interpreter_ctx = InterpreterContext::builder(request_id)
-            // Use current ctx's catalog and tenant as default catalog and tenant
-            .default_catalog_and_schema(ctx.catalog().to_string(), ctx.tenant().to_string())
-            .build();
-        let interpreter_factory = Factory::new(
-            instance.query_executor.clone(),
-            instance.catalog_manager.clone(),
-            instance.table_engine.clone(),
-        );
-        let interpreter = interpreter_factory.create(interpreter_ctx, plan);
-
-        let row_num = match interpreter
-            .execute()
-            .await
-            .map_err(|e| Box::new(e) as _)
-            .context(ErrWithCause {
-                code: StatusCode::InternalError,
-                msg: "Failed to execute interpreter",
-            })? {
-            Output::AffectedRows(n) => n,
-            _ => unreachable!(),
-        };
-
-        success += row_num;
+        success += execute_plan(ctx, plan).await?;
     }
 
-    let mut resp = WriteResponse::new();
-    resp.set_header(grpc::build_ok_header());
-    resp.set_success(success as u32);
-
-    debug!(
-        "Grpc handle write finished, catalog:{}, tenant:{}, resp:{:?}",
-        ctx.catalog(),
-        ctx.tenant(),
-        resp
-    );
-
-    Ok(resp)
+    Ok(success)
 }
 
-async fn write_request_to_insert_plan<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
-    ctx: &HandlerContext<'_, C, Q>,
-    mut write_request: WriteRequest,
-    request_id: RequestId,
+async fn write_metrics_to_insert_plan<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
+    ctx: &WriteContext<'_, C, Q>,
+    metrics: Vec<WriteMetric>,
 ) -> Result<Vec<InsertPlan>> {
-    let mut plan_vec = Vec::with_capacity(write_request.get_metrics().len());
+    let mut plan_vec = Vec::with_capacity(metrics.len());
 
-    for write_metric in write_request.take_metrics() {
+    for write_metric in metrics {
         let table_name = write_metric.get_metric();
         let mut table = try_get_table(ctx, table_name)?;
-
-        if table.is_none() {
-            if let Some(config) = ctx.schema_config {
-                if config.auto_create_tables {
-                    create_table(ctx, &write_metric, request_id).await?;
-                    // try to get table again
-                    table = try_get_table(ctx, table_name)?;
-                }
-            }
+        let auto_create_tables = ctx
+            .schema_config
+            .map(|config| config.auto_create_tables)
+            .unwrap_or(false);
+
+        if table.is_none() && auto_create_tables {
+            create_table(ctx, &write_metric).await?;
+            // try to get table again
+            table = try_get_table(ctx, table_name)?;
         }
 
         match table {
             Some(table) => {
+                if auto_create_tables {
+                    add_missing_columns(ctx, &table, &write_metric).await?;
+                }
                 let plan = write_metric_to_insert_plan(table, write_metric)?;
                 plan_vec.push(plan);
             }
@@ -145,30 +158,30 @@ async fn write_request_to_insert_plan<C: CatalogManager + 'static, Q: QueryExecu
 }
 
 fn try_get_table<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
-    ctx: &HandlerContext<'_, C, Q>,
+    ctx: &WriteContext<'_, C, Q>,
     table_name: &str,
 ) -> Result<Option<TableRef>> {
     ctx.instance
         .catalog_manager
-        .catalog_by_name(ctx.catalog())
+        .catalog_by_name(ctx.catalog)
         .map_err(|e| Box::new(e) as _)
         .with_context(|| ErrWithCause {
             code: StatusCode::InternalError,
-            msg: format!("Failed to find catalog, catalog_name:{}", ctx.catalog()),
+            msg: format!("Failed to find catalog, catalog_name:{}", ctx.catalog),
         })?
         .with_context(|| ErrNoCause {
             code: StatusCode::InvalidArgument,
-            msg: format!("Catalog not found, catalog_name:{}", ctx.catalog()),
+            msg: format!("Catalog not found, catalog_name:{}", ctx.catalog),
         })?
-        .schema_by_name(ctx.tenant())
+        .schema_by_name(ctx.tenant)
         .map_err(|e| Box::new(e) as _)
         .with_context(|| ErrWithCause {
             code: StatusCode::InternalError,
-            msg: format!("Failed to find tenant, tenant_name:{}", ctx.tenant()),
+            msg: format!("Failed to find tenant, tenant_name:{}", ctx.tenant),
         })?
         .with_context(|| ErrNoCause {
             code: StatusCode::InvalidArgument,
-            msg: format!("Tenant not found, tenant_name:{}", ctx.tenant()),
+            msg: format!("Tenant not found, tenant_name:{}", ctx.tenant),
         })?
         .table_by_name(table_name)
         .map_err(|e| Box::new(e) as _)
@@ -178,30 +191,29 @@ fn try_get_table<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
         })
 }
 
+// The caller must ENSURE that the `schema_config` of the ctx is not None.
 async fn create_table<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
-    ctx: &HandlerContext<'_, C, Q>,
+    ctx: &WriteContext<'_, C, Q>,
     write_metric: &WriteMetric,
-    request_id: RequestId,
 ) -> Result<()> {
-    let create_table_plan = grpc::write_metric_to_create_table_plan(ctx, write_metric)
-        .map_err(|e| Box::new(e) as _)
-        .with_context(|| ErrWithCause {
-            code: StatusCode::InternalError,
-            msg: format!(
-                "Failed to build creating table plan from metric, table:{}",
-                write_metric.get_metric()
-            ),
-        })?;
+    let create_table_plan =
+        grpc::write_metric_to_create_table_plan(ctx.schema_config.unwrap(), write_metric)
+            .map_err(|e| Box::new(e) as _)
+            .with_context(|| ErrWithCause {
+                code: StatusCode::InternalError,
+                msg: format!(
+                    "Failed to build creating table plan from metric, table:{}",
+                    write_metric.get_metric()
+                ),
+            })?;
 
     debug!(
-        "Grpc handle create table begin, table:{}, schema: {:?}",
+        "Handle create table begin, table:{}, schema: {:?}",
         create_table_plan.table, create_table_plan.table_schema,
     );
     let plan = Plan::Create(create_table_plan);
 
-    let instance = &ctx.instance;
-
-    if instance.limiter.should_limit(&plan) {
+    if ctx.instance.limiter.should_limit(&plan) {
         ErrNoCause {
             code: StatusCode::TooManyRequests,
             msg: "Create table limited by reject list",
@@ -209,9 +221,72 @@ async fn create_table<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
         .fail()?;
     }
 
-    let interpreter_ctx = InterpreterContext::builder(request_id)
+    execute_plan(ctx, plan).await?;
+
+    Ok(())
+}
+
+/// Add the tags and fields of the `write_metric` missing from the schema of the
+/// `table` as new columns.
+async fn add_missing_columns<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
+    ctx: &WriteContext<'_, C, Q>,
+    table: &TableRef,
+    write_metric: &WriteMetric,
+) -> Result<()> {
+    let mut retries = 0;
+    loop {
+        // Find the missing columns by the latest schema, which may be altered by
+        // the concurrent writes since the last try.
+        let columns = grpc::write_metric_to_new_columns(&table.schema(), write_metric)
+            .map_err(|e| Box::new(e) as _)
+            .with_context(|| ErrWithCause {
+                code: StatusCode::InvalidArgument,
+                msg: format!(
+                    "Failed to build new columns from metric, table:{}",
+                    table.name()
+                ),
+            })?;
+        if columns.is_empty() {
+            return Ok(());
+        }
+
+        debug!(
+            "Handle add columns begin, table:{}, columns:{:?}",
+            table.name(),
+            columns
+        );
+        let plan = Plan::AlterTable(AlterTablePlan {
+            table: table.clone(),
+            operations: AlterTableOperation::AddColumn(columns),
+        });
+
+        match execute_plan(ctx, plan).await {
+            Ok(_) => return Ok(()),
+            // The alteration fails if the schema is altered by other writes
+            // concurrently, just retry with the latest schema.
+            Err(e) if retries < MAX_ADD_COLUMNS_RETRIES => {
+                warn!(
+                    "Failed to add columns, retry with the latest schema, table:{}, retries:{}, err:{}",
+                    table.name(),
+                    retries,
+                    e
+                );
+                retries += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+async fn execute_plan<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
+    ctx: &WriteContext<'_, C, Q>,
+    plan: Plan,
+) -> Result<usize> {
+    let instance = ctx.instance;
+
+    let interpreter_ctx = InterpreterContext::builder(ctx.request_id)
         // Use current ctx's catalog and tenant as default catalog and tenant
-        .default_catalog_and_schema(ctx.catalog().to_string(), ctx.tenant().to_string())
+        .default_catalog_and_schema(ctx.catalog.to_string(), ctx.tenant.to_string())
         .build();
     let interpreter_factory = Factory::new(
         instance.query_executor.clone(),
@@ -220,7 +295,7 @@ async fn create_table<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
     );
     let interpreter = interpreter_factory.create(interpreter_ctx, plan);
 
-    let _ = match interpreter
+    match interpreter
         .execute()
         .await
         .map_err(|e| Box::new(e) as _)
@@ -228,11 +303,9 @@ async fn create_table<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
             code: StatusCode::InternalError,
             msg: "Failed to execute interpreter",
         })? {
-        Output::AffectedRows(n) => n,
+        Output::AffectedRows(n) => Ok(n),
         _ => unreachable!(),
-    };
-
-    Ok(())
+    }
 }
 
 fn write_metric_to_insert_plan(
@@ -273,11 +346,13 @@ fn write_entry_to_rows(
     field_names: &[String],
     mut write_entry: WriteEntry,
 ) -> Result<Vec<Row>> {
-    // Init all columns by null.
-    let mut rows = vec![
-        Row::from_datums(vec![Datum::Null; schema.num_columns()]);
-        write_entry.get_field_groups().len()
-    ];
+    // Init all columns by their default values.
+    let default_datums = schema
+        .columns()
+        .iter()
+        .map(|column| column.default_datum())
+        .collect();
+    let mut rows = vec![Row::from_datums(default_datums); write_entry.get_field_groups().len()];
 
     // Fill tsid by default value.
     if let Some(tsid_idx) = schema.index_of_tsid() {
@@ -505,6 +580,7 @@ mod test {
                 is_nullable: false,
                 is_tag: false,
                 comment: String::new(),
+                default_value: None,
             })
             .unwrap()
             .add_key_column(ColumnSchema {
@@ -514,6 +590,7 @@ mod test {
                 is_nullable: false,
                 is_tag: true,
                 comment: String::new(),
+                default_value: None,
             })
             .unwrap()
             .add_normal_column(ColumnSchema {
@@ -523,6 +600,7 @@ mod test {
                 is_nullable: false,
                 is_tag: true,
                 comment: String::new(),
+                default_value: None,
             })
             .unwrap()
             .add_normal_column(ColumnSchema {
@@ -532,6 +610,7 @@ mod test {
                 is_nullable: true,
                 is_tag: false,
                 comment: String::new(),
+                default_value: None,
             })
             .unwrap()
             .add_normal_column(ColumnSchema {
@@ -541,6 +620,7 @@ mod test {
                 is_nullable: true,
                 is_tag: false,
                 comment: String::new(),
+                default_value: None,
             })
             .unwrap()
             .build()
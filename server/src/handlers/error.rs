@@ -47,6 +47,83 @@ pub enum Error {
         source: arrow_deps::arrow::error::ArrowError,
         backtrace: Backtrace,
     },
+
+    #[snafu(display("Failed to poll query results, query:{}, err:{}", query, source))]
+    PollStream {
+        query: String,
+        source: table_engine::stream::Error,
+    },
+
+    #[snafu(display(
+        "Stream of query results is aborted, query:{}.\nBacktrace:\n{}",
+        query,
+        backtrace
+    ))]
+    StreamAborted { query: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Failed to serialize rows to json, query:{}, err:{}.\nBacktrace:\n{}",
+        query,
+        source,
+        backtrace
+    ))]
+    SerializeRows {
+        query: String,
+        source: serde_json::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Failed to decompress prometheus request, err:{}.\nBacktrace:\n{}",
+        source,
+        backtrace
+    ))]
+    DecompressPromRequest {
+        source: snap::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Failed to decode prometheus request, err:{}.\nBacktrace:\n{}",
+        source,
+        backtrace
+    ))]
+    DecodePromRequest {
+        source: protobuf::ProtobufError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Invalid prometheus request, msg:{}.\nBacktrace:\n{}", msg, backtrace))]
+    InvalidPromRequest { msg: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to write prometheus samples, err:{}", source))]
+    PromWrite { source: crate::error::ServerError },
+
+    #[snafu(display(
+        "Failed to encode prometheus response, err:{}.\nBacktrace:\n{}",
+        source,
+        backtrace
+    ))]
+    EncodePromResponse {
+        source: protobuf::ProtobufError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Failed to compress prometheus response, err:{}.\nBacktrace:\n{}",
+        source,
+        backtrace
+    ))]
+    CompressPromResponse {
+        source: snap::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to parse promql, err:{}", source))]
+    ParsePromQuery { source: sql::frontend::Error },
+
+    #[snafu(display("Failed to query prometheus samples, err:{}", source))]
+    PromQuery { source: crate::error::ServerError },
 }
 
 define_result!(Error);
@@ -0,0 +1,1272 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Prometheus remote storage and http api handlers
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    str::FromStr,
+};
+
+use ceresdbproto::{
+    prometheus::Sample as SamplePb,
+    storage::{Field, FieldGroup, Tag, Value, WriteEntry, WriteMetric},
+};
+use common_types::{
+    request_id::RequestId,
+    time::{TimeRange, Timestamp},
+};
+use common_util::config::ReadableDuration;
+use log::debug;
+use meta_client::SchemaConfig;
+use proto::prometheus_remote::{
+    Label, LabelMatcher_Type, Query, QueryResult, ReadRequest, ReadResponse, Sample, TimeSeries,
+    WriteRequest,
+};
+use protobuf::Message;
+use snafu::{ensure, OptionExt};
+use sql::{
+    frontend::Context as SqlContext,
+    promql::{EvalParams, Expr, Filter, FilterType, Operand, Selector, DEFAULT_LOOKBACK},
+};
+
+use crate::{
+    error::StatusCode,
+    grpc::{
+        prom_query::{self, QueryContext, Series},
+        write::{self, WriteContext},
+    },
+    handlers::{
+        error::{
+            CompressPromResponse, DecodePromRequest, DecompressPromRequest, EncodePromResponse,
+            InvalidPromRequest, ParsePromQuery, PromQuery, PromWrite,
+        },
+        prelude::*,
+    },
+};
+
+/// Label of the metric name of a prometheus time series.
+pub const METRIC_NAME_LABEL: &str = "__name__";
+/// Field column to store the values of the samples.
+pub const VALUE_FIELD: &str = "value";
+/// Step of the instant query, only the evaluation time is aligned.
+const INSTANT_QUERY_STEP: i64 = 1_000;
+/// Max points of a time series returned by the range query, same as
+/// prometheus.
+const MAX_POINTS_PER_SERIES: i64 = 11_000;
+/// Time range of the series query if the start time is not given, 1 day.
+const DEFAULT_SERIES_RANGE: i64 = 24 * 3600 * 1000;
+/// Min time accepted by the api, 0001-01-01T00:00:00Z.
+const MIN_TIME: i64 = -62_135_596_800_000;
+/// Max time accepted by the api, 9999-12-31T23:59:59.999Z.
+const MAX_TIME: i64 = 253_402_300_799_999;
+
+/// Handle the prometheus remote write request, the body is a snappy
+/// compressed [WriteRequest].
+///
+/// Each metric is written to the table with the same name, the labels are
+/// written to the tag columns and the sample values are written to the field
+/// column [VALUE_FIELD].
+pub async fn handle_remote_write<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
+    ctx: RequestContext,
+    instance: InstanceRef<C, Q>,
+    schema_config: Option<&SchemaConfig>,
+    body: &[u8],
+) -> Result<usize> {
+    let request_id = RequestId::next_id();
+    let request: WriteRequest = decode_request(body)?;
+    let metrics = convert_write_request(request)?;
+
+    debug!(
+        "Prom remote write begin, catalog:{}, tenant:{}, request_id:{}, num_tables:{}",
+        ctx.catalog,
+        ctx.tenant,
+        request_id,
+        metrics.len()
+    );
+
+    let write_ctx = WriteContext {
+        request_id,
+        catalog: &ctx.catalog,
+        tenant: &ctx.tenant,
+        instance: &instance,
+        schema_config,
+    };
+    write::write_metrics(&write_ctx, metrics)
+        .await
+        .context(PromWrite)
+}
+
+/// Decode the snappy compressed protobuf message sent by prometheus.
+pub(crate) fn decode_request<M: Message>(body: &[u8]) -> Result<M> {
+    let buf = snap::raw::Decoder::new()
+        .decompress_vec(body)
+        .context(DecompressPromRequest)?;
+
+    protobuf::parse_from_bytes(&buf).context(DecodePromRequest)
+}
+
+/// Convert the time series of the remote write request to the metrics of the
+/// grpc write request, grouped by the metric names.
+fn convert_write_request(mut request: WriteRequest) -> Result<Vec<WriteMetric>> {
+    let mut metric_builders: BTreeMap<String, MetricBuilder> = BTreeMap::new();
+    for series in request.take_timeseries().into_iter() {
+        if series.get_samples().is_empty() {
+            continue;
+        }
+
+        let metric = series
+            .get_labels()
+            .iter()
+            .find(|label| label.get_name() == METRIC_NAME_LABEL)
+            .map(|label| label.get_value())
+            .context(InvalidPromRequest {
+                msg: "Metric name label is missing",
+            })?;
+        ensure!(
+            !metric.is_empty(),
+            InvalidPromRequest {
+                msg: "Metric name is empty",
+            }
+        );
+
+        if !metric_builders.contains_key(metric) {
+            metric_builders.insert(metric.to_string(), MetricBuilder::default());
+        }
+        metric_builders.get_mut(metric).unwrap().add_series(series);
+    }
+
+    Ok(metric_builders
+        .into_iter()
+        .map(|(metric, builder)| builder.build(metric))
+        .collect())
+}
+
+/// Builder to build the [WriteMetric] of the time series with the same metric
+/// name.
+#[derive(Default)]
+struct MetricBuilder {
+    tag_names: Vec<String>,
+    tag_name_index: HashMap<String, u32>,
+    entries: Vec<WriteEntry>,
+}
+
+impl MetricBuilder {
+    fn add_series(&mut self, mut series: TimeSeries) {
+        let mut tags = Vec::with_capacity(series.get_labels().len());
+        for mut label in series.take_labels().into_iter() {
+            if label.get_name() == METRIC_NAME_LABEL {
+                continue;
+            }
+
+            let mut tag = Tag::new();
+            tag.set_name_index(self.tag_index(label.take_name()));
+            let mut value = Value::new();
+            value.set_string_value(label.take_value());
+            tag.set_value(value);
+            tags.push(tag);
+        }
+
+        let field_groups: Vec<_> = series
+            .get_samples()
+            .iter()
+            .map(|sample| {
+                let mut value = Value::new();
+                value.set_float64_value(sample.get_value());
+                let mut field = Field::new();
+                // The value field is the only field of the metric.
+                field.set_name_index(0);
+                field.set_value(value);
+
+                let mut field_group = FieldGroup::new();
+                field_group.set_timestamp(sample.get_timestamp());
+                field_group.set_fields(vec![field].into());
+                field_group
+            })
+            .collect();
+
+        let mut entry = WriteEntry::new();
+        entry.set_tags(tags.into());
+        entry.set_field_groups(field_groups.into());
+        self.entries.push(entry);
+    }
+
+    fn tag_index(&mut self, name: String) -> u32 {
+        if let Some(index) = self.tag_name_index.get(&name) {
+            return *index;
+        }
+
+        let index = self.tag_names.len() as u32;
+        self.tag_names.push(name.clone());
+        self.tag_name_index.insert(name, index);
+        index
+    }
+
+    fn build(self, metric: String) -> WriteMetric {
+        let mut write_metric = WriteMetric::new();
+        write_metric.set_metric(metric);
+        write_metric.set_tag_names(self.tag_names.into());
+        write_metric.set_field_names(vec![VALUE_FIELD.to_string()].into());
+        write_metric.set_entries(self.entries.into());
+        write_metric
+    }
+}
+
+/// Handle the prometheus remote read request, the body is a snappy
+/// compressed [ReadRequest].
+///
+/// Returns the snappy compressed [ReadResponse], the samples are returned
+/// without any alignment.
+pub async fn handle_remote_read<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
+    ctx: RequestContext,
+    instance: InstanceRef<C, Q>,
+    body: &[u8],
+) -> Result<Vec<u8>> {
+    let request_id = RequestId::next_id();
+    let mut request: ReadRequest = decode_request(body)?;
+
+    debug!(
+        "Prom remote read begin, catalog:{}, tenant:{}, request_id:{}, request:{:?}",
+        ctx.catalog, ctx.tenant, request_id, request
+    );
+
+    let query_ctx = QueryContext {
+        request_id,
+        catalog: &ctx.catalog,
+        tenant: &ctx.tenant,
+        instance: &instance,
+    };
+    let mut response = ReadResponse::new();
+    for query in request.take_queries().into_iter() {
+        let expr = read_query_to_expr(query)?;
+        let metric = expr.get_selector().table.clone();
+        let timeseries: Vec<_> = query_series(&query_ctx, expr)
+            .await?
+            .into_iter()
+            .map(|series| convert_to_timeseries(&metric, series))
+            .collect();
+
+        let mut result = QueryResult::new();
+        result.set_timeseries(timeseries.into());
+        response.mut_results().push(result);
+    }
+
+    let buf = response.write_to_bytes().context(EncodePromResponse)?;
+    snap::raw::Encoder::new()
+        .compress_vec(&buf)
+        .context(CompressPromResponse)
+}
+
+/// Convert the query of the remote read to the selector expr.
+fn read_query_to_expr(mut query: Query) -> Result<Expr> {
+    let mut table = None;
+    let mut filters = Vec::new();
+    for mut matcher in query.take_matchers().into_iter() {
+        let typ = match matcher.get_field_type() {
+            LabelMatcher_Type::EQ => FilterType::LiteralOr,
+            LabelMatcher_Type::NEQ => FilterType::NotLiteralOr,
+            LabelMatcher_Type::RE => FilterType::Regexp,
+            LabelMatcher_Type::NRE => FilterType::NotRegexpMatch,
+        };
+        if matcher.get_name() == METRIC_NAME_LABEL {
+            ensure!(
+                typ == FilterType::LiteralOr && table.is_none(),
+                InvalidPromRequest {
+                    msg: "Only one equal matcher of metric name is supported",
+                }
+            );
+            table = Some(matcher.take_value());
+            continue;
+        }
+
+        filters.push(Filter::from_matcher(
+            matcher.take_name(),
+            typ,
+            matcher.take_value(),
+        ));
+    }
+    let table = table.context(InvalidPromRequest {
+        msg: "Metric name matcher is missing",
+    })?;
+
+    let (start, end) = (query.get_start_timestamp_ms(), query.get_end_timestamp_ms());
+    // [start, end]
+    let time_range =
+        TimeRange::new(Timestamp::new(start), Timestamp::new(end + 1)).with_context(|| {
+            InvalidPromRequest {
+                msg: format!("Invalid time range, start:{}, end:{}", start, end),
+            }
+        })?;
+
+    // The selector at the top level is not aligned, so the align params are
+    // not used.
+    Ok(Expr::SimpleExpr(Operand::Selector(Selector {
+        query_range: time_range,
+        table,
+        filters,
+        field: VALUE_FIELD.to_string(),
+        align_range: time_range,
+        step: INSTANT_QUERY_STEP,
+        range: 0,
+        offset: 0,
+    })))
+}
+
+fn convert_to_timeseries(metric: &str, series: Series) -> TimeSeries {
+    let Series {
+        mut labels,
+        samples,
+    } = series;
+    labels.insert(METRIC_NAME_LABEL.to_string(), metric.to_string());
+
+    let labels: Vec<_> = labels
+        .into_iter()
+        .map(|(name, value)| {
+            let mut label = Label::new();
+            label.set_name(name);
+            label.set_value(value);
+            label
+        })
+        .collect();
+    let samples: Vec<_> = samples
+        .iter()
+        .map(|sample_pb| {
+            let mut sample = Sample::new();
+            sample.set_timestamp(sample_pb.get_timestamp());
+            sample.set_value(sample_pb.get_value());
+            sample
+        })
+        .collect();
+
+    let mut timeseries = TimeSeries::new();
+    timeseries.set_labels(labels.into());
+    timeseries.set_samples(samples.into());
+    timeseries
+}
+
+/// Execute the expr and returns the time series, the result is empty if the
+/// metric is not found, like prometheus does.
+async fn query_series<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
+    ctx: &QueryContext<'_, C, Q>,
+    expr: Expr,
+) -> Result<Vec<Series>> {
+    match prom_query::execute_expr(ctx, expr).await {
+        Ok(series_set) => Ok(series_set),
+        Err(e) if matches!(e.code(), StatusCode::NotFound) => Ok(Vec::new()),
+        Err(e) => Err(e).context(PromQuery),
+    }
+}
+
+/// Parameters of the prometheus http api, from the url query or the form
+/// body.
+#[derive(Debug, Default)]
+pub struct ApiParams(Vec<(String, String)>);
+
+impl ApiParams {
+    /// Parse the `application/x-www-form-urlencoded` params.
+    pub fn parse(input: &[u8]) -> Self {
+        Self(form_urlencoded::parse(input).into_owned().collect())
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn get_all(&self, name: &str) -> Vec<&str> {
+        self.0
+            .iter()
+            .filter(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    fn required(&self, name: &str) -> Result<&str> {
+        self.get(name).with_context(|| InvalidPromRequest {
+            msg: format!("Missing parameter {}", name),
+        })
+    }
+
+    /// Get the time in milliseconds, the time can be a unix timestamp in
+    /// seconds or a RFC3339 time.
+    fn time(&self, name: &str) -> Result<Option<i64>> {
+        self.get(name).map(parse_time).transpose()
+    }
+}
+
+/// Response of the prometheus http api
+#[derive(Debug, Serialize)]
+pub struct ApiResponse<T> {
+    status: &'static str,
+    data: T,
+}
+
+impl<T> ApiResponse<T> {
+    fn success(data: T) -> Self {
+        Self {
+            status: "success",
+            data,
+        }
+    }
+}
+
+/// Data of the query response
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryData {
+    result_type: &'static str,
+    result: QueryResultData,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum QueryResultData {
+    Matrix(Vec<MatrixSeries>),
+    Vector(Vec<VectorSample>),
+    /// Scalar or string.
+    Value(SamplePair),
+}
+
+impl QueryData {
+    fn matrix(result: Vec<MatrixSeries>) -> Self {
+        Self {
+            result_type: "matrix",
+            result: QueryResultData::Matrix(result),
+        }
+    }
+
+    fn vector(result: Vec<VectorSample>) -> Self {
+        Self {
+            result_type: "vector",
+            result: QueryResultData::Vector(result),
+        }
+    }
+}
+
+/// Timestamp in seconds and the formatted value.
+pub type SamplePair = (f64, String);
+
+#[derive(Debug, Serialize)]
+pub struct MatrixSeries {
+    metric: BTreeMap<String, String>,
+    values: Vec<SamplePair>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VectorSample {
+    metric: BTreeMap<String, String>,
+    value: SamplePair,
+}
+
+/// Handle the instant query of the prometheus http api.
+pub async fn handle_query<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
+    ctx: RequestContext,
+    instance: InstanceRef<C, Q>,
+    params: &ApiParams,
+) -> Result<ApiResponse<QueryData>> {
+    let query = params.required("query")?;
+    let time = params
+        .time("time")?
+        .unwrap_or_else(|| Timestamp::now().as_i64());
+    let eval_params = EvalParams {
+        start: time,
+        end: time,
+        step: INSTANT_QUERY_STEP,
+        field: VALUE_FIELD.to_string(),
+    };
+
+    let request_id = RequestId::next_id();
+    debug!(
+        "Prom query begin, catalog:{}, tenant:{}, request_id:{}, query:{}, time:{}",
+        ctx.catalog, ctx.tenant, request_id, query, time
+    );
+
+    let query_ctx = QueryContext {
+        request_id,
+        catalog: &ctx.catalog,
+        tenant: &ctx.tenant,
+        instance: &instance,
+    };
+    let expr = parse_query(&query_ctx, query, &eval_params)?;
+    let data = match expr {
+        Expr::SimpleExpr(Operand::Float(value)) => QueryData {
+            result_type: "scalar",
+            result: QueryResultData::Value(to_sample_pair(time, value)),
+        },
+        Expr::SimpleExpr(Operand::String(value)) => QueryData {
+            result_type: "string",
+            result: QueryResultData::Value((to_secs(time), value)),
+        },
+        // The range vector returns the raw samples in the range.
+        Expr::SimpleExpr(Operand::Selector(selector)) if selector.range > 0 => {
+            let end = time
+                .checked_sub(selector.offset)
+                .context(InvalidPromRequest {
+                    msg: "Offset of the selector overflows",
+                })?;
+            let start = end
+                .checked_sub(selector.range)
+                .context(InvalidPromRequest {
+                    msg: "Range of the selector overflows",
+                })?;
+            let metric = selector.table.clone();
+            let expr = Expr::SimpleExpr(Operand::Selector(selector));
+            let result = query_series(&query_ctx, expr)
+                .await?
+                .into_iter()
+                .filter_map(|series| to_matrix_series(Some(&metric), series, start, end))
+                .collect();
+            QueryData::matrix(result)
+        }
+        expr => {
+            let metric = metric_of_expr(&expr);
+            let result = query_series(&query_ctx, expr)
+                .await?
+                .into_iter()
+                .filter_map(|series| to_vector_sample(metric.as_deref(), series, time))
+                .collect();
+            QueryData::vector(result)
+        }
+    };
+
+    Ok(ApiResponse::success(data))
+}
+
+/// Handle the range query of the prometheus http api.
+pub async fn handle_query_range<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
+    ctx: RequestContext,
+    instance: InstanceRef<C, Q>,
+    params: &ApiParams,
+) -> Result<ApiResponse<QueryData>> {
+    let query = params.required("query")?;
+    let start = parse_time(params.required("start")?)?;
+    let end = parse_time(params.required("end")?)?;
+    let step = parse_step(params.required("step")?)?;
+    ensure!(
+        start <= end,
+        InvalidPromRequest {
+            msg: "End timestamp must not be before start time",
+        }
+    );
+    ensure!(
+        (end - start) / step < MAX_POINTS_PER_SERIES,
+        InvalidPromRequest {
+            msg: format!(
+                "Exceeded maximum resolution of {} points per timeseries",
+                MAX_POINTS_PER_SERIES
+            ),
+        }
+    );
+    let eval_params = EvalParams {
+        start,
+        end,
+        step,
+        field: VALUE_FIELD.to_string(),
+    };
+
+    let request_id = RequestId::next_id();
+    debug!(
+        "Prom query range begin, catalog:{}, tenant:{}, request_id:{}, query:{}, params:{:?}",
+        ctx.catalog, ctx.tenant, request_id, query, eval_params
+    );
+
+    let query_ctx = QueryContext {
+        request_id,
+        catalog: &ctx.catalog,
+        tenant: &ctx.tenant,
+        instance: &instance,
+    };
+    let expr = parse_query(&query_ctx, query, &eval_params)?;
+    let result = match expr {
+        Expr::SimpleExpr(Operand::Float(value)) => {
+            let values = (start..=end)
+                .step_by(step as usize)
+                .map(|timestamp| to_sample_pair(timestamp, value))
+                .collect();
+            vec![MatrixSeries {
+                metric: BTreeMap::new(),
+                values,
+            }]
+        }
+        Expr::SimpleExpr(Operand::String(_)) => {
+            return InvalidPromRequest {
+                msg: "Invalid expression type string for range query, must be scalar or instant vector",
+            }
+            .fail()
+        }
+        Expr::SimpleExpr(Operand::Selector(selector)) if selector.range > 0 => {
+            return InvalidPromRequest {
+                msg: "Invalid expression type range vector for range query, must be scalar or instant vector",
+            }
+            .fail()
+        }
+        // The selector at the top level returns the raw samples, which need to be
+        // aligned to the steps.
+        Expr::SimpleExpr(Operand::Selector(selector)) => {
+            let metric = selector.table.clone();
+            let offset = selector.offset;
+            let expr = Expr::SimpleExpr(Operand::Selector(selector));
+            let steps = StepRange {
+                start,
+                end,
+                step,
+                offset,
+            };
+            query_series(&query_ctx, expr)
+                .await?
+                .into_iter()
+                .filter_map(|series| to_aligned_matrix_series(&metric, series, &steps))
+                .collect()
+        }
+        expr => {
+            let metric = metric_of_expr(&expr);
+            query_series(&query_ctx, expr)
+                .await?
+                .into_iter()
+                .filter_map(|series| to_matrix_series(metric.as_deref(), series, start, end))
+                .collect()
+        }
+    };
+
+    Ok(ApiResponse::success(QueryData::matrix(result)))
+}
+
+/// Handle the label names query of the prometheus http api.
+///
+/// Returns the tag names of all the tables in the tenant, the matchers and
+/// the time range of the request are not supported yet.
+pub async fn handle_labels<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
+    ctx: RequestContext,
+    instance: InstanceRef<C, Q>,
+) -> Result<ApiResponse<Vec<String>>> {
+    let query_ctx = QueryContext {
+        request_id: RequestId::next_id(),
+        catalog: &ctx.catalog,
+        tenant: &ctx.tenant,
+        instance: &instance,
+    };
+    let mut label_names: BTreeSet<_> = prom_query::list_tag_names(&query_ctx)
+        .context(PromQuery)?
+        .into_iter()
+        .collect();
+    label_names.insert(METRIC_NAME_LABEL.to_string());
+
+    Ok(ApiResponse::success(label_names.into_iter().collect()))
+}
+
+/// Handle the series query of the prometheus http api.
+///
+/// Only the tag columns are read, and the start time defaults to
+/// [DEFAULT_SERIES_RANGE] before the end time.
+pub async fn handle_series<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
+    ctx: RequestContext,
+    instance: InstanceRef<C, Q>,
+    params: &ApiParams,
+) -> Result<ApiResponse<Vec<BTreeMap<String, String>>>> {
+    let matches = params.get_all("match[]");
+    ensure!(
+        !matches.is_empty(),
+        InvalidPromRequest {
+            msg: "No match[] parameter provided",
+        }
+    );
+    let end = params
+        .time("end")?
+        .unwrap_or_else(|| Timestamp::now().as_i64());
+    let start = params
+        .time("start")?
+        .unwrap_or_else(|| end.saturating_sub(DEFAULT_SERIES_RANGE));
+    ensure!(
+        start <= end,
+        InvalidPromRequest {
+            msg: "End timestamp must not be before start time",
+        }
+    );
+    let eval_params = EvalParams {
+        start,
+        end,
+        step: INSTANT_QUERY_STEP,
+        field: VALUE_FIELD.to_string(),
+    };
+
+    let request_id = RequestId::next_id();
+    debug!(
+        "Prom series begin, catalog:{}, tenant:{}, request_id:{}, matches:{:?}, params:{:?}",
+        ctx.catalog, ctx.tenant, request_id, matches, eval_params
+    );
+
+    let query_ctx = QueryContext {
+        request_id,
+        catalog: &ctx.catalog,
+        tenant: &ctx.tenant,
+        instance: &instance,
+    };
+    let mut series_labels = BTreeSet::new();
+    for query in matches {
+        let selector = match parse_query(&query_ctx, query, &eval_params)? {
+            Expr::SimpleExpr(Operand::Selector(selector)) if selector.range == 0 => selector,
+            _ => {
+                return InvalidPromRequest {
+                    msg: format!(
+                        "Parameter match[] must be an instant vector selector, match:{}",
+                        query
+                    ),
+                }
+                .fail()
+            }
+        };
+
+        let metric = selector.table.clone();
+        let labels_set = match prom_query::execute_series(&query_ctx, selector).await {
+            Ok(v) => v,
+            Err(e) if matches!(e.code(), StatusCode::NotFound) => continue,
+            Err(e) => return Err(e).context(PromQuery),
+        };
+        for mut labels in labels_set {
+            labels.insert(METRIC_NAME_LABEL.to_string(), metric.clone());
+            series_labels.insert(labels);
+        }
+    }
+
+    Ok(ApiResponse::success(series_labels.into_iter().collect()))
+}
+
+fn parse_query<C: CatalogManager, Q>(
+    ctx: &QueryContext<'_, C, Q>,
+    query: &str,
+    params: &EvalParams,
+) -> Result<Expr> {
+    let frontend = prom_query::new_frontend(ctx);
+    let mut sql_ctx = SqlContext::new(ctx.request_id);
+
+    frontend
+        .parse_promql_query(&mut sql_ctx, query, params)
+        .context(ParsePromQuery)
+}
+
+/// Metric name of the series returned by the expr, only the selector keeps
+/// the metric name.
+fn metric_of_expr(expr: &Expr) -> Option<String> {
+    if expr.is_selector() {
+        Some(expr.get_selector().table.clone())
+    } else {
+        None
+    }
+}
+
+fn with_metric_name(
+    metric: Option<&str>,
+    mut labels: BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    if let Some(metric) = metric {
+        labels.insert(METRIC_NAME_LABEL.to_string(), metric.to_string());
+    }
+    labels
+}
+
+/// Convert the series to the matrix series with the samples in [start, end],
+/// returns None if there is no sample in the range.
+fn to_matrix_series(
+    metric: Option<&str>,
+    series: Series,
+    start: i64,
+    end: i64,
+) -> Option<MatrixSeries> {
+    let mut samples: Vec<_> = series
+        .samples
+        .iter()
+        .filter(|sample| (start..=end).contains(&sample.get_timestamp()))
+        .collect();
+    if samples.is_empty() {
+        return None;
+    }
+    samples.sort_by_key(|sample| sample.get_timestamp());
+
+    Some(MatrixSeries {
+        metric: with_metric_name(metric, series.labels),
+        values: samples
+            .into_iter()
+            .map(|sample| to_sample_pair(sample.get_timestamp(), sample.get_value()))
+            .collect(),
+    })
+}
+
+/// Steps of the range query.
+struct StepRange {
+    start: i64,
+    end: i64,
+    step: i64,
+    /// Offset of the selector
+    offset: i64,
+}
+
+/// Convert the series of a selector to the matrix series with a sample at
+/// each step, returns None if there is no sample at any step.
+///
+/// Like prometheus, the sample at a step is the latest sample within the
+/// lookback window before the step (shifted by the offset).
+fn to_aligned_matrix_series(
+    metric: &str,
+    series: Series,
+    steps: &StepRange,
+) -> Option<MatrixSeries> {
+    let mut samples: Vec<_> = series
+        .samples
+        .iter()
+        .map(|sample| (sample.get_timestamp(), sample.get_value()))
+        .collect();
+    samples.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let mut values = Vec::new();
+    // Number of samples not after the current step.
+    let mut num_samples = 0;
+    for time in (steps.start..=steps.end).step_by(steps.step as usize) {
+        let eval_time = time.saturating_sub(steps.offset);
+        while num_samples < samples.len() && samples[num_samples].0 <= eval_time {
+            num_samples += 1;
+        }
+
+        if num_samples == 0 {
+            continue;
+        }
+        let (timestamp, value) = samples[num_samples - 1];
+        if timestamp > eval_time.saturating_sub(DEFAULT_LOOKBACK) {
+            values.push(to_sample_pair(time, value));
+        }
+    }
+    if values.is_empty() {
+        return None;
+    }
+
+    Some(MatrixSeries {
+        metric: with_metric_name(Some(metric), series.labels),
+        values,
+    })
+}
+
+/// Convert the series to the vector sample with the latest value not after
+/// the `time`, returns None if there is no such sample.
+fn to_vector_sample(metric: Option<&str>, series: Series, time: i64) -> Option<VectorSample> {
+    let sample: &SamplePb = series
+        .samples
+        .iter()
+        .filter(|sample| sample.get_timestamp() <= time)
+        .max_by_key(|sample| sample.get_timestamp())?;
+    let value = to_sample_pair(time, sample.get_value());
+
+    Some(VectorSample {
+        metric: with_metric_name(metric, series.labels),
+        value,
+    })
+}
+
+fn to_secs(timestamp: i64) -> f64 {
+    timestamp as f64 / 1000.0
+}
+
+fn to_sample_pair(timestamp: i64, value: f64) -> SamplePair {
+    (to_secs(timestamp), format_value(value))
+}
+
+/// Format the value like prometheus does.
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Parse the time in milliseconds from the unix timestamp in seconds or the
+/// RFC3339 time, the time must be in [[MIN_TIME], [MAX_TIME]].
+fn parse_time(value: &str) -> Result<i64> {
+    let time = match f64::from_str(value) {
+        Ok(secs) => Some(secs * 1000.0)
+            .filter(|millis| *millis >= MIN_TIME as f64 && *millis <= MAX_TIME as f64)
+            .map(|millis| millis.round() as i64),
+        Err(_) => chrono::DateTime::parse_from_rfc3339(value)
+            .ok()
+            .map(|time| time.timestamp_millis())
+            .filter(|millis| (MIN_TIME..=MAX_TIME).contains(millis)),
+    };
+
+    time.with_context(|| InvalidPromRequest {
+        msg: format!("Invalid time {}", value),
+    })
+}
+
+/// Parse the step in milliseconds from the seconds or the duration like
+/// `1m30s`.
+fn parse_step(value: &str) -> Result<i64> {
+    let step = match f64::from_str(value) {
+        Ok(secs) => (secs * 1000.0).round() as i64,
+        Err(_) => ReadableDuration::from_str(value)
+            .map(|duration| duration.as_millis() as i64)
+            .unwrap_or(0),
+    };
+    ensure!(
+        step > 0,
+        InvalidPromRequest {
+            msg: format!("Invalid step {}, must be a positive duration", value),
+        }
+    );
+
+    Ok(step)
+}
+
+#[cfg(test)]
+mod tests {
+    use proto::prometheus_remote::LabelMatcher;
+
+    use super::*;
+
+    fn new_series(labels: &[(&str, &str)], samples: &[(i64, f64)]) -> TimeSeries {
+        let mut series = TimeSeries::new();
+        for (name, value) in labels {
+            let mut label = Label::new();
+            label.set_name(name.to_string());
+            label.set_value(value.to_string());
+            series.mut_labels().push(label);
+        }
+        for (timestamp, value) in samples {
+            let mut sample = Sample::new();
+            sample.set_timestamp(*timestamp);
+            sample.set_value(*value);
+            series.mut_samples().push(sample);
+        }
+        series
+    }
+
+    fn encode_request<M: Message>(request: &M) -> Vec<u8> {
+        let buf = request.write_to_bytes().unwrap();
+        snap::raw::Encoder::new().compress_vec(&buf).unwrap()
+    }
+
+    #[test]
+    fn test_convert_write_request() {
+        let mut request = WriteRequest::new();
+        request.set_timeseries(
+            vec![
+                new_series(
+                    &[("__name__", "cpu"), ("host", "a")],
+                    &[(1000, 1.0), (2000, 2.0)],
+                ),
+                new_series(&[("__name__", "mem"), ("host", "a")], &[(1000, 10.0)]),
+                new_series(
+                    &[("region", "r1"), ("__name__", "cpu"), ("host", "b")],
+                    &[(1000, 3.0)],
+                ),
+                // Series without samples is ignored.
+                new_series(&[("__name__", "disk")], &[]),
+            ]
+            .into(),
+        );
+
+        let body = encode_request(&request);
+        let request: WriteRequest = decode_request(&body).unwrap();
+        let metrics = convert_write_request(request).unwrap();
+        assert_eq!(2, metrics.len());
+
+        let cpu = &metrics[0];
+        assert_eq!("cpu", cpu.get_metric());
+        assert_eq!(
+            &["host".to_string(), "region".to_string()],
+            cpu.get_tag_names()
+        );
+        assert_eq!(&[VALUE_FIELD.to_string()], cpu.get_field_names());
+        assert_eq!(2, cpu.get_entries().len());
+        let entry = &cpu.get_entries()[1];
+        let tags: Vec<_> = entry
+            .get_tags()
+            .iter()
+            .map(|tag| (tag.get_name_index(), tag.get_value().get_string_value()))
+            .collect();
+        assert_eq!(vec![(1, "r1"), (0, "b")], tags);
+        let field_groups = cpu.get_entries()[0].get_field_groups();
+        assert_eq!(2, field_groups.len());
+        assert_eq!(2000, field_groups[1].get_timestamp());
+        assert_eq!(
+            2.0,
+            field_groups[1].get_fields()[0]
+                .get_value()
+                .get_float64_value()
+        );
+
+        let mem = &metrics[1];
+        assert_eq!("mem", mem.get_metric());
+        assert_eq!(1, mem.get_entries().len());
+    }
+
+    #[test]
+    fn test_convert_invalid_write_request() {
+        let mut request = WriteRequest::new();
+        request.set_timeseries(vec![new_series(&[("host", "a")], &[(1000, 1.0)])].into());
+        assert!(convert_write_request(request).is_err());
+
+        let mut request = WriteRequest::new();
+        request.set_timeseries(vec![new_series(&[("__name__", "")], &[(1000, 1.0)])].into());
+        assert!(convert_write_request(request).is_err());
+    }
+
+    #[test]
+    fn test_decode_invalid_request() {
+        // Not compressed by snappy.
+        let buf = WriteRequest::new().write_to_bytes().unwrap();
+        let mut body = buf.clone();
+        body.extend_from_slice(b"invalid");
+        assert!(decode_request::<WriteRequest>(&body).is_err());
+
+        // Not a protobuf message.
+        let body = snap::raw::Encoder::new()
+            .compress_vec(b"invalid message")
+            .unwrap();
+        assert!(decode_request::<WriteRequest>(&body).is_err());
+    }
+
+    fn new_matcher(typ: LabelMatcher_Type, name: &str, value: &str) -> LabelMatcher {
+        let mut matcher = LabelMatcher::new();
+        matcher.set_field_type(typ);
+        matcher.set_name(name.to_string());
+        matcher.set_value(value.to_string());
+        matcher
+    }
+
+    fn new_query(matchers: Vec<LabelMatcher>) -> Query {
+        let mut query = Query::new();
+        query.set_start_timestamp_ms(1000);
+        query.set_end_timestamp_ms(2000);
+        query.set_matchers(matchers.into());
+        query
+    }
+
+    #[test]
+    fn test_read_query_to_expr() {
+        let mut request = ReadRequest::new();
+        request.mut_queries().push(new_query(vec![
+            new_matcher(LabelMatcher_Type::EQ, "__name__", "cpu"),
+            new_matcher(LabelMatcher_Type::RE, "host", "a|b"),
+            new_matcher(LabelMatcher_Type::NEQ, "region", "r1"),
+        ]));
+        let body = encode_request(&request);
+        let mut request: ReadRequest = decode_request(&body).unwrap();
+
+        let expr = read_query_to_expr(request.take_queries().into_vec().remove(0)).unwrap();
+        assert!(expr.is_selector());
+        let selector = expr.get_selector();
+        assert_eq!("cpu", selector.table);
+        assert_eq!(VALUE_FIELD, selector.field);
+        assert_eq!(
+            TimeRange::new(Timestamp::new(1000), Timestamp::new(2001)).unwrap(),
+            selector.query_range
+        );
+        let filters: Vec<_> = selector
+            .filters
+            .iter()
+            .map(|f| {
+                (
+                    f.tag_key.as_str(),
+                    f.operators[0].typ.clone(),
+                    f.operators[0].params[0].as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("host", FilterType::Regexp, "^(?:a|b)$"),
+                ("region", FilterType::NotLiteralOr, "r1"),
+            ],
+            filters
+        );
+    }
+
+    #[test]
+    fn test_read_invalid_query() {
+        // Metric name is missing.
+        let query = new_query(vec![new_matcher(LabelMatcher_Type::EQ, "host", "a")]);
+        assert!(read_query_to_expr(query).is_err());
+
+        // Metric name is not matched by equal.
+        let query = new_query(vec![new_matcher(
+            LabelMatcher_Type::RE,
+            "__name__",
+            "cpu.*",
+        )]);
+        assert!(read_query_to_expr(query).is_err());
+
+        // Invalid time range.
+        let mut query = new_query(vec![new_matcher(LabelMatcher_Type::EQ, "__name__", "cpu")]);
+        query.set_start_timestamp_ms(3000);
+        assert!(read_query_to_expr(query).is_err());
+    }
+
+    #[test]
+    fn test_convert_to_timeseries() {
+        let series = new_prom_series(&[("host", "a")], &[(1000, 1.0), (2000, 2.0)]);
+        let timeseries = convert_to_timeseries("cpu", series);
+
+        let labels: Vec<_> = timeseries
+            .get_labels()
+            .iter()
+            .map(|label| (label.get_name(), label.get_value()))
+            .collect();
+        assert_eq!(vec![("__name__", "cpu"), ("host", "a")], labels);
+        let samples: Vec<_> = timeseries
+            .get_samples()
+            .iter()
+            .map(|sample| (sample.get_timestamp(), sample.get_value()))
+            .collect();
+        assert_eq!(vec![(1000, 1.0), (2000, 2.0)], samples);
+    }
+
+    fn new_prom_series(labels: &[(&str, &str)], samples: &[(i64, f64)]) -> Series {
+        Series {
+            labels: labels
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            samples: samples
+                .iter()
+                .map(|(timestamp, value)| {
+                    let mut sample = SamplePb::new();
+                    sample.set_timestamp(*timestamp);
+                    sample.set_value(*value);
+                    sample
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_api_params() {
+        let params = ApiParams::parse(
+            b"query=sum%28rate%28cpu%5B5m%5D%29%29&match[]=cpu&match%5B%5D=mem&time=1.5",
+        );
+        assert_eq!("sum(rate(cpu[5m]))", params.required("query").unwrap());
+        assert_eq!(vec!["cpu", "mem"], params.get_all("match[]"));
+        assert_eq!(Some(1500), params.time("time").unwrap());
+        assert_eq!(None, params.time("start").unwrap());
+        assert!(params.required("step").is_err());
+    }
+
+    #[test]
+    fn test_parse_time_and_step() {
+        assert_eq!(1_435_781_451_781, parse_time("1435781451.781").unwrap());
+        assert_eq!(
+            1_435_781_451_781,
+            parse_time("2015-07-01T20:10:51.781Z").unwrap()
+        );
+        assert!(parse_time("yesterday").is_err());
+        assert!(parse_time("1e300").is_err());
+        assert!(parse_time("-1e300").is_err());
+        assert!(parse_time("NaN").is_err());
+        assert_eq!(MAX_TIME, parse_time("9999-12-31T23:59:59.999Z").unwrap());
+
+        assert_eq!(15_000, parse_step("15").unwrap());
+        assert_eq!(500, parse_step("0.5").unwrap());
+        assert_eq!(90_000, parse_step("1m30s").unwrap());
+        assert!(parse_step("0").is_err());
+        assert!(parse_step("-1").is_err());
+        assert!(parse_step("1x").is_err());
+    }
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!("1", format_value(1.0));
+        assert_eq!("0.25", format_value(0.25));
+        assert_eq!("NaN", format_value(f64::NAN));
+        assert_eq!("+Inf", format_value(f64::INFINITY));
+        assert_eq!("-Inf", format_value(f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn test_query_response() {
+        let series = new_prom_series(&[("host", "a")], &[(3000, 3.0), (1000, 1.0), (2000, 2.0)]);
+        let matrix = to_matrix_series(Some("cpu"), series, 1000, 2000).unwrap();
+        let resp = ApiResponse::success(QueryData::matrix(vec![matrix]));
+        assert_eq!(
+            r#"{"status":"success","data":{"resultType":"matrix","result":[{"metric":{"__name__":"cpu","host":"a"},"values":[[1.0,"1"],[2.0,"2"]]}]}}"#,
+            serde_json::to_string(&resp).unwrap()
+        );
+
+        let series = new_prom_series(&[("host", "a")], &[(1000, 1.0), (3000, 3.0), (2000, 2.0)]);
+        let sample = to_vector_sample(None, series, 2500).unwrap();
+        let resp = ApiResponse::success(QueryData::vector(vec![sample]));
+        assert_eq!(
+            r#"{"status":"success","data":{"resultType":"vector","result":[{"metric":{"host":"a"},"value":[2.5,"2"]}]}}"#,
+            serde_json::to_string(&resp).unwrap()
+        );
+
+        // No sample in the range.
+        let series = new_prom_series(&[("host", "a")], &[(3000, 3.0)]);
+        assert!(to_matrix_series(None, series, 1000, 2000).is_none());
+        let series = new_prom_series(&[("host", "a")], &[(3000, 3.0)]);
+        assert!(to_vector_sample(None, series, 2000).is_none());
+    }
+
+    #[test]
+    fn test_aligned_matrix_series() {
+        let samples = [(1000, 1.0), (62_000, 2.0), (65_000, 3.0)];
+        let steps = StepRange {
+            start: 0,
+            end: 65_000 + DEFAULT_LOOKBACK + 30_000,
+            step: 30_000,
+            offset: 0,
+        };
+        let series = new_prom_series(&[("host", "a")], &samples);
+        let matrix = to_aligned_matrix_series("cpu", series, &steps).unwrap();
+        let values: Vec<_> = matrix
+            .values
+            .iter()
+            .map(|(t, v)| (*t, v.as_str()))
+            .collect();
+        // The sample at 65s is stale after the lookback window.
+        assert_eq!(
+            vec![
+                (30.0, "1"),
+                (60.0, "1"),
+                (90.0, "3"),
+                (120.0, "3"),
+                (150.0, "3"),
+                (180.0, "3"),
+                (210.0, "3"),
+                (240.0, "3"),
+                (270.0, "3"),
+                (300.0, "3"),
+                (330.0, "3"),
+                (360.0, "3"),
+            ],
+            values
+        );
+
+        // The steps are shifted by the offset.
+        let steps = StepRange {
+            start: 120_000,
+            end: 180_000,
+            step: 60_000,
+            offset: 60_000,
+        };
+        let series = new_prom_series(&[("host", "a")], &samples);
+        let matrix = to_aligned_matrix_series("cpu", series, &steps).unwrap();
+        let values: Vec<_> = matrix
+            .values
+            .iter()
+            .map(|(t, v)| (*t, v.as_str()))
+            .collect();
+        assert_eq!(vec![(120.0, "1"), (180.0, "3")], values);
+
+        // No sample within the lookback window of any step.
+        let steps = StepRange {
+            start: DEFAULT_LOOKBACK + 70_000,
+            end: DEFAULT_LOOKBACK + 100_000,
+            step: 10_000,
+            offset: 0,
+        };
+        let series = new_prom_series(&[("host", "a")], &samples);
+        assert!(to_aligned_matrix_series("cpu", series, &steps).is_none());
+    }
+}
@@ -5,9 +5,11 @@
 use std::collections::HashMap;
 
 use arrow_deps::arrow::error::Result as ArrowResult;
-use common_types::{datum::Datum, request_id::RequestId};
+use common_types::{datum::Datum, record_batch::RecordBatch, request_id::RequestId};
+use common_util::runtime::Runtime;
+use futures::stream::{self, BoxStream, StreamExt};
 use interpreters::{context::Context as InterpreterContext, factory::Factory, interpreter::Output};
-use log::info;
+use log::{error, info};
 use query_engine::executor::RecordBatchVec;
 use serde_derive::Serialize;
 use snafu::ensure;
@@ -15,12 +17,20 @@ use sql::{
     frontend::{Context as SqlContext, Frontend},
     provider::CatalogMetaProvider,
 };
+use table_engine::stream::SendableRecordBatchStream;
+use tokio::sync::mpsc;
 
 use crate::handlers::{
-    error::{ArrowToString, CreatePlan, InterpreterExec, ParseSql, TooMuchStmt},
+    error::{
+        ArrowToString, CreatePlan, InterpreterExec, ParseSql, PollStream, SerializeRows,
+        StreamAborted, TooMuchStmt,
+    },
     prelude::*,
 };
 
+/// Channel size of the serialized rows of a streaming query.
+const STREAM_ROWS_CHANNEL_LEN: usize = 16;
+
 #[derive(Debug, Deserialize)]
 pub struct Request {
     query: String,
@@ -34,11 +44,25 @@ pub enum Response {
     Rows(Vec<HashMap<String, Datum>>),
 }
 
+/// Stream of the serialized json bytes
+pub type JsonStream = BoxStream<'static, Result<Vec<u8>>>;
+
+/// Output of the sql handler
+pub enum HandlerOutput {
+    /// Response can be serialized at once
+    Response(Response),
+    /// Rows of the query are serialized while the stream is polled, the json
+    /// format is the same as [Response::Rows]. If polling the stream fails
+    /// after the first batch, the json ends with the error, e.g.
+    /// `{"rows":[...],"error":"..."}`
+    Stream(JsonStream),
+}
+
 pub async fn handle_sql<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
     ctx: RequestContext,
     instance: InstanceRef<C, Q>,
     request: Request,
-) -> Result<Response> {
+) -> Result<HandlerOutput> {
     let request_id = RequestId::next_id();
 
     info!(
@@ -65,7 +89,7 @@ pub async fn handle_sql<C: CatalogManager + 'static, Q: QueryExecutor + 'static>
         .context(ParseSql)?;
 
     if stmts.is_empty() {
-        return Ok(Response::AffectedRows(0));
+        return Ok(HandlerOutput::Response(Response::AffectedRows(0)));
     }
 
     // TODO(yingwen): For simplicity, we only support executing one statement now
@@ -103,9 +127,18 @@ pub async fn handle_sql<C: CatalogManager + 'static, Q: QueryExecutor + 'static>
     })?;
 
     // Convert output to json
-    let resp = convert_output(output).context(ArrowToString {
-        query: &request.query,
-    })?;
+    let resp = match output {
+        Output::AffectedRows(n) => HandlerOutput::Response(Response::AffectedRows(n)),
+        Output::Records(records) => {
+            let resp = convert_records(records).context(ArrowToString {
+                query: &request.query,
+            })?;
+            HandlerOutput::Response(resp)
+        }
+        Output::Stream(stream) => HandlerOutput::Stream(
+            stream_rows_to_json(&ctx.runtime, stream, request.query.clone()).await?,
+        ),
+    };
 
     info!(
         "sql handler finished processing request, request:{:?}",
@@ -115,34 +148,249 @@ pub async fn handle_sql<C: CatalogManager + 'static, Q: QueryExecutor + 'static>
     Ok(resp)
 }
 
-fn convert_output(output: Output) -> ArrowResult<Response> {
-    match output {
-        Output::AffectedRows(n) => Ok(Response::AffectedRows(n)),
-        Output::Records(records) => convert_records(records),
-    }
-}
-
 fn convert_records(records: RecordBatchVec) -> ArrowResult<Response> {
     let total_rows = records.iter().map(|v| v.num_rows()).sum();
     let mut resp = Vec::with_capacity(total_rows);
     for record_batch in records {
-        let num_cols = record_batch.num_columns();
-        let num_rows = record_batch.num_rows();
-        let schema = record_batch.schema();
-
-        for row_idx in 0..num_rows {
-            let mut row = HashMap::with_capacity(num_cols);
-            for col_idx in 0..num_cols {
-                let column = record_batch.column(col_idx);
-                let column = column.datum(row_idx);
-
-                let column_name = schema.column(col_idx).name.clone();
-                row.insert(column_name, column);
+        for row_idx in 0..record_batch.num_rows() {
+            resp.push(row_at(&record_batch, row_idx));
+        }
+    }
+
+    Ok(Response::Rows(resp))
+}
+
+fn row_at(record_batch: &RecordBatch, row_idx: usize) -> HashMap<String, Datum> {
+    let num_cols = record_batch.num_columns();
+    let schema = record_batch.schema();
+
+    let mut row = HashMap::with_capacity(num_cols);
+    for col_idx in 0..num_cols {
+        let column = record_batch.column(col_idx);
+        let column = column.datum(row_idx);
+
+        let column_name = schema.column(col_idx).name.clone();
+        row.insert(column_name, column);
+    }
+
+    row
+}
+
+/// Serialize the rows in the `stream` into json.
+///
+/// The stream is polled in the `runtime` and the bounded channel stops polling
+/// it until the client consumes the serialized rows.
+///
+/// The first batch is polled before returning, so the error of it is returned
+/// before the status of the response is sent. The errors of the following
+/// batches are appended to the end of the json as the status has been sent.
+async fn stream_rows_to_json(
+    runtime: &Runtime,
+    mut stream: SendableRecordBatchStream,
+    query: String,
+) -> Result<JsonStream> {
+    let (tx, mut rx) = mpsc::channel(STREAM_ROWS_CHANNEL_LEN);
+    let stream_query = query.clone();
+    runtime.spawn(async move {
+        let query = stream_query;
+        let mut is_first_row = true;
+        let mut head = b"{\"rows\":[".to_vec();
+        match next_rows(&mut stream, &mut is_first_row, &query).await {
+            Some(Ok(bytes)) => head.extend_from_slice(&bytes),
+            Some(Err(e)) => {
+                let _ = tx.send(Err(e)).await;
+                return;
+            }
+            None => {
+                head.extend_from_slice(b"]}");
+                let _ = tx.send(Ok(head)).await;
+                return;
+            }
+        }
+        // The receiver is dropped if the client is disconnected.
+        if tx.send(Ok(head)).await.is_err() {
+            return;
+        }
+
+        while let Some(bytes) = next_rows(&mut stream, &mut is_first_row, &query).await {
+            let bytes = match bytes {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("Failed to stream rows of query, err:{}", e);
+                    let _ = tx.send(Ok(error_tail(&e))).await;
+                    return;
+                }
+            };
+            if tx.send(Ok(bytes)).await.is_err() {
+                return;
             }
+        }
+
+        let _ = tx.send(Ok(b"]}".to_vec())).await;
+    });
+
+    let head = match rx.recv().await {
+        Some(v) => v?,
+        None => return StreamAborted { query }.fail(),
+    };
+    let rest = stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|v| (v, rx)) });
+
+    Ok(stream::once(async { Ok(head) }).chain(rest).boxed())
+}
+
+/// Poll the next batch of the `stream` and serialize its rows, returns None if
+/// the stream is exhausted.
+async fn next_rows(
+    stream: &mut SendableRecordBatchStream,
+    is_first_row: &mut bool,
+    query: &str,
+) -> Option<Result<Vec<u8>>> {
+    let batch = stream.next().await?;
+    let bytes = batch
+        .context(PollStream { query })
+        .and_then(|batch| serialize_rows(&batch, is_first_row).context(SerializeRows { query }));
 
-            resp.push(row);
+    Some(bytes)
+}
+
+/// Returns the end of the json with the error.
+fn error_tail(e: &Error) -> Vec<u8> {
+    let mut buf = b"],\"error\":".to_vec();
+    // Serializing a string never fails.
+    let _ = serde_json::to_writer(&mut buf, &e.to_string());
+    buf.push(b'}');
+
+    buf
+}
+
+/// Serialize rows of the `record_batch` as elements of a json array.
+fn serialize_rows(
+    record_batch: &RecordBatch,
+    is_first_row: &mut bool,
+) -> serde_json::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    for row_idx in 0..record_batch.num_rows() {
+        if !*is_first_row {
+            buf.push(b',');
         }
+        *is_first_row = false;
+
+        serde_json::to_writer(&mut buf, &row_at(record_batch, row_idx))?;
     }
 
-    Ok(Response::Rows(resp))
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, convert::TryFrom, pin::Pin, sync::Arc, task::Poll};
+
+    use arrow_deps::arrow::{
+        array::Float64Array,
+        datatypes::{DataType, Field, Schema as ArrowSchema},
+        record_batch::RecordBatch as ArrowRecordBatch,
+    };
+    use common_types::schema::RecordSchema;
+    use common_util::runtime::Builder as RuntimeBuilder;
+    use futures::Stream;
+    use table_engine::stream::{self as table_stream, ErrNoSource, RecordBatchStream};
+
+    use super::*;
+
+    struct VecStream {
+        schema: RecordSchema,
+        batches: VecDeque<table_stream::Result<RecordBatch>>,
+    }
+
+    impl Stream for VecStream {
+        type Item = table_stream::Result<RecordBatch>;
+
+        fn poll_next(
+            mut self: Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> Poll<Option<Self::Item>> {
+            Poll::Ready(self.batches.pop_front())
+        }
+    }
+
+    impl RecordBatchStream for VecStream {
+        fn schema(&self) -> &RecordSchema {
+            &self.schema
+        }
+    }
+
+    fn build_stream(num_batches: usize, with_err: bool) -> SendableRecordBatchStream {
+        let arrow_schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "value",
+            DataType::Float64,
+            true,
+        )]));
+        let schema = RecordSchema::try_from(arrow_schema.clone()).unwrap();
+        let mut batches = VecDeque::new();
+        for _ in 0..num_batches {
+            let arrow_record_batch = ArrowRecordBatch::try_new(
+                arrow_schema.clone(),
+                vec![Arc::new(Float64Array::from(vec![1.0, 2.0]))],
+            )
+            .unwrap();
+            batches.push_back(Ok(RecordBatch::try_from(arrow_record_batch).unwrap()));
+        }
+        if with_err {
+            batches.push_back(ErrNoSource { msg: "mock error" }.fail());
+        }
+
+        Box::pin(VecStream { schema, batches })
+    }
+
+    async fn collect_json(stream: JsonStream) -> serde_json::Value {
+        let chunks: Vec<_> = stream.collect().await;
+        let mut body = Vec::new();
+        for chunk in chunks {
+            body.extend_from_slice(&chunk.unwrap());
+        }
+
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[test]
+    fn test_stream_rows_to_json() {
+        let runtime = RuntimeBuilder::default()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        let query = "select * from test".to_string();
+
+        runtime.block_on(async {
+            let stream = build_stream(0, false);
+            let json = stream_rows_to_json(&runtime, stream, query.clone())
+                .await
+                .unwrap();
+            let json = collect_json(json).await;
+            assert_eq!(serde_json::json!({ "rows": [] }), json);
+
+            let stream = build_stream(2, false);
+            let json = stream_rows_to_json(&runtime, stream, query.clone())
+                .await
+                .unwrap();
+            let json = collect_json(json).await;
+            assert_eq!(4, json["rows"].as_array().unwrap().len());
+            assert!(json.get("error").is_none());
+
+            // The error of the first batch is returned directly.
+            let stream = build_stream(0, true);
+            assert!(stream_rows_to_json(&runtime, stream, query.clone())
+                .await
+                .is_err());
+
+            // The error of the following batches ends the json.
+            let stream = build_stream(1, true);
+            let json = stream_rows_to_json(&runtime, stream, query.clone())
+                .await
+                .unwrap();
+            let json = collect_json(json).await;
+            assert_eq!(2, json["rows"].as_array().unwrap().len());
+            assert!(json["error"].as_str().unwrap().contains("mock error"));
+        });
+    }
 }
@@ -8,6 +8,7 @@ use std::{
 
 use catalog::manager::Manager as CatalogManager;
 use log::error;
+use meta_client::MetaClient;
 use profile::Profiler;
 use query_engine::executor::Executor as QueryExecutor;
 use serde_derive::Serialize;
@@ -17,12 +18,23 @@ use tokio::sync::oneshot::{self, Sender};
 use warp::{
     header,
     http::StatusCode,
+    hyper::{body::Bytes, Body},
     reject,
     reply::{self, Reply},
     Filter,
 };
 
-use crate::{consts, context::RequestContext, error, handlers, instance::InstanceRef, metrics};
+use crate::{
+    consts,
+    context::RequestContext,
+    error,
+    handlers::{self, prom::ApiParams, sql::HandlerOutput},
+    instance::InstanceRef,
+    metrics,
+};
+
+/// Max body size of the prometheus remote storage requests, 32MB
+const PROM_REMOTE_BODY_LIMIT: u64 = 32 * 1024 * 1024;
 
 #[derive(Debug)]
 pub struct Config {
@@ -46,6 +58,9 @@ pub enum Error {
     #[snafu(display("Missing instance to build service.\nBacktrace:\n{}", backtrace))]
     MissingInstance { backtrace: Backtrace },
 
+    #[snafu(display("Missing meta client to build service.\nBacktrace:\n{}", backtrace))]
+    MissingMetaClient { backtrace: Backtrace },
+
     #[snafu(display(
         "Fail to do heap profiling, err:{}.\nBacktrace:\n{}",
         source,
@@ -87,6 +102,7 @@ impl reject::Reject for Error {}
 pub struct Service<C, Q> {
     runtimes: Arc<EngineRuntimes>,
     instance: InstanceRef<C, Q>,
+    meta_client: Arc<dyn MetaClient + Send + Sync>,
     profiler: Arc<Profiler>,
     tx: Sender<()>,
 }
@@ -106,6 +122,12 @@ impl<C: CatalogManager + 'static, Q: QueryExecutor + 'static> Service<C, Q> {
             .or(self.heap_profile())
             .or(self.admin_reject())
             .or(self.flush_memtable())
+            .or(self.prom_remote_write())
+            .or(self.prom_remote_read())
+            .or(self.prom_query())
+            .or(self.prom_query_range())
+            .or(self.prom_labels())
+            .or(self.prom_series())
     }
 
     fn home(&self) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -135,12 +157,136 @@ impl<C: CatalogManager + 'static, Q: QueryExecutor + 'static> Service<C, Q> {
                     })
                     .context(HandleRequest);
                 match result {
-                    Ok(res) => Ok(reply::json(&res)),
+                    Ok(HandlerOutput::Response(res)) => Ok(reply::json(&res).into_response()),
+                    Ok(HandlerOutput::Stream(stream)) => {
+                        let body = reply::Response::new(Body::wrap_stream(stream));
+                        Ok(reply::with_header(body, "content-type", "application/json")
+                            .into_response())
+                    }
                     Err(e) => Err(reject::custom(e)),
                 }
             })
     }
 
+    fn prom_remote_write(
+        &self,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("prom" / "v1" / "write")
+            .and(warp::post())
+            .and(warp::body::content_length_limit(PROM_REMOTE_BODY_LIMIT))
+            .and(warp::body::bytes())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and(self.with_meta_client())
+            .and_then(
+                |body: Bytes,
+                 ctx: RequestContext,
+                 instance: InstanceRef<C, Q>,
+                 meta_client: Arc<dyn MetaClient + Send + Sync>| async move {
+                    // The tables are auto created by the schema config of the tenant.
+                    let cluster_view = meta_client.get_cluster_view();
+                    let schema_config = cluster_view.schema_configs.get(&ctx.tenant);
+                    let result =
+                        handlers::prom::handle_remote_write(ctx, instance, schema_config, &body)
+                            .await
+                            .map_err(|e| {
+                                error!(
+                                    "Http service failed to handle prom remote write, err:{}",
+                                    e
+                                );
+                                e
+                            })
+                            .context(HandleRequest);
+
+                    match result {
+                        Ok(_) => Ok(reply::with_status(reply::reply(), StatusCode::NO_CONTENT)),
+                        Err(e) => Err(reject::custom(e)),
+                    }
+                },
+            )
+    }
+
+    fn prom_remote_read(
+        &self,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("prom" / "v1" / "read")
+            .and(warp::post())
+            .and(warp::body::content_length_limit(PROM_REMOTE_BODY_LIMIT))
+            .and(warp::body::bytes())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|body: Bytes, ctx, instance| async move {
+                let result = handlers::prom::handle_remote_read(ctx, instance, &body)
+                    .await
+                    .map_err(|e| {
+                        error!("Http service failed to handle prom remote read, err:{}", e);
+                        e
+                    })
+                    .context(HandleRequest);
+
+                match result {
+                    Ok(body) => {
+                        let resp =
+                            reply::with_header(body, "content-type", "application/x-protobuf");
+                        Ok(reply::with_header(resp, "content-encoding", "snappy"))
+                    }
+                    Err(e) => Err(reject::custom(e)),
+                }
+            })
+    }
+
+    fn prom_query(
+        &self,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("api" / "v1" / "query")
+            .and(self.with_prom_api_params())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|params, ctx, instance| async move {
+                let result = handlers::prom::handle_query(ctx, instance, &params).await;
+                Ok::<_, warp::Rejection>(prom_api_reply(result))
+            })
+    }
+
+    fn prom_query_range(
+        &self,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("api" / "v1" / "query_range")
+            .and(self.with_prom_api_params())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|params, ctx, instance| async move {
+                let result = handlers::prom::handle_query_range(ctx, instance, &params).await;
+                Ok::<_, warp::Rejection>(prom_api_reply(result))
+            })
+    }
+
+    fn prom_labels(
+        &self,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("api" / "v1" / "labels")
+            .and(self.with_prom_api_params())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|_params, ctx, instance| async move {
+                let result = handlers::prom::handle_labels(ctx, instance).await;
+                Ok::<_, warp::Rejection>(prom_api_reply(result))
+            })
+    }
+
+    fn prom_series(
+        &self,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("api" / "v1" / "series")
+            .and(self.with_prom_api_params())
+            .and(self.with_context())
+            .and(self.with_instance())
+            .and_then(|params, ctx, instance| async move {
+                let result = handlers::prom::handle_series(ctx, instance, &params).await;
+                Ok::<_, warp::Rejection>(prom_api_reply(result))
+            })
+    }
+
     fn flush_memtable(
         &self,
     ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -263,6 +409,30 @@ impl<C: CatalogManager + 'static, Q: QueryExecutor + 'static> Service<C, Q> {
         warp::any().map(move || profiler.clone())
     }
 
+    /// Params of the prometheus http api, which can be passed by the url query
+    /// of the GET request or the form body of the POST request.
+    fn with_prom_api_params(
+        &self,
+    ) -> impl Filter<Extract = (ApiParams,), Error = warp::Rejection> + Clone {
+        let query_params = warp::get()
+            .and(warp::query::raw().or(warp::any().map(String::new)).unify())
+            .map(|query: String| ApiParams::parse(query.as_bytes()));
+        let form_params = warp::post()
+            .and(warp::body::content_length_limit(PROM_REMOTE_BODY_LIMIT))
+            .and(warp::body::bytes())
+            .map(|body: Bytes| ApiParams::parse(&body));
+
+        query_params.or(form_params).unify()
+    }
+
+    fn with_meta_client(
+        &self,
+    ) -> impl Filter<Extract = (Arc<dyn MetaClient + Send + Sync>,), Error = Infallible> + Clone
+    {
+        let meta_client = self.meta_client.clone();
+        warp::any().map(move || meta_client.clone())
+    }
+
     fn with_instance(
         &self,
     ) -> impl Filter<Extract = (InstanceRef<C, Q>,), Error = Infallible> + Clone {
@@ -300,6 +470,7 @@ pub struct Builder<C, Q> {
     config: Config,
     runtimes: Option<Arc<EngineRuntimes>>,
     instance: Option<InstanceRef<C, Q>>,
+    meta_client: Option<Arc<dyn MetaClient + Send + Sync>>,
 }
 
 impl<C, Q> Builder<C, Q> {
@@ -308,6 +479,7 @@ impl<C, Q> Builder<C, Q> {
             config,
             runtimes: None,
             instance: None,
+            meta_client: None,
         }
     }
 
@@ -320,6 +492,11 @@ impl<C, Q> Builder<C, Q> {
         self.instance = Some(instance);
         self
     }
+
+    pub fn meta_client(mut self, meta_client: Arc<dyn MetaClient + Send + Sync>) -> Self {
+        self.meta_client = Some(meta_client);
+        self
+    }
 }
 
 impl<C: CatalogManager + 'static, Q: QueryExecutor + 'static> Builder<C, Q> {
@@ -327,11 +504,13 @@ impl<C: CatalogManager + 'static, Q: QueryExecutor + 'static> Builder<C, Q> {
     pub fn build(self) -> Result<Service<C, Q>> {
         let runtimes = self.runtimes.context(MissingRuntimes)?;
         let instance = self.instance.context(MissingInstance)?;
+        let meta_client = self.meta_client.context(MissingMetaClient)?;
         let (tx, rx) = oneshot::channel();
 
         let service = Service {
             runtimes: runtimes.clone(),
             instance,
+            meta_client,
             profiler: Arc::new(Profiler::default()),
             tx,
         };
@@ -361,13 +540,48 @@ struct ErrorResponse {
     message: String,
 }
 
+/// Error response of the prometheus http api
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PromApiErrorResponse {
+    status: &'static str,
+    error_type: &'static str,
+    error: String,
+}
+
+/// Reply the result of the prometheus http api, the error is replied in the
+/// format of prometheus instead of being rejected.
+fn prom_api_reply<T: serde::Serialize>(result: handlers::error::Result<T>) -> reply::Response {
+    match result {
+        Ok(resp) => reply::json(&resp).into_response(),
+        Err(e) => {
+            error!("Http service failed to handle prom api, err:{}", e);
+
+            let code = handle_request_error_to_status_code(&e);
+            let error_type = match code {
+                StatusCode::BAD_REQUEST => "bad_data",
+                StatusCode::TOO_MANY_REQUESTS => "execution",
+                _ => "internal",
+            };
+            let err_string = e.to_string();
+            let json = reply::json(&PromApiErrorResponse {
+                status: "error",
+                error_type,
+                error: error::first_line_in_error(&err_string).to_string(),
+            });
+
+            reply::with_status(json, code).into_response()
+        }
+    }
+}
+
 fn error_to_status_code(err: &Error) -> StatusCode {
     match err {
         Error::CreateContext { .. } => StatusCode::BAD_REQUEST,
-        // TODO(yingwen): Map handle request error to more accurate status code
-        Error::HandleRequest { .. }
-        | Error::MissingRuntimes { .. }
+        Error::HandleRequest { source } => handle_request_error_to_status_code(source),
+        Error::MissingRuntimes { .. }
         | Error::MissingInstance { .. }
+        | Error::MissingMetaClient { .. }
         | Error::ParseIpAddr { .. }
         | Error::ProfileHeap { .. }
         | Error::Internal { .. }
@@ -375,6 +589,28 @@ fn error_to_status_code(err: &Error) -> StatusCode {
     }
 }
 
+// TODO(yingwen): Map errors of other handlers to more accurate status code
+fn handle_request_error_to_status_code(err: &handlers::error::Error) -> StatusCode {
+    use handlers::error::Error as HandlerError;
+
+    match err {
+        HandlerError::DecompressPromRequest { .. }
+        | HandlerError::DecodePromRequest { .. }
+        | HandlerError::InvalidPromRequest { .. }
+        | HandlerError::ParsePromQuery { .. } => StatusCode::BAD_REQUEST,
+        HandlerError::PromWrite { source } | HandlerError::PromQuery { source } => {
+            match source.code() {
+                error::StatusCode::Ok => StatusCode::OK,
+                error::StatusCode::InvalidArgument => StatusCode::BAD_REQUEST,
+                error::StatusCode::NotFound => StatusCode::NOT_FOUND,
+                error::StatusCode::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+                error::StatusCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
 async fn handle_rejection(
     rejection: warp::Rejection,
 ) -> std::result::Result<impl warp::Reply, Infallible> {
@@ -23,3 +23,4 @@ mod metrics;
 mod router;
 pub mod server;
 pub mod table_engine;
+mod view_scheduler;
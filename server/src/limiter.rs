@@ -33,6 +33,11 @@ impl Limiter {
                 .read()
                 .unwrap()
                 .contains(insert.table.name()),
+            Plan::Delete(delete) => self
+                .write_reject_list
+                .read()
+                .unwrap()
+                .contains(delete.table.name()),
             _ => false,
         }
     }
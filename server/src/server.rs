@@ -17,6 +17,7 @@ use crate::{
     http::{self, Service},
     instance::{Instance, InstanceRef},
     limiter::Limiter,
+    view_scheduler::ViewScheduler,
 };
 
 #[derive(Debug, Snafu)]
@@ -59,12 +60,14 @@ define_result!(Error);
 pub struct Server<C, Q> {
     http_service: Service<C, Q>,
     rpc_services: RpcServices,
+    view_scheduler: ViewScheduler,
 }
 
 impl<C, Q> Server<C, Q> {
     pub fn stop(mut self) {
         self.rpc_services.shutdown();
         self.http_service.stop();
+        self.view_scheduler.stop();
     }
 
     pub async fn start(&mut self) -> Result<()> {
@@ -145,35 +148,44 @@ impl<C: CatalogManager + 'static, Q: QueryExecutor + 'static> Builder<C, Q> {
         };
         let instance = InstanceRef::new(instance);
 
-        // Create http config
-        let http_config = http::Config {
-            ip: self.config.bind_addr.clone(),
-            port: self.config.http_port,
-        };
-
-        // Start http service
-        let http_service = http::Builder::new(http_config)
-            .runtimes(runtimes.clone())
-            .instance(instance.clone())
-            .build()
-            .context(StartHttpService)?;
+        // Start the scheduler to refresh the materialized views
+        let view_scheduler = ViewScheduler::start(
+            &runtimes.bg_runtime,
+            instance.clone(),
+            self.config.view_refresh_interval.0,
+        );
 
         let meta_client_config = self.config.meta_client;
         let env = Arc::new(Environment::new(self.config.grpc_server_cq_count));
         let rpc_services = grpc::Builder::new()
-            .bind_addr(self.config.bind_addr)
+            .bind_addr(self.config.bind_addr.clone())
             .port(self.config.grpc_port)
             .meta_client_config(meta_client_config)
             .env(env)
-            .runtimes(runtimes)
-            .instance(instance)
+            .runtimes(runtimes.clone())
+            .instance(instance.clone())
             .route_rules(self.config.route_rules)
             .build()
             .context(BuildGrpcService)?;
 
+        // Create http config
+        let http_config = http::Config {
+            ip: self.config.bind_addr,
+            port: self.config.http_port,
+        };
+
+        // Start http service, it shares the meta client with the rpc services
+        let http_service = http::Builder::new(http_config)
+            .runtimes(runtimes)
+            .instance(instance)
+            .meta_client(rpc_services.meta_client())
+            .build()
+            .context(StartHttpService)?;
+
         let server = Server {
             http_service,
             rpc_services,
+            view_scheduler,
         };
         Ok(server)
     }
@@ -0,0 +1,557 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Scheduler to refresh the materialized views in background
+//!
+//! A view is refreshed once new segments of its source table are flushed. Each
+//! refresh aggregates the rows of the source table between the watermark of
+//! the view and the start of the current time bucket, writes the result into
+//! the table of the view, then advances the watermark. The range is refreshed
+//! step by step, each step covers a bounded time window and advances the
+//! watermark once done, so a long range (e.g. the backfill of a new view) never
+//! aggregates all its rows at once. Rows of the source table older than the
+//! watermark written after the refresh are not reflected in the view.
+
+use std::{collections::HashMap, time::Duration};
+
+use catalog::{
+    manager::Manager as CatalogManager,
+    schema::{SchemaRef, ViewInfo},
+};
+use common_types::{
+    datum::Datum,
+    record_batch::RecordBatch,
+    request_id::RequestId,
+    row::{RowGroup, RowGroupBuilder},
+    schema::Schema,
+    time::{TimeRange, Timestamp},
+};
+use common_util::runtime::Runtime;
+use df_operator::udfs::time_bucket::Period;
+use interpreters::{context::Context as InterpreterContext, factory::Factory, interpreter::Output};
+use log::{error, info};
+use query_engine::executor::{self, Executor as QueryExecutor};
+use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+use sql::{
+    frontend::{Context as SqlContext, Frontend},
+    parser::Parser,
+    provider::CatalogMetaProvider,
+};
+use table_engine::table::{TableRef, WriteRequest};
+use tokio::{
+    sync::oneshot::{self, Sender},
+    time,
+};
+
+use crate::instance::InstanceRef;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to find table, table:{}, err:{}", table, source))]
+    FindTable {
+        table: String,
+        source: catalog::schema::Error,
+    },
+
+    #[snafu(display("Table not found, table:{}.\nBacktrace:\n{}", table, backtrace))]
+    TableNotFound { table: String, backtrace: Backtrace },
+
+    #[snafu(display("Invalid period of view, view:{}, err:{}", view, source))]
+    InvalidPeriod {
+        view: String,
+        source: df_operator::udfs::time_bucket::Error,
+    },
+
+    #[snafu(display("Failed to parse query of view, view:{}, err:{}", view, source))]
+    ParseQuery {
+        view: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[snafu(display("Failed to create plan of view, view:{}, err:{}", view, source))]
+    CreatePlan {
+        view: String,
+        source: sql::frontend::Error,
+    },
+
+    #[snafu(display("Failed to execute query of view, view:{}, err:{}", view, source))]
+    ExecuteQuery {
+        view: String,
+        source: interpreters::interpreter::Error,
+    },
+
+    #[snafu(display("Failed to collect records of view, view:{}, err:{}", view, source))]
+    CollectRecords {
+        view: String,
+        source: query_engine::executor::Error,
+    },
+
+    #[snafu(display(
+        "Unexpected output of view query, view:{}.\nBacktrace:\n{}",
+        view,
+        backtrace
+    ))]
+    UnexpectedOutput { view: String, backtrace: Backtrace },
+
+    #[snafu(display("Failed to build rows of view, view:{}, err:{}", view, source))]
+    BuildRow {
+        view: String,
+        source: common_types::row::Error,
+    },
+
+    #[snafu(display("Failed to write view, view:{}, err:{}", view, source))]
+    WriteView {
+        view: String,
+        source: table_engine::table::Error,
+    },
+
+    #[snafu(display("Failed to update watermark of view, view:{}, err:{}", view, source))]
+    UpdateView {
+        view: String,
+        source: catalog::schema::Error,
+    },
+}
+
+define_result!(Error);
+
+/// Max time window in milliseconds refreshed by a step, the window of a step
+/// may be larger if the period of the view is larger.
+const MAX_REFRESH_WINDOW_MS: i64 = 24 * 3600 * 1000;
+/// Max steps of a view in a refresh, the remaining range is refreshed in the
+/// next round, so a view with a long range doesn't block other views.
+const MAX_REFRESH_STEPS: usize = 24;
+
+/// Scheduler refreshes the materialized views periodically.
+pub struct ViewScheduler {
+    stop_tx: Sender<()>,
+}
+
+impl ViewScheduler {
+    /// Start the scheduler in the `runtime`.
+    pub fn start<C: CatalogManager + 'static, Q: QueryExecutor + 'static>(
+        runtime: &Runtime,
+        instance: InstanceRef<C, Q>,
+        interval: Duration,
+    ) -> Self {
+        let (stop_tx, mut stop_rx) = oneshot::channel();
+        let mut refresher = ViewRefresher {
+            instance,
+            flushed: HashMap::new(),
+        };
+
+        runtime.spawn(async move {
+            // Stop once the sender is notified or dropped.
+            while time::timeout(interval, &mut stop_rx).await.is_err() {
+                refresher.refresh_all().await;
+            }
+
+            info!("View schedule loop exit");
+        });
+
+        Self { stop_tx }
+    }
+
+    pub fn stop(self) {
+        let _ = self.stop_tx.send(());
+    }
+}
+
+struct ViewRefresher<C, Q> {
+    instance: InstanceRef<C, Q>,
+    /// Number of flushes of the source table seen by the last refresh, indexed
+    /// by the full name of the view.
+    flushed: HashMap<String, u64>,
+}
+
+impl<C: CatalogManager + 'static, Q: QueryExecutor + 'static> ViewRefresher<C, Q> {
+    async fn refresh_all(&mut self) {
+        let catalogs = match self.instance.catalog_manager.all_catalogs() {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Failed to get catalogs to refresh views, err:{}", e);
+                return;
+            }
+        };
+
+        for catalog in catalogs {
+            let schemas = match catalog.all_schemas() {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(
+                        "Failed to get schemas to refresh views, catalog:{}, err:{}",
+                        catalog.name(),
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            for schema in schemas {
+                let views = match schema.all_views() {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!(
+                            "Failed to get views to refresh, schema:{}, err:{}",
+                            schema.name(),
+                            e
+                        );
+                        continue;
+                    }
+                };
+
+                for view in views {
+                    if let Err(e) = self.refresh_view(&schema, view).await {
+                        error!("Failed to refresh view, err:{}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn refresh_view(&mut self, schema: &SchemaRef, mut view: ViewInfo) -> Result<()> {
+        let source_table = find_table(schema, &view.source_table)?;
+        let view_table = find_table(schema, &view.view_name)?;
+
+        // Only refresh the view after new segments are flushed.
+        let full_name = format!(
+            "{}.{}.{}",
+            view.catalog_name, view.schema_name, view.view_name
+        );
+        let num_flush = source_table.stats().num_flush;
+        if self.flushed.get(&full_name) == Some(&num_flush) {
+            return Ok(());
+        }
+
+        // The bucket containing now is still open.
+        let period = Period::parse(&view.period).context(InvalidPeriod {
+            view: &view.view_name,
+        })?;
+        let end = match period.truncate(Timestamp::now()) {
+            Some(v) if v > view.watermark => v,
+            _ => {
+                self.flushed.insert(full_name, num_flush);
+                return Ok(());
+            }
+        };
+
+        // Skip the range before the oldest sst of the source table, e.g. the range
+        // since zero of a new view. The view is only refreshed after a flush, so the
+        // rows before the oldest sst are only the rows written late.
+        let mut watermark = view.watermark;
+        let oldest = source_table
+            .sst_files()
+            .iter()
+            .map(|f| f.time_range.inclusive_start())
+            .min()
+            .and_then(|v| period.truncate(v));
+        if let Some(oldest) = oldest {
+            if oldest > watermark {
+                watermark = oldest.min(end);
+            }
+        }
+
+        for _ in 0..MAX_REFRESH_STEPS {
+            if watermark >= end {
+                break;
+            }
+
+            let next_watermark = next_watermark(&period, watermark, end);
+            let time_range = TimeRange::new_unchecked(watermark, next_watermark);
+            view = self
+                .refresh_range(schema, &source_table, &view_table, view, time_range)
+                .await?;
+            watermark = next_watermark;
+        }
+
+        if watermark >= end {
+            if view.watermark < end {
+                // The whole range is skipped as there is no sst in it.
+                self.update_watermark(schema, view, end).await?;
+            }
+            self.flushed.insert(full_name, num_flush);
+        }
+
+        Ok(())
+    }
+
+    /// Refresh the rows of the view in `time_range` and advance the watermark
+    /// to the end of the `time_range`, returns the updated view.
+    async fn refresh_range(
+        &self,
+        schema: &SchemaRef,
+        source_table: &TableRef,
+        view_table: &TableRef,
+        view: ViewInfo,
+        time_range: TimeRange,
+    ) -> Result<ViewInfo> {
+        let source_schema = source_table.schema();
+        let row_group = self
+            .query_view(
+                &view,
+                source_schema.timestamp_name(),
+                time_range,
+                view_table.schema(),
+            )
+            .await?;
+        let num_rows = row_group.num_rows();
+        if num_rows > 0 {
+            view_table
+                .write(WriteRequest { row_group })
+                .await
+                .context(WriteView {
+                    view: &view.view_name,
+                })?;
+        }
+
+        let view = self
+            .update_watermark(schema, view, time_range.exclusive_end())
+            .await?;
+
+        info!(
+            "View refreshed, view:{}, time_range:{:?}, rows:{}",
+            view.view_name, time_range, num_rows
+        );
+
+        Ok(view)
+    }
+
+    async fn update_watermark(
+        &self,
+        schema: &SchemaRef,
+        view: ViewInfo,
+        watermark: Timestamp,
+    ) -> Result<ViewInfo> {
+        let view = ViewInfo { watermark, ..view };
+        schema.update_view(view.clone()).await.context(UpdateView {
+            view: &view.view_name,
+        })?;
+
+        Ok(view)
+    }
+
+    /// Returns the rows of the view aggregated from the rows of the source
+    /// table in `time_range`.
+    async fn query_view(
+        &self,
+        view: &ViewInfo,
+        timestamp_column: &str,
+        time_range: TimeRange,
+        view_schema: Schema,
+    ) -> Result<RowGroup> {
+        let instance = &self.instance;
+        let request_id = RequestId::next_id();
+        let statement =
+            Parser::parse_view_query_in_range(&view.query, timestamp_column, time_range)
+                .map_err(|e| Box::new(e) as _)
+                .context(ParseQuery {
+                    view: &view.view_name,
+                })?;
+
+        let provider = CatalogMetaProvider {
+            manager: &instance.catalog_manager,
+            default_catalog: &view.catalog_name,
+            default_schema: &view.schema_name,
+            function_registry: &*instance.function_registry,
+        };
+        let frontend = Frontend::new(provider);
+        let mut sql_ctx = SqlContext::new(request_id);
+        let plan = frontend
+            .statement_to_plan(&mut sql_ctx, statement)
+            .context(CreatePlan {
+                view: &view.view_name,
+            })?;
+
+        let interpreter_ctx = InterpreterContext::builder(request_id)
+            .default_catalog_and_schema(view.catalog_name.clone(), view.schema_name.clone())
+            .build();
+        let interpreter_factory = Factory::new(
+            instance.query_executor.clone(),
+            instance.catalog_manager.clone(),
+            instance.table_engine.clone(),
+        );
+        let interpreter = interpreter_factory.create(interpreter_ctx, plan);
+        let output = interpreter.execute().await.context(ExecuteQuery {
+            view: &view.view_name,
+        })?;
+
+        let records = match output {
+            Output::Records(v) => v,
+            Output::Stream(stream) => executor::collect(stream).await.context(CollectRecords {
+                view: &view.view_name,
+            })?,
+            Output::AffectedRows(_) => {
+                return UnexpectedOutput {
+                    view: &view.view_name,
+                }
+                .fail()
+            }
+        };
+
+        build_view_rows(view_schema, &records).context(BuildRow {
+            view: &view.view_name,
+        })
+    }
+}
+
+fn find_table(schema: &SchemaRef, table: &str) -> Result<TableRef> {
+    schema
+        .table_by_name(table)
+        .context(FindTable { table })?
+        .context(TableNotFound { table })
+}
+
+/// Returns the watermark after refreshing a step from `watermark`, which is the
+/// start of a bucket no later than `end`.
+fn next_watermark(period: &Period, watermark: Timestamp, end: Timestamp) -> Timestamp {
+    let mut window = MAX_REFRESH_WINDOW_MS;
+    loop {
+        let next = Timestamp::new(watermark.as_i64().saturating_add(window));
+        if next >= end {
+            return end;
+        }
+
+        match period.truncate(next) {
+            Some(v) if v > watermark => return v,
+            // The window is shorter than the bucket.
+            _ => window = window.saturating_mul(2),
+        }
+    }
+}
+
+/// Convert the records of the view query into rows of the view table, columns
+/// are matched by name.
+fn build_view_rows(schema: Schema, records: &[RecordBatch]) -> common_types::row::Result<RowGroup> {
+    let num_rows = records.iter().map(|v| v.num_rows()).sum();
+    let tsid_index = schema.index_of_tsid();
+    let mut builder = RowGroupBuilder::with_capacity(schema.clone(), num_rows);
+    for record_batch in records {
+        let record_schema = record_batch.schema();
+        let column_indexes: Vec<_> = schema
+            .columns()
+            .iter()
+            .map(|column| record_schema.index_of(&column.name))
+            .collect();
+
+        for row_idx in 0..record_batch.num_rows() {
+            let mut row_builder = builder.row_builder();
+            for (i, column_index) in column_indexes.iter().enumerate() {
+                let datum = match column_index {
+                    Some(column_index) => record_batch.column(*column_index).datum(row_idx),
+                    // The tsid is generated by the engine.
+                    None if Some(i) == tsid_index => Datum::empty(&schema.column(i).data_type),
+                    None => schema.column(i).default_datum(),
+                };
+                row_builder = row_builder.append_datum(datum)?;
+            }
+            row_builder.finish()?;
+        }
+    }
+
+    Ok(builder.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::TryFrom, sync::Arc};
+
+    use arrow_deps::arrow::{
+        array::{Float64Array, StringArray, TimestampMillisecondArray},
+        datatypes::{DataType, Field, Schema as ArrowSchema, TimeUnit},
+        record_batch::RecordBatch as ArrowRecordBatch,
+    };
+    use common_types::{
+        column_schema,
+        datum::DatumKind,
+        schema::{self, TSID_COLUMN},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_next_watermark() {
+        let hour_ms = 3600 * 1000;
+        let day_ms = 24 * hour_ms;
+        let end = Timestamp::new(10 * day_ms);
+
+        let period = Period::parse("PT1H").unwrap();
+        let cases = [
+            (0, day_ms),
+            (9 * day_ms + hour_ms, 10 * day_ms),
+            (9 * day_ms + 23 * hour_ms, 10 * day_ms),
+        ];
+        for (watermark, expect) in cases.iter() {
+            let next = next_watermark(&period, Timestamp::new(*watermark), end);
+            assert_eq!(Timestamp::new(*expect), next);
+        }
+
+        // The window is extended to cover a bucket.
+        let period = Period::parse("P1M").unwrap();
+        let end = Timestamp::new(100 * day_ms);
+        let watermark = period.truncate(Timestamp::new(day_ms)).unwrap();
+        let next = next_watermark(&period, watermark, end);
+        let expect = period.truncate(Timestamp::new(40 * day_ms)).unwrap();
+        assert_eq!(expect, next);
+    }
+
+    #[test]
+    fn test_build_view_rows() {
+        let schema = schema::Builder::new()
+            .auto_increment_column_id(true)
+            .enable_tsid_primary_key(true)
+            .add_key_column(
+                column_schema::Builder::new("ts".to_string(), DatumKind::Timestamp)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_key_column(
+                column_schema::Builder::new(TSID_COLUMN.to_string(), DatumKind::UInt64)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_normal_column(
+                column_schema::Builder::new("host".to_string(), DatumKind::String)
+                    .is_tag(true)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_normal_column(
+                column_schema::Builder::new("cpu".to_string(), DatumKind::Double)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // The order of the columns in the records differs from the schema.
+        let arrow_schema = ArrowSchema::new(vec![
+            Field::new("host", DataType::Utf8, true),
+            Field::new(
+                "ts",
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                false,
+            ),
+            Field::new("cpu", DataType::Float64, true),
+        ]);
+        let arrow_record_batch = ArrowRecordBatch::try_new(
+            Arc::new(arrow_schema),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                Arc::new(TimestampMillisecondArray::from(vec![1000, 2000])),
+                Arc::new(Float64Array::from(vec![1.0, 2.0])),
+            ],
+        )
+        .unwrap();
+        let record_batch = RecordBatch::try_from(arrow_record_batch).unwrap();
+
+        let row_group = build_view_rows(schema, &[record_batch]).unwrap();
+        assert_eq!(2, row_group.num_rows());
+        let row = row_group.get_row(1).unwrap();
+        assert_eq!(Datum::Timestamp(Timestamp::new(2000)), row[0]);
+        assert_eq!(Datum::UInt64(0), row[1]);
+        assert_eq!(Datum::String("b".into()), row[2]);
+        assert_eq!(Datum::Double(2.0), row[3]);
+    }
+}
@@ -3,7 +3,8 @@
 //! SQL statement
 
 use sqlparser::ast::{
-    ColumnDef, ObjectName, SqlOption, Statement as SqlStatement, TableConstraint,
+    ColumnDef, Ident, ObjectName, Query, SqlOption, Statement as SqlStatement, TableConstraint,
+    Value,
 };
 
 /// Statement representations
@@ -19,9 +20,13 @@ pub enum Statement {
     Describe(DescribeTable),
     AlterModifySetting(AlterModifySetting),
     AlterAddColumn(AlterAddColumn),
+    AlterDropColumn(AlterDropColumn),
+    AlterRenameColumn(AlterRenameColumn),
     /// SHOW CREATE TABLE
     ShowCreate(ShowCreate),
     Exists(ExistsTable),
+    /// CREATE MATERIALIZED VIEW
+    CreateMaterializedView(CreateMaterializedView),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -41,6 +46,41 @@ pub struct CreateTable {
     pub constraints: Vec<TableConstraint>,
     /// Table options in `WITH`.
     pub options: Vec<SqlOption>,
+    /// Partition definition in `PARTITION BY`.
+    pub partition: Option<Partition>,
+}
+
+/// Partition definition of table
+#[derive(Debug, PartialEq)]
+pub enum Partition {
+    /// `PARTITION BY HASH(columns) PARTITIONS num`
+    Hash {
+        columns: Vec<Ident>,
+        partition_num: u64,
+    },
+    /// `PARTITION BY RANGE(column) (PARTITION name VALUES LESS THAN (v), ...)`
+    Range {
+        column: Ident,
+        partitions: Vec<RangePartitionDef>,
+    },
+    /// `PARTITION BY LIST(column) (PARTITION name VALUES IN (value, ...), ...)`
+    List {
+        column: Ident,
+        partitions: Vec<ListPartitionDef>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RangePartitionDef {
+    pub name: Ident,
+    /// Exclusive upper bound, None means `MAXVALUE`
+    pub less_than: Option<Value>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ListPartitionDef {
+    pub name: Ident,
+    pub values: Vec<Value>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -68,6 +108,19 @@ pub struct AlterAddColumn {
     pub columns: Vec<ColumnDef>,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct AlterDropColumn {
+    pub table_name: ObjectName,
+    pub columns: Vec<Ident>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AlterRenameColumn {
+    pub table_name: ObjectName,
+    pub old_name: Ident,
+    pub new_name: Ident,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct ShowCreate {
     pub obj_type: ShowCreateObject,
@@ -78,3 +131,13 @@ pub struct ShowCreate {
 pub struct ExistsTable {
     pub table_name: ObjectName,
 }
+
+#[derive(Debug, PartialEq)]
+pub struct CreateMaterializedView {
+    /// Create if not exists
+    pub if_not_exists: bool,
+    /// View name
+    pub name: ObjectName,
+    /// Query of the view
+    pub query: Box<Query>,
+}
@@ -14,7 +14,7 @@ use crate::{
     parser::Parser,
     plan::Plan,
     planner::Planner,
-    promql::{ColumnNames, Expr},
+    promql::{self, ColumnNames, EvalParams, Expr, Selector},
     provider::MetaProvider,
 };
 
@@ -33,6 +33,12 @@ pub enum Error {
 
     #[snafu(display("Invalid prom request, err:{}", source))]
     InvalidPromRequest { source: crate::promql::Error },
+
+    #[snafu(display("Invalid promql, query:{}, err:{}", query, source))]
+    InvalidPromQuery {
+        query: String,
+        source: crate::promql::ParseError,
+    },
 }
 
 define_result!(Error);
@@ -86,6 +92,16 @@ impl<P> Frontend<P> {
     ) -> Result<Expr> {
         req.take_expr().try_into().context(InvalidPromRequest)
     }
+
+    /// Parse the PromQL text and returns the Expr
+    pub fn parse_promql_query(
+        &self,
+        _ctx: &mut Context,
+        query: &str,
+        params: &EvalParams,
+    ) -> Result<Expr> {
+        promql::parse(query, params).context(InvalidPromQuery { query })
+    }
 }
 
 impl<P: MetaProvider> Frontend<P> {
@@ -105,4 +121,16 @@ impl<P: MetaProvider> Frontend<P> {
 
         planner.promql_expr_to_plan(expr).context(CreatePlan)
     }
+
+    /// Create the plan to list the series matching the prometheus selector,
+    /// returns the plan and the names of the tag columns.
+    pub fn promql_series_to_plan(
+        &self,
+        ctx: &mut Context,
+        selector: Selector,
+    ) -> Result<(Plan, Vec<String>)> {
+        let planner = Planner::new(&self.provider, ctx.request_id, ctx.read_parallelism);
+
+        planner.promql_series_to_plan(selector).context(CreatePlan)
+    }
 }
@@ -4,10 +4,14 @@
 //!
 //! Some codes are copied from datafusion: <https://github.com/apache/arrow/blob/9d86440946b8b07e03abb94fad2da278affae08f/rust/datafusion/src/sql/parser.rs#L74>
 
+use common_types::time::{TimeRange, Timestamp};
 use log::debug;
 use paste::paste;
 use sqlparser::{
-    ast::{ColumnDef, ColumnOption, ColumnOptionDef, Ident, TableConstraint},
+    ast::{
+        BinaryOperator, ColumnDef, ColumnOption, ColumnOptionDef, Expr, Ident, SetExpr,
+        Statement as SqlStatement, TableConstraint, Value,
+    },
     dialect::{keywords::Keyword, Dialect, MySqlDialect},
     parser::{IsOptional::Mandatory, Parser as SqlParser, ParserError},
     tokenizer::{Token, Tokenizer},
@@ -15,8 +19,9 @@ use sqlparser::{
 use table_engine::ANALYTIC_ENGINE_TYPE;
 
 use crate::ast::{
-    AlterAddColumn, AlterModifySetting, CreateTable, DescribeTable, DropTable, ExistsTable,
-    ShowCreate, ShowCreateObject, Statement,
+    AlterAddColumn, AlterDropColumn, AlterModifySetting, AlterRenameColumn, CreateMaterializedView,
+    CreateTable, DescribeTable, DropTable, ExistsTable, ListPartitionDef, Partition,
+    RangePartitionDef, ShowCreate, ShowCreateObject, Statement,
 };
 
 define_result!(ParserError);
@@ -34,6 +39,14 @@ const COMMENT: &str = "COMMENT";
 const UNSIGN: &str = "UNSIGN";
 const MODIFY: &str = "MODIFY";
 const SETTING: &str = "SETTING";
+const PARTITION: &str = "PARTITION";
+const PARTITIONS: &str = "PARTITIONS";
+const HASH: &str = "HASH";
+const RANGE: &str = "RANGE";
+const LIST: &str = "LIST";
+const LESS: &str = "LESS";
+const THAN: &str = "THAN";
+const MAXVALUE: &str = "MAXVALUE";
 
 macro_rules! is_custom_column {
     ($name: ident) => {
@@ -133,6 +146,45 @@ impl<'a> Parser<'a> {
         Ok(stmts)
     }
 
+    /// Parse the query of a materialized view and restrict it to the rows whose
+    /// `timestamp_column` is in the `time_range`.
+    pub fn parse_view_query_in_range(
+        query: &str,
+        timestamp_column: &str,
+        time_range: TimeRange,
+    ) -> Result<Statement> {
+        let dialect = &MySqlDialect {};
+        let mut parser = Parser::new_with_dialect(query, dialect)?;
+        let mut query = parser.parser.parse_query()?;
+        let select = match &mut query.body {
+            SetExpr::Select(select) => select,
+            body => return parser_err!(format!("Expected select, found: {}", body)),
+        };
+
+        let bound = |op, ts: Timestamp| Expr::BinaryOp {
+            left: Box::new(Expr::Identifier(Ident::new(timestamp_column))),
+            op,
+            right: Box::new(Expr::Value(Value::Number(ts.as_i64().to_string(), false))),
+        };
+        let mut selection = Expr::BinaryOp {
+            left: Box::new(bound(BinaryOperator::GtEq, time_range.inclusive_start())),
+            op: BinaryOperator::And,
+            right: Box::new(bound(BinaryOperator::Lt, time_range.exclusive_end())),
+        };
+        if let Some(origin) = select.selection.take() {
+            selection = Expr::BinaryOp {
+                left: Box::new(Expr::Nested(Box::new(origin))),
+                op: BinaryOperator::And,
+                right: Box::new(selection),
+            };
+        }
+        select.selection = Some(selection);
+
+        Ok(Statement::Standard(Box::new(SqlStatement::Query(
+            Box::new(query),
+        ))))
+    }
+
     // Report unexpected token
     fn expected<T>(&self, expected: &str, found: Token) -> Result<T> {
         parser_err!(format!("Expected {}, found: {}", expected, found))
@@ -211,6 +263,20 @@ impl<'a> Parser<'a> {
             {
                 return self.parse_alter_add_column();
             }
+            // examples:
+            // ALTER TABLE test_table DROP COLUMN col_17
+            // ALTER TABLE test_table DROP COLUMN (col_18, col_19)
+            if let (Keyword::TABLE, Keyword::DROP, Keyword::COLUMN) =
+                (nth1_word.keyword, nth2_word.keyword, nth3_word.keyword)
+            {
+                return self.parse_alter_drop_column();
+            }
+            // example: ALTER TABLE test_table RENAME COLUMN col_17 TO col_20
+            if let (Keyword::TABLE, Keyword::RENAME, Keyword::COLUMN) =
+                (nth1_word.keyword, nth2_word.keyword, nth3_word.keyword)
+            {
+                return self.parse_alter_rename_column();
+            }
         }
         Ok(Statement::Standard(Box::new(self.parser.parse_alter()?)))
     }
@@ -257,6 +323,37 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    fn parse_alter_drop_column(&mut self) -> Result<Statement> {
+        self.parser.expect_keyword(Keyword::TABLE)?;
+        let table_name = self.parser.parse_object_name()?;
+        self.parser
+            .expect_keywords(&[Keyword::DROP, Keyword::COLUMN])?;
+        let columns = if self.parser.peek_token() == Token::LParen {
+            self.parser.parse_parenthesized_column_list(Mandatory)?
+        } else {
+            vec![self.parser.parse_identifier()?]
+        };
+        Ok(Statement::AlterDropColumn(AlterDropColumn {
+            table_name,
+            columns,
+        }))
+    }
+
+    fn parse_alter_rename_column(&mut self) -> Result<Statement> {
+        self.parser.expect_keyword(Keyword::TABLE)?;
+        let table_name = self.parser.parse_object_name()?;
+        self.parser
+            .expect_keywords(&[Keyword::RENAME, Keyword::COLUMN])?;
+        let old_name = self.parser.parse_identifier()?;
+        self.parser.expect_keyword(Keyword::TO)?;
+        let new_name = self.parser.parse_identifier()?;
+        Ok(Statement::AlterRenameColumn(AlterRenameColumn {
+            table_name,
+            old_name,
+            new_name,
+        }))
+    }
+
     fn parse_alter_modify_setting(&mut self) -> Result<Statement> {
         self.parser.expect_keyword(Keyword::TABLE)?;
         let table_name = self.parser.parse_object_name()?;
@@ -281,6 +378,13 @@ impl<'a> Parser<'a> {
 
     // Parse a SQL CREATE statement
     pub fn parse_create(&mut self) -> Result<Statement> {
+        if self
+            .parser
+            .parse_keywords(&[Keyword::MATERIALIZED, Keyword::VIEW])
+        {
+            return self.parse_create_materialized_view();
+        }
+
         self.parser.expect_keyword(Keyword::TABLE)?;
         let if_not_exists =
             self.parser
@@ -289,6 +393,7 @@ impl<'a> Parser<'a> {
         let (columns, constraints) = self.parse_columns()?;
         let engine = self.parse_table_engine()?;
         let options = self.parser.parse_options(Keyword::WITH)?;
+        let partition = self.parse_partition()?;
 
         Ok(Statement::Create(CreateTable {
             if_not_exists,
@@ -297,6 +402,23 @@ impl<'a> Parser<'a> {
             engine,
             constraints,
             options,
+            partition,
+        }))
+    }
+
+    // example: CREATE MATERIALIZED VIEW v AS SELECT time_bucket(t, 'PT5M'), ...
+    fn parse_create_materialized_view(&mut self) -> Result<Statement> {
+        let if_not_exists =
+            self.parser
+                .parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+        let name = self.parser.parse_object_name()?;
+        self.parser.expect_keyword(Keyword::AS)?;
+        let query = self.parser.parse_query()?;
+
+        Ok(Statement::CreateMaterializedView(CreateMaterializedView {
+            if_not_exists,
+            name,
+            query: Box::new(query),
         }))
     }
 
@@ -368,6 +490,103 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Parse the optional `PARTITION BY` clause
+    fn parse_partition(&mut self) -> Result<Option<Partition>> {
+        if !self.consume_token(PARTITION) {
+            return Ok(None);
+        }
+        self.parser.expect_keyword(Keyword::BY)?;
+
+        let partition = if self.consume_token(HASH) {
+            let columns = self.parser.parse_parenthesized_column_list(Mandatory)?;
+            self.expect_custom_keyword(PARTITIONS)?;
+            let partition_num = self.parser.parse_literal_uint()?;
+            Partition::Hash {
+                columns,
+                partition_num,
+            }
+        } else if self.consume_token(RANGE) {
+            let column = self.parse_partition_column()?;
+            let partitions = self.parse_partition_defs(Self::parse_range_partition_def)?;
+            Partition::Range { column, partitions }
+        } else if self.consume_token(LIST) {
+            let column = self.parse_partition_column()?;
+            let partitions = self.parse_partition_defs(Self::parse_list_partition_def)?;
+            Partition::List { column, partitions }
+        } else {
+            return self.expected("HASH, RANGE or LIST", self.parser.peek_token());
+        };
+
+        Ok(Some(partition))
+    }
+
+    /// Parse the single partition column in parentheses
+    fn parse_partition_column(&mut self) -> Result<Ident> {
+        let mut columns = self.parser.parse_parenthesized_column_list(Mandatory)?;
+        if columns.len() != 1 {
+            return parser_err!(format!(
+                "Expected one partition column, found: {}",
+                columns.len()
+            ));
+        }
+
+        Ok(columns.remove(0))
+    }
+
+    /// Parse the partition definitions in parentheses
+    fn parse_partition_defs<T>(
+        &mut self,
+        parse_def: impl Fn(&mut Self) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        self.parser.expect_token(&Token::LParen)?;
+        let mut defs = Vec::new();
+        loop {
+            self.expect_custom_keyword(PARTITION)?;
+            defs.push(parse_def(self)?);
+            if !self.parser.consume_token(&Token::Comma) {
+                break;
+            }
+        }
+        self.parser.expect_token(&Token::RParen)?;
+
+        Ok(defs)
+    }
+
+    // name VALUES LESS THAN (value | MAXVALUE)
+    fn parse_range_partition_def(&mut self) -> Result<RangePartitionDef> {
+        let name = self.parser.parse_identifier()?;
+        self.parser.expect_keyword(Keyword::VALUES)?;
+        self.expect_custom_keyword(LESS)?;
+        self.expect_custom_keyword(THAN)?;
+
+        let has_paren = self.parser.consume_token(&Token::LParen);
+        let less_than = if self.consume_token(MAXVALUE) {
+            None
+        } else {
+            Some(self.parser.parse_value()?)
+        };
+        if has_paren {
+            self.parser.expect_token(&Token::RParen)?;
+        }
+
+        Ok(RangePartitionDef { name, less_than })
+    }
+
+    // name VALUES IN (value, ...)
+    fn parse_list_partition_def(&mut self) -> Result<ListPartitionDef> {
+        let name = self.parser.parse_identifier()?;
+        self.parser.expect_keyword(Keyword::VALUES)?;
+        self.parser.expect_keyword(Keyword::IN)?;
+        self.parser.expect_token(&Token::LParen)?;
+        let mut values = vec![self.parser.parse_value()?];
+        while self.parser.consume_token(&Token::Comma) {
+            values.push(self.parser.parse_value()?);
+        }
+        self.parser.expect_token(&Token::RParen)?;
+
+        Ok(ListPartitionDef { name, values })
+    }
+
     // Copy from sqlparser
     fn parse_column_def(&mut self) -> Result<ColumnDef> {
         let name = self.parser.parse_identifier()?;
@@ -487,6 +706,14 @@ impl<'a> Parser<'a> {
             false
         }
     }
+
+    fn expect_custom_keyword(&mut self, expected: &str) -> Result<()> {
+        if self.consume_token(expected) {
+            Ok(())
+        } else {
+            self.expected(expected, self.parser.peek_token())
+        }
+    }
 }
 
 #[cfg(test)]
@@ -569,6 +796,7 @@ mod tests {
             engine: table_engine::ANALYTIC_ENGINE_TYPE.to_string(),
             constraints: vec![],
             options: vec![],
+            partition: None,
         });
         expect_parse_ok(sql, expected).unwrap();
 
@@ -585,6 +813,7 @@ mod tests {
             engine: "XX".to_string(),
             constraints: vec![],
             options: vec![],
+            partition: None,
         });
         expect_parse_ok(sql, expected).unwrap();
 
@@ -596,6 +825,76 @@ mod tests {
         );
     }
 
+    fn parse_partition(sql: &str) -> Option<Partition> {
+        let mut statements = Parser::parse_sql(sql).unwrap();
+        assert_eq!(1, statements.len());
+        match statements.remove(0) {
+            Statement::Create(create) => create.partition,
+            other => panic!("Unexpected statement, stmt:{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_create_table_with_partition() {
+        let sql = "CREATE TABLE t(c1 string TAG, c2 string TAG, ts timestamp NOT NULL, TIMESTAMP KEY(ts)) \
+            ENGINE=Analytic WITH (enable_ttl='false') PARTITION BY HASH(c1, c2) PARTITIONS 4";
+        let expected = Partition::Hash {
+            columns: vec![Ident::new("c1"), Ident::new("c2")],
+            partition_num: 4,
+        };
+        assert_eq!(Some(expected), parse_partition(sql));
+
+        let sql =
+            "CREATE TABLE t(ts timestamp NOT NULL, TIMESTAMP KEY(ts)) PARTITION BY RANGE(ts) \
+            (PARTITION p0 VALUES LESS THAN (1000), PARTITION p1 VALUES LESS THAN MAXVALUE)";
+        let expected = Partition::Range {
+            column: Ident::new("ts"),
+            partitions: vec![
+                RangePartitionDef {
+                    name: Ident::new("p0"),
+                    less_than: Some(Value::Number("1000".to_string(), false)),
+                },
+                RangePartitionDef {
+                    name: Ident::new("p1"),
+                    less_than: None,
+                },
+            ],
+        };
+        assert_eq!(Some(expected), parse_partition(sql));
+
+        let sql = "CREATE TABLE t(c1 string TAG, ts timestamp NOT NULL, TIMESTAMP KEY(ts)) \
+            PARTITION BY LIST(c1) (PARTITION east VALUES IN ('hz', 'sh'), PARTITION west VALUES IN ('cd'))";
+        let expected = Partition::List {
+            column: Ident::new("c1"),
+            partitions: vec![
+                ListPartitionDef {
+                    name: Ident::new("east"),
+                    values: vec![
+                        Value::SingleQuotedString("hz".to_string()),
+                        Value::SingleQuotedString("sh".to_string()),
+                    ],
+                },
+                ListPartitionDef {
+                    name: Ident::new("west"),
+                    values: vec![Value::SingleQuotedString("cd".to_string())],
+                },
+            ],
+        };
+        assert_eq!(Some(expected), parse_partition(sql));
+
+        // Error cases
+        let sql = "CREATE TABLE t(c1 timestamp) PARTITION BY KEY(c1)";
+        expect_parse_error(
+            sql,
+            "sql parser error: Expected HASH, RANGE or LIST, found: KEY",
+        );
+        let sql = "CREATE TABLE t(c1 string, c2 string) PARTITION BY LIST(c1, c2) (PARTITION p0 VALUES IN ('a'))";
+        expect_parse_error(
+            sql,
+            "sql parser error: Expected one partition column, found: 2",
+        );
+    }
+
     #[test]
     fn test_unsign_tag_column() {
         let sql = "CREATE TABLE IF NOT EXISTS t(c1 string tag, c2 float, c3 bigint unsign)";
@@ -758,6 +1057,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_alter_table_drop_column() {
+        {
+            let sql = "ALTER TABLE t DROP COLUMN (c1, c2)";
+            let expected = Statement::AlterDropColumn(AlterDropColumn {
+                table_name: make_object_name("t"),
+                columns: vec![Ident::new("c1"), Ident::new("c2")],
+            });
+            expect_parse_ok(sql, expected).unwrap();
+        }
+
+        {
+            let sql = "ALTER TABLE t DROP COLUMN c1";
+            let expected = Statement::AlterDropColumn(AlterDropColumn {
+                table_name: make_object_name("t"),
+                columns: vec![Ident::new("c1")],
+            });
+            expect_parse_ok(sql, expected).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_alter_table_rename_column() {
+        let sql = "ALTER TABLE t RENAME COLUMN c1 TO c2";
+        let expected = Statement::AlterRenameColumn(AlterRenameColumn {
+            table_name: make_object_name("t"),
+            old_name: Ident::new("c1"),
+            new_name: Ident::new("c2"),
+        });
+        expect_parse_ok(sql, expected).unwrap();
+
+        let sql = "ALTER TABLE t RENAME COLUMN c1 c2";
+        assert!(Parser::parse_sql(sql).is_err());
+    }
+
     #[test]
     fn test_drop_table() {
         let sql = "drop table test_ttl";
@@ -811,4 +1145,49 @@ mod tests {
             expect_parse_ok(sql, expected).unwrap()
         }
     }
+    #[test]
+    fn test_create_materialized_view() {
+        let sql =
+            "CREATE MATERIALIZED VIEW IF NOT EXISTS v AS SELECT time_bucket(t, 'PT5M') AS t, \
+                   host, avg(cpu) AS cpu FROM test GROUP BY time_bucket(t, 'PT5M'), host";
+        let statements = Parser::parse_sql(sql).unwrap();
+        assert_eq!(statements.len(), 1);
+        match &statements[0] {
+            Statement::CreateMaterializedView(CreateMaterializedView {
+                if_not_exists,
+                name,
+                query,
+            }) => {
+                assert!(if_not_exists);
+                assert_eq!(name.to_string(), "v");
+                assert_eq!(
+                    query.to_string(),
+                    "SELECT time_bucket(t, 'PT5M') AS t, host, avg(cpu) AS cpu FROM test \
+                     GROUP BY time_bucket(t, 'PT5M'), host"
+                );
+            }
+            _ => panic!("failed"),
+        }
+
+        expect_parse_error(
+            "CREATE MATERIALIZED VIEW v SELECT * FROM test",
+            "Expected AS, found: SELECT",
+        );
+    }
+    #[test]
+    fn test_parse_view_query_in_range() {
+        let query = "SELECT time_bucket(t, 'PT5M') AS t, host, avg(cpu) AS cpu FROM test \
+                     WHERE host = 'a' GROUP BY time_bucket(t, 'PT5M'), host";
+        let time_range = TimeRange::new(Timestamp::new(1000), Timestamp::new(2000)).unwrap();
+        let statement = Parser::parse_view_query_in_range(query, "t", time_range).unwrap();
+        match statement {
+            Statement::Standard(statement) => assert_eq!(
+                statement.to_string(),
+                "SELECT time_bucket(t, 'PT5M') AS t, host, avg(cpu) AS cpu FROM test \
+                 WHERE (host = 'a') AND t >= 1000 AND t < 2000 \
+                 GROUP BY time_bucket(t, 'PT5M'), host"
+            ),
+            _ => panic!("failed"),
+        }
+    }
 }
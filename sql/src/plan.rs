@@ -13,7 +13,10 @@ use arrow_deps::datafusion::logical_plan::LogicalPlan as DataFusionLogicalPlan;
 use common_types::{column_schema::ColumnSchema, row::RowGroup, schema::Schema};
 use common_util::define_result;
 use snafu::Snafu;
-use table_engine::table::TableRef;
+use table_engine::{
+    partition::PartitionInfo,
+    table::{DeleteRequest, TableRef},
+};
 
 use crate::{ast::ShowCreateObject, container::TableContainer};
 
@@ -42,6 +45,8 @@ pub enum Plan {
     Query(QueryPlan),
     // TODO(yingwen): Other sql command
     Insert(InsertPlan),
+    /// Delete plan
+    Delete(DeletePlan),
     /// Create table plan
     Create(CreateTablePlan),
     /// Drop table plan
@@ -54,6 +59,8 @@ pub enum Plan {
     ShowCreate(ShowCreatePlan),
     /// Exists table
     Exists(ExistsTablePlan),
+    /// Create materialized view plan
+    CreateMaterializedView(CreateMaterializedViewPlan),
 }
 
 pub struct QueryPlan {
@@ -83,6 +90,8 @@ pub struct CreateTablePlan {
     pub table_schema: Schema,
     /// Table options
     pub options: HashMap<String, String>,
+    /// Partition info, None if the table is not partitioned
+    pub partition_info: Option<PartitionInfo>,
 }
 
 impl Debug for CreateTablePlan {
@@ -100,10 +109,28 @@ impl Debug for CreateTablePlan {
                     .map(|(k, v)| (k.clone(), v.clone()))
                     .collect::<BTreeMap<String, String>>(),
             )
+            .field("partition_info", &self.partition_info)
             .finish()
     }
 }
 
+/// Create materialized view plan
+#[derive(Debug)]
+pub struct CreateMaterializedViewPlan {
+    /// Create view if not exists
+    pub if_not_exists: bool,
+    /// View name, also the name of the table storing the rows of the view
+    pub view: String,
+    /// Sql of the query of the view
+    pub query: String,
+    /// Name of the table the view selects from
+    pub source_table: String,
+    /// Period of the time bucket in the query
+    pub period: String,
+    /// Schema of the table storing the rows of the view
+    pub table_schema: Schema,
+}
+
 #[derive(Debug)]
 pub struct DropTablePlan {
     /// Engine
@@ -123,6 +150,15 @@ pub struct InsertPlan {
     pub rows: RowGroup,
 }
 
+/// Delete logical plan
+#[derive(Debug)]
+pub struct DeletePlan {
+    /// The table to delete from
+    pub table: TableRef,
+    /// Time range and tag filters of the rows to delete
+    pub request: DeleteRequest,
+}
+
 #[derive(Debug)]
 pub struct DescribeTablePlan {
     /// The table to describe
@@ -133,6 +169,15 @@ pub struct DescribeTablePlan {
 pub enum AlterTableOperation {
     /// Add a new column, the column id will be ignored.
     AddColumn(Vec<ColumnSchema>),
+    /// Drop the columns with the given names, the dropped columns are hidden
+    /// from reads at once and removed from the ssts by compaction.
+    DropColumn(Vec<String>),
+    /// Rename the column, only the schema is changed as the data of the
+    /// column is found by its id.
+    RenameColumn {
+        old_name: String,
+        new_name: String,
+    },
     ModifySetting(HashMap<String, String>),
 }
 
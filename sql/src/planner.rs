@@ -3,41 +3,58 @@
 //! Planner converts a SQL AST into logical plans
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     convert::TryFrom,
     mem,
     sync::Arc,
 };
 
-use arrow_deps::datafusion::{error::DataFusionError, sql::planner::SqlToRel};
+use arrow_deps::datafusion::{
+    error::DataFusionError,
+    logical_plan::{
+        plan::{Filter, Projection},
+        Expr as DfExpr, LogicalPlan, TableScan,
+    },
+    scalar::ScalarValue,
+    sql::planner::SqlToRel,
+};
 use common_types::{
     column_schema::{self, ColumnSchema},
     datum::{Datum, DatumKind},
     request_id::RequestId,
     row::{RowGroup, RowGroupBuilder},
     schema::{self, Schema, TSID_COLUMN},
+    time::{TimeRange, Timestamp},
 };
+use df_operator::udfs::time_bucket::Period;
 use log::debug;
 use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
 use sqlparser::ast::{
-    ColumnDef, ColumnOption, Expr, ObjectName, Query, SetExpr, SqlOption,
-    Statement as SqlStatement, TableConstraint, Value, Values,
+    BinaryOperator, ColumnDef, ColumnOption, Expr, ObjectName, Query, SetExpr, SqlOption,
+    Statement as SqlStatement, TableConstraint, UnaryOperator, Value, Values,
+};
+use table_engine::{
+    partition::{ListPartition, PartitionInfo, PartitionMethod, PartitionNum, RangePartition},
+    provider::TableProviderAdapter,
+    table::{DeleteRequest, TableRef, TagFilter},
 };
-use table_engine::table::TableRef;
 
 use crate::{
     ast::{
-        AlterAddColumn, AlterModifySetting, CreateTable, DescribeTable, DropTable, ExistsTable,
+        AlterAddColumn, AlterDropColumn, AlterModifySetting, AlterRenameColumn,
+        CreateMaterializedView, CreateTable, DescribeTable, DropTable, ExistsTable, Partition,
         ShowCreate, Statement,
     },
     container::TableReference,
     parser,
     plan::{
-        AlterTableOperation, AlterTablePlan, CreateTablePlan, DescribeTablePlan, DropTablePlan,
-        ExistsTablePlan, InsertPlan, Plan, QueryPlan, ShowCreatePlan,
+        AlterTableOperation, AlterTablePlan, CreateMaterializedViewPlan, CreateTablePlan,
+        DeletePlan, DescribeTablePlan, DropTablePlan, ExistsTablePlan, InsertPlan, Plan, QueryPlan,
+        ShowCreatePlan,
     },
-    promql::{ColumnNames, Expr as PromExpr},
+    promql::{ColumnNames, Expr as PromExpr, Selector},
     provider::{ContextProviderAdapter, MetaProvider},
+    rollup::{RollupRewriter, TIME_BUCKET_FUNCTION},
 };
 
 // We do not carry backtrace in sql error because it is mainly used in server
@@ -92,6 +109,23 @@ pub enum Error {
     #[snafu(display("Unsupported SQL data type, err:{}", source))]
     UnsupportedDataType { source: common_types::datum::Error },
 
+    #[snafu(display(
+        "Default value of column is not a constant, column_name:{}, expr:{}",
+        column_name,
+        expr
+    ))]
+    DefaultValueNotConstant { column_name: String, expr: Expr },
+
+    #[snafu(display(
+        "Failed to convert default value of column, column_name:{}, err:{}",
+        column_name,
+        source
+    ))]
+    ConvertDefaultValue {
+        column_name: String,
+        source: common_types::datum::Error,
+    },
+
     #[snafu(display("Invalid column schema, column_name:{}, err:{}", column_name, source))]
     InvalidColumnSchema {
         column_name: String,
@@ -145,6 +179,69 @@ pub enum Error {
 
     #[snafu(display("Failed to build plan from promql, error:{}", source))]
     BuildPromPlanError { source: crate::promql::Error },
+
+    #[snafu(display("Delete stmt must contain where clause, table:{}", table))]
+    DeleteMissingWhere { table: String },
+
+    #[snafu(display("Unsupported expr in delete stmt, expr:{}", expr))]
+    UnsupportedDeleteExpr { expr: String },
+
+    #[snafu(display("Unknown delete column, name:{}", name))]
+    UnknownDeleteColumn { name: String },
+
+    #[snafu(display(
+        "Only tag and timestamp columns are allowed in delete stmt, column:{}",
+        name
+    ))]
+    InvalidDeleteColumn { name: String },
+
+    #[snafu(display("Duplicate tag column in delete stmt, column:{}", name))]
+    DeleteDuplicateTag { name: String },
+
+    #[snafu(display("Delete Failed to convert value, err:{}", source))]
+    DeleteConvertValue { source: common_types::datum::Error },
+
+    #[snafu(display("Invalid time range in delete stmt, start:{:?}, end:{:?}", start, end))]
+    InvalidDeleteTimeRange { start: Timestamp, end: Timestamp },
+
+    #[snafu(display("Invalid partition num, num:{}", num))]
+    InvalidPartitionNum { num: u64 },
+
+    #[snafu(display("Table must contain at least one partition"))]
+    EmptyPartitions,
+
+    #[snafu(display("Partition column not found, name:{}", name))]
+    PartitionColumnNotFound { name: String },
+
+    #[snafu(display("Hash and list partition column must be a tag column, name:{}", name))]
+    PartitionColumnNotTag { name: String },
+
+    #[snafu(display("Range partition column must be the timestamp column, name:{}", name))]
+    RangePartitionColumnNotTimestamp { name: String },
+
+    #[snafu(display("Duplicate partition column, name:{}", name))]
+    DuplicatePartitionColumn { name: String },
+
+    #[snafu(display("Duplicate partition name, name:{}", name))]
+    DuplicatePartitionName { name: String },
+
+    #[snafu(display("Partition Failed to convert value, err:{}", source))]
+    PartitionConvertValue { source: common_types::datum::Error },
+
+    #[snafu(display(
+        "Upper bounds of range partitions must be strictly increasing, partition:{}",
+        name
+    ))]
+    RangePartitionNotIncreasing { name: String },
+
+    #[snafu(display("Only the last range partition can be MAXVALUE, partition:{}", name))]
+    RangePartitionMaxValueNotLast { name: String },
+
+    #[snafu(display("Duplicate value in list partitions, value:{:?}", value))]
+    DuplicateListPartitionValue { value: Datum },
+
+    #[snafu(display("Invalid materialized view, view:{}, msg:{}", view, msg))]
+    InvalidMaterializedView { view: String, msg: String },
 }
 
 define_result!(Error);
@@ -187,8 +284,11 @@ impl<'a, P: MetaProvider> Planner<'a, P> {
             Statement::Describe(s) => planner.describe_table_to_plan(s),
             Statement::AlterModifySetting(s) => planner.alter_modify_setting_to_plan(s),
             Statement::AlterAddColumn(s) => planner.alter_add_column_to_plan(s),
+            Statement::AlterDropColumn(s) => planner.alter_drop_column_to_plan(s),
+            Statement::AlterRenameColumn(s) => planner.alter_rename_column_to_plan(s),
             Statement::ShowCreate(s) => planner.show_create_to_plan(s),
             Statement::Exists(s) => planner.exists_table_to_plan(s),
+            Statement::CreateMaterializedView(s) => planner.create_materialized_view_to_plan(s),
         }
     }
 
@@ -204,6 +304,15 @@ impl<'a, P: MetaProvider> Planner<'a, P> {
         expr.to_plan(planner.meta_provider, self.read_parallelism)
             .context(BuildPromPlanError)
     }
+
+    /// Create the plan to list the series matching the prometheus selector,
+    /// returns the plan and the names of the tag columns.
+    pub fn promql_series_to_plan(&self, selector: Selector) -> Result<(Plan, Vec<String>)> {
+        let adapter =
+            ContextProviderAdapter::new(self.provider, self.request_id, self.read_parallelism);
+
+        selector.to_series_plan(adapter).context(BuildPromPlanError)
+    }
 }
 
 /// A planner wraps the datafusion's logical planner, and delegate sql like
@@ -224,6 +333,10 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
                 self.sql_statement_to_datafusion_plan(sql_stmt)
             }
             SqlStatement::Insert { .. } => self.insert_to_plan(sql_stmt),
+            SqlStatement::Delete {
+                table_name,
+                selection,
+            } => self.delete_to_plan(table_name, selection),
             _ => UnsupportedStatement.fail(),
         }
     }
@@ -234,6 +347,7 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
         let df_plan = df_planner
             .sql_statement_to_plan(&sql_stmt)
             .context(DataFusionPlan)?;
+        let df_plan = RollupRewriter::new(&self.meta_provider).rewrite(df_plan);
 
         debug!("Sql statement to datafusion plan, df_plan:\n{:#?}", df_plan);
 
@@ -360,12 +474,18 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
 
         let options = parse_options(stmt.options)?;
 
+        let partition_info = stmt
+            .partition
+            .map(|partition| parse_partition(partition, &table_schema))
+            .transpose()?;
+
         let plan = CreateTablePlan {
             engine: stmt.engine,
             if_not_exists: stmt.if_not_exists,
             table,
             table_schema,
             options,
+            partition_info,
         };
 
         debug!("Create table to plan, plan:{:?}", plan);
@@ -438,7 +558,9 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
                         }
                         None => {
                             // This column in schema is not in insert stmt
-                            if column.is_nullable {
+                            if column.default_value.is_some() {
+                                column_index_in_insert.push(InsertMode::Default);
+                            } else if column.is_nullable {
                                 column_index_in_insert.push(InsertMode::Null);
                             } else {
                                 // Column is not null and input does not contains that column
@@ -461,6 +583,20 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
         }
     }
 
+    fn delete_to_plan(&self, table_name: ObjectName, selection: Option<Expr>) -> Result<Plan> {
+        let table = self.find_table(table_name)?;
+        let selection = selection.context(DeleteMissingWhere {
+            table: table.name(),
+        })?;
+
+        let schema = table.schema();
+        let mut parser = DeleteFilterParser::new(&schema);
+        parser.parse(selection)?;
+        let request = parser.build()?;
+
+        Ok(Plan::Delete(DeletePlan { table, request }))
+    }
+
     fn alter_modify_setting_to_plan(&self, stmt: AlterModifySetting) -> Result<Plan> {
         let table = self.find_table(stmt.table_name)?;
         let plan = AlterTablePlan {
@@ -479,6 +615,64 @@ impl<'a, P: MetaProvider> PlannerDelegate<'a, P> {
         Ok(Plan::AlterTable(plan))
     }
 
+    fn alter_drop_column_to_plan(&self, stmt: AlterDropColumn) -> Result<Plan> {
+        let table = self.find_table(stmt.table_name)?;
+        let plan = AlterTablePlan {
+            table,
+            operations: AlterTableOperation::DropColumn(
+                stmt.columns.into_iter().map(|c| c.value).collect(),
+            ),
+        };
+        Ok(Plan::AlterTable(plan))
+    }
+
+    fn alter_rename_column_to_plan(&self, stmt: AlterRenameColumn) -> Result<Plan> {
+        let table = self.find_table(stmt.table_name)?;
+        let plan = AlterTablePlan {
+            table,
+            operations: AlterTableOperation::RenameColumn {
+                old_name: stmt.old_name.value,
+                new_name: stmt.new_name.value,
+            },
+        };
+        Ok(Plan::AlterTable(plan))
+    }
+
+    fn create_materialized_view_to_plan(self, stmt: CreateMaterializedView) -> Result<Plan> {
+        ensure!(!stmt.name.0.is_empty(), CreateTableNameEmpty);
+
+        debug!("Create materialized view to plan, stmt:{:?}", stmt);
+
+        let view_ref = TableReference::try_from(&stmt.name).context(InvalidCreateTableName)?;
+        let view = view_ref.table().to_string();
+
+        let df_planner = SqlToRel::new(&self.meta_provider);
+        let df_plan = df_planner
+            .sql_statement_to_plan(&SqlStatement::Query(stmt.query.clone()))
+            .context(DataFusionPlan)?;
+        let (source_table, period, table_schema) = analyze_view_query(&view, &df_plan)?;
+        ensure!(
+            source_table != view,
+            InvalidMaterializedView {
+                view: &view,
+                msg: "view can't select from itself",
+            }
+        );
+
+        let plan = CreateMaterializedViewPlan {
+            if_not_exists: stmt.if_not_exists,
+            view,
+            query: stmt.query.to_string(),
+            source_table,
+            period,
+            table_schema,
+        };
+
+        debug!("Create materialized view to plan, plan:{:?}", plan);
+
+        Ok(Plan::CreateMaterializedView(plan))
+    }
+
     fn exists_table_to_plan(&self, stmt: ExistsTable) -> Result<Plan> {
         let table = self.find_table(stmt.table_name);
         match table {
@@ -514,6 +708,8 @@ enum InsertMode {
     Direct(usize),
     // No value provided, insert a null.
     Null,
+    // No value provided, insert the default value of the column.
+    Default,
     // Auto generated column, just temporary fill by default value, the real value will
     // be filled by interpreter.
     Auto,
@@ -566,6 +762,12 @@ fn build_row_group(
                             row_builder =
                                 row_builder.append_datum(Datum::Null).context(BuildRow)?;
                         }
+                        InsertMode::Default => {
+                            // This column has a default value
+                            row_builder = row_builder
+                                .append_datum(column_schema.default_datum())
+                                .context(BuildRow)?;
+                        }
                         InsertMode::Auto => {
                             // This is an auto generated column, fill by default value.
                             let kind = &column_schema.data_type;
@@ -587,6 +789,178 @@ fn build_row_group(
     }
 }
 
+/// Parser converts the where clause of delete stmt into [DeleteRequest].
+///
+/// Only the conjunction of `tag = value`, `tag IN (values)` and comparisons
+/// (including BETWEEN) on the timestamp column is supported now.
+struct DeleteFilterParser<'a> {
+    schema: &'a Schema,
+    inclusive_start: Timestamp,
+    exclusive_end: Timestamp,
+    tag_filters: Vec<TagFilter>,
+}
+
+impl<'a> DeleteFilterParser<'a> {
+    fn new(schema: &'a Schema) -> Self {
+        let time_range = TimeRange::min_to_max();
+        Self {
+            schema,
+            inclusive_start: time_range.inclusive_start(),
+            exclusive_end: time_range.exclusive_end(),
+            tag_filters: Vec::new(),
+        }
+    }
+
+    fn parse(&mut self, expr: Expr) -> Result<()> {
+        match expr {
+            Expr::Nested(expr) => self.parse(*expr),
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::And,
+                right,
+            } => {
+                self.parse(*left)?;
+                self.parse(*right)
+            }
+            expr => self.parse_predicate(expr),
+        }
+    }
+
+    fn parse_predicate(&mut self, expr: Expr) -> Result<()> {
+        let expr_desc = expr.to_string();
+        match expr {
+            Expr::BinaryOp { left, op, right } => {
+                let (name, kind, is_timestamp) = self.column_of_expr(&left, &expr_desc)?;
+                let value = datum_of_expr(&kind, *right, &expr_desc)?;
+                if is_timestamp {
+                    let ts = timestamp_of_datum(value);
+                    match op {
+                        BinaryOperator::Eq => {
+                            self.update_start(ts);
+                            self.update_end(next_timestamp(ts));
+                        }
+                        BinaryOperator::Gt => self.update_start(next_timestamp(ts)),
+                        BinaryOperator::GtEq => self.update_start(ts),
+                        BinaryOperator::Lt => self.update_end(ts),
+                        BinaryOperator::LtEq => self.update_end(next_timestamp(ts)),
+                        _ => return UnsupportedDeleteExpr { expr: expr_desc }.fail(),
+                    }
+                    Ok(())
+                } else {
+                    ensure!(
+                        op == BinaryOperator::Eq,
+                        UnsupportedDeleteExpr { expr: expr_desc }
+                    );
+                    self.add_tag_filter(name, vec![value])
+                }
+            }
+            Expr::InList {
+                expr,
+                list,
+                negated: false,
+            } => {
+                let (name, kind, is_timestamp) = self.column_of_expr(&expr, &expr_desc)?;
+                ensure!(!is_timestamp, UnsupportedDeleteExpr { expr: expr_desc });
+
+                let values = list
+                    .into_iter()
+                    .map(|value_expr| datum_of_expr(&kind, value_expr, &expr_desc))
+                    .collect::<Result<Vec<_>>>()?;
+                self.add_tag_filter(name, values)
+            }
+            Expr::Between {
+                expr,
+                negated: false,
+                low,
+                high,
+            } => {
+                let (_, kind, is_timestamp) = self.column_of_expr(&expr, &expr_desc)?;
+                ensure!(is_timestamp, UnsupportedDeleteExpr { expr: expr_desc });
+
+                let low = timestamp_of_datum(datum_of_expr(&kind, *low, &expr_desc)?);
+                let high = timestamp_of_datum(datum_of_expr(&kind, *high, &expr_desc)?);
+                self.update_start(low);
+                self.update_end(next_timestamp(high));
+                Ok(())
+            }
+            _ => UnsupportedDeleteExpr { expr: expr_desc }.fail(),
+        }
+    }
+
+    /// Returns the (name, data type, is timestamp) of the column referenced by
+    /// `expr`.
+    fn column_of_expr(&self, expr: &Expr, expr_desc: &str) -> Result<(String, DatumKind, bool)> {
+        let name = match expr {
+            Expr::Identifier(ident) => &ident.value,
+            _ => return UnsupportedDeleteExpr { expr: expr_desc }.fail(),
+        };
+        let column = self
+            .schema
+            .column_with_name(name)
+            .context(UnknownDeleteColumn { name })?;
+        let is_timestamp = self.schema.timestamp_name() == column.name;
+        ensure!(is_timestamp || column.is_tag, InvalidDeleteColumn { name });
+
+        Ok((column.name.clone(), column.data_type, is_timestamp))
+    }
+
+    fn add_tag_filter(&mut self, column: String, values: Vec<Datum>) -> Result<()> {
+        ensure!(
+            self.tag_filters
+                .iter()
+                .all(|filter| filter.column != column),
+            DeleteDuplicateTag { name: column }
+        );
+        self.tag_filters.push(TagFilter { column, values });
+
+        Ok(())
+    }
+
+    fn update_start(&mut self, start: Timestamp) {
+        self.inclusive_start = self.inclusive_start.max(start);
+    }
+
+    fn update_end(&mut self, end: Timestamp) {
+        self.exclusive_end = self.exclusive_end.min(end);
+    }
+
+    fn build(self) -> Result<DeleteRequest> {
+        // An empty time range deletes nothing, which is usually a mistake.
+        ensure!(
+            self.inclusive_start < self.exclusive_end,
+            InvalidDeleteTimeRange {
+                start: self.inclusive_start,
+                end: self.exclusive_end,
+            }
+        );
+
+        Ok(DeleteRequest {
+            time_range: TimeRange::new_unchecked(self.inclusive_start, self.exclusive_end),
+            tag_filters: self.tag_filters,
+        })
+    }
+}
+
+fn datum_of_expr(kind: &DatumKind, expr: Expr, expr_desc: &str) -> Result<Datum> {
+    match expr {
+        Expr::Value(value) => Datum::try_from_sql_value(kind, value).context(DeleteConvertValue),
+        _ => UnsupportedDeleteExpr { expr: expr_desc }.fail(),
+    }
+}
+
+/// REQUIRE: The datum must be a timestamp.
+fn timestamp_of_datum(datum: Datum) -> Timestamp {
+    match datum {
+        Datum::Timestamp(ts) => ts,
+        _ => unreachable!(),
+    }
+}
+
+#[inline]
+fn next_timestamp(ts: Timestamp) -> Timestamp {
+    ts.checked_add_i64(1).unwrap_or(Timestamp::MAX)
+}
+
 #[inline]
 fn is_tsid_column(name: &str) -> bool {
     name == TSID_COLUMN
@@ -644,6 +1018,333 @@ pub fn parse_for_option(value: Value) -> Result<Option<String>> {
     Ok(value_opt)
 }
 
+/// Build the partition info from the partition definition of the table.
+fn parse_partition(partition: Partition, schema: &Schema) -> Result<PartitionInfo> {
+    let method = match partition {
+        Partition::Hash {
+            columns,
+            partition_num,
+        } => {
+            let partition_num = PartitionNum::try_from(partition_num)
+                .ok()
+                .filter(|num| *num > 0)
+                .context(InvalidPartitionNum { num: partition_num })?;
+
+            let mut names: Vec<String> = Vec::with_capacity(columns.len());
+            for column in columns {
+                find_tag_column(schema, &column.value)?;
+                ensure!(
+                    !names.contains(&column.value),
+                    DuplicatePartitionColumn { name: column.value }
+                );
+                names.push(column.value);
+            }
+
+            PartitionMethod::Hash {
+                columns: names,
+                partition_num,
+            }
+        }
+        Partition::Range { column, partitions } => {
+            let column = column.value;
+            schema
+                .column_with_name(&column)
+                .context(PartitionColumnNotFound { name: &column })?;
+            ensure!(
+                schema.timestamp_name() == column,
+                RangePartitionColumnNotTimestamp { name: column }
+            );
+            check_partition_names(partitions.iter().map(|p| &p.name.value))?;
+
+            let mut range_partitions: Vec<RangePartition> = Vec::with_capacity(partitions.len());
+            for def in partitions {
+                let name = def.name.value;
+                if let Some(last) = range_partitions.last() {
+                    ensure!(
+                        last.less_than.is_some(),
+                        RangePartitionMaxValueNotLast { name: &last.name }
+                    );
+                }
+
+                let less_than = match def.less_than {
+                    Some(value) => {
+                        let datum = Datum::try_from_sql_value(&DatumKind::Timestamp, value)
+                            .context(PartitionConvertValue)?;
+                        Some(timestamp_of_datum(datum))
+                    }
+                    None => None,
+                };
+                if let (Some(Some(prev)), Some(curr)) =
+                    (range_partitions.last().map(|p| p.less_than), less_than)
+                {
+                    ensure!(prev < curr, RangePartitionNotIncreasing { name });
+                }
+
+                range_partitions.push(RangePartition { name, less_than });
+            }
+
+            PartitionMethod::Range {
+                column,
+                partitions: range_partitions,
+            }
+        }
+        Partition::List { column, partitions } => {
+            let column = column.value;
+            let kind = find_tag_column(schema, &column)?.data_type;
+            check_partition_names(partitions.iter().map(|p| &p.name.value))?;
+
+            let mut all_values: Vec<Datum> = Vec::new();
+            let mut list_partitions = Vec::with_capacity(partitions.len());
+            for def in partitions {
+                let mut values = Vec::with_capacity(def.values.len());
+                for value in def.values {
+                    let datum =
+                        Datum::try_from_sql_value(&kind, value).context(PartitionConvertValue)?;
+                    ensure!(
+                        !all_values.contains(&datum),
+                        DuplicateListPartitionValue { value: datum }
+                    );
+                    all_values.push(datum.clone());
+                    values.push(datum);
+                }
+
+                list_partitions.push(ListPartition {
+                    name: def.name.value,
+                    values,
+                });
+            }
+
+            PartitionMethod::List {
+                column,
+                partitions: list_partitions,
+            }
+        }
+    };
+
+    ensure!(method.partition_num() > 0, EmptyPartitions);
+
+    Ok(PartitionInfo::new(method))
+}
+
+/// Analyze the query of a materialized view, returns the source table, the
+/// period of the time bucket and the schema of the view table.
+///
+/// The query must aggregate a single table, grouped by exactly one
+/// `time_bucket` of the timestamp column and some other columns, and every
+/// group expr must be selected. The time bucket becomes the timestamp key of
+/// the view table, the other group columns become tags and the remaining
+/// outputs become fields.
+fn analyze_view_query(view: &str, plan: &LogicalPlan) -> Result<(String, String, Schema)> {
+    let (projection, output_schema, aggregate) = match plan {
+        LogicalPlan::Projection(Projection {
+            expr,
+            input,
+            schema,
+            ..
+        }) => match input.as_ref() {
+            LogicalPlan::Aggregate(aggregate) => Some((expr, schema, aggregate)),
+            _ => None,
+        },
+        _ => None,
+    }
+    .context(InvalidMaterializedView {
+        view,
+        msg: "query must be a plain aggregation",
+    })?;
+
+    let scan = match aggregate.input.as_ref() {
+        LogicalPlan::Filter(Filter { input, .. }) => input.as_ref(),
+        input => input,
+    };
+    let source_table = match scan {
+        LogicalPlan::TableScan(TableScan { source, .. }) => source
+            .as_any()
+            .downcast_ref::<TableProviderAdapter>()
+            .map(|v| v.as_table_ref().clone()),
+        _ => None,
+    }
+    .context(InvalidMaterializedView {
+        view,
+        msg: "query must select from a single table",
+    })?;
+    let source_schema = source_table.schema();
+
+    // Find the time bucket of the group exprs.
+    let mut period = None;
+    let mut time_bucket_index = None;
+    for (i, expr) in aggregate.group_expr.iter().enumerate() {
+        match expr {
+            DfExpr::ScalarUDF { fun, args } if fun.name == TIME_BUCKET_FUNCTION => {
+                ensure!(
+                    time_bucket_index.is_none(),
+                    InvalidMaterializedView {
+                        view,
+                        msg: "query must group by only one time_bucket",
+                    }
+                );
+                match args.as_slice() {
+                    [DfExpr::Column(column), DfExpr::Literal(ScalarValue::Utf8(Some(v))), ..]
+                        if column.name == source_schema.timestamp_name()
+                            && Period::parse(v).is_ok() =>
+                    {
+                        period = Some(v.clone());
+                        time_bucket_index = Some(i);
+                    }
+                    _ => {
+                        return InvalidMaterializedView {
+                            view,
+                            msg: "invalid time_bucket of the timestamp column",
+                        }
+                        .fail()
+                    }
+                }
+            }
+            DfExpr::Column(_) => (),
+            _ => {
+                return InvalidMaterializedView {
+                    view,
+                    msg: format!("unsupported group expr:{:?}", expr),
+                }
+                .fail()
+            }
+        }
+    }
+    let (period, time_bucket_index) =
+        period
+            .zip(time_bucket_index)
+            .context(InvalidMaterializedView {
+                view,
+                msg: "query must group by time_bucket",
+            })?;
+
+    let mut timestamp_column = None;
+    let mut tag_columns = Vec::new();
+    let mut field_columns = Vec::new();
+    let mut selected_groups = HashSet::new();
+    for (expr, output_field) in projection.iter().zip(output_schema.fields()) {
+        let column = match expr {
+            DfExpr::Alias(expr, _) => expr.as_ref(),
+            expr => expr,
+        };
+        // Index of the output in the aggregate schema.
+        let index = match column {
+            DfExpr::Column(column) => aggregate.schema.fields().iter().position(|field| {
+                field.name() == &column.name
+                    && (column.relation.is_none() || field.qualifier() == column.relation.as_ref())
+            }),
+            _ => None,
+        }
+        .with_context(|| InvalidMaterializedView {
+            view,
+            msg: format!("unsupported output expr:{:?}", expr),
+        })?;
+
+        let name = output_field.name();
+        ensure!(
+            !is_tsid_column(name),
+            ColumnNameReserved { name: name.clone() }
+        );
+        let kind = DatumKind::from_data_type(output_field.data_type()).with_context(|| {
+            InvalidMaterializedView {
+                view,
+                msg: format!("unsupported data type of output:{}", name),
+            }
+        })?;
+
+        if index < aggregate.group_expr.len() {
+            ensure!(
+                selected_groups.insert(index),
+                InvalidMaterializedView {
+                    view,
+                    msg: format!("group expr is selected more than once, output:{}", name),
+                }
+            );
+        }
+
+        if index == time_bucket_index {
+            timestamp_column = Some(
+                column_schema::Builder::new(name.clone(), DatumKind::Timestamp)
+                    .is_nullable(false)
+                    .build()
+                    .context(InvalidColumnSchema { column_name: name })?,
+            );
+        } else if index < aggregate.group_expr.len() {
+            ensure!(
+                ColumnSchema::is_valid_tag_type(kind),
+                InvalidMaterializedView {
+                    view,
+                    msg: format!("invalid data type of tag, output:{}, type:{}", name, kind),
+                }
+            );
+            tag_columns.push(
+                column_schema::Builder::new(name.clone(), kind)
+                    .is_tag(true)
+                    .build()
+                    .context(InvalidColumnSchema { column_name: name })?,
+            );
+        } else {
+            field_columns.push(
+                column_schema::Builder::new(name.clone(), kind)
+                    .is_nullable(true)
+                    .build()
+                    .context(InvalidColumnSchema { column_name: name })?,
+            );
+        }
+    }
+    ensure!(
+        selected_groups.len() == aggregate.group_expr.len(),
+        InvalidMaterializedView {
+            view,
+            msg: "all the group exprs must be selected",
+        }
+    );
+
+    // The time bucket is always selected here.
+    let timestamp_column = timestamp_column.unwrap();
+    let tsid_column = column_schema::Builder::new(TSID_COLUMN.to_string(), DatumKind::UInt64)
+        .is_nullable(false)
+        .build()
+        .context(InvalidColumnSchema {
+            column_name: TSID_COLUMN,
+        })?;
+    let mut schema_builder = schema::Builder::with_capacity(projection.len() + 1)
+        .auto_increment_column_id(true)
+        .enable_tsid_primary_key(true)
+        .add_key_column(timestamp_column)
+        .context(BuildTableSchema)?
+        .add_key_column(tsid_column)
+        .context(BuildTableSchema)?;
+    for column in tag_columns.into_iter().chain(field_columns) {
+        schema_builder = schema_builder
+            .add_normal_column(column)
+            .context(BuildTableSchema)?;
+    }
+    let table_schema = schema_builder.build().context(BuildTableSchema)?;
+
+    Ok((source_table.name().to_string(), period, table_schema))
+}
+
+fn find_tag_column<'a>(schema: &'a Schema, name: &str) -> Result<&'a ColumnSchema> {
+    let column = schema
+        .column_with_name(name)
+        .context(PartitionColumnNotFound { name })?;
+    ensure!(column.is_tag, PartitionColumnNotTag { name });
+
+    Ok(column)
+}
+
+fn check_partition_names<'a>(names: impl Iterator<Item = &'a String>) -> Result<()> {
+    let mut visited = HashSet::new();
+    for name in names {
+        ensure!(
+            visited.insert(name.as_str()),
+            DuplicatePartitionName { name }
+        );
+    }
+
+    Ok(())
+}
+
 fn parse_columns(cols: Vec<ColumnDef>) -> Result<Vec<ColumnSchema>> {
     let mut parsed_columns = Vec::with_capacity(cols.len());
 
@@ -663,9 +1364,12 @@ fn parse_column(col: &ColumnDef) -> Result<ColumnSchema> {
     let mut is_tag = false;
     let mut is_unsign = false;
     let mut comment = String::new();
+    let mut default_expr = None;
     for option_def in &col.options {
         if matches!(option_def.option, ColumnOption::NotNull) {
             is_nullable = false;
+        } else if let ColumnOption::Default(expr) = &option_def.option {
+            default_expr = Some(expr);
         } else if parser::is_tag_column(&option_def.option) {
             is_tag = true;
         } else if parser::is_unsign_column(&option_def.option) {
@@ -681,16 +1385,61 @@ fn parse_column(col: &ColumnDef) -> Result<ColumnSchema> {
             .context(InvalidUnsignType { kind: data_type })?;
     }
 
+    // The default value must be parsed after the data type is determined.
+    let default_value = match default_expr {
+        Some(expr) => parse_default_value(&col.name.value, &data_type, expr.clone())?,
+        None => None,
+    };
+
     let builder = column_schema::Builder::new(col.name.value.clone(), data_type)
         .is_nullable(is_nullable)
         .is_tag(is_tag)
-        .comment(comment);
+        .comment(comment)
+        .default_value(default_value);
 
     builder.build().context(InvalidColumnSchema {
         column_name: &col.name.value,
     })
 }
 
+/// Evaluate the constant `expr` of the `DEFAULT` column option to the default
+/// value of the column, returns `None` if the default value is null.
+fn parse_default_value(
+    column_name: &str,
+    data_type: &DatumKind,
+    expr: Expr,
+) -> Result<Option<Datum>> {
+    let value = match expr {
+        Expr::Value(Value::Null) => return Ok(None),
+        Expr::Value(value) => value,
+        Expr::Nested(expr) => return parse_default_value(column_name, data_type, *expr),
+        Expr::UnaryOp {
+            op: op @ (UnaryOperator::Minus | UnaryOperator::Plus),
+            expr,
+        } => match *expr {
+            Expr::Value(Value::Number(n, long)) if op == UnaryOperator::Minus => {
+                Value::Number(format!("-{}", n), long)
+            }
+            Expr::Value(Value::Number(n, long)) => Value::Number(n, long),
+            expr => {
+                return DefaultValueNotConstant {
+                    column_name,
+                    expr: Expr::UnaryOp {
+                        op,
+                        expr: Box::new(expr),
+                    },
+                }
+                .fail()
+            }
+        },
+        expr => return DefaultValueNotConstant { column_name, expr }.fail(),
+    };
+
+    Datum::try_from_sql_value(data_type, value)
+        .map(Some)
+        .context(ConvertDefaultValue { column_name })
+}
+
 #[cfg(test)]
 mod tests {
     use sqlparser::ast::Value;
@@ -791,6 +1540,7 @@ mod tests {
                         is_nullable: false,
                         is_tag: true,
                         comment: "",
+                        default_value: None,
                     },
                     ColumnSchema {
                         id: 2,
@@ -799,6 +1549,7 @@ mod tests {
                         is_nullable: false,
                         is_tag: false,
                         comment: "",
+                        default_value: None,
                     },
                     ColumnSchema {
                         id: 3,
@@ -807,6 +1558,7 @@ mod tests {
                         is_nullable: true,
                         is_tag: false,
                         comment: "",
+                        default_value: None,
                     },
                 ],
             },
@@ -817,12 +1569,158 @@ mod tests {
             "ttl": "70d",
             "update_mode": "overwrite",
         },
+        partition_info: None,
     },
 )"#,
         )
         .unwrap();
     }
 
+    #[test]
+    fn test_create_table_with_default_value() {
+        let sql = "CREATE TABLE t(c1 string tag default 'a', ts timestamp not null, \
+            c3 int not null default -1, c4 double default (1.5), c5 string default null, \
+            timestamp key(ts))";
+        let mock = MockMetaProvider::default();
+        let planner = build_planner(&mock);
+        let mut statements = Parser::parse_sql(sql).unwrap();
+        let schema = match planner.statement_to_plan(statements.remove(0)).unwrap() {
+            Plan::Create(plan) => plan.table_schema,
+            _ => unreachable!(),
+        };
+        let default_values: Vec<_> = schema
+            .columns()
+            .iter()
+            .map(|column| column.default_value.clone())
+            .collect();
+        assert_eq!(
+            vec![
+                None,
+                None,
+                Some(Datum::String("a".into())),
+                None,
+                Some(Datum::Int32(-1)),
+                Some(Datum::Double(1.5)),
+                None,
+            ],
+            default_values
+        );
+
+        // The default value must be a constant of the column type.
+        let sqls = [
+            "CREATE TABLE t(c1 int default 'a', ts timestamp not null, timestamp key(ts))",
+            "CREATE TABLE t(c1 int default c2, ts timestamp not null, timestamp key(ts))",
+            "CREATE TABLE t(c1 string default -1, ts timestamp not null, timestamp key(ts))",
+        ];
+        for sql in sqls {
+            let mut statements = Parser::parse_sql(sql).unwrap();
+            assert!(planner.statement_to_plan(statements.remove(0)).is_err());
+        }
+    }
+
+    fn create_table_partition_info(sql: &str) -> Result<Option<PartitionInfo>> {
+        let mock = MockMetaProvider::default();
+        let planner = build_planner(&mock);
+        let mut statements = Parser::parse_sql(sql).unwrap();
+        match planner.statement_to_plan(statements.remove(0))? {
+            Plan::Create(plan) => Ok(plan.partition_info),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_create_partitioned_table_to_plan() {
+        let sql =
+            "CREATE TABLE t(c1 string tag, c2 string, ts timestamp not null, timestamp key(ts)) \
+            PARTITION BY HASH(c1) PARTITIONS 4";
+        let info = create_table_partition_info(sql).unwrap().unwrap();
+        assert_eq!(
+            PartitionMethod::Hash {
+                columns: vec!["c1".to_string()],
+                partition_num: 4,
+            },
+            info.method
+        );
+        assert!(info.sub_table_ids.is_empty());
+
+        let sql = "CREATE TABLE t(c1 string tag, ts timestamp not null, timestamp key(ts)) \
+            PARTITION BY RANGE(ts) (PARTITION p0 VALUES LESS THAN (1000), PARTITION p1 VALUES LESS THAN MAXVALUE)";
+        let info = create_table_partition_info(sql).unwrap().unwrap();
+        assert_eq!(
+            PartitionMethod::Range {
+                column: "ts".to_string(),
+                partitions: vec![
+                    RangePartition {
+                        name: "p0".to_string(),
+                        less_than: Some(Timestamp::new(1000)),
+                    },
+                    RangePartition {
+                        name: "p1".to_string(),
+                        less_than: None,
+                    },
+                ],
+            },
+            info.method
+        );
+
+        let sql = "CREATE TABLE t(c1 string tag, ts timestamp not null, timestamp key(ts)) \
+            PARTITION BY LIST(c1) (PARTITION east VALUES IN ('hz', 'sh'), PARTITION west VALUES IN ('cd'))";
+        let info = create_table_partition_info(sql).unwrap().unwrap();
+        assert_eq!(
+            PartitionMethod::List {
+                column: "c1".to_string(),
+                partitions: vec![
+                    ListPartition {
+                        name: "east".to_string(),
+                        values: vec![Datum::from("hz"), Datum::from("sh")],
+                    },
+                    ListPartition {
+                        name: "west".to_string(),
+                        values: vec![Datum::from("cd")],
+                    },
+                ],
+            },
+            info.method
+        );
+
+        let sql = "CREATE TABLE t(c1 string tag, ts timestamp not null, timestamp key(ts))";
+        assert!(create_table_partition_info(sql).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_create_partitioned_table_to_plan_invalid() {
+        let invalid_sqls = [
+            // Partition num is zero.
+            "CREATE TABLE t(c1 string tag, ts timestamp not null, timestamp key(ts)) \
+            PARTITION BY HASH(c1) PARTITIONS 0",
+            // Hash column is not a tag.
+            "CREATE TABLE t(c1 string, ts timestamp not null, timestamp key(ts)) \
+            PARTITION BY HASH(c1) PARTITIONS 2",
+            // Unknown column.
+            "CREATE TABLE t(c1 string tag, ts timestamp not null, timestamp key(ts)) \
+            PARTITION BY HASH(c2) PARTITIONS 2",
+            // Range column is not the timestamp column.
+            "CREATE TABLE t(c1 string tag, ts timestamp not null, timestamp key(ts)) \
+            PARTITION BY RANGE(c1) (PARTITION p0 VALUES LESS THAN MAXVALUE)",
+            // Upper bounds are not increasing.
+            "CREATE TABLE t(c1 string tag, ts timestamp not null, timestamp key(ts)) \
+            PARTITION BY RANGE(ts) (PARTITION p0 VALUES LESS THAN (1000), PARTITION p1 VALUES LESS THAN (1000))",
+            // MAXVALUE is not the last.
+            "CREATE TABLE t(c1 string tag, ts timestamp not null, timestamp key(ts)) \
+            PARTITION BY RANGE(ts) (PARTITION p0 VALUES LESS THAN MAXVALUE, PARTITION p1 VALUES LESS THAN (1000))",
+            // Duplicate partition name.
+            "CREATE TABLE t(c1 string tag, ts timestamp not null, timestamp key(ts)) \
+            PARTITION BY LIST(c1) (PARTITION p0 VALUES IN ('a'), PARTITION p0 VALUES IN ('b'))",
+            // Duplicate list value.
+            "CREATE TABLE t(c1 string tag, ts timestamp not null, timestamp key(ts)) \
+            PARTITION BY LIST(c1) (PARTITION p0 VALUES IN ('a'), PARTITION p1 VALUES IN ('a'))",
+        ];
+
+        for sql in &invalid_sqls {
+            assert!(create_table_partition_info(sql).is_err(), "sql:{}", sql);
+        }
+    }
+
     #[test]
     fn test_query_statement_to_plan() {
         let sql = "select * from test_tablex;";
@@ -837,6 +1735,121 @@ mod tests {
 )").unwrap();
     }
 
+    fn query_sql_to_plan_string(sql: &str) -> String {
+        let mock = MockMetaProvider::default();
+        let planner = build_planner(&mock);
+        let mut statements = Parser::parse_sql(sql).unwrap();
+        assert_eq!(statements.len(), 1);
+        match planner.statement_to_plan(statements.remove(0)).unwrap() {
+            Plan::Query(plan) => format!("{:?}", plan.df_plan),
+            plan => panic!("Unexpected plan, plan:{:?}", plan),
+        }
+    }
+
+    #[test]
+    fn test_query_routed_to_rollup_table() {
+        // The rollup table aggregates field1 by 5 minutes.
+        let routed_sqls = [
+            "SELECT time_bucket(key2, 'PT1H'), key1, avg(field1) FROM rollup_table \
+             GROUP BY time_bucket(key2, 'PT1H'), key1",
+            "SELECT time_bucket(key2, 'PT10M'), count(field1), sum(field1) FROM rollup_table \
+             WHERE key2 >= 1651737600000 AND key2 < 1651741200000 AND key1 = 'a' \
+             GROUP BY time_bucket(key2, 'PT10M')",
+            "SELECT time_bucket(key2, 'P1D'), avg(field1) FROM rollup_table \
+             GROUP BY time_bucket(key2, 'P1D')",
+            // The aggregation in subquery.
+            "SELECT * FROM (SELECT time_bucket(key2, 'PT1H'), avg(field1) \
+             FROM rollup_table GROUP BY time_bucket(key2, 'PT1H'))",
+        ];
+        for sql in routed_sqls {
+            let plan = query_sql_to_plan_string(sql);
+            assert!(plan.contains("#rollup_table.field1_sum"), "plan:{}", plan);
+            // The rows after the watermark are read from the table, and the
+            // watermark is aligned to the rollup interval.
+            assert!(plan.contains("Union"), "plan:{}", plan);
+            assert!(
+                plan.contains("#rollup_table.key2 < TimestampMillisecond(1651737600000"),
+                "plan:{}",
+                plan
+            );
+            assert!(
+                plan.contains("#rollup_table.key2 >= TimestampMillisecond(1651737600000"),
+                "plan:{}",
+                plan
+            );
+        }
+
+        let not_routed_sqls = [
+            // The bucket is finer than the rollup interval.
+            "SELECT time_bucket(key2, 'PT1M'), avg(field1) FROM rollup_table \
+             GROUP BY time_bucket(key2, 'PT1M')",
+            // The time range is not aligned to the rollup interval.
+            "SELECT time_bucket(key2, 'PT1H'), avg(field1) FROM rollup_table \
+             WHERE key2 >= 1651737600001 GROUP BY time_bucket(key2, 'PT1H')",
+            // The state of max is absent in the rollup table.
+            "SELECT time_bucket(key2, 'PT1H'), max(field1) FROM rollup_table \
+             GROUP BY time_bucket(key2, 'PT1H')",
+            // Filter on the field.
+            "SELECT time_bucket(key2, 'PT1H'), avg(field1) FROM rollup_table \
+             WHERE field1 > 1.0 GROUP BY time_bucket(key2, 'PT1H')",
+            // No rollup table.
+            "SELECT time_bucket(key2, 'PT1H'), avg(field1) FROM test_table \
+             GROUP BY time_bucket(key2, 'PT1H')",
+        ];
+        for sql in not_routed_sqls {
+            let plan = query_sql_to_plan_string(sql);
+            assert!(!plan.contains("field1_sum"), "plan:{}", plan);
+        }
+    }
+
+    #[test]
+    fn test_create_materialized_view_to_plan() {
+        let mock = MockMetaProvider::default();
+        let sql = "CREATE MATERIALIZED VIEW IF NOT EXISTS v AS \
+                   SELECT time_bucket(key2, 'PT5M') AS ts, key1, avg(field1) AS avg_field1 \
+                   FROM test_table WHERE key1 = 'a' GROUP BY time_bucket(key2, 'PT5M'), key1";
+        let mut statements = Parser::parse_sql(sql).unwrap();
+        let plan = build_planner(&mock)
+            .statement_to_plan(statements.remove(0))
+            .unwrap();
+        let plan = match plan {
+            Plan::CreateMaterializedView(v) => v,
+            plan => panic!("Unexpected plan, plan:{:?}", plan),
+        };
+        assert!(plan.if_not_exists);
+        assert_eq!("v", plan.view);
+        assert_eq!("test_table", plan.source_table);
+        assert_eq!("PT5M", plan.period);
+
+        let schema = &plan.table_schema;
+        assert_eq!("ts", schema.timestamp_name());
+        assert!(schema.index_of_tsid().is_some());
+        let key1 = schema.column_with_name("key1").unwrap();
+        assert!(key1.is_tag);
+        assert_eq!(DatumKind::Varbinary, key1.data_type);
+        let avg_field1 = schema.column_with_name("avg_field1").unwrap();
+        assert!(!avg_field1.is_tag);
+        assert_eq!(DatumKind::Double, avg_field1.data_type);
+
+        let invalid_sqls = [
+            // No time bucket.
+            "CREATE MATERIALIZED VIEW v AS SELECT key1, avg(field1) FROM test_table GROUP BY key1",
+            // Not an aggregation.
+            "CREATE MATERIALIZED VIEW v AS SELECT key1, field1 FROM test_table",
+            // The group column is not selected.
+            "CREATE MATERIALIZED VIEW v AS SELECT time_bucket(key2, 'PT5M'), avg(field1) \
+             FROM test_table GROUP BY time_bucket(key2, 'PT5M'), key1",
+            // Field can't be a tag.
+            "CREATE MATERIALIZED VIEW v AS SELECT time_bucket(key2, 'PT5M'), field1 \
+             FROM test_table GROUP BY time_bucket(key2, 'PT5M'), field1",
+        ];
+        for sql in invalid_sqls {
+            let mut statements = Parser::parse_sql(sql).unwrap();
+            let res = build_planner(&mock).statement_to_plan(statements.remove(0));
+            assert!(res.is_err(), "sql:{}", sql);
+        }
+    }
+
     #[test]
     fn test_insert_statement_to_plan() {
         let sql = "INSERT INTO test_tablex(key1, key2, field1,field2) VALUES('tagk', 1638428434000,100, 'hello3');";
@@ -864,6 +1877,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 2,
@@ -872,6 +1886,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 3,
@@ -880,6 +1895,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 4,
@@ -888,6 +1904,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                     ],
                 },
@@ -909,6 +1926,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 2,
@@ -917,6 +1935,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 3,
@@ -925,6 +1944,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 4,
@@ -933,6 +1953,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                     ],
                 },
@@ -973,6 +1994,64 @@ mod tests {
         .unwrap();
     }
 
+    fn delete_sql_to_request(sql: &str) -> Result<DeleteRequest> {
+        let mock = MockMetaProvider::default();
+        let planner = build_planner(&mock);
+        let mut statements = Parser::parse_sql(sql).unwrap();
+        assert_eq!(statements.len(), 1);
+        match planner.statement_to_plan(statements.remove(0))? {
+            Plan::Delete(plan) => Ok(plan.request),
+            plan => panic!("Unexpected plan, plan:{:?}", plan),
+        }
+    }
+
+    #[test]
+    fn test_delete_statement_to_plan() {
+        let request =
+            delete_sql_to_request("DELETE FROM test_table WHERE key2 >= 1000 AND key2 < 2000")
+                .unwrap();
+        assert_eq!(
+            DeleteRequest {
+                time_range: TimeRange::new_unchecked_for_test(1000, 2000),
+                tag_filters: Vec::new(),
+            },
+            request
+        );
+
+        let request =
+            delete_sql_to_request("DELETE FROM test_table WHERE (key2 BETWEEN 1000 AND 1999)")
+                .unwrap();
+        assert_eq!(
+            TimeRange::new_unchecked_for_test(1000, 2000),
+            request.time_range
+        );
+
+        let request = delete_sql_to_request(
+            "DELETE FROM test_table WHERE key2 > 1000 AND key2 <= 3000 AND key2 < 2000",
+        )
+        .unwrap();
+        assert_eq!(
+            TimeRange::new_unchecked_for_test(1001, 2000),
+            request.time_range
+        );
+
+        // Where clause is required.
+        assert!(delete_sql_to_request("DELETE FROM test_table").is_err());
+        // Only tag and timestamp columns are allowed.
+        assert!(delete_sql_to_request("DELETE FROM test_table WHERE field1 = 1.0").is_err());
+        assert!(delete_sql_to_request("DELETE FROM test_table WHERE key1 = 'a'").is_err());
+        // Disjunction is not supported.
+        assert!(
+            delete_sql_to_request("DELETE FROM test_table WHERE key2 < 1000 OR key2 > 2000")
+                .is_err()
+        );
+        // Empty time range.
+        assert!(
+            delete_sql_to_request("DELETE FROM test_table WHERE key2 >= 2000 AND key2 < 1000")
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_drop_statement_to_plan() {
         let sql = "drop table test_table;";
@@ -1032,6 +2111,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 2,
@@ -1040,6 +2120,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 3,
@@ -1048,6 +2129,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 4,
@@ -1056,6 +2138,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                     ],
                 },
@@ -1095,6 +2178,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 2,
@@ -1103,6 +2187,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 3,
@@ -1111,6 +2196,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 4,
@@ -1119,6 +2205,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                     ],
                 },
@@ -1134,7 +2221,80 @@ mod tests {
                     is_nullable: true,
                     is_tag: false,
                     comment: "",
+                    default_value: None,
+                },
+            ],
+        ),
+    },
+)"#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_alter_drop_column_statement_to_plan() {
+        let sql = "ALTER TABLE test_tablex DROP COLUMN field1;";
+        assert!(quick_test(sql, "").is_err());
+
+        let sql = "ALTER TABLE test_table DROP COLUMN field1;";
+        quick_test(
+            sql,
+            r#"AlterTable(
+    AlterTablePlan {
+        table: MemoryTable {
+            name: "test_table",
+            id: TableId(100, 0, 100),
+            schema: Schema {
+                num_key_columns: 2,
+                timestamp_index: 1,
+                tsid_index: None,
+                enable_tsid_primary_key: false,
+                column_schemas: ColumnSchemas {
+                    columns: [
+                        ColumnSchema {
+                            id: 1,
+                            name: "key1",
+                            data_type: Varbinary,
+                            is_nullable: false,
+                            is_tag: false,
+                            comment: "",
+                            default_value: None,
+                        },
+                        ColumnSchema {
+                            id: 2,
+                            name: "key2",
+                            data_type: Timestamp,
+                            is_nullable: false,
+                            is_tag: false,
+                            comment: "",
+                            default_value: None,
+                        },
+                        ColumnSchema {
+                            id: 3,
+                            name: "field1",
+                            data_type: Double,
+                            is_nullable: false,
+                            is_tag: false,
+                            comment: "",
+                            default_value: None,
+                        },
+                        ColumnSchema {
+                            id: 4,
+                            name: "field2",
+                            data_type: String,
+                            is_nullable: false,
+                            is_tag: false,
+                            comment: "",
+                            default_value: None,
+                        },
+                    ],
                 },
+                version: 1,
+            },
+        },
+        operations: DropColumn(
+            [
+                "field1",
             ],
         ),
     },
@@ -1143,6 +2303,77 @@ mod tests {
         .unwrap();
     }
 
+    #[test]
+    fn test_alter_rename_column_statement_to_plan() {
+        let sql = "ALTER TABLE test_tablex RENAME COLUMN field1 TO field3;";
+        assert!(quick_test(sql, "").is_err());
+
+        let sql = "ALTER TABLE test_table RENAME COLUMN field1 TO field3;";
+        quick_test(
+            sql,
+            r#"AlterTable(
+    AlterTablePlan {
+        table: MemoryTable {
+            name: "test_table",
+            id: TableId(100, 0, 100),
+            schema: Schema {
+                num_key_columns: 2,
+                timestamp_index: 1,
+                tsid_index: None,
+                enable_tsid_primary_key: false,
+                column_schemas: ColumnSchemas {
+                    columns: [
+                        ColumnSchema {
+                            id: 1,
+                            name: "key1",
+                            data_type: Varbinary,
+                            is_nullable: false,
+                            is_tag: false,
+                            comment: "",
+                            default_value: None,
+                        },
+                        ColumnSchema {
+                            id: 2,
+                            name: "key2",
+                            data_type: Timestamp,
+                            is_nullable: false,
+                            is_tag: false,
+                            comment: "",
+                            default_value: None,
+                        },
+                        ColumnSchema {
+                            id: 3,
+                            name: "field1",
+                            data_type: Double,
+                            is_nullable: false,
+                            is_tag: false,
+                            comment: "",
+                            default_value: None,
+                        },
+                        ColumnSchema {
+                            id: 4,
+                            name: "field2",
+                            data_type: String,
+                            is_nullable: false,
+                            is_tag: false,
+                            comment: "",
+                            default_value: None,
+                        },
+                    ],
+                },
+                version: 1,
+            },
+        },
+        operations: RenameColumn {
+            old_name: "field1",
+            new_name: "field3",
+        },
+    },
+)"#,
+        )
+        .unwrap();
+    }
+
     #[test]
     fn test_alter_option_statement_to_plan() {
         let sql = "ALTER TABLE test_tablex modify SETTING ttl='9d';";
@@ -1170,6 +2401,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 2,
@@ -1178,6 +2410,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 3,
@@ -1186,6 +2419,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 4,
@@ -1194,6 +2428,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                     ],
                 },
@@ -1238,6 +2473,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 2,
@@ -1246,6 +2482,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 3,
@@ -1254,6 +2491,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                         ColumnSchema {
                             id: 4,
@@ -1262,6 +2500,7 @@ mod tests {
                             is_nullable: false,
                             is_tag: false,
                             comment: "",
+                            default_value: None,
                         },
                     ],
                 },
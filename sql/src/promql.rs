@@ -2,9 +2,13 @@
 
 mod convert;
 mod datafusion_util;
+mod parser;
 mod pushdown;
 mod udf;
 
-pub use convert::{Error, Expr};
+pub use convert::{
+    Error, Expr, Filter, FilterOperator, FilterType, Operand, Selector, DEFAULT_LOOKBACK,
+};
 pub use datafusion_util::{ColumnNames, PromAlignNode};
+pub use parser::{parse, Error as ParseError, EvalParams};
 pub use pushdown::{AlignParameter, Func};
@@ -35,7 +35,8 @@ use crate::{
 };
 
 const INIT_LEVEL: usize = 1;
-const DEFAULT_LOOKBACK: i64 = 300_000;
+/// Lookback window of the instant vector selector in milliseconds.
+pub const DEFAULT_LOOKBACK: i64 = 300_000;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -465,16 +466,16 @@ impl SubExpr {
 
 #[derive(Debug, Clone)]
 pub struct AggrExpr {
-    op: String,
-    operands: Vec<Expr>,
-    group_by: Vec<String>,
-    without: bool,
+    pub op: String,
+    pub operands: Vec<Expr>,
+    pub group_by: Vec<String>,
+    pub without: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct FuncExpr {
-    op: String,
-    operands: Vec<Expr>,
+    pub op: String,
+    pub operands: Vec<Expr>,
 }
 
 #[derive(Debug, Clone)]
@@ -484,7 +485,7 @@ pub struct BinaryExpr {
     _return_bool: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum FilterType {
     LiteralOr,
     NotLiteralOr,
@@ -505,14 +506,33 @@ impl From<FilterPbType> for FilterType {
 
 #[derive(Debug, Clone)]
 pub struct FilterOperator {
-    typ: FilterType,
-    params: Vec<String>,
+    pub typ: FilterType,
+    pub params: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Filter {
-    tag_key: String,
-    operators: Vec<FilterOperator>,
+    pub tag_key: String,
+    pub operators: Vec<FilterOperator>,
+}
+
+impl Filter {
+    /// Create a filter from the label matcher of PromQL, the regex of the
+    /// matcher is fully anchored.
+    pub fn from_matcher(tag_key: String, typ: FilterType, value: String) -> Self {
+        let value = match typ {
+            FilterType::Regexp | FilterType::NotRegexpMatch => format!("^(?:{})$", value),
+            FilterType::LiteralOr | FilterType::NotLiteralOr => value,
+        };
+
+        Self {
+            tag_key,
+            operators: vec![FilterOperator {
+                typ,
+                params: vec![value],
+            }],
+        }
+    }
 }
 
 impl From<Filter> for DataFusionExpr {
@@ -577,6 +597,62 @@ pub struct Selector {
 }
 
 impl Selector {
+    /// Build the plan to list the distinct series matching the selector, the
+    /// plan only projects the tag columns and the tsid column.
+    ///
+    /// Returns the plan and the names of the tag columns.
+    pub fn to_series_plan<P: MetaProvider>(
+        self,
+        meta_provider: ContextProviderAdapter<'_, P>,
+    ) -> Result<(Plan, Vec<String>)> {
+        let Selector {
+            query_range,
+            filters,
+            table,
+            ..
+        } = self;
+        let table_ref = meta_provider
+            .table(table.as_str().into())
+            .context(MetaProviderError {
+                msg: "failed to find table".to_string(),
+            })?
+            .context(TableNotFound { name: &table })?;
+
+        let table_provider = meta_provider
+            .get_table_provider(table_ref.name().into())
+            .context(TableNotFound { name: &table })?;
+        let schema = Schema::try_from(table_provider.schema()).context(BuildTableSchema)?;
+        let tag_keys: Vec<_> = schema
+            .columns()
+            .iter()
+            .filter(|column| column.is_tag)
+            .map(|column| column.name.clone())
+            .collect();
+        let mut filter_exprs = filters
+            .iter()
+            .filter(|f| tag_keys.contains(&f.tag_key))
+            .map(|f| DataFusionExpr::from(f.clone()))
+            .collect::<Vec<_>>();
+        filter_exprs.push(timerange_to_expr(query_range, schema.timestamp_name()));
+
+        let mut group_exprs: Vec<_> = tag_keys.iter().map(|tag_key| col(tag_key)).collect();
+        group_exprs.push(col(TSID_COLUMN));
+        let df_plan = LogicalPlanBuilder::scan(table, table_provider, None)?
+            .filter(combine_filters(&filter_exprs).expect("at least one filter(timestamp)"))?
+            .aggregate(group_exprs, Vec::<DataFusionExpr>::new())?
+            .build()
+            .context(BuildPlanError)?;
+        let tables = Arc::new(
+            meta_provider
+                .try_into_container()
+                .context(MetaProviderError {
+                    msg: "Failed to find meta",
+                })?,
+        );
+
+        Ok((Plan::Query(QueryPlan { df_plan, tables }), tag_keys))
+    }
+
     fn into_scan_plan<P: MetaProvider>(
         self,
         meta_provider: &ContextProviderAdapter<'_, P>,
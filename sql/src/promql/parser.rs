@@ -0,0 +1,766 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Parser of the PromQL text
+//!
+//! Only a subset of PromQL that can be planned by [Expr::to_plan] is
+//! supported now:
+//! - Selectors with label matchers, range and offset, eg.
+//!   `http_requests{job=~"api.*", code!="200"}[5m] offset 1m`
+//! - Function calls, eg. `rate(http_requests[5m])`
+//! - Aggregations with `by` or `without` modifier, eg. `sum by (job) (...)`
+//! - Number and string literals
+
+use std::str::FromStr;
+
+use common_types::time::{TimeRange, Timestamp};
+use common_util::config::ReadableDuration;
+use snafu::{ensure, Backtrace, OptionExt, Snafu};
+
+use crate::promql::convert::{
+    AggrExpr, Expr, Filter, FilterType, FuncExpr, Operand, Selector, SubExpr, DEFAULT_LOOKBACK,
+};
+
+/// Label of the metric name in the label matchers.
+const METRIC_NAME_LABEL: &str = "__name__";
+
+const AGGR_OPS: [&str; 5] = ["sum", "min", "max", "avg", "count"];
+
+/// Max nesting depth of the expressions, the parser is recursive so the depth
+/// is limited to avoid overflowing the stack.
+const MAX_EXPR_DEPTH: usize = 256;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Invalid promql, pos:{}, msg:{}.\nBacktrace:\n{}", pos, msg, backtrace))]
+    InvalidQuery {
+        pos: usize,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Unsupported promql, msg:{}.\nBacktrace:\n{}", msg, backtrace))]
+    Unsupported { msg: String, backtrace: Backtrace },
+}
+
+define_result!(Error);
+
+/// Parameters to evaluate the parsed expression, all the timestamps and
+/// durations are in milliseconds.
+#[derive(Debug, Clone)]
+pub struct EvalParams {
+    /// Inclusive start of the evaluation.
+    pub start: i64,
+    /// Inclusive end of the evaluation.
+    pub end: i64,
+    /// Step between two evaluations, must be positive.
+    pub step: i64,
+    /// Field column to read the sample values from.
+    pub field: String,
+}
+
+/// Parse the PromQL `query` into [Expr].
+pub fn parse(query: &str, params: &EvalParams) -> Result<Expr> {
+    ensure!(
+        params.step > 0 && params.start <= params.end,
+        Unsupported {
+            msg: format!(
+                "invalid evaluation params, start:{}, end:{}, step:{}",
+                params.start, params.end, params.step
+            ),
+        }
+    );
+
+    let tokens = Lexer::new(query).tokenize()?;
+    let mut parser = Parser {
+        tokens,
+        index: 0,
+        params,
+        end_pos: query.len(),
+        depth: 0,
+    };
+
+    let expr = parser.parse_expr()?;
+    if let Some((token, pos)) = parser.peek_with_pos() {
+        if let Token::Operator(op) = token {
+            return Unsupported {
+                msg: format!("binary operator {} is not supported", op),
+            }
+            .fail();
+        }
+        return InvalidQuery {
+            pos,
+            msg: format!("unexpected token {:?}", token),
+        }
+        .fail();
+    }
+
+    Ok(expr)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Identifier or keyword.
+    Ident(String),
+    /// Number or duration, which is decided by the parser.
+    Number(String),
+    String(String),
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+    LeftBracket,
+    RightBracket,
+    Comma,
+    /// Label match operators: `=`, `!=`, `=~`, `!~`.
+    Match(FilterType),
+    /// Binary operators.
+    Operator(String),
+}
+
+struct Lexer<'a> {
+    query: &'a str,
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(query: &'a str) -> Self {
+        Self {
+            query,
+            chars: query.char_indices().peekable(),
+        }
+    }
+
+    fn tokenize(mut self) -> Result<Vec<(Token, usize)>> {
+        let mut tokens = Vec::new();
+        while let Some((pos, c)) = self.chars.next() {
+            let token = match c {
+                c if c.is_whitespace() => continue,
+                // Comments run until the end of the line.
+                '#' => {
+                    for (_, c) in self.chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                '(' => Token::LeftParen,
+                ')' => Token::RightParen,
+                '{' => Token::LeftBrace,
+                '}' => Token::RightBrace,
+                '[' => Token::LeftBracket,
+                ']' => Token::RightBracket,
+                ',' => Token::Comma,
+                '=' => {
+                    if self.next_if_eq('~') {
+                        Token::Match(FilterType::Regexp)
+                    } else if self.next_if_eq('=') {
+                        Token::Operator("==".to_string())
+                    } else {
+                        Token::Match(FilterType::LiteralOr)
+                    }
+                }
+                '!' => {
+                    if self.next_if_eq('~') {
+                        Token::Match(FilterType::NotRegexpMatch)
+                    } else if self.next_if_eq('=') {
+                        Token::Match(FilterType::NotLiteralOr)
+                    } else {
+                        return InvalidQuery {
+                            pos,
+                            msg: "unexpected character after '!'",
+                        }
+                        .fail();
+                    }
+                }
+                '<' | '>' => {
+                    let mut op = c.to_string();
+                    if self.next_if_eq('=') {
+                        op.push('=');
+                    }
+                    Token::Operator(op)
+                }
+                '+' | '-' | '*' | '/' | '%' | '^' => Token::Operator(c.to_string()),
+                '"' | '\'' | '`' => Token::String(self.read_string(pos, c)?),
+                c if c.is_ascii_digit() || c == '.' => {
+                    Token::Number(self.read_while(pos, |c| c.is_ascii_alphanumeric() || c == '.'))
+                }
+                c if c.is_ascii_alphabetic() || c == '_' || c == ':' => Token::Ident(
+                    self.read_while(pos, |c| c.is_ascii_alphanumeric() || c == '_' || c == ':'),
+                ),
+                c => {
+                    return InvalidQuery {
+                        pos,
+                        msg: format!("unexpected character {:?}", c),
+                    }
+                    .fail()
+                }
+            };
+            tokens.push((token, pos));
+        }
+
+        Ok(tokens)
+    }
+
+    fn next_if_eq(&mut self, expected: char) -> bool {
+        match self.chars.peek() {
+            Some((_, c)) if *c == expected => {
+                self.chars.next();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Read the word starting at `start` while the chars match `pred`.
+    fn read_while(&mut self, start: usize, pred: impl Fn(char) -> bool) -> String {
+        let mut end = self.query.len();
+        while let Some((pos, c)) = self.chars.peek() {
+            if !pred(*c) {
+                end = *pos;
+                break;
+            }
+            self.chars.next();
+        }
+
+        self.query[start..end].to_string()
+    }
+
+    /// Read the string quoted by `quote`, escape sequences are not allowed in
+    /// the raw string quoted by backticks.
+    fn read_string(&mut self, start: usize, quote: char) -> Result<String> {
+        let mut value = String::new();
+        while let Some((pos, c)) = self.chars.next() {
+            match c {
+                c if c == quote => return Ok(value),
+                '\\' if quote != '`' => {
+                    let escaped = match self.chars.next() {
+                        Some((_, 'n')) => '\n',
+                        Some((_, 't')) => '\t',
+                        Some((_, 'r')) => '\r',
+                        Some((_, c @ ('\\' | '"' | '\''))) => c,
+                        _ => {
+                            return InvalidQuery {
+                                pos,
+                                msg: "invalid escape sequence",
+                            }
+                            .fail()
+                        }
+                    };
+                    value.push(escaped);
+                }
+                c => value.push(c),
+            }
+        }
+
+        InvalidQuery {
+            pos: start,
+            msg: "unterminated string",
+        }
+        .fail()
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    params: &'a EvalParams,
+    /// Position of the end of the query, used in the error of unexpected end.
+    end_pos: usize,
+    /// Nesting depth of the expression being parsed.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse_expr(&mut self) -> Result<Expr> {
+        self.depth += 1;
+        ensure!(
+            self.depth <= MAX_EXPR_DEPTH,
+            InvalidQuery {
+                pos: self.peek_with_pos().map_or(self.end_pos, |(_, pos)| pos),
+                msg: format!("expression nests deeper than {}", MAX_EXPR_DEPTH),
+            }
+        );
+        let expr = self.parse_expr_inner();
+        self.depth -= 1;
+
+        expr
+    }
+
+    fn parse_expr_inner(&mut self) -> Result<Expr> {
+        let (token, pos) = self.next_with_pos()?;
+        match token {
+            Token::LeftParen => {
+                let expr = self.parse_expr()?;
+                self.expect(Token::RightParen)?;
+                Ok(expr)
+            }
+            Token::Number(number) => {
+                let value = f64::from_str(&number).ok().context(InvalidQuery {
+                    pos,
+                    msg: format!("invalid number {}", number),
+                })?;
+                Ok(Expr::SimpleExpr(Operand::Float(value)))
+            }
+            Token::String(value) => Ok(Expr::SimpleExpr(Operand::String(value))),
+            Token::LeftBrace => self.parse_selector(None, pos),
+            Token::Ident(name) => {
+                if AGGR_OPS.contains(&name.as_str()) && self.next_is_aggr_body() {
+                    self.parse_aggr_expr(name)
+                } else if self.peek() == Some(&Token::LeftParen) {
+                    self.parse_func_expr(name)
+                } else {
+                    self.parse_selector(Some(name), pos)
+                }
+            }
+            token => InvalidQuery {
+                pos,
+                msg: format!("unexpected token {:?}", token),
+            }
+            .fail(),
+        }
+    }
+
+    /// Parse the aggregation like `sum by (a) (expr)` or `sum(expr) by (a)`,
+    /// the aggregation op is already consumed.
+    fn parse_aggr_expr(&mut self, op: String) -> Result<Expr> {
+        let mut modifier = self.parse_aggr_modifier()?;
+        self.expect(Token::LeftParen)?;
+        let operand = self.parse_expr()?;
+        self.expect(Token::RightParen)?;
+        if modifier.is_none() {
+            modifier = self.parse_aggr_modifier()?;
+        }
+
+        let (group_by, without) = modifier.unwrap_or_default();
+        Ok(Expr::RecursiveExpr(SubExpr::Aggr(AggrExpr {
+            op,
+            operands: vec![operand],
+            group_by,
+            without,
+        })))
+    }
+
+    /// Whether the next token starts the body of an aggregation, the metric
+    /// may be named as an aggregation op.
+    fn next_is_aggr_body(&self) -> bool {
+        match self.peek() {
+            Some(Token::LeftParen) => true,
+            Some(Token::Ident(keyword)) => keyword == "by" || keyword == "without",
+            _ => false,
+        }
+    }
+
+    /// Parse the optional `by (labels)` or `without (labels)` modifier.
+    fn parse_aggr_modifier(&mut self) -> Result<Option<(Vec<String>, bool)>> {
+        let without = match self.peek() {
+            Some(Token::Ident(keyword)) if keyword == "by" => false,
+            Some(Token::Ident(keyword)) if keyword == "without" => true,
+            _ => return Ok(None),
+        };
+        self.index += 1;
+
+        self.expect(Token::LeftParen)?;
+        let mut labels = Vec::new();
+        loop {
+            let (token, pos) = self.next_with_pos()?;
+            match token {
+                Token::RightParen => break,
+                Token::Ident(label) => labels.push(label),
+                token => {
+                    return InvalidQuery {
+                        pos,
+                        msg: format!("unexpected token {:?} in grouping labels", token),
+                    }
+                    .fail()
+                }
+            }
+            if self.peek() == Some(&Token::Comma) {
+                self.index += 1;
+            }
+        }
+
+        Ok(Some((labels, without)))
+    }
+
+    /// Parse the function call, the function name is already consumed.
+    fn parse_func_expr(&mut self, op: String) -> Result<Expr> {
+        self.expect(Token::LeftParen)?;
+        let mut operands = Vec::new();
+        while self.peek() != Some(&Token::RightParen) {
+            operands.push(self.parse_expr()?);
+            if self.peek() == Some(&Token::Comma) {
+                self.index += 1;
+            } else {
+                break;
+            }
+        }
+        self.expect(Token::RightParen)?;
+
+        ensure!(
+            !operands.is_empty(),
+            Unsupported {
+                msg: format!("function {} without arguments", op),
+            }
+        );
+
+        Ok(Expr::RecursiveExpr(SubExpr::Func(FuncExpr {
+            op,
+            operands,
+        })))
+    }
+
+    /// Parse the selector, the metric name or the left brace is already
+    /// consumed.
+    fn parse_selector(&mut self, mut table: Option<String>, start_pos: usize) -> Result<Expr> {
+        let mut filters = Vec::new();
+        let has_matchers = table.is_none() || self.peek() == Some(&Token::LeftBrace);
+        if has_matchers {
+            if table.is_some() {
+                self.index += 1;
+            }
+            for (name, typ, value) in self.parse_label_matchers()? {
+                if name == METRIC_NAME_LABEL {
+                    ensure!(
+                        matches!(typ, FilterType::LiteralOr) && table.is_none(),
+                        Unsupported {
+                            msg: "only one equal matcher of metric name is supported",
+                        }
+                    );
+                    table = Some(value);
+                    continue;
+                }
+
+                filters.push(Filter::from_matcher(name, typ, value));
+            }
+        }
+        let table = table.context(InvalidQuery {
+            pos: start_pos,
+            msg: "metric name is missing in selector",
+        })?;
+
+        let mut range = 0;
+        if self.peek() == Some(&Token::LeftBracket) {
+            self.index += 1;
+            range = self.parse_duration()?;
+            self.expect(Token::RightBracket)?;
+        }
+        let mut offset = 0;
+        if matches!(self.peek(), Some(Token::Ident(keyword)) if keyword == "offset") {
+            self.index += 1;
+            offset = self.parse_duration()?;
+        }
+
+        let EvalParams {
+            start,
+            end,
+            step,
+            field,
+        } = self.params.clone();
+        let lookback = if range > 0 { range } else { DEFAULT_LOOKBACK };
+        // The query range is [start - offset - lookback, end - offset] and the align
+        // range is [start, end].
+        let ranges = || {
+            Some((
+                start.checked_sub(offset)?.checked_sub(lookback)?,
+                end.checked_sub(offset)?.checked_add(1)?,
+                end.checked_add(1)?,
+            ))
+        };
+        let (query_start, query_end, align_end) = ranges().with_context(|| InvalidQuery {
+            pos: start_pos,
+            msg: format!(
+                "time range of selector overflows, range:{}, offset:{}",
+                range, offset
+            ),
+        })?;
+        Ok(Expr::SimpleExpr(Operand::Selector(Selector {
+            query_range: TimeRange::new_unchecked(
+                Timestamp::new(query_start),
+                Timestamp::new(query_end),
+            ),
+            table,
+            filters,
+            field,
+            align_range: TimeRange::new_unchecked(Timestamp::new(start), Timestamp::new(align_end)),
+            step,
+            range,
+            offset,
+        })))
+    }
+
+    /// Parse the label matchers until the right brace.
+    fn parse_label_matchers(&mut self) -> Result<Vec<(String, FilterType, String)>> {
+        let mut matchers = Vec::new();
+        loop {
+            let (token, pos) = self.next_with_pos()?;
+            let name = match token {
+                Token::RightBrace => break,
+                Token::Ident(name) => name,
+                token => {
+                    return InvalidQuery {
+                        pos,
+                        msg: format!("unexpected token {:?} in label matchers", token),
+                    }
+                    .fail()
+                }
+            };
+
+            let (token, pos) = self.next_with_pos()?;
+            let typ = match token {
+                Token::Match(typ) => typ,
+                token => {
+                    return InvalidQuery {
+                        pos,
+                        msg: format!("expect label match operator, found {:?}", token),
+                    }
+                    .fail()
+                }
+            };
+
+            let (token, pos) = self.next_with_pos()?;
+            let value = match token {
+                Token::String(value) => value,
+                token => {
+                    return InvalidQuery {
+                        pos,
+                        msg: format!("expect label value, found {:?}", token),
+                    }
+                    .fail()
+                }
+            };
+            matchers.push((name, typ, value));
+
+            match self.next_with_pos()? {
+                (Token::Comma, _) => continue,
+                (Token::RightBrace, _) => break,
+                (token, pos) => {
+                    return InvalidQuery {
+                        pos,
+                        msg: format!("unexpected token {:?} in label matchers", token),
+                    }
+                    .fail()
+                }
+            }
+        }
+
+        Ok(matchers)
+    }
+
+    /// Parse the duration like `1h30m` into milliseconds.
+    fn parse_duration(&mut self) -> Result<i64> {
+        let (token, pos) = self.next_with_pos()?;
+        match token {
+            Token::Number(duration) => ReadableDuration::from_str(&duration)
+                .ok()
+                .map(|duration| duration.as_millis() as i64)
+                .filter(|duration| *duration > 0)
+                .context(InvalidQuery {
+                    pos,
+                    msg: format!("invalid duration {}", duration),
+                }),
+            token => InvalidQuery {
+                pos,
+                msg: format!("expect duration, found {:?}", token),
+            }
+            .fail(),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn peek_with_pos(&self) -> Option<(&Token, usize)> {
+        self.tokens
+            .get(self.index)
+            .map(|(token, pos)| (token, *pos))
+    }
+
+    fn next_with_pos(&mut self) -> Result<(Token, usize)> {
+        let next = self.tokens.get(self.index).cloned().context(InvalidQuery {
+            pos: self.end_pos,
+            msg: "unexpected end of query",
+        })?;
+        self.index += 1;
+
+        Ok(next)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        let (token, pos) = self.next_with_pos()?;
+        ensure!(
+            token == expected,
+            InvalidQuery {
+                pos,
+                msg: format!("expect {:?}, found {:?}", expected, token),
+            }
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> EvalParams {
+        EvalParams {
+            start: 1_000_000,
+            end: 2_000_000,
+            step: 15_000,
+            field: "value".to_string(),
+        }
+    }
+
+    fn parse_selector(query: &str) -> Selector {
+        match parse(query, &params()).unwrap() {
+            Expr::SimpleExpr(Operand::Selector(selector)) => selector,
+            expr => panic!("unexpected expr:{:?}", expr),
+        }
+    }
+
+    #[test]
+    fn test_parse_selector() {
+        let selector =
+            parse_selector(r#"http_requests{job=~"api.*", code!="200", env='prod'}[5m] offset 1m"#);
+        assert_eq!("http_requests", selector.table);
+        assert_eq!("value", selector.field);
+        assert_eq!(300_000, selector.range);
+        assert_eq!(60_000, selector.offset);
+        assert_eq!(15_000, selector.step);
+        assert_eq!(
+            TimeRange::new_unchecked_for_test(1_000_000, 2_000_001),
+            selector.align_range
+        );
+        assert_eq!(
+            TimeRange::new_unchecked_for_test(640_000, 1_940_001),
+            selector.query_range
+        );
+
+        let filters: Vec<_> = selector
+            .filters
+            .iter()
+            .map(|f| {
+                (
+                    f.tag_key.as_str(),
+                    format!("{:?}", f.operators[0].typ),
+                    f.operators[0].params[0].as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("job", "Regexp".to_string(), "^(?:api.*)$"),
+                ("code", "NotLiteralOr".to_string(), "200"),
+                ("env", "LiteralOr".to_string(), "prod"),
+            ],
+            filters
+        );
+    }
+
+    #[test]
+    fn test_parse_selector_with_name_matcher() {
+        let selector = parse_selector(r#"{__name__="cpu", host="a"}"#);
+        assert_eq!("cpu", selector.table);
+        assert_eq!(0, selector.range);
+        assert_eq!(1, selector.filters.len());
+        assert_eq!(
+            TimeRange::new_unchecked_for_test(1_000_000 - DEFAULT_LOOKBACK, 2_000_001),
+            selector.query_range
+        );
+    }
+
+    #[test]
+    fn test_parse_func_and_aggr() {
+        for query in [
+            "sum by (job, code) (rate(http_requests[1m]))",
+            "sum(rate(http_requests[1m])) by (job, code)",
+        ] {
+            let expr = parse(query, &params()).unwrap();
+            let aggr = match expr {
+                Expr::RecursiveExpr(SubExpr::Aggr(aggr)) => aggr,
+                expr => panic!("unexpected expr:{:?}", expr),
+            };
+            assert_eq!("sum", aggr.op);
+            assert!(!aggr.without);
+            assert_eq!(vec!["job".to_string(), "code".to_string()], aggr.group_by);
+
+            match &aggr.operands[0] {
+                Expr::RecursiveExpr(SubExpr::Func(func)) => {
+                    assert_eq!("rate", func.op);
+                    assert_eq!(60_000, func.operands[0].get_selector().range);
+                }
+                expr => panic!("unexpected expr:{:?}", expr),
+            }
+        }
+
+        let expr = parse("max without (host) (cpu)", &params()).unwrap();
+        assert!(
+            matches!(expr, Expr::RecursiveExpr(SubExpr::Aggr(AggrExpr { without, .. })) if without)
+        );
+    }
+
+    #[test]
+    fn test_parse_literal() {
+        assert!(matches!(
+            parse("1.5", &params()).unwrap(),
+            Expr::SimpleExpr(Operand::Float(v)) if v == 1.5
+        ));
+        assert!(matches!(
+            parse(r#""a\"b""#, &params()).unwrap(),
+            Expr::SimpleExpr(Operand::String(v)) if v == "a\"b"
+        ));
+    }
+
+    #[test]
+    fn test_parse_invalid_query() {
+        for query in [
+            "",
+            "cpu{",
+            "cpu{host}",
+            r#"cpu{host="a"#,
+            "cpu[5x]",
+            "sum by (host (cpu)",
+            "rate()",
+            "cpu + 1",
+            r#"{host="a"}"#,
+            r#"cpu{__name__="mem"}"#,
+            "cpu mem",
+        ] {
+            assert!(parse(query, &params()).is_err(), "query:{}", query);
+        }
+
+        let params = EvalParams {
+            step: 0,
+            ..params()
+        };
+        assert!(parse("cpu", &params).is_err());
+    }
+
+    #[test]
+    fn test_parse_deeply_nested_query() {
+        let query = format!("{}cpu{}", "(".repeat(100), ")".repeat(100));
+        assert!(parse(&query, &params()).is_ok());
+
+        for query in [
+            "(".repeat(200_000),
+            "sum(".repeat(200_000),
+            "rate(".repeat(200_000),
+            format!("{}cpu{}", "(".repeat(1000), ")".repeat(1000)),
+        ] {
+            assert!(parse(&query, &params()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_overflowed_time_range() {
+        let params = EvalParams {
+            start: i64::MIN + 1,
+            end: i64::MAX,
+            ..params()
+        };
+        assert!(parse("cpu", &params).is_err());
+    }
+}
@@ -0,0 +1,545 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Route aggregate queries to the rollup table
+//!
+//! A query like
+//! ```sql
+//! SELECT time_bucket(timestamp, 'PT1H'), host, avg(value) FROM cpu
+//! GROUP BY time_bucket(timestamp, 'PT1H'), host
+//! ```
+//! can be answered by the rollup table of `cpu` if the buckets of the query are
+//! multiples of the rollup interval, so it's rewritten to aggregate the states
+//! in the rollup table instead of the raw rows.
+//!
+//! The rollup table is filled during compaction, so the rows not compacted yet
+//! are absent in it. The table reports a watermark, all the rows before it have
+//! been rolled up, thus the routed query reads the rollup table for the time
+//! before the watermark and the raw rows for the time after it, and aggregates
+//! the states of both.
+
+use std::{collections::HashSet, convert::TryFrom, sync::Arc};
+
+use arrow_deps::{
+    arrow::{compute::kernels::cast_utils::string_to_timestamp_nanos, datatypes::DataType},
+    datafusion::{
+        datasource::TableProvider,
+        error::{DataFusionError, Result},
+        logical_plan::{
+            plan::{Aggregate, Filter},
+            union_with_alias, Column, Expr, LogicalPlan, LogicalPlanBuilder, Operator, TableScan,
+        },
+        optimizer::utils,
+        physical_plan::aggregates::AggregateFunction as DfAggregateFunction,
+        scalar::ScalarValue,
+        sql::planner::ContextProvider,
+    },
+};
+use common_types::{schema::Schema, time::Timestamp};
+use df_operator::udfs::time_bucket::Period;
+use log::debug;
+use table_engine::{
+    provider::TableProviderAdapter,
+    rollup::{self, AggregateFunction, AggregateState, RollupOptions},
+};
+
+use crate::provider::{ContextProviderAdapter, MetaProvider};
+
+pub(crate) const TIME_BUCKET_FUNCTION: &str = "time_bucket";
+
+/// Rewrites the aggregations over the tables with rollup to read the rollup
+/// tables if possible.
+pub struct RollupRewriter<'a, 'b, P> {
+    meta_provider: &'a ContextProviderAdapter<'b, P>,
+}
+
+impl<'a, 'b, P: MetaProvider> RollupRewriter<'a, 'b, P> {
+    pub fn new(meta_provider: &'a ContextProviderAdapter<'b, P>) -> Self {
+        Self { meta_provider }
+    }
+
+    /// Returns the rewritten plan, or the origin plan if nothing can be
+    /// routed to the rollup table.
+    pub fn rewrite(&self, plan: LogicalPlan) -> LogicalPlan {
+        match self.rewrite_plan(&plan) {
+            Ok(Some(new_plan)) => {
+                debug!("Rollup rewrite plan, new_plan:\n{:#?}", new_plan);
+                new_plan
+            }
+            Ok(None) => plan,
+            Err(e) => {
+                debug!("Failed to rewrite plan by rollup, err:{}", e);
+                plan
+            }
+        }
+    }
+
+    /// Returns None if the plan is not changed.
+    fn rewrite_plan(&self, plan: &LogicalPlan) -> Result<Option<LogicalPlan>> {
+        if let LogicalPlan::Aggregate(aggregate) = plan {
+            if let Some(new_plan) = self.rewrite_aggregate(aggregate)? {
+                return Ok(Some(new_plan));
+            }
+        }
+
+        let inputs = plan.inputs();
+        let mut changed = false;
+        let mut new_inputs = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            match self.rewrite_plan(input)? {
+                Some(new_input) => {
+                    changed = true;
+                    new_inputs.push(new_input);
+                }
+                None => new_inputs.push(input.clone()),
+            }
+        }
+
+        if !changed {
+            return Ok(None);
+        }
+
+        utils::from_plan(plan, &plan.expressions(), &new_inputs).map(Some)
+    }
+
+    fn rewrite_aggregate(&self, aggregate: &Aggregate) -> Result<Option<LogicalPlan>> {
+        let (scan, predicate) = match aggregate.input.as_ref() {
+            LogicalPlan::Filter(Filter { predicate, input }) => match input.as_ref() {
+                LogicalPlan::TableScan(scan) => (scan, Some(predicate)),
+                _ => return Ok(None),
+            },
+            LogicalPlan::TableScan(scan) => (scan, None),
+            _ => return Ok(None),
+        };
+        let (rollup, watermark) = match rollup_of(scan.source.as_ref()) {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        // Nothing is rolled up yet.
+        if watermark == Timestamp::MIN {
+            return Ok(None);
+        }
+        // Only the buckets before the watermark are complete in the rollup table.
+        let interval_millis = rollup.interval.as_millis() as i64;
+        let watermark = match watermark
+            .as_i64()
+            .checked_sub(watermark.as_i64().rem_euclid(interval_millis))
+        {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let rollup_table = rollup::rollup_table_name(&scan.table_name);
+        let rollup_provider = match self
+            .meta_provider
+            .get_table_provider(rollup_table.as_str().into())
+        {
+            Some(v) => v,
+            None => {
+                debug!(
+                    "Rollup table not found, table:{}, rollup_table:{}",
+                    scan.table_name, rollup_table
+                );
+                return Ok(None);
+            }
+        };
+        let rollup_schema = Schema::try_from(rollup_provider.schema())
+            .map_err(|e| DataFusionError::Plan(e.to_string()))?;
+
+        let matcher = RollupMatcher {
+            rollup: &rollup,
+            rollup_schema: &rollup_schema,
+        };
+        if !matcher.match_group_exprs(&aggregate.group_expr)
+            || !predicate.map_or(true, |v| matcher.match_predicate(v))
+        {
+            return Ok(None);
+        }
+        let routed_aggregates = match aggregate
+            .aggr_expr
+            .iter()
+            .map(|expr| matcher.route_aggregate(expr))
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        let routed_plan = RoutedPlan {
+            scan,
+            rollup_provider,
+            timestamp_name: matcher.timestamp_name(),
+            watermark,
+            predicate,
+        };
+        routed_plan.build(aggregate, &routed_aggregates).map(Some)
+    }
+}
+
+/// Returns the rollup options and the rollup watermark of the non-partitioned
+/// table.
+fn rollup_of(source: &dyn TableProvider) -> Option<(RollupOptions, Timestamp)> {
+    let table = source
+        .as_any()
+        .downcast_ref::<TableProviderAdapter>()?
+        .as_table_ref();
+    if table.partition_info().is_some() {
+        return None;
+    }
+
+    let rollup = RollupOptions::parse_from(&table.options()).ok().flatten()?;
+    let watermark = table.rollup_watermark()?;
+    Some((rollup, watermark))
+}
+
+/// Aggregation of the origin query answered by the states of the rollup table.
+struct RoutedAggregate {
+    function: AggregateFunction,
+    column: String,
+}
+
+struct RollupMatcher<'a> {
+    rollup: &'a RollupOptions,
+    rollup_schema: &'a Schema,
+}
+
+impl<'a> RollupMatcher<'a> {
+    fn timestamp_name(&self) -> &str {
+        self.rollup_schema.timestamp_name()
+    }
+
+    /// Returns true if the column is a key or tag column of the rollup table.
+    fn is_dimension(&self, name: &str) -> bool {
+        self.rollup_schema.column_with_name(name).is_some() && !self.is_state_column(name)
+    }
+
+    fn is_state_column(&self, name: &str) -> bool {
+        self.rollup.aggregates.iter().any(|aggregate| {
+            aggregate
+                .function
+                .states()
+                .iter()
+                .any(|state| rollup::state_column_name(&aggregate.column, *state) == name)
+        })
+    }
+
+    fn is_timestamp(&self, expr: &Expr) -> bool {
+        matches!(expr, Expr::Column(column) if column.name == self.timestamp_name())
+    }
+
+    /// Group exprs must contain a coarse enough time bucket, and other exprs
+    /// must be dimension columns.
+    fn match_group_exprs(&self, group_exprs: &[Expr]) -> bool {
+        let mut has_time_bucket = false;
+        for expr in group_exprs {
+            match expr {
+                Expr::ScalarUDF { fun, args } if fun.name == TIME_BUCKET_FUNCTION => {
+                    if !self.is_aligned_time_bucket(args) {
+                        return false;
+                    }
+                    has_time_bucket = true;
+                }
+                Expr::Column(column) if column.name != self.timestamp_name() => {
+                    if !self.is_dimension(&column.name) {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+
+        has_time_bucket
+    }
+
+    fn is_aligned_time_bucket(&self, args: &[Expr]) -> bool {
+        // The optional format and timezone arguments are ignored by time_bucket.
+        match args {
+            [ts, Expr::Literal(ScalarValue::Utf8(Some(period))), ..] if self.is_timestamp(ts) => {
+                Period::parse(period)
+                    .map(|period| period.is_aligned_to(self.rollup.interval.0))
+                    .unwrap_or(false)
+            }
+            _ => false,
+        }
+    }
+
+    /// Filters must only reference dimension columns, and the time range must
+    /// be aligned to the rollup interval.
+    fn match_predicate(&self, predicate: &Expr) -> bool {
+        let mut conjuncts = Vec::new();
+        split_conjunction(predicate, &mut conjuncts);
+
+        conjuncts.into_iter().all(|expr| {
+            let mut columns = HashSet::new();
+            if utils::expr_to_columns(expr, &mut columns).is_err() {
+                return false;
+            }
+            if columns.iter().any(|c| c.name == self.timestamp_name()) {
+                return self.is_aligned_time_filter(expr);
+            }
+
+            columns.iter().all(|c| self.is_dimension(&c.name))
+        })
+    }
+
+    fn is_aligned_time_filter(&self, expr: &Expr) -> bool {
+        let (op, value) = match expr {
+            Expr::BinaryExpr { left, op, right } => match (left.as_ref(), right.as_ref()) {
+                (column, Expr::Literal(value)) if self.is_timestamp(column) => (*op, value),
+                (Expr::Literal(value), column) if self.is_timestamp(column) => {
+                    let op = match op {
+                        Operator::LtEq => Operator::GtEq,
+                        Operator::Gt => Operator::Lt,
+                        _ => return false,
+                    };
+                    (op, value)
+                }
+                _ => return false,
+            },
+            _ => return false,
+        };
+        if !matches!(op, Operator::GtEq | Operator::Lt) {
+            return false;
+        }
+
+        let interval_millis = self.rollup.interval.as_millis() as i64;
+        timestamp_millis_of(value)
+            .map(|ts| interval_millis > 0 && ts % interval_millis == 0)
+            .unwrap_or(false)
+    }
+
+    fn route_aggregate(&self, expr: &Expr) -> Option<RoutedAggregate> {
+        let (fun, args) = match expr {
+            Expr::AggregateFunction {
+                fun,
+                args,
+                distinct: false,
+            } => (fun, args),
+            _ => return None,
+        };
+        let column = match args.as_slice() {
+            [Expr::Column(column)] => column.name.clone(),
+            _ => return None,
+        };
+        let function = match fun {
+            DfAggregateFunction::Avg => AggregateFunction::Avg,
+            DfAggregateFunction::Min => AggregateFunction::Min,
+            DfAggregateFunction::Max => AggregateFunction::Max,
+            DfAggregateFunction::Sum => AggregateFunction::Sum,
+            DfAggregateFunction::Count => AggregateFunction::Count,
+            _ => return None,
+        };
+
+        let all_states_exist = function.states().iter().all(|state| {
+            let name = rollup::state_column_name(&column, *state);
+            self.rollup_schema.column_with_name(&name).is_some()
+        });
+        if !all_states_exist {
+            return None;
+        }
+
+        Some(RoutedAggregate { function, column })
+    }
+}
+
+/// Returns the expr to aggregate the `state` column in the rollup table.
+fn state_aggregate_expr(table_name: &str, column: &str, state: AggregateState) -> Expr {
+    let fun = match state {
+        AggregateState::Sum | AggregateState::Count => DfAggregateFunction::Sum,
+        AggregateState::Min => DfAggregateFunction::Min,
+        AggregateState::Max => DfAggregateFunction::Max,
+    };
+    let state_column = Column {
+        relation: Some(table_name.to_string()),
+        name: rollup::state_column_name(column, state),
+    };
+
+    Expr::AggregateFunction {
+        fun,
+        args: vec![Expr::Column(state_column)],
+        distinct: false,
+    }
+}
+
+/// Sources of the routed aggregation, the rollup table before the `watermark`
+/// and the table after it.
+struct RoutedPlan<'a> {
+    scan: &'a TableScan,
+    rollup_provider: Arc<dyn TableProvider>,
+    timestamp_name: &'a str,
+    watermark: i64,
+    predicate: Option<&'a Expr>,
+}
+
+impl<'a> RoutedPlan<'a> {
+    fn column(&self, name: &str) -> Expr {
+        Expr::Column(Column {
+            relation: Some(self.scan.table_name.clone()),
+            name: name.to_string(),
+        })
+    }
+
+    fn filter_expr(&self, op: Operator) -> Expr {
+        let time_filter = Expr::BinaryExpr {
+            left: Box::new(self.column(self.timestamp_name)),
+            op,
+            right: Box::new(Expr::Literal(ScalarValue::TimestampMillisecond(
+                Some(self.watermark),
+                None,
+            ))),
+        };
+
+        match self.predicate {
+            Some(predicate) => predicate.clone().and(time_filter),
+            None => time_filter,
+        }
+    }
+
+    /// Returns the expr to compute the `state` of `column` from the raw rows,
+    /// which has the same type as the state column in the rollup table.
+    fn raw_state_expr(&self, column: &str, state: AggregateState) -> Result<Expr> {
+        let name = rollup::state_column_name(column, state);
+        let rollup_schema = self.rollup_provider.schema();
+        let data_type = rollup_schema.field_with_name(&name)?.data_type().clone();
+        let expr = match state {
+            AggregateState::Count => Expr::Case {
+                expr: None,
+                when_then_expr: vec![(
+                    Box::new(Expr::IsNull(Box::new(self.column(column)))),
+                    Box::new(Expr::Literal(ScalarValue::UInt64(Some(0)))),
+                )],
+                else_expr: Some(Box::new(Expr::Literal(ScalarValue::UInt64(Some(1))))),
+            },
+            AggregateState::Sum | AggregateState::Min | AggregateState::Max => self.column(column),
+        };
+
+        Ok(Expr::Alias(Box::new(cast_expr(expr, data_type)), name))
+    }
+
+    /// Build the plan which aggregates the states in the rollup table and the
+    /// states of the raw rows, and projects to the same schema as the origin
+    /// aggregate plan.
+    fn build(
+        &self,
+        aggregate: &Aggregate,
+        routed_aggregates: &[RoutedAggregate],
+    ) -> Result<LogicalPlan> {
+        let table_name = &self.scan.table_name;
+        let mut states = Vec::new();
+        for routed in routed_aggregates {
+            for state in routed.function.states() {
+                if !states.contains(&(routed.column.as_str(), *state)) {
+                    states.push((routed.column.as_str(), *state));
+                }
+            }
+        }
+        let mut group_columns = HashSet::new();
+        for expr in &aggregate.group_expr {
+            utils::expr_to_columns(expr, &mut group_columns)?;
+        }
+        let mut group_columns: Vec<_> = group_columns.into_iter().map(Expr::Column).collect();
+        group_columns.sort_by_key(|expr| expr.to_string());
+
+        // Use the origin table name as the qualifier, so the columns referenced
+        // by the exprs are still valid.
+        let mut rollup_exprs = group_columns.clone();
+        for (column, state) in &states {
+            rollup_exprs.push(self.column(&rollup::state_column_name(column, *state)));
+        }
+        let rollup_plan =
+            LogicalPlanBuilder::scan(table_name.clone(), self.rollup_provider.clone(), None)?
+                .filter(self.filter_expr(Operator::Lt))?
+                .project(rollup_exprs)?
+                .build()?;
+
+        let mut raw_exprs = group_columns;
+        for (column, state) in &states {
+            raw_exprs.push(self.raw_state_expr(column, *state)?);
+        }
+        let raw_plan =
+            LogicalPlanBuilder::scan(table_name.clone(), self.scan.source.clone(), None)?
+                .filter(self.filter_expr(Operator::GtEq))?
+                .project(raw_exprs)?
+                .build()?;
+
+        let union_plan = union_with_alias(rollup_plan, raw_plan, Some(table_name.clone()))?;
+        let state_exprs: Vec<_> = states
+            .iter()
+            .map(|(column, state)| state_aggregate_expr(table_name, column, *state))
+            .collect();
+        let builder = LogicalPlanBuilder::from(union_plan)
+            .aggregate(aggregate.group_expr.clone(), state_exprs.clone())?;
+
+        let num_groups = aggregate.group_expr.len();
+        let aggregate_schema = builder.schema().clone();
+        let state_column_of = |column: &str, state: AggregateState| {
+            let expr = state_aggregate_expr(table_name, column, state);
+            let index = state_exprs.iter().position(|v| *v == expr).unwrap();
+            Expr::Column(
+                aggregate_schema
+                    .field(num_groups + index)
+                    .qualified_column(),
+            )
+        };
+
+        let mut project_exprs: Vec<_> = (0..num_groups)
+            .map(|i| Expr::Column(aggregate_schema.field(i).qualified_column()))
+            .collect();
+        for (i, routed) in routed_aggregates.iter().enumerate() {
+            let origin_field = aggregate.schema.field(num_groups + i);
+            let expr = match routed.function {
+                AggregateFunction::Avg => Expr::BinaryExpr {
+                    left: Box::new(cast_expr(
+                        state_column_of(&routed.column, AggregateState::Sum),
+                        DataType::Float64,
+                    )),
+                    op: Operator::Divide,
+                    right: Box::new(cast_expr(
+                        state_column_of(&routed.column, AggregateState::Count),
+                        DataType::Float64,
+                    )),
+                },
+                AggregateFunction::Min => state_column_of(&routed.column, AggregateState::Min),
+                AggregateFunction::Max => state_column_of(&routed.column, AggregateState::Max),
+                AggregateFunction::Sum => state_column_of(&routed.column, AggregateState::Sum),
+                AggregateFunction::Count => state_column_of(&routed.column, AggregateState::Count),
+            };
+
+            project_exprs.push(Expr::Alias(
+                Box::new(cast_expr(expr, origin_field.data_type().clone())),
+                origin_field.name().clone(),
+            ));
+        }
+
+        builder.project(project_exprs)?.build()
+    }
+}
+
+fn cast_expr(expr: Expr, data_type: DataType) -> Expr {
+    Expr::Cast {
+        expr: Box::new(expr),
+        data_type,
+    }
+}
+
+fn split_conjunction<'a>(expr: &'a Expr, exprs: &mut Vec<&'a Expr>) {
+    match expr {
+        Expr::BinaryExpr {
+            left,
+            op: Operator::And,
+            right,
+        } => {
+            split_conjunction(left, exprs);
+            split_conjunction(right, exprs);
+        }
+        expr => exprs.push(expr),
+    }
+}
+
+fn timestamp_millis_of(value: &ScalarValue) -> Option<i64> {
+    match value {
+        ScalarValue::TimestampMillisecond(Some(v), _) | ScalarValue::Int64(Some(v)) => Some(*v),
+        ScalarValue::Utf8(Some(s)) => string_to_timestamp_nanos(s)
+            .ok()
+            .map(|nanos| nanos / 1_000_000),
+        _ => None,
+    }
+}
@@ -1,13 +1,18 @@
 // Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use arrow_deps::datafusion::catalog::TableReference;
 use catalog::consts::{DEFAULT_CATALOG, DEFAULT_SCHEMA};
-use common_types::tests::build_schema;
-use df_operator::{scalar::ScalarUdf, udaf::AggregateUdf};
+use common_types::{tests::build_schema, time::Timestamp};
+use df_operator::{
+    registry::{FunctionRegistry, FunctionRegistryImpl},
+    scalar::ScalarUdf,
+    udaf::AggregateUdf,
+};
 use table_engine::{
     memory::MemoryTable,
+    rollup::{self, RollupOptions},
     table::{Table, TableId, TableRef},
     ANALYTIC_ENGINE_TYPE,
 };
@@ -16,10 +21,29 @@ use crate::provider::MetaProvider;
 
 pub struct MockMetaProvider {
     tables: Vec<Arc<MemoryTable>>,
+    function_registry: FunctionRegistryImpl,
 }
 
 impl Default for MockMetaProvider {
     fn default() -> Self {
+        let mut rollup_options = HashMap::new();
+        rollup_options.insert(
+            rollup::OPTION_KEY_ROLLUP_INTERVAL.to_string(),
+            "5m".to_string(),
+        );
+        rollup_options.insert(
+            rollup::OPTION_KEY_ROLLUP_AGGREGATES.to_string(),
+            "field1=avg".to_string(),
+        );
+        let rollup_schema = RollupOptions::parse_from(&rollup_options)
+            .unwrap()
+            .unwrap()
+            .build_rollup_schema(&build_schema())
+            .unwrap();
+
+        let mut function_registry = FunctionRegistryImpl::new();
+        function_registry.load_functions().unwrap();
+
         Self {
             tables: vec![
                 Arc::new(MemoryTable::new(
@@ -34,7 +58,25 @@ impl Default for MockMetaProvider {
                     build_schema(),
                     ANALYTIC_ENGINE_TYPE.to_string(),
                 )),
+                Arc::new(
+                    MemoryTable::new(
+                        "rollup_table".to_string(),
+                        TableId::from(102),
+                        build_schema(),
+                        ANALYTIC_ENGINE_TYPE.to_string(),
+                    )
+                    .with_options(rollup_options)
+                    // The rows before 2022-05-05T08:00:00Z are rolled up.
+                    .with_rollup_watermark(Timestamp::new(1651737700000)),
+                ),
+                Arc::new(MemoryTable::new(
+                    rollup::rollup_table_name("rollup_table"),
+                    TableId::from(103),
+                    rollup_schema,
+                    ANALYTIC_ENGINE_TYPE.to_string(),
+                )),
             ],
+            function_registry,
         }
     }
 }
@@ -59,11 +101,11 @@ impl MetaProvider for MockMetaProvider {
         Ok(None)
     }
 
-    fn scalar_udf(&self, _name: &str) -> crate::provider::Result<Option<ScalarUdf>> {
-        todo!()
+    fn scalar_udf(&self, name: &str) -> crate::provider::Result<Option<ScalarUdf>> {
+        Ok(self.function_registry.find_udf(name).unwrap())
     }
 
-    fn aggregate_udf(&self, _name: &str) -> crate::provider::Result<Option<AggregateUdf>> {
-        todo!()
+    fn aggregate_udf(&self, name: &str) -> crate::provider::Result<Option<AggregateUdf>> {
+        Ok(self.function_registry.find_udaf(name).unwrap())
     }
 }
@@ -16,7 +16,10 @@ use server::{
     server::Builder,
     table_engine::{MemoryTableEngine, TableEngineProxy},
 };
-use table_engine::engine::EngineRuntimes;
+use table_engine::{
+    engine::{EngineRuntimes, TableEngineRef},
+    partition::engine::PartitionTableEngine,
+};
 use tracing_util::{
     self,
     tracing_appender::{non_blocking::WorkerGuard, rolling::Rotation},
@@ -83,6 +86,15 @@ pub fn run_server(config: Config) {
             memory,
             analytic: analytic.clone(),
         });
+        // Support partitioned tables on top of the proxy
+        let engine_proxy: TableEngineRef = Arc::new(PartitionTableEngine::new(engine_proxy));
+
+        // Init function registry.
+        let mut function_registry = FunctionRegistryImpl::new();
+        function_registry.load_functions().unwrap_or_else(|e| {
+            panic!("Failed to create function registry, err:{}", e);
+        });
+        let function_registry = Arc::new(function_registry);
 
         // Create catalog manager, use analytic table as backend
         let catalog_manager = CatalogManagerImpl::new(
@@ -91,15 +103,9 @@ pub fn run_server(config: Config) {
                 .unwrap_or_else(|e| {
                     panic!("Failed to create catalog manager, err:{}", e);
                 }),
+            function_registry.clone(),
         );
 
-        // Init function registry.
-        let mut function_registry = FunctionRegistryImpl::new();
-        function_registry.load_functions().unwrap_or_else(|e| {
-            panic!("Failed to create function registry, err:{}", e);
-        });
-        let function_registry = Arc::new(function_registry);
-
         // Create query executor
         let query_executor = ExecutorImpl::new();
 
@@ -0,0 +1,121 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+/// implementation of system table: Columns
+/// For example `SELECT * FROM system.public.columns`
+use std::fmt::{Debug, Formatter};
+
+use async_trait::async_trait;
+use catalog::{manager::Manager, schema::SchemaRef, CatalogRef};
+use common_types::{
+    datum::{Datum, DatumKind},
+    row::Row,
+    schema::Schema,
+};
+use table_engine::{
+    stream::SendableRecordBatchStream,
+    table::{ReadRequest, TableId, TableRef},
+};
+
+use crate::{
+    build_stream, filter::TableNameFilter, for_each_table, new_column, table_key_datums,
+    table_schema_builder, SystemTable, COLUMNS_TABLE_ID, COLUMNS_TABLE_NAME,
+};
+
+/// Build a new table schema for columns
+fn columns_schema() -> Schema {
+    table_schema_builder(11)
+        .add_key_column(new_column("column_name", DatumKind::String))
+        .unwrap()
+        .add_normal_column(new_column("column_id", DatumKind::UInt32))
+        .unwrap()
+        .add_normal_column(new_column("data_type", DatumKind::String))
+        .unwrap()
+        .add_normal_column(new_column("is_nullable", DatumKind::Boolean))
+        .unwrap()
+        .add_normal_column(new_column("is_tag", DatumKind::Boolean))
+        .unwrap()
+        .add_normal_column(new_column("is_key", DatumKind::Boolean))
+        .unwrap()
+        .add_normal_column(new_column("comment", DatumKind::String))
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+pub struct Columns<M> {
+    schema: Schema,
+    catalog_manager: M,
+}
+
+impl<M> Debug for Columns<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SysColumns")
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl<M: Manager> Columns<M> {
+    pub fn new(catalog_manager: M) -> Self {
+        Self {
+            schema: columns_schema(),
+            catalog_manager,
+        }
+    }
+
+    fn from_table(
+        &self,
+        catalog: &CatalogRef,
+        schema: &SchemaRef,
+        table: &TableRef,
+        rows: &mut Vec<Row>,
+    ) {
+        let table_schema = table.schema();
+        let num_key_columns = table_schema.num_key_columns();
+        for (i, column) in table_schema.columns().iter().enumerate() {
+            let mut datums = table_key_datums(catalog, schema, table);
+            datums.push(Datum::from(column.name.as_str()));
+            datums.push(Datum::from(column.id));
+            datums.push(Datum::from(column.data_type.to_string().as_str()));
+            datums.push(Datum::from(column.is_nullable));
+            datums.push(Datum::from(column.is_tag));
+            datums.push(Datum::from(i < num_key_columns));
+            datums.push(Datum::from(column.comment.as_str()));
+            rows.push(Row::from_datums(datums));
+        }
+    }
+}
+
+#[async_trait]
+impl<M: Manager> SystemTable for Columns<M> {
+    fn name(&self) -> &str {
+        COLUMNS_TABLE_NAME
+    }
+
+    fn id(&self) -> TableId {
+        COLUMNS_TABLE_ID
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    async fn read(
+        &self,
+        request: ReadRequest,
+    ) -> table_engine::table::Result<SendableRecordBatchStream> {
+        let filter = TableNameFilter::new(&request.predicate, "table_name");
+        let mut rows = Vec::new();
+        for_each_table(
+            &self.catalog_manager,
+            self.name(),
+            &filter,
+            |catalog, schema, table| {
+                self.from_table(catalog, schema, table, &mut rows);
+                Ok(())
+            },
+        )?;
+
+        build_stream(self.name(), &self.schema, &request, rows)
+    }
+}
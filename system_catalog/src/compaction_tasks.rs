@@ -0,0 +1,113 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+/// implementation of system table: CompactionTasks
+/// For example `SELECT * FROM system.public.compaction_tasks`
+use std::fmt::{Debug, Formatter};
+
+use async_trait::async_trait;
+use catalog::{manager::Manager, schema::SchemaRef, CatalogRef};
+use common_types::{
+    datum::{Datum, DatumKind},
+    row::Row,
+    schema::Schema,
+};
+use table_engine::{
+    stream::SendableRecordBatchStream,
+    table::{ReadRequest, TableId, TableRef},
+};
+
+use crate::{
+    build_stream, filter::TableNameFilter, for_each_table, new_column, table_key_datums,
+    table_schema_builder, SystemTable, COMPACTION_TASKS_TABLE_ID, COMPACTION_TASKS_TABLE_NAME,
+};
+
+/// Build a new table schema for compaction tasks
+fn compaction_tasks_schema() -> Schema {
+    table_schema_builder(9)
+        .add_key_column(new_column("request_id", DatumKind::UInt64))
+        .unwrap()
+        .add_normal_column(new_column("start_time", DatumKind::Timestamp))
+        .unwrap()
+        .add_normal_column(new_column("num_input_files", DatumKind::UInt64))
+        .unwrap()
+        .add_normal_column(new_column("input_size", DatumKind::UInt64))
+        .unwrap()
+        .add_normal_column(new_column("num_expired_files", DatumKind::UInt64))
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+pub struct CompactionTasks<M> {
+    schema: Schema,
+    catalog_manager: M,
+}
+
+impl<M> Debug for CompactionTasks<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SysCompactionTasks")
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl<M: Manager> CompactionTasks<M> {
+    pub fn new(catalog_manager: M) -> Self {
+        Self {
+            schema: compaction_tasks_schema(),
+            catalog_manager,
+        }
+    }
+
+    fn from_table(
+        &self,
+        catalog: &CatalogRef,
+        schema: &SchemaRef,
+        table: &TableRef,
+        rows: &mut Vec<Row>,
+    ) {
+        for task in table.compaction_tasks() {
+            let mut datums = table_key_datums(catalog, schema, table);
+            datums.push(Datum::from(task.request_id));
+            datums.push(Datum::Timestamp(task.start_time));
+            datums.push(Datum::from(task.num_input_files));
+            datums.push(Datum::from(task.input_size));
+            datums.push(Datum::from(task.num_expired_files));
+            rows.push(Row::from_datums(datums));
+        }
+    }
+}
+
+#[async_trait]
+impl<M: Manager> SystemTable for CompactionTasks<M> {
+    fn name(&self) -> &str {
+        COMPACTION_TASKS_TABLE_NAME
+    }
+
+    fn id(&self) -> TableId {
+        COMPACTION_TASKS_TABLE_ID
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    async fn read(
+        &self,
+        request: ReadRequest,
+    ) -> table_engine::table::Result<SendableRecordBatchStream> {
+        let filter = TableNameFilter::new(&request.predicate, "table_name");
+        let mut rows = Vec::new();
+        for_each_table(
+            &self.catalog_manager,
+            self.name(),
+            &filter,
+            |catalog, schema, table| {
+                self.from_table(catalog, schema, table, &mut rows);
+                Ok(())
+            },
+        )?;
+
+        build_stream(self.name(), &self.schema, &request, rows)
+    }
+}
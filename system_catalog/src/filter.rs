@@ -0,0 +1,83 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Filter on the table name pushed down to the system tables
+
+use std::collections::HashSet;
+
+use common_types::datum::DatumKind;
+use table_engine::predicate::Predicate;
+
+/// Filter extracted from the `column = 'name'` and `column IN ('name', ...)`
+/// exprs of the predicate, so the system tables can skip the tables not
+/// queried.
+///
+/// The filter is inexact, the rows returned are still filtered by the query
+/// engine.
+#[derive(Debug)]
+pub struct TableNameFilter {
+    /// Names allowed by the filter, None if all names are allowed.
+    names: Option<HashSet<String>>,
+}
+
+impl TableNameFilter {
+    /// Build a filter on `column` from the exprs of the `predicate`.
+    pub fn new(predicate: &Predicate, column: &str) -> Self {
+        let names = predicate
+            .values_of_column(column, DatumKind::String)
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|v| v.as_str().map(|name| name.to_string()))
+                    .collect()
+            });
+
+        Self { names }
+    }
+
+    /// Returns true if the table named `name` should be read.
+    pub fn contains(&self, name: &str) -> bool {
+        match &self.names {
+            Some(names) => names.contains(name),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_deps::datafusion::logical_plan::{col, lit, Expr};
+    use common_types::time::TimeRange;
+
+    use super::*;
+
+    fn new_predicate(exprs: Vec<Expr>) -> Predicate {
+        Predicate {
+            exprs,
+            time_range: TimeRange::min_to_max(),
+        }
+    }
+
+    #[test]
+    fn test_table_name_filter() {
+        let filter = TableNameFilter::new(&Predicate::empty(), "table_name");
+        assert!(filter.contains("t1"));
+
+        let predicate = new_predicate(vec![col("table_name").eq(lit("t1"))]);
+        let filter = TableNameFilter::new(&predicate, "table_name");
+        assert!(filter.contains("t1"));
+        assert!(!filter.contains("t2"));
+
+        let predicate = new_predicate(vec![
+            col("table_name").in_list(vec![lit("t1"), lit("t2")], false),
+            lit("t2").eq(col("table_name")),
+        ]);
+        let filter = TableNameFilter::new(&predicate, "table_name");
+        assert!(!filter.contains("t1"));
+        assert!(filter.contains("t2"));
+
+        // Exprs on other columns are ignored.
+        let predicate = new_predicate(vec![col("schema").eq(lit("t1"))]);
+        let filter = TableNameFilter::new(&predicate, "table_name");
+        assert!(filter.contains("t2"));
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+/// implementation of system table: Functions
+/// For example `SELECT * FROM system.public.functions`
+use std::fmt::{Debug, Formatter};
+
+use async_trait::async_trait;
+use common_types::{
+    datum::{Datum, DatumKind},
+    row::Row,
+    schema::{self, Schema},
+};
+use df_operator::registry::FunctionRegistryRef;
+use snafu::ResultExt;
+use table_engine::{
+    stream::SendableRecordBatchStream,
+    table::{ReadRequest, TableId},
+};
+
+use crate::{
+    build_stream, filter::TableNameFilter, new_column, tables::ENTRY_TIMESTAMP, SystemTable,
+    FUNCTIONS_TABLE_ID, FUNCTIONS_TABLE_NAME,
+};
+
+const SCALAR_FUNCTION_KIND: &str = "scalar";
+const AGGREGATE_FUNCTION_KIND: &str = "aggregate";
+
+/// Build a new table schema for functions
+fn functions_schema() -> Schema {
+    schema::Builder::with_capacity(3)
+        .auto_increment_column_id(true)
+        .add_key_column(new_column("timestamp", DatumKind::Timestamp))
+        .unwrap()
+        .add_key_column(new_column("name", DatumKind::String))
+        .unwrap()
+        .add_normal_column(new_column("kind", DatumKind::String))
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+pub struct Functions {
+    schema: Schema,
+    function_registry: FunctionRegistryRef,
+}
+
+impl Debug for Functions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SysFunctions")
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl Functions {
+    pub fn new(function_registry: FunctionRegistryRef) -> Self {
+        Self {
+            schema: functions_schema(),
+            function_registry,
+        }
+    }
+
+    fn from_function(name: &str, kind: &str) -> Row {
+        Row::from_datums(vec![
+            Datum::Timestamp(ENTRY_TIMESTAMP),
+            Datum::from(name),
+            Datum::from(kind),
+        ])
+    }
+}
+
+#[async_trait]
+impl SystemTable for Functions {
+    fn name(&self) -> &str {
+        FUNCTIONS_TABLE_NAME
+    }
+
+    fn id(&self) -> TableId {
+        FUNCTIONS_TABLE_ID
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    async fn read(
+        &self,
+        request: ReadRequest,
+    ) -> table_engine::table::Result<SendableRecordBatchStream> {
+        let filter = TableNameFilter::new(&request.predicate, "name");
+        let udfs = self
+            .function_registry
+            .list_udfs()
+            .map_err(|e| Box::new(e) as _)
+            .context(table_engine::table::Scan { table: self.name() })?;
+        let udafs = self
+            .function_registry
+            .list_udafs()
+            .map_err(|e| Box::new(e) as _)
+            .context(table_engine::table::Scan { table: self.name() })?;
+
+        let mut rows = Vec::with_capacity(udfs.len() + udafs.len());
+        for udf in &udfs {
+            if filter.contains(udf.name()) {
+                rows.push(Self::from_function(udf.name(), SCALAR_FUNCTION_KIND));
+            }
+        }
+        for udaf in &udafs {
+            if filter.contains(udaf.name()) {
+                rows.push(Self::from_function(udaf.name(), AGGREGATE_FUNCTION_KIND));
+            }
+        }
+
+        build_stream(self.name(), &self.schema, &request, rows)
+    }
+}
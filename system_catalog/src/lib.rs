@@ -11,22 +11,36 @@ use std::{
 };
 
 use async_trait::async_trait;
+use catalog::{manager::Manager, schema::SchemaRef, CatalogRef};
 use common_types::{
-    record_batch::RecordBatch,
+    column_schema::{self, ColumnSchema},
+    datum::{Datum, DatumKind},
+    record_batch::{RecordBatch, RecordBatchWithKeyBuilder},
     row::Row,
-    schema::{RecordSchema, Schema},
+    schema::{self, RecordSchema, Schema},
+    time::TimeRange,
 };
 use futures::Stream;
+use snafu::ResultExt;
 use table_engine::{
+    partition::PartitionInfo,
     stream,
     stream::{PartitionedStreams, RecordBatchStream, SendableRecordBatchStream},
     table::{
-        AlterSchemaRequest, FlushRequest, GetRequest, ReadRequest, SchemaId, Table, TableId,
-        TableSeq, TableStats, WriteRequest,
+        AlterSchemaRequest, DeleteRequest, FlushRequest, GetRequest, ReadRequest, ScanStatistics,
+        SchemaId, Table, TableId, TableRef, TableSeq, TableStats, UnsupportedMethod, WriteRequest,
     },
 };
 
+use crate::filter::TableNameFilter;
+
+pub mod columns;
+pub mod compaction_tasks;
+mod filter;
+pub mod functions;
+pub mod sst_files;
 pub mod sys_catalog_table;
+pub mod table_stats;
 pub mod tables;
 
 /// Schema id of the sys catalog schema (`system/public`).
@@ -46,11 +60,162 @@ pub const TABLES_TABLE_SEQ: TableSeq = TableSeq::from_u32(2);
 /// Table id of the `tables` table.
 pub const TABLES_TABLE_ID: TableId = TableId::new(SYSTEM_SCHEMA_ID, TABLES_TABLE_SEQ);
 
+/// Table name of the `columns` table.
+pub const COLUMNS_TABLE_NAME: &str = "columns";
+/// Table sequence of the `columns` table.
+pub const COLUMNS_TABLE_SEQ: TableSeq = TableSeq::from_u32(3);
+/// Table id of the `columns` table.
+pub const COLUMNS_TABLE_ID: TableId = TableId::new(SYSTEM_SCHEMA_ID, COLUMNS_TABLE_SEQ);
+
+/// Table name of the `sst_files` table.
+pub const SST_FILES_TABLE_NAME: &str = "sst_files";
+/// Table sequence of the `sst_files` table.
+pub const SST_FILES_TABLE_SEQ: TableSeq = TableSeq::from_u32(4);
+/// Table id of the `sst_files` table.
+pub const SST_FILES_TABLE_ID: TableId = TableId::new(SYSTEM_SCHEMA_ID, SST_FILES_TABLE_SEQ);
+
+/// Table name of the `table_stats` table.
+pub const TABLE_STATS_TABLE_NAME: &str = "table_stats";
+/// Table sequence of the `table_stats` table.
+pub const TABLE_STATS_TABLE_SEQ: TableSeq = TableSeq::from_u32(5);
+/// Table id of the `table_stats` table.
+pub const TABLE_STATS_TABLE_ID: TableId = TableId::new(SYSTEM_SCHEMA_ID, TABLE_STATS_TABLE_SEQ);
+
+/// Table name of the `compaction_tasks` table.
+pub const COMPACTION_TASKS_TABLE_NAME: &str = "compaction_tasks";
+/// Table sequence of the `compaction_tasks` table.
+pub const COMPACTION_TASKS_TABLE_SEQ: TableSeq = TableSeq::from_u32(6);
+/// Table id of the `compaction_tasks` table.
+pub const COMPACTION_TASKS_TABLE_ID: TableId =
+    TableId::new(SYSTEM_SCHEMA_ID, COMPACTION_TASKS_TABLE_SEQ);
+
+/// Table name of the `functions` table.
+pub const FUNCTIONS_TABLE_NAME: &str = "functions";
+/// Table sequence of the `functions` table.
+pub const FUNCTIONS_TABLE_SEQ: TableSeq = TableSeq::from_u32(7);
+/// Table id of the `functions` table.
+pub const FUNCTIONS_TABLE_ID: TableId = TableId::new(SYSTEM_SCHEMA_ID, FUNCTIONS_TABLE_SEQ);
+
 // NOTE: The MAX_SYSTEM_TABLE_ID should be updated if any new system table is
 // added.
 
 /// Max table id of all the system tables.
-pub const MAX_SYSTEM_TABLE_SEQ: TableSeq = TABLES_TABLE_SEQ;
+pub const MAX_SYSTEM_TABLE_SEQ: TableSeq = FUNCTIONS_TABLE_SEQ;
+
+/// Build a non-null column.
+fn new_column(name: &str, data_type: DatumKind) -> ColumnSchema {
+    column_schema::Builder::new(name.to_string(), data_type)
+        .is_nullable(false)
+        .is_tag(false)
+        .build()
+        .unwrap()
+}
+
+/// Returns a schema builder with the key columns (timestamp, catalog, schema,
+/// table_name) shared by the system tables describing the tables.
+fn table_schema_builder(capacity: usize) -> schema::Builder {
+    schema::Builder::with_capacity(capacity)
+        .auto_increment_column_id(true)
+        .add_key_column(new_column("timestamp", DatumKind::Timestamp))
+        .unwrap()
+        .add_key_column(new_column("catalog", DatumKind::String))
+        .unwrap()
+        .add_key_column(new_column("schema", DatumKind::String))
+        .unwrap()
+        .add_key_column(new_column("table_name", DatumKind::String))
+        .unwrap()
+}
+
+/// Returns the leading datums of the key columns added by
+/// [table_schema_builder].
+fn table_key_datums(catalog: &CatalogRef, schema: &SchemaRef, table: &TableRef) -> Vec<Datum> {
+    vec![
+        Datum::Timestamp(tables::ENTRY_TIMESTAMP),
+        Datum::from(catalog.name()),
+        Datum::from(schema.name()),
+        Datum::from(table.name()),
+    ]
+}
+
+/// Call `f` on the tables of all the catalogs, the tables not allowed by the
+/// `filter` on the table name are skipped.
+fn for_each_table<M, F>(
+    catalog_manager: &M,
+    system_table: &str,
+    filter: &TableNameFilter,
+    mut f: F,
+) -> table_engine::table::Result<()>
+where
+    M: Manager,
+    F: FnMut(&CatalogRef, &SchemaRef, &TableRef) -> table_engine::table::Result<()>,
+{
+    let catalogs = catalog_manager
+        .all_catalogs()
+        .map_err(|e| Box::new(e) as _)
+        .context(table_engine::table::Scan {
+            table: system_table,
+        })?;
+    for catalog in &catalogs {
+        for schema in &catalog
+            .all_schemas()
+            .map_err(|e| Box::new(e) as _)
+            .context(table_engine::table::Scan {
+                table: system_table,
+            })?
+        {
+            for table in &schema.all_tables().map_err(|e| Box::new(e) as _).context(
+                table_engine::table::Scan {
+                    table: system_table,
+                },
+            )? {
+                if filter.contains(table.name()) {
+                    f(catalog, schema, table)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Build a stream of one record batch containing the `rows` projected by the
+/// `request`.
+fn build_stream(
+    system_table: &str,
+    schema: &Schema,
+    request: &ReadRequest,
+    rows: Vec<Row>,
+) -> table_engine::table::Result<SendableRecordBatchStream> {
+    let projector = request
+        .projected_schema
+        .try_project_with_key(schema)
+        .map_err(|e| Box::new(e) as _)
+        .context(table_engine::table::Scan {
+            table: system_table,
+        })?;
+    let mut builder = RecordBatchWithKeyBuilder::new(projector.schema_with_key().clone());
+    for row in &rows {
+        let projected_row = projector.project_row(row, Vec::new());
+        builder
+            .append_row(projected_row)
+            .map_err(|e| Box::new(e) as _)
+            .context(table_engine::table::Scan {
+                table: system_table,
+            })?;
+    }
+    let record_batch = builder
+        .build()
+        .map_err(|e| Box::new(e) as _)
+        .context(table_engine::table::Scan {
+            table: system_table,
+        })?
+        .into_record_batch();
+
+    Ok(Box::pin(OneRecordBatchStream {
+        schema: request.projected_schema.to_record_schema(),
+        record_batch: Some(record_batch),
+    }))
+}
 
 /// The minimal thing that a system table needs to implement
 #[async_trait]
@@ -102,6 +267,10 @@ impl Table for SystemTableAdapter {
         HashMap::new()
     }
 
+    fn partition_info(&self) -> Option<PartitionInfo> {
+        None
+    }
+
     fn engine_type(&self) -> &str {
         "system"
     }
@@ -110,10 +279,22 @@ impl Table for SystemTableAdapter {
         TableStats::default()
     }
 
+    fn scan_statistics(&self, _time_range: TimeRange) -> ScanStatistics {
+        ScanStatistics::default()
+    }
+
     async fn write(&self, _request: WriteRequest) -> table_engine::table::Result<usize> {
         Ok(0)
     }
 
+    async fn delete(&self, _request: DeleteRequest) -> table_engine::table::Result<usize> {
+        UnsupportedMethod {
+            table: self.name(),
+            method: "delete",
+        }
+        .fail()
+    }
+
     async fn read(
         &self,
         request: ReadRequest,
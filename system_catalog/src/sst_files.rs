@@ -0,0 +1,125 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+/// implementation of system table: SstFiles
+/// For example `SELECT * FROM system.public.sst_files`
+use std::fmt::{Debug, Formatter};
+
+use async_trait::async_trait;
+use catalog::{manager::Manager, schema::SchemaRef, CatalogRef};
+use common_types::{
+    datum::{Datum, DatumKind},
+    row::Row,
+    schema::Schema,
+};
+use table_engine::{
+    stream::SendableRecordBatchStream,
+    table::{ReadRequest, TableId, TableRef},
+};
+
+use crate::{
+    build_stream, filter::TableNameFilter, for_each_table, new_column, table_key_datums,
+    table_schema_builder, SystemTable, SST_FILES_TABLE_ID, SST_FILES_TABLE_NAME,
+};
+
+/// Build a new table schema for sst files
+fn sst_files_schema() -> Schema {
+    table_schema_builder(13)
+        .add_key_column(new_column("table_id", DatumKind::UInt64))
+        .unwrap()
+        .add_key_column(new_column("file_id", DatumKind::UInt64))
+        .unwrap()
+        .add_normal_column(new_column("level", DatumKind::UInt16))
+        .unwrap()
+        .add_normal_column(new_column("start_time", DatumKind::Timestamp))
+        .unwrap()
+        .add_normal_column(new_column("end_time", DatumKind::Timestamp))
+        .unwrap()
+        .add_normal_column(new_column("size", DatumKind::UInt64))
+        .unwrap()
+        .add_normal_column(new_column("row_num", DatumKind::UInt64))
+        .unwrap()
+        .add_normal_column(new_column("max_sequence", DatumKind::UInt64))
+        .unwrap()
+        .add_normal_column(new_column("being_compacted", DatumKind::Boolean))
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+pub struct SstFiles<M> {
+    schema: Schema,
+    catalog_manager: M,
+}
+
+impl<M> Debug for SstFiles<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SysSstFiles")
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl<M: Manager> SstFiles<M> {
+    pub fn new(catalog_manager: M) -> Self {
+        Self {
+            schema: sst_files_schema(),
+            catalog_manager,
+        }
+    }
+
+    fn from_table(
+        &self,
+        catalog: &CatalogRef,
+        schema: &SchemaRef,
+        table: &TableRef,
+        rows: &mut Vec<Row>,
+    ) {
+        for file in table.sst_files() {
+            let mut datums = table_key_datums(catalog, schema, table);
+            datums.push(Datum::from(file.table_id.as_u64()));
+            datums.push(Datum::from(file.file_id));
+            datums.push(Datum::from(file.level));
+            datums.push(Datum::Timestamp(file.time_range.inclusive_start()));
+            datums.push(Datum::Timestamp(file.time_range.exclusive_end()));
+            datums.push(Datum::from(file.size));
+            datums.push(Datum::from(file.row_num));
+            datums.push(Datum::from(file.max_sequence));
+            datums.push(Datum::from(file.being_compacted));
+            rows.push(Row::from_datums(datums));
+        }
+    }
+}
+
+#[async_trait]
+impl<M: Manager> SystemTable for SstFiles<M> {
+    fn name(&self) -> &str {
+        SST_FILES_TABLE_NAME
+    }
+
+    fn id(&self) -> TableId {
+        SST_FILES_TABLE_ID
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    async fn read(
+        &self,
+        request: ReadRequest,
+    ) -> table_engine::table::Result<SendableRecordBatchStream> {
+        let filter = TableNameFilter::new(&request.predicate, "table_name");
+        let mut rows = Vec::new();
+        for_each_table(
+            &self.catalog_manager,
+            self.name(),
+            &filter,
+            |catalog, schema, table| {
+                self.from_table(catalog, schema, table, &mut rows);
+                Ok(())
+            },
+        )?;
+
+        build_stream(self.name(), &self.schema, &request, rows)
+    }
+}
@@ -5,7 +5,7 @@
 use std::{collections::HashMap, convert::TryFrom, mem};
 
 use async_trait::async_trait;
-use catalog::consts;
+use catalog::{consts, schema::ViewInfo};
 use common_types::{
     bytes::{Bytes, BytesMut, MemBuf, MemBufMut},
     column_schema,
@@ -23,7 +23,7 @@ use common_util::{
 };
 use futures::TryStreamExt;
 use log::{debug, info, warn};
-use proto::sys_catalog::{CatalogEntry, SchemaEntry, TableEntry};
+use proto::sys_catalog::{CatalogEntry, SchemaEntry, TableEntry, ViewEntry};
 use protobuf::Message;
 use snafu::{ensure, Backtrace, OptionExt, ResultExt, Snafu};
 use table_engine::{
@@ -72,6 +72,9 @@ pub enum Error {
     #[snafu(display("Failed to persist tables to table, err:{}", source))]
     PersistTables { source: table_engine::table::Error },
 
+    #[snafu(display("Failed to persist views to table, err:{}", source))]
+    PersistViews { source: table_engine::table::Error },
+
     #[snafu(display("Failed to read table, err:{}", source))]
     ReadTable { source: table_engine::table::Error },
 
@@ -273,6 +276,7 @@ impl SysCatalogTable {
             table_name: SYS_CATALOG_TABLE_NAME.to_string(),
             table_id: SYS_CATALOG_TABLE_ID,
             engine: table_engine.engine_type().to_string(),
+            partition_info: None,
         };
 
         let table_opt = table_engine
@@ -435,6 +439,54 @@ impl SysCatalogTable {
         Ok(())
     }
 
+    /// Returns the persisted info of the table, including the dropped table.
+    pub async fn table_info(
+        &self,
+        catalog: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Option<TableInfo>> {
+        let table_key = TableKey {
+            catalog,
+            schema,
+            table,
+        };
+
+        self.get_table_info(table_key).await
+    }
+
+    /// Create or update the view in the catalog.
+    pub async fn write_view(&self, view: ViewInfo) -> Result<()> {
+        info!("Write view to sys_catalog table, view:{:?}", view);
+
+        let row_group = ViewWriter {
+            view,
+            dropped: false,
+        }
+        .into_row_group(self.table.schema())?;
+
+        let write_req = WriteRequest { row_group };
+        self.table.write(write_req).await.context(PersistViews)?;
+
+        Ok(())
+    }
+
+    /// Drop the view in the catalog.
+    pub async fn drop_view(&self, view: ViewInfo) -> Result<()> {
+        info!("Drop view to sys_catalog table, view:{:?}", view);
+
+        let row_group = ViewWriter {
+            view,
+            dropped: true,
+        }
+        .into_row_group(self.table.schema())?;
+
+        let write_req = WriteRequest { row_group };
+        self.table.write(write_req).await.context(PersistViews)?;
+
+        Ok(())
+    }
+
     /// Returns the inner table of the sys catalog.
     #[inline]
     pub fn inner_table(&self) -> TableRef {
@@ -528,6 +580,7 @@ impl SysCatalogTable {
             projected_schema: ProjectedSchema::no_projection(self.table.schema()),
             predicate: PredicateBuilder::default().build(),
             order: ReadOrder::None,
+            limit: None,
         };
         let mut batch_stream = self.table.read(read_request).await.context(ReadTable)?;
 
@@ -584,6 +637,14 @@ impl SysCatalogTable {
             DecodedRequest::CreateCatalog(req) => visitor.visit_catalog(req),
             DecodedRequest::CreateSchema(req) => visitor.visit_schema(req),
             DecodedRequest::TableEntry(req) => visitor.visit_tables(req).await,
+            DecodedRequest::ViewEntry { view, dropped } => {
+                if dropped {
+                    debug!("Sys catalog table skip dropped view, view:{:?}", view);
+                    Ok(())
+                } else {
+                    visitor.visit_view(view)
+                }
+            }
         }
     }
 }
@@ -599,6 +660,8 @@ pub trait Visitor {
 
     // FIXME(xikai): Should this method be called visit_table?
     async fn visit_tables(&mut self, table_info: TableInfo) -> Result<()>;
+
+    fn visit_view(&mut self, view: ViewInfo) -> Result<()>;
 }
 
 /// Build a new table schema for sys catalog
@@ -642,6 +705,7 @@ enum KeyType {
     CreateCatalog = 1,
     CreateSchema = 2,
     TableEntry = 3,
+    ViewEntry = 4,
 }
 
 impl KeyType {
@@ -656,6 +720,7 @@ impl KeyType {
             v if v == Self::CreateCatalog as u8 => Ok(Self::CreateCatalog),
             v if v == Self::CreateSchema as u8 => Ok(Self::CreateSchema),
             v if v == Self::TableEntry as u8 => Ok(Self::TableEntry),
+            v if v == Self::ViewEntry as u8 => Ok(Self::ViewEntry),
             value => InvalidKeyHeader { value }.fail(),
         }
     }
@@ -682,6 +747,15 @@ pub struct TableKey<'a> {
     table: &'a str,
 }
 
+/// View entry key
+///
+/// Use (catalog, schema, view) as key
+struct ViewKey<'a> {
+    catalog: &'a str,
+    schema: &'a str,
+    view: &'a str,
+}
+
 /// Encoder for entry key
 struct EntryKeyEncoder;
 
@@ -754,6 +828,34 @@ impl<'a> Encoder<TableKey<'a>> for EntryKeyEncoder {
     }
 }
 
+impl<'a> Encoder<ViewKey<'a>> for EntryKeyEncoder {
+    type Error = Error;
+
+    fn encode<B: MemBufMut>(&self, buf: &mut B, value: &ViewKey) -> Result<()> {
+        buf.write_u8(KeyType::ViewEntry.to_u8())
+            .context(EncodeKeyHeader)?;
+        let encoder = MemComparable;
+        encoder
+            .encode(buf, value.catalog.as_bytes())
+            .context(EncodeKeyBody)?;
+        encoder
+            .encode(buf, value.schema.as_bytes())
+            .context(EncodeKeyBody)?;
+        encoder
+            .encode(buf, value.view.as_bytes())
+            .context(EncodeKeyBody)?;
+        Ok(())
+    }
+
+    fn estimate_encoded_size(&self, value: &ViewKey) -> usize {
+        let encoder = MemComparable;
+        mem::size_of::<u8>()
+            + encoder.estimate_encoded_size(value.catalog.as_bytes())
+            + encoder.estimate_encoded_size(value.schema.as_bytes())
+            + encoder.estimate_encoded_size(value.view.as_bytes())
+    }
+}
+
 /// Information of the catalog to add
 #[derive(Debug)]
 pub struct CreateCatalogRequest {
@@ -984,12 +1086,78 @@ impl TableWriter {
     }
 }
 
+/// Writer of the view entry.
+struct ViewWriter {
+    view: ViewInfo,
+    dropped: bool,
+}
+
+impl ViewWriter {
+    /// Convert into [common_types::row::RowGroup]
+    fn into_row_group(self, schema: Schema) -> Result<RowGroup> {
+        let key = self.to_key()?;
+        let value = self.into_value()?;
+        let mut builder = RowGroupBuilder::new(schema);
+        TableWriter::build_row(&mut builder, key, value)?;
+
+        Ok(builder.build())
+    }
+
+    fn to_key(&self) -> Result<Bytes> {
+        let encoder = EntryKeyEncoder;
+        let key = ViewKey {
+            catalog: &self.view.catalog_name,
+            schema: &self.view.schema_name,
+            view: &self.view.view_name,
+        };
+        let mut buf = BytesMut::with_capacity(encoder.estimate_encoded_size(&key));
+        encoder.encode(&mut buf, &key)?;
+        Ok(buf.into())
+    }
+
+    fn into_value(self) -> Result<Bytes> {
+        let entry = self.into_pb();
+
+        let buf = entry.write_to_bytes().context(EncodeEntryPb)?;
+        Ok(buf.into())
+    }
+
+    fn into_pb(self) -> ViewEntry {
+        let view = self.view;
+        let mut entry = ViewEntry::new();
+        entry.set_catalog_name(view.catalog_name);
+        entry.set_schema_name(view.schema_name);
+        entry.set_view_name(view.view_name);
+        entry.set_query(view.query);
+        entry.set_source_table(view.source_table);
+        entry.set_period(view.period);
+        entry.set_watermark(view.watermark.as_i64());
+        entry.set_dropped(self.dropped);
+        entry.set_modified_time(Timestamp::now().as_i64());
+
+        entry
+    }
+}
+
+fn view_info_from_pb(entry: ViewEntry) -> ViewInfo {
+    ViewInfo {
+        catalog_name: entry.catalog_name,
+        schema_name: entry.schema_name,
+        view_name: entry.view_name,
+        query: entry.query,
+        source_table: entry.source_table,
+        period: entry.period,
+        watermark: Timestamp::new(entry.watermark),
+    }
+}
+
 /// Decoded sys catalog request
 #[derive(Debug)]
 enum DecodedRequest {
     CreateCatalog(CreateCatalogRequest),
     CreateSchema(CreateSchemaRequest),
     TableEntry(TableInfo),
+    ViewEntry { view: ViewInfo, dropped: bool },
 }
 
 /// Decode request from key/value
@@ -1009,6 +1177,14 @@ fn decode_one_request(key: &[u8], value: &[u8]) -> Result<DecodedRequest> {
             let table_info = TableInfo::try_from(entry).context(DecodeTableEntry)?;
             DecodedRequest::TableEntry(table_info)
         }
+        KeyType::ViewEntry => {
+            let entry = ViewEntry::parse_from_bytes(value).context(DecodeEntryPb)?;
+            let dropped = entry.dropped;
+            DecodedRequest::ViewEntry {
+                view: view_info_from_pb(entry),
+                dropped,
+            }
+        }
     };
 
     Ok(req)
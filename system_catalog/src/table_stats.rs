@@ -0,0 +1,109 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+/// implementation of system table: TableStats
+/// For example `SELECT * FROM system.public.table_stats`
+use std::fmt::{Debug, Formatter};
+
+use async_trait::async_trait;
+use catalog::{manager::Manager, schema::SchemaRef, CatalogRef};
+use common_types::{
+    datum::{Datum, DatumKind},
+    row::Row,
+    schema::Schema,
+};
+use table_engine::{
+    stream::SendableRecordBatchStream,
+    table::{ReadRequest, TableId, TableRef},
+};
+
+use crate::{
+    build_stream, filter::TableNameFilter, for_each_table, new_column, table_key_datums,
+    table_schema_builder, SystemTable, TABLE_STATS_TABLE_ID, TABLE_STATS_TABLE_NAME,
+};
+
+/// Build a new table schema for table stats
+fn table_stats_schema() -> Schema {
+    table_schema_builder(10)
+        .add_normal_column(new_column("num_write", DatumKind::UInt64))
+        .unwrap()
+        .add_normal_column(new_column("num_read", DatumKind::UInt64))
+        .unwrap()
+        .add_normal_column(new_column("num_flush", DatumKind::UInt64))
+        .unwrap()
+        .add_normal_column(new_column("memtable_memory_usage", DatumKind::UInt64))
+        .unwrap()
+        .add_normal_column(new_column("num_sst_files", DatumKind::UInt64))
+        .unwrap()
+        .add_normal_column(new_column("sst_size", DatumKind::UInt64))
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+pub struct TableStats<M> {
+    schema: Schema,
+    catalog_manager: M,
+}
+
+impl<M> Debug for TableStats<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SysTableStats")
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl<M: Manager> TableStats<M> {
+    pub fn new(catalog_manager: M) -> Self {
+        Self {
+            schema: table_stats_schema(),
+            catalog_manager,
+        }
+    }
+
+    fn from_table(&self, catalog: &CatalogRef, schema: &SchemaRef, table: &TableRef) -> Row {
+        let stats = table.stats();
+        let mut datums = table_key_datums(catalog, schema, table);
+        datums.push(Datum::from(stats.num_write));
+        datums.push(Datum::from(stats.num_read));
+        datums.push(Datum::from(stats.num_flush));
+        datums.push(Datum::from(stats.memtable_memory_usage));
+        datums.push(Datum::from(stats.num_sst_files));
+        datums.push(Datum::from(stats.sst_size));
+        Row::from_datums(datums)
+    }
+}
+
+#[async_trait]
+impl<M: Manager> SystemTable for TableStats<M> {
+    fn name(&self) -> &str {
+        TABLE_STATS_TABLE_NAME
+    }
+
+    fn id(&self) -> TableId {
+        TABLE_STATS_TABLE_ID
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+
+    async fn read(
+        &self,
+        request: ReadRequest,
+    ) -> table_engine::table::Result<SendableRecordBatchStream> {
+        let filter = TableNameFilter::new(&request.predicate, "table_name");
+        let mut rows = Vec::new();
+        for_each_table(
+            &self.catalog_manager,
+            self.name(),
+            &filter,
+            |catalog, schema, table| {
+                rows.push(self.from_table(catalog, schema, table));
+                Ok(())
+            },
+        )?;
+
+        build_stream(self.name(), &self.schema, &request, rows)
+    }
+}
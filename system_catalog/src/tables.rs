@@ -7,76 +7,30 @@ use std::fmt::{Debug, Formatter};
 use async_trait::async_trait;
 use catalog::{manager::Manager, schema::SchemaRef, CatalogRef};
 use common_types::{
-    column_schema,
     datum::{Datum, DatumKind},
-    record_batch::RecordBatchWithKeyBuilder,
     row::Row,
-    schema,
     schema::Schema,
     time::Timestamp,
 };
-use snafu::ResultExt;
 use table_engine::{
     stream::SendableRecordBatchStream,
     table::{ReadRequest, TableId, TableRef},
 };
 
-use crate::{OneRecordBatchStream, SystemTable, TABLES_TABLE_ID, TABLES_TABLE_NAME};
+use crate::{
+    build_stream, filter::TableNameFilter, for_each_table, new_column, table_key_datums,
+    table_schema_builder, SystemTable, TABLES_TABLE_ID, TABLES_TABLE_NAME,
+};
 
 /// Timestamp of entry
 pub const ENTRY_TIMESTAMP: Timestamp = Timestamp::new(0);
 
 /// Build a new table schema for tables
 fn tables_schema() -> Schema {
-    schema::Builder::with_capacity(6)
-        .auto_increment_column_id(true)
-        .add_key_column(
-            column_schema::Builder::new("timestamp".to_string(), DatumKind::Timestamp)
-                .is_nullable(false)
-                .is_tag(false)
-                .build()
-                .unwrap(),
-        )
-        .unwrap()
-        .add_key_column(
-            column_schema::Builder::new("catalog".to_string(), DatumKind::String)
-                .is_nullable(false)
-                .is_tag(false)
-                .build()
-                .unwrap(),
-        )
-        .unwrap()
-        .add_key_column(
-            column_schema::Builder::new("schema".to_string(), DatumKind::String)
-                .is_nullable(false)
-                .is_tag(false)
-                .build()
-                .unwrap(),
-        )
+    table_schema_builder(6)
+        .add_normal_column(new_column("table_id", DatumKind::UInt64))
         .unwrap()
-        .add_key_column(
-            column_schema::Builder::new("table_name".to_string(), DatumKind::String)
-                .is_nullable(false)
-                .is_tag(false)
-                .build()
-                .unwrap(),
-        )
-        .unwrap()
-        .add_normal_column(
-            column_schema::Builder::new("table_id".to_string(), DatumKind::UInt64)
-                .is_nullable(false)
-                .is_tag(false)
-                .build()
-                .unwrap(),
-        )
-        .unwrap()
-        .add_normal_column(
-            column_schema::Builder::new("engine".to_string(), DatumKind::String)
-                .is_nullable(false)
-                .is_tag(false)
-                .build()
-                .unwrap(),
-        )
+        .add_normal_column(new_column("engine", DatumKind::String))
         .unwrap()
         .build()
         .unwrap()
@@ -103,12 +57,8 @@ impl<M: Manager> Tables<M> {
         }
     }
 
-    fn from_table(&self, catalog: CatalogRef, schema: SchemaRef, table: TableRef) -> Row {
-        let mut datums = Vec::with_capacity(self.schema.num_columns());
-        datums.push(Datum::Timestamp(ENTRY_TIMESTAMP));
-        datums.push(Datum::from(catalog.name()));
-        datums.push(Datum::from(schema.name()));
-        datums.push(Datum::from(table.name()));
+    fn from_table(&self, catalog: &CatalogRef, schema: &SchemaRef, table: &TableRef) -> Row {
+        let mut datums = table_key_datums(catalog, schema, table);
         datums.push(Datum::from(table.id().as_u64()));
         datums.push(Datum::from(table.engine_type()));
         Row::from_datums(datums)
@@ -133,42 +83,18 @@ impl<M: Manager> SystemTable for Tables<M> {
         &self,
         request: ReadRequest,
     ) -> table_engine::table::Result<SendableRecordBatchStream> {
-        let catalogs = self
-            .catalog_manager
-            .all_catalogs()
-            .map_err(|e| Box::new(e) as _)
-            .context(table_engine::table::Scan { table: self.name() })?;
-        let mut builder =
-            RecordBatchWithKeyBuilder::new(self.schema.clone().to_record_schema_with_key());
+        let filter = TableNameFilter::new(&request.predicate, "table_name");
+        let mut rows = Vec::new();
+        for_each_table(
+            &self.catalog_manager,
+            self.name(),
+            &filter,
+            |catalog, schema, table| {
+                rows.push(self.from_table(catalog, schema, table));
+                Ok(())
+            },
+        )?;
 
-        let projector = request
-            .projected_schema
-            .try_project_with_key(&self.schema)
-            .expect("Should succeed to try_project_key of sys_tables");
-        for catalog in &catalogs {
-            for schema in &catalog
-                .all_schemas()
-                .map_err(|e| Box::new(e) as _)
-                .context(table_engine::table::Scan { table: self.name() })?
-            {
-                for table in &schema
-                    .all_tables()
-                    .map_err(|e| Box::new(e) as _)
-                    .context(table_engine::table::Scan { table: self.name() })?
-                {
-                    let row = self.from_table(catalog.clone(), schema.clone(), table.clone());
-                    let projected_row = projector.project_row(&row, Vec::new());
-                    builder
-                        .append_row(projected_row)
-                        .map_err(|e| Box::new(e) as _)
-                        .context(table_engine::table::Scan { table: self.name() })?;
-                }
-            }
-        }
-        let record_batch = builder.build().unwrap().into_record_batch();
-        Ok(Box::pin(OneRecordBatchStream {
-            schema: self.schema.clone().to_record_schema(),
-            record_batch: Some(record_batch),
-        }))
+        build_stream(self.name(), &self.schema, &request, rows)
     }
 }
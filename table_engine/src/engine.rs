@@ -147,7 +147,6 @@ pub struct CreateTableRequest {
     /// Table schema
     pub table_schema: Schema,
     /// Partition info if this is a partitioned table
-    // TODO(yingwen): TableEngine should not have knowledge of partitioning
     pub partition_info: Option<PartitionInfo>,
     /// Table engine type
     pub engine: String,
@@ -178,6 +177,9 @@ impl From<CreateTableRequest> for TableEntry {
         entry.set_table_name(req.table_name);
         entry.set_engine(req.engine);
         entry.set_state(TableStatePb::from(req.state));
+        if let Some(partition_info) = req.partition_info {
+            entry.set_partition_info(partition_info.into());
+        }
 
         entry
     }
@@ -193,6 +195,7 @@ impl From<CreateTableRequest> for TableInfo {
             table_id: req.table_id,
             engine: req.engine,
             state: req.state,
+            partition_info: req.partition_info,
         }
     }
 }
@@ -210,6 +213,9 @@ pub struct DropTableRequest {
     pub table_name: String,
     /// Table engine type
     pub engine: String,
+    /// Persisted partition info if this is a partitioned table, its sub tables
+    /// are dropped along with it
+    pub partition_info: Option<PartitionInfo>,
 }
 
 #[derive(Debug, Clone)]
@@ -226,6 +232,8 @@ pub struct OpenTableRequest {
     pub table_id: TableId,
     /// Table engine type
     pub engine: String,
+    /// Partition info if this is a partitioned table
+    pub partition_info: Option<PartitionInfo>,
 }
 
 impl From<TableInfo> for OpenTableRequest {
@@ -237,6 +245,7 @@ impl From<TableInfo> for OpenTableRequest {
             table_name: table_info.table_name,
             table_id: table_info.table_id,
             engine: table_info.engine,
+            partition_info: table_info.partition_info,
         }
     }
 }
@@ -17,18 +17,20 @@ use common_types::{
     record_batch::RecordBatch,
     row::{Row, RowGroup},
     schema::{RecordSchema, Schema},
+    time::{TimeRange, Timestamp},
 };
 use futures::stream::Stream;
 use snafu::{OptionExt, ResultExt};
 
 use crate::{
+    partition::PartitionInfo,
     stream::{
         self, ErrNoSource, ErrWithSource, PartitionedStreams, RecordBatchStream,
         SendableRecordBatchStream,
     },
     table::{
-        AlterSchemaRequest, FlushRequest, GetRequest, ReadRequest, Result, Table, TableId,
-        TableStats, UnsupportedMethod, WriteRequest,
+        AlterSchemaRequest, DeleteRequest, FlushRequest, GetRequest, ReadRequest, Result,
+        ScanStatistics, Table, TableId, TableStats, UnsupportedMethod, WriteRequest,
     },
 };
 
@@ -49,6 +51,10 @@ pub struct MemoryTable {
     row_groups: Arc<RwLock<RowGroupVec>>,
     /// Engine type
     engine_type: String,
+    /// Table options
+    options: HashMap<String, String>,
+    /// Rollup watermark
+    rollup_watermark: Option<Timestamp>,
 }
 
 impl MemoryTable {
@@ -59,8 +65,20 @@ impl MemoryTable {
             schema,
             row_groups: Arc::new(RwLock::new(Vec::new())),
             engine_type,
+            options: HashMap::new(),
+            rollup_watermark: None,
         }
     }
+
+    pub fn with_options(mut self, options: HashMap<String, String>) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_rollup_watermark(mut self, watermark: Timestamp) -> Self {
+        self.rollup_watermark = Some(watermark);
+        self
+    }
 }
 
 impl fmt::Debug for MemoryTable {
@@ -85,13 +103,17 @@ impl Table for MemoryTable {
     }
 
     fn options(&self) -> HashMap<String, String> {
-        HashMap::new()
+        self.options.clone()
     }
 
     fn schema(&self) -> Schema {
         self.schema.clone()
     }
 
+    fn partition_info(&self) -> Option<PartitionInfo> {
+        None
+    }
+
     fn engine_type(&self) -> &str {
         &self.engine_type
     }
@@ -100,6 +122,14 @@ impl Table for MemoryTable {
         TableStats::default()
     }
 
+    fn rollup_watermark(&self) -> Option<Timestamp> {
+        self.rollup_watermark
+    }
+
+    fn scan_statistics(&self, _time_range: TimeRange) -> ScanStatistics {
+        ScanStatistics::default()
+    }
+
     async fn write(&self, request: WriteRequest) -> Result<usize> {
         // TODO(yingwen) Maybe check schema?
         let mut row_groups = self.row_groups.write().unwrap();
@@ -109,6 +139,14 @@ impl Table for MemoryTable {
         Ok(n)
     }
 
+    async fn delete(&self, _request: DeleteRequest) -> Result<usize> {
+        UnsupportedMethod {
+            table: self.name(),
+            method: "delete",
+        }
+        .fail()
+    }
+
     // batch_size is ignored now
     async fn read(&self, request: ReadRequest) -> Result<SendableRecordBatchStream> {
         let scan = MemoryScan {
@@ -0,0 +1,403 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Partitioned table engine
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use log::{error, info};
+use snafu::{Backtrace, ResultExt, Snafu};
+
+use crate::{
+    engine::{
+        CloseTableRequest, CreateTableRequest, DropTableRequest, InvalidArguments,
+        OpenTableRequest, Result, TableEngine, TableEngineRef,
+    },
+    partition::{table::PartitionTableImpl, PartitionInfo},
+    table::{SchemaId, TableRef},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Number of sub tables mismatch, table:{}, expect:{}, given:{}.\nBacktrace:\n{}",
+        table,
+        expect,
+        given,
+        backtrace
+    ))]
+    SubTableNumMismatch {
+        table: String,
+        expect: usize,
+        given: usize,
+        backtrace: Backtrace,
+    },
+}
+
+/// Table engine supports partitioned tables.
+///
+/// The requests of non-partitioned tables are passed to the inner engine
+/// directly. For a partitioned table, one sub table is created in the inner
+/// engine for each partition, the sub tables are under the same schema of the
+/// partitioned table.
+pub struct PartitionTableEngine {
+    inner: TableEngineRef,
+    /// Partition info of the opened partitioned tables
+    partitioned_tables: RwLock<HashMap<(SchemaId, String), PartitionInfo>>,
+}
+
+impl PartitionTableEngine {
+    pub fn new(inner: TableEngineRef) -> Self {
+        Self {
+            inner,
+            partitioned_tables: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn register_table(&self, schema_id: SchemaId, table_name: String, info: PartitionInfo) {
+        self.partitioned_tables
+            .write()
+            .unwrap()
+            .insert((schema_id, table_name), info);
+    }
+
+    fn unregister_table(&self, schema_id: SchemaId, table_name: &str) -> Option<PartitionInfo> {
+        self.partitioned_tables
+            .write()
+            .unwrap()
+            .remove(&(schema_id, table_name.to_string()))
+    }
+
+    fn check_sub_tables(table_name: &str, partition_info: &PartitionInfo) -> Result<()> {
+        let expect = partition_info.partition_num();
+        let given = partition_info.sub_table_ids.len();
+        if expect == 0 || expect != given {
+            return SubTableNumMismatch {
+                table: table_name,
+                expect,
+                given,
+            }
+            .fail()
+            .map_err(|e| Box::new(e) as _)
+            .context(InvalidArguments { table: table_name });
+        }
+
+        Ok(())
+    }
+
+    /// Drop the sub tables of the first `num_created` partitions, which are
+    /// created before the creation of the partitioned table fails.
+    async fn drop_created_sub_tables(
+        &self,
+        request: &CreateTableRequest,
+        partition_info: &PartitionInfo,
+        num_created: usize,
+    ) {
+        for idx in 0..num_created {
+            let sub_request = DropTableRequest {
+                catalog_name: request.catalog_name.clone(),
+                schema_name: request.schema_name.clone(),
+                schema_id: request.schema_id,
+                table_name: partition_info.sub_table_name(idx),
+                engine: request.engine.clone(),
+                partition_info: None,
+            };
+            if let Err(e) = self.inner.drop_table(sub_request).await {
+                error!(
+                    "Failed to drop sub table after failing to create partitioned table, table:{}, partition:{}, err:{}",
+                    request.table_name, idx, e
+                );
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl TableEngine for PartitionTableEngine {
+    fn engine_type(&self) -> &str {
+        self.inner.engine_type()
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    async fn create_table(&self, request: CreateTableRequest) -> Result<TableRef> {
+        let partition_info = match &request.partition_info {
+            Some(v) => v.clone(),
+            None => return self.inner.create_table(request).await,
+        };
+        Self::check_sub_tables(&request.table_name, &partition_info)?;
+
+        let mut sub_tables = Vec::with_capacity(partition_info.partition_num());
+        for (idx, sub_table_id) in partition_info.sub_table_ids.iter().enumerate() {
+            let sub_request = CreateTableRequest {
+                table_id: *sub_table_id,
+                table_name: partition_info.sub_table_name(idx),
+                partition_info: None,
+                ..request.clone()
+            };
+            match self.inner.create_table(sub_request).await {
+                Ok(table) => sub_tables.push(table),
+                Err(e) => {
+                    self.drop_created_sub_tables(&request, &partition_info, idx)
+                        .await;
+                    return Err(e);
+                }
+            }
+        }
+
+        info!(
+            "Partition table engine create table, table:{}, partition_info:{:?}",
+            request.table_name, partition_info
+        );
+
+        self.register_table(
+            request.schema_id,
+            request.table_name.clone(),
+            partition_info.clone(),
+        );
+
+        Ok(Arc::new(PartitionTableImpl::new(
+            request.table_name,
+            request.table_id,
+            request.engine,
+            partition_info,
+            sub_tables,
+        )))
+    }
+
+    async fn drop_table(&self, request: DropTableRequest) -> Result<bool> {
+        self.unregister_table(request.schema_id, &request.table_name);
+        // Drop by the persisted partition info as the table may be not opened if
+        // any of its sub tables is missing.
+        let partition_info = match &request.partition_info {
+            Some(v) => v.clone(),
+            None => return self.inner.drop_table(request).await,
+        };
+
+        let mut dropped = false;
+        for idx in 0..partition_info.partition_num() {
+            let sub_request = DropTableRequest {
+                table_name: partition_info.sub_table_name(idx),
+                partition_info: None,
+                ..request.clone()
+            };
+            dropped |= self.inner.drop_table(sub_request).await?;
+        }
+
+        Ok(dropped)
+    }
+
+    async fn open_table(&self, request: OpenTableRequest) -> Result<Option<TableRef>> {
+        let partition_info = match &request.partition_info {
+            Some(v) => v.clone(),
+            None => return self.inner.open_table(request).await,
+        };
+        Self::check_sub_tables(&request.table_name, &partition_info)?;
+
+        let mut sub_tables = Vec::with_capacity(partition_info.partition_num());
+        for (idx, sub_table_id) in partition_info.sub_table_ids.iter().enumerate() {
+            let sub_request = OpenTableRequest {
+                table_id: *sub_table_id,
+                table_name: partition_info.sub_table_name(idx),
+                partition_info: None,
+                ..request.clone()
+            };
+            match self.inner.open_table(sub_request).await? {
+                Some(table) => sub_tables.push(table),
+                // The partitioned table is unavailable if any of its partition is missing.
+                None => return Ok(None),
+            }
+        }
+
+        self.register_table(
+            request.schema_id,
+            request.table_name.clone(),
+            partition_info.clone(),
+        );
+
+        Ok(Some(Arc::new(PartitionTableImpl::new(
+            request.table_name,
+            request.table_id,
+            request.engine,
+            partition_info,
+            sub_tables,
+        ))))
+    }
+
+    async fn close_table(&self, request: CloseTableRequest) -> Result<()> {
+        let partition_info = match self.unregister_table(request.schema_id, &request.table_name) {
+            Some(v) => v,
+            None => return self.inner.close_table(request).await,
+        };
+
+        for (idx, sub_table_id) in partition_info.sub_table_ids.iter().enumerate() {
+            let sub_request = CloseTableRequest {
+                table_id: *sub_table_id,
+                table_name: partition_info.sub_table_name(idx),
+                partition_info: None,
+                ..request.clone()
+            };
+            self.inner.close_table(sub_request).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Mutex};
+
+    use common_types::tests::build_schema;
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::{
+        engine::{Error, TableState},
+        memory::MemoryTable,
+        partition::PartitionMethod,
+        table::TableId,
+    };
+
+    /// Engine keeps the names of the created tables, fails to create the
+    /// table named `fail_table`.
+    #[derive(Default)]
+    struct MockEngine {
+        fail_table: String,
+        tables: Mutex<HashSet<String>>,
+    }
+
+    #[async_trait]
+    impl TableEngine for MockEngine {
+        fn engine_type(&self) -> &str {
+            "mock"
+        }
+
+        async fn close(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn create_table(&self, request: CreateTableRequest) -> Result<TableRef> {
+            if request.table_name == self.fail_table {
+                return Err(Error::Unexpected {
+                    source: Box::new(std::fmt::Error),
+                });
+            }
+
+            self.tables
+                .lock()
+                .unwrap()
+                .insert(request.table_name.clone());
+            Ok(Arc::new(MemoryTable::new(
+                request.table_name,
+                request.table_id,
+                request.table_schema,
+                request.engine,
+            )))
+        }
+
+        async fn drop_table(&self, request: DropTableRequest) -> Result<bool> {
+            Ok(self.tables.lock().unwrap().remove(&request.table_name))
+        }
+
+        async fn open_table(&self, request: OpenTableRequest) -> Result<Option<TableRef>> {
+            if !self.tables.lock().unwrap().contains(&request.table_name) {
+                return Ok(None);
+            }
+
+            Ok(Some(Arc::new(MemoryTable::new(
+                request.table_name,
+                request.table_id,
+                build_schema(),
+                request.engine,
+            ))))
+        }
+
+        async fn close_table(&self, _request: CloseTableRequest) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn new_partition_info() -> PartitionInfo {
+        let mut info = PartitionInfo::new(PartitionMethod::Hash {
+            columns: vec!["key1".to_string()],
+            partition_num: 3,
+        });
+        info.sub_table_ids = vec![TableId::from(11), TableId::from(12), TableId::from(13)];
+        info
+    }
+
+    fn new_create_request(partition_info: PartitionInfo) -> CreateTableRequest {
+        CreateTableRequest {
+            catalog_name: "catalog".to_string(),
+            schema_name: "schema".to_string(),
+            schema_id: SchemaId::from_u16(1),
+            table_id: TableId::from(10),
+            table_name: "test".to_string(),
+            table_schema: build_schema(),
+            partition_info: Some(partition_info),
+            engine: "mock".to_string(),
+            options: HashMap::new(),
+            state: TableState::Stable,
+        }
+    }
+
+    #[test]
+    fn test_drop_sub_tables_on_create_failure() {
+        let partition_info = new_partition_info();
+        let inner = Arc::new(MockEngine {
+            fail_table: partition_info.sub_table_name(2),
+            ..Default::default()
+        });
+        let engine = PartitionTableEngine::new(inner.clone());
+
+        let request = new_create_request(partition_info);
+        assert!(block_on(engine.create_table(request)).is_err());
+        assert!(inner.tables.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_drop_unopened_partitioned_table() {
+        let partition_info = new_partition_info();
+        let inner = Arc::new(MockEngine::default());
+        let engine = PartitionTableEngine::new(inner.clone());
+
+        let request = new_create_request(partition_info.clone());
+        block_on(engine.create_table(request.clone())).unwrap();
+        assert_eq!(3, inner.tables.lock().unwrap().len());
+
+        // The table can't be opened after one of its sub tables is lost.
+        inner
+            .tables
+            .lock()
+            .unwrap()
+            .remove(&partition_info.sub_table_name(0));
+        let open_request = OpenTableRequest {
+            catalog_name: request.catalog_name.clone(),
+            schema_name: request.schema_name.clone(),
+            schema_id: request.schema_id,
+            table_name: request.table_name.clone(),
+            table_id: request.table_id,
+            engine: request.engine.clone(),
+            partition_info: Some(partition_info.clone()),
+        };
+        let engine = PartitionTableEngine::new(inner.clone());
+        assert!(block_on(engine.open_table(open_request)).unwrap().is_none());
+
+        let drop_request = DropTableRequest {
+            catalog_name: request.catalog_name,
+            schema_name: request.schema_name,
+            schema_id: request.schema_id,
+            table_name: request.table_name,
+            engine: request.engine,
+            partition_info: Some(partition_info),
+        };
+        assert!(block_on(engine.drop_table(drop_request)).unwrap());
+        assert!(inner.tables.lock().unwrap().is_empty());
+    }
+}
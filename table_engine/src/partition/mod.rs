@@ -1,27 +1,388 @@
 // Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
 
 //! Partitioned table supports
+//!
+//! A partitioned table is composed of several sub tables, one for each
+//! partition. The sub tables are created under the same schema (tenant) of the
+//! partitioned table and their ids are allocated from the id space of that
+//! schema, so the partitions of tables from different tenants are always
+//! isolated from each other.
 
-mod expression;
+pub mod engine;
 pub mod rule;
+pub mod table;
 
-/// Partition type of table
-#[derive(Clone, Debug, PartialEq)]
-pub enum PartitionType {
-    None = 0,
-    Hash = 1,
+use std::convert::TryFrom;
+
+use common_types::{
+    datum::{Datum, DatumKind},
+    time::Timestamp,
+};
+use common_util::codec::{
+    compact::{MemCompactDecoder, MemCompactEncoder},
+    DecodeTo, Encoder,
+};
+use proto::{common::DataType as DataTypePb, sys_catalog as sys_catalog_pb};
+use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+
+use crate::table::TableId;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Partition method is missing.\nBacktrace:\n{}", backtrace))]
+    MissingPartitionMethod { backtrace: Backtrace },
+
+    #[snafu(display("Invalid partition num, num:{}.\nBacktrace:\n{}", num, backtrace))]
+    InvalidPartitionNum { num: u32, backtrace: Backtrace },
+
+    #[snafu(display("Failed to decode value of list partition, err:{}", source))]
+    DecodeListValue {
+        source: common_util::codec::compact::Error,
+    },
 }
 
+define_result!(Error);
+
+/// Prefix of the name of sub tables, the names with this prefix are reserved
+/// and can't be used by other tables.
+const SUB_TABLE_NAME_PREFIX: &str = "__";
+
 /// Size type of partition num
 pub type PartitionNum = u16;
 
+/// Partition of the range partitioning
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangePartition {
+    /// Partition name
+    pub name: String,
+    /// Exclusive upper bound of the timestamp, None means MAXVALUE
+    pub less_than: Option<Timestamp>,
+}
+
+/// Partition of the list partitioning
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListPartition {
+    /// Partition name
+    pub name: String,
+    /// Values of the partition key in this partition
+    pub values: Vec<Datum>,
+}
+
+/// How to partition the rows of table
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartitionMethod {
+    /// Partition by the hash of the columns
+    Hash {
+        columns: Vec<String>,
+        partition_num: PartitionNum,
+    },
+    /// Partition by ranges of the timestamp column, the partitions are sorted
+    /// by the upper bound
+    Range {
+        column: String,
+        partitions: Vec<RangePartition>,
+    },
+    /// Partition by values of a tag column
+    List {
+        column: String,
+        partitions: Vec<ListPartition>,
+    },
+}
+
+impl PartitionMethod {
+    /// Returns the number of partitions
+    pub fn partition_num(&self) -> usize {
+        match self {
+            PartitionMethod::Hash { partition_num, .. } => usize::from(*partition_num),
+            PartitionMethod::Range { partitions, .. } => partitions.len(),
+            PartitionMethod::List { partitions, .. } => partitions.len(),
+        }
+    }
+
+    /// Returns the name of the partition at `index`
+    ///
+    /// REQUIRE: `index` is less than the partition num
+    pub fn partition_name(&self, index: usize) -> String {
+        match self {
+            PartitionMethod::Hash { .. } => format!("p{}", index),
+            PartitionMethod::Range { partitions, .. } => partitions[index].name.clone(),
+            PartitionMethod::List { partitions, .. } => partitions[index].name.clone(),
+        }
+    }
+
+    /// Returns the columns of the partition key
+    pub fn columns(&self) -> Vec<&str> {
+        match self {
+            PartitionMethod::Hash { columns, .. } => columns.iter().map(|c| c.as_str()).collect(),
+            PartitionMethod::Range { column, .. } => vec![column.as_str()],
+            PartitionMethod::List { column, .. } => vec![column.as_str()],
+        }
+    }
+}
+
 /// Info for how to partition table
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PartitionInfo {
-    /// Partition type
-    pub partition_type: PartitionType,
-    /// Partition expression
-    pub expr: String,
-    /// Partition num
-    pub partition_num: PartitionNum,
+    /// Partition method
+    pub method: PartitionMethod,
+    /// Ids of the sub tables, the sub table of the `i-th` partition is
+    /// `sub_table_ids[i]`
+    ///
+    /// The ids are allocated by the catalog on table creation, so they are
+    /// empty before the table is created.
+    pub sub_table_ids: Vec<TableId>,
+}
+
+impl PartitionInfo {
+    pub fn new(method: PartitionMethod) -> Self {
+        Self {
+            method,
+            sub_table_ids: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn partition_num(&self) -> usize {
+        self.method.partition_num()
+    }
+
+    /// Returns the name of the sub table for the partition at `index`, which
+    /// is derived from the id of the sub table so it is unique in the schema.
+    ///
+    /// REQUIRE: The ids of the sub tables are allocated
+    pub fn sub_table_name(&self, index: usize) -> String {
+        format!(
+            "{}{}",
+            SUB_TABLE_NAME_PREFIX,
+            self.sub_table_ids[index].as_u64()
+        )
+    }
+}
+
+/// Returns the key to allocate the id of the sub table for the partition at
+/// `index` of the table with `table_id`.
+pub fn sub_table_id_key(table_id: TableId, index: usize) -> String {
+    format!("{}{}_{}", SUB_TABLE_NAME_PREFIX, table_id.as_u64(), index)
+}
+
+/// Returns true if the `table_name` is reserved for the sub tables.
+#[inline]
+pub fn is_reserved_table_name(table_name: &str) -> bool {
+    table_name.starts_with(SUB_TABLE_NAME_PREFIX)
+}
+
+impl From<PartitionInfo> for sys_catalog_pb::PartitionInfo {
+    fn from(info: PartitionInfo) -> Self {
+        let mut target = sys_catalog_pb::PartitionInfo::new();
+        match info.method {
+            PartitionMethod::Hash {
+                columns,
+                partition_num,
+            } => {
+                let mut hash = sys_catalog_pb::HashPartition::new();
+                hash.set_columns(columns.into());
+                hash.set_partition_num(u32::from(partition_num));
+                target.set_hash(hash);
+            }
+            PartitionMethod::Range { column, partitions } => {
+                let definitions: Vec<_> = partitions
+                    .into_iter()
+                    .map(|partition| {
+                        let mut definition = sys_catalog_pb::RangePartitionDefinition::new();
+                        definition.set_name(partition.name);
+                        match partition.less_than {
+                            Some(ts) => definition.set_less_than(ts.as_i64()),
+                            None => definition.set_max_value(true),
+                        }
+                        definition
+                    })
+                    .collect();
+
+                let mut range = sys_catalog_pb::RangePartition::new();
+                range.set_column(column);
+                range.set_partitions(definitions.into());
+                target.set_range(range);
+            }
+            PartitionMethod::List { column, partitions } => {
+                let kind = partitions
+                    .iter()
+                    .flat_map(|p| p.values.first())
+                    .map(|v| v.kind())
+                    .next()
+                    .unwrap_or(DatumKind::Null);
+                let encoder = MemCompactEncoder;
+                let definitions: Vec<_> = partitions
+                    .into_iter()
+                    .map(|partition| {
+                        let values: Vec<_> = partition
+                            .values
+                            .iter()
+                            .map(|value| {
+                                let mut buf =
+                                    Vec::with_capacity(encoder.estimate_encoded_size(value));
+                                encoder.encode(&mut buf, value).expect(
+                                    "Should encode list value into the buffer successfully",
+                                );
+                                buf
+                            })
+                            .collect();
+
+                        let mut definition = sys_catalog_pb::ListPartitionDefinition::new();
+                        definition.set_name(partition.name);
+                        definition.set_values(values.into());
+                        definition
+                    })
+                    .collect();
+
+                let mut list = sys_catalog_pb::ListPartition::new();
+                list.set_column(column);
+                list.set_data_type(DataTypePb::from(kind));
+                list.set_partitions(definitions.into());
+                target.set_list(list);
+            }
+        }
+        target.set_sub_table_ids(info.sub_table_ids.iter().map(|id| id.as_u64()).collect());
+
+        target
+    }
+}
+
+impl TryFrom<sys_catalog_pb::PartitionInfo> for PartitionInfo {
+    type Error = Error;
+
+    fn try_from(mut src: sys_catalog_pb::PartitionInfo) -> Result<Self> {
+        let method = if src.has_hash() {
+            let mut hash = src.take_hash();
+            let partition_num =
+                PartitionNum::try_from(hash.partition_num)
+                    .ok()
+                    .context(InvalidPartitionNum {
+                        num: hash.partition_num,
+                    })?;
+            PartitionMethod::Hash {
+                columns: hash.take_columns().into_vec(),
+                partition_num,
+            }
+        } else if src.has_range() {
+            let mut range = src.take_range();
+            let partitions = range
+                .take_partitions()
+                .into_iter()
+                .map(|definition| RangePartition {
+                    name: definition.name,
+                    less_than: if definition.max_value {
+                        None
+                    } else {
+                        Some(Timestamp::new(definition.less_than))
+                    },
+                })
+                .collect();
+            PartitionMethod::Range {
+                column: range.take_column(),
+                partitions,
+            }
+        } else if src.has_list() {
+            let mut list = src.take_list();
+            let kind = DatumKind::from(list.data_type);
+            let decoder = MemCompactDecoder;
+            let mut partitions = Vec::with_capacity(list.partitions.len());
+            for definition in list.take_partitions().into_iter() {
+                let mut values = Vec::with_capacity(definition.values.len());
+                for value_bytes in &definition.values {
+                    let mut value = Datum::empty(&kind);
+                    decoder
+                        .decode_to(&mut value_bytes.as_slice(), &mut value)
+                        .context(DecodeListValue)?;
+                    values.push(value);
+                }
+                partitions.push(ListPartition {
+                    name: definition.name,
+                    values,
+                });
+            }
+            PartitionMethod::List {
+                column: list.take_column(),
+                partitions,
+            }
+        } else {
+            return MissingPartitionMethod.fail();
+        };
+
+        Ok(Self {
+            method,
+            sub_table_ids: src
+                .sub_table_ids
+                .iter()
+                .map(|id| TableId::from(*id))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_pb_conversion(method: PartitionMethod) {
+        let info = PartitionInfo {
+            method,
+            sub_table_ids: vec![TableId::from(1), TableId::from(2)],
+        };
+        let pb = sys_catalog_pb::PartitionInfo::from(info.clone());
+        let decoded = PartitionInfo::try_from(pb).unwrap();
+
+        assert_eq!(info, decoded);
+    }
+
+    #[test]
+    fn test_partition_info_pb_conversion() {
+        check_pb_conversion(PartitionMethod::Hash {
+            columns: vec!["host".to_string(), "region".to_string()],
+            partition_num: 2,
+        });
+
+        check_pb_conversion(PartitionMethod::Range {
+            column: "ts".to_string(),
+            partitions: vec![
+                RangePartition {
+                    name: "p0".to_string(),
+                    less_than: Some(Timestamp::new(1000)),
+                },
+                RangePartition {
+                    name: "p1".to_string(),
+                    less_than: None,
+                },
+            ],
+        });
+
+        check_pb_conversion(PartitionMethod::List {
+            column: "region".to_string(),
+            partitions: vec![
+                ListPartition {
+                    name: "east".to_string(),
+                    values: vec![Datum::from("hangzhou"), Datum::from("shanghai")],
+                },
+                ListPartition {
+                    name: "west".to_string(),
+                    values: vec![Datum::from("chengdu")],
+                },
+            ],
+        });
+    }
+
+    #[test]
+    fn test_sub_table_name() {
+        let mut info = PartitionInfo::new(PartitionMethod::Hash {
+            columns: vec!["host".to_string()],
+            partition_num: 2,
+        });
+        info.sub_table_ids = vec![TableId::from(101), TableId::from(102)];
+
+        assert_eq!(2, info.partition_num());
+        assert_eq!("__102", info.sub_table_name(1));
+        assert!(is_reserved_table_name(&info.sub_table_name(0)));
+
+        assert_eq!("__100_1", sub_table_id_key(TableId::from(100), 1));
+        assert!(!is_reserved_table_name("cpu"));
+    }
 }
@@ -2,107 +2,458 @@
 
 //! Partition rules
 
-use common_types::{datum::Datum, row::Row, schema::Schema};
-use common_util::define_result;
+use std::collections::BTreeSet;
+
+use common_types::{
+    datum::{Datum, DatumKind},
+    hash::hash64,
+    row::Row,
+    schema::Schema,
+    time::Timestamp,
+};
+use common_util::{
+    codec::{compact::MemCompactEncoder, Encoder},
+    define_result,
+};
 use smallvec::SmallVec;
-use snafu::{ensure, Backtrace, ResultExt, Snafu};
+use snafu::{Backtrace, OptionExt, Snafu};
+
+use crate::{
+    partition::{ListPartition, PartitionInfo, PartitionMethod, RangePartition},
+    predicate::Predicate,
+};
 
-use crate::partition::{expression::Expression, PartitionInfo, PartitionType};
+const HASH_COLUMN_NUM: usize = 2;
 
-const HASH_COLUMN_NUM: usize = 1;
+/// Max number of the value combinations of the hash columns to evaluate when
+/// pruning partitions, all the partitions are chosen if exceeded.
+const MAX_HASH_PRUNE_COMBINATIONS: usize = 256;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
-    #[snafu(display("No column for hash partitioning.\nBacktrace:\n{}", backtrace))]
-    NoColumnForHash { backtrace: Backtrace },
-
-    #[snafu(display("Only support one hash column.\nBacktrace:\n{}", backtrace))]
-    TooMuchHashColumn { backtrace: Backtrace },
+    #[snafu(display(
+        "Partition column not found, column:{}.\nBacktrace:\n{}",
+        column,
+        backtrace
+    ))]
+    ColumnNotFound {
+        column: String,
+        backtrace: Backtrace,
+    },
 
-    #[snafu(display("Failed to eval partition expr, err:{}", source))]
-    EvalExpr {
-        source: crate::partition::expression::Error,
+    #[snafu(display(
+        "No partition for the row, column:{}, value:{:?}.\nBacktrace:\n{}",
+        column,
+        value,
+        backtrace
+    ))]
+    NoPartitionForRow {
+        column: String,
+        value: Datum,
+        backtrace: Backtrace,
     },
 }
 
 define_result!(Error);
 
 /// Partition rule locate partition by input records
-// TODO(yingwen): Recreate partition rule once the schema of the table is changed
 #[derive(Debug)]
 pub enum PartitionRule {
-    None,
     Hash(HashPartitionRule),
+    Range(RangePartitionRule),
+    List(ListPartitionRule),
 }
 
 impl PartitionRule {
+    /// Create the rule for rows in `schema`.
+    ///
+    /// The rule should be recreated once the schema of rows is changed.
     pub fn new(partition_info: &PartitionInfo, schema: &Schema) -> Result<Self> {
-        match partition_info.partition_type {
-            PartitionType::None => Ok(PartitionRule::None),
-            PartitionType::Hash => {
-                let rule = HashPartitionRule::new(partition_info, schema)?;
-                Ok(PartitionRule::Hash(rule))
+        let rule = match &partition_info.method {
+            PartitionMethod::Hash {
+                columns,
+                partition_num,
+            } => PartitionRule::Hash(HashPartitionRule::new(
+                columns,
+                usize::from(*partition_num),
+                schema,
+            )?),
+            PartitionMethod::Range { column, partitions } => {
+                PartitionRule::Range(RangePartitionRule::new(column, partitions, schema)?)
             }
-        }
+            PartitionMethod::List { column, partitions } => {
+                PartitionRule::List(ListPartitionRule::new(column, partitions, schema)?)
+            }
+        };
+
+        Ok(rule)
     }
 
     /// Return the index of partition
     pub fn locate_partition(&self, row: &Row) -> Result<usize> {
         match self {
-            // Always return the first partition
-            PartitionRule::None => Ok(0),
-            PartitionRule::Hash(rule) => rule.eval_partition_index(row),
+            PartitionRule::Hash(rule) => Ok(rule.locate_partition(row)),
+            PartitionRule::Range(rule) => rule.locate_partition(row),
+            PartitionRule::List(rule) => rule.locate_partition(row),
+        }
+    }
+
+    /// Return the indexes (in ascending order) of the partitions which may
+    /// contain rows matching the `predicate`.
+    pub fn prune(&self, predicate: &Predicate) -> Vec<usize> {
+        match self {
+            PartitionRule::Hash(rule) => rule.prune(predicate),
+            PartitionRule::Range(rule) => rule.prune(predicate),
+            PartitionRule::List(rule) => rule.prune(predicate),
         }
     }
 }
 
-/// Partition rule based on hash
+/// Partition rule based on hash of the partition columns
 #[derive(Debug)]
 pub struct HashPartitionRule {
     /// Total number of partitions
-    partition_num: u16,
-    /// Expression to evaluate a hash value
-    expression: Expression,
+    partition_num: usize,
+    /// Name and data type of the partition columns
+    columns: Vec<(String, DatumKind)>,
     /// Offsets of columns for evaluate
-    // TODO(yingwen): The column index may be invalid after schema change (add/del column)
     column_index: SmallVec<[usize; HASH_COLUMN_NUM]>,
 }
 
 impl HashPartitionRule {
-    pub fn new(partition_info: &PartitionInfo, schema: &Schema) -> Result<Self> {
-        let expr = Expression::new(partition_info);
-
-        let col_name_list = expr.extract_column_name();
-        let mut column_index = SmallVec::with_capacity(col_name_list.size_hint().0);
-        for col_name in col_name_list {
-            for (i, v) in schema.columns().iter().enumerate() {
-                if col_name == v.name {
-                    column_index.push(i);
-                    break;
-                }
+    fn new(columns: &[String], partition_num: usize, schema: &Schema) -> Result<Self> {
+        let mut column_index = SmallVec::with_capacity(columns.len());
+        let mut column_kinds = Vec::with_capacity(columns.len());
+        for column in columns {
+            let index = column_index_of(schema, column)?;
+            column_index.push(index);
+            column_kinds.push((column.clone(), schema.column(index).data_type));
+        }
+
+        Ok(Self {
+            partition_num,
+            columns: column_kinds,
+            column_index,
+        })
+    }
+
+    fn locate_partition(&self, row: &Row) -> usize {
+        self.partition_of(self.column_index.iter().map(|i| &row[*i]))
+    }
+
+    /// Hash the datums in compact format, so the same values always go to the
+    /// same partition.
+    fn partition_of<'a>(&self, datums: impl Iterator<Item = &'a Datum>) -> usize {
+        let encoder = MemCompactEncoder;
+        let mut buf = Vec::new();
+        for datum in datums {
+            encoder
+                .encode(&mut buf, datum)
+                .expect("Should encode datum into the buffer successfully");
+        }
+
+        (hash64(&buf) % self.partition_num as u64) as usize
+    }
+
+    /// Only the predicate with equality (or IN list) on all the partition
+    /// columns can be used to prune partitions.
+    fn prune(&self, predicate: &Predicate) -> Vec<usize> {
+        let mut candidates = Vec::with_capacity(self.columns.len());
+        let mut num_combinations = 1_usize;
+        for (column, kind) in &self.columns {
+            let values = match predicate.values_of_column(column, *kind) {
+                Some(v) => v,
+                None => return (0..self.partition_num).collect(),
+            };
+            num_combinations = num_combinations.saturating_mul(values.len());
+            if num_combinations > MAX_HASH_PRUNE_COMBINATIONS {
+                return (0..self.partition_num).collect();
+            }
+            candidates.push(values);
+        }
+
+        let mut combinations: Vec<Vec<&Datum>> = vec![Vec::new()];
+        for values in &candidates {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|prefix| {
+                    values.iter().map(move |v| {
+                        let mut combination = prefix.clone();
+                        combination.push(v);
+                        combination
+                    })
+                })
+                .collect();
+        }
+
+        let partitions: BTreeSet<_> = combinations
+            .into_iter()
+            .map(|combination| self.partition_of(combination.into_iter()))
+            .collect();
+
+        partitions.into_iter().collect()
+    }
+}
+
+/// Partition rule based on the ranges of timestamp
+#[derive(Debug)]
+pub struct RangePartitionRule {
+    column: String,
+    column_index: usize,
+    /// Exclusive upper bounds of the partitions in ascending order, None means
+    /// unbounded
+    upper_bounds: Vec<Option<Timestamp>>,
+}
+
+impl RangePartitionRule {
+    fn new(column: &str, partitions: &[RangePartition], schema: &Schema) -> Result<Self> {
+        Ok(Self {
+            column: column.to_string(),
+            column_index: column_index_of(schema, column)?,
+            upper_bounds: partitions.iter().map(|p| p.less_than).collect(),
+        })
+    }
+
+    fn locate_partition(&self, row: &Row) -> Result<usize> {
+        let value = &row[self.column_index];
+        let partition = value.as_timestamp().and_then(|ts| {
+            self.upper_bounds
+                .iter()
+                .position(|bound| bound.map(|b| ts < b).unwrap_or(true))
+        });
+
+        partition.with_context(|| NoPartitionForRow {
+            column: &self.column,
+            value: value.clone(),
+        })
+    }
+
+    fn prune(&self, predicate: &Predicate) -> Vec<usize> {
+        let start = predicate.time_range.inclusive_start();
+        let end = predicate.time_range.exclusive_end();
+        if start >= end {
+            return Vec::new();
+        }
+
+        let mut partitions = Vec::new();
+        let mut lower_bound = Timestamp::MIN;
+        for (idx, upper_bound) in self.upper_bounds.iter().enumerate() {
+            // The partition covers [lower_bound, upper_bound).
+            let below_end = lower_bound < end;
+            let above_start = upper_bound.map(|b| start < b).unwrap_or(true);
+            if below_end && above_start {
+                partitions.push(idx);
+            }
+
+            match upper_bound {
+                Some(b) => lower_bound = *b,
+                None => break,
             }
         }
 
-        ensure!(!column_index.is_empty(), NoColumnForHash);
-        ensure!(column_index.len() == 1, TooMuchHashColumn);
+        partitions
+    }
+}
+
+/// Partition rule based on the values of a tag column
+#[derive(Debug)]
+pub struct ListPartitionRule {
+    column: String,
+    column_index: usize,
+    data_type: DatumKind,
+    partition_values: Vec<Vec<Datum>>,
+}
+
+impl ListPartitionRule {
+    fn new(column: &str, partitions: &[ListPartition], schema: &Schema) -> Result<Self> {
+        let column_index = column_index_of(schema, column)?;
 
         Ok(Self {
-            partition_num: partition_info.partition_num,
-            expression: expr,
+            column: column.to_string(),
             column_index,
+            data_type: schema.column(column_index).data_type,
+            partition_values: partitions.iter().map(|p| p.values.clone()).collect(),
         })
     }
 
-    // TODO(yingwen): Also pass schema?
-    pub fn eval_partition_index(&self, row: &Row) -> Result<usize> {
-        let mut col_vals: SmallVec<[&Datum; HASH_COLUMN_NUM]> =
-            SmallVec::with_capacity(self.column_index.len());
-        for i in &self.column_index {
-            // TODO(yingwen): Check index?
-            col_vals.push(&row[*i]);
+    fn partition_of(&self, value: &Datum) -> Option<usize> {
+        self.partition_values
+            .iter()
+            .position(|values| values.contains(value))
+    }
+
+    fn locate_partition(&self, row: &Row) -> Result<usize> {
+        let value = &row[self.column_index];
+
+        self.partition_of(value).with_context(|| NoPartitionForRow {
+            column: &self.column,
+            value: value.clone(),
+        })
+    }
+
+    fn prune(&self, predicate: &Predicate) -> Vec<usize> {
+        match predicate.values_of_column(&self.column, self.data_type) {
+            Some(values) => {
+                let partitions: BTreeSet<_> = values
+                    .iter()
+                    .filter_map(|value| self.partition_of(value))
+                    .collect();
+                partitions.into_iter().collect()
+            }
+            None => (0..self.partition_values.len()).collect(),
+        }
+    }
+}
+
+fn column_index_of(schema: &Schema, column: &str) -> Result<usize> {
+    schema.index_of(column).context(ColumnNotFound { column })
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_deps::datafusion::{
+        logical_plan::{col, lit, Expr},
+        scalar::ScalarValue,
+    };
+    use common_types::{
+        bytes::Bytes,
+        tests::{build_row, build_schema},
+        time::TimeRange,
+    };
+
+    use super::*;
+
+    fn build_predicate(exprs: Vec<Expr>, time_range: TimeRange) -> Predicate {
+        Predicate { exprs, time_range }
+    }
+
+    fn key1_literal(v: &[u8]) -> Expr {
+        lit(ScalarValue::Binary(Some(v.to_vec())))
+    }
+
+    #[test]
+    fn test_hash_partition_rule() {
+        let schema = build_schema();
+        let info = PartitionInfo::new(PartitionMethod::Hash {
+            columns: vec!["key1".to_string()],
+            partition_num: 4,
+        });
+        let rule = PartitionRule::new(&info, &schema).unwrap();
+
+        let row = build_row(b"a", 1000, 10.0, "v1");
+        let partition = rule.locate_partition(&row).unwrap();
+        assert!(partition < 4);
+        // Always locate the same partition for the same key.
+        let row = build_row(b"a", 2000, 20.0, "v2");
+        assert_eq!(partition, rule.locate_partition(&row).unwrap());
+
+        let predicate = build_predicate(
+            vec![col("key1").eq(key1_literal(b"a"))],
+            TimeRange::min_to_max(),
+        );
+        assert_eq!(vec![partition], rule.prune(&predicate));
+
+        // Can't prune by range of the partition key.
+        let predicate = build_predicate(
+            vec![col("key1").gt(key1_literal(b"a"))],
+            TimeRange::min_to_max(),
+        );
+        assert_eq!(vec![0, 1, 2, 3], rule.prune(&predicate));
+    }
+
+    #[test]
+    fn test_range_partition_rule() {
+        let schema = build_schema();
+        let info = PartitionInfo::new(PartitionMethod::Range {
+            column: schema.timestamp_name().to_string(),
+            partitions: vec![
+                RangePartition {
+                    name: "p0".to_string(),
+                    less_than: Some(Timestamp::new(1000)),
+                },
+                RangePartition {
+                    name: "p1".to_string(),
+                    less_than: Some(Timestamp::new(2000)),
+                },
+                RangePartition {
+                    name: "p2".to_string(),
+                    less_than: None,
+                },
+            ],
+        });
+        let rule = PartitionRule::new(&info, &schema).unwrap();
+
+        for (ts, expect) in [(999, 0), (1000, 1), (1999, 1), (2000, 2), (i64::MAX, 2)] {
+            let row = build_row(b"a", ts, 10.0, "v1");
+            assert_eq!(expect, rule.locate_partition(&row).unwrap());
         }
-        let eval_uint = self.expression.eval_uint(&col_vals).context(EvalExpr)?;
 
-        Ok((eval_uint % self.partition_num as u64) as usize)
+        let cases = [
+            ((0, 1000), vec![0]),
+            ((500, 1500), vec![0, 1]),
+            ((1000, 2000), vec![1]),
+            ((1500, 5000), vec![1, 2]),
+            ((3000, 3000), vec![]),
+        ];
+        for ((start, end), expect) in cases {
+            let predicate = build_predicate(vec![], TimeRange::new_unchecked_for_test(start, end));
+            assert_eq!(expect, rule.prune(&predicate));
+        }
+        let predicate = build_predicate(vec![], TimeRange::min_to_max());
+        assert_eq!(vec![0, 1, 2], rule.prune(&predicate));
+    }
+
+    #[test]
+    fn test_list_partition_rule() {
+        let schema = build_schema();
+        let info = PartitionInfo::new(PartitionMethod::List {
+            column: "key1".to_string(),
+            partitions: vec![
+                ListPartition {
+                    name: "ab".to_string(),
+                    values: vec![
+                        Datum::Varbinary(Bytes::from_static(b"a")),
+                        Datum::Varbinary(Bytes::from_static(b"b")),
+                    ],
+                },
+                ListPartition {
+                    name: "c".to_string(),
+                    values: vec![Datum::Varbinary(Bytes::from_static(b"c"))],
+                },
+            ],
+        });
+        let rule = PartitionRule::new(&info, &schema).unwrap();
+
+        let row = build_row(b"b", 1000, 10.0, "v1");
+        assert_eq!(0, rule.locate_partition(&row).unwrap());
+        let row = build_row(b"c", 1000, 10.0, "v1");
+        assert_eq!(1, rule.locate_partition(&row).unwrap());
+        let row = build_row(b"d", 1000, 10.0, "v1");
+        assert!(rule.locate_partition(&row).is_err());
+
+        let predicate = build_predicate(
+            vec![col("key1").eq(key1_literal(b"c"))],
+            TimeRange::min_to_max(),
+        );
+        assert_eq!(vec![1], rule.prune(&predicate));
+
+        let predicate = build_predicate(
+            vec![col("key1").in_list(vec![key1_literal(b"a"), key1_literal(b"b")], false)],
+            TimeRange::min_to_max(),
+        );
+        assert_eq!(vec![0], rule.prune(&predicate));
+
+        // Contradictory predicates.
+        let predicate = build_predicate(
+            vec![
+                col("key1").eq(key1_literal(b"a")),
+                col("key1").eq(key1_literal(b"c")),
+            ],
+            TimeRange::min_to_max(),
+        );
+        assert!(rule.prune(&predicate).is_empty());
+
+        let predicate = build_predicate(vec![], TimeRange::min_to_max());
+        assert_eq!(vec![0, 1], rule.prune(&predicate));
     }
 }
@@ -0,0 +1,411 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Partitioned table implementation
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use arrow_deps::datafusion::logical_plan::{col, lit, Expr};
+use async_trait::async_trait;
+use common_types::{
+    record_batch::RecordBatch,
+    row::{Row, RowGroupBuilder},
+    schema::{RecordSchema, Schema},
+    time::TimeRange,
+};
+use futures::{future, stream::Stream};
+use snafu::ResultExt;
+
+use crate::{
+    partition::{rule::PartitionRule, PartitionInfo},
+    predicate::Predicate,
+    stream::{self, PartitionedStreams, RecordBatchStream, SendableRecordBatchStream},
+    table::{
+        AlterOptions, AlterSchemaRequest, CompactionTaskInfo, DeleteRequest, FlushRequest,
+        GetRequest, ReadOptions, ReadRequest, Result, Scan, ScanStatistics, SstFileInfo, Table,
+        TableId, TableRef, TableStats, UnsupportedMethod, Write, WriteRequest,
+    },
+};
+
+/// Partitioned table
+///
+/// The rows are stored in the sub tables, one for each partition. Writes are
+/// split by the partition rule and reads only scan the partitions not pruned by
+/// the predicate.
+pub struct PartitionTableImpl {
+    /// Table name
+    name: String,
+    /// Table id
+    id: TableId,
+    /// Engine type
+    engine_type: String,
+    /// How the table is partitioned
+    partition_info: PartitionInfo,
+    /// Sub tables, the `i-th` one holds the rows of the `i-th` partition
+    sub_tables: Vec<TableRef>,
+}
+
+impl PartitionTableImpl {
+    /// Create a partitioned table from its sub tables.
+    ///
+    /// REQUIRE: `sub_tables` is not empty and the number of sub tables equals
+    /// to the partition num.
+    pub fn new(
+        name: String,
+        id: TableId,
+        engine_type: String,
+        partition_info: PartitionInfo,
+        sub_tables: Vec<TableRef>,
+    ) -> Self {
+        assert!(!sub_tables.is_empty());
+        assert_eq!(partition_info.partition_num(), sub_tables.len());
+
+        Self {
+            name,
+            id,
+            engine_type,
+            partition_info,
+            sub_tables,
+        }
+    }
+
+    /// Returns the indexes of the partitions to read.
+    fn prune_partitions(&self, predicate: &Predicate) -> Result<Vec<usize>> {
+        let rule = PartitionRule::new(&self.partition_info, &self.schema())
+            .map_err(|e| Box::new(e) as _)
+            .context(Scan { table: &self.name })?;
+
+        Ok(rule.prune(predicate))
+    }
+
+    /// Build a read request to a sub table.
+    fn sub_read_request(request: &ReadRequest, read_parallelism: usize) -> ReadRequest {
+        ReadRequest {
+            request_id: request.request_id,
+            opts: ReadOptions {
+                batch_size: request.opts.batch_size,
+                read_parallelism,
+            },
+            projected_schema: request.projected_schema.clone(),
+            predicate: request.predicate.clone(),
+            order: request.order,
+            limit: request.limit,
+        }
+    }
+}
+
+impl fmt::Debug for PartitionTableImpl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PartitionTableImpl")
+            .field("name", &self.name)
+            .field("id", &self.id)
+            .field("partition_info", &self.partition_info)
+            .field(
+                "sub_tables",
+                &self.sub_tables.iter().map(|t| t.name()).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Table for PartitionTableImpl {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn id(&self) -> TableId {
+        self.id
+    }
+
+    fn schema(&self) -> Schema {
+        // All the sub tables share the same schema.
+        self.sub_tables[0].schema()
+    }
+
+    fn options(&self) -> HashMap<String, String> {
+        self.sub_tables[0].options()
+    }
+
+    fn partition_info(&self) -> Option<PartitionInfo> {
+        Some(self.partition_info.clone())
+    }
+
+    fn engine_type(&self) -> &str {
+        &self.engine_type
+    }
+
+    fn stats(&self) -> TableStats {
+        self.sub_tables
+            .iter()
+            .map(|t| t.stats())
+            .fold(TableStats::default(), |acc, stats| TableStats {
+                num_write: acc.num_write + stats.num_write,
+                num_read: acc.num_read + stats.num_read,
+                num_flush: acc.num_flush + stats.num_flush,
+                memtable_memory_usage: acc.memtable_memory_usage + stats.memtable_memory_usage,
+                num_sst_files: acc.num_sst_files + stats.num_sst_files,
+                sst_size: acc.sst_size + stats.sst_size,
+            })
+    }
+
+    fn sst_files(&self) -> Vec<SstFileInfo> {
+        self.sub_tables.iter().flat_map(|t| t.sst_files()).collect()
+    }
+
+    fn compaction_tasks(&self) -> Vec<CompactionTaskInfo> {
+        self.sub_tables
+            .iter()
+            .flat_map(|t| t.compaction_tasks())
+            .collect()
+    }
+
+    fn scan_statistics(&self, time_range: TimeRange) -> ScanStatistics {
+        let mut sub_stats = self
+            .sub_tables
+            .iter()
+            .map(|t| t.scan_statistics(time_range));
+        // There is at least one sub table.
+        let first = sub_stats.next().unwrap();
+        sub_stats.fold(first, ScanStatistics::merge)
+    }
+
+    async fn write(&self, request: WriteRequest) -> Result<usize> {
+        let schema = request.row_group.schema().clone();
+        let rule = PartitionRule::new(&self.partition_info, &schema)
+            .map_err(|e| Box::new(e) as _)
+            .context(Write { table: &self.name })?;
+
+        let mut partition_rows: Vec<Vec<Row>> =
+            (0..self.sub_tables.len()).map(|_| Vec::new()).collect();
+        for row in request.row_group {
+            let partition = rule
+                .locate_partition(&row)
+                .map_err(|e| Box::new(e) as _)
+                .context(Write { table: &self.name })?;
+            partition_rows[partition].push(row);
+        }
+
+        let writes = partition_rows
+            .into_iter()
+            .enumerate()
+            .filter(|(_, rows)| !rows.is_empty())
+            .map(|(partition, rows)| {
+                let mut builder = RowGroupBuilder::with_capacity(schema.clone(), rows.len());
+                for row in rows {
+                    builder.push_checked_row(row);
+                }
+                let request = WriteRequest {
+                    row_group: builder.build(),
+                };
+                self.sub_tables[partition].write(request)
+            });
+        let written = future::try_join_all(writes).await?;
+
+        Ok(written.into_iter().sum())
+    }
+
+    async fn delete(&self, request: DeleteRequest) -> Result<usize> {
+        // Prune the partitions by the filters of the delete request.
+        let exprs: Vec<Expr> = request
+            .tag_filters
+            .iter()
+            .filter_map(|filter| {
+                let values: Option<Vec<_>> = filter
+                    .values
+                    .iter()
+                    .map(|v| v.as_scalar_value().map(lit))
+                    .collect();
+                values.map(|v| col(&filter.column).in_list(v, false))
+            })
+            .collect();
+        let predicate = Predicate {
+            exprs,
+            time_range: request.time_range,
+        };
+        let partitions = self.prune_partitions(&predicate)?;
+
+        let deletes = partitions
+            .into_iter()
+            .map(|partition| self.sub_tables[partition].delete(request.clone()));
+        future::try_join_all(deletes).await?;
+
+        Ok(1)
+    }
+
+    async fn read(&self, request: ReadRequest) -> Result<SendableRecordBatchStream> {
+        let partitions = self.prune_partitions(&request.predicate)?;
+        if partitions.len() == 1 {
+            return self.sub_tables[partitions[0]].read(request).await;
+        }
+
+        // Rows from different partitions can't be read in order by chaining.
+        if request.order.is_in_order() && partitions.len() > 1 {
+            return UnsupportedMethod {
+                table: &self.name,
+                method: "read multiple partitions in order",
+            }
+            .fail();
+        }
+
+        let mut streams = VecDeque::with_capacity(partitions.len());
+        for partition in partitions {
+            let sub_request = Self::sub_read_request(&request, 1);
+            streams.push_back(self.sub_tables[partition].read(sub_request).await?);
+        }
+
+        Ok(Box::pin(ChainedStream {
+            schema: request.projected_schema.to_record_schema(),
+            streams,
+        }))
+    }
+
+    async fn get(&self, request: GetRequest) -> Result<Option<Row>> {
+        // The partition key may be not a part of the primary key, so ask every
+        // partition for the row.
+        for sub_table in &self.sub_tables {
+            let sub_request = GetRequest {
+                request_id: request.request_id,
+                projected_schema: request.projected_schema.clone(),
+                primary_key: request.primary_key.clone(),
+            };
+            if let Some(row) = sub_table.get(sub_request).await? {
+                return Ok(Some(row));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn partitioned_read(&self, request: ReadRequest) -> Result<PartitionedStreams> {
+        let read_parallelism = request.opts.read_parallelism;
+        let partitions = self.prune_partitions(&request.predicate)?;
+        if partitions.len() == 1 {
+            return self.sub_tables[partitions[0]]
+                .partitioned_read(request)
+                .await;
+        }
+
+        if request.order.is_in_order() && partitions.len() > 1 {
+            return UnsupportedMethod {
+                table: &self.name,
+                method: "read multiple partitions in order",
+            }
+            .fail();
+        }
+
+        // Spread the parallelism over the partitions.
+        let sub_parallelism = (read_parallelism / partitions.len().max(1)).max(1);
+        let mut sub_streams = Vec::new();
+        for partition in partitions {
+            let sub_request = Self::sub_read_request(&request, sub_parallelism);
+            let partitioned_streams = self.sub_tables[partition]
+                .partitioned_read(sub_request)
+                .await?;
+            sub_streams.extend(partitioned_streams.streams);
+        }
+
+        // The number of returned streams must equal to the read parallelism, so
+        // chain the sub streams in a round-robin way, or leave some chained
+        // streams empty if there are not enough sub streams.
+        let schema = request.projected_schema.to_record_schema();
+        let mut chained: Vec<_> = (0..read_parallelism)
+            .map(|_| ChainedStream {
+                schema: schema.clone(),
+                streams: VecDeque::new(),
+            })
+            .collect();
+        for (idx, stream) in sub_streams.into_iter().enumerate() {
+            chained[idx % read_parallelism].streams.push_back(stream);
+        }
+
+        Ok(PartitionedStreams {
+            streams: chained
+                .into_iter()
+                .map(|s| Box::pin(s) as SendableRecordBatchStream)
+                .collect(),
+        })
+    }
+
+    async fn alter_schema(&self, request: AlterSchemaRequest) -> Result<usize> {
+        // Alter the sub tables one by one so the schema version is checked by
+        // each of them.
+        for sub_table in &self.sub_tables {
+            let sub_request = AlterSchemaRequest {
+                schema: request.schema.clone(),
+                pre_schema_version: request.pre_schema_version,
+            };
+            sub_table.alter_schema(sub_request).await?;
+        }
+
+        Ok(1)
+    }
+
+    async fn alter_options(&self, options: HashMap<String, String>) -> Result<usize> {
+        let alters = self
+            .sub_tables
+            .iter()
+            .map(|t| t.alter_options(options.clone()));
+        future::try_join_all(alters)
+            .await
+            .map_err(|e| Box::new(e) as _)
+            .context(AlterOptions { table: &self.name })?;
+
+        Ok(1)
+    }
+
+    async fn flush(&self, request: FlushRequest) -> Result<()> {
+        let flushes = self.sub_tables.iter().map(|t| {
+            t.flush(FlushRequest {
+                compact_after_flush: request.compact_after_flush,
+                sync: request.sync,
+            })
+        });
+        future::try_join_all(flushes).await?;
+
+        Ok(())
+    }
+
+    async fn compact(&self) -> Result<()> {
+        let compactions = self.sub_tables.iter().map(|t| t.compact());
+        future::try_join_all(compactions).await?;
+
+        Ok(())
+    }
+}
+
+/// Stream reads the streams one by one
+struct ChainedStream {
+    schema: RecordSchema,
+    streams: VecDeque<SendableRecordBatchStream>,
+}
+
+impl Stream for ChainedStream {
+    type Item = stream::Result<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let stream = match self.streams.front_mut() {
+                Some(v) => v,
+                None => return Poll::Ready(None),
+            };
+
+            match stream.as_mut().poll_next(ctx) {
+                Poll::Ready(None) => {
+                    self.streams.pop_front();
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+impl RecordBatchStream for ChainedStream {
+    fn schema(&self) -> &RecordSchema {
+        &self.schema
+    }
+}
@@ -20,6 +20,7 @@ use arrow_deps::{
     parquet::file::statistics::Statistics as ParquetStatistics,
 };
 use common_types::{
+    datum::{Datum, DatumKind},
     schema::Schema,
     time::{TimeRange, Timestamp},
 };
@@ -200,6 +201,69 @@ impl Predicate {
 
         results
     }
+
+    /// Extract the possible values of `column` from the conjunctive exprs,
+    /// only equality and IN list are taken into account.
+    ///
+    /// Returns None if the values of the column is not restricted by the exprs.
+    pub fn values_of_column(&self, column: &str, kind: DatumKind) -> Option<Vec<Datum>> {
+        let mut result: Option<Vec<Datum>> = None;
+        for expr in &self.exprs {
+            let values = match expr {
+                Expr::BinaryExpr {
+                    left,
+                    op: Operator::Eq,
+                    right,
+                } => match (left.as_ref(), right.as_ref()) {
+                    (Expr::Column(col), Expr::Literal(v))
+                    | (Expr::Literal(v), Expr::Column(col))
+                        if col.name == column =>
+                    {
+                        literals_to_datums(std::iter::once(v), kind)
+                    }
+                    _ => None,
+                },
+                Expr::InList {
+                    expr,
+                    list,
+                    negated: false,
+                } => match expr.as_ref() {
+                    Expr::Column(col) if col.name == column => {
+                        let literals: Option<Vec<_>> = list
+                            .iter()
+                            .map(|e| match e {
+                                Expr::Literal(v) => Some(v),
+                                _ => None,
+                            })
+                            .collect();
+                        literals.and_then(|v| literals_to_datums(v.into_iter(), kind))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            if let Some(values) = values {
+                result = match result {
+                    Some(prev) => Some(prev.into_iter().filter(|v| values.contains(v)).collect()),
+                    None => Some(values),
+                };
+            }
+        }
+
+        result
+    }
+}
+
+/// Convert the literals into datums, returns None if any of them is not of
+/// type `kind`, as type coercion is not handled here.
+fn literals_to_datums<'a>(
+    literals: impl Iterator<Item = &'a ScalarValue>,
+    kind: DatumKind,
+) -> Option<Vec<Datum>> {
+    literals
+        .map(|v| Datum::from_scalar_value(v).filter(|datum| datum.kind() == kind))
+        .collect()
 }
 
 /// Builder for [Predicate]
@@ -271,7 +335,7 @@ impl PredicateBuilder {
             return false;
         }
 
-        columns.len() == 1 && Self::is_primitive_binary_expr(expr)
+        columns.len() == 1 && (Self::is_primitive_binary_expr(expr) || Self::is_in_list_expr(expr))
     }
 
     /// Recursively split all "AND" expressions into smaller one
@@ -311,6 +375,22 @@ impl PredicateBuilder {
             _ => false,
         }
     }
+
+    /// Return true if the given expression is in the form: `column IN
+    /// (constant, ...)`, which is useful to prune partitions.
+    fn is_in_list_expr(expr: &Expr) -> bool {
+        match expr {
+            Expr::InList {
+                expr,
+                list,
+                negated: false,
+            } => {
+                matches!(&**expr, Expr::Column(_))
+                    && list.iter().all(|e| matches!(e, Expr::Literal(_)))
+            }
+            _ => false,
+        }
+    }
 }
 
 struct TimeRangeExtractor<'a> {
@@ -12,7 +12,7 @@ use arrow_deps::{
         execution::runtime_env::RuntimeEnv,
         logical_plan::Expr,
         physical_plan::{
-            DisplayFormatType, ExecutionPlan, Partitioning,
+            ColumnStatistics, DisplayFormatType, ExecutionPlan, Partitioning,
             SendableRecordBatchStream as DfSendableRecordBatchStream, Statistics,
         },
     },
@@ -98,6 +98,7 @@ impl TableProviderAdapter {
             read_order,
             read_parallelism,
             predicate,
+            limit,
             stream_state: Mutex::new(ScanStreamState::default()),
         }))
     }
@@ -169,6 +170,9 @@ struct ScanTable {
     read_order: ReadOrder,
     read_parallelism: usize,
     predicate: PredicateRef,
+    /// Max number of rows to read from each partition, None if there is no
+    /// limit.
+    limit: Option<usize>,
 
     stream_state: Mutex<ScanStreamState>,
 }
@@ -189,6 +193,7 @@ impl ScanTable {
             projected_schema: self.projected_schema.clone(),
             predicate: self.predicate.clone(),
             order: self.read_order,
+            limit: self.limit,
         };
 
         let read_res = self.table.partitioned_read(req).await;
@@ -249,16 +254,50 @@ impl ExecutionPlan for ScanTable {
     fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "ScanTable: table={}, parallelism={}, order={:?}, ",
+            "ScanTable: table={}, parallelism={}, order={:?}, limit={:?}, ",
             self.table.name(),
             self.read_parallelism,
             self.read_order,
+            self.limit,
         )
     }
 
     fn statistics(&self) -> Statistics {
-        // TODO(yingwen): Implement this
-        Statistics::default()
+        let stats = self.table.scan_statistics(self.predicate.time_range);
+
+        // The column statistics are unknown if the schema of the table is
+        // changed after the plan is created.
+        let num_columns = self.projected_schema.original_schema().num_columns();
+        let column_statistics = if stats.column_statistics.len() == num_columns {
+            let projection: Vec<usize> = match self.projected_schema.projection() {
+                Some(projection) => projection.to_vec(),
+                None => (0..num_columns).collect(),
+            };
+            let column_statistics = projection
+                .into_iter()
+                .map(|idx| to_df_column_statistics(&stats.column_statistics[idx]))
+                .collect();
+            Some(column_statistics)
+        } else {
+            None
+        };
+
+        Statistics {
+            num_rows: stats.num_rows,
+            total_byte_size: stats.total_byte_size,
+            column_statistics,
+            // All the statistics are estimated.
+            is_exact: false,
+        }
+    }
+}
+
+fn to_df_column_statistics(stats: &table::ColumnStatistics) -> ColumnStatistics {
+    ColumnStatistics {
+        null_count: stats.null_count,
+        max_value: stats.max_value.as_ref().and_then(|v| v.as_scalar_value()),
+        min_value: stats.min_value.as_ref().and_then(|v| v.as_scalar_value()),
+        distinct_count: stats.distinct_count,
     }
 }
 
@@ -270,6 +309,7 @@ impl fmt::Debug for ScanTable {
             .field("read_order", &self.read_order)
             .field("read_parallelism", &self.read_parallelism)
             .field("predicate", &self.predicate)
+            .field("limit", &self.limit)
             .finish()
     }
 }
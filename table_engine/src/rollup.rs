@@ -0,0 +1,514 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Rollup (downsampling) of tables
+//!
+//! A table with rollup options has a companion rollup table named
+//! [rollup_table_name()], which holds the rows of the table aggregated by the
+//! rollup interval. The rollup table is maintained by the engine during
+//! compaction and stores the partial states (sum, count, min, max) of the
+//! aggregates, so a bucket may be made up of several rows and queries must
+//! aggregate the states again.
+//!
+//! Each sst of the table is rolled up into a sst of the rollup table when it is
+//! compacted for the first time, and the rollup sst records the id of the
+//! source sst, so a retried compaction won't roll it up again. The ssts are
+//! rolled up independently, so rows overwritten in different ssts would be
+//! rolled up more than once, and only tables in `APPEND` update mode can have
+//! a rollup.
+//!
+//! The columns used by the rollup can't be dropped or renamed, as the rollup
+//! table is filled by the names of the columns.
+
+use std::{collections::HashMap, fmt, time::Duration};
+
+use common_types::{
+    column_schema::{self, ColumnSchema},
+    datum::DatumKind,
+    schema::{self, Schema},
+};
+use common_util::config::ReadableDuration;
+use serde_derive::Deserialize;
+use snafu::{ensure, Backtrace, GenerateBacktrace, OptionExt, ResultExt, Snafu};
+
+/// Interval of the rollup, e.g. `5m`
+pub const OPTION_KEY_ROLLUP_INTERVAL: &str = "rollup_interval";
+/// Aggregate functions of the fields to rollup, e.g. `value=avg,cpu=max`
+pub const OPTION_KEY_ROLLUP_AGGREGATES: &str = "rollup_aggregates";
+
+const ROLLUP_TABLE_SUFFIX: &str = "__rollup";
+const MIN_ROLLUP_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Rollup option is missing, key:{}.\nBacktrace:\n{}", key, backtrace))]
+    MissingOption { key: String, backtrace: Backtrace },
+
+    #[snafu(display(
+        "Invalid rollup interval, interval:{}, err:{}.\nBacktrace:\n{}",
+        interval,
+        msg,
+        backtrace
+    ))]
+    InvalidInterval {
+        interval: String,
+        msg: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Invalid rollup aggregate, aggregate:{}.\nBacktrace:\n{}",
+        aggregate,
+        backtrace
+    ))]
+    InvalidAggregate {
+        aggregate: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Field to rollup is duplicated, column:{}.\nBacktrace:\n{}",
+        column,
+        backtrace
+    ))]
+    DuplicateColumn {
+        column: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Field to rollup is not found, column:{}.\nBacktrace:\n{}",
+        column,
+        backtrace
+    ))]
+    ColumnNotFound {
+        column: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Only numeric field can be rolled up, column:{}, data_type:{:?}.\nBacktrace:\n{}",
+        column,
+        data_type,
+        backtrace
+    ))]
+    NonNumericColumn {
+        column: String,
+        data_type: DatumKind,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Timestamp must be the last key column of the table to rollup.\nBacktrace:\n{}",
+        backtrace
+    ))]
+    TimestampNotLastKey { backtrace: Backtrace },
+
+    #[snafu(display("Failed to build rollup schema, err:{}", source))]
+    BuildSchema { source: common_types::schema::Error },
+
+    #[snafu(display("Failed to build column of rollup schema, err:{}", source))]
+    BuildColumn {
+        source: common_types::column_schema::Error,
+    },
+}
+
+define_result!(Error);
+
+/// Returns the name of the rollup table of the table `table_name`.
+pub fn rollup_table_name(table_name: &str) -> String {
+    format!("{}{}", table_name, ROLLUP_TABLE_SUFFIX)
+}
+
+/// Returns the name of the table rolled up into the rollup table
+/// `rollup_table_name`, or None if it is not a rollup table.
+pub fn source_table_name(rollup_table_name: &str) -> Option<&str> {
+    rollup_table_name.strip_suffix(ROLLUP_TABLE_SUFFIX)
+}
+
+/// Returns the options to create the rollup table.
+///
+/// The rows of the rollup table are partial states of the aggregates, which
+/// must not be deduplicated, so the rollup table is always in append mode.
+pub fn rollup_table_options() -> HashMap<String, String> {
+    let mut options = HashMap::with_capacity(1);
+    options.insert("update_mode".to_string(), "APPEND".to_string());
+    options
+}
+
+/// Aggregate function to rollup a field
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+pub enum AggregateFunction {
+    Avg,
+    Min,
+    Max,
+    Sum,
+    Count,
+}
+
+impl AggregateFunction {
+    pub fn parse_from(s: &str) -> Option<Self> {
+        let func = match s.to_ascii_lowercase().as_str() {
+            "avg" => AggregateFunction::Avg,
+            "min" => AggregateFunction::Min,
+            "max" => AggregateFunction::Max,
+            "sum" => AggregateFunction::Sum,
+            "count" => AggregateFunction::Count,
+            _ => return None,
+        };
+
+        Some(func)
+    }
+
+    /// Partial states needed to compute this aggregate.
+    pub fn states(&self) -> &'static [AggregateState] {
+        match self {
+            AggregateFunction::Avg => &[AggregateState::Sum, AggregateState::Count],
+            AggregateFunction::Min => &[AggregateState::Min],
+            AggregateFunction::Max => &[AggregateState::Max],
+            AggregateFunction::Sum => &[AggregateState::Sum],
+            AggregateFunction::Count => &[AggregateState::Count],
+        }
+    }
+}
+
+impl fmt::Display for AggregateFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            AggregateFunction::Avg => "avg",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Count => "count",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Partial state of an aggregate stored in the rollup table, the states of the
+/// same bucket can be merged by aggregating them again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggregateState {
+    Sum,
+    Count,
+    Min,
+    Max,
+}
+
+impl AggregateState {
+    /// Data type of the state of a field with type `field_type`.
+    pub fn data_type(&self, field_type: DatumKind) -> DatumKind {
+        match self {
+            AggregateState::Sum => match field_type {
+                DatumKind::Double | DatumKind::Float => DatumKind::Double,
+                DatumKind::UInt64 | DatumKind::UInt32 | DatumKind::UInt16 | DatumKind::UInt8 => {
+                    DatumKind::UInt64
+                }
+                _ => DatumKind::Int64,
+            },
+            AggregateState::Count => DatumKind::UInt64,
+            AggregateState::Min | AggregateState::Max => field_type,
+        }
+    }
+}
+
+impl fmt::Display for AggregateState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            AggregateState::Sum => "sum",
+            AggregateState::Count => "count",
+            AggregateState::Min => "min",
+            AggregateState::Max => "max",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Returns the name of the column holding the `state` of `column` in the
+/// rollup table.
+pub fn state_column_name(column: &str, state: AggregateState) -> String {
+    format!("{}_{}", column, state)
+}
+
+/// Aggregate of a field
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct RollupAggregate {
+    pub column: String,
+    pub function: AggregateFunction,
+}
+
+/// Options to rollup a table
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct RollupOptions {
+    /// Rows are aggregated into buckets of this interval.
+    pub interval: ReadableDuration,
+    /// Aggregate of each field to rollup, other fields are dropped.
+    pub aggregates: Vec<RollupAggregate>,
+}
+
+impl RollupOptions {
+    /// Parse the rollup options from table options, returns None if the table
+    /// has no rollup.
+    pub fn parse_from(options: &HashMap<String, String>) -> Result<Option<Self>> {
+        let interval = options.get(OPTION_KEY_ROLLUP_INTERVAL);
+        let aggregates = options.get(OPTION_KEY_ROLLUP_AGGREGATES);
+        let (interval, aggregates) = match (interval, aggregates) {
+            (None, None) => return Ok(None),
+            (Some(interval), Some(aggregates)) => (interval, aggregates),
+            (None, Some(_)) => {
+                return MissingOption {
+                    key: OPTION_KEY_ROLLUP_INTERVAL,
+                }
+                .fail()
+            }
+            (Some(_), None) => {
+                return MissingOption {
+                    key: OPTION_KEY_ROLLUP_AGGREGATES,
+                }
+                .fail()
+            }
+        };
+
+        let interval =
+            interval
+                .parse::<ReadableDuration>()
+                .map_err(|msg| Error::InvalidInterval {
+                    interval: interval.clone(),
+                    msg,
+                    backtrace: Backtrace::generate(),
+                })?;
+        ensure!(
+            interval.0 >= MIN_ROLLUP_INTERVAL && interval.0.subsec_nanos() == 0,
+            InvalidInterval {
+                interval: interval.to_string(),
+                msg: "interval must be whole seconds and at least 1s",
+            }
+        );
+
+        let mut parsed_aggregates: Vec<RollupAggregate> = Vec::new();
+        for aggregate in aggregates.split(',').map(str::trim) {
+            let (column, function) = aggregate
+                .split_once('=')
+                .context(InvalidAggregate { aggregate })?;
+            let column = column.trim();
+            let function = AggregateFunction::parse_from(function.trim())
+                .context(InvalidAggregate { aggregate })?;
+            ensure!(!column.is_empty(), InvalidAggregate { aggregate });
+            ensure!(
+                parsed_aggregates.iter().all(|v| v.column != column),
+                DuplicateColumn { column }
+            );
+
+            parsed_aggregates.push(RollupAggregate {
+                column: column.to_string(),
+                function,
+            });
+        }
+
+        Ok(Some(Self {
+            interval,
+            aggregates: parsed_aggregates,
+        }))
+    }
+
+    pub fn fill_raw_map(&self, m: &mut HashMap<String, String>) {
+        m.insert(
+            OPTION_KEY_ROLLUP_INTERVAL.to_string(),
+            self.interval.to_string(),
+        );
+        let aggregates: Vec<_> = self
+            .aggregates
+            .iter()
+            .map(|v| format!("{}={}", v.column, v.function))
+            .collect();
+        m.insert(
+            OPTION_KEY_ROLLUP_AGGREGATES.to_string(),
+            aggregates.join(","),
+        );
+    }
+
+    /// Returns the aggregate of the `column`, or None if the column is not
+    /// rolled up.
+    pub fn aggregate_of(&self, column: &str) -> Option<&RollupAggregate> {
+        self.aggregates.iter().find(|v| v.column == column)
+    }
+
+    /// Build the schema of the rollup table from the schema of the table.
+    ///
+    /// The rollup table has the same key columns and tag columns as the
+    /// table, followed by the state columns of each aggregate. The timestamp
+    /// must be the last key column, so the rows of a bucket are adjacent.
+    pub fn build_rollup_schema(&self, schema: &Schema) -> Result<Schema> {
+        ensure!(
+            schema.timestamp_index() + 1 == schema.num_key_columns(),
+            TimestampNotLastKey
+        );
+
+        let mut builder = schema::Builder::with_capacity(schema.num_columns())
+            .auto_increment_column_id(true)
+            .enable_tsid_primary_key(schema.index_of_tsid().is_some());
+        for column in schema.key_columns() {
+            builder = builder
+                .add_key_column(column.clone())
+                .context(BuildSchema)?;
+        }
+        for column in schema.normal_columns().iter().filter(|c| c.is_tag) {
+            builder = builder
+                .add_normal_column(column.clone())
+                .context(BuildSchema)?;
+        }
+
+        for aggregate in &self.aggregates {
+            let column = field_column(schema, &aggregate.column)?;
+            for state in aggregate.function.states() {
+                let state_column = column_schema::Builder::new(
+                    state_column_name(&column.name, *state),
+                    state.data_type(column.data_type),
+                )
+                .is_nullable(true)
+                .build()
+                .context(BuildColumn)?;
+                builder = builder
+                    .add_normal_column(state_column)
+                    .context(BuildSchema)?;
+            }
+        }
+
+        builder.build().context(BuildSchema)
+    }
+}
+
+fn field_column<'a>(schema: &'a Schema, name: &str) -> Result<&'a ColumnSchema> {
+    let column = schema
+        .column_with_name(name)
+        .context(ColumnNotFound { column: name })?;
+    ensure!(
+        column.data_type.is_f64_castable(),
+        NonNumericColumn {
+            column: name,
+            data_type: column.data_type,
+        }
+    );
+
+    Ok(column)
+}
+
+#[cfg(test)]
+mod tests {
+    use common_types::tests::build_schema;
+
+    use super::*;
+
+    fn build_options(interval: &str, aggregates: &str) -> HashMap<String, String> {
+        let mut options = HashMap::new();
+        options.insert(OPTION_KEY_ROLLUP_INTERVAL.to_string(), interval.to_string());
+        options.insert(
+            OPTION_KEY_ROLLUP_AGGREGATES.to_string(),
+            aggregates.to_string(),
+        );
+        options
+    }
+
+    #[test]
+    fn test_parse_rollup_options() {
+        assert!(RollupOptions::parse_from(&HashMap::new())
+            .unwrap()
+            .is_none());
+
+        let options = build_options("5m", "field1=avg, field2=MAX");
+        let rollup = RollupOptions::parse_from(&options).unwrap().unwrap();
+        assert_eq!(Duration::from_secs(300), rollup.interval.0);
+        assert_eq!(
+            vec![
+                RollupAggregate {
+                    column: "field1".to_string(),
+                    function: AggregateFunction::Avg,
+                },
+                RollupAggregate {
+                    column: "field2".to_string(),
+                    function: AggregateFunction::Max,
+                },
+            ],
+            rollup.aggregates
+        );
+
+        let mut raw_map = HashMap::new();
+        rollup.fill_raw_map(&mut raw_map);
+        assert_eq!(
+            rollup,
+            RollupOptions::parse_from(&raw_map).unwrap().unwrap()
+        );
+
+        for (interval, aggregates) in [
+            ("5m", "field1"),
+            ("5m", "field1=median"),
+            ("5m", "field1=avg,field1=max"),
+            ("500ms", "field1=avg"),
+            ("abc", "field1=avg"),
+        ] {
+            assert!(RollupOptions::parse_from(&build_options(interval, aggregates)).is_err());
+        }
+
+        let mut options = build_options("5m", "field1=avg");
+        options.remove(OPTION_KEY_ROLLUP_AGGREGATES);
+        assert!(RollupOptions::parse_from(&options).is_err());
+    }
+
+    #[test]
+    fn test_rollup_table_name() {
+        let name = rollup_table_name("t1");
+        assert_eq!("t1__rollup", name);
+        assert_eq!(Some("t1"), source_table_name(&name));
+        assert_eq!(None, source_table_name("t1"));
+    }
+
+    #[test]
+    fn test_build_rollup_schema() {
+        let schema = build_schema();
+        let options = build_options("5m", "field1=avg");
+        let rollup = RollupOptions::parse_from(&options).unwrap().unwrap();
+        let rollup_schema = rollup.build_rollup_schema(&schema).unwrap();
+
+        assert_eq!(schema.key_columns(), rollup_schema.key_columns());
+        let normal_columns: Vec<_> = rollup_schema
+            .normal_columns()
+            .iter()
+            .map(|c| (c.name.as_str(), c.data_type))
+            .collect();
+        assert_eq!(
+            vec![
+                ("field1_sum", DatumKind::Double),
+                ("field1_count", DatumKind::UInt64)
+            ],
+            normal_columns
+        );
+
+        // Only numeric fields can be rolled up.
+        let options = build_options("5m", "field2=max");
+        let rollup = RollupOptions::parse_from(&options).unwrap().unwrap();
+        assert!(rollup.build_rollup_schema(&schema).is_err());
+
+        // The timestamp must be the last key column.
+        let column = |name: &str, data_type| {
+            column_schema::Builder::new(name.to_string(), data_type)
+                .build()
+                .unwrap()
+        };
+        let schema = schema::Builder::new()
+            .auto_increment_column_id(true)
+            .add_key_column(column("ts", DatumKind::Timestamp))
+            .unwrap()
+            .add_key_column(column("key", DatumKind::Varbinary))
+            .unwrap()
+            .add_normal_column(column("field1", DatumKind::Double))
+            .unwrap()
+            .build()
+            .unwrap();
+        let rollup = RollupOptions::parse_from(&build_options("5m", "field1=avg"))
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            rollup.build_rollup_schema(&schema),
+            Err(Error::TimestampNotLastKey { .. })
+        ));
+    }
+}
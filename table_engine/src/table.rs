@@ -20,7 +20,7 @@ use common_types::{
     request_id::RequestId,
     row::{Row, RowGroup},
     schema::{RecordSchemaWithKey, Schema, Version},
-    time::Timestamp,
+    time::{TimeRange, Timestamp},
 };
 use proto::sys_catalog::{TableEntry, TableState as TableStatePb};
 use serde_derive::Deserialize;
@@ -28,6 +28,7 @@ use snafu::{Backtrace, Snafu};
 
 use crate::{
     engine::{TableRequestType, TableState},
+    partition::{self, PartitionInfo},
     predicate::PredicateRef,
     stream::{PartitionedStreams, SendableRecordBatchStream},
 };
@@ -90,6 +91,12 @@ pub enum Error {
         source: Box<dyn std::error::Error + Send + Sync>,
     },
 
+    #[snafu(display("Failed to delete from table, table:{}, err:{}", table, source))]
+    Delete {
+        table: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
     #[snafu(display("Failed to scan table, table:{}, err:{}", table, source))]
     Scan {
         table: String,
@@ -133,7 +140,7 @@ define_result!(Error);
 pub const DEFAULT_READ_PARALLELISM: usize = 8;
 
 /// Schema id (24 bits)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SchemaId(u32);
 
 impl SchemaId {
@@ -294,13 +301,32 @@ impl fmt::Display for TableId {
     }
 }
 
-// TODO(yingwen): Support DELETE/UPDATE... , a mutation type is needed.
+// TODO(yingwen): Support UPDATE... , a mutation type is needed. DELETE is
+// served by [DeleteRequest].
 #[derive(Debug)]
 pub struct WriteRequest {
     /// rows to write
     pub row_group: RowGroup,
 }
 
+/// Filter on a tag column, matches the rows whose value of `column` equals to
+/// any one of the `values`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagFilter {
+    pub column: String,
+    pub values: Vec<Datum>,
+}
+
+/// Delete the rows in `time_range` matching all the `tag_filters`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeleteRequest {
+    /// Time range of the rows to delete
+    pub time_range: TimeRange,
+    /// Filters on tag columns, all of them must be satisfied. Empty filters
+    /// matches all the rows in `time_range`.
+    pub tag_filters: Vec<TagFilter>,
+}
+
 #[derive(Debug)]
 pub struct ReadOptions {
     pub batch_size: usize,
@@ -375,6 +401,12 @@ pub struct ReadRequest {
     pub predicate: PredicateRef,
     /// Read the rows in reverse order.
     pub order: ReadOrder,
+    /// Max number of rows each output stream returns, None if there is no
+    /// limit.
+    ///
+    /// The rows are cut in the read `order`, so the limit can't be set if the
+    /// rows need to be filtered after reading.
+    pub limit: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -421,15 +453,47 @@ pub trait Table: std::fmt::Debug {
     /// Options of this table.
     fn options(&self) -> HashMap<String, String>;
 
+    /// Partition info of this table, returns None if the table is not
+    /// partitioned.
+    fn partition_info(&self) -> Option<PartitionInfo>;
+
     /// Engine type of this table.
     fn engine_type(&self) -> &str;
 
     /// Get table's statistics.
     fn stats(&self) -> TableStats;
 
+    /// Returns the sst files of this table, empty if the table has no sst.
+    fn sst_files(&self) -> Vec<SstFileInfo> {
+        Vec::new()
+    }
+
+    /// Returns the running compaction tasks of this table.
+    fn compaction_tasks(&self) -> Vec<CompactionTaskInfo> {
+        Vec::new()
+    }
+
+    /// Returns the watermark of the rollup of this table, all the rows before
+    /// it have been rolled up into the rollup table. Returns None if the table
+    /// has no rollup table.
+    fn rollup_watermark(&self) -> Option<Timestamp> {
+        None
+    }
+
+    /// Estimate the statistics of the rows in the `time_range`, used by the
+    /// query planner.
+    fn scan_statistics(&self, time_range: TimeRange) -> ScanStatistics;
+
     /// Write to table.
     async fn write(&self, request: WriteRequest) -> Result<usize>;
 
+    /// Delete rows matching the [DeleteRequest] from table.
+    ///
+    /// The number of deleted rows is unknown when the delete is applied, so the
+    /// returned number is the number of applied delete requests instead of the
+    /// number of deleted rows.
+    async fn delete(&self, request: DeleteRequest) -> Result<usize>;
+
     /// Read from table.
     async fn read(&self, request: ReadRequest) -> Result<SendableRecordBatchStream>;
 
@@ -469,6 +533,127 @@ pub struct TableStats {
     pub num_read: u64,
     /// Total flush request
     pub num_flush: u64,
+    /// Memory usage of the memtables in bytes
+    pub memtable_memory_usage: u64,
+    /// Total number of the sst files
+    pub num_sst_files: u64,
+    /// Total size of the sst files in bytes
+    pub sst_size: u64,
+}
+
+/// Info of a sst file of table.
+#[derive(Debug, Clone)]
+pub struct SstFileInfo {
+    /// Id of the table the file belongs to, which is the sub table of a
+    /// partitioned table
+    pub table_id: TableId,
+    /// Level of the file
+    pub level: u16,
+    /// Id of the file
+    pub file_id: u64,
+    /// Time range of the rows in the file
+    pub time_range: TimeRange,
+    /// Size of the file in bytes
+    pub size: u64,
+    /// Number of rows in the file
+    pub row_num: u64,
+    /// Max sequence of the rows in the file
+    pub max_sequence: u64,
+    /// Whether the file is being compacted
+    pub being_compacted: bool,
+}
+
+/// Info of a running compaction task of table.
+#[derive(Debug, Clone)]
+pub struct CompactionTaskInfo {
+    /// Id of the request starting the task
+    pub request_id: u64,
+    /// Time when the task starts
+    pub start_time: Timestamp,
+    /// Number of the sst files to compact
+    pub num_input_files: u64,
+    /// Total size of the sst files to compact in bytes
+    pub input_size: u64,
+    /// Number of the expired sst files to purge
+    pub num_expired_files: u64,
+}
+
+/// Estimated statistics of the rows to scan.
+#[derive(Debug, Clone, Default)]
+pub struct ScanStatistics {
+    /// Number of rows, None if unknown
+    pub num_rows: Option<usize>,
+    /// Total size of the rows in bytes, None if unknown
+    pub total_byte_size: Option<usize>,
+    /// Statistics of the columns in the order of the table schema, empty if
+    /// unknown
+    pub column_statistics: Vec<ColumnStatistics>,
+}
+
+impl ScanStatistics {
+    /// Merge the statistics of the rows in another table with disjoint rows,
+    /// e.g. another partition of the same table.
+    pub fn merge(self, other: ScanStatistics) -> Self {
+        let column_statistics = if self.column_statistics.len() == other.column_statistics.len() {
+            self.column_statistics
+                .into_iter()
+                .zip(other.column_statistics)
+                .map(|(a, b)| a.merge(b))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            num_rows: add_option(self.num_rows, other.num_rows),
+            total_byte_size: add_option(self.total_byte_size, other.total_byte_size),
+            column_statistics,
+        }
+    }
+}
+
+/// Estimated statistics of a column.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnStatistics {
+    /// Number of null values
+    pub null_count: Option<usize>,
+    /// Min value of the column
+    pub min_value: Option<Datum>,
+    /// Max value of the column
+    pub max_value: Option<Datum>,
+    /// Number of distinct values
+    pub distinct_count: Option<usize>,
+}
+
+impl ColumnStatistics {
+    fn merge(self, other: ColumnStatistics) -> Self {
+        let min_value = match (self.min_value, other.min_value) {
+            (Some(a), Some(b)) => Some(if b < a { b } else { a }),
+            _ => None,
+        };
+        let max_value = match (self.max_value, other.max_value) {
+            (Some(a), Some(b)) => Some(if b > a { b } else { a }),
+            _ => None,
+        };
+        // The distinct values may repeat in both sides, so only the larger
+        // count is kept as a lower bound.
+        let distinct_count = match (self.distinct_count, other.distinct_count) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            _ => None,
+        };
+
+        Self {
+            null_count: add_option(self.null_count, other.null_count),
+            min_value,
+            max_value,
+            distinct_count,
+        }
+    }
+}
+
+#[inline]
+fn add_option(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    Some(a? + b?)
 }
 
 /// A reference-counted pointer to Table
@@ -551,15 +736,25 @@ pub struct TableInfo {
     pub engine: String,
     /// Tells state of the table
     pub state: TableState,
+    /// Partition info if this is a partitioned table
+    pub partition_info: Option<PartitionInfo>,
 }
 
 #[derive(Debug, Snafu)]
-pub struct TryFromTableEntryError(common_types::schema::Error);
+pub struct TryFromTableEntryError(partition::Error);
 
 impl TryFrom<TableEntry> for TableInfo {
     type Error = TryFromTableEntryError;
 
-    fn try_from(entry: TableEntry) -> std::result::Result<Self, Self::Error> {
+    fn try_from(mut entry: TableEntry) -> std::result::Result<Self, Self::Error> {
+        let partition_info = if entry.has_partition_info() {
+            let info = PartitionInfo::try_from(entry.take_partition_info())
+                .map_err(TryFromTableEntryError)?;
+            Some(info)
+        } else {
+            None
+        };
+
         Ok(Self {
             catalog_name: entry.catalog_name,
             schema_name: entry.schema_name,
@@ -568,6 +763,7 @@ impl TryFrom<TableEntry> for TableInfo {
             table_name: entry.table_name,
             engine: entry.engine,
             state: TableState::from(entry.state),
+            partition_info,
         })
     }
 }
@@ -582,6 +778,9 @@ impl From<TableInfo> for TableEntry {
         entry.set_table_name(table_info.table_name);
         entry.set_engine(table_info.engine);
         entry.set_state(TableStatePb::from(table_info.state));
+        if let Some(partition_info) = table_info.partition_info {
+            entry.set_partition_info(partition_info.into());
+        }
 
         entry
     }
@@ -0,0 +1,111 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Compression of the log payloads
+
+use common_util::define_result;
+use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Failed to compress payload, codec:{:?}, err:{}", codec, source))]
+    Compress {
+        codec: Compression,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Failed to decompress payload, codec:{:?}, err:{}", codec, source))]
+    Decompress {
+        codec: Compression,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Unknown compression codec:{}.\nBacktrace:\n{}", codec, backtrace))]
+    UnknownCodec { codec: u8, backtrace: Backtrace },
+}
+
+define_result!(Error);
+
+/// Compression codec of the log payload.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    None = 0,
+    Lz4 = 1,
+    Zstd = 2,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression::None
+    }
+}
+
+impl Compression {
+    #[inline]
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_u8(codec: u8) -> Result<Self> {
+        let compression = match codec {
+            0 => Compression::None,
+            1 => Compression::Lz4,
+            2 => Compression::Zstd,
+            _ => return None.context(UnknownCodec { codec }),
+        };
+
+        Ok(compression)
+    }
+
+    /// Compress the `data`.
+    ///
+    /// Returns None if the codec is [Compression::None] or the compressed data
+    /// is not smaller than the `data`, and the `data` should be stored
+    /// uncompressed.
+    pub fn compress(self, data: &[u8]) -> Result<Option<Vec<u8>>> {
+        let compressed = match self {
+            Compression::None => return Ok(None),
+            Compression::Lz4 => lz4::block::compress(data, None, true),
+            Compression::Zstd => zstd::stream::encode_all(data, ZSTD_LEVEL),
+        }
+        .context(Compress { codec: self })?;
+
+        if compressed.len() >= data.len() {
+            return Ok(None);
+        }
+
+        Ok(Some(compressed))
+    }
+
+    /// Decompress the `data` compressed by this codec.
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Lz4 => lz4::block::decompress(data, None),
+            Compression::Zstd => zstd::stream::decode_all(data),
+        }
+        .context(Decompress { codec: self })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_decompress() {
+        let data = b"ceresdb".repeat(100);
+        for codec in [Compression::Lz4, Compression::Zstd] {
+            let compressed = codec.compress(&data).unwrap().unwrap();
+            assert!(compressed.len() < data.len());
+            assert_eq!(data, codec.decompress(&compressed).unwrap());
+            assert_eq!(codec, Compression::from_u8(codec.as_u8()).unwrap());
+        }
+
+        assert!(Compression::None.compress(&data).unwrap().is_none());
+        // Data not compressible is stored uncompressed.
+        assert!(Compression::Lz4.compress(b"a").unwrap().is_none());
+        assert!(Compression::from_u8(100).is_err());
+    }
+}
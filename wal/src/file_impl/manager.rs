@@ -0,0 +1,1008 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! WalManager implementation based on local segment files
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    fmt::Formatter,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use common_types::{SequenceNumber, MIN_SEQUENCE_NUMBER};
+use common_util::runtime::Runtime;
+use log::{debug, error, info, warn};
+use snafu::ResultExt;
+use tokio::{
+    sync::oneshot::{self, Sender},
+    time,
+};
+
+use crate::{
+    compression::Compression,
+    file_impl::segment::{self, Segment, SegmentReader},
+    log_batch::{LogEntry, LogWriteBatch, Payload, PayloadDecoder},
+    manager::{
+        error::*, LogIterator, LogReader, LogWriter, ReadContext, ReadRequest, RegionId,
+        WalManager, WriteContext,
+    },
+};
+
+/// File to persist the max deleted sequence number of the region.
+const META_FILE_NAME: &str = "META";
+const META_TMP_FILE_NAME: &str = "META.tmp";
+const META_ENCODING_V0: u8 = 0;
+const META_SIZE: usize = 9;
+
+/// Policy to sync the written log entries to the disk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncPolicy {
+    /// Sync on every write.
+    EveryWrite,
+    /// Sync the written entries periodically in background.
+    Interval(Duration),
+    /// Never sync and leave it to the os.
+    Never,
+}
+
+/// Options of [FileImpl].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// A new segment file is created once the size of the active segment file
+    /// exceeds `segment_size`.
+    pub segment_size: usize,
+    pub sync_policy: SyncPolicy,
+    /// Compression of the log payloads.
+    pub compression: Compression,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            segment_size: 64 * 1024 * 1024,
+            sync_policy: SyncPolicy::Interval(Duration::from_secs(1)),
+            compression: Compression::None,
+        }
+    }
+}
+
+/// Writer of the active segment.
+struct SegmentWriter {
+    file: File,
+    path: PathBuf,
+    /// Size of the valid records in the segment file
+    size: usize,
+}
+
+impl SegmentWriter {
+    fn sync(&self) -> segment::Result<()> {
+        self.file
+            .sync_data()
+            .context(segment::Io { path: &self.path })
+    }
+}
+
+/// Mutable state of the region.
+struct RegionState {
+    /// Segments sorted by the start sequence, and the last one is the active
+    /// segment to write.
+    segments: Vec<Segment>,
+    /// Writer of the active segment, created on the first write
+    writer: Option<SegmentWriter>,
+    /// Whether the active segment has entries not synced yet
+    unsynced: bool,
+    /// Buffer to encode the records
+    record_buf: Vec<u8>,
+}
+
+/// Region in the Wal.
+struct Region {
+    /// id of the Region
+    id: RegionId,
+    /// Directory of the segment files of the region
+    dir: PathBuf,
+    options: Options,
+    /// `next_sequence_num` is ensured to be positive
+    next_sequence_num: AtomicU64,
+    /// Entries whose sequence number is not greater than it are deleted
+    deleted_sequence_num: AtomicU64,
+    state: Mutex<RegionState>,
+    /// Runtime for write and delete requests
+    runtime: Arc<Runtime>,
+    /// Ensure the delete procedure to be sequential
+    delete_lock: tokio::sync::Mutex<()>,
+}
+
+impl Region {
+    fn new(id: RegionId, dir: PathBuf, options: Options, runtime: Arc<Runtime>) -> Self {
+        Self {
+            id,
+            dir,
+            options,
+            // ensure `next_sequence_number` to start from 1 (larger than MIN_SEQUENCE_NUMBER)
+            next_sequence_num: AtomicU64::new(MIN_SEQUENCE_NUMBER + 1),
+            deleted_sequence_num: AtomicU64::new(MIN_SEQUENCE_NUMBER),
+            state: Mutex::new(RegionState {
+                segments: Vec::new(),
+                writer: None,
+                unsynced: false,
+                record_buf: Vec::new(),
+            }),
+            runtime,
+            delete_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Open the region from its directory.
+    ///
+    /// The incomplete records at the tail of the last segment, which may be
+    /// caused by a crash during writing, are truncated.
+    fn open(
+        id: RegionId,
+        dir: PathBuf,
+        options: Options,
+        runtime: Arc<Runtime>,
+    ) -> segment::Result<Self> {
+        let deleted_sequence_num = read_meta(&dir)?;
+
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(&dir).context(segment::Io { path: &dir })? {
+            let entry = entry.context(segment::Io { path: &dir })?;
+            if let Some(segment) = Segment::from_path(entry.path()) {
+                segments.push(segment);
+            }
+        }
+        segments.sort_unstable_by_key(|segment| segment.start_sequence);
+
+        let mut max_sequence_num = deleted_sequence_num;
+        for (idx, segment) in segments.iter().enumerate().rev() {
+            let scanned = segment::scan_records(&segment.path)?;
+            let (last_sequence, valid_len, file_size) = match scanned {
+                Some(v) => v,
+                None => continue,
+            };
+            if idx + 1 == segments.len() && valid_len < file_size {
+                warn!(
+                    "Wal region truncates the incomplete tail of segment, region_id:{}, path:{}, size:{}, valid_size:{}",
+                    id,
+                    segment.path.display(),
+                    file_size,
+                    valid_len
+                );
+                let file =
+                    OpenOptions::new()
+                        .write(true)
+                        .open(&segment.path)
+                        .context(segment::Io {
+                            path: &segment.path,
+                        })?;
+                file.set_len(valid_len as u64)
+                    .and_then(|_| file.sync_all())
+                    .context(segment::Io {
+                        path: &segment.path,
+                    })?;
+            }
+
+            // The sequence numbers are increasing across the segments, so the
+            // last record of the last non-empty segment holds the max one.
+            if let Some(sequence) = last_sequence {
+                max_sequence_num = max_sequence_num.max(sequence);
+                break;
+            }
+        }
+
+        info!(
+            "Wal region opened, region_id:{}, segments:{}, max_sequence_num:{}, deleted_sequence_num:{}",
+            id,
+            segments.len(),
+            max_sequence_num,
+            deleted_sequence_num
+        );
+
+        let region = Self::new(id, dir, options, runtime);
+        region
+            .next_sequence_num
+            .store(max_sequence_num + 1, Ordering::Relaxed);
+        region
+            .deleted_sequence_num
+            .store(deleted_sequence_num, Ordering::Relaxed);
+        region.state.lock().unwrap().segments = segments;
+
+        Ok(region)
+    }
+
+    /// Returns the current sequence number which must be positive.
+    fn sequence_num(&self) -> Result<u64> {
+        let next_seq_num = self.next_sequence_num.load(Ordering::Relaxed);
+        debug_assert!(next_seq_num > 0);
+
+        Ok(next_seq_num - 1)
+    }
+
+    /// Delete entries in the range `[0, sequence_num]`.
+    ///
+    /// The deleted sequence number is persisted first and then the segments
+    /// whose entries are all deleted are removed. The delete procedure is
+    /// ensured to be sequential.
+    async fn delete_entries_up_to(
+        self: &Arc<Self>,
+        mut sequence_num: SequenceNumber,
+    ) -> Result<()> {
+        debug!(
+            "Wal Region delete entries begin deleting, sequence_num:{:?}",
+            sequence_num
+        );
+
+        let _delete_guard = self.delete_lock.lock().await;
+        let max_seq = self.sequence_num()?;
+        if sequence_num > max_seq {
+            warn!(
+                "Try to delete entries up to sequence number({}) greater than current max sequence \
+                number({})",
+                sequence_num,
+                max_seq
+            );
+            sequence_num = max_seq;
+        }
+
+        let region = self.clone();
+        self.runtime
+            .spawn_blocking(move || {
+                region
+                    .delete_segments_up_to(sequence_num)
+                    .map_err(|e| Box::new(e) as _)
+                    .context(Delete)
+            })
+            .await
+            .map_err(|e| Box::new(e) as _)
+            .context(Delete)?
+    }
+
+    fn delete_segments_up_to(&self, sequence_num: SequenceNumber) -> segment::Result<()> {
+        if sequence_num <= self.deleted_sequence_num.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        write_meta(&self.dir, sequence_num)?;
+        self.deleted_sequence_num
+            .store(sequence_num, Ordering::Relaxed);
+
+        let obsolete_segments: Vec<_> = {
+            let mut state = self.state.lock().unwrap();
+            // All entries of a segment are deleted if the next segment starts
+            // after the deleted sequence number. The active segment is always
+            // kept.
+            let num = state
+                .segments
+                .windows(2)
+                .take_while(|pair| pair[1].start_sequence <= sequence_num.saturating_add(1))
+                .count();
+            state.segments.drain(..num).collect()
+        };
+
+        for segment in obsolete_segments {
+            debug!(
+                "Wal region remove segment, region_id:{}, path:{}",
+                self.id,
+                segment.path.display()
+            );
+
+            match fs::remove_file(&segment.path) {
+                Ok(()) => (),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+                Err(e) => {
+                    return Err(e).context(segment::Io { path: segment.path });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read(&self, ctx: &ReadContext, req: &ReadRequest) -> Result<FileLogIterator> {
+        debug!("Wal region begin reading, ctx:{:?}, req:{:?}", ctx, req);
+
+        let start_sequence = if let Some(n) = req.start.as_start_sequence_number() {
+            n
+        } else {
+            return Ok(FileLogIterator::new_empty());
+        };
+
+        let end_sequence = if let Some(n) = req.end.as_end_sequence_number() {
+            n
+        } else {
+            return Ok(FileLogIterator::new_empty());
+        };
+
+        // Skip the deleted entries.
+        let deleted_sequence = self.deleted_sequence_num.load(Ordering::Relaxed);
+        let start_sequence = start_sequence.max(deleted_sequence.saturating_add(1));
+        if start_sequence > end_sequence {
+            return Ok(FileLogIterator::new_empty());
+        }
+
+        let state = self.state.lock().unwrap();
+        let segments = &state.segments;
+        let paths = segments
+            .iter()
+            .enumerate()
+            .filter(|(idx, segment)| {
+                let overlap_end = segment.start_sequence <= end_sequence;
+                let overlap_start = segments
+                    .get(idx + 1)
+                    .map(|next| next.start_sequence > start_sequence)
+                    .unwrap_or(true);
+                overlap_start && overlap_end
+            })
+            .map(|(_, segment)| segment.path.clone())
+            .collect();
+
+        Ok(FileLogIterator::with_data(
+            paths,
+            start_sequence,
+            end_sequence,
+        ))
+    }
+
+    async fn write<P: Payload>(
+        self: &Arc<Self>,
+        ctx: &WriteContext,
+        batch: &LogWriteBatch<P>,
+    ) -> Result<SequenceNumber> {
+        debug!(
+            "Wal region begin writing, ctx:{:?}, log_entries_num:{}",
+            ctx,
+            batch.entries.len()
+        );
+
+        // The sequence numbers are allocated while appending to the segment to
+        // keep the entries in the segment ordered, so only the payloads are
+        // encoded and compressed here.
+        let mut payload_buf = Vec::new();
+        let mut payload_ends = Vec::with_capacity(batch.len());
+        let mut raw_buf = Vec::new();
+        for entry in &batch.entries {
+            raw_buf.clear();
+            entry
+                .payload
+                .encode_to(&mut raw_buf)
+                .map_err(|e| Box::new(e) as _)
+                .context(Encoding)?;
+
+            let compressed = self
+                .options
+                .compression
+                .compress(&raw_buf)
+                .map_err(|e| Box::new(e) as _)
+                .context(Encoding)?;
+            let compression = match compressed {
+                Some(compressed) => {
+                    payload_buf.extend_from_slice(&compressed);
+                    self.options.compression
+                }
+                None => {
+                    payload_buf.extend_from_slice(&raw_buf);
+                    Compression::None
+                }
+            };
+            payload_ends.push((compression, payload_buf.len()));
+        }
+
+        let region = self.clone();
+        self.runtime
+            .spawn_blocking(move || {
+                region
+                    .append(&payload_buf, &payload_ends)
+                    .map_err(|e| Box::new(e) as _)
+                    .context(Write)
+            })
+            .await
+            .map_err(|e| Box::new(e) as _)
+            .context(Write)?
+    }
+
+    /// Append the encoded payloads to the active segment, `payload_ends` is the
+    /// compression and the end offset of every payload in the `payload_buf`.
+    ///
+    /// Returns the max sequence number of the appended entries.
+    fn append(
+        &self,
+        payload_buf: &[u8],
+        payload_ends: &[(Compression, usize)],
+    ) -> segment::Result<u64> {
+        let mut state = self.state.lock().unwrap();
+        let next_sequence_num = self.next_sequence_num.load(Ordering::Relaxed);
+        if payload_ends.is_empty() {
+            return Ok(next_sequence_num - 1);
+        }
+
+        self.maybe_switch_segment(&mut state, next_sequence_num)?;
+
+        let RegionState {
+            writer,
+            unsynced,
+            record_buf,
+            ..
+        } = &mut *state;
+        let writer = writer.as_mut().unwrap();
+
+        record_buf.clear();
+        let mut sequence = next_sequence_num;
+        let mut payload_start = 0;
+        for (compression, payload_end) in payload_ends {
+            segment::encode_record(
+                record_buf,
+                sequence,
+                *compression,
+                &payload_buf[payload_start..*payload_end],
+            );
+            payload_start = *payload_end;
+            sequence += 1;
+        }
+
+        if let Err(e) = writer.file.write_all(record_buf) {
+            // Drop the partially written records and switch to a new segment on next write.
+            let _ = writer.file.set_len(writer.size as u64);
+            let path = writer.path.clone();
+            state.writer = None;
+            return Err(e).context(segment::Io { path });
+        }
+        writer.size += record_buf.len();
+
+        match self.options.sync_policy {
+            SyncPolicy::EveryWrite => writer.sync()?,
+            // Synced by the background sync task.
+            SyncPolicy::Interval(_) => *unsynced = true,
+            SyncPolicy::Never => (),
+        }
+
+        self.next_sequence_num.store(sequence, Ordering::Relaxed);
+
+        Ok(sequence - 1)
+    }
+
+    /// Sync the active segment if it has entries not synced yet.
+    ///
+    /// The file is synced without holding the lock of the state, so the writes
+    /// are not blocked.
+    fn sync_unsynced(&self) -> segment::Result<()> {
+        let (file, path) = {
+            let mut state = self.state.lock().unwrap();
+            if !state.unsynced {
+                return Ok(());
+            }
+            state.unsynced = false;
+
+            match &state.writer {
+                Some(writer) => {
+                    let file = writer
+                        .file
+                        .try_clone()
+                        .context(segment::Io { path: &writer.path })?;
+                    (file, writer.path.clone())
+                }
+                None => return Ok(()),
+            }
+        };
+
+        if let Err(e) = file.sync_data() {
+            self.state.lock().unwrap().unsynced = true;
+            return Err(e).context(segment::Io { path });
+        }
+
+        Ok(())
+    }
+
+    /// Create a new active segment if there is no one or the current one is
+    /// full.
+    fn maybe_switch_segment(
+        &self,
+        state: &mut RegionState,
+        next_sequence_num: SequenceNumber,
+    ) -> segment::Result<()> {
+        if let Some(writer) = &state.writer {
+            if writer.size < self.options.segment_size {
+                return Ok(());
+            }
+            if self.options.sync_policy != SyncPolicy::Never {
+                writer.sync()?;
+            }
+        }
+
+        fs::create_dir_all(&self.dir).context(segment::Io { path: &self.dir })?;
+        let segment = Segment::new(&self.dir, next_sequence_num);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&segment.path)
+            .context(segment::Io {
+                path: &segment.path,
+            })?;
+        let size = file
+            .metadata()
+            .context(segment::Io {
+                path: &segment.path,
+            })?
+            .len() as usize;
+        if self.options.sync_policy != SyncPolicy::Never {
+            sync_dir(&self.dir)?;
+        }
+
+        info!(
+            "Wal region switch to new segment, region_id:{}, path:{}",
+            self.id,
+            segment.path.display()
+        );
+
+        state.writer = Some(SegmentWriter {
+            file,
+            path: segment.path.clone(),
+            size,
+        });
+        state.unsynced = false;
+        // The segment may be reopened if it has no entries.
+        let is_reopened = state
+            .segments
+            .last()
+            .map(|last| last.start_sequence == segment.start_sequence)
+            .unwrap_or(false);
+        if !is_reopened {
+            state.segments.push(segment);
+        }
+
+        Ok(())
+    }
+}
+
+fn sync_dir(dir: &Path) -> segment::Result<()> {
+    File::open(dir)
+        .and_then(|f| f.sync_all())
+        .context(segment::Io { path: dir })
+}
+
+/// Read the deleted sequence number from the meta file of the region.
+fn read_meta(dir: &Path) -> segment::Result<SequenceNumber> {
+    let path = dir.join(META_FILE_NAME);
+    let data = match segment::read_file(&path)? {
+        Some(v) => v,
+        None => return Ok(MIN_SEQUENCE_NUMBER),
+    };
+
+    if data.len() != META_SIZE || data[0] != META_ENCODING_V0 {
+        warn!(
+            "Wal region ignores invalid meta file, path:{}, size:{}",
+            path.display(),
+            data.len()
+        );
+        return Ok(MIN_SEQUENCE_NUMBER);
+    }
+
+    let mut sequence = [0; 8];
+    sequence.copy_from_slice(&data[1..]);
+    Ok(SequenceNumber::from_be_bytes(sequence))
+}
+
+/// Persist the deleted sequence number to the meta file of the region.
+fn write_meta(dir: &Path, sequence_num: SequenceNumber) -> segment::Result<()> {
+    let mut data = Vec::with_capacity(META_SIZE);
+    data.push(META_ENCODING_V0);
+    data.extend_from_slice(&sequence_num.to_be_bytes());
+
+    fs::create_dir_all(dir).context(segment::Io { path: dir })?;
+    let tmp_path = dir.join(META_TMP_FILE_NAME);
+    let mut file = File::create(&tmp_path).context(segment::Io { path: &tmp_path })?;
+    file.write_all(&data)
+        .and_then(|_| file.sync_all())
+        .context(segment::Io { path: &tmp_path })?;
+
+    let path = dir.join(META_FILE_NAME);
+    fs::rename(&tmp_path, &path).context(segment::Io { path: &path })?;
+    sync_dir(dir)
+}
+
+type RegionMapRef = Arc<RwLock<HashMap<RegionId, Arc<Region>>>>;
+
+/// Spawn the task to sync the regions every `interval` on the `runtime`.
+///
+/// Returns the sender to stop the task.
+fn start_sync_task(runtime: Arc<Runtime>, regions: RegionMapRef, interval: Duration) -> Sender<()> {
+    let (stop_tx, mut stop_rx) = oneshot::channel();
+    let sync_runtime = runtime.clone();
+    runtime.spawn(async move {
+        // Stop once the sender is notified or dropped.
+        while time::timeout(interval, &mut stop_rx).await.is_err() {
+            let regions: Vec<_> = regions.read().unwrap().values().cloned().collect();
+            let synced = sync_runtime
+                .spawn_blocking(move || {
+                    for region in regions {
+                        if let Err(e) = region.sync_unsynced() {
+                            error!(
+                                "Wal region failed to sync, region_id:{}, err:{}",
+                                region.id, e
+                            );
+                        }
+                    }
+                })
+                .await;
+            if let Err(e) = synced {
+                error!("Wal failed to run sync task, err:{}", e);
+            }
+        }
+
+        info!("Wal sync loop exit");
+    });
+
+    stop_tx
+}
+
+/// [WalManager] implementation based on local segment files.
+///
+/// Every region has its own directory under the `wal_path`, and the log
+/// entries of the region are appended to the segment files in the directory.
+pub struct FileImpl {
+    /// Wal data path
+    wal_path: String,
+    /// Runtime for read/write log entries
+    runtime: Arc<Runtime>,
+    options: Options,
+    /// Regions
+    regions: RegionMapRef,
+    /// Sender to stop the background sync task, only used by
+    /// [SyncPolicy::Interval]
+    sync_stop_tx: Option<Sender<()>>,
+}
+
+impl Drop for FileImpl {
+    fn drop(&mut self) {
+        if let Some(stop_tx) = self.sync_stop_tx.take() {
+            let _ = stop_tx.send(());
+        }
+
+        // Clear all regions.
+        {
+            let mut regions = self.regions.write().unwrap();
+            regions.clear();
+        }
+
+        info!("FileImpl dropped, wal_path:{}", self.wal_path);
+    }
+}
+
+impl FileImpl {
+    /// Open all the regions under the `wal_path`.
+    fn build_regions(&self) -> Result<()> {
+        let wal_path = Path::new(&self.wal_path);
+        let entries = fs::read_dir(wal_path)
+            .map_err(|e| Box::new(e) as _)
+            .context(Initialization)?;
+
+        let mut regions = self.regions.write().unwrap();
+        for entry in entries {
+            let entry = entry
+                .map_err(|e| Box::new(e) as _)
+                .context(Initialization)?;
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let region_id = match path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.parse::<RegionId>().ok())
+            {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let region = Region::open(region_id, path, self.options.clone(), self.runtime.clone())
+                .map_err(|e| Box::new(e) as _)
+                .context(Initialization)?;
+            regions.insert(region_id, Arc::new(region));
+        }
+
+        info!(
+            "FileImpl build regions, wal_path:{}, regions:{}",
+            self.wal_path,
+            regions.len()
+        );
+
+        Ok(())
+    }
+
+    /// Get the region and create it if not found.
+    fn get_or_create_region(&self, region_id: RegionId) -> Arc<Region> {
+        {
+            let regions = self.regions.read().unwrap();
+            if let Some(region) = regions.get(&region_id) {
+                return region.clone();
+            }
+        }
+
+        let mut regions = self.regions.write().unwrap();
+        if let Some(region) = regions.get(&region_id) {
+            return region.clone();
+        }
+
+        info!(
+            "FileImpl create new region, wal_path:{}, region_id:{}",
+            self.wal_path, region_id
+        );
+
+        // The directory of the region is created on the first write.
+        let dir = Path::new(&self.wal_path).join(region_id.to_string());
+        let region = Arc::new(Region::new(
+            region_id,
+            dir,
+            self.options.clone(),
+            self.runtime.clone(),
+        ));
+
+        regions.insert(region_id, region.clone());
+        region
+    }
+
+    /// Get the region
+    fn region(&self, region_id: RegionId) -> Option<Arc<Region>> {
+        let regions = self.regions.read().unwrap();
+        regions.get(&region_id).cloned()
+    }
+}
+
+/// Builder for `FileImpl`.
+pub struct Builder {
+    wal_path: String,
+    runtime: Arc<Runtime>,
+    options: Options,
+}
+
+impl Builder {
+    pub fn new(wal_path: impl Into<PathBuf>, runtime: Arc<Runtime>) -> Self {
+        let wal_path: PathBuf = wal_path.into();
+        Self {
+            wal_path: wal_path.to_str().unwrap().to_owned(),
+            runtime,
+            options: Options::default(),
+        }
+    }
+
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn build(self) -> Result<FileImpl> {
+        fs::create_dir_all(&self.wal_path)
+            .map_err(|e| Box::new(e) as _)
+            .context(Open {
+                wal_path: self.wal_path.clone(),
+            })?;
+
+        let mut file_impl = FileImpl {
+            wal_path: self.wal_path,
+            runtime: self.runtime,
+            options: self.options,
+            regions: Arc::new(RwLock::new(HashMap::new())),
+            sync_stop_tx: None,
+        };
+        file_impl.build_regions()?;
+
+        if let SyncPolicy::Interval(interval) = file_impl.options.sync_policy {
+            file_impl.sync_stop_tx = Some(start_sync_task(
+                file_impl.runtime.clone(),
+                file_impl.regions.clone(),
+                interval,
+            ));
+        }
+
+        Ok(file_impl)
+    }
+}
+
+/// Iterator over log entries in the segment files.
+pub struct FileLogIterator {
+    /// Segments to iterate, a segment is skipped if it has been deleted
+    segments: VecDeque<PathBuf>,
+    start_sequence: SequenceNumber,
+    end_sequence: SequenceNumber,
+    /// Reader of the segment being iterated
+    current: Option<SegmentReader>,
+    /// denotes no more data to iterate and it is set to true when:
+    ///  - initialized as no data iterator, or
+    ///  - iterate to the end.
+    no_more_data: bool,
+}
+
+impl FileLogIterator {
+    /// Create iterator maybe containing data.
+    fn with_data(
+        segments: VecDeque<PathBuf>,
+        start_sequence: SequenceNumber,
+        end_sequence: SequenceNumber,
+    ) -> Self {
+        Self {
+            segments,
+            start_sequence,
+            end_sequence,
+            current: None,
+            no_more_data: false,
+        }
+    }
+
+    /// Create empty iterator.
+    fn new_empty() -> Self {
+        Self {
+            segments: VecDeque::new(),
+            start_sequence: 0,
+            end_sequence: 0,
+            current: None,
+            no_more_data: true,
+        }
+    }
+
+    /// Load the next segment to iterate, returns false if all segments are
+    /// exhausted.
+    fn load_next_segment(&mut self) -> Result<bool> {
+        while let Some(path) = self.segments.pop_front() {
+            let reader = SegmentReader::open(&path)
+                .map_err(|e| Box::new(e) as _)
+                .context(Read)?;
+            if let Some(reader) = reader {
+                self.current = Some(reader);
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+impl LogIterator for FileLogIterator {
+    fn next_log_entry<D: PayloadDecoder>(
+        &mut self,
+        decoder: &D,
+    ) -> Result<Option<LogEntry<D::Target>>> {
+        loop {
+            if self.no_more_data {
+                return Ok(None);
+            }
+
+            if self.current.is_none() && !self.load_next_segment()? {
+                self.no_more_data = true;
+                return Ok(None);
+            }
+
+            let reader = self.current.as_mut().unwrap();
+            let decoded = reader
+                .next_record()
+                .map_err(|e| Box::new(e) as _)
+                .context(Read)?;
+            let record = match decoded {
+                Some(v) => v,
+                None => {
+                    // The segment is exhausted.
+                    self.current = None;
+                    continue;
+                }
+            };
+
+            let sequence = record.sequence;
+            if sequence > self.end_sequence {
+                self.no_more_data = true;
+                return Ok(None);
+            }
+            if sequence < self.start_sequence {
+                continue;
+            }
+
+            let payload = if record.compression == Compression::None {
+                let mut payload_buf = record.payload;
+                decoder.decode(&mut payload_buf)
+            } else {
+                let raw = record
+                    .compression
+                    .decompress(record.payload)
+                    .map_err(|e| Box::new(e) as _)
+                    .context(Decoding)?;
+                decoder.decode(&mut raw.as_slice())
+            }
+            .map_err(|e| Box::new(e) as _)
+            .context(Decoding)?;
+
+            return Ok(Some(LogEntry { sequence, payload }));
+        }
+    }
+}
+
+impl LogReader for FileImpl {
+    type Iterator = FileLogIterator;
+
+    fn read(&self, ctx: &ReadContext, req: &ReadRequest) -> Result<Self::Iterator> {
+        if let Some(region) = self.region(req.region_id) {
+            region.read(ctx, req)
+        } else {
+            Ok(FileLogIterator::new_empty())
+        }
+    }
+}
+
+#[async_trait]
+impl LogWriter for FileImpl {
+    async fn write<P: Payload>(
+        &self,
+        ctx: &WriteContext,
+        batch: &LogWriteBatch<P>,
+    ) -> Result<SequenceNumber> {
+        let region = self.get_or_create_region(batch.region_id);
+        region.write(ctx, batch).await
+    }
+}
+
+#[async_trait]
+impl WalManager for FileImpl {
+    fn sequence_num(&self, region_id: RegionId) -> Result<u64> {
+        if let Some(region) = self.region(region_id) {
+            return region.sequence_num();
+        }
+
+        Ok(MIN_SEQUENCE_NUMBER)
+    }
+
+    async fn mark_delete_entries_up_to(
+        &self,
+        region_id: RegionId,
+        sequence_num: SequenceNumber,
+    ) -> Result<()> {
+        if let Some(region) = self.region(region_id) {
+            return region.delete_entries_up_to(sequence_num).await;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for FileImpl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileImpl")
+            .field("wal_path", &self.wal_path)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::util::FileTestEnv;
+
+    #[test]
+    fn test_sync_unsynced() {
+        let env = FileTestEnv::new(2);
+        let options = Options {
+            sync_policy: SyncPolicy::Interval(Duration::from_secs(3600)),
+            ..Default::default()
+        };
+        let wal = Builder::new(env.dir.path(), env.runtime.clone())
+            .options(options)
+            .build()
+            .unwrap();
+
+        let region_id = 1;
+        let batch = env.build_log_batch(region_id, 0, 10);
+        env.runtime.block_on(async {
+            wal.write(&env.write_ctx, &batch).await.unwrap();
+        });
+
+        let region = wal.region(region_id).unwrap();
+        assert!(region.state.lock().unwrap().unsynced);
+        region.sync_unsynced().unwrap();
+        assert!(!region.state.lock().unwrap().unsynced);
+    }
+}
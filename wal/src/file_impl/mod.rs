@@ -0,0 +1,6 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! WalManager implementation based on local segment files
+
+pub mod manager;
+mod segment;
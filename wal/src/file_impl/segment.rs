@@ -0,0 +1,453 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Segment file of the wal.
+//!
+//! The log entries of a region are appended to the segment files in order of
+//! the sequence number, and a segment file is named by the first sequence
+//! number it may contain. Every log entry is encoded into a record:
+//!
+//! ```text
+//! +-------------+------------------+---------------------------------------------------+
+//! | crc32 (u32) | body length(u32) | body                                              |
+//! +-------------+------------------+---------------------------------------------------+
+//!                                  | version(u8) | sequence(u64) | codec(u8) | payload |
+//!                                  +-------------+---------------+-----------+---------+
+//! ```
+//!
+//! The payload is compressed by the codec, and the records of version 0 have
+//! no codec.
+
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind, Read},
+    path::{Path, PathBuf},
+};
+
+use common_types::SequenceNumber;
+use common_util::define_result;
+use log::warn;
+use snafu::{ensure, Backtrace, ResultExt, Snafu};
+
+use crate::compression::{self, Compression};
+
+const SEGMENT_FILE_EXTENSION: &str = "log";
+/// Size of the crc and the body length.
+const RECORD_HEADER_SIZE: usize = 8;
+/// Size of the version and the sequence.
+const RECORD_BODY_HEADER_SIZE: usize = 9;
+/// Record without compression codec.
+const RECORD_ENCODING_V0: u8 = 0;
+/// Record with compression codec.
+const RECORD_ENCODING_V1: u8 = 1;
+const NEWEST_RECORD_ENCODING_VERSION: u8 = RECORD_ENCODING_V1;
+/// Size of the buffer to read the segment file.
+const READ_BUFFER_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[snafu(display("Failed to access wal file, path:{}, err:{}", path.display(), source))]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display(
+        "Wal record is corrupted, offset:{}, expect crc:{}, actual crc:{}.\nBacktrace:\n{}",
+        offset,
+        expect,
+        actual,
+        backtrace
+    ))]
+    ChecksumMismatch {
+        offset: usize,
+        expect: u32,
+        actual: u32,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Invalid wal record, offset:{}, body length:{}.\nBacktrace:\n{}",
+        offset,
+        len,
+        backtrace
+    ))]
+    InvalidRecord {
+        offset: usize,
+        len: usize,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Failed to decode codec of wal record, err:{}", source))]
+    DecodeCodec { source: compression::Error },
+
+    #[snafu(display(
+        "Invalid version of wal record, expect:{}, given:{}.\nBacktrace:\n{}",
+        expect,
+        given,
+        backtrace
+    ))]
+    InvalidVersion {
+        expect: u8,
+        given: u8,
+        backtrace: Backtrace,
+    },
+}
+
+define_result!(Error);
+
+/// Segment file of a region.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    /// The first sequence number the segment may contain.
+    pub start_sequence: SequenceNumber,
+    pub path: PathBuf,
+}
+
+impl Segment {
+    pub fn new(dir: &Path, start_sequence: SequenceNumber) -> Self {
+        Self {
+            start_sequence,
+            path: dir.join(format!("{:020}.{}", start_sequence, SEGMENT_FILE_EXTENSION)),
+        }
+    }
+
+    /// Returns the segment if the `path` is a segment file.
+    pub fn from_path(path: PathBuf) -> Option<Self> {
+        if path.extension()? != SEGMENT_FILE_EXTENSION {
+            return None;
+        }
+        let start_sequence = path.file_stem()?.to_str()?.parse().ok()?;
+
+        Some(Self {
+            start_sequence,
+            path,
+        })
+    }
+}
+
+/// Append the record of the encoded `payload` compressed by `compression` to
+/// the `buf`.
+pub fn encode_record(
+    buf: &mut Vec<u8>,
+    sequence: SequenceNumber,
+    compression: Compression,
+    payload: &[u8],
+) {
+    let body_len = (RECORD_BODY_HEADER_SIZE + 1 + payload.len()) as u32;
+    let header_offset = buf.len();
+    // Fill the crc after the body is encoded.
+    buf.extend_from_slice(&[0; 4]);
+    buf.extend_from_slice(&body_len.to_be_bytes());
+
+    let body_offset = buf.len();
+    buf.push(NEWEST_RECORD_ENCODING_VERSION);
+    buf.extend_from_slice(&sequence.to_be_bytes());
+    buf.push(compression.as_u8());
+    buf.extend_from_slice(payload);
+
+    let crc = crc32fast::hash(&buf[body_offset..]);
+    buf[header_offset..header_offset + 4].copy_from_slice(&crc.to_be_bytes());
+}
+
+/// A record decoded from the segment file, whose payload is not decoded yet.
+#[derive(Debug)]
+pub struct Record<'a> {
+    pub sequence: SequenceNumber,
+    /// Codec to decompress the payload
+    pub compression: Compression,
+    pub payload: &'a [u8],
+}
+
+/// Decode the record starting from `offset` of the `buf`.
+///
+/// Returns the record and the offset of the next record, or None if the `buf`
+/// doesn't contain a complete record (e.g. the end of the segment or an
+/// incomplete write).
+pub fn decode_record(buf: &[u8], offset: usize) -> Result<Option<(Record, usize)>> {
+    let buf_len = buf.len();
+    if buf_len < offset + RECORD_HEADER_SIZE {
+        return Ok(None);
+    }
+
+    let (expect_crc, body_len) = decode_header(&buf[offset..offset + RECORD_HEADER_SIZE]);
+    let body_offset = offset + RECORD_HEADER_SIZE;
+    if buf_len < body_offset + body_len {
+        return Ok(None);
+    }
+
+    let body = &buf[body_offset..body_offset + body_len];
+    let record = decode_body(body, expect_crc, offset)?;
+
+    Ok(Some((record, body_offset + body_len)))
+}
+
+/// Returns the crc and the body length in the record `header`.
+fn decode_header(header: &[u8]) -> (u32, usize) {
+    let crc = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    let body_len = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+
+    (crc, body_len)
+}
+
+/// Decode the `body` of the record starting from `offset`.
+fn decode_body(body: &[u8], expect_crc: u32, offset: usize) -> Result<Record> {
+    let body_len = body.len();
+    let actual_crc = crc32fast::hash(body);
+    ensure!(
+        expect_crc == actual_crc,
+        ChecksumMismatch {
+            offset,
+            expect: expect_crc,
+            actual: actual_crc,
+        }
+    );
+    ensure!(
+        body_len >= RECORD_BODY_HEADER_SIZE,
+        InvalidRecord {
+            offset,
+            len: body_len,
+        }
+    );
+    let version = body[0];
+    ensure!(
+        version <= NEWEST_RECORD_ENCODING_VERSION,
+        InvalidVersion {
+            expect: NEWEST_RECORD_ENCODING_VERSION,
+            given: version,
+        }
+    );
+
+    let mut sequence = [0; 8];
+    sequence.copy_from_slice(&body[1..RECORD_BODY_HEADER_SIZE]);
+    let sequence = SequenceNumber::from_be_bytes(sequence);
+    let record = if version == RECORD_ENCODING_V0 {
+        Record {
+            sequence,
+            compression: Compression::None,
+            payload: &body[RECORD_BODY_HEADER_SIZE..],
+        }
+    } else {
+        ensure!(
+            body_len > RECORD_BODY_HEADER_SIZE,
+            InvalidRecord {
+                offset,
+                len: body_len,
+            }
+        );
+        Record {
+            sequence,
+            compression: Compression::from_u8(body[RECORD_BODY_HEADER_SIZE])
+                .context(DecodeCodec)?,
+            payload: &body[RECORD_BODY_HEADER_SIZE + 1..],
+        }
+    };
+
+    Ok(record)
+}
+
+/// Reader to read the records of a segment file one by one, so only a record
+/// is held in memory instead of the whole file.
+pub struct SegmentReader {
+    path: PathBuf,
+    reader: BufReader<File>,
+    /// Size of the file when it is opened
+    file_size: usize,
+    /// Offset of the next record
+    offset: usize,
+    /// Buffer of the current record
+    buf: Vec<u8>,
+}
+
+impl SegmentReader {
+    /// Open the segment file, returns None if it has been deleted.
+    pub fn open(path: &Path) -> Result<Option<Self>> {
+        let file = match File::open(path) {
+            Ok(v) => v,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).context(Io { path }),
+        };
+        let file_size = file.metadata().context(Io { path })?.len() as usize;
+
+        Ok(Some(Self {
+            path: path.to_path_buf(),
+            reader: BufReader::with_capacity(READ_BUFFER_SIZE, file),
+            file_size,
+            offset: 0,
+            buf: Vec::new(),
+        }))
+    }
+
+    /// Size of the file when it is opened.
+    pub fn file_size(&self) -> usize {
+        self.file_size
+    }
+
+    /// Offset of the next record, which is also the length of the valid records
+    /// read.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Read the next record, returns None if there is no complete record (e.g.
+    /// the end of the segment or an incomplete write).
+    pub fn next_record(&mut self) -> Result<Option<Record>> {
+        self.buf.resize(RECORD_HEADER_SIZE, 0);
+        if !self.read_buf(0)? {
+            return Ok(None);
+        }
+
+        let (expect_crc, body_len) = decode_header(&self.buf);
+        // The body length of a corrupted header may be too large to allocate.
+        if self.offset + RECORD_HEADER_SIZE + body_len > self.file_size {
+            return Ok(None);
+        }
+        self.buf.resize(RECORD_HEADER_SIZE + body_len, 0);
+        if !self.read_buf(RECORD_HEADER_SIZE)? {
+            return Ok(None);
+        }
+
+        let record = decode_body(&self.buf[RECORD_HEADER_SIZE..], expect_crc, self.offset)?;
+        self.offset += RECORD_HEADER_SIZE + body_len;
+
+        Ok(Some(record))
+    }
+
+    /// Fill the `buf` from `start`, returns false if the file ends before the
+    /// `buf` is filled.
+    fn read_buf(&mut self, start: usize) -> Result<bool> {
+        match self.reader.read_exact(&mut self.buf[start..]) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+            Err(e) => Err(e).context(Io { path: &self.path }),
+        }
+    }
+}
+
+/// Read the whole file, returns None if it has been deleted.
+pub fn read_file(path: &Path) -> Result<Option<Vec<u8>>> {
+    match std::fs::read(path) {
+        Ok(v) => Ok(Some(v)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).context(Io { path }),
+    }
+}
+
+/// Scan the records in the segment file, returns None if it has been deleted.
+///
+/// Returns the sequence of the last valid record, the length of the valid
+/// records and the size of the file. The records after the first incomplete or
+/// corrupted record are ignored.
+pub fn scan_records(path: &Path) -> Result<Option<(Option<SequenceNumber>, usize, usize)>> {
+    let mut reader = match SegmentReader::open(path)? {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+
+    let mut last_sequence = None;
+    loop {
+        let offset = reader.offset();
+        match reader.next_record() {
+            Ok(Some(record)) => last_sequence = Some(record.sequence),
+            Ok(None) => break,
+            Err(e @ Error::Io { .. }) => return Err(e),
+            Err(e) => {
+                warn!(
+                    "Found corrupted wal record, path:{}, offset:{}, err:{}",
+                    path.display(),
+                    offset,
+                    e
+                );
+                break;
+            }
+        }
+    }
+
+    // The offset is not advanced by the incomplete or corrupted record.
+    Ok(Some((last_sequence, reader.offset(), reader.file_size())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_record() {
+        let mut buf = Vec::new();
+        encode_record(&mut buf, 1, Compression::None, b"hello");
+        encode_record(&mut buf, 2, Compression::Zstd, b"");
+
+        let (record, offset) = decode_record(&buf, 0).unwrap().unwrap();
+        assert_eq!(1, record.sequence);
+        assert_eq!(Compression::None, record.compression);
+        assert_eq!(b"hello", record.payload);
+        let (record, offset) = decode_record(&buf, offset).unwrap().unwrap();
+        assert_eq!(2, record.sequence);
+        assert_eq!(Compression::Zstd, record.compression);
+        assert!(record.payload.is_empty());
+        assert_eq!(buf.len(), offset);
+        assert!(decode_record(&buf, offset).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_scan_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.log");
+        let scan = |data: &[u8]| {
+            std::fs::write(&path, data).unwrap();
+            scan_records(&path).unwrap().unwrap()
+        };
+
+        let mut buf = Vec::new();
+        encode_record(&mut buf, 1, Compression::None, b"hello");
+        encode_record(&mut buf, 2, Compression::None, b"world");
+        let valid_len = buf.len();
+        assert_eq!((Some(2), valid_len, valid_len), scan(&buf));
+
+        // Incomplete record.
+        let mut incomplete = buf.clone();
+        encode_record(&mut incomplete, 3, Compression::None, b"incomplete");
+        incomplete.truncate(incomplete.len() - 1);
+        assert_eq!((Some(2), valid_len, incomplete.len()), scan(&incomplete));
+
+        // Corrupted record.
+        let mut corrupted = buf.clone();
+        encode_record(&mut corrupted, 3, Compression::None, b"corrupted");
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0xff;
+        assert!(decode_record(&corrupted, valid_len).is_err());
+        assert_eq!((Some(2), valid_len, corrupted.len()), scan(&corrupted));
+
+        // Corrupted body length.
+        let mut corrupted = buf.clone();
+        corrupted.extend_from_slice(&[0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+        assert_eq!((Some(2), valid_len, corrupted.len()), scan(&corrupted));
+
+        assert_eq!((None, 0, 0), scan(&[]));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(scan_records(&path).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_segment_reader() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.log");
+        let mut buf = Vec::new();
+        encode_record(&mut buf, 1, Compression::None, b"hello");
+        encode_record(&mut buf, 2, Compression::Zstd, b"");
+        std::fs::write(&path, &buf).unwrap();
+
+        let mut reader = SegmentReader::open(&path).unwrap().unwrap();
+        let record = reader.next_record().unwrap().unwrap();
+        assert_eq!(1, record.sequence);
+        assert_eq!(Compression::None, record.compression);
+        assert_eq!(b"hello", record.payload);
+        let record = reader.next_record().unwrap().unwrap();
+        assert_eq!(2, record.sequence);
+        assert_eq!(Compression::Zstd, record.compression);
+        assert!(record.payload.is_empty());
+        assert!(reader.next_record().unwrap().is_none());
+        assert_eq!(buf.len(), reader.offset());
+    }
+}
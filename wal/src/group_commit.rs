@@ -0,0 +1,256 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Group commit for the wal.
+//!
+//! [GroupCommitWal] queues the log batches written concurrently and a
+//! background committer merges the queued batches, which may belong to
+//! different regions, into one [LogWriter::write_batches] call of the
+//! underlying wal.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use common_types::{
+    bytes::{self, MemBufMut},
+    SequenceNumber,
+};
+use common_util::runtime::Runtime;
+use log::{error, info};
+use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    log_batch::{LogWriteBatch, LogWriteEntry, Payload},
+    manager::{
+        self, Encoding, LogReader, LogWriter, ReadContext, ReadRequest, RegionId, WalManager,
+        Write, WriteContext,
+    },
+    metrics,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Wal group committer is stopped.\nBacktrace:\n{}", backtrace))]
+    CommitterStopped { backtrace: Backtrace },
+
+    #[snafu(display("Failed to commit the group of log batches, err:{}", source))]
+    Commit { source: Arc<manager::Error> },
+}
+
+/// Options of [GroupCommitWal].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Max number of log batches merged into one commit.
+    pub max_batch_num: usize,
+    /// Capacity of the queue of the log batches to commit.
+    pub channel_cap: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            max_batch_num: 256,
+            channel_cap: 1024,
+        }
+    }
+}
+
+/// Payload already encoded by the writer, so the batches of different payload
+/// types can be merged.
+#[derive(Debug)]
+struct EncodedPayload(Vec<u8>);
+
+impl Payload for EncodedPayload {
+    type Error = bytes::Error;
+
+    fn encode_size(&self) -> usize {
+        self.0.len()
+    }
+
+    fn encode_to<B: MemBufMut>(&self, buf: &mut B) -> Result<(), Self::Error> {
+        buf.write_slice(&self.0)
+    }
+}
+
+type CommitResult = std::result::Result<SequenceNumber, Arc<manager::Error>>;
+
+struct CommitRequest {
+    ctx: WriteContext,
+    batch: LogWriteBatch<EncodedPayload>,
+    /// Sender to notify the max sequence number of the batch
+    tx: oneshot::Sender<CommitResult>,
+    enqueue_time: Instant,
+}
+
+/// Wal which commits the concurrent writes in groups.
+///
+/// Every writer still gets the sequence numbers of its own batch, other
+/// operations are delegated to the underlying wal directly.
+pub struct GroupCommitWal<W> {
+    wal: Arc<W>,
+    sender: mpsc::Sender<CommitRequest>,
+}
+
+impl<W: WalManager + Send + Sync + 'static> GroupCommitWal<W> {
+    /// Create a [GroupCommitWal] and spawn the committer on the `runtime`.
+    ///
+    /// The committer exits after the [GroupCommitWal] is dropped.
+    pub fn new(wal: W, runtime: &Runtime, options: Options) -> Self {
+        let wal = Arc::new(wal);
+        let (sender, receiver) = mpsc::channel(options.channel_cap);
+        runtime.spawn(run_committer(wal.clone(), receiver, options.max_batch_num));
+
+        Self { wal, sender }
+    }
+}
+
+async fn run_committer<W: LogWriter + Send + Sync>(
+    wal: Arc<W>,
+    mut receiver: mpsc::Receiver<CommitRequest>,
+    max_batch_num: usize,
+) {
+    info!(
+        "Wal group committer started, max_batch_num:{}",
+        max_batch_num
+    );
+
+    // The batches queued while the previous group is being committed are merged
+    // into the next group, so no extra delay is introduced.
+    while let Some(request) = receiver.recv().await {
+        let mut requests = vec![request];
+        while requests.len() < max_batch_num {
+            match receiver.try_recv() {
+                Ok(request) => requests.push(request),
+                Err(_) => break,
+            }
+        }
+
+        commit(&*wal, requests).await;
+    }
+
+    info!("Wal group committer stopped");
+}
+
+async fn commit<W: LogWriter + Send + Sync>(wal: &W, requests: Vec<CommitRequest>) {
+    let commit_time = Instant::now();
+    // All the writers share the context of the first one.
+    let ctx = requests[0].ctx.clone();
+    let mut batches = Vec::with_capacity(requests.len());
+    let mut senders = Vec::with_capacity(requests.len());
+    let mut entry_num = 0;
+    for request in requests {
+        let wait = commit_time
+            .checked_duration_since(request.enqueue_time)
+            .unwrap_or_else(|| Duration::from_secs(0));
+        metrics::GROUP_COMMIT_WAIT_DURATION_HISTOGRAM.observe(wait.as_secs_f64());
+
+        entry_num += request.batch.len();
+        batches.push(request.batch);
+        senders.push(request.tx);
+    }
+    metrics::GROUP_COMMIT_BATCH_NUM_HISTOGRAM.observe(batches.len() as f64);
+    metrics::GROUP_COMMIT_ENTRY_NUM_HISTOGRAM.observe(entry_num as f64);
+
+    match wal.write_batches(&ctx, &batches).await {
+        Ok(sequences) => {
+            for (tx, sequence) in senders.into_iter().zip(sequences) {
+                // The writer may be cancelled, ignore the send error.
+                let _ = tx.send(Ok(sequence));
+            }
+        }
+        Err(e) => {
+            error!(
+                "Wal group committer failed to commit, batch_num:{}, err:{}",
+                batches.len(),
+                e
+            );
+
+            let e = Arc::new(e);
+            for tx in senders {
+                let _ = tx.send(Err(e.clone()));
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<W: LogWriter + Send + Sync> LogWriter for GroupCommitWal<W> {
+    async fn write<P: Payload>(
+        &self,
+        ctx: &WriteContext,
+        batch: &LogWriteBatch<P>,
+    ) -> manager::Result<SequenceNumber> {
+        let mut encoded_batch = LogWriteBatch::with_capacity(batch.region_id, batch.len());
+        for entry in &batch.entries {
+            let mut buf = Vec::with_capacity(entry.payload.encode_size());
+            entry
+                .payload
+                .encode_to(&mut buf)
+                .map_err(|e| Box::new(e) as _)
+                .context(Encoding)?;
+            encoded_batch.push(LogWriteEntry {
+                payload: EncodedPayload(buf),
+            });
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let request = CommitRequest {
+            ctx: ctx.clone(),
+            batch: encoded_batch,
+            tx,
+            enqueue_time: Instant::now(),
+        };
+        self.sender
+            .send(request)
+            .await
+            .ok()
+            .context(CommitterStopped)
+            .map_err(|e| Box::new(e) as _)
+            .context(Write)?;
+
+        rx.await
+            .ok()
+            .context(CommitterStopped)
+            .map_err(|e| Box::new(e) as _)
+            .context(Write)?
+            .context(Commit)
+            .map_err(|e| Box::new(e) as _)
+            .context(Write)
+    }
+}
+
+impl<W: LogReader> LogReader for GroupCommitWal<W> {
+    type Iterator = W::Iterator;
+
+    fn read(&self, ctx: &ReadContext, req: &ReadRequest) -> manager::Result<Self::Iterator> {
+        self.wal.read(ctx, req)
+    }
+}
+
+#[async_trait]
+impl<W: WalManager + Send + Sync> WalManager for GroupCommitWal<W> {
+    fn sequence_num(&self, region_id: RegionId) -> manager::Result<SequenceNumber> {
+        self.wal.sequence_num(region_id)
+    }
+
+    async fn mark_delete_entries_up_to(
+        &self,
+        region_id: RegionId,
+        sequence_num: SequenceNumber,
+    ) -> manager::Result<()> {
+        self.wal
+            .mark_delete_entries_up_to(region_id, sequence_num)
+            .await
+    }
+}
+
+impl<W: std::fmt::Debug> std::fmt::Debug for GroupCommitWal<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GroupCommitWal")
+            .field("wal", &self.wal)
+            .finish()
+    }
+}
@@ -2,8 +2,12 @@
 
 //! Write Ahead Log
 
+pub mod compression;
+pub mod file_impl;
+pub mod group_commit;
 pub mod log_batch;
 pub mod manager;
+mod metrics;
 pub mod rocks_impl;
 
 #[cfg(test)]
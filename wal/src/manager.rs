@@ -126,6 +126,25 @@ pub trait LogWriter {
         ctx: &WriteContext,
         batch: &LogWriteBatch<P>,
     ) -> Result<SequenceNumber>;
+
+    /// Write multiple batches of log entries, and the batches may belong to
+    /// different regions.
+    ///
+    /// Returns the max sequence number of every batch. The default
+    /// implementation writes the batches one by one, implementations should
+    /// override it if they can write the batches in one physical write.
+    async fn write_batches<P: Payload>(
+        &self,
+        ctx: &WriteContext,
+        batches: &[LogWriteBatch<P>],
+    ) -> Result<Vec<SequenceNumber>> {
+        let mut sequences = Vec::with_capacity(batches.len());
+        for batch in batches {
+            sequences.push(self.write(ctx, batch).await?);
+        }
+
+        Ok(sequences)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,31 @@
+// Copyright 2022 CeresDB Project Authors. Licensed under Apache-2.0.
+
+//! Metrics of wal.
+
+use lazy_static::lazy_static;
+use prometheus::{exponential_buckets, register_histogram, Histogram};
+
+lazy_static! {
+    // Histograms:
+    // Buckets: 0, 1, .., 2^10
+    pub static ref GROUP_COMMIT_BATCH_NUM_HISTOGRAM: Histogram = register_histogram!(
+        "wal_group_commit_batch_num",
+        "Histogram for number of log batches merged into one group commit",
+        exponential_buckets(1.0, 2.0, 11).unwrap()
+    )
+    .unwrap();
+    // Buckets: 0, 1, .., 4^9
+    pub static ref GROUP_COMMIT_ENTRY_NUM_HISTOGRAM: Histogram = register_histogram!(
+        "wal_group_commit_entry_num",
+        "Histogram for number of log entries written by one group commit",
+        exponential_buckets(1.0, 4.0, 10).unwrap()
+    )
+    .unwrap();
+    // Buckets: 0, 0.0001, .., 0.0001 * 4^9
+    pub static ref GROUP_COMMIT_WAIT_DURATION_HISTOGRAM: Histogram = register_histogram!(
+        "wal_group_commit_wait_duration",
+        "Histogram for duration in seconds a log batch waits before being committed",
+        exponential_buckets(0.0001, 4.0, 10).unwrap()
+    )
+    .unwrap();
+}
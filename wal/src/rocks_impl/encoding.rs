@@ -13,6 +13,7 @@ use common_util::{
 use snafu::{ensure, Backtrace, ResultExt, Snafu};
 
 use crate::{
+    compression::{self, Compression},
     log_batch::{Payload, PayloadDecoder},
     manager::{self, RegionId},
 };
@@ -20,8 +21,11 @@ use crate::{
 const LOG_KEY_ENCODING_V0: u8 = 0;
 const NEWEST_LOG_KEY_ENCODING_VERSION: u8 = LOG_KEY_ENCODING_V0;
 
+/// Log value without compression codec.
 const LOG_VALUE_ENCODING_V0: u8 = 0;
-const NEWEST_LOG_VALUE_ENCODING_VERSION: u8 = LOG_VALUE_ENCODING_V0;
+/// Log value with compression codec.
+const LOG_VALUE_ENCODING_V1: u8 = 1;
+const NEWEST_LOG_VALUE_ENCODING_VERSION: u8 = LOG_VALUE_ENCODING_V1;
 
 const META_KEY_ENCODING_V0: u8 = 0;
 const NEWEST_META_KEY_ENCODING_VERSION: u8 = META_KEY_ENCODING_V0;
@@ -56,6 +60,12 @@ pub enum Error {
         source: Box<dyn std::error::Error + Send + Sync>,
     },
 
+    #[snafu(display("Failed to compress log value payload, err:{}", source))]
+    CompressLogValuePayload { source: compression::Error },
+
+    #[snafu(display("Failed to decompress log value payload, err:{}", source))]
+    DecompressLogValuePayload { source: compression::Error },
+
     #[snafu(display("Failed to encode meta key, err:{}", source))]
     EncodeMetaKey {
         source: bytes::Error,
@@ -126,14 +136,18 @@ pub struct LogEncoding {
 
 impl LogEncoding {
     pub fn newest() -> Self {
+        Self::with_compression(Compression::None)
+    }
+
+    /// Create the newest encoding which compresses the log payloads with
+    /// `compression`.
+    pub fn with_compression(compression: Compression) -> Self {
         Self {
             key_enc: LogKeyEncoder {
                 version: NEWEST_LOG_KEY_ENCODING_VERSION,
                 namespace: Namespace::Log,
             },
-            value_enc: LogValueEncoder {
-                version: NEWEST_LOG_VALUE_ENCODING_VERSION,
-            },
+            value_enc: LogValueEncoder { compression },
             value_enc_version: NEWEST_LOG_VALUE_ENCODING_VERSION,
         }
     }
@@ -270,53 +284,120 @@ impl Decoder<LogKey> for LogKeyEncoder {
 
 #[derive(Debug, Clone)]
 struct LogValueEncoder {
-    version: u8,
+    compression: Compression,
 }
 
 impl<T: Payload> Encoder<T> for LogValueEncoder {
     type Error = Error;
 
     /// Value format:
+    ///
+    /// ```text
     /// +--------------------+---------+
     /// | version_header(u8) | payload |
     /// +--------------------+---------+
+    /// ```
+    ///
+    /// The payload may be compressed since version 1, and the codec follows the
+    /// header:
+    ///
+    /// ```text
+    /// +--------------------+-----------+---------+
+    /// | version_header(u8) | codec(u8) | payload |
+    /// +--------------------+-----------+---------+
+    /// ```
+    ///
+    /// The version 0 is still used if the compression is disabled, so the
+    /// logs are readable by the older versions.
     fn encode<B: MemBufMut>(&self, buf: &mut B, payload: &T) -> Result<()> {
-        buf.write_u8(self.version).context(EncodeLogValueHeader)?;
+        if self.compression == Compression::None {
+            buf.write_u8(LOG_VALUE_ENCODING_V0)
+                .context(EncodeLogValueHeader)?;
+
+            return payload
+                .encode_to(buf)
+                .map_err(|e| Box::new(e) as _)
+                .context(EncodeLogValuePayload);
+        }
 
+        let mut raw = Vec::with_capacity(payload.encode_size());
         payload
-            .encode_to(buf)
+            .encode_to(&mut raw)
             .map_err(|e| Box::new(e) as _)
-            .context(EncodeLogValuePayload)
+            .context(EncodeLogValuePayload)?;
+        let compressed = self
+            .compression
+            .compress(&raw)
+            .context(CompressLogValuePayload)?;
+
+        buf.write_u8(LOG_VALUE_ENCODING_V1)
+            .context(EncodeLogValueHeader)?;
+        match compressed {
+            Some(compressed) => {
+                buf.write_u8(self.compression.as_u8())
+                    .context(EncodeLogValueHeader)?;
+                buf.write_slice(&compressed).context(EncodeLogValueHeader)
+            }
+            None => {
+                buf.write_u8(Compression::None.as_u8())
+                    .context(EncodeLogValueHeader)?;
+                buf.write_slice(&raw).context(EncodeLogValueHeader)
+            }
+        }
     }
 
     fn estimate_encoded_size(&self, payload: &T) -> usize {
         // Refer to value format.
-        1 + payload.encode_size()
+        2 + payload.encode_size()
     }
 }
 
 struct LogValueDecoder<'a, D: PayloadDecoder> {
+    /// The newest version supported
     version: u8,
     payload_dec: &'a D,
 }
 
+impl<'a, D: PayloadDecoder> LogValueDecoder<'a, D> {
+    fn decode_payload<B: MemBuf>(&self, buf: &mut B) -> Result<D::Target> {
+        self.payload_dec
+            .decode(buf)
+            .map_err(|e| Box::new(e) as _)
+            .context(DecodeLogValuePayload)
+    }
+}
+
 impl<'a, D: PayloadDecoder> Decoder<D::Target> for LogValueDecoder<'a, D> {
     type Error = Error;
 
     fn decode<B: MemBuf>(&self, buf: &mut B) -> Result<D::Target> {
         let version = buf.read_u8().context(DecodeLogValueHeader)?;
         ensure!(
-            version == self.version,
+            version <= self.version,
             InvalidVersion {
                 expect: self.version,
                 given: version
             }
         );
 
-        self.payload_dec
-            .decode(buf)
-            .map_err(|e| Box::new(e) as _)
-            .context(DecodeLogValuePayload)
+        if version == LOG_VALUE_ENCODING_V0 {
+            return self.decode_payload(buf);
+        }
+
+        let codec = buf.read_u8().context(DecodeLogValueHeader)?;
+        let compression = Compression::from_u8(codec).context(DecompressLogValuePayload)?;
+        if compression == Compression::None {
+            return self.decode_payload(buf);
+        }
+
+        let compressed = buf.remaining_slice();
+        let compressed_len = compressed.len();
+        let raw = compression
+            .decompress(compressed)
+            .context(DecompressLogValuePayload)?;
+        buf.must_advance(compressed_len);
+
+        self.decode_payload(&mut raw.as_slice())
     }
 }
 
@@ -531,3 +612,69 @@ impl MaxSeqMetaEncoding {
             .context(manager::Decoding)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct BytesPayload(Vec<u8>);
+
+    impl Payload for BytesPayload {
+        type Error = bytes::Error;
+
+        fn encode_size(&self) -> usize {
+            self.0.len()
+        }
+
+        fn encode_to<B: MemBufMut>(&self, buf: &mut B) -> std::result::Result<(), Self::Error> {
+            buf.write_slice(&self.0)
+        }
+    }
+
+    struct BytesPayloadDecoder;
+
+    impl PayloadDecoder for BytesPayloadDecoder {
+        type Error = bytes::Error;
+        type Target = BytesPayload;
+
+        fn decode<B: MemBuf>(&self, buf: &mut B) -> std::result::Result<BytesPayload, Self::Error> {
+            let payload = buf.remaining_slice().to_vec();
+            buf.must_advance(payload.len());
+            Ok(BytesPayload(payload))
+        }
+    }
+
+    #[test]
+    fn test_log_value_compression() {
+        let compressible = BytesPayload(b"ceresdb".repeat(100));
+        let incompressible = BytesPayload(b"a".to_vec());
+        for compression in [Compression::None, Compression::Lz4, Compression::Zstd] {
+            let encoding = LogEncoding::with_compression(compression);
+            for payload in [&compressible, &incompressible] {
+                let mut buf = BytesMut::new();
+                encoding.encode_value(&mut buf, payload).unwrap();
+                if compression != Compression::None && payload == &compressible {
+                    assert!(buf.len() < payload.0.len());
+                }
+
+                let decoded = encoding.decode_value(&buf, &BytesPayloadDecoder).unwrap();
+                assert_eq!(payload, &decoded);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_uncompressed_log_value() {
+        let payload = BytesPayload(b"ceresdb".repeat(100));
+
+        // The version 0 log value written by older versions.
+        let mut buf = BytesMut::new();
+        buf.write_u8(LOG_VALUE_ENCODING_V0).unwrap();
+        payload.encode_to(&mut buf).unwrap();
+
+        let encoding = LogEncoding::with_compression(Compression::Zstd);
+        let decoded = encoding.decode_value(&buf, &BytesPayloadDecoder).unwrap();
+        assert_eq!(payload, decoded);
+    }
+}
@@ -22,6 +22,7 @@ use snafu::ResultExt;
 use tokio::sync::Mutex;
 
 use crate::{
+    compression::Compression,
     log_batch::{LogEntry, LogWriteBatch, Payload, PayloadDecoder},
     manager::{
         error::*, LogIterator, LogReader, LogWriter, ReadContext, ReadRequest, RegionId,
@@ -166,27 +167,8 @@ impl Region {
             batch.entries.len()
         );
 
-        let entries_num = batch.len() as u64;
-        let (wb, max_sequence_num) = {
-            let wb = WriteBatch::default();
-            let mut next_sequence_num = self.alloc_sequence_num(entries_num);
-            let mut key_buf = BytesMut::new();
-            let mut value_buf = BytesMut::new();
-
-            for entry in &batch.entries {
-                self.log_encoding
-                    .encode_key(&mut key_buf, &(batch.region_id, next_sequence_num))?;
-                self.log_encoding
-                    .encode_value(&mut value_buf, &entry.payload)?;
-                wb.put(&key_buf, &value_buf)
-                    .map_err(|e| e.into())
-                    .context(Write)?;
-
-                next_sequence_num += 1;
-            }
-
-            (wb, next_sequence_num - 1)
-        };
+        let wb = WriteBatch::default();
+        let max_sequence_num = self.put_batch(&wb, batch)?;
 
         let db = self.db.clone();
         self.runtime
@@ -200,6 +182,35 @@ impl Region {
             .map_err(|e| Box::new(e) as _)
             .context(Write)?
     }
+
+    /// Allocate sequence numbers for the log entries of the `batch` and put
+    /// them into the `wb`.
+    ///
+    /// Returns the max sequence number of the `batch`.
+    fn put_batch<P: Payload>(
+        &self,
+        wb: &WriteBatch,
+        batch: &LogWriteBatch<P>,
+    ) -> Result<SequenceNumber> {
+        let entries_num = batch.len() as u64;
+        let mut next_sequence_num = self.alloc_sequence_num(entries_num);
+        let mut key_buf = BytesMut::new();
+        let mut value_buf = BytesMut::new();
+
+        for entry in &batch.entries {
+            self.log_encoding
+                .encode_key(&mut key_buf, &(batch.region_id, next_sequence_num))?;
+            self.log_encoding
+                .encode_value(&mut value_buf, &entry.payload)?;
+            wb.put(&key_buf, &value_buf)
+                .map_err(|e| e.into())
+                .context(Write)?;
+
+            next_sequence_num += 1;
+        }
+
+        Ok(next_sequence_num - 1)
+    }
 }
 
 /// [WalManager] implementation based on RocksDB.
@@ -402,6 +413,7 @@ pub struct Builder {
     wal_path: String,
     rocksdb_config: DBOptions,
     runtime: Arc<Runtime>,
+    compression: Compression,
 }
 
 impl Builder {
@@ -425,9 +437,16 @@ impl Builder {
             wal_path: wal_path.to_str().unwrap().to_owned(),
             rocksdb_config,
             runtime,
+            compression: Compression::None,
         }
     }
 
+    /// Compress the log payloads with `compression`.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
     pub fn build(self) -> Result<RocksImpl> {
         let db = DB::open(self.rocksdb_config, &self.wal_path)
             .map_err(|e| e.into())
@@ -438,7 +457,7 @@ impl Builder {
             wal_path: self.wal_path,
             db: Arc::new(db),
             runtime: self.runtime,
-            log_encoding: LogEncoding::newest(),
+            log_encoding: LogEncoding::with_compression(self.compression),
             max_seq_meta_encoding: MaxSeqMetaEncoding::newest(),
             regions: RwLock::new(HashMap::new()),
         };
@@ -587,6 +606,35 @@ impl LogWriter for RocksImpl {
         let region = self.get_or_create_region(batch.region_id);
         region.write(ctx, batch).await
     }
+
+    /// Put all the batches into one RocksDB write batch.
+    async fn write_batches<P: Payload>(
+        &self,
+        ctx: &WriteContext,
+        batches: &[LogWriteBatch<P>],
+    ) -> Result<Vec<SequenceNumber>> {
+        debug!(
+            "RocksImpl begin writing batches, ctx:{:?}, batch_num:{}",
+            ctx,
+            batches.len()
+        );
+
+        let wb = WriteBatch::default();
+        let mut sequences = Vec::with_capacity(batches.len());
+        for batch in batches {
+            let region = self.get_or_create_region(batch.region_id);
+            sequences.push(region.put_batch(&wb, batch)?);
+        }
+
+        let db = self.db.clone();
+        self.runtime
+            .spawn_blocking(move || db.write(&wb).map_err(|e| e.into()).context(Write))
+            .await
+            .map_err(|e| Box::new(e) as _)
+            .context(Write)??;
+
+        Ok(sequences)
+    }
 }
 
 #[async_trait]
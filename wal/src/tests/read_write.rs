@@ -7,7 +7,9 @@ use common_types::SequenceNumber;
 use crate::{
     log_batch::LogWriteBatch,
     manager::{LogReader, LogWriter, ReadBoundary, ReadRequest, RegionId, WalManager},
-    tests::util::{RocksTestEnv, TestEnv, TestPayload, WalBuilder},
+    tests::util::{
+        FileTestEnv, GroupCommitTestEnv, RocksTestEnv, TestEnv, TestPayload, WalBuilder,
+    },
 };
 
 fn check_write_batch_with_read_request<B: WalBuilder>(
@@ -143,6 +145,26 @@ async fn write_multiple_regions_parallelly<B: WalBuilder + 'static>(env: Arc<Tes
         });
 }
 
+/// Test writing batches of multiple regions at once.
+async fn write_batches_multiple_regions<B: WalBuilder>(env: &TestEnv<B>) {
+    let wal = env.build_wal();
+    let batches = vec![
+        env.build_log_batch(0, 0, 10),
+        env.build_log_batch(1, 10, 20),
+        env.build_log_batch(0, 20, 30),
+    ];
+    let seqs = wal
+        .write_batches(&env.write_ctx, &batches)
+        .await
+        .expect("should succeed to write");
+    assert_eq!(batches.len(), seqs.len());
+
+    check_write_batch(env, wal.clone(), 0, seqs[0], &batches[0]);
+    check_write_batch(env, wal.clone(), 1, seqs[1], &batches[1]);
+    check_write_batch(env, wal.clone(), 0, seqs[2], &batches[2]);
+    assert!(seqs[2] > seqs[0]);
+}
+
 /// Test whether the written logs can be read after reopen.
 async fn reopen<B: WalBuilder>(env: &TestEnv<B>) {
     let region_id = 0;
@@ -371,16 +393,77 @@ async fn sequence_increase_monotonically_delete_reopen_write<B: WalBuilder>(env:
     assert!(seq_2 > seq_1);
 }
 
+/// The segments whose entries are all deleted should be removed.
+async fn delete_obsolete_segments(env: &FileTestEnv) {
+    let region_id = 0;
+    let wal = env.build_wal();
+    let region_dir = env.dir.path().join(region_id.to_string());
+    let num_segments = || std::fs::read_dir(&region_dir).unwrap().count();
+
+    let mut seqs = Vec::new();
+    for i in 0..5 {
+        let write_batch = env.build_log_batch(region_id, i * 10, (i + 1) * 10);
+        let seq = wal
+            .write(&env.write_ctx, &write_batch)
+            .await
+            .expect("should succeed to write");
+        seqs.push(seq);
+    }
+    // Every batch is larger than the segment size.
+    assert_eq!(5, num_segments());
+
+    // delete the first two batches.
+    wal.mark_delete_entries_up_to(region_id, seqs[1])
+        .await
+        .expect("should succeed to delete");
+    // 3 segments and the meta file.
+    assert_eq!(4, num_segments());
+    let write_batch = env.build_log_batch(region_id, 20, 50);
+    check_write_batch(env, wal.clone(), region_id, seqs[4], &write_batch);
+
+    // reopen and the deleted entries should not be read.
+    drop(wal);
+    let wal = env.build_wal();
+    let read_req = ReadRequest {
+        region_id,
+        start: ReadBoundary::Min,
+        end: ReadBoundary::Max,
+    };
+    check_write_batch_with_read_request(env, wal.clone(), read_req, seqs[4], &write_batch);
+
+    // the active segment is kept even if all entries are deleted.
+    wal.mark_delete_entries_up_to(region_id, seqs[4])
+        .await
+        .expect("should succeed to delete");
+    assert_eq!(2, num_segments());
+}
+
 #[test]
 fn test_simple_read_write() {
     let rocks_env = RocksTestEnv::new(2);
     rocks_env.runtime.block_on(simple_read_write(&rocks_env, 0));
+
+    let file_env = FileTestEnv::new(2);
+    file_env.runtime.block_on(simple_read_write(&file_env, 0));
+
+    let group_commit_env = GroupCommitTestEnv::new(2);
+    group_commit_env
+        .runtime
+        .block_on(simple_read_write(&group_commit_env, 0));
 }
 
 #[test]
 fn test_read_with_boundary() {
     let rocks_env = RocksTestEnv::new(2);
     rocks_env.runtime.block_on(read_with_boundary(&rocks_env));
+
+    let file_env = FileTestEnv::new(2);
+    file_env.runtime.block_on(read_with_boundary(&file_env));
+
+    let group_commit_env = GroupCommitTestEnv::new(2);
+    group_commit_env
+        .runtime
+        .block_on(read_with_boundary(&group_commit_env));
 }
 
 #[test]
@@ -389,37 +472,88 @@ fn test_write_multiple_regions() {
     rocks_env
         .runtime
         .block_on(write_multiple_regions_parallelly(rocks_env.clone()));
+
+    let file_env = Arc::new(FileTestEnv::new(4));
+    file_env
+        .runtime
+        .block_on(write_multiple_regions_parallelly(file_env.clone()));
+
+    let group_commit_env = Arc::new(GroupCommitTestEnv::new(4));
+    group_commit_env
+        .runtime
+        .block_on(write_multiple_regions_parallelly(group_commit_env.clone()));
+}
+
+#[test]
+fn test_write_batches_multiple_regions() {
+    let rocks_env = RocksTestEnv::new(2);
+    rocks_env
+        .runtime
+        .block_on(write_batches_multiple_regions(&rocks_env));
+
+    let file_env = FileTestEnv::new(2);
+    file_env
+        .runtime
+        .block_on(write_batches_multiple_regions(&file_env));
 }
 
 #[test]
 fn test_reopen() {
     let rocks_env = RocksTestEnv::new(2);
     rocks_env.runtime.block_on(reopen(&rocks_env));
+
+    let file_env = FileTestEnv::new(2);
+    file_env.runtime.block_on(reopen(&file_env));
 }
 
 #[test]
 fn test_complex_read_write() {
     let rocks_env = RocksTestEnv::new(2);
     rocks_env.runtime.block_on(complex_read_write(&rocks_env));
+
+    let file_env = FileTestEnv::new(2);
+    file_env.runtime.block_on(complex_read_write(&file_env));
+
+    let group_commit_env = GroupCommitTestEnv::new(2);
+    group_commit_env
+        .runtime
+        .block_on(complex_read_write(&group_commit_env));
 }
 
 #[test]
 fn test_simple_write_delete() {
     let rocks_env = RocksTestEnv::new(2);
     rocks_env.runtime.block_on(simple_write_delete(&rocks_env));
+
+    let file_env = FileTestEnv::new(2);
+    file_env.runtime.block_on(simple_write_delete(&file_env));
 }
 
 #[test]
 fn test_write_delete_half() {
     let rocks_env = RocksTestEnv::new(2);
     rocks_env.runtime.block_on(write_delete_half(&rocks_env));
+
+    let file_env = FileTestEnv::new(2);
+    file_env.runtime.block_on(write_delete_half(&file_env));
 }
+
 #[test]
 fn test_write_delete_multiple_regions() {
     let rocks_env = RocksTestEnv::new(2);
     rocks_env
         .runtime
         .block_on(write_delete_multiple_regions(&rocks_env));
+
+    let file_env = FileTestEnv::new(2);
+    file_env
+        .runtime
+        .block_on(write_delete_multiple_regions(&file_env));
+
+    let group_commit_env = GroupCommitTestEnv::new(2);
+    group_commit_env
+        .runtime
+        .block_on(write_delete_multiple_regions(&group_commit_env));
 }
 
 #[test]
@@ -428,6 +562,18 @@ fn test_sequence_increase_monotonically_multiple_writes() {
     rocks_env
         .runtime
         .block_on(sequence_increase_monotonically_multiple_writes(&rocks_env));
+
+    let file_env = FileTestEnv::new(2);
+    file_env
+        .runtime
+        .block_on(sequence_increase_monotonically_multiple_writes(&file_env));
+
+    let group_commit_env = GroupCommitTestEnv::new(2);
+    group_commit_env
+        .runtime
+        .block_on(sequence_increase_monotonically_multiple_writes(
+            &group_commit_env,
+        ));
 }
 
 #[test]
@@ -436,6 +582,11 @@ fn test_sequence_increase_monotonically_delete_write() {
     rocks_env
         .runtime
         .block_on(sequence_increase_monotonically_delete_write(&rocks_env));
+
+    let file_env = FileTestEnv::new(2);
+    file_env
+        .runtime
+        .block_on(sequence_increase_monotonically_delete_write(&file_env));
 }
 
 #[test]
@@ -446,4 +597,19 @@ fn test_sequence_increase_monotonically_delete_reopen_write() {
         .block_on(sequence_increase_monotonically_delete_reopen_write(
             &rocks_env,
         ));
+
+    let file_env = FileTestEnv::new(2);
+    file_env
+        .runtime
+        .block_on(sequence_increase_monotonically_delete_reopen_write(
+            &file_env,
+        ));
+}
+
+#[test]
+fn test_delete_obsolete_segments() {
+    let file_env = FileTestEnv::new(2);
+    file_env
+        .runtime
+        .block_on(delete_obsolete_segments(&file_env));
 }
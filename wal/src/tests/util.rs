@@ -9,6 +9,12 @@ use common_util::runtime::{self, Runtime};
 use tempfile::TempDir;
 
 use crate::{
+    compression::Compression,
+    file_impl::{
+        self,
+        manager::{FileImpl, SyncPolicy},
+    },
+    group_commit::{self, GroupCommitWal},
     log_batch::{LogWriteBatch, LogWriteEntry, Payload, PayloadDecoder},
     manager::{LogIterator, LogReader, ReadContext, RegionId, WalManager, WriteContext},
     rocks_impl::{self, manager::RocksImpl},
@@ -44,6 +50,53 @@ impl WalBuilder for RocksWalBuilder {
 
 pub type RocksTestEnv = TestEnv<RocksWalBuilder>;
 
+#[derive(Default)]
+pub struct FileWalBuilder;
+
+impl WalBuilder for FileWalBuilder {
+    type Wal = FileImpl;
+
+    fn build(&self, data_path: &Path, runtime: Arc<Runtime>) -> Arc<Self::Wal> {
+        // Use a small segment size to cover the segment switching.
+        let options = file_impl::manager::Options {
+            segment_size: 128,
+            sync_policy: SyncPolicy::EveryWrite,
+            compression: Compression::Lz4,
+        };
+        let wal_builder = file_impl::manager::Builder::new(data_path, runtime).options(options);
+
+        Arc::new(
+            wal_builder
+                .build()
+                .expect("should succeed to build fileimpl wal"),
+        )
+    }
+}
+
+pub type FileTestEnv = TestEnv<FileWalBuilder>;
+
+#[derive(Default)]
+pub struct GroupCommitWalBuilder;
+
+impl WalBuilder for GroupCommitWalBuilder {
+    type Wal = GroupCommitWal<RocksImpl>;
+
+    fn build(&self, data_path: &Path, runtime: Arc<Runtime>) -> Arc<Self::Wal> {
+        let wal =
+            rocks_impl::manager::Builder::with_default_rocksdb_config(data_path, runtime.clone())
+                .build()
+                .expect("should succeed to build rocksimpl wal");
+
+        Arc::new(GroupCommitWal::new(
+            wal,
+            &runtime,
+            group_commit::Options::default(),
+        ))
+    }
+}
+
+pub type GroupCommitTestEnv = TestEnv<GroupCommitWalBuilder>;
+
 /// The environment for testing wal.
 pub struct TestEnv<B> {
     pub dir: TempDir,